@@ -1,13 +1,15 @@
 use crate::{
+    cli::FileAnchor,
     configs::{GeneralAction, KeyMap, KEY_MAP},
     error::IdiomResult,
     global_state::{GlobalState, IdiomEvent},
+    instance::{coordinate, Coordination},
     popups::{
         pallet::Pallet,
         popup_find::{FindPopup, GoToLinePopup},
         popup_replace::ReplacePopup,
         popup_tree_search::ActivePathSearch,
-        popups_editor::{save_all_popup, selector_editors},
+        popups_editor::{instance_running, save_all_popup, selector_editors},
     },
     render::backend::Backend,
     runner::EditorTerminal,
@@ -19,7 +21,7 @@ use std::{path::PathBuf, time::Duration};
 
 const MIN_FRAMERATE: Duration = Duration::from_millis(8);
 
-pub async fn app(open_file: Option<PathBuf>, backend: Backend) -> IdiomResult<()> {
+pub async fn app(mut open_file: Option<(PathBuf, FileAnchor)>, backend: Backend) -> IdiomResult<()> {
     // builtin cursor is not used - cursor is positioned during render
 
     let mut gs = GlobalState::new(backend)?;
@@ -29,12 +31,31 @@ pub async fn app(open_file: Option<PathBuf>, backend: Backend) -> IdiomResult<()
     // COMPONENTS
     let mut tree = Tree::new(configs.tree_key_map(), &mut gs);
     let mut workspace = Workspace::new(configs.editor_key_map(), tree.get_base_file_names(), &mut gs).await;
+    tree.set_order(workspace.tree_order());
     let mut term = EditorTerminal::new(gs.editor_area.width as u16);
 
+    // INSTANCE COORDINATION
+    let instance_lock = match coordinate() {
+        Coordination::Lock(lock) => Some(lock),
+        Coordination::Running(mut peer) => {
+            // the file is opened after the user picks standalone
+            peer.set_pending(open_file.take());
+            gs.popup(instance_running(peer.pid, peer.has_pending()));
+            workspace.set_peer(peer);
+            None
+        }
+        Coordination::Unavailable => None,
+    };
+
     // CLI SETUP
-    if let Some(path) = open_file {
-        tree.select_by_path(&path);
-        gs.event.push(IdiomEvent::OpenAtLine(path, 0));
+    if let Some((path, anchor)) = open_file {
+        if workspace.tree_follows_file() {
+            tree.select_by_path(&path);
+        }
+        match anchor {
+            FileAnchor::Line(line) => gs.event.push(IdiomEvent::OpenAtLine(path, line)),
+            anchor => gs.event.push(IdiomEvent::OpenAtAnchor(path, anchor)),
+        }
         gs.toggle_tree();
     }
 
@@ -57,7 +78,7 @@ pub async fn app(open_file: Option<PathBuf>, backend: Backend) -> IdiomResult<()
                                 }
                                 GeneralAction::Replace => {
                                     if gs.is_insert() {
-                                        gs.popup(ReplacePopup::new());
+                                        gs.popup(ReplacePopup::new(gs.search_history.clone()));
                                     };
                                 }
                                 GeneralAction::SelectOpenEditor => {
@@ -81,7 +102,9 @@ pub async fn app(open_file: Option<PathBuf>, backend: Backend) -> IdiomResult<()
                                     };
                                 }
                                 GeneralAction::FileTreeModeOrCancelInput => gs.select_mode(),
-                                GeneralAction::SaveAll => workspace.save_all(&mut gs),
+                                GeneralAction::SaveAll => {
+                                    workspace.save_all(&mut gs);
+                                }
                                 GeneralAction::HideFileTree => {
                                     gs.toggle_tree();
                                 }
@@ -90,6 +113,7 @@ pub async fn app(open_file: Option<PathBuf>, backend: Backend) -> IdiomResult<()
                                     general_key_map = new_key_map.general_key_map();
                                     tree.key_map = new_key_map.tree_key_map();
                                     workspace.refresh_cfg(new_key_map.editor_key_map(), &mut gs);
+                                    tree.set_order(workspace.tree_order());
                                 }
                                 GeneralAction::GoToLinePopup => {
                                     if gs.is_insert() {
@@ -117,16 +141,25 @@ pub async fn app(open_file: Option<PathBuf>, backend: Backend) -> IdiomResult<()
                     term.resize(gs.editor_area.width as u16);
                 }
                 Event::Mouse(event) => gs.map_mouse(event, &mut tree, &mut workspace),
+                Event::FocusLost => workspace.focus_lost(&mut gs),
                 _ => (),
             }
         }
 
+        workspace.auto_save_idle(&mut gs);
+        workspace.set_parsed_files(&mut gs);
+        workspace.poll_formats(&mut gs);
+
+        if let Some(lock) = instance_lock.as_ref() {
+            lock.serve(|request| workspace.instance_request(request, &mut gs));
+        }
+
         // render updates
         gs.draw(&mut workspace, &mut tree, &mut term)?;
 
         // do event exchanges
         if gs.exchange_should_exit(&mut tree, &mut workspace).await {
-            workspace.graceful_exit().await;
+            workspace.graceful_exit(&mut gs).await;
             return Ok(());
         };
     }
@@ -6,10 +6,12 @@ use crate::{
         layout::Rect,
         state::State,
     },
-    tree::TreePath,
+    tree::{PathFilter, TreePath},
+    workspace::{line::EditorLine, CursorPosition},
 };
 use clap::Parser;
 use crossterm::event::{Event, KeyCode, KeyEvent};
+use regex::Regex;
 use std::{path::PathBuf, time::Duration};
 
 const MIN_FRAMERATE: Duration = Duration::from_millis(8);
@@ -17,17 +19,18 @@ const MIN_FRAMERATE: Duration = Duration::from_millis(8);
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Args {
-    /// Optinal path to open on start
-    path: Option<PathBuf>,
+    /// Optinal path to open on start, files can be anchored: file:line, file:@byteoffset or file:/regex/
+    path: Option<String>,
     /// Run in select mode opening basic file tree from HOME dir (ignores provided PATH args)
     #[arg(short, long)]
     pub select: bool,
 }
 
 impl Args {
-    pub fn get_path(self) -> IdiomResult<Option<PathBuf>> {
+    pub fn get_path(self) -> IdiomResult<Option<(PathBuf, FileAnchor)>> {
         match self.path {
-            Some(rel_path) => {
+            Some(arg) => {
+                let (rel_path, anchor) = FileAnchor::parse(&arg)?;
                 let path = rel_path.canonicalize()?;
 
                 match path.is_dir() {
@@ -39,7 +42,7 @@ impl Args {
                         if let Some(path) = path.parent() {
                             std::env::set_current_dir(path)?;
                         }
-                        Ok(Some(path))
+                        Ok(Some((path, anchor)))
                     }
                 }
             }
@@ -48,6 +51,85 @@ impl Args {
     }
 }
 
+/// Position within file provided together with the path
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileAnchor {
+    /// zero based line
+    Line(usize),
+    ByteOffset(usize),
+    /// first match of the regex
+    Pattern(String),
+}
+
+impl Default for FileAnchor {
+    fn default() -> Self {
+        Self::Line(0)
+    }
+}
+
+impl FileAnchor {
+    /// splits anchor from path - existing paths are always taken as they are
+    fn parse(arg: &str) -> IdiomResult<(PathBuf, Self)> {
+        let path = PathBuf::from(arg);
+        if path.exists() {
+            return Ok((path, Self::default()));
+        }
+        if let Some((path, pattern)) = arg.split_once(":/").filter(|(_, pattern)| pattern.ends_with('/')) {
+            let pattern = &pattern[..pattern.len() - 1];
+            Regex::new(pattern).map_err(|err| IdiomError::any(format!("Bad anchor pattern: {err}")))?;
+            return Ok((PathBuf::from(path), Self::Pattern(pattern.to_owned())));
+        }
+        if let Some((path, offset)) = arg.rsplit_once(":@") {
+            if let Ok(offset) = offset.parse() {
+                return Ok((PathBuf::from(path), Self::ByteOffset(offset)));
+            }
+        }
+        if let Some((path, line)) = arg.rsplit_once(':') {
+            if let Ok(line) = line.parse::<usize>() {
+                return Ok((PathBuf::from(path), Self::Line(line.saturating_sub(1))));
+            }
+        }
+        Ok((path, Self::default()))
+    }
+
+    /// resolves anchor against file content into select (from == to for positions)
+    pub fn resolve(&self, content: &[EditorLine]) -> Option<(CursorPosition, CursorPosition)> {
+        match self {
+            Self::Line(line) => {
+                let position = CursorPosition { line: (*line).min(content.len().checked_sub(1)?), char: 0 };
+                Some((position, position))
+            }
+            Self::ByteOffset(offset) => {
+                let mut offset = *offset;
+                for (line_idx, text) in content.iter().enumerate() {
+                    if offset <= text.len() {
+                        // byte offset inside utf8 char snaps to its start
+                        let mut byte_idx = offset;
+                        while !text.content.is_char_boundary(byte_idx) {
+                            byte_idx -= 1;
+                        }
+                        let char = text.unsafe_utf8_to_idx(byte_idx);
+                        let position = CursorPosition { line: line_idx, char };
+                        return Some((position, position));
+                    }
+                    // new line char
+                    offset -= text.len() + 1;
+                }
+                None
+            }
+            Self::Pattern(pattern) => {
+                let regex = Regex::new(pattern).ok()?;
+                content.iter().enumerate().find_map(|(line_idx, text)| {
+                    let found = regex.find(&text.content)?;
+                    let from = CursorPosition { line: line_idx, char: text.unsafe_utf8_to_idx(found.start()) };
+                    let to = CursorPosition { line: line_idx, char: text.unsafe_utf8_to_idx(found.end()) };
+                    Some((from, to))
+                })
+            }
+        }
+    }
+}
+
 pub struct TreeSeletor {
     pub key_map: TreeKeyMap,
     state: State,
@@ -66,7 +148,7 @@ impl TreeSeletor {
         let rect = Backend::screen()?;
         let path_str = home.display().to_string();
         let display_offset = path_str.split(std::path::MAIN_SEPARATOR).count() * 2;
-        let tree = TreePath::from_path(home.clone());
+        let tree = TreePath::from_path(home.clone(), &PathFilter::all(&home));
         let mut tree = Self {
             state: State::new(),
             key_map: config.tree_key_map(),
@@ -191,3 +273,38 @@ impl TreeSeletor {
         false
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::FileAnchor;
+    use crate::workspace::{line::EditorLine, CursorPosition};
+    use std::path::PathBuf;
+
+    #[test]
+    fn parse_anchor() {
+        assert_eq!(FileAnchor::parse("a.rs:12").unwrap(), (PathBuf::from("a.rs"), FileAnchor::Line(11)));
+        assert_eq!(FileAnchor::parse("a.rs:@40").unwrap(), (PathBuf::from("a.rs"), FileAnchor::ByteOffset(40)));
+        assert_eq!(
+            FileAnchor::parse("a.rs:/fn [a-z]+/").unwrap(),
+            (PathBuf::from("a.rs"), FileAnchor::Pattern("fn [a-z]+".to_owned()))
+        );
+        assert_eq!(FileAnchor::parse("a.rs").unwrap(), (PathBuf::from("a.rs"), FileAnchor::Line(0)));
+        assert!(FileAnchor::parse("a.rs:/fn (/").is_err());
+    }
+
+    #[test]
+    fn resolve_anchor() {
+        let content: Vec<EditorLine> =
+            ["first", "s🚀cond", "fn main() {}"].into_iter().map(|l| l.to_owned().into()).collect();
+        let at = |line, char| CursorPosition { line, char };
+        assert_eq!(FileAnchor::ByteOffset(7).resolve(&content), Some((at(1, 1), at(1, 1))));
+        // inside the rocket
+        assert_eq!(FileAnchor::ByteOffset(9).resolve(&content), Some((at(1, 1), at(1, 1))));
+        assert_eq!(FileAnchor::ByteOffset(11).resolve(&content), Some((at(1, 2), at(1, 2))));
+        assert_eq!(FileAnchor::ByteOffset(200).resolve(&content), None);
+        assert_eq!(FileAnchor::Pattern("c.n".to_owned()).resolve(&content), Some((at(1, 2), at(1, 5))));
+        assert_eq!(FileAnchor::Pattern("main\\(\\)".to_owned()).resolve(&content), Some((at(2, 3), at(2, 9))));
+        assert_eq!(FileAnchor::Pattern("missing".to_owned()).resolve(&content), None);
+        assert_eq!(FileAnchor::Line(10).resolve(&content), Some((at(2, 0), at(2, 0))));
+    }
+}
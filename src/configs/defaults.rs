@@ -1,3 +1,6 @@
+use super::ViewOverrides;
+use std::collections::HashMap;
+
 // MODS
 pub const SHIFT: &str = "shift";
 pub const CTRL: &str = "ctrl";
@@ -44,6 +47,43 @@ pub fn remove_line() -> String {
     format!("{CTRL} && {DELETE} || {CTRL} && h || {CTRL} && {BACKSPACE}")
 }
 
+pub fn delete_line() -> String {
+    format!("{CTRL} && k")
+}
+
+pub fn delete_to_end_of_line() -> String {
+    format!("{ALT} && k")
+}
+
+pub fn delete_to_start_of_line() -> String {
+    format!("{ALT} && u")
+}
+
+pub fn repeat_last() -> String {
+    format!("{ALT} && .")
+}
+
+pub fn reflow_paragraph() -> String {
+    format!("{ALT} && q")
+}
+
+pub fn go_to_matching_bracket() -> String {
+    format!("{ALT} && b")
+}
+
+pub fn fold() -> String {
+    format!("{ALT} && -")
+}
+
+pub fn unfold() -> String {
+    format!("{ALT} && =")
+}
+
+/// unfolds all if there are folded blocks
+pub fn fold_all() -> String {
+    format!("{ALT} && 0")
+}
+
 pub fn indent_start() -> String {
     format!("{CTRL} && ]")
 }
@@ -112,6 +152,18 @@ pub fn swap_down() -> String {
     format!("{ALT} && {DOWN}")
 }
 
+pub fn move_line_mode() -> String {
+    format!("{ALT} && m")
+}
+
+pub fn column_select_up() -> String {
+    format!("{ALT} && {SHIFT} && {UP}")
+}
+
+pub fn column_select_down() -> String {
+    format!("{ALT} && {SHIFT} && {DOWN}")
+}
+
 pub fn jump_left() -> String {
     format!("{CTRL} && {LEFT} || {ALT} && {LEFT}")
 }
@@ -140,6 +192,10 @@ pub fn home() -> String {
     HOME.to_owned()
 }
 
+pub fn select_home() -> String {
+    format!("{SHIFT} && {HOME}")
+}
+
 pub fn start_of_file() -> String {
     format!("{CTRL} && {HOME}")
 }
@@ -152,6 +208,14 @@ pub fn go_to_declaration() -> String {
     format!("{F}12")
 }
 
+pub fn peek_definition() -> String {
+    format!("{ALT} && {F}12")
+}
+
+pub fn outline() -> String {
+    format!("{CTRL} && o")
+}
+
 pub fn help() -> String {
     format!("{F}1")
 }
@@ -164,6 +228,18 @@ pub fn rename() -> String {
     format!("{F}2")
 }
 
+pub fn format() -> String {
+    format!("{ALT} && f")
+}
+
+pub fn code_action() -> String {
+    format!("{ALT} && {ENTER}")
+}
+
+pub fn quick_fix() -> String {
+    format!("{CTRL} && .")
+}
+
 pub fn cut() -> String {
     format!("{CTRL} && x")
 }
@@ -176,6 +252,18 @@ pub fn paste() -> String {
     format!("{CTRL} && v")
 }
 
+pub fn paste_reindent() -> String {
+    format!("{ALT} && p")
+}
+
+pub fn cycle_paste() -> String {
+    format!("{ALT} && v")
+}
+
+pub fn register() -> String {
+    format!("{ALT} && r")
+}
+
 pub fn undo() -> String {
     format!("{CTRL} && z")
 }
@@ -200,6 +288,14 @@ pub fn comment_out() -> String {
     format!("{CTRL} && /")
 }
 
+pub fn focus_other_split() -> String {
+    format!("{ALT} && o")
+}
+
+pub fn revert_buffer() -> String {
+    format!("{CTRL} && r")
+}
+
 pub fn select_open_editor() -> String {
     format!("{CTRL} && {UP} || {CTRL} && {DOWN}")
 }
@@ -284,10 +380,101 @@ pub fn tree_size_dec() -> String {
     format!("{CTRL} && {LEFT}")
 }
 
+pub fn copy_path() -> String {
+    format!("{CTRL} && c")
+}
+
+pub fn toggle_hidden() -> String {
+    format!("{ALT} && h")
+}
+
+pub fn toggle_ignored() -> String {
+    format!("{ALT} && i")
+}
+
+pub fn toggle_mark() -> String {
+    String::from("m")
+}
+
+pub fn cut_file() -> String {
+    format!("{CTRL} && x")
+}
+
+pub fn copy_file() -> String {
+    format!("{ALT} && c")
+}
+
+pub fn paste_file() -> String {
+    format!("{CTRL} && v")
+}
+
 pub const fn get_indent_spaces() -> usize {
     4
 }
 
+pub const fn get_detect_indent() -> bool {
+    true
+}
+
+pub const fn get_wrap_column() -> usize {
+    80
+}
+
+pub const fn get_tree_follow_file() -> bool {
+    true
+}
+
+pub const fn get_md_emphasis() -> bool {
+    true
+}
+
+pub const fn get_scrollbar() -> bool {
+    false
+}
+
+pub const fn get_line_numbers() -> bool {
+    true
+}
+
+pub const fn get_highlight_trailing_whitespace() -> bool {
+    true
+}
+
+pub const fn get_show_inlay_hints() -> bool {
+    true
+}
+
+pub const fn get_insert_final_newline() -> bool {
+    true
+}
+
+pub const fn get_undo_limit() -> usize {
+    10_000
+}
+
+pub const fn get_undo_memory_kb() -> usize {
+    64 * 1024
+}
+
+pub const fn get_undo_group_timeout_ms() -> u64 {
+    1000
+}
+
+pub const fn get_clipboard_history() -> usize {
+    20
+}
+
+/// prose is shown without the code guides
+pub fn get_view_defaults() -> HashMap<String, ViewOverrides> {
+    let prose = ViewOverrides {
+        line_numbers: Some(false),
+        highlight_current_line: Some(false),
+        rulers: Some(Vec::new()),
+        ..Default::default()
+    };
+    HashMap::from([(String::from("text"), prose.clone()), (String::from("markdown"), prose)])
+}
+
 pub fn get_indent_after() -> String {
     String::from("({[")
 }
@@ -296,6 +483,10 @@ pub fn get_unident_before() -> String {
     String::from("]})")
 }
 
+pub fn get_auto_close() -> String {
+    String::from("{}()[]\"\"''")
+}
+
 pub fn pallet() -> String {
     format!("{CTRL} && p")
 }
@@ -1,25 +1,144 @@
 use super::{
-    defaults::{get_indent_after, get_indent_spaces, get_unident_before},
+    defaults::{
+        get_auto_close, get_clipboard_history, get_detect_indent, get_highlight_trailing_whitespace, get_indent_after,
+        get_indent_spaces, get_insert_final_newline, get_line_numbers, get_md_emphasis, get_scrollbar,
+        get_show_inlay_hints, get_tree_follow_file, get_undo_group_timeout_ms, get_undo_limit, get_undo_memory_kb,
+        get_unident_before, get_view_defaults, get_wrap_column,
+    },
     load_or_create_config,
     types::FileType,
-    EDITOR_CFG_FILE,
+    ViewDefaults, ViewFamily, ViewOverrides, ViewSettings, EDITOR_CFG_FILE,
 };
 use crate::global_state::GlobalState;
 use crate::utils::{trim_start_inplace, Offset};
-use crate::workspace::line::EditorLine;
+use crate::workspace::{actions::HistoryLimit, line::EditorLine};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path, time::Duration};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EditorConfigs {
+    /// document is formatted by LSP before it is written, the save waits for the server up to 2 seconds
     #[serde(default)]
     pub format_on_save: bool,
+    /// external formatter by language used instead of LSP (buffer on stdin, formatted text on stdout),
+    /// e.g. python = "black -q -" - the command is split on whitespace and run without shell
+    #[serde(default)]
+    pub formatters: HashMap<String, String>,
+    /// writes unsaved editors automatically: "off", "on_focus_loss" or { after_delay = ms }
+    #[serde(default)]
+    pub auto_save: AutoSave,
     #[serde(default = "get_indent_spaces")]
     pub indent_spaces: usize,
+    /// indent of opened files (tabs or spaces width) is guessed from their content, indent_spaces if not indented
+    #[serde(default = "get_detect_indent")]
+    pub detect_indent: bool,
     #[serde(default = "get_indent_after")]
     pub indent_after: String,
     #[serde(default = "get_unident_before")]
     pub unindent_before: String,
+    /// pairs closed on typing the opening char, written one after another "{}()"
+    #[serde(default = "get_auto_close")]
+    pub auto_close: String,
+    /// auto close pairs by language (e.g. rust = "{}()[]\"\""), by default rust does not close '
+    #[serde(default)]
+    pub auto_close_overrides: HashMap<String, String>,
+    /// expand and select the opened file in the tree (finder, CLI, mouse)
+    #[serde(default = "get_tree_follow_file")]
+    pub tree_follow_file: bool,
+    /// order of files within tree folders (folders are listed first): "name", "name_case_sensitive" or "extension"
+    #[serde(default)]
+    pub tree_sort: TreeSort,
+    /// numbers within names are compared by value in the tree (file2 before file10)
+    #[serde(default)]
+    pub tree_natural_sort: bool,
+    /// typing *, _ or ` with select in markdown wraps the select instead of replacing it
+    #[serde(default = "get_md_emphasis")]
+    pub md_emphasis: bool,
+    /// first row of code editors shows the opening line of the scope above the view
+    #[serde(default)]
+    pub sticky_scope: bool,
+    /// scrollbar with diagnostic markers on the right edge of editors
+    #[serde(default = "get_scrollbar")]
+    pub scrollbar: bool,
+    /// background on trailing whitespace in code files (color from theme)
+    #[serde(default = "get_highlight_trailing_whitespace")]
+    pub highlight_trailing_whitespace: bool,
+    /// LSP inlay hints (inferred types, parameter names) shown as dim text within the lines
+    #[serde(default = "get_show_inlay_hints")]
+    pub show_inlay_hints: bool,
+    /// removes trailing whitespace from lines changed since the last save
+    #[serde(default)]
+    pub trim_trailing_whitespace_on_save: bool,
+    /// code files are saved ending with new line (empty last line is added)
+    #[serde(default = "get_insert_final_newline")]
+    pub insert_final_newline: bool,
+    /// line numbers in the gutter, without them only the separating column is left
+    #[serde(default = "get_line_numbers")]
+    pub line_numbers: bool,
+    /// line numbers show the distance from the cursor line (cursor line number stays absolute)
+    #[serde(default)]
+    pub relative_line_numbers: bool,
+    /// whitespace shown as dim markers (display only): none, all or trailing
+    #[serde(default)]
+    pub render_whitespace: RenderWhitespace,
+    /// background on the cursor line and its number in the gutter (color from theme)
+    #[serde(default)]
+    pub highlight_current_line: bool,
+    /// character columns marked by a dim vertical ruler in code files, e.g. [80, 100]
+    #[serde(default)]
+    pub rulers: Vec<usize>,
+    /// command prefix used to remove chattr attributes blocking a save (restored after the write),
+    /// e.g. "sudo -n" - the command can not prompt for password
+    #[serde(default)]
+    pub attribute_elevation: Option<String>,
+    /// max undo steps kept per editor, the oldest are dropped first
+    #[serde(default = "get_undo_limit")]
+    pub undo_limit: usize,
+    /// approximate memory budget (KB) of the undo steps per editor
+    #[serde(default = "get_undo_memory_kb")]
+    pub undo_memory_kb: usize,
+    /// pause in typing (ms) ending the undo step, 0 groups by words and cursor moves only
+    #[serde(default = "get_undo_group_timeout_ms")]
+    pub undo_group_timeout_ms: u64,
+    /// copied and cut clips kept for paste from history
+    #[serde(default = "get_clipboard_history")]
+    pub clipboard_history: usize,
+    /// where copied text goes: "native" (system clipboard, OSC 52 if it fails), "osc52" (terminal) or "internal"
+    #[serde(default)]
+    pub clipboard_provider: ClipboardProvider,
+    /// text removed by delete to the end (start) of line is copied to the clipboard and its history
+    #[serde(default)]
+    pub delete_to_clipboard: bool,
+    /// keeps undo history of saved files after close (written to the config folder)
+    #[serde(default)]
+    pub persist_undo: bool,
+    /// debug: every N frames the editor is drawn without caches and compared with the screen,
+    /// stale rows are logged to render_check.log in the config folder (0 disables)
+    #[serde(default)]
+    pub render_check_frames: usize,
+    /// soft wrap long lines in code files (applies to newly opened files)
+    #[serde(default)]
+    pub code_wrap: bool,
+    /// column used by reflow paragraph
+    #[serde(default = "get_wrap_column")]
+    pub wrap_column: usize,
+    /// view settings by file family (code, text, markdown) or file type (e.g. rust),
+    /// file type wins over family and both over the global settings
+    #[serde(default = "get_view_defaults")]
+    pub view_defaults: HashMap<String, ViewOverrides>,
+    /// wrap column by file extension (e.g. md = 72)
+    #[serde(default)]
+    pub wrap_column_overrides: HashMap<String, usize>,
+    /// column trailing comments are aligned to (widest code line when not set)
+    #[serde(default)]
+    pub comment_column: Option<usize>,
+    /// overrides detected terminal colors: "truecolor", "256" or "16"
+    #[serde(default)]
+    pub color_support: Option<String>,
+    /// overrides detected mouse support (false also stops mouse capture)
+    #[serde(default)]
+    pub mouse: Option<bool>,
     /// LSP
     rust_lsp: Option<String>,
     rust_lsp_preload_if_present: Option<Vec<String>>,
@@ -49,9 +168,45 @@ impl Default for EditorConfigs {
     fn default() -> Self {
         Self {
             format_on_save: true,
+            formatters: HashMap::new(),
+            auto_save: AutoSave::Off,
             indent_spaces: get_indent_spaces(),
+            detect_indent: get_detect_indent(),
             indent_after: get_indent_after(),
             unindent_before: get_unident_before(),
+            tree_follow_file: get_tree_follow_file(),
+            tree_sort: TreeSort::default(),
+            tree_natural_sort: false,
+            code_wrap: false,
+            line_numbers: get_line_numbers(),
+            relative_line_numbers: false,
+            highlight_current_line: false,
+            rulers: Vec::new(),
+            attribute_elevation: None,
+            undo_limit: get_undo_limit(),
+            undo_memory_kb: get_undo_memory_kb(),
+            undo_group_timeout_ms: get_undo_group_timeout_ms(),
+            clipboard_history: get_clipboard_history(),
+            clipboard_provider: ClipboardProvider::Native,
+            delete_to_clipboard: false,
+            persist_undo: false,
+            render_check_frames: 0,
+            render_whitespace: RenderWhitespace::None,
+            wrap_column: get_wrap_column(),
+            wrap_column_overrides: HashMap::new(),
+            view_defaults: get_view_defaults(),
+            md_emphasis: get_md_emphasis(),
+            sticky_scope: false,
+            scrollbar: get_scrollbar(),
+            highlight_trailing_whitespace: get_highlight_trailing_whitespace(),
+            show_inlay_hints: get_show_inlay_hints(),
+            trim_trailing_whitespace_on_save: false,
+            insert_final_newline: get_insert_final_newline(),
+            auto_close: get_auto_close(),
+            auto_close_overrides: HashMap::new(),
+            comment_column: None,
+            color_support: None,
+            mouse: None,
             // lsp
             rust_lsp: Some(String::from("rust-analyzer")),
             rust_lsp_preload_if_present: Some(vec!["Cargo.toml".to_owned(), "Cargo.lock".to_owned()]),
@@ -79,21 +234,135 @@ impl Default for EditorConfigs {
     }
 }
 
+/// Automatic saving of editors, files changed on disk are never written
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoSave {
+    #[default]
+    Off,
+    /// milliseconds without edits
+    AfterDelay(u64),
+    OnFocusLoss,
+}
+
+/// Destination of copied text - pasting from the terminal clipboard (OSC 52) is not possible,
+/// so clips are kept internally as well
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardProvider {
+    /// system clipboard, on failure (or over SSH) the clip is sent to the terminal as well
+    #[default]
+    Native,
+    /// terminal clipboard by escape sequence - works over SSH
+    Osc52,
+    /// clips stay within idiom
+    Internal,
+}
+
+/// Whitespace rendered as markers - space as `·` and tab as `→`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderWhitespace {
+    #[default]
+    None,
+    All,
+    /// only whitespace after the last non whitespace char
+    Trailing,
+}
+
+/// Order of tree entries with the same kind (folder or file)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeSort {
+    /// alphabetical ignoring case
+    #[default]
+    Name,
+    /// alphabetical with upper case first
+    NameCaseSensitive,
+    /// files grouped by extension, alphabetical within the group
+    Extension,
+}
+
 impl EditorConfigs {
     pub fn new() -> Result<Self, toml::de::Error> {
         load_or_create_config(EDITOR_CFG_FILE)
     }
 
     pub fn get_indent_cfg(&self, file_type: &FileType) -> IndentConfigs {
-        let indent_cfg = self.default_indent_cfg();
-        indent_cfg.update_by_file_type(file_type)
+        let mut indent_cfg = self.default_indent_cfg().update_by_file_type(file_type);
+        if let Some(pairs) = self.auto_close_overrides.get(<&str>::from(*file_type)) {
+            indent_cfg.auto_close = parse_pairs(pairs);
+        }
+        indent_cfg
+    }
+
+    /// indent detected from the content if enabled
+    pub fn with_detected_indent(&self, indent_cfg: IndentConfigs, content: &[EditorLine]) -> IndentConfigs {
+        match self.detect_indent {
+            true => indent_cfg.detect_from(content),
+            false => indent_cfg,
+        }
+    }
+
+    pub fn wrap_column(&self, path: &Path) -> usize {
+        path.extension()
+            .and_then(|ext| self.wrap_column_overrides.get(ext.to_str()?))
+            .copied()
+            .unwrap_or(self.wrap_column)
+    }
+
+    pub fn view(&self, family: ViewFamily, file_type: FileType) -> ViewDefaults {
+        let global = ViewSettings {
+            wrap: self.code_wrap,
+            line_numbers: self.line_numbers,
+            relative_line_numbers: self.relative_line_numbers,
+            highlight_current_line: self.highlight_current_line,
+            render_whitespace: self.render_whitespace,
+            rulers: self.rulers.clone(),
+        };
+        let mut keys = vec![family.key()];
+        if family == ViewFamily::Code && file_type != FileType::Ignored {
+            keys.push(file_type.into());
+        }
+        let scopes = keys.into_iter().filter_map(|key| Some((key, self.view_defaults.get(key)?.clone()))).collect();
+        ViewDefaults { global, scopes }
     }
 
     pub fn default_indent_cfg(&self) -> IndentConfigs {
         IndentConfigs {
             indent: (0..self.indent_spaces).map(|_| ' ').collect(),
+            tab_width: self.indent_spaces,
             indent_after: self.indent_after.to_owned(),
             unindent_before: self.unindent_before.to_owned(),
+            auto_close: parse_pairs(&self.auto_close),
+            emphasis: Vec::new(),
+        }
+    }
+
+    pub fn history_limit(&self) -> HistoryLimit {
+        HistoryLimit { entries: self.undo_limit, bytes: self.undo_memory_kb.saturating_mul(1024) }
+    }
+
+    pub fn undo_group_timeout(&self) -> Option<Duration> {
+        match self.undo_group_timeout_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        }
+    }
+
+    /// markdown wraps the select with emphasis and code span markers
+    pub fn get_md_indent_cfg(&self) -> IndentConfigs {
+        let mut indent_cfg = self.default_indent_cfg();
+        if self.md_emphasis {
+            indent_cfg.emphasis = vec!['*', '_', '`'];
+        }
+        indent_cfg
+    }
+
+    pub fn formatter(&self, file_type: &FileType) -> Option<String> {
+        match file_type {
+            FileType::Ignored => None,
+            file_type => self.formatters.get(<&str>::from(*file_type)).cloned(),
         }
     }
 
@@ -139,28 +408,127 @@ impl EditorConfigs {
     }
 }
 
+/// lines checked by indent detection
+const INDENT_SAMPLE: usize = 2000;
+
+/// indent unit of the editor as shown in the status bar ("Tabs", "Spaces: 4")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentLabel {
+    Tabs,
+    Spaces(usize),
+}
+
+impl Default for IndentLabel {
+    fn default() -> Self {
+        Self::Spaces(get_indent_spaces())
+    }
+}
+
+impl std::fmt::Display for IndentLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tabs => f.write_str("Tabs"),
+            Self::Spaces(width) => write!(f, "Spaces: {width}"),
+        }
+    }
+}
+
 pub struct IndentConfigs {
     pub indent: String,
+    /// columns of tab (indent_spaces from the config)
+    pub tab_width: usize,
     pub indent_after: String,
     pub unindent_before: String,
+    /// (opening, closing) pairs inserted together
+    pub auto_close: Vec<(char, char)>,
+    /// markers wrapping the select on both sides (toggled by repeating the char)
+    pub emphasis: Vec<char>,
 }
 
 impl Default for IndentConfigs {
     fn default() -> Self {
-        Self { indent: "    ".to_owned(), unindent_before: get_unident_before(), indent_after: get_indent_after() }
+        Self {
+            indent: "    ".to_owned(),
+            tab_width: get_indent_spaces(),
+            unindent_before: get_unident_before(),
+            indent_after: get_indent_after(),
+            auto_close: parse_pairs(&get_auto_close()),
+            emphasis: Vec::new(),
+        }
     }
 }
 
 impl IndentConfigs {
+    /// indent guessed from the leading whitespace of the content, kept if nothing is indented
+    /// tabs if more lines start with tab, otherwise the most common step between the indents of the lines
+    pub fn detect_from(mut self, content: &[EditorLine]) -> Self {
+        let (mut tabs, mut spaces) = (0, 0);
+        // count of indent increases by step (spaces), steps of single space are usually alignment
+        let mut steps = [0_usize; 9];
+        let mut prev_indent = 0;
+        for line in content.iter().take(INDENT_SAMPLE) {
+            if line.trim_start().is_empty() {
+                continue;
+            }
+            match line.chars().next() {
+                Some('\t') => {
+                    tabs += 1;
+                    continue;
+                }
+                Some(' ') => spaces += 1,
+                _ => (),
+            }
+            let indent = line.chars().take_while(|ch| *ch == ' ').count();
+            if let Some(count) = indent.checked_sub(prev_indent).and_then(|step| steps.get_mut(step)) {
+                *count += 1;
+            }
+            prev_indent = indent;
+        }
+        if tabs > spaces {
+            self.indent = String::from("\t");
+        } else if let Some((step, ..)) =
+            steps.iter().enumerate().skip(2).filter(|(_, count)| **count != 0).max_by_key(|(_, count)| **count)
+        {
+            self.indent = " ".repeat(step);
+        }
+        self
+    }
+
+    /// indent shown in the status bar
+    pub fn indent_label(&self) -> IndentLabel {
+        match self.indent.as_str() {
+            "\t" => IndentLabel::Tabs,
+            indent => IndentLabel::Spaces(indent.len()),
+        }
+    }
+
+    pub fn indent_width(&self) -> usize {
+        match self.indent.as_str() {
+            "\t" => self.tab_width,
+            indent => indent.len(),
+        }
+    }
+
     pub fn update_by_file_type(mut self, file_type: &FileType) -> Self {
-        #[allow(clippy::single_match)]
         match file_type {
             FileType::Python | FileType::Nim | FileType::Lobster => self.indent_after.push(':'),
+            // lifetimes and labels are more common than char literals
+            FileType::Rust => self.auto_close.retain(|(opening, _)| *opening != '\''),
             _ => (),
         }
         self
     }
 
+    #[inline]
+    pub fn closing_char(&self, ch: char) -> Option<char> {
+        self.auto_close.iter().find(|(opening, _)| *opening == ch).map(|(_, closing)| *closing)
+    }
+
+    #[inline]
+    pub fn opening_char(&self, ch: char) -> Option<char> {
+        self.auto_close.iter().find(|(_, closing)| *closing == ch).map(|(opening, _)| *opening)
+    }
+
     pub fn unindent_if_before_base_pattern(&self, line: &mut EditorLine) -> usize {
         if line.starts_with(&self.indent)
             && matches!(line.trim_start().chars().next(), Some(first) if self.unindent_before.contains(first))
@@ -227,3 +595,8 @@ fn map_preload(
     }
     None
 }
+
+/// pairs are written one after another - "{}()" (odd char is dropped)
+fn parse_pairs(pairs: &str) -> Vec<(char, char)> {
+    pairs.chars().collect::<Vec<_>>().chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+}
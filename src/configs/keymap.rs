@@ -12,6 +12,15 @@ pub enum EditorAction {
     Backspace,
     Delete,
     RemoveLine,
+    DeleteLine,
+    DeleteToEndOfLine,
+    DeleteToStartOfLine,
+    ReflowParagraph,
+    RepeatLast,
+    GoToMatchingBracket,
+    Fold,
+    Unfold,
+    FoldAll,
     IndentStart,
     Unintent,
     Up,
@@ -29,6 +38,9 @@ pub enum EditorAction {
     ScrollDown,
     SwapUp,
     SwapDown,
+    MoveLineMode,
+    ColumnSelectUp,
+    ColumnSelectDown,
     JumpLeft,
     JumpLeftSelect,
     JumpRight,
@@ -36,21 +48,32 @@ pub enum EditorAction {
     EndOfLine,
     EndOfFile,
     StartOfLine,
+    SelectStartOfLine,
     StartOfFile,
     FindReferences,
     GoToDeclaration,
+    PeekDefinition,
+    Outline,
     Help,
     LSPRename,
+    Format,
+    CodeAction,
+    QuickFix,
     RefreshUI,
     Cut,
     Copy,
     Paste,
+    PasteReindent,
+    CyclePaste,
+    Register,
     Undo,
     Redo,
     Save,
     Cancel,
     Close,
     CommentOut,
+    FocusOtherSplit,
+    RevertBuffer,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +88,24 @@ pub struct EditorUserKeyMap {
     delete: String,
     #[serde(default = "remove_line")]
     remove_line: String,
+    #[serde(default = "delete_line")]
+    delete_line: String,
+    #[serde(default = "delete_to_end_of_line")]
+    delete_to_end_of_line: String,
+    #[serde(default = "delete_to_start_of_line")]
+    delete_to_start_of_line: String,
+    #[serde(default = "reflow_paragraph")]
+    reflow_paragraph: String,
+    #[serde(default = "repeat_last")]
+    repeat_last: String,
+    #[serde(default = "go_to_matching_bracket")]
+    go_to_matching_bracket: String,
+    #[serde(default = "fold")]
+    fold: String,
+    #[serde(default = "unfold")]
+    unfold: String,
+    #[serde(default = "fold_all")]
+    fold_all: String,
     #[serde(default = "indent_start")]
     indent_start: String,
     #[serde(default = "unindent")]
@@ -99,6 +140,12 @@ pub struct EditorUserKeyMap {
     swap_up: String,
     #[serde(default = "swap_down")]
     swap_down: String,
+    #[serde(default = "move_line_mode")]
+    move_line_mode: String,
+    #[serde(default = "column_select_up")]
+    column_select_up: String,
+    #[serde(default = "column_select_down")]
+    column_select_down: String,
     #[serde(default = "jump_left")]
     jump_left: String,
     #[serde(default = "jump_left_select")]
@@ -113,24 +160,42 @@ pub struct EditorUserKeyMap {
     end_of_file: String,
     #[serde(default = "home")]
     start_of_line: String,
+    #[serde(default = "select_home")]
+    select_start_of_line: String,
     #[serde(default = "start_of_file")]
     start_of_file: String,
     #[serde(default = "find_references")]
     find_references: String,
     #[serde(default = "go_to_declaration")]
     go_to_declaration: String,
+    #[serde(default = "peek_definition")]
+    peek_definition: String,
+    #[serde(default = "outline")]
+    outline: String,
     #[serde(default = "help")]
     help: String,
     #[serde(default = "refresh")]
     refresh_ui: String,
     #[serde(default = "rename")]
     lsp_rename: String,
+    #[serde(default = "format")]
+    format: String,
+    #[serde(default = "code_action")]
+    code_action: String,
+    #[serde(default = "quick_fix")]
+    quick_fix: String,
     #[serde(default = "cut")]
     cut: String,
     #[serde(default = "copy")]
     copy: String,
     #[serde(default = "paste")]
     paste: String,
+    #[serde(default = "paste_reindent")]
+    paste_reindent: String,
+    #[serde(default = "cycle_paste")]
+    cycle_paste: String,
+    #[serde(default = "register")]
+    register: String,
     #[serde(default = "undo")]
     undo: String,
     #[serde(default = "redo")]
@@ -143,6 +208,10 @@ pub struct EditorUserKeyMap {
     close: String,
     #[serde(default = "comment_out")]
     comment_out: String,
+    #[serde(default = "focus_other_split")]
+    focus_other_split: String,
+    #[serde(default = "revert_buffer")]
+    revert_buffer: String,
 }
 
 impl From<EditorUserKeyMap> for HashMap<KeyEvent, EditorAction> {
@@ -153,6 +222,15 @@ impl From<EditorUserKeyMap> for HashMap<KeyEvent, EditorAction> {
         insert_key_event(&mut hash, &val.backspace, EditorAction::Backspace);
         insert_key_event(&mut hash, &val.delete, EditorAction::Delete);
         insert_key_event(&mut hash, &val.remove_line, EditorAction::RemoveLine);
+        insert_key_event(&mut hash, &val.delete_line, EditorAction::DeleteLine);
+        insert_key_event(&mut hash, &val.delete_to_end_of_line, EditorAction::DeleteToEndOfLine);
+        insert_key_event(&mut hash, &val.delete_to_start_of_line, EditorAction::DeleteToStartOfLine);
+        insert_key_event(&mut hash, &val.reflow_paragraph, EditorAction::ReflowParagraph);
+        insert_key_event(&mut hash, &val.repeat_last, EditorAction::RepeatLast);
+        insert_key_event(&mut hash, &val.go_to_matching_bracket, EditorAction::GoToMatchingBracket);
+        insert_key_event(&mut hash, &val.fold, EditorAction::Fold);
+        insert_key_event(&mut hash, &val.unfold, EditorAction::Unfold);
+        insert_key_event(&mut hash, &val.fold_all, EditorAction::FoldAll);
         insert_key_event(&mut hash, &val.indent_start, EditorAction::IndentStart);
         insert_key_event(&mut hash, &val.unindent, EditorAction::Unintent);
         insert_key_event(&mut hash, &val.up, EditorAction::Up);
@@ -170,6 +248,9 @@ impl From<EditorUserKeyMap> for HashMap<KeyEvent, EditorAction> {
         insert_key_event(&mut hash, &val.scroll_down, EditorAction::ScrollDown);
         insert_key_event(&mut hash, &val.swap_up, EditorAction::SwapUp);
         insert_key_event(&mut hash, &val.swap_down, EditorAction::SwapDown);
+        insert_key_event(&mut hash, &val.move_line_mode, EditorAction::MoveLineMode);
+        insert_key_event(&mut hash, &val.column_select_up, EditorAction::ColumnSelectUp);
+        insert_key_event(&mut hash, &val.column_select_down, EditorAction::ColumnSelectDown);
         insert_key_event(&mut hash, &val.jump_left, EditorAction::JumpLeft);
         insert_key_event(&mut hash, &val.jump_left_select, EditorAction::JumpLeftSelect);
         insert_key_event(&mut hash, &val.jump_right, EditorAction::JumpRight);
@@ -177,20 +258,31 @@ impl From<EditorUserKeyMap> for HashMap<KeyEvent, EditorAction> {
         insert_key_event(&mut hash, &val.end_of_line, EditorAction::EndOfLine);
         insert_key_event(&mut hash, &val.end_of_file, EditorAction::EndOfFile);
         insert_key_event(&mut hash, &val.start_of_line, EditorAction::StartOfLine);
+        insert_key_event(&mut hash, &val.select_start_of_line, EditorAction::SelectStartOfLine);
         insert_key_event(&mut hash, &val.start_of_file, EditorAction::StartOfFile);
         insert_key_event(&mut hash, &val.find_references, EditorAction::FindReferences);
         insert_key_event(&mut hash, &val.go_to_declaration, EditorAction::GoToDeclaration);
+        insert_key_event(&mut hash, &val.peek_definition, EditorAction::PeekDefinition);
+        insert_key_event(&mut hash, &val.outline, EditorAction::Outline);
         insert_key_event(&mut hash, &val.help, EditorAction::Help);
         insert_key_event(&mut hash, &val.lsp_rename, EditorAction::LSPRename);
+        insert_key_event(&mut hash, &val.format, EditorAction::Format);
+        insert_key_event(&mut hash, &val.code_action, EditorAction::CodeAction);
+        insert_key_event(&mut hash, &val.quick_fix, EditorAction::QuickFix);
         insert_key_event(&mut hash, &val.cut, EditorAction::Cut);
         insert_key_event(&mut hash, &val.copy, EditorAction::Copy);
         insert_key_event(&mut hash, &val.paste, EditorAction::Paste);
+        insert_key_event(&mut hash, &val.paste_reindent, EditorAction::PasteReindent);
+        insert_key_event(&mut hash, &val.cycle_paste, EditorAction::CyclePaste);
+        insert_key_event(&mut hash, &val.register, EditorAction::Register);
         insert_key_event(&mut hash, &val.undo, EditorAction::Undo);
         insert_key_event(&mut hash, &val.redo, EditorAction::Redo);
         insert_key_event(&mut hash, &val.save, EditorAction::Save);
         insert_key_event(&mut hash, &val.cancel, EditorAction::Cancel);
         insert_key_event(&mut hash, &val.close, EditorAction::Close);
         insert_key_event(&mut hash, &val.comment_out, EditorAction::CommentOut);
+        insert_key_event(&mut hash, &val.focus_other_split, EditorAction::FocusOtherSplit);
+        insert_key_event(&mut hash, &val.revert_buffer, EditorAction::RevertBuffer);
         hash
     }
 }
@@ -203,6 +295,15 @@ impl Default for EditorUserKeyMap {
             backspace: backspace(),
             delete: delete(),
             remove_line: remove_line(),
+            delete_line: delete_line(),
+            delete_to_end_of_line: delete_to_end_of_line(),
+            delete_to_start_of_line: delete_to_start_of_line(),
+            reflow_paragraph: reflow_paragraph(),
+            repeat_last: repeat_last(),
+            go_to_matching_bracket: go_to_matching_bracket(),
+            fold: fold(),
+            unfold: unfold(),
+            fold_all: fold_all(),
             indent_start: indent_start(),
             unindent: unindent(),
             up: up(),
@@ -220,6 +321,9 @@ impl Default for EditorUserKeyMap {
             scroll_down: scroll_down(),
             swap_up: swap_up(),
             swap_down: swap_down(),
+            move_line_mode: move_line_mode(),
+            column_select_up: column_select_up(),
+            column_select_down: column_select_down(),
             jump_left: jump_left(),
             jump_left_select: jump_left_select(),
             jump_right: jump_right(),
@@ -227,21 +331,32 @@ impl Default for EditorUserKeyMap {
             end_of_line: end(),
             end_of_file: end_of_file(),
             start_of_line: home(),
+            select_start_of_line: select_home(),
             start_of_file: start_of_file(),
             find_references: find_references(),
             go_to_declaration: go_to_declaration(),
+            peek_definition: peek_definition(),
+            outline: outline(),
             help: help(),
             refresh_ui: refresh(),
             lsp_rename: rename(),
+            format: format(),
+            code_action: code_action(),
+            quick_fix: quick_fix(),
             cut: cut(),
             copy: copy(),
             paste: paste(),
+            paste_reindent: paste_reindent(),
+            cycle_paste: cycle_paste(),
+            register: register(),
             undo: undo(),
             redo: redo(),
             save: save(),
             cancel: esc(),
             close: close(),
             comment_out: comment_out(),
+            focus_other_split: focus_other_split(),
+            revert_buffer: revert_buffer(),
         }
     }
 }
@@ -389,6 +504,13 @@ pub enum TreeAction {
     NewFile,
     IncreaseSize,
     DecreaseSize,
+    CopyPath,
+    ToggleHidden,
+    ToggleIgnored,
+    ToggleMark,
+    CutFile,
+    CopyFile,
+    Paste,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -411,6 +533,20 @@ pub struct TreeUserKeyMap {
     increase_size: String,
     #[serde(default = "tree_size_dec")]
     decrease_size: String,
+    #[serde(default = "copy_path")]
+    copy_path: String,
+    #[serde(default = "toggle_hidden")]
+    toggle_hidden: String,
+    #[serde(default = "toggle_ignored")]
+    toggle_ignored: String,
+    #[serde(default = "toggle_mark")]
+    toggle_mark: String,
+    #[serde(default = "cut_file")]
+    cut_file: String,
+    #[serde(default = "copy_file")]
+    copy_file: String,
+    #[serde(default = "paste_file")]
+    paste: String,
 }
 
 impl Default for TreeUserKeyMap {
@@ -425,6 +561,13 @@ impl Default for TreeUserKeyMap {
             new_file: new_file(),
             increase_size: tree_size_inc(),
             decrease_size: tree_size_dec(),
+            copy_path: copy_path(),
+            toggle_hidden: toggle_hidden(),
+            toggle_ignored: toggle_ignored(),
+            toggle_mark: toggle_mark(),
+            cut_file: cut_file(),
+            copy_file: copy_file(),
+            paste: paste_file(),
         }
     }
 }
@@ -441,6 +584,13 @@ impl From<TreeUserKeyMap> for HashMap<KeyEvent, TreeAction> {
         insert_key_event(&mut hash, &val.new_file, TreeAction::NewFile);
         insert_key_event(&mut hash, &val.increase_size, TreeAction::IncreaseSize);
         insert_key_event(&mut hash, &val.decrease_size, TreeAction::DecreaseSize);
+        insert_key_event(&mut hash, &val.copy_path, TreeAction::CopyPath);
+        insert_key_event(&mut hash, &val.toggle_hidden, TreeAction::ToggleHidden);
+        insert_key_event(&mut hash, &val.toggle_ignored, TreeAction::ToggleIgnored);
+        insert_key_event(&mut hash, &val.toggle_mark, TreeAction::ToggleMark);
+        insert_key_event(&mut hash, &val.cut_file, TreeAction::CutFile);
+        insert_key_event(&mut hash, &val.copy_file, TreeAction::CopyFile);
+        insert_key_event(&mut hash, &val.paste, TreeAction::Paste);
         hash
     }
 }
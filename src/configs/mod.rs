@@ -1,19 +1,23 @@
 mod defaults;
 mod editor;
 mod keymap;
+mod patterns;
 mod theme;
 mod theme_ui;
 mod types;
+mod view;
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use dirs::config_dir;
-pub use editor::{EditorConfigs, IndentConfigs};
+pub use editor::{AutoSave, ClipboardProvider, EditorConfigs, IndentConfigs, IndentLabel, RenderWhitespace, TreeSort};
 pub use keymap::{EditorAction, EditorUserKeyMap, GeneralAction, GeneralUserKeyMap, TreeAction, TreeUserKeyMap};
+pub use patterns::{SavedPattern, SavedPatterns, SearchOptions};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{collections::HashMap, path::PathBuf};
 pub use theme::Theme;
 pub use theme_ui::UITheme;
 pub use types::FileType;
+pub use view::{ViewDefaults, ViewFamily, ViewOverrides, ViewSettings};
 
 pub const CONFIG_FOLDER: &str = "idiom";
 pub const EDITOR_CFG_FILE: &str = "editor.toml";
@@ -0,0 +1,173 @@
+use super::get_config_dir;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const PATTERNS_FILE: &str = "patterns.toml";
+
+/// how find/replace matches the pattern
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub ignore_case: bool,
+    pub whole_word: bool,
+}
+
+impl SearchOptions {
+    /// literal case sensitive search - no regex needed
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn build(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        let pattern = match self.regex {
+            true => pattern.to_owned(),
+            false => regex::escape(pattern),
+        };
+        let pattern = match self.whole_word {
+            true => format!(r"\b(?:{pattern})\b"),
+            false => pattern,
+        };
+        RegexBuilder::new(&pattern).case_insensitive(self.ignore_case).build()
+    }
+
+    /// short label of the enabled options, empty for plain search
+    pub fn label(&self) -> String {
+        let flags = [
+            (self.regex, "regex"),
+            (self.ignore_case, "nocase"),
+            (self.whole_word, "word"),
+        ];
+        let enabled = flags.into_iter().filter(|(on, _)| *on).map(|(_, name)| name).collect::<Vec<_>>();
+        match enabled.is_empty() {
+            true => String::new(),
+            false => format!("[{}] ", enabled.join(" ")),
+        }
+    }
+}
+
+/// named find/replace combination stored in the config folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPattern {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+    #[serde(default)]
+    pub options: SearchOptions,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedPatterns {
+    #[serde(default)]
+    pub patterns: Vec<SavedPattern>,
+}
+
+impl SavedPatterns {
+    /// missing or broken file gives empty list
+    pub fn load() -> Self {
+        get_config_dir().and_then(|dir| Self::read(&dir.join(PATTERNS_FILE)).ok()).unwrap_or_default()
+    }
+
+    /// stores the pattern keeping the ones written by other instances, same name is overwritten
+    pub fn store(pattern: SavedPattern) -> Result<(), String> {
+        let dir = get_config_dir().ok_or("Config folder not found!")?;
+        std::fs::create_dir_all(&dir).map_err(|error| error.to_string())?;
+        Self::store_to(&dir.join(PATTERNS_FILE), pattern)
+    }
+
+    fn store_to(path: &Path, pattern: SavedPattern) -> Result<(), String> {
+        // merged with the file as it is now - other instances could have written since the last load
+        let mut saved = match Self::read(path) {
+            Ok(saved) => saved,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(error) => return Err(format!("{}: {error}", path.display())),
+        };
+        saved.insert(pattern);
+        let text = toml::to_string_pretty(&saved).map_err(|error| error.to_string())?;
+        // rename is atomic - readers see the old or the new file, never partial write
+        let temp = path.with_extension(format!("toml.{}.tmp", std::process::id()));
+        std::fs::write(&temp, text).map_err(|error| error.to_string())?;
+        std::fs::rename(&temp, path).map_err(|error| {
+            let _ = std::fs::remove_file(&temp);
+            error.to_string()
+        })
+    }
+
+    fn read(path: &Path) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let loaded = toml::from_str::<Self>(&text).map_err(std::io::Error::other)?;
+        // hand edited files could repeat names - the last one wins
+        let mut saved = Self::default();
+        for pattern in loaded.patterns {
+            saved.insert(pattern);
+        }
+        Ok(saved)
+    }
+
+    fn insert(&mut self, pattern: SavedPattern) {
+        match self.patterns.iter_mut().find(|saved| saved.name == pattern.name) {
+            Some(saved) => *saved = pattern,
+            None => self.patterns.push(pattern),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SavedPattern, SavedPatterns, SearchOptions};
+
+    fn pattern(name: &str, pattern: &str) -> SavedPattern {
+        SavedPattern {
+            name: name.to_owned(),
+            pattern: pattern.to_owned(),
+            replacement: String::new(),
+            options: SearchOptions::default(),
+        }
+    }
+
+    #[test]
+    fn merge_on_store() {
+        let path = std::env::temp_dir().join(format!("idiom_patterns_{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let quotes = SavedPattern {
+            replacement: "\"".to_owned(),
+            options: SearchOptions { regex: true, ignore_case: false, whole_word: true },
+            ..pattern("quotes", "'")
+        };
+        SavedPatterns::store_to(&path, quotes.clone()).unwrap();
+        // another instance stores without knowing about "quotes"
+        SavedPatterns::store_to(&path, pattern("timestamps", r"\d{2}:\d{2}")).unwrap();
+        SavedPatterns::store_to(&path, pattern("quotes", "`")).unwrap();
+        let saved = SavedPatterns::read(&path).unwrap();
+        assert_eq!(saved.patterns, vec![pattern("quotes", "`"), pattern("timestamps", r"\d{2}:\d{2}")]);
+        // duplicated names in hand edited file
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str(&toml::to_string(&SavedPatterns { patterns: vec![quotes.clone()] }).unwrap());
+        std::fs::write(&path, text).unwrap();
+        assert_eq!(SavedPatterns::read(&path).unwrap().patterns[0], quotes);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn broken_file_is_kept() {
+        let path = std::env::temp_dir().join(format!("idiom_patterns_broken_{}.toml", std::process::id()));
+        std::fs::write(&path, "patterns = 3").unwrap();
+        assert!(SavedPatterns::store_to(&path, pattern("name", "text")).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "patterns = 3");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn search_options() {
+        let options = SearchOptions { regex: false, ignore_case: true, whole_word: true };
+        let matcher = options.build("a.b").unwrap();
+        assert!(matcher.is_match("x A.B y"));
+        assert!(!matcher.is_match("xa.b"));
+        assert!(!matcher.is_match("axb"));
+        assert_eq!(options.label(), "[nocase word] ");
+        assert!(SearchOptions { regex: true, ..Default::default() }.build("(").is_err());
+        assert!(SearchOptions::default().is_plain());
+    }
+}
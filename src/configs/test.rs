@@ -1,5 +1,50 @@
-use super::{EditorKeyMap, EditorUserKeyMap};
+use super::{EditorKeyMap, EditorUserKeyMap, TreeKeyMap, TreeUserKeyMap};
 
 pub fn mock_editor_key_map() -> EditorKeyMap {
     EditorKeyMap { key_map: EditorUserKeyMap::default().into() }
 }
+
+pub fn mock_tree_key_map() -> TreeKeyMap {
+    TreeKeyMap { key_map: TreeUserKeyMap::default().into() }
+}
+
+mod indent {
+    use crate::configs::{EditorConfigs, IndentConfigs};
+    use crate::workspace::line::EditorLine;
+
+    fn lines(text: &str) -> Vec<EditorLine> {
+        text.split('\n').map(|line| EditorLine::from(line.to_owned())).collect()
+    }
+
+    #[test]
+    fn detect_spaces() {
+        let content = lines("def f():\n  if x:\n    y()\n\n  return\n/*\n * aligned\n */");
+        assert_eq!(IndentConfigs::default().detect_from(&content).indent, "  ");
+        let content = lines("fn f() {\n    let x = 1;\n    if x {\n        g();\n    }\n}");
+        assert_eq!(IndentConfigs::default().detect_from(&content).indent_label().to_string(), "Spaces: 4");
+    }
+
+    #[test]
+    fn detect_tabs() {
+        let content = lines("func f() {\n\tx := 1\n\tif x {\n\t\tg()\n\t}\n   // odd\n}");
+        let cfg = IndentConfigs::default().detect_from(&content);
+        assert_eq!(cfg.indent, "\t");
+        assert_eq!(cfg.indent_label().to_string(), "Tabs");
+    }
+
+    #[test]
+    fn nothing_to_detect() {
+        let content = lines("a\nb\n\n c");
+        let cfg = IndentConfigs { indent: "   ".to_owned(), ..Default::default() };
+        assert_eq!(cfg.detect_from(&content).indent, "   ");
+    }
+
+    #[test]
+    fn tab_width_from_config() {
+        let mut configs = EditorConfigs::default();
+        configs.indent_spaces = 2;
+        let mut cfg = configs.default_indent_cfg();
+        cfg.indent = String::from("\t");
+        assert_eq!(cfg.indent_width(), 2);
+    }
+}
@@ -17,6 +17,8 @@ const NUMERIC: Color = color::rgb(153, 173, 142);
 const SELECTED: Color = color::rgb(72, 72, 72);
 const STRING: Color = color::dark_yellow();
 const STRING_ESCAPE: Color = color::yellow();
+const TRAILING_WHITESPACE: Color = color::rgb(84, 48, 48);
+const CURRENT_LINE: Color = color::rgb(38, 38, 38);
 
 #[derive(Debug, Clone)]
 pub struct Theme {
@@ -33,6 +35,8 @@ pub struct Theme {
     pub string: Color,
     pub string_escape: Color,
     pub comment: Color,
+    pub trailing_whitespace: Color,
+    pub current_line: Color,
 }
 
 impl Serialize for Theme {
@@ -40,7 +44,7 @@ impl Serialize for Theme {
     where
         S: serde::Serializer,
     {
-        let mut s = serializer.serialize_struct("Theme", 13)?;
+        let mut s = serializer.serialize_struct("Theme", 15)?;
         s.serialize_field("imports", &serialize_rgb(112, 199, 176))?;
         s.serialize_field("key_words", &serialize_rgb(79, 106, 214))?;
         s.serialize_field("flow_control", "lightmagenta")?;
@@ -54,6 +58,8 @@ impl Serialize for Theme {
         s.serialize_field("selected", &serialize_rgb(72, 72, 72))?;
         s.serialize_field("string", "yellow")?;
         s.serialize_field("string_escape", "lightyellow")?;
+        s.serialize_field("trailing_whitespace", &serialize_rgb(84, 48, 48))?;
+        s.serialize_field("current_line", &serialize_rgb(38, 38, 38))?;
         s.end()
     }
 }
@@ -88,6 +94,12 @@ impl<'de> serde::Deserialize<'de> for Theme {
                 string_escape: pull_color(&mut map, "string_escape")
                     .unwrap_or(Ok(STRING_ESCAPE))
                     .map_err(serde::de::Error::custom)?,
+                trailing_whitespace: pull_color(&mut map, "trailing_whitespace")
+                    .unwrap_or(Ok(TRAILING_WHITESPACE))
+                    .map_err(serde::de::Error::custom)?,
+                current_line: pull_color(&mut map, "current_line")
+                    .unwrap_or(Ok(CURRENT_LINE))
+                    .map_err(serde::de::Error::custom)?,
             }),
             _ => Err(serde::de::Error::custom(IdiomError::io_err("theme.json in not an Object!"))),
         }
@@ -110,6 +122,8 @@ impl Default for Theme {
             selected: SELECTED,
             string: STRING,
             string_escape: STRING_ESCAPE,
+            trailing_whitespace: TRAILING_WHITESPACE,
+            current_line: CURRENT_LINE,
         }
     }
 }
@@ -24,7 +24,25 @@ impl FileType {
     #[allow(clippy::ptr_arg)]
     pub fn derive_type(path: &PathBuf) -> Option<Self> {
         let extension = path.extension().and_then(|e| e.to_str())?;
-        match extension.to_lowercase().as_str() {
+        Self::from_extension(&extension.to_lowercase())
+    }
+
+    /// language of markdown code fences - name (rust, python) or extension (rs, py)
+    pub fn from_lang(lang: &str) -> Option<Self> {
+        match lang.to_lowercase().as_str() {
+            "rust" => Some(Self::Rust),
+            "python" => Some(Self::Python),
+            "javascript" => Some(Self::JavaScript),
+            "typescript" => Some(Self::TypeScript),
+            "c++" => Some(Self::Cpp),
+            "yaml" => Some(Self::Yml),
+            "shell" | "shellscript" | "bash" | "zsh" => Some(Self::Shell),
+            lang => Self::from_extension(lang),
+        }
+    }
+
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
             "rs" => Some(Self::Rust),
             "zig" => Some(Self::Zig),
             "c" => Some(Self::C),
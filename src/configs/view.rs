@@ -0,0 +1,119 @@
+use super::RenderWhitespace;
+use serde::{Deserialize, Serialize};
+
+/// kind of editor opened for the file, view defaults are looked up by it before the file type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViewFamily {
+    Code,
+    Text,
+    Markdown,
+}
+
+impl ViewFamily {
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Code => "code",
+            Self::Text => "text",
+            Self::Markdown => "markdown",
+        }
+    }
+}
+
+/// display settings of an editor
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewSettings {
+    /// soft wrap of code - text and markdown are always wrapped
+    pub wrap: bool,
+    pub line_numbers: bool,
+    pub relative_line_numbers: bool,
+    pub highlight_current_line: bool,
+    pub render_whitespace: RenderWhitespace,
+    pub rulers: Vec<usize>,
+}
+
+/// view settings set by a scope, the rest is taken from the wider one
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewOverrides {
+    pub wrap: Option<bool>,
+    pub line_numbers: Option<bool>,
+    pub relative_line_numbers: Option<bool>,
+    pub highlight_current_line: Option<bool>,
+    pub render_whitespace: Option<RenderWhitespace>,
+    pub rulers: Option<Vec<usize>>,
+}
+
+impl ViewOverrides {
+    fn apply(&self, settings: &mut ViewSettings) {
+        if let Some(wrap) = self.wrap {
+            settings.wrap = wrap;
+        }
+        if let Some(line_numbers) = self.line_numbers {
+            settings.line_numbers = line_numbers;
+        }
+        if let Some(relative_line_numbers) = self.relative_line_numbers {
+            settings.relative_line_numbers = relative_line_numbers;
+        }
+        if let Some(highlight_current_line) = self.highlight_current_line {
+            settings.highlight_current_line = highlight_current_line;
+        }
+        if let Some(render_whitespace) = self.render_whitespace {
+            settings.render_whitespace = render_whitespace;
+        }
+        if let Some(rulers) = self.rulers.as_ref() {
+            settings.rulers.clone_from(rulers);
+        }
+    }
+}
+
+/// global view settings with the overrides of the family and the file type (in order of precedence)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewDefaults {
+    pub global: ViewSettings,
+    pub scopes: Vec<(&'static str, ViewOverrides)>,
+}
+
+impl ViewDefaults {
+    /// settings of the buffer - its toggles win over the defaults
+    pub fn resolve(&self, buffer: &ViewOverrides) -> ViewSettings {
+        let mut settings = self.global.clone();
+        for (_, overrides) in self.scopes.iter() {
+            overrides.apply(&mut settings);
+        }
+        buffer.apply(&mut settings);
+        settings
+    }
+
+    /// effective settings with the scope each comes from
+    pub fn report(&self, buffer: &ViewOverrides) -> String {
+        let settings = self.resolve(buffer);
+        let source = |is_set: fn(&ViewOverrides) -> bool| {
+            if is_set(buffer) {
+                return "buffer";
+            }
+            self.scopes.iter().rev().find(|(_, overrides)| is_set(overrides)).map(|(key, _)| *key).unwrap_or("global")
+        };
+        format!(
+            "wrap {} ({}), line numbers {} ({}), relative numbers {} ({}), current line {} ({}), whitespace {:?} ({}), rulers {:?} ({})",
+            on_off(settings.wrap),
+            source(|overrides| overrides.wrap.is_some()),
+            on_off(settings.line_numbers),
+            source(|overrides| overrides.line_numbers.is_some()),
+            on_off(settings.relative_line_numbers),
+            source(|overrides| overrides.relative_line_numbers.is_some()),
+            on_off(settings.highlight_current_line),
+            source(|overrides| overrides.highlight_current_line.is_some()),
+            settings.render_whitespace,
+            source(|overrides| overrides.render_whitespace.is_some()),
+            settings.rulers,
+            source(|overrides| overrides.rulers.is_some()),
+        )
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    match value {
+        true => "on",
+        false => "off",
+    }
+}
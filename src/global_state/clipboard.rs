@@ -1,36 +1,244 @@
+use crate::configs::ClipboardProvider as ProviderKind;
 use copypasta::{ClipboardContext, ClipboardProvider};
+use std::collections::{HashMap, VecDeque};
 
-pub enum Clipboard {
+pub const HISTORY_SIZE: usize = 20;
+/// encoded payload accepted by most terminals
+pub const OSC52_LIMIT: usize = 100_000;
+
+pub struct Clipboard {
+    provider: Provider,
+    /// clips of the internal provider - kept when the provider is switched
+    internal: Vec<String>,
+    /// clips not reaching the system clipboard are sent to the terminal
+    osc52: bool,
+    osc52_clip: Option<String>,
+    /// running over SSH - the system clipboard (if any) is the one of the server, clips go to the terminal as well
+    remote: bool,
+    // newest first - each clip pushed or pulled
+    history: VecDeque<String>,
+    history_size: usize,
+    /// named clips (a-z, 0-9) - kept apart from the system clipboard and the history
+    registers: HashMap<char, String>,
+}
+
+enum Provider {
     System(ClipboardContext),
-    Internal(Vec<String>),
+    Internal,
 }
 
 impl Default for Clipboard {
     fn default() -> Self {
-        if let Ok(clipboard) = ClipboardContext::new() {
-            Self::System(clipboard)
-        } else {
-            Self::Internal(Vec::new())
+        let provider =
+            if let Ok(clipboard) = ClipboardContext::new() { Provider::System(clipboard) } else { Provider::Internal };
+        Self {
+            provider,
+            internal: Vec::new(),
+            osc52: true,
+            osc52_clip: None,
+            remote: is_remote_session(),
+            history: VecDeque::new(),
+            history_size: HISTORY_SIZE,
+            registers: HashMap::new(),
         }
     }
 }
 
 impl Clipboard {
     pub fn pull(&mut self) -> Option<String> {
-        match self {
-            Self::System(cliboard) => cliboard.get_contents().ok(),
-            Self::Internal(inner) => inner.pop(),
-        }
+        let clip = match &mut self.provider {
+            Provider::System(cliboard) => cliboard.get_contents().ok(),
+            Provider::Internal => self.internal.pop(),
+        }?;
+        self.record(&clip);
+        Some(clip)
     }
 
     pub fn push(&mut self, clip: String) {
-        match self {
-            Self::System(clipboard) => {
-                let _ = clipboard.set_contents(clip);
+        self.record(&clip);
+        match &mut self.provider {
+            Provider::System(clipboard) => {
+                let failed = clipboard.set_contents(clip.clone()).is_err();
+                if self.osc52 && (failed || self.remote) {
+                    self.osc52_clip = Some(clip);
+                }
+            }
+            Provider::Internal => {
+                if self.osc52 {
+                    self.osc52_clip = Some(clip.clone());
+                }
+                self.internal.push(clip);
+            }
+        }
+    }
+
+    /// native falls back on terminal clipboard (OSC 52) if the system one is not available
+    /// the latest clip is handed over to the new provider, internal clips are kept for switching back
+    pub fn set_provider(&mut self, kind: ProviderKind) {
+        self.osc52 = kind != ProviderKind::Internal;
+        match kind {
+            ProviderKind::Native => {
+                if matches!(self.provider, Provider::Internal) {
+                    if let Ok(mut clipboard) = ClipboardContext::new() {
+                        if let Some(clip) = self.internal.last() {
+                            let _ = clipboard.set_contents(clip.to_owned());
+                        }
+                        self.provider = Provider::System(clipboard);
+                    }
+                }
             }
-            Self::Internal(inner) => {
-                inner.push(clip);
+            ProviderKind::Osc52 | ProviderKind::Internal => {
+                if let Provider::System(clipboard) = &mut self.provider {
+                    if let Ok(clip) = clipboard.get_contents() {
+                        if !clip.is_empty() && self.internal.last() != Some(&clip) {
+                            self.internal.push(clip);
+                        }
+                    }
+                    self.provider = Provider::Internal;
+                }
             }
         }
     }
+
+    /// latest clip waiting to be sent to the terminal clipboard
+    pub fn take_osc52(&mut self) -> Option<String> {
+        self.osc52_clip.take()
+    }
+
+    /// clip from history (0 is the latest), does not change the clipboard content
+    pub fn history(&self, idx: usize) -> Option<&str> {
+        self.history.get(idx).map(String::as_str)
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// clips in history, the latest first
+    pub fn history_iter(&self) -> impl Iterator<Item = &str> {
+        self.history.iter().map(String::as_str)
+    }
+
+    pub fn set_register(&mut self, name: char, clip: String) {
+        self.registers.insert(name, clip);
+    }
+
+    pub fn register(&self, name: char) -> Option<&str> {
+        self.registers.get(&name).map(String::as_str)
+    }
+
+    /// at least the latest clip is kept
+    pub fn set_history_size(&mut self, size: usize) {
+        self.history_size = size.max(1);
+        self.history.truncate(self.history_size);
+    }
+
+    /// the longest start of the clip fitting the terminal limit once encoded
+    pub fn osc52_fit(clip: &str) -> &str {
+        let mut end = std::cmp::min(clip.len(), OSC52_LIMIT / 4 * 3);
+        while !clip.is_char_boundary(end) {
+            end -= 1;
+        }
+        &clip[..end]
+    }
+
+    fn record(&mut self, clip: &str) {
+        if self.history.front().map(String::as_str) == Some(clip) {
+            return;
+        }
+        self.history.retain(|old| old != clip);
+        self.history.push_front(clip.to_owned());
+        self.history.truncate(self.history_size);
+    }
+}
+
+fn is_remote_session() -> bool {
+    ["SSH_TTY", "SSH_CONNECTION", "SSH_CLIENT"].into_iter().any(|var| std::env::var_os(var).is_some())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Clipboard, Provider, OSC52_LIMIT};
+    use crate::configs::ClipboardProvider;
+    use crate::render::backend::encode_base64;
+    use std::collections::{HashMap, VecDeque};
+
+    fn internal(history_size: usize) -> Clipboard {
+        Clipboard {
+            provider: Provider::Internal,
+            internal: Vec::new(),
+            osc52: false,
+            osc52_clip: None,
+            remote: false,
+            history: VecDeque::new(),
+            history_size,
+            registers: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn history_ring() {
+        let mut clipboard = internal(3);
+        for clip in ["first", "second", "second", "third", "fourth"] {
+            clipboard.push(clip.to_owned());
+        }
+        assert_eq!(clipboard.history_iter().collect::<Vec<_>>(), ["fourth", "third", "second"]);
+        // repeated clip moves to the front
+        clipboard.push(String::from("second"));
+        assert_eq!(clipboard.history_iter().collect::<Vec<_>>(), ["second", "fourth", "third"]);
+        assert_eq!(clipboard.pull().as_deref(), Some("second"));
+        clipboard.set_history_size(0);
+        assert_eq!(clipboard.history_iter().collect::<Vec<_>>(), ["second"]);
+    }
+
+    #[test]
+    fn osc52_fallback() {
+        let mut clipboard = internal(3);
+        clipboard.push(String::from("kept"));
+        assert_eq!(clipboard.take_osc52(), None);
+        clipboard.set_provider(ClipboardProvider::Osc52);
+        clipboard.push(String::from("first"));
+        clipboard.push(String::from("second"));
+        assert_eq!(clipboard.take_osc52().as_deref(), Some("second"));
+        assert_eq!(clipboard.take_osc52(), None);
+        // paste is served internally
+        assert_eq!(clipboard.pull().as_deref(), Some("second"));
+        clipboard.set_provider(ClipboardProvider::Internal);
+        clipboard.push(String::from("third"));
+        assert_eq!(clipboard.take_osc52(), None);
+    }
+
+    #[test]
+    fn clips_kept_over_provider_switch() {
+        let mut clipboard = internal(3);
+        clipboard.push(String::from("first"));
+        clipboard.push(String::from("second"));
+        // native is used only if the system clipboard is available
+        clipboard.set_provider(ClipboardProvider::Native);
+        clipboard.set_provider(ClipboardProvider::Internal);
+        assert_eq!(clipboard.pull().as_deref(), Some("second"));
+        assert_eq!(clipboard.pull().as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn osc52_payload() {
+        assert_eq!(Clipboard::osc52_fit("short"), "short");
+        let long = "ж".repeat(OSC52_LIMIT);
+        let fit = Clipboard::osc52_fit(&long);
+        assert!(fit.len() < long.len());
+        assert!(encode_base64(fit.as_bytes()).len() <= OSC52_LIMIT);
+        let encoded = ["", "f", "fo", "foo", "foob", "fooba", "foobar"].map(|text| encode_base64(text.as_bytes()));
+        assert_eq!(encoded, ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"]);
+    }
+
+    #[test]
+    fn registers_apart_from_history() {
+        let mut clipboard = internal(3);
+        clipboard.push(String::from("clip"));
+        clipboard.set_register('a', String::from("named"));
+        assert_eq!(clipboard.register('a'), Some("named"));
+        assert_eq!(clipboard.register('b'), None);
+        assert_eq!(clipboard.history_iter().collect::<Vec<_>>(), ["clip"]);
+        assert_eq!(clipboard.pull().as_deref(), Some("clip"));
+    }
 }
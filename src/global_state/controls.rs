@@ -18,6 +18,24 @@ pub enum PopupMessage {
     Clear,
 }
 
+/// keyboard paths for mouse only affordances - shown once if mouse is not available
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyboardHint {
+    Tree,
+    Tabs,
+    Popup,
+}
+
+impl KeyboardHint {
+    pub const fn text(self) -> &'static str {
+        match self {
+            Self::Tree => "Tree: Esc to focus, Up/Down to move, Enter to open, Ctrl+E to hide",
+            Self::Tabs => "Tabs: Tab to focus, Alt+1..9 to jump, Ctrl+Up/Down to pick from list",
+            Self::Popup => "Popup: Up/Down to move, Enter to confirm, Esc to close",
+        }
+    }
+}
+
 #[derive(Default)]
 pub enum Mode {
     #[default]
@@ -81,11 +99,19 @@ pub fn mouse_handler(gs: &mut GlobalState, event: MouseEvent, tree: &mut Tree, w
             }
         }
         MouseEventKind::Down(MouseButton::Left) => {
-            if let Some(position) = gs.editor_area.relative_position(event.row, event.column) {
+            if matches!(gs.mode, Mode::Insert)
+                && workspace.get_active().is_some_and(|editor| editor.mouse_modal(event.row, event.column, gs))
+            {
+                return;
+            }
+            if let Some(position) = workspace.mouse_position(event.row, event.column, gs) {
+                let follow_file = workspace.tree_follows_file();
                 if let Some(editor) = workspace.get_active() {
                     editor.mouse_cursor(position);
                     gs.insert_mode();
-                    tree.select_by_path(&editor.path);
+                    if follow_file {
+                        tree.select_by_path(&editor.path);
+                    }
                     workspace.toggle_editor();
                 }
                 return;
@@ -101,7 +127,7 @@ pub fn mouse_handler(gs: &mut GlobalState, event: MouseEvent, tree: &mut Tree, w
             if let Some(pos) = gs.tab_area.relative_position(event.row, event.column) {
                 if !workspace.is_empty() {
                     gs.insert_mode();
-                    if let Some(idx) = workspace.select_tab_mouse(pos.char) {
+                    if let Some(idx) = workspace.select_tab_mouse(pos.char, gs.tab_area.width) {
                         workspace.activate_editor(idx, gs);
                     };
                 }
@@ -115,16 +141,16 @@ pub fn mouse_handler(gs: &mut GlobalState, event: MouseEvent, tree: &mut Tree, w
             if let Some(pos) = gs.tab_area.relative_position(event.row, event.column) {
                 if !workspace.is_empty() {
                     gs.insert_mode();
-                    if let Some(idx) = workspace.select_tab_mouse(pos.char) {
+                    if let Some(idx) = workspace.select_tab_mouse(pos.char, gs.tab_area.width) {
                         workspace.activate_editor(idx, gs);
                         workspace.close_active(gs);
                     }
                 }
             }
-            if let Some(position) = gs.editor_area.relative_position(event.row, event.column) {
+            if let Some(position) = workspace.mouse_position(event.row, event.column, gs) {
                 if let Some(editor) = workspace.get_active() {
-                    if let Some(clip) = editor.mouse_copy_paste(position, gs.clipboard.pull()) {
-                        gs.clipboard.push(clip);
+                    if let Some(clip) = editor.mouse_copy_paste(position, gs.clipboard_paste()) {
+                        gs.clipboard_copy(clip);
                         gs.success("Copied select!");
                     };
                     gs.insert_mode();
@@ -132,7 +158,7 @@ pub fn mouse_handler(gs: &mut GlobalState, event: MouseEvent, tree: &mut Tree, w
             }
         }
         MouseEventKind::Drag(MouseButton::Left) => {
-            if let Some(position) = gs.editor_area.relative_position(event.row, event.column) {
+            if let Some(position) = workspace.mouse_position_focused(event.row, event.column, gs) {
                 if let Some(editor) = workspace.get_active() {
                     editor.mouse_select(position);
                     gs.insert_mode();
@@ -35,6 +35,7 @@ pub fn full_rebuild(
     term: &mut EditorTerminal,
 ) -> Result<()> {
     gs.screen_rect.clear(&mut gs.writer);
+    gs.status_line.invalidate();
     let mut tree_area = gs.screen_rect;
     gs.footer_area = tree_area.splitoff_rows(1);
     if let Some(mut line) = gs.footer_area.get_line(0) {
@@ -65,9 +66,7 @@ pub fn full_rebuild(
 
     gs.editor_area = gs.tab_area.keep_rows(1);
     workspace.render(gs);
-    if let Some(editor) = workspace.get_active() {
-        editor.render(gs);
-    }
+    workspace.render_editors(gs);
 
     // term override
     if gs.components.contains(Components::TERM) {
@@ -90,9 +89,7 @@ pub fn draw(
     _term: &mut EditorTerminal,
 ) -> Result<()> {
     workspace.render(gs);
-    if let Some(editor) = workspace.get_active() {
-        editor.fast_render(gs);
-    } else {
+    if !workspace.fast_render_editors(gs) {
         gs.messages.fast_render(gs.theme.accent_style, &mut gs.writer);
     };
     gs.writer.flush()
@@ -106,9 +103,7 @@ pub fn draw_with_tree(
 ) -> Result<()> {
     tree.fast_render(gs);
     workspace.render(gs);
-    if let Some(editor) = workspace.get_active() {
-        editor.fast_render(gs);
-    } else {
+    if !workspace.fast_render_editors(gs) {
         gs.messages.fast_render(gs.theme.accent_style, &mut gs.writer);
     };
     gs.writer.flush()
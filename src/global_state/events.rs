@@ -1,14 +1,27 @@
 use super::{GlobalState, PopupMessage};
+use crate::cli::FileAnchor;
 use crate::lsp::TreeDiagnostics;
 use crate::popups::{
-    popup_replace::ReplacePopup, popup_tree_search::ActiveFileSearch, popups_editor::selector_ranges, PopupInterface,
+    popup_clipboard::ClipboardPopup,
+    popup_diagnostics::DiagnosticsPopup,
+    popup_diff::DiskDiffPopup,
+    popup_file_finder::FileFinderPopup,
+    popup_project_search::ProjectSearchPopup,
+    popup_replace::ReplacePopup,
+    popup_tree_search::ActiveFileSearch,
+    popup_workspace_symbols::SymbolSearchPopup,
+    popups_editor::{selector_patterns, selector_ranges},
+    PopupInterface,
 };
 use crate::tree::Tree;
-use crate::workspace::Workspace;
-use crate::{configs::FileType, workspace::CursorPosition};
+use crate::workspace::{Opened, Workspace};
+use crate::{
+    configs::{FileType, SavedPattern, SavedPatterns, SearchOptions},
+    workspace::{snippet::Snippet, CursorPosition},
+};
 use lsp_types::{
-    request::GotoDeclarationResponse, CompletionItem, CompletionTextEdit, InsertTextFormat, Location, LocationLink,
-    WorkspaceEdit,
+    request::GotoDeclarationResponse, CodeActionOrCommand, CompletionItem, CompletionTextEdit, InsertTextFormat,
+    Location, LocationLink, TextEdit, WorkspaceEdit,
 };
 use std::path::PathBuf;
 
@@ -19,31 +32,76 @@ pub enum IdiomEvent {
     NewPopup(fn() -> Box<dyn PopupInterface>),
     OpenAtLine(PathBuf, usize),
     OpenAtSelect(PathBuf, (CursorPosition, CursorPosition)),
+    OpenAtAnchor(PathBuf, FileAnchor),
+    OpenAtPosition(PathBuf, CursorPosition),
+    RecentFiles,
+    FindFile,
+    SearchProject,
+    ReplaceInFiles {
+        files: Vec<PathBuf>,
+        pattern: String,
+        options: SearchOptions,
+        new_text: String,
+    },
+    SearchSymbols,
+    ProjectDiagnostics,
+    ClipboardHistory,
+    PasteFromHistory(usize),
+    NewScratch,
+    SetWrapColumn(usize),
+    SetIndent(String),
+    ShowViewSettings,
+    AlignComments,
     SelectPath(PathBuf),
     CreateFileOrFolder {
         name: String,
         from_base: bool,
     },
     RenameFile(String),
+    DeleteMarked,
+    PasteFiles,
     SearchFiles(String),
     FileUpdated(PathBuf),
+    /// lines of the file parsed in the background are set - LSP is attached
+    FileParsed(PathBuf),
     CheckLSP(FileType),
     TreeDiagnostics(TreeDiagnostics),
     AutoComplete(String),
-    Snippet(String, Option<(usize, usize)>),
+    Snippet(Snippet),
+    /// additional edits of the accepted completion (imports), applied into its undo group
+    CompletionEdits(Vec<TextEdit>),
+    RetryCompletionResolve,
     InsertText(String),
     WorkspaceEdit(WorkspaceEdit),
+    References(Vec<Location>),
+    /// definition (LSP) in other file than the active one
+    PeekDefinition(Location),
+    /// rename (LSP) result shown before it is applied
+    RenamePreview(WorkspaceEdit),
+    /// rename confirmed in the preview
+    ApplyRename(WorkspaceEdit),
+    CodeAction(Box<CodeActionOrCommand>),
     FindSelector(String),
+    SavePattern(SavedPattern),
+    SavedPatterns,
+    RecallPattern(SavedPattern),
     ActivateEditor(usize),
-    ReplaceAll(String, Vec<(CursorPosition, CursorPosition)>),
+    ReplaceAll {
+        pattern: String,
+        new_text: String,
+        ranges: Vec<(CursorPosition, CursorPosition)>,
+    },
     FindToReplace(String, Vec<(CursorPosition, CursorPosition)>),
     ReplaceNextSelect {
+        pattern: String,
         new_text: String,
         select: (CursorPosition, CursorPosition),
         next_select: Option<(CursorPosition, CursorPosition)>,
     },
     GoToLine {
         line: usize,
+        /// column as well if set
+        char: Option<usize>,
         clear_popup: bool,
     },
     GoToSelect {
@@ -51,10 +109,26 @@ pub enum IdiomEvent {
         clear_popup: bool,
     },
     Resize,
+    ToggleSplit,
     Save,
+    SaveElevated,
+    SaveCopy(String),
     Rebase,
+    DiskDiff,
+    Revert,
+    ClosePinned,
     Exit,
     SaveAndExit,
+    SaveAllAndCheck,
+    AnnotateLine,
+    SetAnnotation(String),
+    ShowAnnotation,
+    ListAnnotations,
+    ExportAnnotations,
+    ExportContext,
+    ImportContext,
+    ForwardToInstance,
+    Standalone,
 }
 
 impl IdiomEvent {
@@ -81,16 +155,22 @@ impl IdiomEvent {
                 }
             }
             IdiomEvent::OpenAtLine(path, line) => {
-                tree.select_by_path(&path);
+                if ws.tree_follows_file() {
+                    tree.select_by_path(&path);
+                }
                 gs.clear_popup();
                 match ws.new_at_line(path, line, gs).await {
+                    Ok(Opened::ReadOnly) => (),
                     Ok(..) => gs.insert_mode(),
                     Err(error) => gs.error(error.to_string()),
                 }
             }
             IdiomEvent::OpenAtSelect(path, (from, to)) => {
-                tree.select_by_path(&path);
+                if ws.tree_follows_file() {
+                    tree.select_by_path(&path);
+                }
                 match ws.new_from(path, gs).await {
+                    Ok(Opened::ReadOnly) => (),
                     Ok(..) => {
                         gs.insert_mode();
                         if let Some(editor) = ws.get_active() {
@@ -101,9 +181,133 @@ impl IdiomEvent {
                     Err(error) => gs.error(error.to_string()),
                 }
             }
-            IdiomEvent::GoToLine { line, clear_popup } => match ws.get_active() {
+            IdiomEvent::OpenAtAnchor(path, anchor) => {
+                if ws.tree_follows_file() {
+                    tree.select_by_path(&path);
+                }
+                match ws.new_from(path, gs).await {
+                    Ok(Opened::ReadOnly) => (),
+                    Ok(..) => {
+                        gs.insert_mode();
+                        if let Some(editor) = ws.get_active() {
+                            if !editor.go_to_anchor(anchor) {
+                                gs.error("Unable to resolve file anchor!");
+                            }
+                        };
+                    }
+                    Err(error) => gs.error(error.to_string()),
+                }
+            }
+            IdiomEvent::OpenAtPosition(path, position) => {
+                if ws.tree_follows_file() {
+                    tree.select_by_path(&path);
+                }
+                gs.clear_popup();
+                match ws.new_from(path, gs).await {
+                    Ok(Opened::ReadOnly) => (),
+                    Ok(..) => {
+                        gs.insert_mode();
+                        if let Some(editor) = ws.get_active() {
+                            editor.go_to_position(position);
+                        }
+                    }
+                    Err(error) => gs.error(error.to_string()),
+                }
+            }
+            IdiomEvent::FindFile => {
+                gs.clear_popup();
+                gs.popup(FileFinderPopup::boxed(tree.indexed_files()));
+            }
+            IdiomEvent::SearchProject => {
+                gs.clear_popup();
+                gs.popup(ProjectSearchPopup::boxed(tree.indexed_files()));
+            }
+            IdiomEvent::SearchSymbols => {
+                gs.clear_popup();
+                match ws.workspace_symbols_client() {
+                    Some(client) => gs.popup(SymbolSearchPopup::boxed(client)),
+                    None => gs.error("Workspace symbols are not supported by the LSP of the active file"),
+                }
+            }
+            IdiomEvent::ProjectDiagnostics => {
+                gs.clear_popup();
+                let clients = ws.lsp_clients();
+                match clients.is_empty() {
+                    true => gs.message("No LSP running - diagnostics are published by LSP servers and local LSPs"),
+                    false => gs.popup(DiagnosticsPopup::boxed(clients)),
+                }
+            }
+            IdiomEvent::RecentFiles => {
+                gs.clear_popup();
+                ws.recent_files(gs);
+            }
+            IdiomEvent::ClipboardHistory => {
+                gs.clear_popup();
+                match gs.clipboard.history_len() {
+                    0 => gs.message("Clipboard history is empty"),
+                    _ => gs.popup(ClipboardPopup::boxed(&gs.clipboard)),
+                }
+            }
+            IdiomEvent::PasteFromHistory(idx) => {
+                gs.clear_popup();
+                let Some(clip) = gs.clipboard.history(idx).map(str::to_owned) else {
+                    return;
+                };
+                if let Some(editor) = ws.get_active() {
+                    editor.paste_from_history(clip, idx);
+                    gs.insert_mode();
+                }
+            }
+            IdiomEvent::NewScratch => {
+                gs.clear_popup();
+                match ws.new_scratch(gs) {
+                    Ok(..) => gs.insert_mode(),
+                    Err(error) => gs.error(error.to_string()),
+                }
+            }
+            IdiomEvent::SetWrapColumn(column) => {
+                gs.clear_popup();
+                if let Some(editor) = ws.get_active() {
+                    editor.wrap_column = column;
+                    gs.success(format!("Wrap column set to {column}"));
+                }
+            }
+            IdiomEvent::ShowViewSettings => {
+                gs.clear_popup();
+                match ws.get_active() {
+                    Some(editor) => gs.message(format!("View: {}", editor.view_report())),
+                    None => gs.message("No active editor"),
+                }
+            }
+            IdiomEvent::SetIndent(indent) => {
+                gs.clear_popup();
+                if let Some(editor) = ws.get_active() {
+                    editor.set_indent(indent);
+                    gs.success(format!("Indent set to {}", editor.indent_label()));
+                }
+            }
+            IdiomEvent::AlignComments => {
+                gs.clear_popup();
+                if let Some(editor) = ws.get_active() {
+                    match editor.align_comments() {
+                        None => gs.message("No trailing comments to align"),
+                        Some((0, 0)) => gs.message("Comments already aligned"),
+                        Some((0, skipped)) => {
+                            gs.message(format!("Nothing to align, skipped {skipped} (code past column)"))
+                        }
+                        Some((aligned, 0)) => gs.success(format!("Aligned {aligned} comments")),
+                        Some((aligned, skipped)) => {
+                            gs.message(format!("Aligned {aligned} comments, skipped {skipped} (code past column)"))
+                        }
+                    }
+                }
+            }
+            IdiomEvent::GoToLine { line, char, clear_popup } => match ws.get_active() {
                 Some(editor) => {
-                    editor.go_to(line);
+                    match char {
+                        Some(char) => editor.go_to_position(CursorPosition { line, char }),
+                        None => editor.go_to(line),
+                    }
                     match clear_popup {
                         true => gs.clear_popup(),
                         false => {
@@ -168,38 +372,170 @@ impl IdiomEvent {
                 };
                 gs.clear_popup();
             }
+            IdiomEvent::DeleteMarked => {
+                tree.delete_marked(gs);
+                gs.clear_popup();
+            }
+            IdiomEvent::PasteFiles => {
+                for (old, new_path) in tree.paste_files(gs) {
+                    ws.rename_editors(old, new_path, gs);
+                }
+            }
             IdiomEvent::AutoComplete(completion) => {
                 if let Some(editor) = ws.get_active() {
                     editor.replace_token(completion);
                 }
             }
-            IdiomEvent::Snippet(snippet, cursor_offset) => {
+            IdiomEvent::Snippet(snippet) => {
                 if let Some(editor) = ws.get_active() {
-                    editor.insert_snippet(snippet, cursor_offset);
+                    editor.insert_snippet(snippet);
                 };
             }
+            IdiomEvent::CompletionEdits(edits) => {
+                if let Some(editor) = ws.get_active() {
+                    if !editor.apply_completion_edits(edits) {
+                        gs.error("Unable to apply completion edits, the text was changed");
+                    }
+                }
+            }
+            IdiomEvent::RetryCompletionResolve => {
+                if let Some(editor) = ws.get_active() {
+                    editor.lexer.retry_completion_resolve(gs);
+                }
+            }
             IdiomEvent::WorkspaceEdit(edits) => ws.apply_edits(edits, gs),
+            IdiomEvent::ApplyRename(edits) => {
+                gs.clear_popup();
+                ws.apply_edits(edits, gs);
+            }
+            IdiomEvent::RenamePreview(edits) => ws.preview_rename(edits, gs),
+            IdiomEvent::References(locations) => ws.show_references(locations, gs),
+            IdiomEvent::PeekDefinition(location) => ws.peek_definition(location, gs),
+            IdiomEvent::CodeAction(action) => {
+                gs.clear_popup();
+                let (edit, command) = match *action {
+                    CodeActionOrCommand::Command(command) => (None, Some(command)),
+                    CodeActionOrCommand::CodeAction(action) => (action.edit, action.command),
+                };
+                if edit.is_none() && command.is_none() {
+                    gs.message("Code action has nothing to apply (resolve is not supported)");
+                }
+                if let Some(edit) = edit {
+                    ws.apply_edits(edit, gs);
+                }
+                if let (Some(command), Some(editor)) = (command, ws.get_active()) {
+                    editor.lexer.execute_command(command, gs);
+                }
+            }
             IdiomEvent::Resize => {
                 ws.resize_all(gs.editor_area.width, gs.editor_area.height as usize);
             }
+            IdiomEvent::ToggleSplit => {
+                gs.clear_popup();
+                ws.toggle_split(gs);
+            }
             IdiomEvent::Rebase => {
                 if let Some(editor) = ws.get_active() {
                     editor.rebase(gs);
                 }
                 gs.clear_popup();
             }
+            IdiomEvent::DiskDiff => {
+                if let Some(editor) = ws.get_active() {
+                    match editor.disk_diff() {
+                        Ok(diff) => gs.popup(DiskDiffPopup::boxed(diff)),
+                        Err(error) => {
+                            gs.error(format!("Failed to compare with file on disk! {error}"));
+                            gs.clear_popup();
+                        }
+                    }
+                }
+            }
+            IdiomEvent::ClosePinned => {
+                gs.clear_popup();
+                ws.close_active_unchecked(gs);
+            }
+            IdiomEvent::Revert => {
+                if let Some(editor) = ws.get_active() {
+                    editor.revert(gs);
+                }
+                gs.clear_popup();
+            }
             IdiomEvent::Save => {
                 if let Some(editor) = ws.get_active() {
                     editor.save(gs);
                 }
                 gs.clear_popup();
             }
+            IdiomEvent::SaveElevated => {
+                gs.clear_popup();
+                if let Some(editor) = ws.get_active() {
+                    editor.save_elevated(gs);
+                }
+            }
+            IdiomEvent::SaveCopy(path) => {
+                if path.is_empty() {
+                    gs.error("Save copy requires path!");
+                    return;
+                }
+                gs.clear_popup();
+                if let Some(editor) = ws.get_active() {
+                    editor.save_copy(PathBuf::from(path), gs);
+                }
+            }
             IdiomEvent::CheckLSP(ft) => {
                 ws.check_lsp(ft, gs).await;
             }
             IdiomEvent::SaveAndExit => {
-                ws.save_all(gs);
-                gs.exit = true;
+                // failed files are listed in popup instead of being lost on exit
+                if ws.save_all(gs) {
+                    gs.exit = true;
+                }
+            }
+            IdiomEvent::SaveAllAndCheck => {
+                gs.clear_popup();
+                ws.save_all_and_check(gs);
+            }
+            IdiomEvent::AnnotateLine => {
+                gs.clear_popup();
+                ws.annotate_line(gs);
+            }
+            IdiomEvent::SetAnnotation(text) => {
+                gs.clear_popup();
+                ws.set_annotation(text.trim().to_owned(), gs);
+            }
+            IdiomEvent::ShowAnnotation => {
+                gs.clear_popup();
+                ws.show_annotation(gs);
+            }
+            IdiomEvent::ListAnnotations => {
+                gs.clear_popup();
+                ws.list_annotations(gs);
+            }
+            IdiomEvent::ExportAnnotations => {
+                gs.clear_popup();
+                ws.export_annotations(gs);
+            }
+            IdiomEvent::ExportContext => {
+                gs.clear_popup();
+                ws.export_context(gs);
+            }
+            IdiomEvent::ImportContext => {
+                gs.clear_popup();
+                ws.import_context(gs).await;
+            }
+            IdiomEvent::ForwardToInstance => match ws.forward_to_peer() {
+                Ok(()) => gs.exit = true,
+                Err(error) => gs.error(format!("Failed to forward to instance: {error}")),
+            },
+            IdiomEvent::Standalone => {
+                gs.clear_popup();
+                gs.message("Standalone - saving files open in the other instance requires confirmation");
+                match ws.take_peer_pending() {
+                    Some((path, FileAnchor::Line(line))) => gs.event.push(IdiomEvent::OpenAtLine(path, line)),
+                    Some((path, anchor)) => gs.event.push(IdiomEvent::OpenAtAnchor(path, anchor)),
+                    None => (),
+                }
             }
             IdiomEvent::Exit => {
                 gs.exit = true;
@@ -207,6 +543,9 @@ impl IdiomEvent {
             IdiomEvent::FileUpdated(path) => {
                 ws.notify_update(path, gs);
             }
+            IdiomEvent::FileParsed(path) => {
+                ws.parsed_file(path, gs).await;
+            }
             IdiomEvent::InsertText(insert) => {
                 if let Some(editor) = ws.get_active() {
                     editor.insert_text_with_relative_offset(insert);
@@ -226,15 +565,42 @@ impl IdiomEvent {
                 gs.insert_mode();
             }
             IdiomEvent::FindToReplace(pattern, options) => {
-                gs.popup(ReplacePopup::from_search(pattern, options));
+                gs.popup(ReplacePopup::from_search(pattern, options, gs.search_history.clone()));
+            }
+            IdiomEvent::SavePattern(pattern) => {
+                let name = pattern.name.clone();
+                match SavedPatterns::store(pattern) {
+                    Ok(()) => gs.success(format!("Pattern saved as {name}")),
+                    Err(error) => gs.error(format!("Failed to save pattern: {error}")),
+                }
             }
-            IdiomEvent::ReplaceAll(clip, ranges) => {
+            IdiomEvent::SavedPatterns => {
+                let saved = SavedPatterns::load();
+                if saved.patterns.is_empty() {
+                    gs.message("No saved patterns (Ctrl+S in replace to save one)");
+                } else {
+                    gs.popup(selector_patterns(saved.patterns));
+                }
+            }
+            IdiomEvent::RecallPattern(pattern) => {
+                let mut popup = ReplacePopup::from_saved(pattern, gs.search_history.clone());
+                popup.component_access(ws, tree);
+                gs.popup(popup);
+            }
+            IdiomEvent::ReplaceInFiles { files, pattern, options, new_text } => {
+                gs.search_history.push(&pattern, &new_text);
+                ws.replace_in_files(&files, &pattern, options, &new_text, gs);
+                gs.clear_popup();
+            }
+            IdiomEvent::ReplaceAll { pattern, new_text, ranges } => {
+                gs.search_history.push(&pattern, &new_text);
                 if let Some(editor) = ws.get_active() {
-                    editor.mass_replace(ranges, clip);
+                    editor.mass_replace(ranges, new_text);
                 }
                 gs.clear_popup();
             }
-            IdiomEvent::ReplaceNextSelect { new_text, select: (from, to), next_select } => {
+            IdiomEvent::ReplaceNextSelect { pattern, new_text, select: (from, to), next_select } => {
+                gs.search_history.push(&pattern, &new_text);
                 if let Some(editor) = ws.get_active() {
                     editor.replace_select(from, to, new_text.as_str());
                     if let Some((from, to)) = next_select {
@@ -248,48 +614,7 @@ impl IdiomEvent {
 }
 
 fn parse_snippet(snippet: String) -> IdiomEvent {
-    let mut cursor_offset = None;
-    let mut named = false;
-    let mut text = String::default();
-    let mut is_expr = false;
-    let mut line_offset = 0;
-    let mut char_offset = 0;
-    for ch in snippet.chars() {
-        if ch == '\n' {
-            line_offset += 1;
-            char_offset = 0;
-            text.push(ch);
-        } else {
-            if named {
-                if ch == '}' {
-                    named = false;
-                    continue;
-                };
-                if ch == ':' || ch.is_numeric() {
-                    continue;
-                };
-            } else if is_expr {
-                if ch.is_numeric() {
-                    continue;
-                };
-                if ch == '{' {
-                    named = true;
-                    cursor_offset = None;
-                    continue;
-                };
-                is_expr = false;
-            } else if ch == '$' {
-                is_expr = true;
-                if cursor_offset.is_none() {
-                    cursor_offset.replace((line_offset, char_offset));
-                };
-                continue;
-            };
-            char_offset += 1;
-            text.push(ch);
-        };
-    }
-    IdiomEvent::Snippet(text, cursor_offset)
+    IdiomEvent::Snippet(Snippet::parse(&snippet))
 }
 
 impl From<IdiomEvent> for PopupMessage {
@@ -4,26 +4,29 @@ mod controls;
 mod draw;
 mod events;
 mod message;
+mod status_line;
 
 use crate::{
     configs::{FileType, UITheme},
     lsp::{LSPError, LSPResult},
-    popups::{self, PopupInterface},
+    popups::{self, popup_replace::SearchHistory, PopupInterface},
     render::{
-        backend::{Backend, BackendProtocol},
+        backend::{mouse_support, Backend, BackendProtocol, ColorSupport},
         layout::Rect,
     },
     runner::EditorTerminal,
     tree::Tree,
-    workspace::{CursorPosition, Workspace},
+    workspace::Workspace,
 };
 pub use clipboard::Clipboard;
-pub use controls::{Mode, PopupMessage};
+pub use controls::{KeyboardHint, Mode, PopupMessage};
 use crossterm::event::{KeyEvent, MouseEvent};
 pub use events::IdiomEvent;
+pub use status_line::{EditorStatus, LspStatus};
 
 use draw::Components;
 use message::Messages;
+use status_line::StatusLine;
 
 type KeyMapCallback = fn(&mut GlobalState, &KeyEvent, &mut Workspace, &mut Tree, &mut EditorTerminal) -> bool;
 type MouseMapCallback = fn(&mut GlobalState, MouseEvent, &mut Tree, &mut Workspace);
@@ -40,6 +43,7 @@ pub struct GlobalState {
     pub popup: Box<dyn PopupInterface>,
     pub event: Vec<IdiomEvent>,
     pub clipboard: Clipboard,
+    pub search_history: SearchHistory,
     pub exit: bool,
     pub screen_rect: Rect,
     pub tree_area: Rect,
@@ -47,7 +51,10 @@ pub struct GlobalState {
     pub editor_area: Rect,
     pub footer_area: Rect,
     messages: Messages,
+    status_line: StatusLine,
     components: Components,
+    mouse: bool,
+    shown_hints: Vec<KeyboardHint>,
 }
 
 impl GlobalState {
@@ -65,6 +72,7 @@ impl GlobalState {
             popup: popups::placeholder(),
             event: Vec::default(),
             clipboard: Clipboard::default(),
+            search_history: SearchHistory::default(),
             exit: false,
             screen_rect,
             tree_area: Rect::default(),
@@ -72,7 +80,10 @@ impl GlobalState {
             editor_area: Rect::default(),
             footer_area: Rect::default(),
             messages,
+            status_line: StatusLine::default(),
             components: Components::default(),
+            mouse: mouse_support(),
+            shown_hints: Vec::new(),
         })
     }
 
@@ -88,21 +99,47 @@ impl GlobalState {
         tree: &mut Tree,
         term: &mut EditorTerminal,
     ) -> std::io::Result<()> {
+        self.send_osc52_clip();
         (self.draw_callback)(self, workspace, tree, term)
     }
 
-    pub fn render_stats(&mut self, len: usize, select_len: usize, cursor: CursorPosition) {
+    /// sets the clip on the system clipboard or the terminal one (OSC 52) if the system one is not reachable
+    pub fn clipboard_copy(&mut self, clip: String) {
+        self.clipboard.push(clip);
+        self.send_osc52_clip();
+    }
+
+    /// the terminal clipboard can not be read - clips sent to it are served from the internal one
+    pub fn clipboard_paste(&mut self) -> Option<String> {
+        self.clipboard.pull()
+    }
+
+    /// clip not set on the system clipboard goes to the terminal, cut to the size it accepts
+    fn send_osc52_clip(&mut self) {
+        let Some(clip) = self.clipboard.take_osc52() else {
+            return;
+        };
+        let text = Clipboard::osc52_fit(&clip);
+        if text.len() < clip.len() {
+            self.message(format!(
+                "Clip cut to {} of {} bytes for the terminal clipboard (OSC 52)",
+                text.len(),
+                clip.len()
+            ));
+        }
+        self.writer.clipboard_osc52(text);
+    }
+
+    /// skipped for the dimmed (inactive split) editor - the footer belongs to the focused one
+    pub fn render_stats(&mut self, status: &EditorStatus) {
+        if self.writer.is_dim() {
+            return;
+        }
         if let Some(mut line) = self.footer_area.get_line(0) {
             line += Mode::len();
-            self.writer.set_style(self.theme.accent_style);
-            let mut rev_builder = line.unsafe_builder_rev(&mut self.writer);
-            if select_len != 0 {
-                rev_builder.push(&format!(" ({select_len} selected)"));
-            }
-            rev_builder.push(&format!("  Doc Len {len}, Ln {}, Col {}", cursor.line + 1, cursor.char + 1));
-            self.messages.set_line(rev_builder.into_line());
+            let rest = self.status_line.render(status, line, self.theme.accent_style, &mut self.writer);
+            self.messages.set_line(rest);
             self.messages.fast_render(self.theme.accent_style, &mut self.writer);
-            self.writer.reset_style();
         }
     }
 
@@ -110,6 +147,7 @@ impl GlobalState {
         if let Some(mut line) = self.footer_area.get_line(0) {
             let accent_style = self.theme.accent_style;
             line += Mode::len();
+            self.status_line.invalidate();
             self.writer.set_style(accent_style);
             self.writer.go_to(line.row, line.col);
             self.writer.clear_to_eol();
@@ -148,15 +186,55 @@ impl GlobalState {
         match self.mode {
             Mode::Insert => {
                 self.key_mapper = controls::map_editor;
-                self.mouse_mapper = controls::mouse_handler;
+                self.mouse_mapper = self.mouse_or_disabled(controls::mouse_handler);
             }
             Mode::Select => {
                 self.key_mapper = controls::map_tree;
-                self.mouse_mapper = controls::mouse_handler;
+                self.mouse_mapper = self.mouse_or_disabled(controls::mouse_handler);
             }
         }
     }
 
+    #[inline]
+    fn mouse_or_disabled(&self, handler: MouseMapCallback) -> MouseMapCallback {
+        if self.mouse {
+            handler
+        } else {
+            controls::disable_mouse
+        }
+    }
+
+    /// applies config overrides over the detected terminal capabilities
+    pub fn configure_terminal(&mut self, color_support: Option<&str>, mouse: Option<bool>) {
+        let colors = match color_support {
+            Some(value) => ColorSupport::parse(value).unwrap_or_else(|| {
+                self.error(format!("Unknown color_support {value:?} (expected truecolor, 256 or 16)"));
+                ColorSupport::from_env()
+            }),
+            None => ColorSupport::from_env(),
+        };
+        colors.set();
+        let mouse = mouse.unwrap_or_else(mouse_support);
+        if mouse != self.mouse {
+            self.mouse = mouse;
+            self.writer.mouse_capture(mouse);
+        }
+        self.config_controls();
+        self.draw_callback = draw::full_rebuild;
+        if self.components.contains(Components::TREE) {
+            self.keyboard_hint(KeyboardHint::Tree);
+        }
+    }
+
+    /// footer hint with the keyboard alternative, shown only the first time and only without mouse
+    pub fn keyboard_hint(&mut self, hint: KeyboardHint) {
+        if self.mouse || self.shown_hints.contains(&hint) {
+            return;
+        }
+        self.shown_hints.push(hint);
+        self.message(hint.text());
+    }
+
     pub fn select_mode(&mut self) {
         self.mode = Mode::Select;
         self.config_controls();
@@ -200,8 +278,9 @@ impl GlobalState {
         self.components.insert(Components::POPUP);
         self.config_controls();
         self.draw_callback = draw::full_rebuild;
-        self.mouse_mapper = controls::mouse_popup_handler;
+        self.mouse_mapper = self.mouse_or_disabled(controls::mouse_popup_handler);
         self.popup = popup;
+        self.keyboard_hint(KeyboardHint::Popup);
     }
 
     pub fn clear_popup(&mut self) {
@@ -213,9 +292,17 @@ impl GlobalState {
         self.popup = popups::placeholder();
     }
 
+    #[inline]
+    pub fn force_screen_rebuild(&mut self) {
+        self.draw_callback = draw::full_rebuild;
+    }
+
     pub fn toggle_tree(&mut self) {
         self.components.toggle(Components::TREE);
         self.draw_callback = draw::full_rebuild;
+        if self.components.contains(Components::TREE) {
+            self.keyboard_hint(KeyboardHint::Tree);
+        }
     }
 
     pub fn expand_tree_size(&mut self) {
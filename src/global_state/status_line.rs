@@ -0,0 +1,143 @@
+use crate::{
+    configs::IndentLabel,
+    render::{
+        backend::{Backend, BackendProtocol, Style},
+        layout::Line,
+        UTF8Safe,
+    },
+    workspace::{cursor::SelectionStats, CursorPosition},
+};
+
+const SEPARATOR: &str = " | ";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LspStatus {
+    /// language server is running
+    Server,
+    /// tokens are produced by the built in lexer
+    Local,
+    #[default]
+    None,
+}
+
+/// State of the active editor shown on the right side of the footer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditorStatus {
+    pub len: usize,
+    pub cursor: CursorPosition,
+    pub select: Option<SelectionStats>,
+    pub indent: IndentLabel,
+    pub file_type: &'static str,
+    pub lsp: LspStatus,
+}
+
+impl EditorStatus {
+    /// segments from the least important one - cursor position is last
+    fn segments(&self) -> Vec<String> {
+        let mut segments = Vec::new();
+        if let Some(SelectionStats { lines, chars, words }) = self.select {
+            segments.push(format!("Sel: {lines} lines, {chars} chars, {words} words"));
+        }
+        segments.push(format!("Doc Len {}", self.len));
+        segments.push(self.indent.to_string());
+        segments.push(match self.lsp {
+            LspStatus::Server => format!("{} (LSP)", self.file_type),
+            LspStatus::Local => format!("{} (local)", self.file_type),
+            LspStatus::None => self.file_type.to_owned(),
+        });
+        segments.push(format!("Ln {}, Col {}", self.cursor.line + 1, self.cursor.char + 1));
+        segments
+    }
+
+    /// segments are dropped from the left while wider than the width, the last one is cut from the start
+    fn text(&self, width: usize) -> String {
+        let segments = self.segments();
+        let fitting = (0..segments.len())
+            .map(|idx| segments[idx..].join(SEPARATOR))
+            .find(|fitting| fitting.width() + 2 <= width)
+            .unwrap_or_else(|| segments.last().cloned().unwrap_or_default());
+        let text = format!(" {fitting} ");
+        match text.truncate_if_wider_start(width) {
+            Ok(cut) => cut.to_owned(),
+            Err(..) => text,
+        }
+    }
+}
+
+/// Renders the editor status, the footer is written only if the status or the line changed
+#[derive(Default)]
+pub struct StatusLine {
+    /// last status with the line it was rendered on and the rest of it (left for messages)
+    rendered: Option<(EditorStatus, Line, Line)>,
+}
+
+impl StatusLine {
+    /// returns the part of the line left for messages
+    pub fn render(&mut self, status: &EditorStatus, line: Line, style: Style, backend: &mut Backend) -> Line {
+        if let Some((last_status, last_line, rest)) = self.rendered.as_ref() {
+            if last_status == status && *last_line == line {
+                return rest.clone();
+            }
+        }
+        let text = status.text(line.width);
+        backend.set_style(style);
+        let mut builder = line.clone().unsafe_builder_rev(backend);
+        builder.push(&text);
+        let rest = builder.into_line();
+        backend.reset_style();
+        self.rendered = Some((status.clone(), line, rest.clone()));
+        rest
+    }
+
+    /// the footer was cleared or redrawn
+    pub fn invalidate(&mut self) {
+        self.rendered = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EditorStatus, LspStatus, StatusLine};
+    use crate::configs::IndentLabel;
+    use crate::render::{
+        backend::{Backend, BackendProtocol, Style},
+        layout::Line,
+    };
+    use crate::workspace::{cursor::SelectionStats, CursorPosition};
+
+    #[test]
+    fn written_on_change() {
+        let mut backend = Backend::init();
+        let mut status_line = StatusLine::default();
+        let mut status = EditorStatus { len: 3, indent: IndentLabel::Tabs, file_type: "rust", ..Default::default() };
+        let line = Line { row: 10, col: 0, width: 60 };
+        let rest = status_line.render(&status, line.clone(), Style::default(), &mut backend);
+        assert!(rest.width < 60);
+        let written = backend.data.len();
+        assert_eq!(status_line.render(&status, line.clone(), Style::default(), &mut backend), rest);
+        assert_eq!(backend.data.len(), written);
+        status.cursor = CursorPosition { line: 2, char: 0 };
+        status_line.render(&status, line, Style::default(), &mut backend);
+        assert!(backend.data[written..].iter().any(|(_, text)| text.contains("Ln 3, Col 1")));
+    }
+
+    #[test]
+    fn status_truncated_from_left() {
+        let mut status = EditorStatus {
+            len: 120,
+            cursor: CursorPosition { line: 9, char: 4 },
+            select: Some(SelectionStats { lines: 2, chars: 17, words: 3 }),
+            indent: IndentLabel::Spaces(4),
+            file_type: "rust",
+            lsp: LspStatus::Server,
+        };
+        let full = " Sel: 2 lines, 17 chars, 3 words | Doc Len 120 | Spaces: 4 | rust (LSP) | Ln 10, Col 5 ";
+        assert_eq!(status.text(100), full);
+        assert_eq!(status.text(60), " Doc Len 120 | Spaces: 4 | rust (LSP) | Ln 10, Col 5 ");
+        assert_eq!(status.text(20), " Ln 10, Col 5 ");
+        assert_eq!(status.text(10), "10, Col 5 ");
+        status.select = None;
+        status.lsp = LspStatus::Local;
+        assert_eq!(status.text(100), " Doc Len 120 | Spaces: 4 | rust (local) | Ln 10, Col 5 ");
+    }
+}
@@ -0,0 +1,432 @@
+use crate::cli::FileAnchor;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// time the other side has to answer - the main loop of the instance serves the requests
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+/// open files of the other instance are asked again (in the background) once older
+const OPEN_FILES_TTL: Duration = Duration::from_secs(1);
+
+/// line of json sent to the instance holding the lock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Request {
+    Ping,
+    Open { path: PathBuf, line: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Response {
+    /// files open in the instance
+    Pong {
+        pid: u32,
+        open: Vec<PathBuf>,
+    },
+    Opened,
+}
+
+/// content of the lock file
+#[derive(Debug, Serialize, Deserialize)]
+struct LockInfo {
+    pid: u32,
+    socket: PathBuf,
+}
+
+pub enum Coordination {
+    /// this instance holds the lock of the workspace
+    Lock(InstanceLock),
+    /// live instance holds the lock
+    Running(Peer),
+    /// not supported on the platform or the lock could not be taken
+    Unavailable,
+}
+
+/// takes the lock of the current dir, if another instance holds it the user picks forward or standalone
+pub fn coordinate() -> Coordination {
+    match std::env::current_dir() {
+        Ok(workspace) => coordinate_at(&std::env::temp_dir(), &workspace),
+        Err(..) => Coordination::Unavailable,
+    }
+}
+
+/// lock file and socket of the workspace
+#[cfg(unix)]
+fn lock_paths(dir: &Path, workspace: &Path) -> (PathBuf, PathBuf) {
+    let key = crate::workspace::utils::text_hash(&workspace.display().to_string());
+    (dir.join(format!("idiom-{key:x}.lock")), dir.join(format!("idiom-{key:x}.sock")))
+}
+
+#[cfg(unix)]
+fn coordinate_at(dir: &Path, workspace: &Path) -> Coordination {
+    let (lock_path, socket) = lock_paths(dir, workspace);
+    // single retry after stale lock is removed
+    for _ in 0..2 {
+        match unix::InstanceLock::create(&lock_path, &socket) {
+            Ok(lock) => return Coordination::Lock(lock),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => match Peer::from_lock(&lock_path) {
+                Some(peer) if peer.is_alive() => return Coordination::Running(peer),
+                _ => {
+                    let _ = std::fs::remove_file(&lock_path);
+                }
+            },
+            Err(..) => return Coordination::Unavailable,
+        }
+    }
+    Coordination::Unavailable
+}
+
+#[cfg(not(unix))]
+fn coordinate_at(_dir: &Path, _workspace: &Path) -> Coordination {
+    Coordination::Unavailable
+}
+
+#[cfg(unix)]
+pub use unix::InstanceLock;
+
+/// no-op lock outside unix
+#[cfg(not(unix))]
+pub struct InstanceLock;
+
+#[cfg(not(unix))]
+impl InstanceLock {
+    pub fn serve(&self, _handler: impl FnMut(Request) -> Response) {}
+}
+
+/// last known open files of the other instance
+struct OpenFiles {
+    files: Vec<PathBuf>,
+    checked: Instant,
+    refreshing: bool,
+}
+
+impl OpenFiles {
+    fn new(files: Vec<PathBuf>) -> Self {
+        Self { files, checked: Instant::now(), refreshing: false }
+    }
+}
+
+/// instance holding the lock of the workspace
+pub struct Peer {
+    pub pid: u32,
+    socket: PathBuf,
+    /// file from the command line - forwarded or opened after the user picks
+    pending: Option<(PathBuf, FileAnchor)>,
+    open: Arc<Mutex<OpenFiles>>,
+}
+
+impl Peer {
+    fn from_lock(path: &Path) -> Option<Self> {
+        let info = serde_json::from_str::<LockInfo>(&std::fs::read_to_string(path).ok()?).ok()?;
+        let open = Arc::new(Mutex::new(OpenFiles::new(Vec::new())));
+        Some(Self { pid: info.pid, socket: info.socket, pending: None, open })
+    }
+
+    pub fn set_pending(&mut self, open_file: Option<(PathBuf, FileAnchor)>) {
+        self.pending = open_file;
+    }
+
+    pub fn take_pending(&mut self) -> Option<(PathBuf, FileAnchor)> {
+        self.pending.take()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// asks the instance to open the file from the command line (only line anchors are kept)
+    pub fn forward_pending(&mut self) -> std::io::Result<()> {
+        let Some((path, anchor)) = self.pending.take() else {
+            return Ok(());
+        };
+        let line = match anchor {
+            FileAnchor::Line(line) => line,
+            _ => 0,
+        };
+        let path = path.canonicalize().unwrap_or(path);
+        match self.request(&Request::Open { path, line })? {
+            Response::Opened => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// saving a file open in the other instance should be confirmed
+    pub fn has_open(&self, path: &Path) -> bool {
+        self.open_files().iter().any(|open_path| open_path == path)
+    }
+
+    /// last known files open in the other instance (empty if it does not answer)
+    /// does not block - stale list is refreshed in the background
+    pub fn open_files(&self) -> Vec<PathBuf> {
+        let Ok(mut open) = self.open.lock() else {
+            return Vec::new();
+        };
+        if !open.refreshing && open.checked.elapsed() > OPEN_FILES_TTL {
+            open.refreshing = true;
+            let socket = self.socket.clone();
+            let slot = Arc::clone(&self.open);
+            std::thread::spawn(move || {
+                let files = match send(&socket, &Request::Ping) {
+                    Ok(Response::Pong { open, .. }) => open,
+                    _ => Vec::new(),
+                };
+                if let Ok(mut open) = slot.lock() {
+                    *open = OpenFiles::new(files);
+                }
+            });
+        }
+        open.files.clone()
+    }
+
+    /// the answer to the ping is kept as the first list of open files
+    fn is_alive(&self) -> bool {
+        if !pid_alive(self.pid) {
+            return false;
+        }
+        match self.request(&Request::Ping) {
+            Ok(Response::Pong { open, .. }) => {
+                if let Ok(mut open_files) = self.open.lock() {
+                    *open_files = OpenFiles::new(open);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn request(&self, request: &Request) -> std::io::Result<Response> {
+        send(&self.socket, request)
+    }
+}
+
+#[cfg(unix)]
+fn send(socket: &Path, request: &Request) -> std::io::Result<Response> {
+    unix::request(socket, request)
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &Path, _request: &Request) -> std::io::Result<Response> {
+    Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+}
+
+fn unexpected(response: Response) -> std::io::Error {
+    std::io::Error::other(format!("Unexpected response from instance: {response:?}"))
+}
+
+/// other unix systems rely on the socket answering
+#[cfg(target_os = "linux")]
+fn pid_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pid_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{LockInfo, Request, Response, REPLY_TIMEOUT};
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::net::{UnixListener, UnixStream},
+        path::{Path, PathBuf},
+        sync::mpsc::{channel, Receiver, Sender},
+    };
+
+    type Incoming = (Request, Sender<Response>);
+
+    /// lock file with the pid and the socket of the instance, removed on drop
+    pub struct InstanceLock {
+        path: PathBuf,
+        socket: PathBuf,
+        requests: Receiver<Incoming>,
+    }
+
+    impl InstanceLock {
+        pub fn create(path: &Path, socket: &Path) -> std::io::Result<Self> {
+            let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+            let info = LockInfo { pid: std::process::id(), socket: socket.to_owned() };
+            let listener = serde_json::to_string(&info)
+                .map_err(std::io::Error::other)
+                .and_then(|text| file.write_all(text.as_bytes()))
+                .and_then(|_| {
+                    // left by instance that did not exit cleanly
+                    let _ = std::fs::remove_file(socket);
+                    UnixListener::bind(socket)
+                });
+            let listener = match listener {
+                Ok(listener) => listener,
+                Err(error) => {
+                    let _ = std::fs::remove_file(path);
+                    return Err(error);
+                }
+            };
+            let (sender, requests) = channel();
+            std::thread::spawn(move || listen(listener, sender));
+            Ok(Self { path: path.to_owned(), socket: socket.to_owned(), requests })
+        }
+
+        /// answers the requests received since the last call
+        pub fn serve(&self, mut handler: impl FnMut(Request) -> Response) {
+            while let Ok((request, reply)) = self.requests.try_recv() {
+                let _ = reply.send(handler(request));
+            }
+        }
+    }
+
+    impl Drop for InstanceLock {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.socket);
+            let holds_lock = std::fs::read_to_string(&self.path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<LockInfo>(&text).ok())
+                .is_some_and(|info| info.pid == std::process::id());
+            if holds_lock {
+                let _ = std::fs::remove_file(&self.path);
+            }
+        }
+    }
+
+    /// requests are passed to the main loop, the thread ends with the first request after the lock is dropped
+    fn listen(listener: UnixListener, sender: Sender<Incoming>) {
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+            let Ok(mut writer) = stream.try_clone() else {
+                continue;
+            };
+            for line in BufReader::new(stream).lines() {
+                let Some(request) = line.ok().and_then(|line| serde_json::from_str::<Request>(&line).ok()) else {
+                    break;
+                };
+                let (reply, response) = channel();
+                if sender.send((request, reply)).is_err() {
+                    return;
+                }
+                let Some(mut text) = response
+                    .recv_timeout(REPLY_TIMEOUT)
+                    .ok()
+                    .and_then(|response| serde_json::to_string(&response).ok())
+                else {
+                    break;
+                };
+                text.push('\n');
+                if writer.write_all(text.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn request(socket: &Path, request: &Request) -> std::io::Result<Response> {
+        let mut stream = UnixStream::connect(socket)?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        stream.set_write_timeout(Some(REPLY_TIMEOUT))?;
+        let mut text = serde_json::to_string(request).map_err(std::io::Error::other)?;
+        text.push('\n');
+        stream.write_all(text.as_bytes())?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        serde_json::from_str(&reply).map_err(std::io::Error::other)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::{coordinate_at, lock_paths, Coordination, LockInfo, OpenFiles, Peer, Request, Response};
+    use crate::cli::FileAnchor;
+    use std::{
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("idiom_instance_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn open_forwarding() {
+        let dir = test_dir("forward");
+        let workspace = PathBuf::from("/idiom/forward/workspace");
+        let Coordination::Lock(lock) = coordinate_at(&dir, &workspace) else {
+            panic!("Expected lock");
+        };
+        let server = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            for _ in 0..400 {
+                lock.serve(|request| {
+                    received.push(request.clone());
+                    match request {
+                        Request::Ping => Response::Pong { pid: std::process::id(), open: vec![PathBuf::from("/a.rs")] },
+                        Request::Open { .. } => Response::Opened,
+                    }
+                });
+                if received.iter().any(|request| matches!(request, Request::Open { .. })) {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            received
+        });
+        let Coordination::Running(mut peer) = coordinate_at(&dir, &workspace) else {
+            panic!("Expected running instance");
+        };
+        assert_eq!(peer.pid, std::process::id());
+        assert!(peer.has_open(&PathBuf::from("/a.rs")));
+        assert!(!peer.has_open(&PathBuf::from("/b.rs")));
+        peer.set_pending(Some((PathBuf::from("/missing/b.rs"), FileAnchor::Line(3))));
+        peer.forward_pending().unwrap();
+        let received = server.join().unwrap();
+        assert_eq!(received.last(), Some(&Request::Open { path: PathBuf::from("/missing/b.rs"), line: 3 }));
+        // lock is released with the server
+        assert!(matches!(coordinate_at(&dir, &workspace), Coordination::Lock(..)));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn open_files_refreshed_in_background() {
+        let dir = test_dir("refresh");
+        let mut open = OpenFiles::new(vec![PathBuf::from("/a.rs")]);
+        open.checked = Instant::now().checked_sub(Duration::from_secs(5)).unwrap();
+        let open = Arc::new(Mutex::new(open));
+        let peer = Peer { pid: 0, socket: dir.join("missing.sock"), pending: None, open: Arc::clone(&open) };
+        // stale list is returned while the instance is asked
+        assert!(peer.has_open(&PathBuf::from("/a.rs")));
+        for _ in 0..400 {
+            if !open.lock().unwrap().refreshing {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        // no answer - nothing is open
+        assert!(peer.open_files().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn stale_lock_reclaimed() {
+        let dir = test_dir("stale");
+        let workspace = PathBuf::from("/idiom/stale/workspace");
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        let (lock_path, _) = lock_paths(&dir, &workspace);
+        let stale = LockInfo { pid: dead_pid, socket: dir.join("dead.sock") };
+        std::fs::write(&lock_path, serde_json::to_string(&stale).unwrap()).unwrap();
+        let Coordination::Lock(lock) = coordinate_at(&dir, &workspace) else {
+            panic!("Expected stale lock to be reclaimed");
+        };
+        let info = serde_json::from_str::<LockInfo>(&std::fs::read_to_string(&lock_path).unwrap()).unwrap();
+        assert_eq!(info.pid, std::process::id());
+        drop(lock);
+        assert!(!lock_path.exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
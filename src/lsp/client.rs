@@ -2,14 +2,18 @@ use super::{
     local::{build_with_enrichment, create_semantic_capabilities, start_lsp_handler},
     messages::DiagnosticHandle,
     payload::Payload,
-    EditorDiagnostics, LSPError, LSPNotification, LSPRequest, LSPResult, Response, Responses, TreeDiagnostics,
+    EditorDiagnostics, LSPError, LSPNotification, LSPRequest, LSPResult, ProjectDiagnostic, Response, Responses,
+    TreeDiagnostics,
 };
 use crate::{configs::FileType, utils::split_arc, workspace::CursorPosition};
 use lsp_types::{
-    notification::{DidCloseTextDocument, DidOpenTextDocument, DidRenameFiles, DidSaveTextDocument, Exit, Initialized},
+    notification::{
+        Cancel, DidCloseTextDocument, DidOpenTextDocument, DidRenameFiles, DidSaveTextDocument, Exit, Initialized,
+    },
     request::Shutdown,
-    CompletionOptions, InitializedParams, PositionEncodingKind, Range, ServerCapabilities,
-    TextDocumentContentChangeEvent, TextDocumentSyncKind, Uri,
+    CancelParams, Command, CompletionItem, CompletionOptions, Diagnostic, FormattingOptions, InitializedParams,
+    NumberOrString, PositionEncodingKind, Range, ServerCapabilities, TextDocumentContentChangeEvent,
+    TextDocumentSyncKind, Uri,
 };
 use std::{
     cell::RefCell,
@@ -128,11 +132,27 @@ impl LSPClient {
         }
     }
 
+    /// count of publishes received - changes when the project diagnostics should be listed again
+    #[inline]
+    pub fn diagnostics_version(&self) -> Option<usize> {
+        self.diagnostics.try_lock().ok().map(|guard| guard.version())
+    }
+
+    /// all published diagnostics, regardless of the files being open
+    pub fn project_diagnostics(&self) -> Vec<ProjectDiagnostic> {
+        self.diagnostics.lock().map(|guard| guard.project_diagnostics()).unwrap_or_default()
+    }
+
     #[inline]
     pub fn is_closed(&self) -> bool {
         self.channel.is_closed()
     }
 
+    #[inline]
+    pub fn is_local(&self) -> bool {
+        self.local_lsp.is_some()
+    }
+
     #[inline]
     pub fn request_partial_tokens(&mut self, uri: Uri, range: Range) -> LSPResult<i64> {
         let id = self.id_gen.next_id();
@@ -140,6 +160,12 @@ impl LSPClient {
         Ok(id)
     }
 
+    pub fn request_inlay_hints(&mut self, uri: Uri, range: Range) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::InlayHint(uri, range, id))?;
+        Ok(id)
+    }
+
     #[inline]
     pub fn request_full_tokens(&mut self, uri: Uri) -> LSPResult<i64> {
         let id = self.id_gen.next_id();
@@ -154,12 +180,43 @@ impl LSPClient {
         Ok(id)
     }
 
+    #[inline]
+    pub fn request_completion_resolve(&mut self, item: CompletionItem) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::CompletionResolve(Box::new(item), id))?;
+        Ok(id)
+    }
+
     pub fn request_rename(&mut self, uri: Uri, c: CursorPosition, new_name: String) -> LSPResult<i64> {
         let id = self.id_gen.next_id();
         self.channel.send(Payload::Rename(uri, c, new_name, id))?;
         Ok(id)
     }
 
+    pub fn request_formatting(&mut self, uri: Uri, options: FormattingOptions) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::Formatting(uri, Box::new(options), id))?;
+        Ok(id)
+    }
+
+    pub fn request_range_formatting(&mut self, uri: Uri, range: Range, options: FormattingOptions) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::RangeFormatting(uri, range, Box::new(options), id))?;
+        Ok(id)
+    }
+
+    pub fn request_code_actions(&mut self, uri: Uri, range: Range, diagnostics: Vec<Diagnostic>) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::CodeAction(uri, range, diagnostics.into_boxed_slice(), id))?;
+        Ok(id)
+    }
+
+    pub fn request_execute_command(&mut self, command: Command) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::ExecuteCommand(Box::new(command), id))?;
+        Ok(id)
+    }
+
     pub fn request_signitures(&mut self, uri: Uri, c: CursorPosition) -> LSPResult<i64> {
         let id = self.id_gen.next_id();
         self.channel.send(Payload::SignatureHelp(uri, c, id))?;
@@ -178,6 +235,24 @@ impl LSPClient {
         Ok(id)
     }
 
+    /// the response can still arrive - it is ignored as the request is no longer tracked
+    pub fn cancel_request(&mut self, id: i64) -> Result<(), LSPError> {
+        let notification = LSPNotification::<Cancel>::with(CancelParams { id: NumberOrString::Number(id as i32) });
+        self.channel.send(notification.stringify()?.into()).map_err(LSPError::from)
+    }
+
+    pub fn request_workspace_symbols(&mut self, query: String) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::WorkspaceSymbol(query, id))?;
+        Ok(id)
+    }
+
+    pub fn request_document_symbols(&mut self, uri: Uri) -> LSPResult<i64> {
+        let id = self.id_gen.next_id();
+        self.channel.send(Payload::DocumentSymbol(uri, id))?;
+        Ok(id)
+    }
+
     pub fn request_declarations(&mut self, uri: Uri, c: CursorPosition) -> LSPResult<i64> {
         let id = self.id_gen.next_id();
         self.channel.send(Payload::Declaration(uri, c, id))?;
@@ -1,13 +1,15 @@
 use lsp_types::{
     notification::{Notification, PublishDiagnostics},
     request::GotoDeclarationResponse,
-    CompletionItem, CompletionResponse, DiagnosticSeverity, GotoDefinitionResponse, Hover, Location,
-    PublishDiagnosticsParams, SemanticTokensRangeResult, SemanticTokensResult, SignatureHelp, Uri, WorkspaceEdit,
+    CodeActionOrCommand, CompletionItem, CompletionResponse, DiagnosticSeverity, DocumentSymbolResponse,
+    GotoDefinitionResponse, Hover, InlayHint, Location, PublishDiagnosticsParams, SemanticTokensRangeResult,
+    SemanticTokensResult, SignatureHelp, TextEdit, Uri, WorkspaceEdit, WorkspaceSymbolResponse,
 };
 use serde_json::{from_value, Value};
 use std::{
     collections::{hash_map::Entry, HashMap},
     fmt::Display,
+    ops::Range,
     path::PathBuf,
 };
 
@@ -104,11 +106,23 @@ pub struct Response {
 pub type EditorDiagnostics = Vec<(usize, DiagnosticLine)>;
 pub type TreeDiagnostics = Vec<(PathBuf, DiagnosticType)>;
 
+/// published diagnostic kept for the project diagnostics list (files do not need to be open)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectDiagnostic {
+    pub path: PathBuf,
+    pub position: CursorPosition,
+    /// missing severity is listed as information
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
 #[derive(Default)]
 pub struct DiagnosticHandle {
     meta: HashMap<PathBuf, DiagnosticType>,
     diffs: Vec<(PathBuf, DiagnosticType)>,
     files: HashMap<Uri, crate::lsp::Diagnostic>,
+    /// incremented on every publish
+    version: usize,
 }
 
 impl DiagnosticHandle {
@@ -128,6 +142,26 @@ impl DiagnosticHandle {
             self.push_meta(k.as_str(), DiagnosticType::None);
         }
         self.files.insert(k, v);
+        self.version = self.version.wrapping_add(1);
+    }
+
+    #[inline]
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn project_diagnostics(&self) -> Vec<ProjectDiagnostic> {
+        let mut listed = Vec::new();
+        for (uri, diagnostic) in self.files.iter() {
+            let path = PathBuf::from(uri.path().as_str());
+            listed.extend(diagnostic.listed.iter().map(|(position, severity, message)| ProjectDiagnostic {
+                path: path.clone(),
+                position: *position,
+                severity: *severity,
+                message: message.to_owned(),
+            }));
+        }
+        listed
     }
 
     #[inline]
@@ -162,6 +196,8 @@ pub struct Diagnostic {
     pub errors: usize,
     pub warnings: usize,
     pub lines: Option<Vec<(usize, DiagnosticLine)>>,
+    /// kept after the lines are taken by the editor
+    pub listed: Vec<(CursorPosition, DiagnosticSeverity, String)>,
 }
 
 impl Diagnostic {
@@ -169,7 +205,10 @@ impl Diagnostic {
         let mut diagnostic_lines: Vec<(usize, DiagnosticLine)> = Vec::new();
         let mut errors = 0;
         let mut warnings = 0;
+        let mut listed = Vec::new();
         for d in diagnostics {
+            let severity = d.severity.unwrap_or(DiagnosticSeverity::INFORMATION);
+            listed.push((CursorPosition::from(d.range.start), severity, d.message.to_owned()));
             match d.severity {
                 Some(DiagnosticSeverity::ERROR) => errors += 1,
                 Some(DiagnosticSeverity::WARNING) => warnings += 1,
@@ -182,23 +221,38 @@ impl Diagnostic {
                 diagnostic_lines.push((line_idx, d.into()));
             }
         }
-        Self { errors, warnings, lines: Some(diagnostic_lines) }
+        Self { errors, warnings, lines: Some(diagnostic_lines), listed }
     }
 }
 
 #[derive(Debug)]
 pub enum LSPResponseType {
     Completion(i64, String, CursorPosition),
+    CompletionResolve(i64),
     Hover(i64),
     SignatureHelp(i64),
     References(i64),
+    WorkspaceSymbol(i64),
+    DocumentSymbol(i64),
     Renames(i64),
+    /// version of the document the edits are made for
+    Formatting {
+        id: i64,
+        version: i32,
+    },
+    CodeAction(i64),
+    ExecuteCommand(i64),
     Tokens(i64),
     TokensPartial {
         id: i64,
         max_lines: usize,
     },
-    #[allow(dead_code)]
+    /// content lines covered by the request with the version they were requested for
+    InlayHint {
+        id: i64,
+        version: i32,
+        lines: Range<usize>,
+    },
     Definition(i64),
     Declaration(i64),
 }
@@ -207,12 +261,19 @@ impl LSPResponseType {
     pub fn id(&self) -> &i64 {
         match self {
             Self::Completion(id, ..) => id,
+            Self::CompletionResolve(id) => id,
             Self::Hover(id) => id,
             Self::SignatureHelp(id) => id,
             Self::References(id) => id,
+            Self::WorkspaceSymbol(id) => id,
+            Self::DocumentSymbol(id) => id,
             Self::Renames(id) => id,
+            Self::Formatting { id, .. } => id,
+            Self::CodeAction(id) => id,
+            Self::ExecuteCommand(id) => id,
             Self::Tokens(id) => id,
             Self::TokensPartial { id, .. } => id,
+            Self::InlayHint { id, .. } => id,
             Self::Definition(id) => id,
             Self::Declaration(id) => id,
         }
@@ -224,14 +285,44 @@ impl LSPResponseType {
                 CompletionResponse::Array(arr) => LSPResponse::Completion(arr, line.to_owned(), *idx),
                 CompletionResponse::List(ls) => LSPResponse::Completion(ls.items, line.to_owned(), *idx),
             },
+            Self::CompletionResolve(..) => LSPResponse::CompletionResolve(from_value(value?).ok()?),
             Self::Hover(..) => LSPResponse::Hover(from_value(value?).ok()?),
             Self::SignatureHelp(..) => LSPResponse::SignatureHelp(from_value(value?).ok()?),
             Self::References(..) => LSPResponse::References(from_value(value?).ok()?),
+            // null result - no symbols found
+            Self::WorkspaceSymbol(..) => LSPResponse::WorkspaceSymbols(
+                from_value::<Option<WorkspaceSymbolResponse>>(value?)
+                    .ok()?
+                    .unwrap_or(WorkspaceSymbolResponse::Flat(Vec::new())),
+            ),
+            // null result - no symbols in the document
+            Self::DocumentSymbol(..) => LSPResponse::DocumentSymbols(
+                from_value::<Option<DocumentSymbolResponse>>(value?)
+                    .ok()?
+                    .unwrap_or(DocumentSymbolResponse::Flat(Vec::new())),
+            ),
             Self::Renames(..) => LSPResponse::Renames(from_value(value?).ok()?),
+            // null result is valid - nothing to format
+            Self::Formatting { version, .. } => LSPResponse::Formatting {
+                edits: from_value::<Option<Vec<TextEdit>>>(value.unwrap_or_default()).ok()?.unwrap_or_default(),
+                version: *version,
+            },
+            // null result - no actions
+            Self::CodeAction(..) => LSPResponse::CodeAction(
+                from_value::<Option<Vec<CodeActionOrCommand>>>(value?).ok()?.unwrap_or_default(),
+            ),
+            // result is defined by the server - only errors are reported
+            Self::ExecuteCommand(..) => value.map(|_| LSPResponse::ExecuteCommand)?,
             Self::Tokens(..) => LSPResponse::Tokens(from_value(value?).ok()?),
             Self::TokensPartial { max_lines, .. } => {
                 LSPResponse::TokensPartial { result: from_value(value?).ok()?, max_lines: *max_lines }
             }
+            // null result - no hints in range
+            Self::InlayHint { version, lines, .. } => LSPResponse::InlayHints {
+                hints: from_value::<Option<Vec<InlayHint>>>(value?).ok()?.unwrap_or_default(),
+                version: *version,
+                lines: lines.clone(),
+            },
             Self::Definition(..) => LSPResponse::Definition(from_value(value?).ok()?),
             Self::Declaration(..) => LSPResponse::Declaration(from_value(value?).ok()?),
         })
@@ -240,12 +331,19 @@ impl LSPResponseType {
 
 pub enum LSPResponse {
     Completion(Vec<CompletionItem>, String, CursorPosition),
+    CompletionResolve(Box<CompletionItem>),
     Hover(Hover),
     SignatureHelp(SignatureHelp),
     References(Option<Vec<Location>>),
+    WorkspaceSymbols(WorkspaceSymbolResponse),
+    DocumentSymbols(DocumentSymbolResponse),
     Renames(WorkspaceEdit),
+    Formatting { edits: Vec<TextEdit>, version: i32 },
+    CodeAction(Vec<CodeActionOrCommand>),
+    ExecuteCommand,
     Tokens(SemanticTokensResult),
     TokensPartial { result: SemanticTokensRangeResult, max_lines: usize },
+    InlayHints { hints: Vec<InlayHint>, version: i32, lines: Range<usize> },
     Definition(GotoDefinitionResponse),
     Declaration(GotoDeclarationResponse),
 }
@@ -254,14 +352,119 @@ impl Display for LSPResponseType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             LSPResponseType::Completion(..) => f.write_str("Completion"),
+            LSPResponseType::CompletionResolve(..) => f.write_str("CompletionResolve"),
             LSPResponseType::Declaration(..) => f.write_str("Declaration"),
             LSPResponseType::Definition(..) => f.write_str("Definition"),
             LSPResponseType::Hover(..) => f.write_str("Hover"),
             LSPResponseType::Renames(..) => f.write_str("Renames"),
+            LSPResponseType::Formatting { .. } => f.write_str("Formatting"),
+            LSPResponseType::CodeAction(..) => f.write_str("CodeAction"),
+            LSPResponseType::ExecuteCommand(..) => f.write_str("ExecuteCommand"),
             LSPResponseType::SignatureHelp(..) => f.write_str("SignatureHelp"),
             LSPResponseType::Tokens(..) => f.write_str("Tokens"),
             LSPResponseType::TokensPartial { .. } => f.write_str("TokensPartial"),
+            LSPResponseType::InlayHint { .. } => f.write_str("InlayHint"),
             LSPResponseType::References(..) => f.write_str("References"),
+            LSPResponseType::WorkspaceSymbol(..) => f.write_str("WorkspaceSymbol"),
+            LSPResponseType::DocumentSymbol(..) => f.write_str("DocumentSymbol"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LSPResponse, LSPResponseType};
+    use lsp_types::{CodeActionOrCommand, WorkspaceSymbolResponse};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn formatting_response() {
+        let request = LSPResponseType::Formatting { id: 3, version: 7 };
+        let edit = json!([{"range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 2}}, "newText": "    "}]);
+        match request.parse(Some(edit)) {
+            Some(LSPResponse::Formatting { edits, version }) => {
+                assert_eq!(version, 7);
+                assert_eq!(edits.len(), 1);
+                assert_eq!(edits[0].new_text, "    ");
+            }
+            _ => panic!("Expected formatting edits"),
+        }
+        // no edits needed
+        for result in [Some(Value::Null), None] {
+            match request.parse(result) {
+                Some(LSPResponse::Formatting { edits, .. }) => assert!(edits.is_empty()),
+                _ => panic!("Expected empty formatting"),
+            }
+        }
+        assert!(request.parse(Some(json!({"bad": 1}))).is_none());
+    }
+
+    #[test]
+    fn code_action_response() {
+        let request = LSPResponseType::CodeAction(4);
+        let actions = json!([
+            {"title": "Import `HashMap`", "kind": "quickfix", "isPreferred": true, "edit": {"changes": {}}},
+            {"title": "Run fix", "command": "fix.run", "arguments": [1]}
+        ]);
+        match request.parse(Some(actions)) {
+            Some(LSPResponse::CodeAction(actions)) => {
+                assert_eq!(actions.len(), 2);
+                assert!(matches!(&actions[0], CodeActionOrCommand::CodeAction(action) if action.edit.is_some()));
+                assert!(matches!(&actions[1], CodeActionOrCommand::Command(command) if command.command == "fix.run"));
+            }
+            _ => panic!("Expected code actions"),
+        }
+        match request.parse(Some(Value::Null)) {
+            Some(LSPResponse::CodeAction(actions)) => assert!(actions.is_empty()),
+            _ => panic!("Expected no code actions"),
+        }
+        // error response
+        assert!(request.parse(None).is_none());
+    }
+
+    #[test]
+    fn inlay_hint_response() {
+        let request = LSPResponseType::InlayHint { id: 5, version: 2, lines: 10..40 };
+        let hints = json!([
+            {"position": {"line": 12, "character": 9}, "label": ": i32", "kind": 1},
+            {"position": {"line": 15, "character": 4}, "label": [{"value": "count"}, {"value": ":"}], "paddingRight": true}
+        ]);
+        match request.parse(Some(hints)) {
+            Some(LSPResponse::InlayHints { hints, version, lines }) => {
+                assert_eq!(version, 2);
+                assert_eq!(lines, 10..40);
+                assert_eq!(hints.len(), 2);
+                assert_eq!(hints[1].padding_right, Some(true));
+            }
+            _ => panic!("Expected inlay hints"),
+        }
+        match request.parse(Some(Value::Null)) {
+            Some(LSPResponse::InlayHints { hints, .. }) => assert!(hints.is_empty()),
+            _ => panic!("Expected no inlay hints"),
+        }
+    }
+    #[test]
+    fn workspace_symbol_response() {
+        let request = LSPResponseType::WorkspaceSymbol(6);
+        let symbols = json!([{
+            "name": "Tree",
+            "kind": 23,
+            "location": {
+                "uri": "file:///src/tree/mod.rs",
+                "range": {"start": {"line": 30, "character": 11}, "end": {"line": 30, "character": 15}}
+            },
+            "containerName": "tree"
+        }]);
+        match request.parse(Some(symbols)) {
+            Some(LSPResponse::WorkspaceSymbols(WorkspaceSymbolResponse::Flat(symbols))) => {
+                assert_eq!(symbols.len(), 1);
+                assert_eq!(symbols[0].container_name.as_deref(), Some("tree"));
+            }
+            _ => panic!("Expected workspace symbols"),
+        }
+        match request.parse(Some(Value::Null)) {
+            Some(LSPResponse::WorkspaceSymbols(WorkspaceSymbolResponse::Flat(symbols))) => assert!(symbols.is_empty()),
+            _ => panic!("Expected no workspace symbols"),
         }
     }
 }
@@ -15,7 +15,7 @@ pub use local::{init_local_tokens, Highlighter};
 use lsp_stream::JsonRCP;
 pub use messages::{
     Diagnostic, DiagnosticHandle, DiagnosticType, EditorDiagnostics, LSPMessage, LSPResponse, LSPResponseType,
-    Response, TreeDiagnostics,
+    ProjectDiagnostic, Response, TreeDiagnostics,
 };
 pub use notification::LSPNotification;
 pub use request::LSPRequest;
@@ -5,10 +5,11 @@ use crate::{
 use lsp_types::{
     notification::DidChangeTextDocument,
     request::{
-        Completion, GotoDeclaration, GotoDefinition, HoverRequest, References, Rename, SemanticTokensFullRequest,
-        SemanticTokensRangeRequest, SignatureHelpRequest,
+        CodeActionRequest, Completion, DocumentSymbolRequest, ExecuteCommand, Formatting, GotoDeclaration,
+        GotoDefinition, HoverRequest, InlayHintRequest, RangeFormatting, References, Rename, ResolveCompletionItem,
+        SemanticTokensFullRequest, SemanticTokensRangeRequest, SignatureHelpRequest, WorkspaceSymbolRequest,
     },
-    Range, TextDocumentContentChangeEvent, Uri,
+    Command, CompletionItem, Diagnostic, FormattingOptions, Range, TextDocumentContentChangeEvent, Uri,
 };
 
 pub enum Payload {
@@ -18,13 +19,21 @@ pub enum Payload {
     /// Requests
     Tokens(Uri, i64),
     PartialTokens(Uri, Range, i64),
+    InlayHint(Uri, Range, i64),
     Completion(Uri, CursorPosition, i64),
+    CompletionResolve(Box<CompletionItem>, i64),
     Rename(Uri, CursorPosition, String, i64),
     References(Uri, CursorPosition, i64),
+    WorkspaceSymbol(String, i64),
+    DocumentSymbol(Uri, i64),
     Definition(Uri, CursorPosition, i64),
     Declaration(Uri, CursorPosition, i64),
     Hover(Uri, CursorPosition, i64),
     SignatureHelp(Uri, CursorPosition, i64),
+    Formatting(Uri, Box<FormattingOptions>, i64),
+    RangeFormatting(Uri, Range, Box<FormattingOptions>, i64),
+    CodeAction(Uri, Range, Box<[Diagnostic]>, i64),
+    ExecuteCommand(Box<Command>, i64),
     /// Send serialized
     Direct(String),
 }
@@ -44,18 +53,42 @@ impl Payload {
             }
             // Create and send request
             Payload::References(uri, c, id) => LSPRequest::<References>::references(uri, c, id).stringify(),
+            Payload::WorkspaceSymbol(query, id) => {
+                LSPRequest::<WorkspaceSymbolRequest>::workspace_symbol(query, id).stringify()
+            }
+            Payload::DocumentSymbol(uri, id) => {
+                LSPRequest::<DocumentSymbolRequest>::document_symbols(uri, id).stringify()
+            }
             Payload::Definition(uri, c, id) => LSPRequest::<GotoDefinition>::definition(uri, c, id).stringify(),
             Payload::Declaration(uri, c, id) => LSPRequest::<GotoDeclaration>::declaration(uri, c, id).stringify(),
             Payload::Completion(uri, c, id) => LSPRequest::<Completion>::completion(uri, c, id).stringify(),
+            Payload::CompletionResolve(item, id) => {
+                LSPRequest::<ResolveCompletionItem>::completion_resolve(*item, id).stringify()
+            }
             Payload::Tokens(uri, id) => LSPRequest::<SemanticTokensFullRequest>::semantics_full(uri, id).stringify(),
             Payload::PartialTokens(uri, range, id) => {
                 LSPRequest::<SemanticTokensRangeRequest>::semantics_range(uri, range, id).stringify()
             }
+            Payload::InlayHint(uri, range, id) => {
+                LSPRequest::<InlayHintRequest>::inlay_hint(uri, range, id).stringify()
+            }
             Payload::Rename(uri, c, new_name, id) => LSPRequest::<Rename>::rename(uri, c, new_name, id).stringify(),
             Payload::Hover(uri, c, id) => LSPRequest::<HoverRequest>::hover(uri, c, id).stringify(),
             Payload::SignatureHelp(uri, c, id) => {
                 LSPRequest::<SignatureHelpRequest>::signature_help(uri, c, id).stringify()
             }
+            Payload::Formatting(uri, options, id) => {
+                LSPRequest::<Formatting>::formatting(uri, *options, id).stringify()
+            }
+            Payload::RangeFormatting(uri, range, options, id) => {
+                LSPRequest::<RangeFormatting>::range_formatting(uri, range, *options, id).stringify()
+            }
+            Payload::CodeAction(uri, range, diagnostics, id) => {
+                LSPRequest::<CodeActionRequest>::code_action(uri, range, diagnostics.into_vec(), id).stringify()
+            }
+            Payload::ExecuteCommand(command, id) => {
+                LSPRequest::<ExecuteCommand>::execute_command(*command, id).stringify()
+            }
         }
     }
 }
@@ -4,12 +4,16 @@ use crate::{lsp::LSPResult, workspace::CursorPosition};
 use lsp_types::{self as lsp, Uri};
 use lsp_types::{
     request::{
-        Completion, GotoDeclaration, GotoDeclarationParams, GotoDefinition, HoverRequest, Initialize, References,
-        Rename, SemanticTokensFullRequest, SemanticTokensRangeRequest, SignatureHelpRequest,
+        CodeActionRequest, Completion, DocumentSymbolRequest, ExecuteCommand, Formatting, GotoDeclaration,
+        GotoDeclarationParams, GotoDefinition, HoverRequest, Initialize, InlayHintRequest, RangeFormatting, References,
+        Rename, ResolveCompletionItem, SemanticTokensFullRequest, SemanticTokensRangeRequest, SignatureHelpRequest,
+        WorkspaceSymbolRequest,
     },
-    CompletionParams, GotoDefinitionParams, HoverParams, Range, ReferenceContext, ReferenceParams, RenameParams,
-    SemanticTokensParams, SemanticTokensRangeParams, SignatureHelpParams, TextDocumentIdentifier,
-    TextDocumentPositionParams, WorkspaceFolder,
+    CodeActionContext, CodeActionParams, Command, CompletionItem, CompletionParams, Diagnostic,
+    DocumentFormattingParams, DocumentRangeFormattingParams, DocumentSymbolParams, ExecuteCommandParams,
+    FormattingOptions, GotoDefinitionParams, HoverParams, InlayHintParams, Range, ReferenceContext, ReferenceParams,
+    RenameParams, SemanticTokensParams, SemanticTokensRangeParams, SignatureHelpParams, TextDocumentIdentifier,
+    TextDocumentPositionParams, WorkspaceFolder, WorkspaceSymbolParams,
 };
 use serde::Serialize;
 use serde_json::to_string;
@@ -58,6 +62,28 @@ where
         )
     }
 
+    pub fn workspace_symbol(query: String, id: i64) -> LSPRequest<WorkspaceSymbolRequest> {
+        LSPRequest::with(
+            id,
+            WorkspaceSymbolParams {
+                query,
+                work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+                partial_result_params: lsp::PartialResultParams::default(),
+            },
+        )
+    }
+
+    pub fn document_symbols(uri: Uri, id: i64) -> LSPRequest<DocumentSymbolRequest> {
+        LSPRequest::with(
+            id,
+            DocumentSymbolParams {
+                text_document: TextDocumentIdentifier::new(uri),
+                work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+                partial_result_params: lsp::PartialResultParams::default(),
+            },
+        )
+    }
+
     pub fn rename(uri: Uri, c: CursorPosition, new_name: String, id: i64) -> LSPRequest<Rename> {
         LSPRequest::with(
             id,
@@ -72,6 +98,58 @@ where
         )
     }
 
+    pub fn formatting(uri: Uri, options: FormattingOptions, id: i64) -> LSPRequest<Formatting> {
+        LSPRequest::with(
+            id,
+            DocumentFormattingParams {
+                text_document: TextDocumentIdentifier { uri },
+                options,
+                work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            },
+        )
+    }
+
+    pub fn range_formatting(
+        uri: Uri,
+        range: Range,
+        options: FormattingOptions,
+        id: i64,
+    ) -> LSPRequest<RangeFormatting> {
+        LSPRequest::with(
+            id,
+            DocumentRangeFormattingParams {
+                text_document: TextDocumentIdentifier { uri },
+                range,
+                options,
+                work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            },
+        )
+    }
+
+    pub fn code_action(uri: Uri, range: Range, diagnostics: Vec<Diagnostic>, id: i64) -> LSPRequest<CodeActionRequest> {
+        LSPRequest::with(
+            id,
+            CodeActionParams {
+                text_document: TextDocumentIdentifier { uri },
+                range,
+                context: CodeActionContext { diagnostics, ..Default::default() },
+                work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+                partial_result_params: lsp::PartialResultParams::default(),
+            },
+        )
+    }
+
+    pub fn execute_command(command: Command, id: i64) -> LSPRequest<ExecuteCommand> {
+        LSPRequest::with(
+            id,
+            ExecuteCommandParams {
+                command: command.command,
+                arguments: command.arguments.unwrap_or_default(),
+                work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            },
+        )
+    }
+
     pub fn semantics_full(uri: Uri, id: i64) -> LSPRequest<SemanticTokensFullRequest> {
         LSPRequest::with(
             id,
@@ -95,6 +173,17 @@ where
         )
     }
 
+    pub fn inlay_hint(uri: Uri, range: Range, id: i64) -> LSPRequest<InlayHintRequest> {
+        LSPRequest::with(
+            id,
+            InlayHintParams {
+                text_document: TextDocumentIdentifier { uri },
+                range,
+                work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            },
+        )
+    }
+
     pub fn declaration(uri: Uri, c: CursorPosition, id: i64) -> LSPRequest<GotoDeclaration> {
         LSPRequest::with(
             id,
@@ -139,6 +228,11 @@ where
         )
     }
 
+    #[inline]
+    pub fn completion_resolve(item: CompletionItem, id: i64) -> LSPRequest<ResolveCompletionItem> {
+        LSPRequest::with(id, item)
+    }
+
     #[inline]
     pub fn signature_help(uri: Uri, c: CursorPosition, id: i64) -> LSPRequest<SignatureHelpRequest> {
         LSPRequest::with(
@@ -175,7 +269,10 @@ where
             lsp::InitializeParams {
                 workspace_folders: Some(vec![WorkspaceFolder { uri, name: "root".to_owned() }]),
                 capabilities: lsp::ClientCapabilities {
-                    workspace: Some(lsp::WorkspaceClientCapabilities { ..Default::default() }),
+                    workspace: Some(lsp::WorkspaceClientCapabilities {
+                        symbol: Some(lsp::WorkspaceSymbolClientCapabilities::default()),
+                        ..Default::default()
+                    }),
                     text_document: Some(lsp::TextDocumentClientCapabilities {
                         completion: Some(lsp::CompletionClientCapabilities {
                             completion_item: Some(lsp::CompletionItemCapability {
@@ -203,10 +300,41 @@ where
                             ..Default::default()
                         }),
                         references: Some(lsp::ReferenceClientCapabilities::default()),
+                        // flat symbol information is handled as well
+                        document_symbol: Some(lsp::DocumentSymbolClientCapabilities {
+                            hierarchical_document_symbol_support: Some(true),
+                            ..Default::default()
+                        }),
+                        formatting: Some(lsp::DocumentFormattingClientCapabilities::default()),
+                        range_formatting: Some(lsp::DocumentRangeFormattingClientCapabilities::default()),
+                        // literals carry the edits, resolve is not supported
+                        code_action: Some(lsp::CodeActionClientCapabilities {
+                            code_action_literal_support: Some(lsp::CodeActionLiteralSupport {
+                                code_action_kind: lsp::CodeActionKindLiteralSupport {
+                                    value_set: [
+                                        lsp::CodeActionKind::EMPTY,
+                                        lsp::CodeActionKind::QUICKFIX,
+                                        lsp::CodeActionKind::REFACTOR,
+                                        lsp::CodeActionKind::REFACTOR_EXTRACT,
+                                        lsp::CodeActionKind::REFACTOR_INLINE,
+                                        lsp::CodeActionKind::REFACTOR_REWRITE,
+                                        lsp::CodeActionKind::SOURCE,
+                                        lsp::CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+                                    ]
+                                    .into_iter()
+                                    .map(|kind| kind.as_str().to_owned())
+                                    .collect(),
+                                },
+                            }),
+                            is_preferred_support: Some(true),
+                            ..Default::default()
+                        }),
                         signature_help: Some(lsp::SignatureHelpClientCapabilities {
                             context_support: Some(true),
                             ..Default::default()
                         }),
+                        // labels are rendered as plain text, resolve is not supported
+                        inlay_hint: Some(lsp::InlayHintClientCapabilities::default()),
                         ..Default::default()
                     }),
                     general: Some(lsp::GeneralClientCapabilities {
@@ -3,6 +3,7 @@ mod cli;
 mod configs;
 mod error;
 mod global_state;
+mod instance;
 mod lsp;
 mod popups;
 mod render;
@@ -14,7 +15,7 @@ mod workspace;
 
 use app::app;
 use clap::Parser;
-use cli::{Args, TreeSeletor};
+use cli::{Args, FileAnchor, TreeSeletor};
 use error::IdiomResult;
 use render::backend::{Backend, BackendProtocol};
 
@@ -24,7 +25,7 @@ async fn main() -> IdiomResult<()> {
     let mut backend = Backend::init();
     let open_file = match args.select {
         false => args.get_path()?,
-        true => TreeSeletor::select(&mut backend)?,
+        true => TreeSeletor::select(&mut backend)?.map(|path| (path, FileAnchor::default())),
     };
     app(open_file, backend).await
 }
@@ -6,7 +6,7 @@ use crate::{
     render::{
         backend::{Backend, Style},
         layout::{Line, Rect},
-        state::State,
+        state::{SoftLimit, State},
         Button,
     },
 };
@@ -186,6 +186,7 @@ impl Popup {
 pub struct PopupSelector<T> {
     pub options: Vec<T>,
     pub state: State,
+    limit: SoftLimit,
     display: fn(&T) -> &str,
     command: fn(&mut PopupSelector<T>) -> PopupMessage,
     size: (u16, usize),
@@ -202,9 +203,13 @@ impl<T> PopupInterface for PopupSelector<T> {
         rect.draw_borders(None, None, &mut gs.writer);
         if self.options.is_empty() {
             self.state.render_list(["No results found!"].into_iter(), rect, &mut gs.writer);
-        } else {
-            self.state.render_list(self.options.iter().map(|opt| (self.display)(opt)), rect, &mut gs.writer);
-        };
+            return;
+        }
+        if let Some(indicator) = self.limit.indicator(self.options.len(), false) {
+            rect.border_title_bot(&indicator, &mut gs.writer);
+        }
+        let shown = self.limit.shown(self.options.len());
+        self.state.render_list_window(&self.options[..shown], self.display, rect, &mut gs.writer);
     }
 
     fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
@@ -214,11 +219,11 @@ impl<T> PopupInterface for PopupSelector<T> {
         match key.code {
             KeyCode::Enter => (self.command)(self),
             KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('W') => {
-                self.state.prev(self.options.len());
+                self.limit.prev(&mut self.state, self.options.len());
                 PopupMessage::None
             }
             KeyCode::Down | KeyCode::Char('d') | KeyCode::Char('D') => {
-                self.state.next(self.options.len());
+                self.limit.next(&mut self.state, self.options.len());
                 PopupMessage::None
             }
             _ => PopupMessage::None,
@@ -230,20 +235,20 @@ impl<T> PopupInterface for PopupSelector<T> {
             MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), row, column, .. } => {
                 if let Some(pos) = self.rect.and_then(|rect| rect.relative_position(row, column)) {
                     let option_idx = pos.line + self.state.at_line;
-                    if option_idx >= self.options.len() {
+                    if option_idx >= self.limit.shown(self.options.len()) {
                         return PopupMessage::None;
                     }
-                    self.state.select(option_idx, self.options.len());
+                    self.state.selected = option_idx;
                     self.mark_as_updated();
                     return (self.command)(self);
                 }
             }
             MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
-                self.state.prev(self.options.len());
+                self.limit.prev(&mut self.state, self.options.len());
                 self.mark_as_updated();
             }
             MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
-                self.state.next(self.options.len());
+                self.limit.next(&mut self.state, self.options.len());
                 self.mark_as_updated();
             }
             _ => (),
@@ -268,6 +273,15 @@ impl<T> PopupSelector<T> {
         size: Option<(u16, usize)>,
     ) -> Self {
         let size = size.unwrap_or((20, 120));
-        Self { options, display, command, state: State::new(), size, updated: true, rect: None }
+        Self {
+            options,
+            display,
+            command,
+            state: State::new(),
+            limit: SoftLimit::default(),
+            size,
+            updated: true,
+            rect: None,
+        }
     }
 }
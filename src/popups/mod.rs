@@ -1,9 +1,20 @@
 mod generics;
 pub mod pallet;
+pub mod popup_clipboard;
+pub mod popup_diagnostics;
+pub mod popup_diff;
+pub mod popup_file_finder;
 pub mod popup_file_open;
 pub mod popup_find;
+pub mod popup_large_file;
+pub mod popup_outline;
+pub mod popup_project_search;
+pub mod popup_recent;
+pub mod popup_references;
+pub mod popup_rename;
 pub mod popup_replace;
 pub mod popup_tree_search;
+pub mod popup_workspace_symbols;
 pub mod popups_editor;
 pub mod popups_tree;
 mod utils;
@@ -1,10 +1,14 @@
-use super::{popup_file_open::OpenFileSelector, PopupInterface};
+use super::{
+    popup_file_open::OpenFileSelector,
+    popups_editor::{IndentPopup, WrapColumnPopup},
+    PopupInterface,
+};
 use crate::{
     configs::{CONFIG_FOLDER, EDITOR_CFG_FILE, KEY_MAP, THEME_FILE, THEME_UI},
     global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
     render::{layout::Rect, state::State, TextField},
     tree::Tree,
-    workspace::Workspace,
+    workspace::{actions::CaseMode, Workspace},
 };
 use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use dirs::config_dir;
@@ -156,8 +160,39 @@ impl Pallet {
     pub fn new() -> Box<Self> {
         let mut commands = vec![
             (0, Command::pass_event("Open file", IdiomEvent::NewPopup(OpenFileSelector::boxed))),
+            (0, Command::pass_event("Find file in project", IdiomEvent::FindFile)),
+            (0, Command::pass_event("Search text in project", IdiomEvent::SearchProject)),
+            (0, Command::pass_event("Search symbols in workspace (LSP)", IdiomEvent::SearchSymbols)),
+            (0, Command::pass_event("Project diagnostics", IdiomEvent::ProjectDiagnostics)),
+            (0, Command::pass_event("Reopen recently closed file", IdiomEvent::RecentFiles)),
+            (0, Command::pass_event("Paste from clipboard history", IdiomEvent::ClipboardHistory)),
+            (0, Command::pass_event("Toggle split view", IdiomEvent::ToggleSplit)),
+            (0, Command::pass_event("New scratch buffer", IdiomEvent::NewScratch)),
+            (0, Command::pass_event("Save all and run checks", IdiomEvent::SaveAllAndCheck)),
             (0, Command::access_edit("UPPERCASE", uppercase)),
             (0, Command::access_edit("LOWERCASE", lowercase)),
+            (0, Command::access_edit("Title Case", titlecase)),
+            (0, Command::access_edit("tOGGLE cASE", togglecase)),
+            (0, Command::access_edit("Dedupe selected lines", dedupe_lines)),
+            (0, Command::pass_event("Align trailing comments", IdiomEvent::AlignComments)),
+            (0, Command::pass_event("Annotate line", IdiomEvent::AnnotateLine)),
+            (0, Command::pass_event("Show line annotation", IdiomEvent::ShowAnnotation)),
+            (0, Command::pass_event("List annotations", IdiomEvent::ListAnnotations)),
+            (0, Command::pass_event("Export annotations (markdown)", IdiomEvent::ExportAnnotations)),
+            (0, Command::pass_event("Export workspace context", IdiomEvent::ExportContext)),
+            (0, Command::pass_event("Import workspace context", IdiomEvent::ImportContext)),
+            (0, Command::access_edit("Reflow paragraph", reflow_paragraph)),
+            (0, Command::pass_event("Set wrap column", IdiomEvent::NewPopup(WrapColumnPopup::boxed))),
+            (0, Command::pass_event("Set indent", IdiomEvent::NewPopup(IndentPopup::boxed))),
+            (0, Command::access_edit("Convert indent to spaces", retab_spaces)),
+            (0, Command::access_edit("Convert indent to tabs", retab_tabs)),
+            (0, Command::access_edit("Toggle line wrap", toggle_wrap)),
+            (0, Command::access_edit("Toggle line numbers", toggle_line_numbers)),
+            (0, Command::access_edit("Toggle relative line numbers", toggle_relative_line_numbers)),
+            (0, Command::access_edit("Toggle current line highlight", toggle_current_line_highlight)),
+            (0, Command::access_edit("Cycle whitespace rendering", cycle_render_whitespace)),
+            (0, Command::access_edit("Reset view to defaults", reset_view)),
+            (0, Command::pass_event("Show view settings", IdiomEvent::ShowViewSettings)),
         ];
         commands.extend(
             [
@@ -182,31 +217,97 @@ impl Pallet {
     }
 }
 
-fn uppercase(ws: &mut Workspace, _tree: &mut Tree) {
+fn dedupe_lines(ws: &mut Workspace, _tree: &mut Tree) {
     if let Some(editor) = ws.get_active() {
-        if editor.cursor.select_is_none() {
-            editor.select_token();
-        }
-        if editor.cursor.select_is_none() {
-            return;
+        editor.dedupe_selection();
+    }
+}
+
+/// the indent of the editor is changed only if the whole file is converted (no select)
+fn retab_spaces(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        let width = editor.indent_width();
+        let whole_file = editor.cursor.select_get().is_none();
+        editor.retab(true, width);
+        if whole_file {
+            editor.set_indent(" ".repeat(width));
         }
-        if let Some(clip) = editor.copy() {
-            editor.insert_snippet(clip.to_uppercase(), None);
+    }
+}
+
+fn retab_tabs(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        let whole_file = editor.cursor.select_get().is_none();
+        editor.retab(false, editor.indent_width());
+        if whole_file {
+            editor.set_indent(String::from("\t"));
         }
     }
 }
 
+fn toggle_wrap(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.toggle_wrap();
+    }
+}
+
+fn toggle_line_numbers(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.toggle_line_numbers();
+    }
+}
+
+fn toggle_relative_line_numbers(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.toggle_relative_line_numbers();
+    }
+}
+
+fn toggle_current_line_highlight(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.toggle_current_line_highlight();
+    }
+}
+
+fn cycle_render_whitespace(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.cycle_render_whitespace();
+    }
+}
+
+fn reset_view(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.reset_view();
+    }
+}
+
+fn reflow_paragraph(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.reflow_paragraph();
+    }
+}
+
+fn uppercase(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.transform_case(CaseMode::Upper);
+    }
+}
+
 fn lowercase(ws: &mut Workspace, _tree: &mut Tree) {
     if let Some(editor) = ws.get_active() {
-        if editor.cursor.select_is_none() {
-            editor.select_token();
-        }
-        if editor.cursor.select_is_none() {
-            return;
-        }
-        if let Some(clip) = editor.copy() {
-            editor.insert_snippet(clip.to_lowercase(), None);
-        }
+        editor.transform_case(CaseMode::Lower);
+    }
+}
+
+fn titlecase(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.transform_case(CaseMode::Title);
+    }
+}
+
+fn togglecase(ws: &mut Workspace, _tree: &mut Tree) {
+    if let Some(editor) = ws.get_active() {
+        editor.transform_case(CaseMode::Toggle);
     }
 }
 
@@ -0,0 +1,148 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{layout::Rect, state::State},
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+
+const TITLE: &str = " Clipboard history ";
+
+/// Recent clips (latest first) shown by their first line, the selected one is pasted into the editor
+pub struct ClipboardPopup {
+    clips: Vec<String>,
+    updated: bool,
+    rect: Option<Rect>,
+    state: State,
+}
+
+impl ClipboardPopup {
+    pub fn boxed(clipboard: &Clipboard) -> Box<dyn PopupInterface> {
+        Box::new(Self {
+            clips: clipboard.history_iter().map(str::to_owned).collect(),
+            updated: true,
+            rect: None,
+            state: State::new(),
+        })
+    }
+
+    fn paste(&self, idx: usize) -> PopupMessage {
+        match idx < self.clips.len() {
+            true => IdiomEvent::PasteFromHistory(idx).into(),
+            false => PopupMessage::None,
+        }
+    }
+}
+
+impl PopupInterface for ClipboardPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.top(15).vcenter(100);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(TITLE, gs.backend());
+        let width = rect.width;
+        let previews = self.clips.iter().map(|clip| preview(clip, width)).collect::<Vec<_>>();
+        self.state.render_list(previews.iter().map(String::as_str), rect, gs.backend());
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, _clipboard: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Enter => self.paste(self.state.selected),
+            KeyCode::Up => {
+                self.state.prev(self.clips.len());
+                PopupMessage::None
+            }
+            KeyCode::Down => {
+                self.state.next(self.clips.len());
+                PopupMessage::None
+            }
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.clips.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.clips.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) => self.paste(self.state.at_line + pos.line),
+            None => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+/// first line of the clip fitting the width, count of the other lines is added if any
+fn preview(clip: &str, width: usize) -> String {
+    let mut lines = clip.lines();
+    let first = lines.next().unwrap_or_default().trim_end();
+    let more = match lines.count() {
+        0 => String::new(),
+        1 => String::from(" (+1 line)"),
+        count => format!(" (+{count} lines)"),
+    };
+    let first_width = width.saturating_sub(more.chars().count());
+    let mut text = match first.chars().count() > first_width {
+        true => {
+            let mut text = first.chars().take(first_width.saturating_sub(3)).collect::<String>();
+            text.push_str("...");
+            text
+        }
+        false => first.to_owned(),
+    };
+    text.push_str(&more);
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::{preview, ClipboardPopup};
+    use crate::global_state::{Clipboard, IdiomEvent, PopupMessage};
+    use crate::popups::PopupInterface;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn clip_preview() {
+        assert_eq!(preview("let a = 1;\n", 40), "let a = 1;");
+        assert_eq!(preview("fn main() {\n    call();\n}", 40), "fn main() { (+2 lines)");
+        assert_eq!(preview("a very long line of text", 10), "a very ...");
+        assert_eq!(preview("a very long line\nnext", 20), "a very ... (+1 line)");
+        assert_eq!(preview("", 10), "");
+    }
+
+    #[test]
+    fn paste_selected() {
+        let mut clipboard = Clipboard::default();
+        let mut popup = ClipboardPopup {
+            clips: vec![String::from("latest"), String::from("older")],
+            updated: true,
+            rect: None,
+            state: Default::default(),
+        };
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        popup.key_map(&down, &mut clipboard);
+        match popup.key_map(&enter, &mut clipboard) {
+            PopupMessage::Event(IdiomEvent::PasteFromHistory(idx)) => assert_eq!(idx, 1),
+            _ => panic!("Expected paste event"),
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    lsp::{LSPClient, ProjectDiagnostic},
+    render::{
+        backend::{color, BackendProtocol, Style},
+        layout::{IterLines, Rect},
+        UTF8Safe,
+    },
+    syntax::severity_color,
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use lsp_types::DiagnosticSeverity;
+
+/// continuation rows of wrapped messages
+const WRAP_INDENT: &str = "    ";
+/// messages are moved under the location if less is left on its row
+const MIN_MESSAGE_WIDTH: usize = 20;
+
+struct Listed {
+    diagnostic: ProjectDiagnostic,
+    /// path:line relative to the project
+    location: String,
+}
+
+/// Diagnostics published by all running LSP servers and local LSPs (files do not need to be open)
+/// ordered by severity and path
+/// the list is refreshed while open, keys 1-4 show single severity only, Enter opens the location
+pub struct DiagnosticsPopup {
+    clients: Vec<LSPClient>,
+    /// publish counts the list was built from
+    versions: Vec<Option<usize>>,
+    listed: Vec<Listed>,
+    filter: Option<DiagnosticSeverity>,
+    /// indexes of listed diagnostics passing the filter
+    shown: Vec<usize>,
+    selected: usize,
+    /// first rendered (shown) diagnostic, messages can take more rows
+    at_diagnostic: usize,
+    /// shown diagnostic of every rendered row
+    rendered: Vec<usize>,
+    footer: String,
+    rect: Option<Rect>,
+    updated: bool,
+}
+
+impl DiagnosticsPopup {
+    pub fn boxed(clients: Vec<LSPClient>) -> Box<dyn PopupInterface> {
+        let mut popup = Self::new(clients);
+        popup.refresh();
+        Box::new(popup)
+    }
+
+    fn new(clients: Vec<LSPClient>) -> Self {
+        Self {
+            versions: vec![None; clients.len()],
+            clients,
+            listed: Vec::new(),
+            filter: None,
+            shown: Vec::new(),
+            selected: 0,
+            at_diagnostic: 0,
+            rendered: Vec::new(),
+            footer: String::new(),
+            rect: None,
+            updated: true,
+        }
+    }
+
+    /// lists the diagnostics again if any server published since the last time
+    fn refresh(&mut self) {
+        let versions = self.clients.iter().map(LSPClient::diagnostics_version).collect::<Vec<_>>();
+        // locked by the server (publishing) - checked again next render
+        if versions.iter().any(Option::is_none) || versions == self.versions {
+            return;
+        }
+        self.versions = versions;
+        let diagnostics = self.clients.iter().flat_map(|client| client.project_diagnostics()).collect();
+        self.set_diagnostics(diagnostics);
+    }
+
+    /// the selection stays on the same diagnostic if it is still published
+    fn set_diagnostics(&mut self, mut diagnostics: Vec<ProjectDiagnostic>) {
+        let selected = self.selected_diagnostic().map(|diagnostic| (diagnostic.path.clone(), diagnostic.position));
+        diagnostics.sort_by(|lhs, rhs| {
+            (lhs.severity, &lhs.path, lhs.position.line, lhs.position.char).cmp(&(
+                rhs.severity,
+                &rhs.path,
+                rhs.position.line,
+                rhs.position.char,
+            ))
+        });
+        let base = std::env::current_dir().ok();
+        self.listed = diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                let path = &diagnostic.path;
+                let display = base.as_ref().and_then(|base| path.strip_prefix(base).ok()).unwrap_or(path);
+                let location = format!("{}:{}", display.display(), diagnostic.position.line + 1);
+                Listed { diagnostic, location }
+            })
+            .collect();
+        self.footer = footer(&self.listed);
+        self.apply_filter();
+        if let Some((path, position)) = selected {
+            if let Some(idx) = self.shown.iter().position(|idx| {
+                let diagnostic = &self.listed[*idx].diagnostic;
+                diagnostic.path == path && diagnostic.position == position
+            }) {
+                self.selected = idx;
+            }
+        }
+        self.updated = true;
+    }
+
+    fn apply_filter(&mut self) {
+        self.shown = (0..self.listed.len())
+            .filter(|idx| self.filter.map_or(true, |severity| self.listed[*idx].diagnostic.severity == severity))
+            .collect();
+        self.selected = self.selected.min(self.shown.len().saturating_sub(1));
+        self.at_diagnostic = self.at_diagnostic.min(self.selected);
+    }
+
+    /// same severity again shows all
+    fn toggle_filter(&mut self, severity: DiagnosticSeverity) {
+        self.filter = match self.filter == Some(severity) {
+            true => None,
+            false => Some(severity),
+        };
+        self.selected = 0;
+        self.at_diagnostic = 0;
+        self.apply_filter();
+    }
+
+    fn selected_diagnostic(&self) -> Option<&ProjectDiagnostic> {
+        self.shown.get(self.selected).map(|idx| &self.listed[*idx].diagnostic)
+    }
+
+    fn open(&self, shown_idx: usize) -> PopupMessage {
+        match self.shown.get(shown_idx).map(|idx| &self.listed[*idx].diagnostic) {
+            Some(diagnostic) => IdiomEvent::OpenAtPosition(diagnostic.path.clone(), diagnostic.position).into(),
+            None => PopupMessage::None,
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.filter.and_then(severity_name) {
+            Some(name) => format!(" Diagnostics: {name} only (1-4 filter) "),
+            None => String::from(" Diagnostics (1-4 filter) "),
+        }
+    }
+
+    /// scrolls by whole diagnostics to keep all rows of the selected one in view
+    fn update_at_diagnostic(&mut self, width: usize, height: usize) {
+        if self.selected < self.at_diagnostic {
+            self.at_diagnostic = self.selected;
+            return;
+        }
+        let row_count = |idx: &usize| {
+            let listed = &self.listed[self.shown[*idx]];
+            message_rows(&listed.diagnostic.message, &listed.location, width).len()
+        };
+        let mut rows = (self.at_diagnostic..=self.selected).map(|idx| row_count(&idx)).sum::<usize>();
+        while rows > height && self.at_diagnostic < self.selected {
+            rows -= row_count(&self.at_diagnostic);
+            self.at_diagnostic += 1;
+        }
+    }
+
+    fn prev(&mut self) {
+        match self.selected {
+            0 => self.selected = self.shown.len().saturating_sub(1),
+            _ => self.selected -= 1,
+        }
+    }
+
+    fn next(&mut self) {
+        self.selected += 1;
+        if self.selected >= self.shown.len() {
+            self.selected = 0;
+        }
+    }
+}
+
+impl PopupInterface for DiagnosticsPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.center(20, 120);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(&self.title(), gs.backend());
+        rect.border_title_bot(&self.footer, gs.backend());
+        self.rendered.clear();
+        let backend = gs.backend();
+        let mut lines = rect.into_iter();
+        if self.shown.is_empty() {
+            if let Some(line) = lines.next() {
+                line.render_styled("No diagnostics", Style::fg(color::dark_grey()), backend);
+            }
+            lines.clear_to_end(backend);
+            return;
+        }
+        self.update_at_diagnostic(rect.width, rect.height as usize);
+        let location_style = Style::fg(color::blue());
+        let highlight = Style::reversed();
+        'diagnostics: for (shown_idx, idx) in self.shown.iter().enumerate().skip(self.at_diagnostic) {
+            let Listed { diagnostic, location } = &self.listed[*idx];
+            let is_selected = shown_idx == self.selected;
+            let mut icon_style = severity_style(diagnostic.severity);
+            let mut location_style = location_style;
+            if is_selected {
+                backend.set_style(highlight);
+                icon_style.update(highlight);
+                location_style.update(highlight);
+            }
+            for (row_idx, text) in message_rows(&diagnostic.message, location, rect.width).into_iter().enumerate() {
+                let Some(line) = lines.next() else {
+                    if is_selected {
+                        backend.reset_style();
+                    }
+                    break 'diagnostics;
+                };
+                self.rendered.push(shown_idx);
+                let mut builder = line.unsafe_builder(backend);
+                if row_idx == 0 {
+                    builder.push_styled(severity_icon(diagnostic.severity), icon_style);
+                    builder.push(" ");
+                    builder.push_styled(location, location_style);
+                    builder.push(" ");
+                } else {
+                    builder.push(WRAP_INDENT);
+                }
+                builder.push(text);
+            }
+            if is_selected {
+                backend.reset_style();
+            }
+        }
+        lines.clear_to_end(backend);
+    }
+
+    fn fast_render(&mut self, gs: &mut GlobalState) {
+        self.refresh();
+        if self.collect_update_status() {
+            self.render(gs);
+        }
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Enter => return self.open(self.selected),
+            KeyCode::Up => self.prev(),
+            KeyCode::Down => self.next(),
+            KeyCode::Char('1') => self.toggle_filter(DiagnosticSeverity::ERROR),
+            KeyCode::Char('2') => self.toggle_filter(DiagnosticSeverity::WARNING),
+            KeyCode::Char('3') => self.toggle_filter(DiagnosticSeverity::INFORMATION),
+            KeyCode::Char('4') => self.toggle_filter(DiagnosticSeverity::HINT),
+            _ => return PopupMessage::None,
+        }
+        self.updated = true;
+        PopupMessage::None
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.prev();
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.next();
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) => match self.rendered.get(pos.line) {
+                Some(shown_idx) => self.open(*shown_idx),
+                None => PopupMessage::None,
+            },
+            None => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+fn severity_icon(severity: DiagnosticSeverity) -> &'static str {
+    match severity {
+        DiagnosticSeverity::ERROR => "✗",
+        DiagnosticSeverity::WARNING => "!",
+        DiagnosticSeverity::HINT => "·",
+        _ => "i",
+    }
+}
+
+fn severity_style(severity: DiagnosticSeverity) -> Style {
+    Style::fg(severity_color(Some(severity)))
+}
+
+fn severity_name(severity: DiagnosticSeverity) -> Option<&'static str> {
+    match severity {
+        DiagnosticSeverity::ERROR => Some("errors"),
+        DiagnosticSeverity::WARNING => Some("warnings"),
+        DiagnosticSeverity::INFORMATION => Some("info"),
+        DiagnosticSeverity::HINT => Some("hints"),
+        _ => None,
+    }
+}
+
+/// counts of all listed diagnostics (filter ignored), info and hints only if there are any
+fn footer(listed: &[Listed]) -> String {
+    let count = |severity| listed.iter().filter(|listed| listed.diagnostic.severity == severity).count();
+    let plural = |count: usize, name: &str| match count {
+        1 => format!("1 {name}"),
+        _ => format!("{count} {name}s"),
+    };
+    let mut footer = format!(
+        " {}, {}",
+        plural(count(DiagnosticSeverity::ERROR), "error"),
+        plural(count(DiagnosticSeverity::WARNING), "warning")
+    );
+    let info = count(DiagnosticSeverity::INFORMATION);
+    if info != 0 {
+        footer.push_str(&format!(", {info} info"));
+    }
+    let hints = count(DiagnosticSeverity::HINT);
+    if hints != 0 {
+        footer.push_str(&format!(", {}", plural(hints, "hint")));
+    }
+    footer.push(' ');
+    footer
+}
+
+/// message split into rows - the first one starts after the icon and location, the rest after the wrap indent
+/// always at least one row (the location)
+fn message_rows<'a>(message: &'a str, location: &str, width: usize) -> Vec<&'a str> {
+    let first_width = width.saturating_sub(location.width() + 3);
+    let wrap_width = width.saturating_sub(WRAP_INDENT.len());
+    let mut rows = Vec::new();
+    let mut max_width = match first_width < MIN_MESSAGE_WIDTH {
+        true => {
+            rows.push("");
+            wrap_width
+        }
+        false => first_width,
+    };
+    for line in message.lines() {
+        let mut text = line.trim_end();
+        loop {
+            let part = text.truncate_width(max_width).1;
+            if part.is_empty() && !text.is_empty() {
+                // not even a single char fits
+                break;
+            }
+            rows.push(part);
+            text = &text[part.len()..];
+            max_width = wrap_width;
+            if text.is_empty() {
+                break;
+            }
+        }
+    }
+    if rows.is_empty() {
+        rows.push("");
+    }
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::{message_rows, DiagnosticsPopup};
+    use crate::global_state::{IdiomEvent, PopupMessage};
+    use crate::lsp::ProjectDiagnostic;
+    use crate::popups::PopupInterface;
+    use crate::workspace::CursorPosition;
+    use crossterm::event::{KeyCode, KeyEvent};
+    use lsp_types::DiagnosticSeverity;
+    use std::path::PathBuf;
+
+    fn diagnostic(path: &str, line: usize, severity: DiagnosticSeverity, message: &str) -> ProjectDiagnostic {
+        ProjectDiagnostic {
+            path: PathBuf::from(path),
+            position: CursorPosition { line, char: 4 },
+            severity,
+            message: message.to_owned(),
+        }
+    }
+
+    fn listed(popup: &DiagnosticsPopup) -> Vec<String> {
+        popup.shown.iter().map(|idx| popup.listed[*idx].location.to_owned()).collect()
+    }
+
+    #[test]
+    fn sorted_filtered_and_refreshed() {
+        let mut popup = DiagnosticsPopup::new(Vec::new());
+        popup.set_diagnostics(vec![
+            diagnostic("/project/src/b.rs", 3, DiagnosticSeverity::WARNING, "unused"),
+            diagnostic("/project/src/b.rs", 1, DiagnosticSeverity::ERROR, "mismatched types"),
+            diagnostic("/project/src/a.rs", 7, DiagnosticSeverity::WARNING, "unused"),
+            diagnostic("/project/src/a.rs", 2, DiagnosticSeverity::HINT, "consider"),
+        ]);
+        assert_eq!(
+            listed(&popup),
+            [
+                "/project/src/b.rs:2",
+                "/project/src/a.rs:8",
+                "/project/src/b.rs:4",
+                "/project/src/a.rs:3"
+            ]
+        );
+        assert_eq!(popup.footer, " 1 error, 2 warnings, 1 hint ");
+        assert_eq!(popup.title(), " Diagnostics (1-4 filter) ");
+        popup.key_map(&KeyEvent::from(KeyCode::Char('2')), &mut Default::default());
+        assert_eq!(listed(&popup), ["/project/src/a.rs:8", "/project/src/b.rs:4"]);
+        assert_eq!(popup.title(), " Diagnostics: warnings only (1-4 filter) ");
+        popup.key_map(&KeyEvent::from(KeyCode::Down), &mut Default::default());
+        match popup.key_map(&KeyEvent::from(KeyCode::Enter), &mut Default::default()) {
+            PopupMessage::Event(IdiomEvent::OpenAtPosition(path, position)) => {
+                assert_eq!(path, PathBuf::from("/project/src/b.rs"));
+                assert_eq!(position, CursorPosition { line: 3, char: 4 });
+            }
+            _ => panic!("Expected open event"),
+        }
+        // new publish - the selection follows the diagnostic
+        popup.set_diagnostics(vec![
+            diagnostic("/project/src/b.rs", 3, DiagnosticSeverity::WARNING, "unused"),
+            diagnostic("/project/src/a.rs", 7, DiagnosticSeverity::WARNING, "unused"),
+            diagnostic("/project/src/a.rs", 1, DiagnosticSeverity::WARNING, "unused"),
+        ]);
+        assert_eq!(listed(&popup), ["/project/src/a.rs:2", "/project/src/a.rs:8", "/project/src/b.rs:4"]);
+        assert_eq!(popup.selected, 2);
+        assert_eq!(popup.footer, " 0 errors, 3 warnings ");
+        // same key again - all shown
+        popup.key_map(&KeyEvent::from(KeyCode::Char('2')), &mut Default::default());
+        popup.key_map(&KeyEvent::from(KeyCode::Char('1')), &mut Default::default());
+        assert!(popup.shown.is_empty());
+        assert!(matches!(popup.open(popup.selected), PopupMessage::None));
+    }
+
+    #[test]
+    fn wrapped_messages() {
+        let location = "src/main.rs:10";
+        assert_eq!(message_rows("short", location, 60), ["short"]);
+        assert_eq!(message_rows("", location, 60), [""]);
+        let message = "expected `usize`, found `&str`\nhelp: consider borrowing here";
+        assert_eq!(
+            message_rows(message, location, 40),
+            ["expected `usize`, found", " `&str`", "help: consider borrowing here"]
+        );
+        // location leaves no room for the message
+        assert_eq!(message_rows("unused", location, 30), ["", "unused"]);
+    }
+}
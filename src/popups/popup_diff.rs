@@ -0,0 +1,93 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{
+        backend::{color, Style},
+        state::State,
+    },
+    workspace::editor::LineDiff,
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+
+const HINT: &str = " Reload (L) | Keep mine (S) | Close (Esc) ";
+
+/// Differences between the editor and the file on disk, "-" lines are lost on reload
+/// reload rebases the editor, keep mine saves it - closing leaves both untouched
+pub struct DiskDiffPopup {
+    title: String,
+    lines: Vec<(String, Style)>,
+    updated: bool,
+    state: State,
+}
+
+impl DiskDiffPopup {
+    pub fn boxed(diff: Vec<LineDiff>) -> Box<dyn PopupInterface> {
+        let removed = diff.iter().filter(|line| matches!(line, LineDiff::Removed(..))).count();
+        let added = diff.iter().filter(|line| matches!(line, LineDiff::Added(..))).count();
+        let first_change = diff.iter().position(|line| !matches!(line, LineDiff::Same(..))).unwrap_or_default();
+        let lines = diff
+            .into_iter()
+            .map(|line| match line {
+                LineDiff::Same(text) => (format!("  {text}"), Style::default()),
+                LineDiff::Removed(text) => (format!("- {text}"), Style::fg(color::red())),
+                LineDiff::Added(text) => (format!("+ {text}"), Style::fg(color::green())),
+            })
+            .collect::<Vec<_>>();
+        let mut state = State::new();
+        state.select(first_change, lines.len());
+        Box::new(Self { title: format!(" Editor -> disk: -{removed} +{added} "), lines, updated: true, state })
+    }
+
+    fn scroll(&mut self, down: bool) {
+        match down {
+            true => self.state.next(self.lines.len()),
+            false => self.state.prev(self.lines.len()),
+        }
+        self.updated = true;
+    }
+}
+
+impl PopupInterface for DiskDiffPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.center(gs.screen_rect.height.saturating_sub(4), 120);
+        rect.bordered();
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(&self.title, gs.backend());
+        rect.border_title_bot(HINT, gs.backend());
+        let options = self.lines.iter().map(|(text, style)| (text.as_str(), *style));
+        self.state.render_list_styled(options, &rect, gs.backend());
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, _clipboard: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Char('l' | 'L') => IdiomEvent::Rebase.into(),
+            KeyCode::Char('s' | 'S') => IdiomEvent::Save.into(),
+            KeyCode::Up => {
+                self.scroll(false);
+                PopupMessage::None
+            }
+            KeyCode::Down => {
+                self.scroll(true);
+                PopupMessage::None
+            }
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        match event.kind {
+            MouseEventKind::ScrollUp => self.scroll(false),
+            MouseEventKind::ScrollDown => self.scroll(true),
+            _ => (),
+        }
+        PopupMessage::None
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
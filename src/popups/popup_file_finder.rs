@@ -0,0 +1,244 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{
+        backend::{color, BackendProtocol, Style},
+        layout::{IterLines, Rect},
+        state::State,
+        TextField,
+    },
+    tree::{
+        fuzzy::{rank_paths, FuzzyMatch},
+        IndexSlot,
+    },
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use std::path::PathBuf;
+
+const TITLE: &str = " Find file ";
+const MAX_MATCHES: usize = 100;
+
+/// Fuzzy search over all files in the project (ignored files are skipped), the best matches are shown
+/// file list is gathered in the background by the tree and polled on render
+pub struct FileFinderPopup {
+    pattern: TextField<bool>,
+    slot: IndexSlot,
+    /// path with display relative to the project, empty until the walk is finished
+    files: Option<Vec<(PathBuf, String)>>,
+    /// indexes of the files with the matched chars in the display
+    found: Vec<FuzzyMatch>,
+    updated: bool,
+    rect: Option<Rect>,
+    state: State,
+}
+
+impl FileFinderPopup {
+    pub fn boxed(slot: IndexSlot) -> Box<dyn PopupInterface> {
+        Box::new(Self::new(slot))
+    }
+
+    fn new(slot: IndexSlot) -> Self {
+        Self {
+            pattern: TextField::new(String::new(), Some(true)),
+            slot,
+            files: None,
+            found: Vec::new(),
+            updated: true,
+            rect: None,
+            state: State::new(),
+        }
+    }
+
+    fn poll_files(&mut self) {
+        if self.files.is_some() {
+            return;
+        }
+        let Some(paths) = self.slot.try_lock().ok().and_then(|slot| slot.clone()) else {
+            return;
+        };
+        let base = std::env::current_dir().ok();
+        let files = paths
+            .iter()
+            .map(|path| {
+                let display = base.as_ref().and_then(|base| path.strip_prefix(base).ok()).unwrap_or(path);
+                (path.clone(), display.display().to_string())
+            })
+            .collect();
+        self.files = Some(files);
+        self.filter();
+        self.updated = true;
+    }
+
+    fn filter(&mut self) {
+        let Some(files) = self.files.as_ref() else {
+            return;
+        };
+        self.state.select(0, 1);
+        let displays = files.iter().map(|(_, display)| display.as_str());
+        self.found = rank_paths(displays, &self.pattern.text, MAX_MATCHES);
+    }
+
+    fn open(&self, found_idx: usize) -> PopupMessage {
+        let Some(files) = self.files.as_ref() else {
+            return PopupMessage::None;
+        };
+        match self.found.get(found_idx) {
+            Some(found) => IdiomEvent::OpenAtLine(files[found.idx].0.clone(), 0).into(),
+            None => PopupMessage::None,
+        }
+    }
+}
+
+impl PopupInterface for FileFinderPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.top(15).vcenter(100);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(TITLE, gs.backend());
+        match rect.next_line() {
+            Some(line) => self.pattern.widget(line, gs.backend()),
+            None => return,
+        }
+        let Some(files) = self.files.as_ref() else {
+            self.state.render_list(["Indexing files ..."].into_iter(), rect, gs.backend());
+            return;
+        };
+        if self.found.is_empty() {
+            self.state.render_list(["No files found!"].into_iter(), rect, gs.backend());
+            return;
+        }
+        self.state.update_at_line(rect.height as usize);
+        let backend = gs.backend();
+        let mut lines = rect.into_iter();
+        for (idx, found) in self.found.iter().enumerate().skip(self.state.at_line) {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let mut matched_style = Style::fg(color::yellow());
+            matched_style.add_bold();
+            let is_selected = idx == self.state.selected;
+            if is_selected {
+                backend.set_style(self.state.highlight);
+                matched_style.update(self.state.highlight);
+            }
+            let mut builder = line.unsafe_builder(backend);
+            let mut matched = found.matched.iter().peekable();
+            for (char_idx, ch) in files[found.idx].1.chars().enumerate() {
+                let mut buffer = [0; 4];
+                let text = ch.encode_utf8(&mut buffer);
+                let has_space = match matched.next_if_eq(&&char_idx) {
+                    Some(..) => builder.push_styled(text, matched_style),
+                    None => builder.push(text),
+                };
+                if !has_space {
+                    break;
+                }
+            }
+            drop(builder);
+            if is_selected {
+                backend.reset_style();
+            }
+        }
+        lines.clear_to_end(backend);
+    }
+
+    fn fast_render(&mut self, gs: &mut GlobalState) {
+        self.poll_files();
+        if self.collect_update_status() {
+            self.render(gs);
+        }
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, clipboard: &mut Clipboard) -> PopupMessage {
+        if let Some(updated) = self.pattern.map(key, clipboard) {
+            if updated {
+                self.filter();
+            }
+            return PopupMessage::None;
+        }
+        match key.code {
+            KeyCode::Enter => self.open(self.state.selected),
+            KeyCode::Up => {
+                self.state.prev(self.found.len());
+                PopupMessage::None
+            }
+            KeyCode::Down => {
+                self.state.next(self.found.len());
+                PopupMessage::None
+            }
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.found.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.found.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) if pos.line > 0 => self.open(self.state.at_line + pos.line - 1),
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FileFinderPopup;
+    use crate::global_state::{Clipboard, IdiomEvent, PopupMessage};
+    use crate::popups::PopupInterface;
+    use crate::tree::IndexSlot;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::{path::PathBuf, sync::Arc};
+
+    #[test]
+    fn find_and_open() {
+        let slot = IndexSlot::default();
+        let mut popup = FileFinderPopup::new(Arc::clone(&slot));
+        let mut clipboard = Clipboard::default();
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        // nothing to open until the files are indexed
+        assert!(matches!(popup.key_map(&enter, &mut clipboard), PopupMessage::None));
+        let files = [
+            "model/lib.rs",
+            "src/popups/popup_recent.rs",
+            "src/tree/mod.rs",
+            "src/mod.rs",
+        ];
+        slot.lock().unwrap().replace(Arc::new(files.into_iter().map(PathBuf::from).collect()));
+        popup.poll_files();
+        assert_eq!(popup.found.len(), 4);
+        for ch in "mod".chars() {
+            popup.key_map(&KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE), &mut clipboard);
+        }
+        let found =
+            popup.found.iter().map(|found| popup.files.as_ref().unwrap()[found.idx].1.as_str()).collect::<Vec<_>>();
+        assert_eq!(found, ["src/mod.rs", "src/tree/mod.rs", "model/lib.rs"]);
+        match popup.key_map(&enter, &mut clipboard) {
+            PopupMessage::Event(IdiomEvent::OpenAtLine(path, line)) => {
+                assert_eq!(path, PathBuf::from("src/mod.rs"));
+                assert_eq!(line, 0);
+            }
+            _ => panic!("Expected open event"),
+        }
+    }
+}
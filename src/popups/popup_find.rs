@@ -33,10 +33,21 @@ impl GoToLinePopup {
         if self.line_idx.is_empty() {
             return PopupMessage::None;
         }
-        match self.line_idx.parse::<usize>() {
-            Ok(idx) => PopupMessage::Event(IdiomEvent::GoToLine { line: idx.saturating_sub(1), clear_popup: false }),
-            _ => PopupMessage::None,
-        }
+        // line or line:col (1 based)
+        let (line, col) = match self.line_idx.split_once(':') {
+            Some((line, "")) => (line, None),
+            Some((line, col)) => (line, Some(col)),
+            None => (self.line_idx.as_str(), None),
+        };
+        let Ok(line) = line.parse::<usize>() else {
+            return PopupMessage::None;
+        };
+        let char = match col.map(str::parse::<usize>) {
+            Some(Ok(col)) => Some(col.saturating_sub(1)),
+            Some(Err(..)) => return PopupMessage::None,
+            None => None,
+        };
+        PopupMessage::Event(IdiomEvent::GoToLine { line: line.saturating_sub(1), char, clear_popup: false })
     }
 }
 
@@ -47,6 +58,11 @@ impl PopupInterface for GoToLinePopup {
                 self.line_idx.push(ch);
                 self.parse()
             }
+            KeyCode::Char(':') if !self.line_idx.is_empty() && !self.line_idx.contains(':') => {
+                self.line_idx.push(':');
+                self.mark_as_updated();
+                PopupMessage::None
+            }
             KeyCode::Backspace if self.line_idx.pop().is_some() => self.parse(),
             KeyCode::Backspace => PopupMessage::None,
             _ => PopupMessage::Clear,
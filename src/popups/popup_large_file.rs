@@ -0,0 +1,278 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, PopupMessage},
+    render::{
+        backend::{color, Style},
+        layout::IterLines,
+    },
+    workspace::editor::build_display,
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// bytes read at once while indexing the lines
+const INDEX_CHUNK: usize = 1024 * 1024;
+/// bytes of a line read for rendering - minified files can have lines of many MB
+const MAX_LINE_BYTES: usize = 4096;
+const SCROLL_LINES: usize = 3;
+
+/// line starts with the length of the file, filled once the file is indexed in the background
+type LinesSlot = Arc<Mutex<Option<std::io::Result<(Vec<u64>, u64)>>>>;
+
+/// Read-only view of files over the editor limit, only the byte offsets of the lines are kept in memory
+/// rendered lines are read from the file, digits followed by Enter jump to line
+/// the lines are indexed in the background - nothing is shown until the index is done
+pub struct LargeFilePopup {
+    file: File,
+    /// None once the index is taken
+    index: Option<LinesSlot>,
+    /// byte offset where each line starts
+    line_starts: Vec<u64>,
+    file_len: u64,
+    at_line: usize,
+    /// rows rendered last time - used for paging
+    rows: usize,
+    jump: String,
+    name: String,
+    title: String,
+    updated: bool,
+}
+
+impl LargeFilePopup {
+    pub fn boxed(path: PathBuf) -> std::io::Result<Box<dyn PopupInterface>> {
+        Ok(Box::new(Self::new(path)?))
+    }
+
+    fn new(path: PathBuf) -> std::io::Result<Self> {
+        let file = File::open(&path)?;
+        let index = LinesSlot::default();
+        let slot = Arc::clone(&index);
+        let index_path = path.clone();
+        tokio::task::spawn_blocking(move || {
+            let lines = File::open(index_path).and_then(|mut file| index_lines(&mut file));
+            if let Ok(mut slot) = slot.lock() {
+                slot.replace(lines);
+            }
+        });
+        let name = build_display(&path);
+        let title = format!(" {name} (read-only, indexing lines ...) ");
+        Ok(Self {
+            file,
+            index: Some(index),
+            line_starts: Vec::new(),
+            file_len: 0,
+            at_line: 0,
+            rows: 1,
+            jump: String::new(),
+            name,
+            title,
+            updated: true,
+        })
+    }
+
+    /// takes the lines once indexed, errors are shown in the title
+    fn poll_index(&mut self) {
+        let Some(result) = self.index.as_ref().and_then(|slot| slot.try_lock().ok()?.take()) else {
+            return;
+        };
+        self.index = None;
+        match result {
+            Ok((line_starts, file_len)) => {
+                self.title = format!(" {} (read-only, {} lines) ", self.name, line_starts.len());
+                self.line_starts = line_starts;
+                self.file_len = file_len;
+            }
+            Err(error) => self.title = format!(" {} (failed to index lines: {error}) ", self.name),
+        }
+        self.updated = true;
+    }
+
+    /// text of the line with tabs and control chars replaced, long lines are cut
+    fn read_line(&mut self, idx: usize) -> std::io::Result<String> {
+        let Some(start) = self.line_starts.get(idx).copied() else {
+            return Ok(String::new());
+        };
+        let end = self.line_starts.get(idx + 1).copied().unwrap_or(self.file_len);
+        let len = std::cmp::min((end - start) as usize, MAX_LINE_BYTES);
+        let mut buffer = vec![0; len];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut buffer)?;
+        let text = String::from_utf8_lossy(&buffer);
+        let mut line = String::with_capacity(text.len());
+        for ch in text.trim_end_matches(['\n', '\r']).chars() {
+            match ch {
+                '\t' => line.push_str("    "),
+                ch if ch.is_control() => line.push(' '),
+                ch => line.push(ch),
+            }
+        }
+        Ok(line)
+    }
+
+    fn scroll_to(&mut self, line: usize) {
+        let last_top = self.line_starts.len().saturating_sub(self.rows);
+        self.at_line = std::cmp::min(line, last_top);
+    }
+
+    fn jump(&mut self) {
+        if let Ok(line) = std::mem::take(&mut self.jump).parse::<usize>() {
+            self.scroll_to(line.saturating_sub(1));
+        }
+    }
+}
+
+impl PopupInterface for LargeFilePopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let screen = gs.screen_rect;
+        let mut rect = screen.center(screen.height.saturating_sub(2), screen.width.saturating_sub(4));
+        rect.bordered();
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(&self.title, gs.backend());
+        if !self.jump.is_empty() {
+            rect.border_title_bot(&format!(" Go to line: {} ", self.jump), gs.backend());
+        }
+        self.rows = std::cmp::max(rect.height as usize, 1);
+        self.scroll_to(self.at_line);
+        let number_width = self.line_starts.len().to_string().len();
+        let number_style = Style::fg(color::dark_grey());
+        let mut lines = rect.into_iter();
+        for idx in self.at_line..self.line_starts.len() {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let text = self.read_line(idx).unwrap_or_else(|error| format!("<< {error} >>"));
+            let mut builder = line.unsafe_builder(gs.backend());
+            builder.push_styled(&format!("{:>number_width$} ", idx + 1), number_style);
+            builder.push(&text);
+        }
+        lines.clear_to_end(gs.backend());
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Char(ch) if ch.is_ascii_digit() => self.jump.push(ch),
+            KeyCode::Backspace => {
+                self.jump.pop();
+            }
+            KeyCode::Enter => self.jump(),
+            KeyCode::Up => self.scroll_to(self.at_line.saturating_sub(1)),
+            KeyCode::Down => self.scroll_to(self.at_line + 1),
+            KeyCode::PageUp => self.scroll_to(self.at_line.saturating_sub(self.rows)),
+            KeyCode::PageDown => self.scroll_to(self.at_line + self.rows),
+            KeyCode::Home => self.scroll_to(0),
+            KeyCode::End => self.scroll_to(self.line_starts.len()),
+            _ => return PopupMessage::None,
+        }
+        self.updated = true;
+        PopupMessage::None
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        match event.kind {
+            MouseEventKind::ScrollUp => self.scroll_to(self.at_line.saturating_sub(SCROLL_LINES)),
+            MouseEventKind::ScrollDown => self.scroll_to(self.at_line + SCROLL_LINES),
+            _ => return PopupMessage::None,
+        }
+        self.updated = true;
+        PopupMessage::None
+    }
+
+    fn fast_render(&mut self, gs: &mut GlobalState) {
+        self.poll_index();
+        if self.collect_update_status() {
+            self.render(gs);
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+/// byte offsets of line starts with the length of the file, empty files have a single line
+fn index_lines(file: &mut File) -> std::io::Result<(Vec<u64>, u64)> {
+    let mut line_starts = vec![0];
+    let mut buffer = vec![0; INDEX_CHUNK];
+    let mut offset = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for (idx, byte) in buffer[..read].iter().enumerate() {
+            if *byte == b'\n' {
+                line_starts.push(offset + idx as u64 + 1);
+            }
+        }
+        offset += read as u64;
+    }
+    // new line at the end of the file does not start a line
+    if line_starts.len() > 1 && line_starts.last() == Some(&offset) {
+        line_starts.pop();
+    }
+    Ok((line_starts, offset))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LargeFilePopup, MAX_LINE_BYTES};
+    use crate::global_state::Clipboard;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::path::PathBuf;
+
+    fn press(popup: &mut LargeFilePopup, code: KeyCode) {
+        let mut clipboard = Clipboard::default();
+        super::PopupInterface::key_map(popup, &KeyEvent::new(code, KeyModifiers::NONE), &mut clipboard);
+    }
+
+    fn indexed(path: &std::path::Path) -> LargeFilePopup {
+        let mut popup = LargeFilePopup::new(PathBuf::from(path)).unwrap();
+        while popup.index.is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            popup.poll_index();
+        }
+        popup
+    }
+
+    #[test]
+    fn lines_are_read_from_the_file() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let path = std::env::temp_dir().join(format!("idiom_large_file_{}.txt", std::process::id()));
+        let mut text = (1..=100).map(|idx| format!("line {idx}\r\n")).collect::<String>();
+        text.push_str("\tlast ");
+        text.push_str(&"x".repeat(MAX_LINE_BYTES * 2));
+        text.push('\n');
+        std::fs::write(&path, text).unwrap();
+        let mut popup = indexed(&path);
+        assert!(popup.title.ends_with("(read-only, 101 lines) "));
+        assert_eq!(popup.line_starts.len(), 101);
+        assert_eq!(popup.read_line(0).unwrap(), "line 1");
+        assert_eq!(popup.read_line(99).unwrap(), "line 100");
+        let last = popup.read_line(100).unwrap();
+        assert!(last.starts_with("    last xxx"));
+        assert_eq!(last.len(), MAX_LINE_BYTES + 3);
+        assert_eq!(popup.read_line(101).unwrap(), "");
+
+        popup.rows = 10;
+        press(&mut popup, KeyCode::End);
+        assert_eq!(popup.at_line, 91);
+        press(&mut popup, KeyCode::PageUp);
+        assert_eq!(popup.at_line, 81);
+        press(&mut popup, KeyCode::Char('4'));
+        press(&mut popup, KeyCode::Char('2'));
+        press(&mut popup, KeyCode::Enter);
+        assert_eq!(popup.at_line, 41);
+        assert!(popup.jump.is_empty());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,386 @@
+use super::PopupInterface;
+use crate::{
+    configs::FileType,
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{
+        backend::{color, BackendProtocol, Color, Style},
+        layout::{IterLines, Rect},
+        state::State,
+        TextField,
+    },
+    tree::fuzzy::fuzzy_match,
+    workspace::{line::EditorLine, CursorPosition},
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use lsp_types::{DocumentSymbol, DocumentSymbolResponse, SymbolKind};
+use regex::Regex;
+
+/// Symbol of the document outline - nested symbols have depth of their parents + 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineSymbol {
+    name: String,
+    kind: SymbolKind,
+    depth: usize,
+    /// selection range of the symbol (name)
+    select: (CursorPosition, CursorPosition),
+}
+
+/// symbols in document order - flat symbol information is nested by containing ranges
+pub fn outline_from_response(response: DocumentSymbolResponse) -> Vec<OutlineSymbol> {
+    let mut outline = Vec::new();
+    match response {
+        DocumentSymbolResponse::Nested(symbols) => push_nested(symbols, 0, &mut outline),
+        DocumentSymbolResponse::Flat(mut symbols) => {
+            symbols.sort_by_key(|info| (info.location.range.start, std::cmp::Reverse(info.location.range.end)));
+            let mut parents: Vec<lsp_types::Position> = Vec::new();
+            for info in symbols {
+                let range = info.location.range;
+                while parents.last().is_some_and(|parent_end| *parent_end < range.end) {
+                    parents.pop();
+                }
+                outline.push(OutlineSymbol {
+                    name: info.name,
+                    kind: info.kind,
+                    depth: parents.len(),
+                    select: (range.start.into(), range.start.into()),
+                });
+                parents.push(range.end);
+            }
+        }
+    }
+    outline
+}
+
+fn push_nested(mut symbols: Vec<DocumentSymbol>, depth: usize, outline: &mut Vec<OutlineSymbol>) {
+    symbols.sort_by_key(|symbol| symbol.range.start);
+    for symbol in symbols {
+        let range = symbol.selection_range;
+        outline.push(OutlineSymbol {
+            name: symbol.name,
+            kind: symbol.kind,
+            depth,
+            select: (range.start.into(), range.end.into()),
+        });
+        if let Some(children) = symbol.children {
+            push_nested(children, depth + 1, outline);
+        }
+    }
+}
+
+/// headers of functions and types found by patterns (files without LSP) - nested by indent
+pub fn naive_outline(file_type: FileType, content: &[EditorLine]) -> Vec<OutlineSymbol> {
+    let pattern = match file_type {
+        FileType::Rust => {
+            r#"^(\s*)(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*(fn|struct|enum|trait|mod|type|union|impl)\b\s*(?:<[^{]*?>\s*)?([A-Za-z_][A-Za-z0-9_]*)"#
+        }
+        FileType::Python => r"^(\s*)(?:async\s+)?(def|class)\s+([A-Za-z_][A-Za-z0-9_]*)",
+        _ => return Vec::new(),
+    };
+    let Ok(header) = Regex::new(pattern) else {
+        return Vec::new();
+    };
+    let mut outline = Vec::new();
+    let mut parents: Vec<usize> = Vec::new();
+    for (line_idx, line) in content.iter().enumerate() {
+        let Some(captures) = header.captures(&line.content) else {
+            continue;
+        };
+        let (Some(indent), Some(keyword), Some(name)) = (captures.get(1), captures.get(2), captures.get(3)) else {
+            continue;
+        };
+        let indent = indent.as_str().chars().count();
+        while parents.last().is_some_and(|parent_indent| *parent_indent >= indent) {
+            parents.pop();
+        }
+        let kind = match keyword.as_str() {
+            "fn" | "def" if !parents.is_empty() => SymbolKind::METHOD,
+            "fn" | "def" => SymbolKind::FUNCTION,
+            "struct" | "union" => SymbolKind::STRUCT,
+            "enum" => SymbolKind::ENUM,
+            "trait" => SymbolKind::INTERFACE,
+            "mod" => SymbolKind::MODULE,
+            "type" => SymbolKind::TYPE_PARAMETER,
+            "class" => SymbolKind::CLASS,
+            _ => SymbolKind::OBJECT,
+        };
+        let name_text = match keyword.as_str() {
+            // impl blocks are named by the whole header
+            "impl" => line.content[keyword.start()..].trim_end().trim_end_matches('{').trim_end().to_owned(),
+            _ => name.as_str().to_owned(),
+        };
+        let start = CursorPosition { line: line_idx, char: line.content[..name.start()].chars().count() };
+        let end = CursorPosition { line: line_idx, char: line.content[..name.end()].chars().count() };
+        outline.push(OutlineSymbol { name: name_text, kind, depth: parents.len(), select: (start, end) });
+        parents.push(indent);
+    }
+    outline
+}
+
+fn kind_icon(kind: SymbolKind) -> (char, Color) {
+    match kind {
+        SymbolKind::FUNCTION | SymbolKind::METHOD | SymbolKind::CONSTRUCTOR => ('ƒ', color::yellow()),
+        SymbolKind::STRUCT | SymbolKind::CLASS | SymbolKind::OBJECT => ('S', color::blue()),
+        SymbolKind::ENUM => ('E', color::blue()),
+        SymbolKind::ENUM_MEMBER => ('e', color::cyan()),
+        SymbolKind::INTERFACE => ('I', color::magenta()),
+        SymbolKind::MODULE | SymbolKind::NAMESPACE | SymbolKind::PACKAGE => ('M', color::green()),
+        SymbolKind::CONSTANT => ('c', color::magenta()),
+        SymbolKind::VARIABLE | SymbolKind::FIELD | SymbolKind::PROPERTY => ('v', color::cyan()),
+        SymbolKind::TYPE_PARAMETER => ('T', color::green()),
+        _ => ('·', color::dark_grey()),
+    }
+}
+
+/// Symbols of the active editor (LSP documentSymbol or headers found by patterns) filtered by typing
+/// the selected symbol is selected in the editor
+pub struct OutlinePopup {
+    title: &'static str,
+    symbols: Vec<OutlineSymbol>,
+    pattern: TextField<bool>,
+    /// indexes of symbols matching the pattern
+    filtered: Vec<usize>,
+    state: State,
+    rect: Option<Rect>,
+    updated: bool,
+}
+
+impl OutlinePopup {
+    /// symbols outside the content (outline from before the last edits) are dropped
+    pub fn boxed(symbols: &[OutlineSymbol], content: &[EditorLine], from_lsp: bool) -> Box<dyn PopupInterface> {
+        Box::new(Self::new(symbols, content, from_lsp))
+    }
+
+    fn new(symbols: &[OutlineSymbol], content: &[EditorLine], from_lsp: bool) -> Self {
+        let symbols = symbols
+            .iter()
+            .filter(|symbol| symbol.select.1.line < content.len())
+            .cloned()
+            .map(|mut symbol| {
+                let (from, to) = &mut symbol.select;
+                from.char = from.char.min(content[from.line].char_len());
+                to.char = to.char.min(content[to.line].char_len());
+                symbol
+            })
+            .collect::<Vec<_>>();
+        let title = if from_lsp { " Outline (LSP) " } else { " Outline " };
+        Self {
+            title,
+            filtered: (0..symbols.len()).collect(),
+            symbols,
+            pattern: TextField::new(String::new(), Some(true)),
+            state: State::new(),
+            rect: None,
+            updated: true,
+        }
+    }
+
+    /// hierarchy order is kept while filtering
+    fn filter(&mut self) {
+        self.filtered = match self.pattern.text.is_empty() {
+            true => (0..self.symbols.len()).collect(),
+            false => (0..self.symbols.len())
+                .filter(|idx| fuzzy_match(&self.symbols[*idx].name, &self.pattern.text).is_some())
+                .collect(),
+        };
+        self.state.select(0, self.filtered.len());
+        self.updated = true;
+    }
+
+    fn open(&self, idx: usize) -> PopupMessage {
+        match self.filtered.get(idx).map(|symbol_idx| &self.symbols[*symbol_idx]) {
+            Some(symbol) => IdiomEvent::GoToSelect { select: symbol.select, clear_popup: true }.into(),
+            None => PopupMessage::None,
+        }
+    }
+}
+
+impl PopupInterface for OutlinePopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.center(20, 80);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(self.title, gs.backend());
+        match rect.next_line() {
+            Some(line) => self.pattern.widget(line, gs.backend()),
+            None => return,
+        }
+        if self.filtered.is_empty() {
+            let status = if self.symbols.is_empty() { "No symbols found!" } else { "No matching symbols!" };
+            self.state.render_list([status].into_iter(), rect, gs.backend());
+            return;
+        }
+        self.state.update_at_line(rect.height as usize);
+        let backend = gs.backend();
+        let mut lines = rect.into_iter();
+        for (idx, symbol_idx) in self.filtered.iter().enumerate().skip(self.state.at_line) {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let symbol = &self.symbols[*symbol_idx];
+            let (icon, icon_color) = kind_icon(symbol.kind);
+            let mut icon_style = Style::fg(icon_color);
+            let mut line_style = Style::fg(color::dark_grey());
+            let is_selected = idx == self.state.selected;
+            if is_selected {
+                backend.set_style(self.state.highlight);
+                icon_style.update(self.state.highlight);
+                line_style.update(self.state.highlight);
+            }
+            let mut builder = line.unsafe_builder(backend);
+            builder.push(&"  ".repeat(symbol.depth));
+            builder.push_styled(&format!("{icon} "), icon_style);
+            builder.push(&symbol.name);
+            builder.push_styled(&format!(" :{}", symbol.select.0.line + 1), line_style);
+            drop(builder);
+            if is_selected {
+                backend.reset_style();
+            }
+        }
+        lines.clear_to_end(backend);
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, clipboard: &mut Clipboard) -> PopupMessage {
+        if let Some(updated) = self.pattern.map(key, clipboard) {
+            if updated {
+                self.filter();
+            }
+            return PopupMessage::None;
+        }
+        match key.code {
+            KeyCode::Enter => return self.open(self.state.selected),
+            KeyCode::Up => self.state.prev(self.filtered.len()),
+            KeyCode::Down => self.state.next(self.filtered.len()),
+            _ => return PopupMessage::None,
+        }
+        self.updated = true;
+        PopupMessage::None
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.filtered.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.filtered.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) if pos.line > 0 => self.open(self.state.at_line + pos.line - 1),
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{naive_outline, outline_from_response, OutlinePopup};
+    use crate::{
+        configs::FileType,
+        global_state::{IdiomEvent, PopupMessage},
+        workspace::{line::EditorLine, CursorPosition},
+    };
+    use lsp_types::{DocumentSymbolResponse, SymbolKind};
+    use serde_json::{from_value, json};
+
+    fn content(text: &str) -> Vec<EditorLine> {
+        text.lines().map(|line| EditorLine::from(line.to_owned())).collect()
+    }
+
+    fn range(start: u32, end: u32) -> serde_json::Value {
+        json!({"start": {"line": start, "character": 4}, "end": {"line": end, "character": 8}})
+    }
+
+    #[test]
+    fn outline_of_response() {
+        let nested: DocumentSymbolResponse = from_value(json!([
+            {"name": "main", "kind": 12, "range": range(10, 12), "selectionRange": range(10, 10)},
+            {
+                "name": "Tree", "kind": 23, "range": range(0, 8), "selectionRange": range(0, 0),
+                "children": [{"name": "new", "kind": 6, "range": range(2, 4), "selectionRange": range(2, 2)}]
+            }
+        ]))
+        .unwrap();
+        let outline = outline_from_response(nested);
+        let names = outline.iter().map(|symbol| (symbol.name.as_str(), symbol.depth)).collect::<Vec<_>>();
+        assert_eq!(names, [("Tree", 0), ("new", 1), ("main", 0)]);
+        assert_eq!(outline[1].select, (CursorPosition { line: 2, char: 4 }, CursorPosition { line: 2, char: 8 }));
+        // flat information is nested by the ranges
+        let flat: DocumentSymbolResponse = from_value(json!([
+            {"name": "new", "kind": 6, "location": {"uri": "file:///main.rs", "range": range(2, 4)}},
+            {"name": "Tree", "kind": 23, "location": {"uri": "file:///main.rs", "range": range(0, 8)}},
+            {"name": "main", "kind": 12, "location": {"uri": "file:///main.rs", "range": range(10, 12)}}
+        ]))
+        .unwrap();
+        assert_eq!(outline_from_response(flat).iter().map(|s| s.depth).collect::<Vec<_>>(), [0, 1, 0]);
+    }
+
+    #[test]
+    fn naive_headers() {
+        let rust = content(
+            "pub struct Tree {
+    size: usize,
+}
+
+impl<T: Clone> Display for Tree<T> {
+    pub(crate) async fn fmt(&self) {}
+}
+
+const fn build() {}",
+        );
+        let outline = naive_outline(FileType::Rust, &rust);
+        let found = outline.iter().map(|symbol| (symbol.name.as_str(), symbol.kind, symbol.depth)).collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("Tree", SymbolKind::STRUCT, 0),
+                ("impl<T: Clone> Display for Tree<T>", SymbolKind::OBJECT, 0),
+                ("fmt", SymbolKind::METHOD, 1),
+                ("build", SymbolKind::FUNCTION, 0)
+            ]
+        );
+        assert_eq!(outline[0].select, (CursorPosition { line: 0, char: 11 }, CursorPosition { line: 0, char: 15 }));
+        let python = content("class Tree:\n    def __init__(self):\n        pass\n\nasync def main():\n    pass");
+        let outline = naive_outline(FileType::Python, &python);
+        let found = outline.iter().map(|symbol| (symbol.name.as_str(), symbol.depth)).collect::<Vec<_>>();
+        assert_eq!(found, [("Tree", 0), ("__init__", 1), ("main", 0)]);
+        assert!(naive_outline(FileType::Json, &python).is_empty());
+    }
+
+    #[test]
+    fn filtered_open() {
+        let python = content("class Tree:\n    def __init__(self):\n        pass\n\ndef main():\n    pass");
+        let mut outline = naive_outline(FileType::Python, &python);
+        // outdated symbol past the content is dropped
+        outline[2].select.0.line = 10;
+        outline[2].select.1.line = 10;
+        let mut popup = OutlinePopup::new(&outline, &python, false);
+        assert_eq!(popup.filtered, [0, 1]);
+        popup.pattern.text.push_str("init");
+        popup.filter();
+        assert_eq!(popup.filtered, [1]);
+        match popup.open(0) {
+            PopupMessage::Event(IdiomEvent::GoToSelect { select, clear_popup }) => {
+                assert_eq!(select, (CursorPosition { line: 1, char: 8 }, CursorPosition { line: 1, char: 16 }));
+                assert!(clear_popup);
+            }
+            _ => panic!("Expected go to select"),
+        }
+        assert!(matches!(popup.open(1), PopupMessage::None));
+    }
+}
@@ -0,0 +1,551 @@
+use super::PopupInterface;
+use crate::{
+    configs::SearchOptions,
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{
+        backend::{color, BackendProtocol, Style},
+        layout::{IterLines, Line, Rect, BORDERS},
+        state::State,
+        TextField,
+    },
+    tree::IndexSlot,
+    workspace::project_search::{search_file, FileMatches, LineMatcher},
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::task::JoinHandle;
+
+const TITLE: &str = " Search in project ";
+const OPTIONS_HINT: &str = "(Alt+R regex / Alt+C ignore case / Alt+W whole word / Tab replace) ";
+const REPLACE_PREFIX: &str = " Replace with:";
+/// search stops after that many matching lines
+const MAX_MATCHES: usize = 5_000;
+
+type ResultBuffer = Arc<Mutex<Vec<FileMatches>>>;
+
+enum Row {
+    File { path: PathBuf, display: String, count: usize },
+    Match { path: PathBuf, line: usize, text: String },
+}
+
+/// Text search over all files in the project (ignored, binary and big files are skipped)
+/// matches are streamed from background task and listed grouped by file
+/// Tab opens the replace field (matches are previewed replaced), Alt+A twice replaces in all listed files
+pub struct ProjectSearchPopup {
+    pattern: TextField<bool>,
+    replace: Option<TextField<bool>>,
+    on_replace: bool,
+    /// replace all was requested once, the next request is applied
+    confirm: bool,
+    matcher: Option<LineMatcher>,
+    options: SearchOptions,
+    slot: IndexSlot,
+    files: Option<Arc<Vec<PathBuf>>>,
+    buffer: ResultBuffer,
+    join_handle: Option<JoinHandle<()>>,
+    /// set to stop the running search - checked before each file
+    cancelled: Arc<AtomicBool>,
+    rows: Vec<Row>,
+    matches: usize,
+    invalid: bool,
+    updated: bool,
+    rect: Option<Rect>,
+    state: State,
+}
+
+impl ProjectSearchPopup {
+    pub fn boxed(slot: IndexSlot) -> Box<dyn PopupInterface> {
+        Box::new(Self::new(slot))
+    }
+
+    fn new(slot: IndexSlot) -> Self {
+        Self {
+            pattern: TextField::new(String::new(), Some(true)),
+            replace: None,
+            on_replace: false,
+            confirm: false,
+            matcher: None,
+            options: SearchOptions::default(),
+            slot,
+            files: None,
+            buffer: ResultBuffer::default(),
+            join_handle: None,
+            cancelled: Arc::default(),
+            rows: Vec::new(),
+            matches: 0,
+            invalid: false,
+            updated: true,
+            rect: None,
+            state: State::new(),
+        }
+    }
+
+    /// drops the running search with its results and starts new one for the current pattern
+    fn restart(&mut self) {
+        self.cancel();
+        self.rows.clear();
+        self.matches = 0;
+        self.invalid = false;
+        self.confirm = false;
+        self.matcher = None;
+        self.state.select(0, 1);
+        self.buffer = ResultBuffer::default();
+        self.updated = true;
+        let Some(files) = self.files.as_ref() else {
+            return;
+        };
+        if self.pattern.text.is_empty() {
+            return;
+        }
+        match LineMatcher::new(&self.pattern.text, self.options) {
+            Ok(matcher) => {
+                self.matcher = Some(matcher.clone());
+                self.cancelled = Arc::default();
+                let handle = spawn_search(Arc::clone(files), matcher, Arc::clone(&self.buffer), &self.cancelled);
+                self.join_handle = Some(handle);
+            }
+            Err(..) => self.invalid = true,
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.join_handle = None;
+    }
+
+    fn is_searching(&self) -> bool {
+        self.join_handle.as_ref().is_some_and(|handle| !handle.is_finished())
+    }
+
+    fn poll_files(&mut self) {
+        if self.files.is_some() {
+            return;
+        }
+        let Some(files) = self.slot.try_lock().ok().and_then(|slot| slot.clone()) else {
+            return;
+        };
+        self.files = Some(files);
+        self.restart();
+    }
+
+    fn poll_results(&mut self) {
+        let Some(found) = self.buffer.try_lock().ok().map(|mut buffer| std::mem::take(&mut *buffer)) else {
+            return;
+        };
+        if found.is_empty() {
+            return;
+        }
+        let base = std::env::current_dir().ok();
+        for FileMatches { path, lines } in found {
+            let display = base.as_ref().and_then(|base| path.strip_prefix(base).ok()).unwrap_or(&path);
+            let display = display.display().to_string();
+            self.matches += lines.len();
+            self.rows.push(Row::File { path: path.clone(), display, count: lines.len() });
+            for (line, text) in lines {
+                self.rows.push(Row::Match { path: path.clone(), line, text });
+            }
+        }
+        self.updated = true;
+    }
+
+    /// file rows open at the first match
+    fn open(&self, row_idx: usize) -> PopupMessage {
+        match self.rows.get(row_idx) {
+            Some(Row::Match { path, line, .. }) => IdiomEvent::OpenAtLine(path.clone(), *line).into(),
+            Some(Row::File { .. }) => self.open(row_idx + 1),
+            None => PopupMessage::None,
+        }
+    }
+
+    /// listed results stop at MAX_MATCHES - the rest of the project is not searched
+    fn is_capped(&self) -> bool {
+        self.matches >= MAX_MATCHES
+    }
+
+    /// first call asks for confirmation (with the count of changes), the second replaces in the listed files
+    /// or in all project files if the results are capped (the workspace finds the matching ones)
+    fn replace_all(&mut self) -> PopupMessage {
+        let Some(new_text) = self.replace.as_ref().map(|field| field.text.to_owned()) else {
+            return PopupMessage::None;
+        };
+        if self.matches == 0 || self.is_searching() {
+            return PopupMessage::None;
+        }
+        if !self.confirm {
+            self.confirm = true;
+            self.updated = true;
+            return PopupMessage::None;
+        }
+        let files = match self.files.as_ref().filter(|_| self.is_capped()) {
+            Some(files) => files.as_ref().clone(),
+            None => self
+                .rows
+                .iter()
+                .filter_map(|row| match row {
+                    Row::File { path, .. } => Some(path.clone()),
+                    Row::Match { .. } => None,
+                })
+                .collect(),
+        };
+        IdiomEvent::ReplaceInFiles { files, pattern: self.pattern.text.to_owned(), options: self.options, new_text }
+            .into()
+    }
+
+    /// lines above the results (pattern, replace and status)
+    fn header_lines(&self) -> usize {
+        match self.replace {
+            Some(..) => 3,
+            None => 2,
+        }
+    }
+
+    fn status(&self) -> String {
+        if self.confirm && self.is_capped() {
+            return format!(" Alt+A again to replace over {} matches in all project files ", self.matches);
+        }
+        if self.confirm {
+            let files = self.rows.iter().filter(|row| matches!(row, Row::File { .. })).count();
+            return format!(" Alt+A again to replace {} matches in {files} files ", self.matches);
+        }
+        let searching = if self.is_searching() { " searching ..." } else { "" };
+        format!(" {} matches{searching} ", self.matches)
+    }
+
+    /// text of match rows in replace mode is previewed with the replacement
+    fn preview(&self, text: &str) -> Option<String> {
+        let new_text = &self.replace.as_ref()?.text;
+        Some(self.matcher.as_ref()?.replace_line(text, new_text).0)
+    }
+}
+
+impl Drop for ProjectSearchPopup {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl PopupInterface for ProjectSearchPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.center(20, 120);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(&format!("{TITLE}{}{OPTIONS_HINT}", self.options.label()), gs.backend());
+        let mut lines = rect.into_iter();
+        match lines.next() {
+            Some(line) => self.pattern.widget(line, gs.backend()),
+            None => return,
+        }
+        if let Some(replace) = self.replace.as_ref() {
+            let Some(mut line) = lines.next() else {
+                return;
+            };
+            let mut prefix_style = Style::fg(color::dark_grey());
+            if self.on_replace {
+                prefix_style = Style::fg(color::yellow());
+            }
+            let prefix_width = REPLACE_PREFIX.len().min(line.width);
+            Line { width: prefix_width, ..line.clone() }.render_styled(REPLACE_PREFIX, prefix_style, gs.backend());
+            line += prefix_width;
+            replace.widget(line, gs.backend());
+        }
+        if let Some(line) = lines.next() {
+            let status = self.status();
+            let mut builder = line.unsafe_builder(gs.backend());
+            builder.push(&status);
+            let fill = BORDERS.horizontal.to_string().repeat(builder.width());
+            builder.push(&fill);
+        }
+        let Some(list_rect) = lines.into_rect() else {
+            return;
+        };
+        let message = match self.files.is_some() {
+            false => Some("Indexing files ..."),
+            true if self.invalid => Some("Invalid regex!"),
+            true if self.rows.is_empty() && self.is_searching() => Some("Searching ..."),
+            true if self.rows.is_empty() => Some("No results found!"),
+            true => None,
+        };
+        if let Some(message) = message {
+            self.state.render_list([message].into_iter(), list_rect, gs.backend());
+            return;
+        }
+        self.state.update_at_line(list_rect.height as usize);
+        let backend = gs.backend();
+        let mut lines = list_rect.into_iter();
+        for (idx, row) in self.rows.iter().enumerate().skip(self.state.at_line) {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let mut file_style = Style::fg(color::blue());
+            file_style.add_bold();
+            let is_selected = idx == self.state.selected;
+            if is_selected {
+                backend.set_style(self.state.highlight);
+                file_style.update(self.state.highlight);
+            }
+            let mut builder = line.unsafe_builder(backend);
+            match row {
+                Row::File { display, count, .. } => {
+                    builder.push_styled(display, file_style);
+                    builder.push(&format!(" ({count})"));
+                }
+                Row::Match { line, text, .. } => {
+                    builder.push(&format!("  {}| ", line + 1));
+                    match self.preview(text) {
+                        Some(preview) => builder.push_styled(&preview, Style::fg(color::green())),
+                        None => builder.push(text),
+                    };
+                }
+            }
+            drop(builder);
+            if is_selected {
+                backend.reset_style();
+            }
+        }
+        lines.clear_to_end(backend);
+    }
+
+    fn fast_render(&mut self, gs: &mut GlobalState) {
+        let was_searching = self.is_searching();
+        self.poll_files();
+        self.poll_results();
+        // status changes on finish
+        if was_searching && !self.is_searching() {
+            self.poll_results();
+            self.updated = true;
+        }
+        if self.collect_update_status() {
+            self.render(gs);
+        }
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, clipboard: &mut Clipboard) -> PopupMessage {
+        if let KeyCode::Char(ch) = key.code {
+            if key.modifiers.contains(KeyModifiers::ALT) {
+                match ch.to_ascii_lowercase() {
+                    'r' => self.options.regex = !self.options.regex,
+                    'c' => self.options.ignore_case = !self.options.ignore_case,
+                    'w' => self.options.whole_word = !self.options.whole_word,
+                    'a' => return self.replace_all(),
+                    _ => return PopupMessage::None,
+                }
+                self.restart();
+                return PopupMessage::None;
+            }
+        }
+        if key.code == KeyCode::Tab {
+            match self.replace {
+                Some(..) => self.on_replace = !self.on_replace,
+                None => {
+                    self.replace = Some(TextField::new(String::new(), Some(true)));
+                    self.on_replace = true;
+                }
+            }
+            self.confirm = false;
+            self.updated = true;
+            return PopupMessage::None;
+        }
+        if let Some(replace) = self.replace.as_mut().filter(|_| self.on_replace) {
+            if let Some(updated) = replace.map(key, clipboard) {
+                if updated {
+                    self.confirm = false;
+                }
+                self.updated = true;
+                return PopupMessage::None;
+            }
+        } else if let Some(updated) = self.pattern.map(key, clipboard) {
+            if updated {
+                self.restart();
+            }
+            return PopupMessage::None;
+        }
+        match key.code {
+            KeyCode::Enter => self.open(self.state.selected),
+            KeyCode::Up => {
+                self.state.prev(self.rows.len());
+                self.mark_as_updated();
+                PopupMessage::None
+            }
+            KeyCode::Down => {
+                self.state.next(self.rows.len());
+                self.mark_as_updated();
+                PopupMessage::None
+            }
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.rows.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.rows.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) if pos.line >= self.header_lines() => {
+                self.open(self.state.at_line + pos.line - self.header_lines())
+            }
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+/// files are searched one by one on blocking thread, cancel flag is checked between them
+fn spawn_search(
+    files: Arc<Vec<PathBuf>>,
+    matcher: LineMatcher,
+    buffer: ResultBuffer,
+    cancelled: &Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    let cancelled = Arc::clone(cancelled);
+    tokio::task::spawn_blocking(move || {
+        let mut total = 0;
+        for path in files.iter() {
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some(found) = search_file(path, &matcher) {
+                total += found.lines.len();
+                if let Ok(mut buffer) = buffer.lock() {
+                    buffer.push(found);
+                }
+                if total >= MAX_MATCHES {
+                    return;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{spawn_search, FileMatches, LineMatcher, ProjectSearchPopup, ResultBuffer, MAX_MATCHES};
+    use crate::configs::SearchOptions;
+    use crate::global_state::{Clipboard, IdiomEvent, PopupMessage};
+    use crate::popups::PopupInterface;
+    use crate::tree::IndexSlot;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::{
+        path::PathBuf,
+        sync::{atomic::AtomicBool, Arc},
+    };
+
+    #[test]
+    fn search_stops_on_cancel() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let files = Arc::new(vec![PathBuf::from("src/main.rs"), PathBuf::from("Cargo.toml")]);
+        let matcher = || LineMatcher::new("fn main", SearchOptions::default()).unwrap();
+        let buffer = ResultBuffer::default();
+        let cancelled = Arc::new(AtomicBool::new(true));
+        runtime.block_on(spawn_search(Arc::clone(&files), matcher(), Arc::clone(&buffer), &cancelled)).unwrap();
+        assert!(buffer.lock().unwrap().is_empty());
+        let running = Arc::default();
+        runtime.block_on(spawn_search(files, matcher(), Arc::clone(&buffer), &running)).unwrap();
+        let found = buffer.lock().unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, PathBuf::from("src/main.rs"));
+    }
+
+    #[test]
+    fn grouped_results() {
+        let mut popup = ProjectSearchPopup::new(IndexSlot::default());
+        let mut clipboard = Clipboard::default();
+        popup.buffer.lock().unwrap().extend([
+            FileMatches { path: PathBuf::from("src/main.rs"), lines: vec![(3, String::from("let x = 1;"))] },
+            FileMatches {
+                path: PathBuf::from("src/lib.rs"),
+                lines: vec![(0, String::from("x")), (7, String::from("x + 1"))],
+            },
+        ]);
+        popup.poll_results();
+        assert_eq!(popup.matches, 3);
+        assert_eq!(popup.rows.len(), 5);
+        // file row opens the first match
+        match popup.key_map(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), &mut clipboard) {
+            PopupMessage::Event(IdiomEvent::OpenAtLine(path, line)) => {
+                assert_eq!(path, PathBuf::from("src/main.rs"));
+                assert_eq!(line, 3);
+            }
+            _ => panic!("Expected open event"),
+        }
+        for _ in 0..4 {
+            popup.key_map(&KeyEvent::new(KeyCode::Down, KeyModifiers::NONE), &mut clipboard);
+        }
+        match popup.key_map(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), &mut clipboard) {
+            PopupMessage::Event(IdiomEvent::OpenAtLine(path, line)) => {
+                assert_eq!(path, PathBuf::from("src/lib.rs"));
+                assert_eq!(line, 7);
+            }
+            _ => panic!("Expected open event"),
+        }
+    }
+
+    #[test]
+    fn replace_needs_confirmation() {
+        let mut popup = ProjectSearchPopup::new(IndexSlot::default());
+        let mut clipboard = Clipboard::default();
+        let alt_a = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::ALT);
+        popup.pattern.text_set(String::from("x"));
+        popup.matcher = Some(LineMatcher::new("x", SearchOptions::default()).unwrap());
+        popup.buffer.lock().unwrap().extend([
+            FileMatches { path: PathBuf::from("src/main.rs"), lines: vec![(3, String::from("let x = 1;"))] },
+            FileMatches { path: PathBuf::from("src/lib.rs"), lines: vec![(0, String::from("x + x"))] },
+        ]);
+        popup.poll_results();
+        // no replace field
+        assert!(matches!(popup.key_map(&alt_a, &mut clipboard), PopupMessage::None));
+        assert!(!popup.confirm);
+        popup.key_map(&KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE), &mut clipboard);
+        popup.key_map(&KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE), &mut clipboard);
+        assert_eq!(popup.pattern.text, "x");
+        assert_eq!(popup.preview("x + x").as_deref(), Some("y + y"));
+        assert!(matches!(popup.key_map(&alt_a, &mut clipboard), PopupMessage::None));
+        assert!(popup.status().contains("replace 2 matches in 2 files"));
+        match popup.key_map(&alt_a, &mut clipboard) {
+            PopupMessage::Event(IdiomEvent::ReplaceInFiles { files, pattern, new_text, .. }) => {
+                assert_eq!(files, [PathBuf::from("src/main.rs"), PathBuf::from("src/lib.rs")]);
+                assert_eq!(pattern, "x");
+                assert_eq!(new_text, "y");
+            }
+            _ => panic!("Expected replace event"),
+        }
+        // capped results - replaced in all project files
+        let index = vec![
+            PathBuf::from("src/main.rs"),
+            PathBuf::from("src/lib.rs"),
+            PathBuf::from("src/other.rs"),
+        ];
+        popup.files = Some(Arc::new(index.clone()));
+        popup.matches = MAX_MATCHES;
+        assert!(popup.status().contains("in all project files"));
+        match popup.key_map(&alt_a, &mut clipboard) {
+            PopupMessage::Event(IdiomEvent::ReplaceInFiles { files, .. }) => assert_eq!(files, index),
+            _ => panic!("Expected replace event"),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{
+        backend::{color, Style},
+        layout::Rect,
+        state::State,
+        TextField,
+    },
+    workspace::session::ClosedFile,
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const TITLE: &str = " Recently closed files ";
+
+struct RecentEntry {
+    /// path displayed relative to the project if possible, used for matching
+    display: String,
+    label: String,
+    exists: bool,
+    file: ClosedFile,
+}
+
+/// Recently closed files (most recent first), filtered by fuzzy search on the path
+/// files that no longer exist are dimmed
+pub struct RecentFilesPopup {
+    pattern: TextField<bool>,
+    matcher: SkimMatcherV2,
+    entries: Vec<RecentEntry>,
+    /// indexes of the entries matching the pattern, best match first
+    filtered: Vec<usize>,
+    updated: bool,
+    rect: Option<Rect>,
+    state: State,
+}
+
+impl RecentFilesPopup {
+    pub fn boxed(recent: &[ClosedFile]) -> Box<dyn PopupInterface> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+        let base = std::env::current_dir().ok();
+        let entries = recent
+            .iter()
+            .map(|file| {
+                let path = base.as_ref().and_then(|base| file.path.strip_prefix(base).ok()).unwrap_or(&file.path);
+                let display = path.display().to_string();
+                let label = format!("{display} (closed {})", closed_ago(file.closed_at, now));
+                RecentEntry { display, label, exists: file.path.exists(), file: file.clone() }
+            })
+            .collect::<Vec<_>>();
+        Box::new(Self {
+            pattern: TextField::new(String::new(), Some(true)),
+            matcher: SkimMatcherV2::default(),
+            filtered: (0..entries.len()).collect(),
+            entries,
+            updated: true,
+            rect: None,
+            state: State::new(),
+        })
+    }
+
+    fn filter(&mut self) {
+        let mut scored = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| Some((self.matcher.fuzzy_match(&entry.display, &self.pattern.text)?, idx)))
+            .collect::<Vec<_>>();
+        // stable sort keeps the most recent first among equal scores
+        scored.sort_by(|(score, _), (other, _)| other.cmp(score));
+        self.filtered = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.state.select(0, 1);
+    }
+
+    fn open(&self, filtered_idx: usize) -> PopupMessage {
+        match self.filtered.get(filtered_idx).map(|idx| &self.entries[*idx].file) {
+            Some(file) => IdiomEvent::OpenAtPosition(file.path.clone(), file.position()).into(),
+            None => PopupMessage::None,
+        }
+    }
+}
+
+impl PopupInterface for RecentFilesPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.top(15).vcenter(100);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(TITLE, gs.backend());
+        match rect.next_line() {
+            Some(line) => self.pattern.widget(line, gs.backend()),
+            None => return,
+        }
+        let missing = Style::fg(color::dark_grey());
+        let options = self.filtered.iter().map(|idx| &self.entries[*idx]).map(|entry| match entry.exists {
+            true => (entry.label.as_str(), Style::default()),
+            false => (entry.label.as_str(), missing),
+        });
+        self.state.render_list_styled(options, &rect, gs.backend());
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, clipboard: &mut Clipboard) -> PopupMessage {
+        if let Some(updated) = self.pattern.map(key, clipboard) {
+            if updated {
+                self.filter();
+            }
+            return PopupMessage::None;
+        }
+        match key.code {
+            KeyCode::Enter => self.open(self.state.selected),
+            KeyCode::Up => {
+                self.state.prev(self.filtered.len());
+                PopupMessage::None
+            }
+            KeyCode::Down => {
+                self.state.next(self.filtered.len());
+                PopupMessage::None
+            }
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.filtered.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.filtered.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) if pos.line > 0 => self.open(self.state.at_line + pos.line - 1),
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+fn closed_ago(closed_at: u64, now: u64) -> String {
+    match now.saturating_sub(closed_at) {
+        secs if secs < 60 => String::from("just now"),
+        secs if secs < 3600 => format!("{}m ago", secs / 60),
+        secs if secs < 86400 => format!("{}h ago", secs / 3600),
+        secs => format!("{}d ago", secs / 86400),
+    }
+}
@@ -0,0 +1,279 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{
+        backend::{color, BackendProtocol, Style},
+        layout::{IterLines, Rect},
+        state::State,
+    },
+    workspace::line::EditorLine,
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use lsp_types::Location;
+use std::{
+    cell::OnceCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+struct ReferencedFile {
+    path: PathBuf,
+    display: String,
+    /// referenced lines ordered
+    lines: Vec<usize>,
+    /// trimmed text of the referenced lines, files not open in editors are read once (on first render)
+    previews: OnceCell<HashMap<usize, String>>,
+}
+
+impl ReferencedFile {
+    fn preview(&self, line: usize) -> &str {
+        let previews = self.previews.get_or_init(|| {
+            let text = std::fs::read_to_string(&self.path).unwrap_or_default();
+            let mut lines = self.lines.iter().peekable();
+            let mut previews = HashMap::new();
+            for (idx, text) in text.lines().enumerate() {
+                let Some(next) = lines.peek() else {
+                    break;
+                };
+                if **next == idx {
+                    previews.insert(idx, preview_text(text));
+                    while lines.next_if(|line| **line == idx).is_some() {}
+                }
+            }
+            previews
+        });
+        previews.get(&line).map(String::as_str).unwrap_or_default()
+    }
+}
+
+enum Row {
+    File { file: usize, count: usize },
+    Reference { file: usize, location: Location },
+}
+
+/// Locations of references (LSP) grouped by file with the line text, the selected one is opened
+/// servers can return thousands - files are read only when a reference from them is rendered
+pub struct ReferencesPopup {
+    files: Vec<ReferencedFile>,
+    rows: Vec<Row>,
+    title: String,
+    state: State,
+    rect: Option<Rect>,
+    updated: bool,
+}
+
+impl ReferencesPopup {
+    /// previews of files open in editors are taken from the content (can be unsaved)
+    pub fn new<'a>(locations: Vec<Location>, open_content: impl Fn(&Path) -> Option<&'a [EditorLine]>) -> Self {
+        let mut grouped: Vec<(PathBuf, Vec<Location>)> = Vec::new();
+        for location in locations {
+            let path = PathBuf::from(location.uri.path().as_str());
+            match grouped.iter_mut().find(|(grouped_path, ..)| *grouped_path == path) {
+                Some((_, locations)) => locations.push(location),
+                None => grouped.push((path, vec![location])),
+            }
+        }
+        let base = std::env::current_dir().ok();
+        let mut files = Vec::new();
+        let mut rows = Vec::new();
+        let mut count = 0;
+        for (file, (path, mut locations)) in grouped.into_iter().enumerate() {
+            locations.sort_by_key(|location| (location.range.start.line, location.range.start.character));
+            let mut lines = locations.iter().map(|location| location.range.start.line as usize).collect::<Vec<_>>();
+            lines.dedup();
+            let previews = OnceCell::new();
+            if let Some(content) = open_content(&path) {
+                let text = lines.iter().map(|line| (*line, content.get(*line).map(|l| preview_text(&l.content))));
+                let _ = previews.set(text.map(|(line, text)| (line, text.unwrap_or_default())).collect());
+            }
+            let display = base.as_ref().and_then(|base| path.strip_prefix(base).ok()).unwrap_or(&path);
+            let display = display.display().to_string();
+            count += locations.len();
+            rows.push(Row::File { file, count: locations.len() });
+            rows.extend(locations.into_iter().map(|location| Row::Reference { file, location }));
+            files.push(ReferencedFile { path, display, lines, previews });
+        }
+        let title = format!(" References ({count}) ");
+        Self { files, rows, title, state: State::new(), rect: None, updated: true }
+    }
+
+    /// count of references with the count of files
+    pub fn summary(&self) -> String {
+        let references = self.rows.len() - self.files.len();
+        let references = match references {
+            1 => String::from("1 reference"),
+            count => format!("{count} references"),
+        };
+        match self.files.len() {
+            1 => format!("{references} in 1 file"),
+            count => format!("{references} in {count} files"),
+        }
+    }
+
+    /// file rows open at the first reference
+    fn open(&self, row_idx: usize) -> PopupMessage {
+        match self.rows.get(row_idx) {
+            Some(Row::Reference { file, location }) => IdiomEvent::OpenAtSelect(
+                self.files[*file].path.clone(),
+                (location.range.start.into(), location.range.end.into()),
+            )
+            .into(),
+            Some(Row::File { .. }) => self.open(row_idx + 1),
+            None => PopupMessage::None,
+        }
+    }
+}
+
+impl PopupInterface for ReferencesPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.center(20, 120);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(&self.title, gs.backend());
+        self.state.update_at_line(rect.height as usize);
+        let backend = gs.backend();
+        let mut lines = rect.into_iter();
+        for (idx, row) in self.rows.iter().enumerate().skip(self.state.at_line) {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let mut file_style = Style::fg(color::blue());
+            file_style.add_bold();
+            let mut number_style = Style::fg(color::dark_grey());
+            let is_selected = idx == self.state.selected;
+            if is_selected {
+                backend.set_style(self.state.highlight);
+                file_style.update(self.state.highlight);
+                number_style.update(self.state.highlight);
+            }
+            let mut builder = line.unsafe_builder(backend);
+            match row {
+                Row::File { file, count } => {
+                    builder.push_styled(&self.files[*file].display, file_style);
+                    builder.push(&format!(" ({count})"));
+                }
+                Row::Reference { file, location } => {
+                    let line = location.range.start.line as usize;
+                    builder.push_styled(&format!("  {}| ", line + 1), number_style);
+                    builder.push(self.files[*file].preview(line));
+                }
+            }
+            drop(builder);
+            if is_selected {
+                backend.reset_style();
+            }
+        }
+        lines.clear_to_end(backend);
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Enter => return self.open(self.state.selected),
+            KeyCode::Up => self.state.prev(self.rows.len()),
+            KeyCode::Down => self.state.next(self.rows.len()),
+            _ => return PopupMessage::None,
+        }
+        self.updated = true;
+        PopupMessage::None
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.rows.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.rows.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) => self.open(self.state.at_line + pos.line),
+            None => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+fn preview_text(line: &str) -> String {
+    line.trim().chars().map(|ch| if ch.is_control() { ' ' } else { ch }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ReferencesPopup, Row};
+    use crate::global_state::{IdiomEvent, PopupMessage};
+    use crate::workspace::{line::EditorLine, CursorPosition};
+    use lsp_types::{Location, Position, Range, Uri};
+    use std::{
+        path::{Path, PathBuf},
+        str::FromStr,
+    };
+
+    fn location(uri: &Uri, line: u32, char: u32) -> Location {
+        Location::new(uri.clone(), Range::new(Position::new(line, char), Position::new(line, char + 3)))
+    }
+
+    #[test]
+    fn previews_and_open() {
+        let uri = Uri::from_str("file:///project/src/main.rs").unwrap();
+        let other = Uri::from_str("file:///project/missing.rs").unwrap();
+        let content: Vec<EditorLine> = vec!["fn main() {".into(), "    let x = foo();".into(), "}".into()];
+        let popup = ReferencesPopup::new(
+            vec![
+                location(&uri, 1, 12),
+                location(&other, 4, 0),
+                location(&uri, 20, 0),
+                location(&uri, 0, 3),
+            ],
+            |path| (path == Path::new("/project/src/main.rs")).then_some(content.as_slice()),
+        );
+        assert_eq!(popup.title, " References (4) ");
+        assert_eq!(popup.summary(), "4 references in 2 files");
+        // grouped by file in order of appearance, ordered by position within the file
+        let lines = popup
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::File { file, count } => (*file, *count),
+                Row::Reference { file, location } => (*file, location.range.start.line as usize),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [(0, 3), (0, 0), (0, 1), (0, 20), (1, 1), (1, 4)]);
+        // open file from the content
+        assert_eq!(popup.files[0].preview(1), "let x = foo();");
+        assert_eq!(popup.files[0].preview(20), "");
+        // not readable - empty preview
+        assert_eq!(popup.files[1].preview(4), "");
+        match popup.open(2) {
+            PopupMessage::Event(IdiomEvent::OpenAtSelect(path, (from, to))) => {
+                assert_eq!(path, PathBuf::from("/project/src/main.rs"));
+                assert_eq!(from, CursorPosition { line: 1, char: 12 });
+                assert_eq!(to, CursorPosition { line: 1, char: 15 });
+            }
+            _ => panic!("Expected open event"),
+        }
+        // file row opens the first reference
+        match popup.open(4) {
+            PopupMessage::Event(IdiomEvent::OpenAtSelect(path, (from, _))) => {
+                assert_eq!(path, PathBuf::from("/project/missing.rs"));
+                assert_eq!(from, CursorPosition { line: 4, char: 0 });
+            }
+            _ => panic!("Expected open event"),
+        }
+        assert!(matches!(popup.open(6), PopupMessage::None));
+    }
+}
@@ -0,0 +1,432 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    render::{
+        backend::{color, BackendProtocol, Style},
+        layout::{IterLines, Rect},
+        state::State,
+    },
+    workspace::line::EditorLine,
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use lsp_types::{
+    AnnotatedTextEdit, DocumentChangeOperation, DocumentChanges, OneOf, ResourceOp, TextEdit, Uri, WorkspaceEdit,
+};
+use std::{
+    cell::OnceCell,
+    path::{Path, PathBuf},
+};
+
+/// changed line of the file - trimmed text before and after the edits
+#[derive(Debug, Clone, PartialEq)]
+struct LineChange {
+    line: usize,
+    old: String,
+    new: String,
+}
+
+struct FileChange {
+    path: PathBuf,
+    display: String,
+    edits: Vec<TextEdit>,
+    expanded: bool,
+    /// files not open in editors are read once expanded
+    lines: OnceCell<Vec<LineChange>>,
+}
+
+impl FileChange {
+    fn lines(&self) -> &[LineChange] {
+        self.lines.get_or_init(|| {
+            let text = std::fs::read_to_string(&self.path).unwrap_or_default();
+            line_changes(&self.edits, |line| text.lines().nth(line).map(str::to_owned))
+        })
+    }
+}
+
+enum Change {
+    File(FileChange),
+    /// file create, rename or delete
+    Operation(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    Change(usize),
+    Line { change: usize, idx: usize },
+}
+
+/// Files and edits of the rename (LSP) - expanded files show the changed lines, Enter applies the rename
+pub struct RenamePreview {
+    edit: WorkspaceEdit,
+    changes: Vec<Change>,
+    rows: Vec<Row>,
+    title: String,
+    state: State,
+    rect: Option<Rect>,
+    updated: bool,
+}
+
+impl RenamePreview {
+    /// None if the rename changes nothing - lines of files open in editors are taken from the content
+    pub fn new<'a>(edit: WorkspaceEdit, open_content: impl Fn(&Path) -> Option<&'a [EditorLine]>) -> Option<Self> {
+        let mut changes = Vec::new();
+        let mut push_edits = |uri: &Uri, edits: Vec<TextEdit>| {
+            let path = PathBuf::from(uri.path().as_str());
+            let existing = changes.iter_mut().find_map(|change| match change {
+                Change::File(file) if file.path == path => Some(file),
+                _ => None,
+            });
+            match existing {
+                Some(file) => file.edits.extend(edits),
+                None => changes.push(Change::File(FileChange {
+                    display: display_path(&path),
+                    path,
+                    edits,
+                    expanded: false,
+                    lines: OnceCell::new(),
+                })),
+            }
+        };
+        let mut operations = Vec::new();
+        // changes are ignored if document changes are present (same as in LSP spec)
+        match edit.document_changes.as_ref() {
+            Some(DocumentChanges::Edits(edits)) => {
+                for edit in edits {
+                    push_edits(&edit.text_document.uri, text_edits(&edit.edits));
+                }
+            }
+            Some(DocumentChanges::Operations(document_operations)) => {
+                for operation in document_operations {
+                    match operation {
+                        DocumentChangeOperation::Edit(edit) => {
+                            push_edits(&edit.text_document.uri, text_edits(&edit.edits))
+                        }
+                        DocumentChangeOperation::Op(operation) => operations.push(describe_operation(operation)),
+                    }
+                }
+            }
+            None => {
+                let mut file_edits = edit.changes.iter().flatten().collect::<Vec<_>>();
+                file_edits.sort_by(|(lhs, _), (rhs, _)| lhs.path().as_str().cmp(rhs.path().as_str()));
+                for (uri, edits) in file_edits {
+                    push_edits(uri, edits.clone());
+                }
+            }
+        }
+        changes.extend(operations.into_iter().map(Change::Operation));
+        if changes.is_empty() {
+            return None;
+        }
+        let mut edits = 0;
+        let mut files = 0;
+        for change in changes.iter_mut() {
+            let Change::File(file) = change else {
+                continue;
+            };
+            file.edits.sort_by_key(|edit| (edit.range.start.line, edit.range.start.character));
+            edits += file.edits.len();
+            files += 1;
+            if let Some(content) = open_content(&file.path) {
+                let lines = line_changes(&file.edits, |line| content.get(line).map(|line| line.content.to_owned()));
+                let _ = file.lines.set(lines);
+            }
+        }
+        let title = format!(" Rename: {edits} edits in {files} files (Enter apply, Space expand, Esc cancel) ");
+        let mut preview =
+            Self { edit, changes, rows: Vec::new(), title, state: State::new(), rect: None, updated: true };
+        preview.build_rows();
+        Some(preview)
+    }
+
+    fn build_rows(&mut self) {
+        self.rows.clear();
+        for (idx, change) in self.changes.iter().enumerate() {
+            self.rows.push(Row::Change(idx));
+            if let Change::File(file) = change {
+                if file.expanded {
+                    self.rows.extend((0..file.lines().len()).map(|line| Row::Line { change: idx, idx: line }));
+                }
+            }
+        }
+    }
+
+    /// expands (collapses) the file of the row, the selection stays on the file
+    fn toggle(&mut self, row_idx: usize) {
+        let change = match self.rows.get(row_idx) {
+            Some(Row::Change(change) | Row::Line { change, .. }) => *change,
+            None => return,
+        };
+        let Change::File(file) = &mut self.changes[change] else {
+            return;
+        };
+        file.expanded = !file.expanded;
+        self.build_rows();
+        if let Some(idx) = self.rows.iter().position(|row| *row == Row::Change(change)) {
+            self.state.selected = idx;
+        }
+        self.updated = true;
+    }
+}
+
+impl PopupInterface for RenamePreview {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.center(20, 120);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(&self.title, gs.backend());
+        self.state.update_at_line(rect.height as usize);
+        let backend = gs.backend();
+        let mut lines = rect.into_iter();
+        for (idx, row) in self.rows.iter().enumerate().skip(self.state.at_line) {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let mut file_style = Style::fg(color::blue());
+            file_style.add_bold();
+            let mut muted_style = Style::fg(color::dark_grey());
+            let mut new_style = Style::fg(color::green());
+            let is_selected = idx == self.state.selected;
+            if is_selected {
+                backend.set_style(self.state.highlight);
+                for style in [&mut file_style, &mut muted_style, &mut new_style] {
+                    style.update(self.state.highlight);
+                }
+            }
+            let mut builder = line.unsafe_builder(backend);
+            match row {
+                Row::Change(change) => match &self.changes[*change] {
+                    Change::File(file) => {
+                        builder.push(if file.expanded { "- " } else { "+ " });
+                        builder.push_styled(&file.display, file_style);
+                        builder.push(&format!(" ({} edits)", file.edits.len()));
+                    }
+                    Change::Operation(operation) => {
+                        builder.push("  ");
+                        builder.push_styled(operation, file_style);
+                    }
+                },
+                Row::Line { change, idx } => {
+                    if let Change::File(file) = &self.changes[*change] {
+                        let change = &file.lines()[*idx];
+                        builder.push_styled(&format!("    {}| ", change.line + 1), muted_style);
+                        builder.push_styled(&change.old, muted_style);
+                        builder.push(" → ");
+                        builder.push_styled(&change.new, new_style);
+                    }
+                }
+            }
+            drop(builder);
+            if is_selected {
+                backend.reset_style();
+            }
+        }
+        lines.clear_to_end(backend);
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Enter => return IdiomEvent::ApplyRename(self.edit.clone()).into(),
+            KeyCode::Char(' ') | KeyCode::Right | KeyCode::Left => self.toggle(self.state.selected),
+            KeyCode::Up => self.state.prev(self.rows.len()),
+            KeyCode::Down => self.state.next(self.rows.len()),
+            _ => return PopupMessage::None,
+        }
+        self.updated = true;
+        PopupMessage::None
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.rows.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.rows.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        if let Some(pos) = self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            self.toggle(self.state.at_line + pos.line);
+        }
+        PopupMessage::None
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+fn text_edits(edits: &[OneOf<TextEdit, AnnotatedTextEdit>]) -> Vec<TextEdit> {
+    edits
+        .iter()
+        .map(|edit| match edit {
+            OneOf::Left(edit) => edit.clone(),
+            OneOf::Right(annotated) => annotated.text_edit.clone(),
+        })
+        .collect()
+}
+
+fn describe_operation(operation: &ResourceOp) -> String {
+    match operation {
+        ResourceOp::Create(create) => format!("create {}", display_path(Path::new(create.uri.path().as_str()))),
+        ResourceOp::Rename(rename) => format!(
+            "rename {} → {}",
+            display_path(Path::new(rename.old_uri.path().as_str())),
+            display_path(Path::new(rename.new_uri.path().as_str()))
+        ),
+        ResourceOp::Delete(delete) => format!("delete {}", display_path(Path::new(delete.uri.path().as_str()))),
+    }
+}
+
+fn display_path(path: &Path) -> String {
+    let base = std::env::current_dir().ok();
+    base.as_ref().and_then(|base| path.strip_prefix(base).ok()).unwrap_or(path).display().to_string()
+}
+
+/// edits (sorted) grouped by the start line applied on the line text - edits past the line end show the first line
+/// of the new text, lines that can not be read are left empty
+fn line_changes(edits: &[TextEdit], get_line: impl Fn(usize) -> Option<String>) -> Vec<LineChange> {
+    let mut changes = Vec::new();
+    let mut edits = edits.iter().peekable();
+    while let Some(first) = edits.next() {
+        let line = first.range.start.line;
+        let mut line_edits = vec![first];
+        while let Some(edit) = edits.next_if(|edit| edit.range.start.line == line) {
+            line_edits.push(edit);
+        }
+        let old = get_line(line as usize).unwrap_or_default();
+        let mut new = old.chars().collect::<Vec<_>>();
+        for edit in line_edits.iter().rev() {
+            let start = std::cmp::min(edit.range.start.character as usize, new.len());
+            let (end, text) = match edit.range.end.line == line {
+                true => (edit.range.end.character as usize, edit.new_text.as_str()),
+                false => (new.len(), edit.new_text.lines().next().unwrap_or_default()),
+            };
+            let end = end.clamp(start, new.len());
+            new.splice(start..end, text.chars());
+        }
+        let new = new.into_iter().collect::<String>();
+        changes.push(LineChange { line: line as usize, old: old.trim().to_owned(), new: new.trim().to_owned() });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Change, LineChange, RenamePreview, Row};
+    use crate::global_state::{IdiomEvent, PopupMessage};
+    use crate::popups::PopupInterface;
+    use crate::workspace::line::EditorLine;
+    use crossterm::event::{KeyCode, KeyEvent};
+    use lsp_types::{
+        DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, Position, Range,
+        RenameFile, ResourceOp, TextDocumentEdit, TextEdit, Uri, WorkspaceEdit,
+    };
+    use std::{collections::HashMap, path::Path, str::FromStr};
+
+    fn edit(line: u32, start: u32, end: u32, text: &str) -> TextEdit {
+        TextEdit::new(Range::new(Position::new(line, start), Position::new(line, end)), text.to_owned())
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn files_and_operations() {
+        let main = Uri::from_str("file:///project/src/main.rs").unwrap();
+        let missing = Uri::from_str("file:///project/src/missing.rs").unwrap();
+        let content: Vec<EditorLine> = vec!["fn old() {}".into(), "".into(), "fn main() { old(); old() }".into()];
+        // ignored - document changes are present
+        let changes = HashMap::from([(main.clone(), vec![edit(1, 0, 0, "old")])]);
+        let operations = DocumentChanges::Operations(vec![
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier { uri: main.clone(), version: None },
+                edits: vec![
+                    OneOf::Left(edit(2, 19, 22, "new")),
+                    OneOf::Left(edit(0, 3, 6, "new")),
+                    OneOf::Left(edit(2, 12, 15, "new")),
+                ],
+            }),
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier { uri: missing.clone(), version: None },
+                edits: vec![OneOf::Left(edit(4, 0, 3, "new"))],
+            }),
+            DocumentChangeOperation::Op(ResourceOp::Rename(RenameFile {
+                old_uri: Uri::from_str("file:///project/src/old.rs").unwrap(),
+                new_uri: Uri::from_str("file:///project/src/new.rs").unwrap(),
+                options: None,
+                annotation_id: None,
+            })),
+        ]);
+        let edit =
+            WorkspaceEdit { changes: Some(changes), document_changes: Some(operations), change_annotations: None };
+        let mut preview = RenamePreview::new(edit.clone(), |path| {
+            (path == Path::new("/project/src/main.rs")).then_some(content.as_slice())
+        })
+        .unwrap();
+        assert_eq!(preview.title, " Rename: 4 edits in 2 files (Enter apply, Space expand, Esc cancel) ");
+        assert_eq!(preview.rows, [Row::Change(0), Row::Change(1), Row::Change(2)]);
+        assert!(
+            matches!(&preview.changes[2], Change::Operation(op) if op == "rename /project/src/old.rs → /project/src/new.rs")
+        );
+        // open file - lines from the content, edits of the same line applied together
+        let Change::File(file) = &preview.changes[0] else {
+            panic!("Expected file");
+        };
+        assert_eq!(
+            file.lines.get().unwrap(),
+            &[
+                LineChange { line: 0, old: String::from("fn old() {}"), new: String::from("fn new() {}") },
+                LineChange {
+                    line: 2,
+                    old: String::from("fn main() { old(); old() }"),
+                    new: String::from("fn main() { new(); new() }")
+                },
+            ]
+        );
+        // not open - read on expand
+        let Change::File(file) = &preview.changes[1] else {
+            panic!("Expected file");
+        };
+        assert!(file.lines.get().is_none());
+        preview.toggle(1);
+        assert_eq!(
+            preview.rows,
+            [
+                Row::Change(0),
+                Row::Change(1),
+                Row::Line { change: 1, idx: 0 },
+                Row::Change(2)
+            ]
+        );
+        preview.toggle(2);
+        assert_eq!(preview.rows.len(), 3);
+        assert_eq!(preview.state.selected, 1);
+        match preview.key_map(&KeyEvent::from(KeyCode::Enter), &mut Default::default()) {
+            PopupMessage::Event(IdiomEvent::ApplyRename(applied)) => assert_eq!(applied, edit),
+            _ => panic!("Expected workspace edit"),
+        }
+        let empty = WorkspaceEdit::default();
+        assert!(RenamePreview::new(empty, |_| None).is_none());
+        // only changes
+        let changes = HashMap::from([
+            (missing, vec![self::edit(4, 0, 3, "new")]),
+            (main, vec![self::edit(2, 19, 22, "new"), self::edit(2, 12, 15, "new")]),
+        ]);
+        let only_changes = WorkspaceEdit { changes: Some(changes), ..Default::default() };
+        let preview = RenamePreview::new(only_changes, |_| None).unwrap();
+        assert_eq!(preview.title, " Rename: 3 edits in 2 files (Enter apply, Space expand, Esc cancel) ");
+        let Change::File(file) = &preview.changes[0] else {
+            panic!("Expected file");
+        };
+        assert_eq!(file.path, Path::new("/project/src/main.rs"));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    configs::{SavedPattern, SearchOptions},
     global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
     render::backend::{BackendProtocol, Style},
     tree::Tree,
@@ -11,22 +12,70 @@ use super::{
     PopupInterface,
 };
 
+const HISTORY_CAP: usize = 50;
+
+/// patterns and replacements used in the session (oldest first)
+#[derive(Default, Clone)]
+pub struct SearchHistory {
+    patterns: Vec<String>,
+    replacements: Vec<String>,
+}
+
+impl SearchHistory {
+    pub fn push(&mut self, pattern: &str, replacement: &str) {
+        record(&mut self.patterns, pattern);
+        record(&mut self.replacements, replacement);
+    }
+}
+
+fn record(entries: &mut Vec<String>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    entries.retain(|entry| entry != text);
+    entries.push(text.to_owned());
+    if entries.len() > HISTORY_CAP {
+        entries.remove(0);
+    }
+}
+
 #[derive(Default)]
 pub struct ReplacePopup {
     pub options: Vec<(CursorPosition, CursorPosition)>,
     pub pattern: String,
     pub new_text: String,
+    pub search: SearchOptions,
     pub on_text: bool,
     pub state: usize,
+    history: SearchHistory,
+    /// history index shown in the focused field and the text typed before browsing
+    browsing: Option<(usize, String)>,
+    /// name for saving the current pattern
+    naming: Option<String>,
 }
 
 impl ReplacePopup {
-    pub fn new() -> Box<Self> {
-        Box::default()
+    pub fn new(history: SearchHistory) -> Box<Self> {
+        Box::new(Self { history, ..Default::default() })
+    }
+
+    pub fn from_search(
+        pattern: String,
+        options: Vec<(CursorPosition, CursorPosition)>,
+        history: SearchHistory,
+    ) -> Box<Self> {
+        Box::new(Self { on_text: true, pattern, options, history, ..Default::default() })
     }
 
-    pub fn from_search(pattern: String, options: Vec<(CursorPosition, CursorPosition)>) -> Box<Self> {
-        Box::new(Self { on_text: true, pattern, options, ..Default::default() })
+    pub fn from_saved(saved: SavedPattern, history: SearchHistory) -> Box<Self> {
+        Box::new(Self {
+            on_text: true,
+            pattern: saved.pattern,
+            new_text: saved.replacement,
+            search: saved.options,
+            history,
+            ..Default::default()
+        })
     }
 
     fn drain_next(&mut self) -> (CursorPosition, CursorPosition) {
@@ -42,6 +91,7 @@ impl ReplacePopup {
     }
 
     fn push(&mut self, ch: char) {
+        self.browsing = None;
         if self.on_text {
             self.new_text.push(ch);
         } else {
@@ -50,22 +100,85 @@ impl ReplacePopup {
     }
 
     fn backspace(&mut self) {
+        self.browsing = None;
         if self.on_text {
             self.new_text.pop();
         } else {
             self.pattern.pop();
         };
     }
+
+    /// focused field and its history
+    fn field(&mut self) -> (&mut String, &[String]) {
+        match self.on_text {
+            true => (&mut self.new_text, &self.history.replacements),
+            false => (&mut self.pattern, &self.history.patterns),
+        }
+    }
+
+    fn history_prev(&mut self) {
+        let browsing = self.browsing.take();
+        let (field, entries) = self.field();
+        let (idx, draft) = match browsing {
+            Some((idx, draft)) => (idx.saturating_sub(1), draft),
+            None if entries.is_empty() => return,
+            None => (entries.len() - 1, field.clone()),
+        };
+        field.clone_from(&entries[idx]);
+        self.browsing = Some((idx, draft));
+    }
+
+    fn history_next(&mut self) {
+        let Some((idx, draft)) = self.browsing.take() else {
+            return;
+        };
+        let (field, entries) = self.field();
+        match entries.get(idx + 1) {
+            Some(entry) => {
+                field.clone_from(entry);
+                self.browsing = Some((idx + 1, draft));
+            }
+            None => *field = draft,
+        }
+    }
+
+    fn saved(&self, name: String) -> SavedPattern {
+        SavedPattern { name, pattern: self.pattern.clone(), replacement: self.new_text.clone(), options: self.search }
+    }
+
+    fn map_naming(&mut self, key: &KeyEvent) -> PopupMessage {
+        let Some(name) = self.naming.as_mut() else {
+            return PopupMessage::None;
+        };
+        match key.code {
+            KeyCode::Char(ch) => name.push(ch),
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Enter if !name.trim().is_empty() => {
+                let name = name.trim().to_owned();
+                self.naming = None;
+                return IdiomEvent::SavePattern(self.saved(name)).into();
+            }
+            KeyCode::Left => self.naming = None,
+            _ => (),
+        }
+        PopupMessage::None
+    }
 }
 
 impl PopupInterface for ReplacePopup {
     fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
+        if self.naming.is_some() {
+            return self.map_naming(key);
+        }
         match key.code {
             KeyCode::Char('h' | 'H') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 if self.options.is_empty() {
                     return PopupMessage::None;
                 }
                 IdiomEvent::ReplaceNextSelect {
+                    pattern: self.pattern.to_owned(),
                     new_text: self.new_text.to_owned(),
                     select: self.drain_next(),
                     next_select: self.get_state(),
@@ -76,7 +189,30 @@ impl PopupInterface for ReplacePopup {
                 if self.options.is_empty() {
                     return PopupMessage::None;
                 }
-                IdiomEvent::ReplaceAll(self.new_text.to_owned(), self.options.clone()).into()
+                IdiomEvent::ReplaceAll {
+                    pattern: self.pattern.to_owned(),
+                    new_text: self.new_text.to_owned(),
+                    ranges: self.options.clone(),
+                }
+                .into()
+            }
+            KeyCode::Char('s' | 'S') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if !self.pattern.is_empty() {
+                    self.naming = Some(String::new());
+                }
+                PopupMessage::None
+            }
+            KeyCode::Char('p' | 'P') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                IdiomEvent::SavedPatterns.into()
+            }
+            KeyCode::Char(ch) if key.modifiers.contains(KeyModifiers::ALT) => {
+                match ch.to_ascii_lowercase() {
+                    'r' => self.search.regex = !self.search.regex,
+                    'c' => self.search.ignore_case = !self.search.ignore_case,
+                    'w' => self.search.whole_word = !self.search.whole_word,
+                    _ => return PopupMessage::None,
+                }
+                IdiomEvent::PopupAccess.into()
             }
             KeyCode::Char(ch) => {
                 self.push(ch);
@@ -87,9 +223,19 @@ impl PopupInterface for ReplacePopup {
                 IdiomEvent::PopupAccess.into()
             }
             KeyCode::Tab => {
+                self.browsing = None;
                 self.on_text = !self.on_text;
                 PopupMessage::None
             }
+            // history - Alt or nothing to jump between
+            KeyCode::Up if self.options.is_empty() || key.modifiers.contains(KeyModifiers::ALT) => {
+                self.history_prev();
+                IdiomEvent::PopupAccess.into()
+            }
+            KeyCode::Down if self.options.is_empty() || key.modifiers.contains(KeyModifiers::ALT) => {
+                self.history_next();
+                IdiomEvent::PopupAccess.into()
+            }
             KeyCode::Down | KeyCode::Enter => into_message(next_option(&self.options, &mut self.state)),
             KeyCode::Up => into_message(prev_option(&self.options, &mut self.state)),
             KeyCode::Esc | KeyCode::Left => PopupMessage::Clear,
@@ -98,7 +244,8 @@ impl PopupInterface for ReplacePopup {
     }
 
     fn fast_render(&mut self, gs: &mut GlobalState) {
-        let area = gs.editor_area.right_top_corner(2, 50);
+        let rows = if self.naming.is_some() { 3 } else { 2 };
+        let area = gs.editor_area.right_top_corner(rows, 50);
         if area.height < 2 {
             return;
         };
@@ -108,8 +255,9 @@ impl PopupInterface for ReplacePopup {
             let mut find_builder = line.unsafe_builder(&mut gs.writer);
             find_builder.push(count_as_string(&self.options).as_str());
             find_builder.push(" > ");
+            find_builder.push(&self.search.label());
             find_builder.push(&self.pattern);
-            if !self.on_text {
+            if !self.on_text && self.naming.is_none() {
                 find_builder.push_styled("|", Style::slowblink());
             };
         };
@@ -117,10 +265,16 @@ impl PopupInterface for ReplacePopup {
             let mut repl_builder = line.unsafe_builder(&mut gs.writer);
             repl_builder.push("Rep > ");
             repl_builder.push(&self.new_text);
-            if self.on_text {
+            if self.on_text && self.naming.is_none() {
                 repl_builder.push_styled("|", Style::slowblink());
             }
         }
+        if let Some((line, name)) = lines.next().zip(self.naming.as_ref()) {
+            let mut name_builder = line.unsafe_builder(&mut gs.writer);
+            name_builder.push("Save as > ");
+            name_builder.push(name);
+            name_builder.push_styled("|", Style::slowblink());
+        }
         gs.writer.reset_style();
     }
 
@@ -131,7 +285,7 @@ impl PopupInterface for ReplacePopup {
     fn component_access(&mut self, ws: &mut Workspace, _tree: &mut Tree) {
         if let Some(editor) = ws.get_active() {
             self.options.clear();
-            editor.find(&self.pattern, &mut self.options);
+            editor.find_with(&self.pattern, self.search, &mut self.options);
         }
         self.state = self.options.len().saturating_sub(1);
     }
@@ -142,3 +296,89 @@ impl PopupInterface for ReplacePopup {
 
     fn mark_as_updated(&mut self) {}
 }
+
+#[cfg(test)]
+mod test {
+    use super::{ReplacePopup, SearchHistory, HISTORY_CAP};
+    use crate::{
+        configs::{SavedPattern, SearchOptions},
+        global_state::{Clipboard, IdiomEvent, PopupMessage},
+        popups::PopupInterface,
+    };
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn press(popup: &mut ReplacePopup, code: KeyCode, modifiers: KeyModifiers) -> PopupMessage {
+        popup.key_map(&KeyEvent::new(code, modifiers), &mut Clipboard::default())
+    }
+
+    #[test]
+    fn history_order() {
+        let mut history = SearchHistory::default();
+        history.push("first", "1");
+        history.push("second", "");
+        history.push("first", "2");
+        assert_eq!(history.patterns, vec!["second", "first"]);
+        assert_eq!(history.replacements, vec!["1", "2"]);
+        for idx in 0..HISTORY_CAP {
+            history.push(&idx.to_string(), "");
+        }
+        assert_eq!(history.patterns.len(), HISTORY_CAP);
+        assert_eq!(history.patterns[0], "0");
+    }
+
+    #[test]
+    fn history_browsing() {
+        let mut history = SearchHistory::default();
+        history.push("old", "a");
+        history.push("new", "b");
+        let mut popup = ReplacePopup::new(history);
+        popup.pattern.push_str("draft");
+        press(&mut popup, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(popup.pattern, "new");
+        press(&mut popup, KeyCode::Up, KeyModifiers::NONE);
+        press(&mut popup, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(popup.pattern, "old");
+        press(&mut popup, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(popup.pattern, "new");
+        press(&mut popup, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(popup.pattern, "draft");
+        // replacement field has its own history
+        press(&mut popup, KeyCode::Tab, KeyModifiers::NONE);
+        press(&mut popup, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(popup.new_text, "b");
+        assert_eq!(popup.pattern, "draft");
+    }
+
+    #[test]
+    fn recall_and_save() {
+        let saved = SavedPattern {
+            name: "timestamps".to_owned(),
+            pattern: r"\d{2}:\d{2}".to_owned(),
+            replacement: "HH:MM".to_owned(),
+            options: SearchOptions { regex: true, ignore_case: false, whole_word: true },
+        };
+        let mut popup = ReplacePopup::from_saved(saved.clone(), SearchHistory::default());
+        assert_eq!(popup.pattern, saved.pattern);
+        assert_eq!(popup.new_text, saved.replacement);
+        assert_eq!(popup.search, saved.options);
+
+        press(&mut popup, KeyCode::Char('c'), KeyModifiers::ALT);
+        assert!(popup.search.ignore_case);
+        press(&mut popup, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        for ch in "stamps".chars() {
+            press(&mut popup, KeyCode::Char(ch), KeyModifiers::NONE);
+        }
+        // typing goes to the name only
+        assert_eq!(popup.new_text, saved.replacement);
+        let expected = SavedPattern {
+            name: "stamps".to_owned(),
+            options: SearchOptions { ignore_case: true, ..saved.options },
+            ..saved
+        };
+        match press(&mut popup, KeyCode::Enter, KeyModifiers::NONE) {
+            PopupMessage::Event(IdiomEvent::SavePattern(pattern)) => assert_eq!(pattern, expected),
+            _ => panic!("Expected save event!"),
+        }
+        assert!(popup.naming.is_none());
+    }
+}
@@ -214,11 +214,12 @@ impl PopupInterface for ActiveFileSearch {
             Mode::Full => file_tree.shallow_copy_root_tree_path(),
             Mode::Select => file_tree.shallow_copy_selected_tree_path(),
         };
+        let ignore = file_tree.search_rules();
         let buffer = Arc::clone(&self.option_buffer);
         let pattern = self.pattern.text.to_owned();
         if let Some(old_handle) = self.join_handle.replace(tokio::task::spawn(async move {
             buffer.lock().await.clear();
-            let mut join_set = tree_path.search_files_join_set(pattern);
+            let mut join_set = tree_path.search_files_join_set(pattern, &ignore);
             while let Some(task_result) = join_set.join_next().await {
                 if let Ok(result) = task_result {
                     buffer.lock().await.extend(result);
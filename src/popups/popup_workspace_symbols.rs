@@ -0,0 +1,304 @@
+use super::PopupInterface;
+use crate::{
+    global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage},
+    lsp::{LSPClient, LSPResponse, LSPResponseType},
+    render::{
+        backend::{color, Style},
+        layout::{LineBuilder, Rect},
+        state::State,
+        TextField,
+    },
+    workspace::CursorPosition,
+};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use lsp_types::{OneOf, SymbolKind, Uri, WorkspaceSymbolResponse};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+const TITLE: &str = " Workspace symbols (LSP) ";
+/// the query is sent after typing stops for a while
+const DEBOUNCE: Duration = Duration::from_millis(250);
+/// responses can be dropped (requests are cleared on tab change) - the search is not blocked forever
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct FoundSymbol {
+    name: String,
+    kind: SymbolKind,
+    container: Option<String>,
+    path: PathBuf,
+    /// path relative to the project
+    display: String,
+    /// servers can return symbols with the file only (resolved on demand)
+    position: Option<CursorPosition>,
+}
+
+impl FoundSymbol {
+    fn new(name: String, kind: SymbolKind, container: Option<String>, uri: &Uri, base: Option<&Path>) -> Self {
+        let path = PathBuf::from(uri.path().as_str());
+        let display = base.and_then(|base| path.strip_prefix(base).ok()).unwrap_or(&path).display().to_string();
+        Self { name, kind, container, path, display, position: None }
+    }
+
+    fn event(&self) -> IdiomEvent {
+        match self.position {
+            Some(position) => IdiomEvent::OpenAtPosition(self.path.clone(), position),
+            None => IdiomEvent::OpenAtLine(self.path.clone(), 0),
+        }
+    }
+}
+
+/// Symbol search across the project with the LSP of the active file (workspace/symbol)
+/// queries are debounced and only one request is kept in flight
+pub struct SymbolSearchPopup {
+    pattern: TextField<bool>,
+    client: LSPClient,
+    /// time of the last edit not yet sent to the server
+    edited_at: Option<Instant>,
+    request: Option<(i64, Instant)>,
+    symbols: Vec<FoundSymbol>,
+    /// relative path display base
+    base: Option<PathBuf>,
+    updated: bool,
+    rect: Option<Rect>,
+    state: State,
+}
+
+impl SymbolSearchPopup {
+    pub fn boxed(client: LSPClient) -> Box<dyn PopupInterface> {
+        Box::new(Self::new(client))
+    }
+
+    fn new(client: LSPClient) -> Self {
+        Self {
+            pattern: TextField::new(String::new(), Some(true)),
+            client,
+            edited_at: None,
+            request: None,
+            symbols: Vec::new(),
+            base: std::env::current_dir().ok(),
+            updated: true,
+            rect: None,
+            state: State::new(),
+        }
+    }
+
+    fn poll(&mut self, gs: &mut GlobalState) {
+        if let Some((id, sent)) = self.request {
+            let response = self.client.get_responses().and_then(|mut responses| responses.remove(&id));
+            match response {
+                Some(response) => {
+                    self.request = None;
+                    match LSPResponseType::WorkspaceSymbol(id).parse(response.result) {
+                        Some(LSPResponse::WorkspaceSymbols(symbols)) => self.set_symbols(symbols),
+                        _ => {
+                            if let Some(err) = response.error {
+                                gs.error(format!("Workspace symbols: {err}"));
+                            }
+                        }
+                    }
+                    self.updated = true;
+                }
+                None if sent.elapsed() > REQUEST_TIMEOUT => self.request = None,
+                None => return,
+            }
+        }
+        if self.edited_at.is_some_and(|edited_at| edited_at.elapsed() > DEBOUNCE) {
+            self.edited_at = None;
+            if self.pattern.text.is_empty() {
+                self.symbols.clear();
+                self.updated = true;
+                return;
+            }
+            match self.client.request_workspace_symbols(self.pattern.text.to_owned()) {
+                Ok(id) => self.request = Some((id, Instant::now())),
+                Err(err) => gs.error(err.to_string()),
+            }
+        }
+    }
+
+    fn set_symbols(&mut self, response: WorkspaceSymbolResponse) {
+        self.state.select(0, 1);
+        let base = self.base.as_deref();
+        self.symbols = match response {
+            WorkspaceSymbolResponse::Flat(symbols) => symbols
+                .into_iter()
+                .map(|info| {
+                    let uri = &info.location.uri;
+                    let mut symbol = FoundSymbol::new(info.name, info.kind, info.container_name, uri, base);
+                    symbol.position = Some(info.location.range.start.into());
+                    symbol
+                })
+                .collect(),
+            WorkspaceSymbolResponse::Nested(symbols) => symbols
+                .into_iter()
+                .map(|info| match info.location {
+                    OneOf::Left(location) => {
+                        let uri = &location.uri;
+                        let mut symbol = FoundSymbol::new(info.name, info.kind, info.container_name, uri, base);
+                        symbol.position = Some(location.range.start.into());
+                        symbol
+                    }
+                    OneOf::Right(location) => {
+                        FoundSymbol::new(info.name, info.kind, info.container_name, &location.uri, base)
+                    }
+                })
+                .collect(),
+        };
+    }
+
+    fn open(&self, idx: usize) -> PopupMessage {
+        match self.symbols.get(idx) {
+            Some(symbol) => symbol.event().into(),
+            None => PopupMessage::None,
+        }
+    }
+}
+
+impl PopupInterface for SymbolSearchPopup {
+    fn render(&mut self, gs: &mut GlobalState) {
+        let mut rect = gs.screen_rect.top(15).vcenter(100);
+        rect.bordered();
+        self.rect.replace(rect);
+        rect.draw_borders(None, None, gs.backend());
+        rect.border_title(TITLE, gs.backend());
+        match rect.next_line() {
+            Some(line) => self.pattern.widget(line, gs.backend()),
+            None => return,
+        }
+        if self.symbols.is_empty() {
+            let status = match (self.request.is_some() || self.edited_at.is_some(), self.pattern.text.is_empty()) {
+                (true, _) => "Searching ...",
+                (false, true) => "Type to search symbols",
+                (false, false) => "No symbols found!",
+            };
+            self.state.render_list([status].into_iter(), rect, gs.backend());
+            return;
+        }
+        self.state.render_list_complex(&self.symbols, &[symbol_line], &rect, gs.backend());
+    }
+
+    fn fast_render(&mut self, gs: &mut GlobalState) {
+        self.poll(gs);
+        if self.collect_update_status() {
+            self.render(gs);
+        }
+    }
+
+    fn key_map(&mut self, key: &KeyEvent, clipboard: &mut Clipboard) -> PopupMessage {
+        if let Some(updated) = self.pattern.map(key, clipboard) {
+            if updated {
+                self.edited_at = Some(Instant::now());
+            }
+            return PopupMessage::None;
+        }
+        match key.code {
+            KeyCode::Enter => self.open(self.state.selected),
+            KeyCode::Up => {
+                self.state.prev(self.symbols.len());
+                PopupMessage::None
+            }
+            KeyCode::Down => {
+                self.state.next(self.symbols.len());
+                PopupMessage::None
+            }
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mouse_map(&mut self, event: MouseEvent) -> PopupMessage {
+        let (row, column) = match event {
+            MouseEvent { kind: MouseEventKind::Up(MouseButton::Left), column, row, .. } => (row, column),
+            MouseEvent { kind: MouseEventKind::ScrollUp, .. } => {
+                self.state.prev(self.symbols.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            MouseEvent { kind: MouseEventKind::ScrollDown, .. } => {
+                self.state.next(self.symbols.len());
+                self.mark_as_updated();
+                return PopupMessage::None;
+            }
+            _ => return PopupMessage::None,
+        };
+        match self.rect.as_ref().and_then(|rect| rect.relative_position(row, column)) {
+            Some(pos) if pos.line > 0 => self.open(self.state.at_line + pos.line - 1),
+            _ => PopupMessage::None,
+        }
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+fn symbol_line(symbol: &FoundSymbol, mut builder: LineBuilder) {
+    if !builder.push(&symbol.name) {
+        return;
+    }
+    let mut details = format!(" {:?}", symbol.kind);
+    if let Some(container) = symbol.container.as_ref().filter(|container| !container.is_empty()) {
+        details.push_str(" in ");
+        details.push_str(container);
+    }
+    if !builder.push_styled(&details, Style::fg(color::dark_grey())) {
+        return;
+    }
+    match symbol.position {
+        Some(position) => builder.push(&format!(" {}:{}", symbol.display, position.line + 1)),
+        None => builder.push(&format!(" {}", symbol.display)),
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::SymbolSearchPopup;
+    use crate::global_state::{IdiomEvent, PopupMessage};
+    use crate::lsp::LSPClient;
+    use crate::workspace::CursorPosition;
+    use lsp_types::WorkspaceSymbolResponse;
+    use serde_json::{from_value, json};
+    use std::path::PathBuf;
+
+    #[test]
+    fn symbols_open_location() {
+        let mut popup = SymbolSearchPopup::new(LSPClient::placeholder());
+        popup.base = Some(PathBuf::from("/project"));
+        let response: WorkspaceSymbolResponse = from_value(json!([
+            {
+                "name": "Tree",
+                "kind": 23,
+                "location": {
+                    "uri": "file:///project/src/tree/mod.rs",
+                    "range": {"start": {"line": 30, "character": 11}, "end": {"line": 30, "character": 15}}
+                }
+            },
+            {"name": "TreePath", "kind": 10, "location": {"uri": "file:///other/tree_paths.rs"}}
+        ]))
+        .unwrap();
+        popup.set_symbols(response);
+        assert_eq!(popup.symbols.len(), 2);
+        assert_eq!(popup.symbols[0].display, "src/tree/mod.rs");
+        match popup.open(0) {
+            PopupMessage::Event(IdiomEvent::OpenAtPosition(path, position)) => {
+                assert_eq!(path, PathBuf::from("/project/src/tree/mod.rs"));
+                assert_eq!(position, CursorPosition { line: 30, char: 11 });
+            }
+            _ => panic!("Expected open at position"),
+        }
+        // no range - file is opened
+        match popup.open(1) {
+            PopupMessage::Event(IdiomEvent::OpenAtLine(path, line)) => {
+                assert_eq!(path, PathBuf::from("/other/tree_paths.rs"));
+                assert_eq!(line, 0);
+            }
+            _ => panic!("Expected open at line"),
+        }
+        assert!(matches!(popup.open(2), PopupMessage::None));
+    }
+}
@@ -1,10 +1,15 @@
 use std::path::PathBuf;
 
-use super::{Popup, PopupSelector};
-use crate::global_state::IdiomEvent;
-use crate::render::Button;
-use crate::workspace::CursorPosition;
-use crossterm::event::KeyCode;
+use super::{Popup, PopupInterface, PopupSelector};
+use crate::configs::SavedPattern;
+use crate::global_state::{Clipboard, GlobalState, IdiomEvent, PopupMessage};
+use crate::render::{
+    backend::{BackendProtocol, Style},
+    Button,
+};
+use crate::workspace::{annotations::Annotation, editor::AttributeBlock, CursorPosition};
+use crossterm::event::{KeyCode, KeyEvent};
+use lsp_types::CodeActionOrCommand;
 
 pub fn save_all_popup() -> Box<Popup> {
     Box::new(Popup::new(
@@ -28,6 +33,21 @@ pub fn save_all_popup() -> Box<Popup> {
     ))
 }
 
+pub fn revert_changes(path: PathBuf) -> Box<Popup> {
+    Box::new(Popup::new(
+        "Discard all unsaved changes? (Use cancel/close to keep them)".into(),
+        None,
+        Some(path.display().to_string()),
+        None,
+        vec![Button {
+            command: |_| IdiomEvent::Revert.into(),
+            name: "Revert (R)",
+            key: Some(vec![KeyCode::Char('r'), KeyCode::Char('R')]),
+        }],
+        Some((4, 70)),
+    ))
+}
+
 pub fn selector_ranges(
     options: Vec<((CursorPosition, CursorPosition), String)>,
 ) -> Box<PopupSelector<((CursorPosition, CursorPosition), String)>> {
@@ -40,6 +60,25 @@ pub fn selector_ranges(
     ))
 }
 
+/// saved find/replace patterns, Enter opens replace filled with the pattern
+pub fn selector_patterns(patterns: Vec<SavedPattern>) -> Box<PopupSelector<(String, SavedPattern)>> {
+    let options = patterns
+        .into_iter()
+        .map(|saved| {
+            (format!("{}: {}{} -> {}", saved.name, saved.options.label(), saved.pattern, saved.replacement), saved)
+        })
+        .collect();
+    Box::new(PopupSelector::new(
+        options,
+        |(text, _)| text,
+        |popup| match popup.options.get(popup.state.selected) {
+            Some((_, saved)) => IdiomEvent::RecallPattern(saved.clone()).into(),
+            None => PopupMessage::Clear,
+        },
+        None,
+    ))
+}
+
 pub fn selector_editors(options: Vec<String>) -> Box<PopupSelector<String>> {
     Box::new(PopupSelector::new(
         options,
@@ -49,6 +88,35 @@ pub fn selector_editors(options: Vec<String>) -> Box<PopupSelector<String>> {
     ))
 }
 
+/// lists files failed on save all, Enter opens the file
+pub fn save_failures(failed: Vec<(PathBuf, String)>) -> Box<PopupSelector<(String, PathBuf)>> {
+    let options = failed.into_iter().map(|(path, reason)| (format!("{}: {reason}", path.display()), path)).collect();
+    Box::new(PopupSelector::new(
+        options,
+        |(text, _)| text,
+        |popup| match popup.options.get(popup.state.selected) {
+            Some((_, path)) => IdiomEvent::OpenAtLine(path.clone(), 0).into(),
+            None => PopupMessage::Clear,
+        },
+        None,
+    ))
+}
+
+pub fn close_pinned(path: PathBuf) -> Box<Popup> {
+    Box::new(Popup::new(
+        "Close pinned tab? (Use cancel to keep it)".into(),
+        None,
+        Some(path.display().to_string()),
+        None,
+        vec![Button {
+            command: |_| IdiomEvent::ClosePinned.into(),
+            name: "Close (C)",
+            key: Some(vec![KeyCode::Char('c'), KeyCode::Char('C')]),
+        }],
+        Some((4, 60)),
+    ))
+}
+
 pub fn file_updated(path: PathBuf) -> Box<Popup> {
     Box::new(Popup::new(
         "File updated! (Use cancel/close to do nothing)".into(),
@@ -66,7 +134,313 @@ pub fn file_updated(path: PathBuf) -> Box<Popup> {
                 name: "Rebase (L)",
                 key: Some(vec![KeyCode::Char('l'), KeyCode::Char('L')]),
             },
+            Button {
+                command: |_| IdiomEvent::DiskDiff.into(),
+                name: "Diff (D)",
+                key: Some(vec![KeyCode::Char('d'), KeyCode::Char('D')]),
+            },
         ],
         Some((4, 60)),
     ))
 }
+
+/// save failed on chattr attribute - elevation button only if configured
+pub fn protected_file(block: AttributeBlock, elevation: bool) -> Box<Popup> {
+    let mut buttons = vec![Button {
+        command: |_| IdiomEvent::NewPopup(save_copy).into(),
+        name: "Save copy (C)",
+        key: Some(vec![KeyCode::Char('c'), KeyCode::Char('C')]),
+    }];
+    if elevation {
+        buttons.push(Button {
+            command: |_| IdiomEvent::SaveElevated.into(),
+            name: "Remove attribute and save (R)",
+            key: Some(vec![KeyCode::Char('r'), KeyCode::Char('R')]),
+        });
+    }
+    Box::new(Popup::new(
+        format!("{} is {}! (Use cancel/close to do nothing)", block.path.display(), block.attribute),
+        Some("Save blocked: "),
+        Some(format!("chattr -{}", block.attribute.flag())),
+        None,
+        buttons,
+        Some((4, 80)),
+    ))
+}
+
+fn save_copy() -> Box<dyn PopupInterface> {
+    Box::new(Popup::new(
+        String::new(),
+        Some("Save copy to "),
+        Some(String::from("path")),
+        Some(Some),
+        vec![Button {
+            command: |popup| IdiomEvent::SaveCopy(popup.message.to_owned()).into(),
+            name: "Save",
+            key: None,
+        }],
+        Some((4, 60)),
+    ))
+}
+
+/// preferred actions first, selected one is applied by the workspace
+pub fn code_actions_selector(mut actions: Vec<CodeActionOrCommand>) -> Box<PopupSelector<CodeActionOrCommand>> {
+    actions.sort_by_key(|action| !matches!(action, CodeActionOrCommand::CodeAction(a) if a.is_preferred == Some(true)));
+    Box::new(PopupSelector::new(
+        actions,
+        |action| match action {
+            CodeActionOrCommand::Command(command) => &command.title,
+            CodeActionOrCommand::CodeAction(action) => &action.title,
+        },
+        |popup| match popup.options.get(popup.state.selected) {
+            Some(action) => IdiomEvent::CodeAction(Box::new(action.clone())).into(),
+            None => PopupMessage::Clear,
+        },
+        Some((10, 80)),
+    ))
+}
+
+/// the same file is open in another editor - saving would overwrite its changes on disk
+pub fn shared_file(path: PathBuf, other: PathBuf) -> Box<Popup> {
+    Box::new(Popup::new(
+        format!("Also open as {}! (Use cancel/close to do nothing)", other.display()),
+        None,
+        Some(path.display().to_string()),
+        None,
+        vec![Button {
+            command: |_| IdiomEvent::Save.into(),
+            name: "Overwrite (S)",
+            key: Some(vec![KeyCode::Char('s'), KeyCode::Char('S')]),
+        }],
+        Some((4, 60)),
+    ))
+}
+
+/// the file is open in another idiom instance running in the workspace
+pub fn instance_file(path: PathBuf, pid: u32) -> Box<Popup> {
+    Box::new(Popup::new(
+        format!("Open in another instance (pid {pid})! (Use cancel/close to do nothing)"),
+        None,
+        Some(path.display().to_string()),
+        None,
+        vec![Button {
+            command: |_| IdiomEvent::Save.into(),
+            name: "Overwrite (S)",
+            key: Some(vec![KeyCode::Char('s'), KeyCode::Char('S')]),
+        }],
+        Some((4, 60)),
+    ))
+}
+
+/// another instance holds the workspace lock - forward the file to it or continue without coordination
+pub fn instance_running(pid: u32, forward: bool) -> Box<Popup> {
+    let mut buttons = vec![Button {
+        command: |_| IdiomEvent::Standalone.into(),
+        name: "Standalone (S)",
+        key: Some(vec![KeyCode::Char('s'), KeyCode::Char('S')]),
+    }];
+    if forward {
+        buttons.insert(
+            0,
+            Button {
+                command: |_| IdiomEvent::ForwardToInstance.into(),
+                name: "Open there and exit (F)",
+                key: Some(vec![KeyCode::Char('f'), KeyCode::Char('F')]),
+            },
+        );
+    }
+    Box::new(Popup::new(
+        format!("Idiom is already running in this workspace (pid {pid})!"),
+        None,
+        None,
+        None,
+        buttons,
+        Some((4, 70)),
+    ))
+}
+
+/// edits the note on the line, empty note is removed
+pub fn annotation_popup(line: usize, text: String) -> Box<Popup> {
+    Box::new(Popup::new(
+        text,
+        Some("Note on "),
+        Some(format!("line {}", line + 1)),
+        Some(Some),
+        vec![Button {
+            command: |popup| IdiomEvent::SetAnnotation(popup.message.to_owned()).into(),
+            name: "Save",
+            key: None,
+        }],
+        Some((4, 60)),
+    ))
+}
+
+pub fn annotation_view(line: usize, text: String) -> Box<Popup> {
+    Box::new(Popup::new(
+        text,
+        Some("Note on "),
+        Some(format!("line {}", line + 1)),
+        None,
+        vec![Button {
+            command: |_| IdiomEvent::AnnotateLine.into(),
+            name: "Edit (E)",
+            key: Some(vec![KeyCode::Char('e'), KeyCode::Char('E')]),
+        }],
+        Some((4, 60)),
+    ))
+}
+
+/// lists notes of the project, Enter opens the file at the note
+pub fn annotations_list(list: Vec<(PathBuf, Annotation)>) -> Box<PopupSelector<(String, PathBuf, usize)>> {
+    let options = list
+        .into_iter()
+        .map(|(path, note)| {
+            let orphaned = if note.orphaned { " orphaned" } else { "" };
+            (format!("{} ({}{orphaned}) {}", path.display(), note.line + 1, note.text), path, note.line)
+        })
+        .collect();
+    Box::new(PopupSelector::new(
+        options,
+        |(text, ..)| text,
+        |popup| match popup.options.get(popup.state.selected) {
+            Some((_, path, line)) => IdiomEvent::OpenAtLine(path.clone(), *line).into(),
+            None => PopupMessage::Clear,
+        },
+        None,
+    ))
+}
+
+/// new name of the rename (LSP) is rejected before the request is sent
+pub fn invalid_identifier(name: &str) -> Box<Popup> {
+    Box::new(Popup::new(
+        format!("'{name}' is not a valid identifier!"),
+        None,
+        Some(String::from("Rename")),
+        None,
+        vec![Button { command: |_| PopupMessage::Clear, name: "Ok", key: None }],
+        Some((4, 60)),
+    ))
+}
+
+/// output (stderr) of the failed formatter command
+pub fn formatter_failed(error: &str) -> Box<PopupSelector<String>> {
+    let lines = error.lines().map(str::to_owned).collect();
+    Box::new(PopupSelector::new(lines, |text| text, |_| PopupMessage::Clear, None))
+}
+
+/// items of the imported context that were not recreated
+pub fn import_skipped(skipped: Vec<String>) -> Box<PopupSelector<String>> {
+    Box::new(PopupSelector::new(skipped, |text| text, |_| PopupMessage::Clear, None))
+}
+
+/// indent override of the editor - spaces width or t for tabs
+pub struct IndentPopup {
+    indent: String,
+    updated: bool,
+}
+
+impl IndentPopup {
+    pub fn boxed() -> Box<dyn PopupInterface> {
+        Box::new(Self { indent: String::new(), updated: true })
+    }
+}
+
+impl PopupInterface for IndentPopup {
+    fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Char('t' | 'T') => IdiomEvent::SetIndent(String::from("\t")).into(),
+            KeyCode::Char(ch) if ch.is_ascii_digit() => {
+                self.indent.push(ch);
+                self.mark_as_updated();
+                PopupMessage::None
+            }
+            KeyCode::Backspace => {
+                self.indent.pop();
+                self.mark_as_updated();
+                PopupMessage::None
+            }
+            KeyCode::Enter => match self.indent.parse::<usize>() {
+                Ok(spaces) if spaces != 0 && spaces <= 16 => IdiomEvent::SetIndent(" ".repeat(spaces)).into(),
+                _ => PopupMessage::Clear,
+            },
+            _ => PopupMessage::Clear,
+        }
+    }
+
+    fn render(&mut self, gs: &mut GlobalState) {
+        if let Some(line) = gs.editor_area.right_top_corner(1, 50).into_iter().next() {
+            gs.writer.set_style(gs.theme.accent_style);
+            {
+                let mut builder = line.unsafe_builder(&mut gs.writer);
+                builder.push(" Indent spaces (t for tabs) >> ");
+                builder.push(&self.indent);
+                builder.push_styled("|", Style::slowblink());
+            }
+            gs.writer.reset_style();
+        };
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
+
+/// sets the wrap column (used by reflow) of the active editor
+#[derive(Default)]
+pub struct WrapColumnPopup {
+    column: String,
+    updated: bool,
+}
+
+impl WrapColumnPopup {
+    pub fn boxed() -> Box<dyn PopupInterface> {
+        Box::new(Self { column: String::new(), updated: true })
+    }
+}
+
+impl PopupInterface for WrapColumnPopup {
+    fn key_map(&mut self, key: &KeyEvent, _: &mut Clipboard) -> PopupMessage {
+        match key.code {
+            KeyCode::Char(ch) if ch.is_ascii_digit() => {
+                self.column.push(ch);
+                self.mark_as_updated();
+                PopupMessage::None
+            }
+            KeyCode::Backspace => {
+                self.column.pop();
+                self.mark_as_updated();
+                PopupMessage::None
+            }
+            KeyCode::Enter => match self.column.parse::<usize>() {
+                Ok(column) if column != 0 => IdiomEvent::SetWrapColumn(column).into(),
+                _ => PopupMessage::Clear,
+            },
+            _ => PopupMessage::Clear,
+        }
+    }
+
+    fn render(&mut self, gs: &mut GlobalState) {
+        if let Some(line) = gs.editor_area.right_top_corner(1, 50).into_iter().next() {
+            gs.writer.set_style(gs.theme.accent_style);
+            {
+                let mut builder = line.unsafe_builder(&mut gs.writer);
+                builder.push(" Wrap column >> ");
+                builder.push(&self.column);
+                builder.push_styled("|", Style::slowblink());
+            }
+            gs.writer.reset_style();
+        };
+    }
+
+    fn mark_as_updated(&mut self) {
+        self.updated = true;
+    }
+
+    fn collect_update_status(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+}
@@ -1,10 +1,6 @@
-use super::{Popup, PopupSelector};
-use crate::{
-    global_state::{IdiomEvent, PopupMessage},
-    render::Button,
-};
-use lsp_types::{Location, Range};
-use std::path::PathBuf;
+use super::Popup;
+use crate::{global_state::IdiomEvent, render::Button};
+use crossterm::event::KeyCode;
 
 pub fn create_file_popup(path: String) -> Box<Popup> {
     let mut buttons = vec![Button {
@@ -37,22 +33,17 @@ pub fn rename_file_popup(path: String) -> Box<Popup> {
     ))
 }
 
-pub fn refrence_selector(options: Vec<Location>) -> Box<PopupSelector<(String, PathBuf, Range)>> {
-    Box::new(PopupSelector::new(
-        options.into_iter().map(location_with_display).collect(),
-        |(display, ..)| display,
-        |popup| {
-            if let Some((_, path, range)) = popup.options.get(popup.state.selected) {
-                return IdiomEvent::OpenAtSelect(path.clone(), (range.start.into(), range.end.into())).into();
-            }
-            PopupMessage::Clear
-        },
+pub fn delete_marked_popup(count: usize) -> Box<Popup> {
+    Box::new(Popup::new(
+        format!("Delete {count} marked path(s)? (Use cancel/close to keep them)"),
+        None,
         None,
+        None,
+        vec![Button {
+            command: |_| IdiomEvent::DeleteMarked.into(),
+            name: "Delete (D)",
+            key: Some(vec![KeyCode::Char('d'), KeyCode::Char('D')]),
+        }],
+        Some((4, 60)),
     ))
 }
-
-fn location_with_display(loc: Location) -> (String, PathBuf, Range) {
-    let path = PathBuf::from(loc.uri.path().as_str());
-    let range = loc.range;
-    (format!("{} ({})", path.display(), range.start.line + 1), path, range)
-}
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::atomic::{AtomicU8, Ordering},
+};
 
 use super::Color;
 use crossterm::style::Color as CTColor;
@@ -105,6 +109,172 @@ pub const fn dark_blue() -> Color {
     CTColor::DarkBlue
 }
 
+/// Colors supported by the terminal - themes are defined mostly in rgb,
+/// on terminals without true color the colors are mapped to the nearest palette color on render
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+static COLOR_SUPPORT: AtomicU8 = AtomicU8::new(0);
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+const ANSI16: [(Color, [u8; 3]); 16] = [
+    (CTColor::Black, [0, 0, 0]),
+    (CTColor::DarkRed, [128, 0, 0]),
+    (CTColor::DarkGreen, [0, 128, 0]),
+    (CTColor::DarkYellow, [128, 128, 0]),
+    (CTColor::DarkBlue, [0, 0, 128]),
+    (CTColor::DarkMagenta, [128, 0, 128]),
+    (CTColor::DarkCyan, [0, 128, 128]),
+    (CTColor::Grey, [192, 192, 192]),
+    (CTColor::DarkGrey, [128, 128, 128]),
+    (CTColor::Red, [255, 0, 0]),
+    (CTColor::Green, [0, 255, 0]),
+    (CTColor::Yellow, [255, 255, 0]),
+    (CTColor::Blue, [0, 0, 255]),
+    (CTColor::Magenta, [255, 0, 255]),
+    (CTColor::Cyan, [0, 255, 255]),
+    (CTColor::White, [255, 255, 255]),
+];
+
+impl ColorSupport {
+    pub fn from_env() -> Self {
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+        let terminfo_colors = term.as_deref().and_then(terminfo_colors);
+        Self::detect(colorterm.as_deref(), term.as_deref(), terminfo_colors)
+    }
+
+    /// generic names (TERM=xterm) are checked in COLORTERM and terminfo (max colors) before downgrading to 16
+    pub fn detect(colorterm: Option<&str>, term: Option<&str>, terminfo_colors: Option<u32>) -> Self {
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return Self::TrueColor;
+        }
+        match term {
+            Some(term) if term.contains("direct") || term.contains("truecolor") => return Self::TrueColor,
+            Some(term) if term.contains("256") => return Self::Ansi256,
+            _ => (),
+        }
+        if colorterm.is_some_and(|colorterm| colorterm.contains("256")) {
+            return Self::Ansi256;
+        }
+        match terminfo_colors {
+            Some(colors) if colors >= 1 << 24 => Self::TrueColor,
+            Some(colors) if colors >= 256 => Self::Ansi256,
+            _ => Self::Ansi16,
+        }
+    }
+
+    /// config values: "truecolor", "256", "16"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "truecolor" | "24bit" => Some(Self::TrueColor),
+            "256" | "ansi256" => Some(Self::Ansi256),
+            "16" | "ansi16" => Some(Self::Ansi16),
+            _ => None,
+        }
+    }
+
+    pub fn current() -> Self {
+        match COLOR_SUPPORT.load(Ordering::Relaxed) {
+            1 => Self::Ansi256,
+            2 => Self::Ansi16,
+            _ => Self::TrueColor,
+        }
+    }
+
+    pub fn set(self) {
+        let value = match self {
+            Self::TrueColor => 0,
+            Self::Ansi256 => 1,
+            Self::Ansi16 => 2,
+        };
+        COLOR_SUPPORT.store(value, Ordering::Relaxed);
+    }
+
+    pub fn downgrade(self, color: Color) -> Color {
+        match (self, color) {
+            (Self::Ansi256, CTColor::Rgb { r, g, b }) => ansi(rgb_to_ansi256(r, g, b)),
+            (Self::Ansi16, CTColor::Rgb { r, g, b }) => rgb_to_ansi16(r, g, b),
+            (Self::Ansi16, CTColor::AnsiValue(value)) if value < 16 => ANSI16[value as usize].0,
+            (Self::Ansi16, CTColor::AnsiValue(value)) => {
+                let [r, g, b] = ansi256_to_rgb(value);
+                rgb_to_ansi16(r, g, b)
+            }
+            _ => color,
+        }
+    }
+}
+
+/// nearest color from the 6x6x6 cube or the grayscale ramp (16 - 255)
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_idx = |value: u8| {
+        CUBE_LEVELS.iter().enumerate().min_by_key(|(_, level)| level.abs_diff(value)).map(|(idx, _)| idx).unwrap_or(0)
+    };
+    let (ri, gi, bi) = (cube_idx(r), cube_idx(g), cube_idx(b));
+    let cube = [CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]];
+    let avg = (r as u16 + g as u16 + b as u16) / 3;
+    let gray_idx = (avg.saturating_sub(8) / 10).min(23) as u8;
+    let gray_level = 8 + gray_idx * 10;
+    let gray = [gray_level; 3];
+    if distance([r, g, b], gray) < distance([r, g, b], cube) {
+        232 + gray_idx
+    } else {
+        16 + (36 * ri + 6 * gi + bi) as u8
+    }
+}
+
+/// maps to one of the 16 basic colors by hue and brightness
+/// plain distance would map most theme colors to greys (the actual values depend on the terminal theme)
+pub fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    if max - min < 40 {
+        return match max {
+            0..=63 => CTColor::Black,
+            64..=159 => CTColor::DarkGrey,
+            160..=223 => CTColor::Grey,
+            _ => CTColor::White,
+        };
+    }
+    let is_on = |channel: u8| channel as u16 * 2 >= max as u16 + min as u16;
+    let bright = max > 170;
+    match (is_on(r), is_on(g), is_on(b), bright) {
+        (true, false, false, true) => CTColor::Red,
+        (true, false, false, false) => CTColor::DarkRed,
+        (false, true, false, true) => CTColor::Green,
+        (false, true, false, false) => CTColor::DarkGreen,
+        (true, true, false, true) => CTColor::Yellow,
+        (true, true, false, false) => CTColor::DarkYellow,
+        (false, false, true, true) => CTColor::Blue,
+        (false, false, true, false) => CTColor::DarkBlue,
+        (true, false, true, true) => CTColor::Magenta,
+        (true, false, true, false) => CTColor::DarkMagenta,
+        (false, true, true, true) => CTColor::Cyan,
+        (false, true, true, false) => CTColor::DarkCyan,
+        _ => CTColor::Grey,
+    }
+}
+
+fn ansi256_to_rgb(value: u8) -> [u8; 3] {
+    match value {
+        0..=15 => ANSI16[value as usize].1,
+        16..=231 => {
+            let idx = (value - 16) as usize;
+            [CUBE_LEVELS[idx / 36], CUBE_LEVELS[(idx / 6) % 6], CUBE_LEVELS[idx % 6]]
+        }
+        _ => [8 + (value - 232) * 10; 3],
+    }
+}
+
+fn distance(lhs: [u8; 3], rhs: [u8; 3]) -> u32 {
+    lhs.iter().zip(rhs).map(|(l, r)| (l.abs_diff(r) as u32).pow(2)).sum()
+}
+
 pub fn serialize_rgb(r: u8, g: u8, b: u8) -> HashMap<&'static str, [u8; 3]> {
     let mut rgb = HashMap::new();
     rgb.insert("rgb", [r, g, b]);
@@ -206,3 +376,186 @@ impl std::fmt::Display for ParseColorError {
 }
 
 impl std::error::Error for ParseColorError {}
+
+/// max colors of the terminal from the compiled terminfo entry
+fn terminfo_colors(term: &str) -> Option<u32> {
+    let first = term.chars().next()?;
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    if let Ok(list) = std::env::var("TERMINFO_DIRS") {
+        dirs.extend(list.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from));
+    }
+    dirs.extend(
+        [
+            "/etc/terminfo",
+            "/lib/terminfo",
+            "/usr/share/terminfo",
+            "/usr/lib/terminfo",
+        ]
+        .map(PathBuf::from),
+    );
+    dirs.into_iter()
+        .flat_map(|dir| [dir.join(first.to_string()), dir.join(format!("{:x}", first as u32))])
+        .find_map(|dir| std::fs::read(dir.join(term)).ok())
+        .and_then(|data| parse_terminfo_colors(&data))
+}
+
+/// max_colors (numeric capability 13) from the legacy (16 bit) or extended number format (32 bit)
+fn parse_terminfo_colors(data: &[u8]) -> Option<u32> {
+    const MAX_COLORS: usize = 13;
+    let short = |idx: usize| Some(i16::from_le_bytes([*data.get(idx * 2)?, *data.get(idx * 2 + 1)?]));
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let names_size = usize::try_from(short(1)?).ok()?;
+    let bools_count = usize::try_from(short(2)?).ok()?;
+    let numbers_count = usize::try_from(short(3)?).ok()?;
+    if numbers_count <= MAX_COLORS {
+        return None;
+    }
+    let mut numbers_start = 12 + names_size + bools_count;
+    // numbers are aligned on even byte
+    numbers_start += numbers_start % 2;
+    let at = numbers_start + MAX_COLORS * number_size;
+    let value = match number_size {
+        2 => i16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as i32,
+        _ => i32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?),
+    };
+    u32::try_from(value).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_terminfo_colors, rgb, rgb_to_ansi16, rgb_to_ansi256, ColorSupport};
+    use crate::configs::{Theme, UITheme};
+    use crossterm::style::Color;
+
+    #[test]
+    fn detection() {
+        assert_eq!(ColorSupport::detect(Some("truecolor"), Some("xterm-256color"), None), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::detect(None, Some("xterm-256color"), None), ColorSupport::Ansi256);
+        assert_eq!(ColorSupport::detect(None, Some("xterm-direct"), None), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::detect(None, Some("linux"), Some(8)), ColorSupport::Ansi16);
+        assert_eq!(ColorSupport::detect(None, None, None), ColorSupport::Ansi16);
+        // generic name is not downgraded if COLORTERM or terminfo report more
+        assert_eq!(ColorSupport::detect(Some("24bit"), Some("xterm"), Some(8)), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::detect(Some("xterm-256color"), Some("xterm"), None), ColorSupport::Ansi256);
+        assert_eq!(ColorSupport::detect(None, Some("xterm"), Some(256)), ColorSupport::Ansi256);
+        assert_eq!(ColorSupport::detect(None, Some("xterm"), Some(1 << 24)), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::detect(None, Some("xterm"), Some(8)), ColorSupport::Ansi16);
+        assert_eq!(ColorSupport::parse("256"), Some(ColorSupport::Ansi256));
+        assert_eq!(ColorSupport::parse("rainbow"), None);
+    }
+
+    /// header, names, bools and numbers of compiled terminfo entry
+    fn terminfo(magic: i16, names: &[u8], bools: usize, numbers: &[i32]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for value in [magic, names.len() as i16, bools as i16, numbers.len() as i16, 0, 0] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(names);
+        data.extend(std::iter::repeat(1).take(bools));
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+        for number in numbers {
+            match magic {
+                0o432 => data.extend((*number as i16).to_le_bytes()),
+                _ => data.extend(number.to_le_bytes()),
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn terminfo_colors() {
+        let mut numbers = vec![-1; 15];
+        numbers[13] = 256;
+        assert_eq!(parse_terminfo_colors(&terminfo(0o432, b"xterm-256color\0", 37, &numbers)), Some(256));
+        numbers[13] = 1 << 24;
+        assert_eq!(parse_terminfo_colors(&terminfo(0o1036, b"xterm-direct\0", 38, &numbers)), Some(1 << 24));
+        numbers[13] = -1;
+        assert_eq!(parse_terminfo_colors(&terminfo(0o432, b"dumb\0", 2, &numbers)), None);
+        // missing numbers or broken entry
+        assert_eq!(parse_terminfo_colors(&terminfo(0o432, b"vt52\0", 2, &[-1; 5])), None);
+        assert_eq!(parse_terminfo_colors(&[0, 1, 2]), None);
+    }
+
+    #[test]
+    fn ansi256() {
+        // exact cube colors
+        assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+        assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+        assert_eq!(rgb_to_ansi256(95, 135, 175), 67);
+        // grays prefer the ramp
+        assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+        assert_eq!(rgb_to_ansi256(30, 30, 30), 234);
+        // theme like colors
+        assert_eq!(rgb_to_ansi256(86, 156, 214), 74);
+        assert_eq!(rgb_to_ansi256(206, 145, 120), 174);
+        assert_eq!(ColorSupport::Ansi256.downgrade(rgb(255, 0, 0)), Color::AnsiValue(196));
+        assert_eq!(ColorSupport::Ansi256.downgrade(Color::DarkGrey), Color::DarkGrey);
+    }
+
+    #[test]
+    fn ansi16() {
+        assert_eq!(rgb_to_ansi16(0, 0, 0), Color::Black);
+        assert_eq!(rgb_to_ansi16(250, 250, 250), Color::White);
+        assert_eq!(rgb_to_ansi16(30, 30, 30), Color::Black);
+        assert_eq!(rgb_to_ansi16(128, 128, 128), Color::DarkGrey);
+        // theme like colors keep their hue
+        assert_eq!(rgb_to_ansi16(86, 156, 214), Color::Cyan);
+        assert_eq!(rgb_to_ansi16(78, 201, 176), Color::Cyan);
+        assert_eq!(rgb_to_ansi16(197, 134, 192), Color::Magenta);
+        assert_eq!(rgb_to_ansi16(220, 220, 170), Color::Yellow);
+        assert_eq!(rgb_to_ansi16(106, 153, 85), Color::DarkGreen);
+        assert_eq!(rgb_to_ansi16(220, 30, 40), Color::Red);
+        assert_eq!(rgb_to_ansi16(0, 0, 139), Color::DarkBlue);
+        assert_eq!(ColorSupport::Ansi16.downgrade(Color::AnsiValue(9)), Color::Red);
+        assert_eq!(ColorSupport::Ansi16.downgrade(Color::AnsiValue(196)), Color::Red);
+        assert_eq!(ColorSupport::Ansi16.downgrade(Color::AnsiValue(244)), Color::DarkGrey);
+        assert_eq!(ColorSupport::TrueColor.downgrade(rgb(1, 2, 3)), rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn default_theme() {
+        let theme = Theme::default();
+        let accent = UITheme::default().accent_background;
+        let colors = [
+            theme.imports,
+            theme.key_words,
+            theme.flow_control,
+            theme.class_or_struct,
+            theme.constant,
+            theme.functions,
+            theme.numeric,
+            theme.default,
+            theme.selected,
+            theme.string,
+            theme.string_escape,
+            theme.comment,
+            accent,
+        ];
+        for support in [ColorSupport::Ansi256, ColorSupport::Ansi16] {
+            for color in colors {
+                assert!(!matches!(support.downgrade(color), Color::Rgb { .. }));
+            }
+            // selection has to stay visible over the accent background
+            assert_ne!(support.downgrade(theme.selected), support.downgrade(accent));
+            assert_ne!(support.downgrade(theme.key_words), support.downgrade(theme.comment));
+        }
+        assert_eq!(ColorSupport::Ansi256.downgrade(theme.key_words), Color::AnsiValue(62));
+        assert_eq!(ColorSupport::Ansi16.downgrade(theme.key_words), Color::Blue);
+        assert_eq!(ColorSupport::Ansi16.downgrade(theme.comment), Color::DarkGreen);
+        assert_eq!(ColorSupport::Ansi16.downgrade(theme.functions), Color::Yellow);
+        assert_eq!(ColorSupport::Ansi16.downgrade(theme.flow_control), Color::Magenta);
+    }
+}
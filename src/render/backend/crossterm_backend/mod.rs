@@ -2,8 +2,9 @@ pub mod color;
 mod style;
 use crossterm::{
     cursor::{Hide, MoveTo, RestorePosition, SavePosition, Show},
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute, queue,
-    style::{Color as CTColor, Print, ResetColor, SetStyle},
+    style::{Attribute, Color as CTColor, Print, ResetColor, SetAttribute, SetStyle},
     terminal::{size, Clear, ClearType},
 };
 #[allow(unused_imports)]
@@ -18,7 +19,7 @@ pub use style::Style;
 
 use crate::render::layout::Rect;
 
-use super::BackendProtocol;
+use super::{encode_base64, BackendProtocol, Screen};
 pub type Color = CTColor;
 
 /// Thin wrapper around rendering framework, allowing easy switching of backend
@@ -32,6 +33,10 @@ pub struct Backend {
     // #[cfg(test)]
     // writer: DummyOut,
     default_styled: Option<Style>,
+    /// faint text - set again after each reset of the style
+    dim: bool,
+    /// output kept to be checked before it is written
+    recording: Option<Vec<u8>>,
 }
 
 impl Write for Backend {
@@ -45,30 +50,43 @@ impl Write for Backend {
 
     #[inline(always)]
     fn flush(&mut self) -> std::io::Result<()> {
-        self.writer.flush()
+        match self.recording {
+            Some(..) => Ok(()),
+            None => self.writer.flush(),
+        }
     }
 
     #[inline(always)]
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.writer.write(buf)
+        match self.recording.as_mut() {
+            Some(recording) => recording.write(buf),
+            None => self.writer.write(buf),
+        }
     }
 
     #[inline(always)]
     fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
-        self.writer.write_all(buf)
+        match self.recording.as_mut() {
+            Some(recording) => recording.write_all(buf),
+            None => self.writer.write_all(buf),
+        }
     }
 
     #[inline(always)]
     fn write_fmt(&mut self, fmt: std::fmt::Arguments<'_>) -> std::io::Result<()> {
-        self.writer.write_fmt(fmt)
+        match self.recording.as_mut() {
+            Some(recording) => recording.write_fmt(fmt),
+            None => self.writer.write_fmt(fmt),
+        }
     }
 }
 
 impl BackendProtocol for Backend {
     #[inline]
     fn init() -> Self {
+        color::ColorSupport::from_env().set();
         init_terminal().expect(ERR_MSG);
-        Self { writer: std::io::stdout(), default_styled: None }
+        Self { writer: std::io::stdout(), default_styled: None, dim: false, recording: None }
     }
 
     #[inline]
@@ -115,7 +133,8 @@ impl BackendProtocol for Backend {
     #[inline]
     fn set_style(&mut self, style: Style) {
         self.default_styled.replace(style);
-        queue!(self, ResetColor, SetStyle(style.into())).expect(ERR_MSG);
+        self.reset_colors();
+        queue!(self, SetStyle(style.into())).expect(ERR_MSG);
     }
 
     #[inline]
@@ -125,11 +144,10 @@ impl BackendProtocol for Backend {
 
     #[inline]
     fn to_set_style(&mut self) {
-        match self.default_styled {
-            Some(style) => queue!(self, ResetColor, SetStyle(style.into())),
-            None => queue!(self, ResetColor),
+        self.reset_colors();
+        if let Some(style) = self.default_styled {
+            queue!(self, SetStyle(style.into())).expect(ERR_MSG);
         }
-        .expect(ERR_MSG);
     }
 
     /// update existing style if exists otherwise sets it to the new one
@@ -171,7 +189,18 @@ impl BackendProtocol for Backend {
     #[inline]
     fn reset_style(&mut self) {
         self.default_styled = None;
-        queue!(self, ResetColor).expect(ERR_MSG);
+        self.reset_colors();
+    }
+
+    #[inline]
+    fn set_dim(&mut self, dim: bool) {
+        self.dim = dim;
+        self.to_set_style();
+    }
+
+    #[inline]
+    fn is_dim(&self) -> bool {
+        self.dim
     }
 
     /// sends the cursor to location
@@ -212,36 +241,65 @@ impl BackendProtocol for Backend {
     /// prints styled text without affecting the writer set style
     #[inline]
     fn print_styled<D: Display>(&mut self, text: D, style: Style) {
-        if let Some(restore_style) = self.default_styled {
-            queue!(self, SetStyle(style.into()), Print(text), ResetColor, SetStyle(restore_style.into()),)
-        } else {
-            queue!(self, SetStyle(style.into()), Print(text), ResetColor,)
-        }
-        .expect(ERR_MSG);
+        queue!(self, SetStyle(style.into()), Print(text)).expect(ERR_MSG);
+        self.to_set_style();
     }
 
     /// goes to location and prints styled text without affecting the writer set style
     #[inline]
     fn print_styled_at<D: Display>(&mut self, row: u16, col: u16, text: D, style: Style) {
-        if let Some(restore_style) = self.default_styled {
-            queue!(
-                self,
-                SetStyle(style.into()),
-                MoveTo(col, row),
-                Print(text),
-                ResetColor,
-                SetStyle(restore_style.into()),
-            )
-        } else {
-            queue!(self, SetStyle(style.into()), MoveTo(col, row), Print(text), ResetColor,)
-        }
-        .expect(ERR_MSG);
+        queue!(self, SetStyle(style.into()), MoveTo(col, row), Print(text)).expect(ERR_MSG);
+        self.to_set_style();
     }
 
     #[inline]
     fn pad(&mut self, width: usize) {
         queue!(self, Print(format!("{:width$}", ""))).expect(ERR_MSG);
     }
+
+    /// direct enabling/disabling of mouse reporting - no buffer queing
+    #[inline]
+    fn mouse_capture(&mut self, enabled: bool) {
+        match enabled {
+            true => execute!(self, EnableMouseCapture),
+            false => execute!(self, DisableMouseCapture),
+        }
+        .expect(ERR_MSG);
+    }
+
+    fn start_recording(&mut self) {
+        self.recording.get_or_insert_with(Vec::new);
+    }
+
+    fn stop_recording(&mut self, screen: &mut Screen) {
+        if let Some(recording) = self.recording.take() {
+            screen.replay_ansi(&recording);
+            self.writer.write_all(&recording).expect(ERR_MSG);
+        }
+    }
+
+    fn discard_recording(&mut self, screen: &mut Screen) {
+        if let Some(recording) = self.recording.take() {
+            screen.replay_ansi(&recording);
+        }
+    }
+
+    /// written directly - not part of recorded output
+    fn clipboard_osc52(&mut self, text: &str) {
+        write!(self.writer, "\x1b]52;c;{}\x07", encode_base64(text.as_bytes())).expect(ERR_MSG);
+    }
+}
+
+impl Backend {
+    /// resets colors and attributes (dim is kept while set)
+    #[inline]
+    fn reset_colors(&mut self) {
+        match self.dim {
+            true => queue!(self, ResetColor, SetAttribute(Attribute::Dim)),
+            false => queue!(self, ResetColor),
+        }
+        .expect(ERR_MSG);
+    }
 }
 
 impl Drop for Backend {
@@ -263,9 +321,24 @@ fn init_terminal() -> std::io::Result<()> {
         crossterm::terminal::EnterAlternateScreen,
         crossterm::terminal::DisableLineWrap,
         crossterm::style::ResetColor,
-        crossterm::event::EnableMouseCapture,
         crossterm::cursor::Hide,
-    )
+        crossterm::event::EnableFocusChange,
+    )?;
+    // mouse sequences are not sent to terminals without mouse reporting
+    if mouse_support() {
+        crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// terminals known to lack mouse reporting (linux console and old serial terminals)
+fn detect_mouse(term: Option<&str>) -> bool {
+    !matches!(term, Some("dumb" | "linux" | "vt100" | "vt102" | "vt220"))
+}
+
+#[inline]
+pub fn mouse_support() -> bool {
+    detect_mouse(std::env::var("TERM").ok().as_deref())
 }
 
 fn graceful_exit() -> std::io::Result<()> {
@@ -274,7 +347,8 @@ fn graceful_exit() -> std::io::Result<()> {
         crossterm::terminal::LeaveAlternateScreen,
         crossterm::terminal::EnableLineWrap,
         crossterm::style::ResetColor,
-        crossterm::event::DisableMouseCapture,
+        DisableMouseCapture,
+        crossterm::event::DisableFocusChange,
         crossterm::cursor::Show,
     )?;
     crossterm::terminal::disable_raw_mode()
@@ -1,6 +1,6 @@
 use crossterm::style::{Attribute, Attributes, ContentStyle};
 
-use super::Color;
+use super::{color::ColorSupport, Color};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Style(ContentStyle);
@@ -97,6 +97,11 @@ impl Style {
         })
     }
 
+    #[inline]
+    pub fn add_italic(&mut self) {
+        self.0.attributes.set(Attribute::Italic);
+    }
+
     #[inline]
     pub fn add_reverse(&mut self) {
         self.0.attributes.set(Attribute::Reverse);
@@ -151,16 +156,27 @@ impl Style {
     }
 }
 
+/// conversion into the terminal style - colors are downgraded if the terminal lacks true color
 impl From<Style> for ContentStyle {
     #[inline]
     fn from(val: Style) -> Self {
-        val.0
+        match ColorSupport::current() {
+            ColorSupport::TrueColor => val.0,
+            support => downgrade(val.0, support),
+        }
     }
 }
 
 impl From<&Style> for ContentStyle {
     #[inline]
     fn from(val: &Style) -> Self {
-        val.0
+        ContentStyle::from(*val)
     }
 }
+
+fn downgrade(mut style: ContentStyle, support: ColorSupport) -> ContentStyle {
+    style.foreground_color = style.foreground_color.map(|color| support.downgrade(color));
+    style.background_color = style.background_color.map(|color| support.downgrade(color));
+    style.underline_color = style.underline_color.map(|color| support.downgrade(color));
+    style
+}
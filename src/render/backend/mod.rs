@@ -1,11 +1,13 @@
 mod crossterm_backend;
+mod screen;
 use super::layout::Rect;
 #[cfg(not(test))]
 pub use crossterm_backend::Backend;
 pub use crossterm_backend::{
-    color::{self, pull_color, serialize_rgb},
-    Color, Style,
+    color::{self, pull_color, serialize_rgb, ColorSupport},
+    mouse_support, Color, Style,
 };
+pub use screen::Screen;
 use std::{
     fmt::Display,
     io::{Result, Write},
@@ -55,6 +57,11 @@ pub trait BackendProtocol: Write + Sized {
     /// restores the style of the writer to default
     fn reset_style(&mut self);
 
+    /// everything printed is fainter until turned off (inactive split pane)
+    fn set_dim(&mut self, dim: bool);
+
+    fn is_dim(&self) -> bool;
+
     /// sends the cursor to location
     fn go_to(&mut self, row: u16, col: u16);
 
@@ -80,6 +87,37 @@ pub trait BackendProtocol: Write + Sized {
 
     /// padding with empty space
     fn pad(&mut self, width: usize);
+
+    /// direct enabling/disabling of mouse reporting - no buffer queing
+    fn mouse_capture(&mut self, enabled: bool);
+
+    /// output is kept until the recording is stopped
+    fn start_recording(&mut self);
+
+    /// draws the recorded output on the screen and writes it out
+    fn stop_recording(&mut self, screen: &mut Screen);
+
+    /// draws the recorded output on the screen only (off-screen render)
+    fn discard_recording(&mut self, screen: &mut Screen);
+
+    /// sets the terminal clipboard (OSC 52), the text is sent base64 encoded
+    fn clipboard_osc52(&mut self, text: &str);
+}
+
+/// standard base64 (with padding) used by OSC 52
+pub fn encode_base64(data: &[u8]) -> String {
+    const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, byte)| bits | (*byte as u32) << (16 - idx * 8));
+        for idx in 0..4 {
+            match idx <= chunk.len() {
+                true => encoded.push(CHARS[(bits >> (18 - idx * 6) & 63) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
 }
 
 #[cfg(test)]
@@ -0,0 +1,259 @@
+use std::rc::Rc;
+use unicode_width::UnicodeWidthChar;
+
+const ESC: char = '\x1b';
+
+/// char on the screen with the style it was printed with (wide chars are followed by empty cell)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Rc<str>,
+}
+
+/// printed cells, the rest of the row is cleared with the fill style
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScreenRow {
+    cells: Vec<Cell>,
+    fill: Rc<str>,
+}
+
+impl ScreenRow {
+    pub fn cell(&self, col: usize) -> Cell {
+        match self.cells.get(col) {
+            Some(cell) => cell.clone(),
+            None => Cell { ch: ' ', style: self.fill.clone() },
+        }
+    }
+
+    fn blank(&self) -> Cell {
+        Cell { ch: ' ', style: self.fill.clone() }
+    }
+}
+
+/// model of terminal state built from recorded output - used to compare renders
+#[derive(Debug, Default)]
+pub struct Screen {
+    rows: Vec<ScreenRow>,
+    row: usize,
+    col: usize,
+    saved: (usize, usize),
+    style: Rc<str>,
+    sgr: Sgr,
+}
+
+impl Screen {
+    pub fn row(&self, row: u16) -> ScreenRow {
+        self.rows.get(row as usize).cloned().unwrap_or_default()
+    }
+
+    /// chars of the row in the column range
+    pub fn text(&self, row: u16, cols: std::ops::Range<usize>) -> String {
+        let row = self.row(row);
+        cols.map(|col| row.cell(col).ch).filter(|ch| *ch != '\0').collect()
+    }
+
+    pub fn go_to(&mut self, row: u16, col: u16) {
+        self.row = row as usize;
+        self.col = col as usize;
+    }
+
+    pub fn save_cursor(&mut self) {
+        self.saved = (self.row, self.col);
+    }
+
+    pub fn restore_cursor(&mut self) {
+        (self.row, self.col) = self.saved;
+    }
+
+    pub fn set_style(&mut self, style: impl Into<Rc<str>>) {
+        self.style = style.into();
+    }
+
+    pub fn print(&mut self, text: &str) {
+        let style = self.style.clone();
+        self.print_with(text, style);
+    }
+
+    pub fn print_with(&mut self, text: &str, style: Rc<str>) {
+        for ch in text.chars() {
+            let width = match UnicodeWidthChar::width(ch) {
+                Some(width) if width != 0 => width,
+                _ => continue,
+            };
+            let col = self.col;
+            let row = self.current_row();
+            while row.cells.len() < col + width {
+                let blank = row.blank();
+                row.cells.push(blank);
+            }
+            row.cells[col] = Cell { ch, style: style.clone() };
+            if width == 2 {
+                row.cells[col + 1] = Cell { ch: '\0', style: style.clone() };
+            }
+            self.col += width;
+        }
+    }
+
+    pub fn clear_to_eol(&mut self) {
+        let (col, style) = (self.col, self.style.clone());
+        let row = self.current_row();
+        while row.cells.len() < col {
+            let blank = row.blank();
+            row.cells.push(blank);
+        }
+        row.cells.truncate(col);
+        row.fill = style;
+    }
+
+    pub fn clear_line(&mut self) {
+        let style = self.style.clone();
+        let row = self.current_row();
+        *row = ScreenRow { cells: Vec::new(), fill: style };
+    }
+
+    pub fn clear_all(&mut self) {
+        let style = self.style.clone();
+        for row in self.rows.iter_mut() {
+            *row = ScreenRow { cells: Vec::new(), fill: style.clone() };
+        }
+    }
+
+    /// draws output written by the terminal backend - only sequences produced by it are understood
+    pub fn replay_ansi(&mut self, bytes: &[u8]) {
+        let text = String::from_utf8_lossy(bytes);
+        let mut chars = text.chars();
+        let mut plain = String::new();
+        while let Some(ch) = chars.next() {
+            if ch != ESC {
+                plain.push(ch);
+                continue;
+            }
+            self.print(&std::mem::take(&mut plain));
+            match chars.next() {
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut command = None;
+                    for ch in chars.by_ref() {
+                        if ch.is_ascii_digit() || ch == ';' || ch == '?' {
+                            params.push(ch);
+                        } else {
+                            command = Some(ch);
+                            break;
+                        }
+                    }
+                    self.csi(&params, command);
+                }
+                Some('7') => self.save_cursor(),
+                Some('8') => self.restore_cursor(),
+                _ => (),
+            }
+        }
+        self.print(&plain);
+    }
+
+    fn csi(&mut self, params: &str, command: Option<char>) {
+        match command {
+            Some('H') => {
+                let mut position = params.split(';').map(|num| num.parse::<u16>().unwrap_or(1));
+                let row = position.next().unwrap_or(1).saturating_sub(1);
+                let col = position.next().unwrap_or(1).saturating_sub(1);
+                self.go_to(row, col);
+            }
+            Some('K') if params.is_empty() || params == "0" => self.clear_to_eol(),
+            Some('K') if params == "2" => self.clear_line(),
+            Some('J') if params == "2" => self.clear_all(),
+            Some('m') => {
+                self.sgr.apply(params);
+                self.style = self.sgr.key().into();
+            }
+            _ => (),
+        }
+    }
+
+    fn current_row(&mut self) -> &mut ScreenRow {
+        if self.rows.len() <= self.row {
+            self.rows.resize_with(self.row + 1, ScreenRow::default);
+        }
+        &mut self.rows[self.row]
+    }
+}
+
+/// graphic rendition state - sequences leading to the same state give the same key
+#[derive(Debug, Default)]
+struct Sgr {
+    fg: Option<String>,
+    bg: Option<String>,
+    underline: Option<String>,
+    attributes: u32,
+}
+
+impl Sgr {
+    fn apply(&mut self, params: &str) {
+        if params.is_empty() {
+            *self = Self::default();
+            return;
+        }
+        let mut params = params.split(';').map(|num| num.parse::<u32>().unwrap_or_default());
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::default(),
+                38 | 48 | 58 => {
+                    let color = match params.next() {
+                        Some(5) => params.next().map(|idx| format!("5;{idx}")),
+                        Some(2) => {
+                            let rgb = params.by_ref().take(3).map(|num| num.to_string()).collect::<Vec<_>>();
+                            Some(format!("2;{}", rgb.join(";")))
+                        }
+                        _ => None,
+                    };
+                    match param {
+                        38 => self.fg = color,
+                        48 => self.bg = color,
+                        _ => self.underline = color,
+                    }
+                }
+                30..=37 | 90..=97 => self.fg = Some(param.to_string()),
+                40..=47 | 100..=107 => self.bg = Some(param.to_string()),
+                39 => self.fg = None,
+                49 => self.bg = None,
+                59 => self.underline = None,
+                1..=9 => self.attributes |= 1 << param,
+                22 => self.attributes &= !(1 << 1 | 1 << 2),
+                23..=29 => self.attributes &= !(1 << (param - 20)),
+                _ => (),
+            }
+        }
+    }
+
+    /// default state has the same key as nothing set
+    fn key(&self) -> String {
+        if self.fg.is_none() && self.bg.is_none() && self.underline.is_none() && self.attributes == 0 {
+            return String::new();
+        }
+        format!("{:?} {:?} {:?} {:b}", self.fg, self.bg, self.underline, self.attributes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Screen;
+
+    #[test]
+    fn replay_ansi() {
+        let mut screen = Screen::default();
+        screen.replay_ansi(b"\x1b[2;3Hab\x1b[38;5;1mcd\x1b[0m\x1b[K");
+        assert_eq!(screen.text(1, 0..6), "  abcd");
+        let row = screen.row(1);
+        assert_eq!(row.cell(2).style, row.cell(6).style);
+        assert_ne!(row.cell(2).style, row.cell(4).style);
+        // same state reached by other sequences
+        let mut other = Screen::default();
+        other.replay_ansi(b"\x1b[2;1H  ab\x1b[1m\x1b[22m\x1b[38;5;1mcd\x1b[0m");
+        assert_eq!(other.row(1).cell(4), row.cell(4));
+        assert_eq!(other.row(1).cell(2), row.cell(2));
+        // wide chars take two cells, clear cuts the row
+        screen.replay_ansi("\x1b[2;1H🚀x\x1b7\x1b[1;1Hy\x1b8\x1b[K".as_bytes());
+        assert_eq!(screen.text(1, 0..6), "🚀x   ");
+        assert_eq!(screen.text(0, 0..2), "y ");
+    }
+}
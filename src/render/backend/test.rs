@@ -1,10 +1,13 @@
 use std::io::Write;
 
-use super::{BackendProtocol, Style};
+use super::{BackendProtocol, Screen, Style};
 
 pub struct Backend {
     pub data: Vec<(Style, String)>,
     pub default_style: Style,
+    /// start of the recorded data
+    pub recording: Option<usize>,
+    pub dim: bool,
 }
 
 impl BackendProtocol for Backend {
@@ -35,7 +38,7 @@ impl BackendProtocol for Backend {
     }
 
     fn init() -> Self {
-        Self { data: Vec::new(), default_style: Style::default() }
+        Self { data: Vec::new(), default_style: Style::default(), recording: None, dim: false }
     }
 
     fn print<D: std::fmt::Display>(&mut self, text: D) {
@@ -64,6 +67,15 @@ impl BackendProtocol for Backend {
         self.data.push((self.default_style, String::from("<<reset style>>")));
     }
 
+    fn set_dim(&mut self, dim: bool) {
+        self.dim = dim;
+        self.data.push((self.default_style, format!("<<dim: {dim}>>")));
+    }
+
+    fn is_dim(&self) -> bool {
+        self.dim
+    }
+
     fn restore_cursor(&mut self) {
         self.data.push((self.default_style, String::from("<<restored cursor>>")))
     }
@@ -107,6 +119,73 @@ impl BackendProtocol for Backend {
     fn pad(&mut self, width: usize) {
         self.data.push((self.default_style, format!("<<padding: {:?}>>", width)))
     }
+
+    fn mouse_capture(&mut self, enabled: bool) {
+        self.data.push((Style::default(), format!("<<mouse capture: {enabled}>>")));
+    }
+
+    fn clipboard_osc52(&mut self, text: &str) {
+        self.data.push((Style::default(), format!("<<osc52: {}>>", super::encode_base64(text.as_bytes()))));
+    }
+
+    fn start_recording(&mut self) {
+        self.recording.get_or_insert(self.data.len());
+    }
+
+    fn stop_recording(&mut self, screen: &mut Screen) {
+        if let Some(start) = self.recording.take() {
+            self.replay(start, screen);
+        }
+    }
+
+    /// recorded data is dropped - as if it was never written
+    fn discard_recording(&mut self, screen: &mut Screen) {
+        if let Some(start) = self.recording.take() {
+            self.replay(start, screen);
+            self.data.truncate(start);
+        }
+    }
+}
+
+impl Backend {
+    fn replay(&self, start: usize, screen: &mut Screen) {
+        for (style, text) in self.data[start..].iter() {
+            let Some(marker) = text.strip_prefix("<<").and_then(|text| text.strip_suffix(">>")) else {
+                screen.print_with(text, style_key(style).into());
+                continue;
+            };
+            if let Some(position) = marker.strip_prefix("go to row: ") {
+                let (row, col) = position.split_once(" col: ").expect("go to marker");
+                screen.go_to(row.parse().expect("row"), col.parse().expect("col"));
+            } else if let Some(width) = marker.strip_prefix("padding: ") {
+                screen.print(&" ".repeat(width.parse().expect("width")));
+            } else {
+                match marker {
+                    "clear EOL" => screen.clear_to_eol(),
+                    "clear line" => screen.clear_line(),
+                    "clear all" => screen.clear_all(),
+                    "saved cursor" => screen.save_cursor(),
+                    "restored cursor" => screen.restore_cursor(),
+                    "set style" | "updated style" | "reset style" => screen.set_style(style_key(style)),
+                    _ if marker.starts_with("style set to")
+                        || marker.starts_with("set bg")
+                        || marker.starts_with("set fg") =>
+                    {
+                        screen.set_style(style_key(style))
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+/// default style has the same key as the terminal screen with nothing set
+fn style_key(style: &Style) -> String {
+    match *style == Style::default() {
+        true => String::new(),
+        false => format!("{style:?}"),
+    }
 }
 
 impl Write for Backend {
@@ -135,6 +135,30 @@ impl State {
         lines.clear_to_end(backend);
     }
 
+    /// renders only the visible window - display is called just for the rendered options
+    pub fn render_list_window<'a, T, S: AsRef<str>>(
+        &mut self,
+        options: &'a [T],
+        display: impl Fn(&'a T) -> S,
+        rect: Rect,
+        backend: &mut Backend,
+    ) {
+        self.update_at_line(rect.height as usize);
+        let mut lines = rect.into_iter();
+        let window = options.get(self.at_line..).unwrap_or_default();
+        for (idx, option) in (self.at_line..).zip(window) {
+            let line = match lines.next() {
+                Some(line) => line,
+                None => break,
+            };
+            match idx == self.selected {
+                true => line.render_styled(display(option).as_ref(), self.highlight, backend),
+                false => line.render(display(option).as_ref(), backend),
+            }
+        }
+        lines.clear_to_end(backend);
+    }
+
     pub fn render_list_padded<'a>(
         &mut self,
         options: impl Iterator<Item = &'a str>,
@@ -155,3 +179,67 @@ impl State {
         lines.clear_to_end(backend);
     }
 }
+
+/// Soft limit on the options reachable in huge lists, moving the selection past the last
+/// shown option reveals the next step instead of wrapping around.
+pub struct SoftLimit {
+    shown: usize,
+}
+
+impl Default for SoftLimit {
+    fn default() -> Self {
+        Self { shown: Self::STEP }
+    }
+}
+
+impl SoftLimit {
+    pub const STEP: usize = 50;
+
+    pub fn reset(&mut self) {
+        self.shown = Self::STEP;
+    }
+
+    #[inline]
+    pub fn shown(&self, option_len: usize) -> usize {
+        std::cmp::min(self.shown, option_len)
+    }
+
+    /// makes sure the option at idx is reachable
+    pub fn reach(&mut self, idx: usize) {
+        while self.shown <= idx {
+            self.shown += Self::STEP;
+        }
+    }
+
+    pub fn next(&mut self, state: &mut State, option_len: usize) {
+        if state.selected + 1 >= self.shown && option_len > self.shown {
+            self.shown += Self::STEP;
+        }
+        state.next(self.shown(option_len));
+    }
+
+    pub fn prev(&mut self, state: &mut State, option_len: usize) {
+        state.prev(self.shown(option_len));
+    }
+
+    /// "showing 50 of 3,214" while not all options are reachable, "+" marks that more may follow
+    pub fn indicator(&self, option_len: usize, more: bool) -> Option<String> {
+        if option_len <= self.shown && !more {
+            return None;
+        }
+        let more = if more { "+" } else { "" };
+        Some(format!(" showing {} of {}{more} ", group_digits(self.shown(option_len)), group_digits(option_len)))
+    }
+}
+
+fn group_digits(number: usize) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx != 0 && (digits.len() - idx) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
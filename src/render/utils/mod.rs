@@ -3,6 +3,42 @@ pub use chunks::{ByteChunks, StrChunks, WriteChunks};
 use std::ops::Range;
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+/// Cut point of text truncated to fit width minus reserved columns (truncation marker)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WidthCut {
+    /// chars before the cut
+    pub chars: usize,
+    /// byte index of the cut
+    pub bytes: usize,
+    /// columns left empty before the reserve, if wide char could not fit flush
+    pub pad: usize,
+}
+
+/// Returns None if the text fits within width, otherwise the cut after which the rendered width
+/// is exactly width - reserve (including pad), so the marker never overwrites half of a wide char.
+pub fn width_cut(text: &str, width: usize, reserve: usize) -> Option<WidthCut> {
+    let limit = width.saturating_sub(reserve);
+    if text.is_ascii() {
+        if text.len() <= width {
+            return None;
+        }
+        return Some(WidthCut { chars: limit, bytes: limit, pad: 0 });
+    }
+    let mut current_width = 0;
+    let mut cut = None;
+    for (chars, (bytes, ch)) in text.char_indices().enumerate() {
+        let char_width = UnicodeWidthChar::width(ch).unwrap_or(0);
+        if cut.is_none() && current_width + char_width > limit {
+            cut = Some(WidthCut { chars, bytes, pad: limit - current_width });
+        }
+        current_width += char_width;
+        if current_width > width {
+            return cut;
+        }
+    }
+    None
+}
+
 /// Trait allowing UTF8 safe operations on str/String
 pub trait UTF8Safe {
     /// returns str that will fit into width of columns, removing chars at the end returning info about remaining width
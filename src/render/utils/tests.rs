@@ -1,6 +1,6 @@
 use crate::render::utils::chunks::ByteChunks;
 
-use super::{StrChunks, UTF8Safe, UTF8SafeStringExt, WriteChunks};
+use super::{width_cut, StrChunks, UTF8Safe, UTF8SafeStringExt, WidthCut, WriteChunks};
 const TEXT: &str = "123🚀13";
 
 #[test]
@@ -230,3 +230,24 @@ fn test_chunks_byte_short() {
     assert_eq!(chunks.next(), Some(StrChunks { width: 3, text: "123" }));
     assert_eq!(chunks.next(), None);
 }
+
+#[test]
+fn test_width_cut() {
+    assert_eq!(width_cut("12345", 5, 2), None);
+    assert_eq!(width_cut("123456", 5, 2), Some(WidthCut { chars: 3, bytes: 3, pad: 0 }));
+    // wide char fits exactly
+    assert_eq!(width_cut("1234🚀", 6, 2), None);
+    assert_eq!(width_cut("12🚀45", 5, 2), Some(WidthCut { chars: 2, bytes: 2, pad: 1 }));
+    assert_eq!(width_cut("1🚀345", 5, 2), Some(WidthCut { chars: 2, bytes: 5, pad: 0 }));
+    assert_eq!(width_cut("🚀🚀🚀", 5, 2), Some(WidthCut { chars: 1, bytes: 4, pad: 1 }));
+    assert_eq!(width_cut("🚀🚀🚀", 5, 0), Some(WidthCut { chars: 2, bytes: 8, pad: 1 }));
+    assert_eq!(width_cut("🚀🚀", 1, 2), Some(WidthCut { chars: 0, bytes: 0, pad: 0 }));
+    for text in ["12🚀45🚀🚀x", "🚀1🚀1🚀1🚀1", "abcdefghij"] {
+        for width in 2..10 {
+            if let Some(cut) = width_cut(text, width, 2) {
+                assert_eq!(text[..cut.bytes].width() + cut.pad, width - 2);
+                assert_eq!(text[..cut.bytes].char_len(), cut.chars);
+            }
+        }
+    }
+}
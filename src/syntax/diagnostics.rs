@@ -6,7 +6,7 @@ use crate::workspace::line::EditorLine;
 use lsp_types::{Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity};
 
 const ELS_COLOR: Color = color::dark_grey();
-const ERR_COLOR: Color = color::red();
+pub const ERR_COLOR: Color = color::red();
 const WAR_COLOR: Color = color::yellow();
 
 #[derive(Default)]
@@ -18,12 +18,14 @@ pub struct DiagnosticInfo {
 #[derive(Clone)]
 pub enum Action {
     Import(String),
+    RetryResolve,
 }
 
 impl From<Action> for IdiomEvent {
     fn from(value: Action) -> Self {
         match value {
             Action::Import(text) => IdiomEvent::InsertText(text),
+            Action::RetryResolve => IdiomEvent::RetryCompletionResolve,
         }
     }
 }
@@ -35,6 +37,7 @@ impl std::fmt::Display for Action {
                 Some(stripped_text) => write!(f, "import {stripped_text}"),
                 None => write!(f, "import {text}"),
             },
+            Self::RetryResolve => f.write_str("retry completion edits"),
         }
     }
 }
@@ -46,23 +49,22 @@ pub struct DiagnosticData {
     pub inline_text: String,
     pub message: String,
     pub info: Option<Vec<DiagnosticRelatedInformation>>,
+    /// as received - sent back as context of code actions
+    pub lsp: Diagnostic,
 }
 
 impl DiagnosticData {
-    fn new(
-        range: lsp_types::Range,
-        message: String,
-        color: Color,
-        info: Option<Vec<DiagnosticRelatedInformation>>,
-    ) -> Self {
-        let inline_text = message.lines().next().map(|s| format!("    {s}")).unwrap_or_default();
+    fn new(diagnostic: Diagnostic, color: Color) -> Self {
+        let range = diagnostic.range;
+        let inline_text = diagnostic.message.lines().next().map(|s| format!("    {s}")).unwrap_or_default();
         Self {
             start: range.start.character as usize,
             end: if range.start.line == range.end.line { Some(range.end.character as usize) } else { None },
             color,
             inline_text,
-            message,
-            info,
+            message: diagnostic.message.clone(),
+            info: diagnostic.related_information.clone(),
+            lsp: diagnostic,
         }
     }
 
@@ -112,6 +114,14 @@ impl DiagnosticLine {
         }
     }
 
+    /// color of the most severe diagnostic, if it is an error or a warning
+    pub fn marker_color(&self) -> Option<Color> {
+        if self.data.iter().any(|d| d.color == ERR_COLOR) {
+            return Some(ERR_COLOR);
+        }
+        self.data.iter().any(|d| d.color == WAR_COLOR).then_some(WAR_COLOR)
+    }
+
     pub fn drop_non_errs(&mut self) {
         self.data.retain(|d| d.color == ERR_COLOR);
     }
@@ -119,18 +129,18 @@ impl DiagnosticLine {
     pub fn append(&mut self, d: Diagnostic) {
         match d.severity {
             Some(DiagnosticSeverity::ERROR) => {
-                self.data.insert(0, DiagnosticData::new(d.range, d.message, ERR_COLOR, d.related_information));
+                self.data.insert(0, DiagnosticData::new(d, ERR_COLOR));
             }
             Some(DiagnosticSeverity::WARNING) => match self.data[0].color {
                 ELS_COLOR => {
-                    self.data.insert(0, DiagnosticData::new(d.range, d.message, WAR_COLOR, d.related_information));
+                    self.data.insert(0, DiagnosticData::new(d, WAR_COLOR));
                 }
                 _ => {
-                    self.data.insert(0, DiagnosticData::new(d.range, d.message, WAR_COLOR, d.related_information));
+                    self.data.insert(0, DiagnosticData::new(d, WAR_COLOR));
                 }
             },
             _ => {
-                self.data.push(DiagnosticData::new(d.range, d.message, ELS_COLOR, d.related_information));
+                self.data.push(DiagnosticData::new(d, ELS_COLOR));
             }
         }
     }
@@ -138,19 +148,16 @@ impl DiagnosticLine {
 
 impl From<Diagnostic> for DiagnosticLine {
     fn from(diagnostic: Diagnostic) -> Self {
-        let color = match diagnostic.severity {
-            Some(DiagnosticSeverity::ERROR) => ERR_COLOR,
-            Some(DiagnosticSeverity::WARNING) => WAR_COLOR,
-            _ => ELS_COLOR,
-        };
-        Self {
-            data: vec![DiagnosticData::new(
-                diagnostic.range,
-                diagnostic.message,
-                color,
-                diagnostic.related_information,
-            )],
-        }
+        let color = severity_color(diagnostic.severity);
+        Self { data: vec![DiagnosticData::new(diagnostic, color)] }
+    }
+}
+
+pub fn severity_color(severity: Option<DiagnosticSeverity>) -> Color {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => ERR_COLOR,
+        Some(DiagnosticSeverity::WARNING) => WAR_COLOR,
+        _ => ELS_COLOR,
     }
 }
 
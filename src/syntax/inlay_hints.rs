@@ -0,0 +1,137 @@
+use crate::render::UTF8Safe;
+use crate::workspace::line::EditorLine;
+use lsp_types::{InlayHint, InlayHintLabel};
+use std::ops::Range;
+
+/// longer labels are cut with ellipsis (types can be very long)
+const MAX_LABEL_WIDTH: usize = 40;
+
+/// Virtual text shown within the line, it is not part of the content and does not take char positions
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineHint {
+    /// rendered before the char at the index (after the content if equal to the line len)
+    pub char_idx: usize,
+    /// label with the padding
+    pub text: String,
+    pub width: usize,
+}
+
+impl LineHint {
+    fn new(hint: InlayHint, line: &EditorLine, char_lsp_pos: fn(char) -> usize) -> Option<Self> {
+        let mut label = match hint.label {
+            InlayHintLabel::String(text) => text,
+            InlayHintLabel::LabelParts(parts) => parts.into_iter().map(|part| part.value).collect(),
+        };
+        label = label.chars().map(|ch| if ch.is_control() { ' ' } else { ch }).collect();
+        if label.trim().is_empty() {
+            return None;
+        }
+        if label.width() > MAX_LABEL_WIDTH {
+            let (_, cut) = label.truncate_width(MAX_LABEL_WIDTH - 1);
+            label = format!("{cut}…");
+        }
+        let mut text = String::new();
+        if hint.padding_left == Some(true) {
+            text.push(' ');
+        }
+        text.push_str(&label);
+        if hint.padding_right == Some(true) {
+            text.push(' ');
+        }
+        let char_idx = char_idx(line, hint.position.character as usize, char_lsp_pos);
+        Some(Self { char_idx, width: text.width(), text })
+    }
+}
+
+/// replaces the hints on the requested lines, hints outside of them are ignored
+pub fn set_inlay_hints(
+    mut hints: Vec<InlayHint>,
+    lines: Range<usize>,
+    char_lsp_pos: fn(char) -> usize,
+    content: &mut [EditorLine],
+) {
+    hints.retain(|hint| lines.contains(&(hint.position.line as usize)));
+    hints.sort_by_key(|hint| (hint.position.line, hint.position.character));
+    let mut hints = hints.into_iter().peekable();
+    let end = lines.end.min(content.len());
+    for (line_idx, line) in content.iter_mut().enumerate().take(end).skip(lines.start) {
+        let mut line_hints = Vec::new();
+        while let Some(hint) = hints.next_if(|hint| hint.position.line as usize == line_idx) {
+            line_hints.extend(LineHint::new(hint, line, char_lsp_pos));
+        }
+        line.set_inlay_hints(line_hints);
+    }
+}
+
+/// char index of the LSP position (clamped to the line len)
+fn char_idx(line: &EditorLine, lsp_char: usize, char_lsp_pos: fn(char) -> usize) -> usize {
+    let mut lsp_idx = 0;
+    for (idx, ch) in line.chars().enumerate() {
+        if lsp_idx >= lsp_char {
+            return idx;
+        }
+        lsp_idx += char_lsp_pos(ch);
+    }
+    line.char_len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{set_inlay_hints, LineHint};
+    use crate::syntax::lsp_calls::char_lsp_utf16;
+    use crate::workspace::line::EditorLine;
+    use lsp_types::{InlayHint, InlayHintLabel, InlayHintLabelPart, Position};
+
+    fn hint(line: u32, character: u32, label: InlayHintLabel) -> InlayHint {
+        InlayHint {
+            position: Position::new(line, character),
+            label,
+            kind: None,
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: None,
+            data: None,
+        }
+    }
+
+    #[test]
+    fn hints_by_line() {
+        let mut content: Vec<EditorLine> = vec![
+            "fn main() {".into(),
+            "    let 𝔁 = 1;".into(),
+            "    f(𝔁, 2);".into(),
+            "}".into(),
+        ];
+        let parts = vec![
+            InlayHintLabelPart { value: "a".into(), ..Default::default() },
+            InlayHintLabelPart { value: ":".into(), ..Default::default() },
+        ];
+        let mut param = hint(2, 10, InlayHintLabel::LabelParts(parts));
+        param.padding_right = Some(true);
+        let hints = vec![
+            param,
+            hint(1, 10, InlayHintLabel::String(": i32".into())),
+            hint(2, 6, InlayHintLabel::String("a:".into())),
+            hint(0, 0, InlayHintLabel::String("outside".into())),
+            hint(3, 0, InlayHintLabel::String("\n".into())),
+        ];
+        content[3].set_inlay_hints(vec![LineHint { char_idx: 0, text: "old".into(), width: 3 }]);
+        set_inlay_hints(hints, 1..4, char_lsp_utf16, &mut content);
+        assert!(content[0].inlay_hints.is_empty());
+        // UTF-16 positions are mapped to chars
+        assert_eq!(content[1].inlay_hints, vec![LineHint { char_idx: 9, text: ": i32".into(), width: 5 }]);
+        assert_eq!(
+            content[2].inlay_hints,
+            vec![
+                LineHint { char_idx: 6, text: "a:".into(), width: 2 },
+                LineHint { char_idx: 9, text: "a: ".into(), width: 3 },
+            ]
+        );
+        // replaced by empty
+        assert!(content[3].inlay_hints.is_empty());
+        // edits drop the hints of the line
+        content[2].insert(0, ' ');
+        assert!(content[2].inlay_hints.is_empty());
+    }
+}
@@ -20,6 +20,8 @@ type DiagnosticHandler = Option<fn(&Lang, &Vec<DiagnosticRelatedInformation>) ->
 pub struct Lang {
     pub file_type: FileType,
     pub compl_trigger_chars: String,
+    /// set from the LSP signature help capability
+    pub sig_trigger_chars: String,
     comment_start: Vec<&'static str>,
     declaration: Vec<&'static str>,
     key_words: Vec<&'static str>,
@@ -37,6 +39,29 @@ impl Lang {
         self.declaration.contains(&token) || self.key_words.contains(&token)
     }
 
+    /// the name can be given to a symbol (rename) - any word is accepted for file types without identifiers
+    pub fn is_identifier(&self, name: &str) -> bool {
+        let extra = match self.file_type {
+            FileType::Ignored | FileType::Html | FileType::Yml | FileType::Toml | FileType::Json => {
+                return !name.is_empty() && !name.contains(char::is_whitespace);
+            }
+            FileType::JavaScript | FileType::TypeScript => "$",
+            _ => "",
+        };
+        let raw = match self.file_type {
+            FileType::Rust => name.strip_prefix("r#"),
+            _ => None,
+        };
+        let is_ident_char = |ch: char| ch.is_alphanumeric() || ch == '_' || extra.contains(ch);
+        let ident = raw.unwrap_or(name);
+        let Some(first) = ident.chars().next() else {
+            return false;
+        };
+        !first.is_numeric()
+            && ident.chars().all(is_ident_char)
+            && (raw.is_some() || !self.is_keyword(ident) && !self.is_flow(ident))
+    }
+
     pub fn is_flow(&self, token: &str) -> bool {
         self.flow_control.contains(&token)
     }
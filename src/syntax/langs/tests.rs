@@ -67,3 +67,24 @@ fn test_completable() {
     let line = EditorLine::from("struct Um");
     assert!(!lang.completable(&line, 9));
 }
+
+#[test]
+fn test_identifier() {
+    let rust = Lang::from(FileType::Rust);
+    assert!(rust.is_identifier("new_name"));
+    assert!(rust.is_identifier("_private2"));
+    assert!(rust.is_identifier("r#fn"));
+    assert!(!rust.is_identifier("fn"));
+    assert!(!rust.is_identifier("2name"));
+    assert!(!rust.is_identifier("new name"));
+    assert!(!rust.is_identifier(""));
+    assert!(!rust.is_identifier("$name"));
+    let js = Lang::from(FileType::JavaScript);
+    assert!(js.is_identifier("$name"));
+    assert!(!js.is_identifier("r#name"));
+    let python = Lang::from(FileType::Python);
+    assert!(python.is_identifier("名前"));
+    assert!(!python.is_identifier("def"));
+    let toml = Lang::from(FileType::Toml);
+    assert!(toml.is_identifier("some-key"));
+}
@@ -1,23 +1,66 @@
 use crate::{
     global_state::{GlobalState, IdiomEvent},
     lsp::{LSPClient, LSPResponse, LSPResponseType, LSPResult},
-    popups::popups_tree::refrence_selector,
-    syntax::Lexer,
+    popups::{
+        popup_outline::{outline_from_response, OutlinePopup},
+        popups_editor::code_actions_selector,
+    },
+    render::backend::color,
+    syntax::{Action, DiagnosticInfo, Lexer},
     workspace::{actions::EditType, line::EditorLine, CursorPosition, Editor},
 };
 use core::str::FromStr;
 use lsp_types::{
+    CodeActionOrCommand, CodeActionProviderCapability, CompletionItem, Diagnostic, FormattingOptions, OneOf, Position,
     Range, SemanticTokensRangeResult, SemanticTokensResult, SemanticTokensServerCapabilities,
     TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, Uri,
 };
-use std::path::Path;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use super::{
-    modal::LSPModal,
+    inlay_hints::set_inlay_hints,
+    modal::{first_location, LSPModal},
     set_diganostics,
     tokens::{set_tokens, set_tokens_partial},
 };
 
+/// time to wait for the resolved completion before offering retry
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(3);
+/// time the save waits for formatting before the file is written as is
+const FORMAT_SAVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Completion inserted ahead of the server response - additional edits (imports) are applied once resolved
+pub struct CompletionResolve {
+    item: CompletionItem,
+    /// undo group of the inserted completion, set when the insert is done
+    group: Option<usize>,
+    /// pending request id and time sent, None if failed (waiting for retry)
+    request: Option<(i64, Instant)>,
+}
+
+impl CompletionResolve {
+    pub fn bind(&mut self, group: usize) {
+        if self.group.is_none() {
+            self.group = Some(group);
+        }
+    }
+
+    pub fn is_undone(&self, done_len: usize) -> bool {
+        self.group.is_some_and(|group| group >= done_len)
+    }
+
+    fn is_pending(&self, id: i64) -> bool {
+        matches!(self.request, Some((pending_id, ..)) if pending_id == id)
+    }
+
+    fn timed_out(&self) -> Option<i64> {
+        self.request.filter(|(_, sent)| sent.elapsed() > RESOLVE_TIMEOUT).map(|(id, ..)| id)
+    }
+}
+
 /// maps LSP state without runtime checks
 #[inline]
 pub fn map_lsp(lexer: &mut Lexer, client: LSPClient) {
@@ -28,6 +71,11 @@ pub fn map_lsp(lexer: &mut Lexer, client: LSPClient) {
     if let Some(provider) = client.capabilities.completion_provider.as_ref() {
         lexer.autocomplete = get_autocomplete;
         lexer.completable = completable;
+        if provider.resolve_provider == Some(true) {
+            lexer.accept_completion = accept_completion_resolve;
+        } else {
+            lexer.accept_completion = accept_completion;
+        }
         if let Some(chars) = provider.trigger_characters.as_ref() {
             if !chars.is_empty() {
                 lexer.lang.compl_trigger_chars.clear();
@@ -66,6 +114,13 @@ pub fn map_lsp(lexer: &mut Lexer, client: LSPClient) {
         lexer.references = info_position_dead;
     }
 
+    // document symbols
+    if client.capabilities.document_symbol_provider.as_ref().is_some_and(is_enabled) {
+        lexer.document_symbols = document_symbols;
+    } else {
+        lexer.document_symbols = document_symbols_dead;
+    }
+
     // declarations
     if client.capabilities.declaration_provider.is_some() {
         lexer.declarations = declarations;
@@ -81,6 +136,33 @@ pub fn map_lsp(lexer: &mut Lexer, client: LSPClient) {
         lexer.start_renames = start_renames_dead;
     }
 
+    // formatting
+    if client.capabilities.document_formatting_provider.as_ref().is_some_and(is_enabled) {
+        lexer.format_document = format_document;
+        lexer.format_save = format_save;
+    } else {
+        lexer.format_document = format_document_dead;
+        lexer.format_save = format_save_dead;
+    }
+    if client.capabilities.document_range_formatting_provider.as_ref().is_some_and(is_enabled) {
+        lexer.format_range = format_range;
+    } else {
+        lexer.format_range = format_range_dead;
+    }
+
+    // code actions
+    match client.capabilities.code_action_provider.as_ref() {
+        None | Some(CodeActionProviderCapability::Simple(false)) => lexer.code_actions = code_actions_dead,
+        Some(..) => lexer.code_actions = code_actions,
+    }
+
+    // inlay hints
+    if client.capabilities.inlay_hint_provider.as_ref().is_some_and(is_enabled) {
+        lexer.inlay_hints = inlay_hints;
+    } else {
+        lexer.inlay_hints = inlay_hints_dead;
+    }
+
     // hover
     if client.capabilities.hover_provider.is_some() {
         lexer.hover = hover;
@@ -89,8 +171,16 @@ pub fn map_lsp(lexer: &mut Lexer, client: LSPClient) {
     }
 
     // sig help
-    if client.capabilities.signature_help_provider.is_some() {
+    if let Some(provider) = client.capabilities.signature_help_provider.as_ref() {
         lexer.signatures = signatures;
+        let triggers = provider.trigger_characters.iter().chain(provider.retrigger_characters.iter()).flatten();
+        lexer.lang.sig_trigger_chars = triggers.map(String::as_str).collect();
+        if lexer.lang.sig_trigger_chars.is_empty() {
+            lexer.lang.sig_trigger_chars.push_str("(,");
+        }
+    } else {
+        lexer.signatures = info_position_dead;
+        lexer.lang.sig_trigger_chars.clear();
     }
 
     // document syncing
@@ -138,6 +228,8 @@ pub fn remove_lsp(lexer: &mut Lexer) {
     lexer.context = context_local;
     lexer.completable = completable_dead;
     lexer.autocomplete = get_autocomplete_dead;
+    lexer.accept_completion = accept_completion;
+    lexer.completion_resolve = None;
     lexer.tokens = tokens_dead;
     lexer.tokens_partial = tokens_partial_dead;
     lexer.references = info_position_dead;
@@ -145,8 +237,19 @@ pub fn remove_lsp(lexer: &mut Lexer) {
     lexer.declarations = info_position_dead;
     lexer.hover = info_position_dead;
     lexer.signatures = info_position_dead;
+    lexer.lang.sig_trigger_chars.clear();
     lexer.start_renames = start_renames_dead;
     lexer.renames = renames_dead;
+    lexer.format_document = format_document_dead;
+    lexer.format_range = format_range_dead;
+    lexer.format_save = format_save_dead;
+    lexer.code_actions = code_actions_dead;
+    lexer.quick_fix = None;
+    lexer.inlay_hints = inlay_hints_dead;
+    lexer.inlay_hints_for = None;
+    lexer.document_symbols = document_symbols_dead;
+    lexer.outline = None;
+    lexer.outline_pending = false;
     lexer.sync = sync_edits_dead;
     lexer.sync_rev = sync_edits_dead_rev;
     lexer.encode_position = encode_pos_utf32;
@@ -156,6 +259,10 @@ pub fn remove_lsp(lexer: &mut Lexer) {
 pub fn context_local(_: &mut Editor, _: &mut GlobalState) {}
 
 pub fn context(editor: &mut Editor, gs: &mut GlobalState) {
+    if let Some(modal_rect) = editor.lexer.score_modal_completions(gs) {
+        editor.updated_rect(modal_rect, gs);
+    }
+
     let lexer = &mut editor.lexer;
     let client = &mut lexer.client;
     let content = &mut editor.content;
@@ -170,6 +277,7 @@ pub fn context(editor: &mut Editor, gs: &mut GlobalState) {
     let (editor_diagnostics, tree_diagnostics) = client.get_diagnostics(&lexer.uri);
     if let Some(diagnostics) = editor_diagnostics {
         set_diganostics(content, diagnostics);
+        lexer.diagnostic_updates = lexer.diagnostic_updates.wrapping_add(1);
         lexer.modal_rect.take(); // force rebuild
     }
 
@@ -178,34 +286,74 @@ pub fn context(editor: &mut Editor, gs: &mut GlobalState) {
     }
 
     // responses
+    let mut resolved = None;
+    let mut formatted = None;
+    let mut replaced_modal = None;
+    let mut resolve_failed = false;
     if let Some(mut responses) = client.get_responses() {
         let unresolved_requests = &mut lexer.requests;
         for request in std::mem::take(unresolved_requests) {
+            // resolved by save_formatted
+            if is_save_format(lexer.save_after_format, *request.id()) {
+                unresolved_requests.push(request);
+                continue;
+            }
             if let Some(response) = responses.remove(request.id()) {
                 match request.parse(response.result) {
                     Some(result) => match result {
                         LSPResponse::Completion(completions, line, c) => {
                             if editor.cursor.line == c.line {
-                                lexer.modal = LSPModal::auto_complete(completions, line, c);
+                                // takes the place of signature help
+                                replaced_modal = replaced_modal.or(lexer.modal_rect.take());
+                                let replaced = lexer.modal.take();
+                                lexer.modal = LSPModal::auto_complete(completions, line, c, replaced);
+                            }
+                        }
+                        LSPResponse::CompletionResolve(item) => {
+                            if lexer.completion_resolve.as_ref().is_some_and(|r| r.is_pending(*request.id())) {
+                                resolved = Some(item);
                             }
                         }
                         LSPResponse::Hover(hover) => {
+                            replaced_modal = replaced_modal.or(lexer.modal_rect.take());
                             if let Some(modal) = lexer.modal.as_mut() {
                                 modal.hover_map(hover, &lexer.theme);
                             } else {
                                 lexer.modal.replace(LSPModal::from_hover(hover, &lexer.theme));
                             }
                         }
-                        LSPResponse::SignatureHelp(signature) => {
-                            if let Some(modal) = lexer.modal.as_mut() {
-                                modal.signature_map(signature, &lexer.theme);
-                            } else {
-                                lexer.modal.replace(LSPModal::from_signature(signature, &lexer.theme));
+                        LSPResponse::SignatureHelp(signature) => match lexer.modal.as_mut() {
+                            // rename and peek keep the space
+                            Some(LSPModal::RenameVar(..) | LSPModal::Peek(..)) => (),
+                            Some(modal @ LSPModal::AutoComplete(..)) => {
+                                replaced_modal = replaced_modal.or(lexer.modal_rect.take());
+                                modal.completion_signature(signature);
                             }
-                        }
+                            Some(LSPModal::Info(modal)) => modal.push_signature(signature, &lexer.theme),
+                            Some(LSPModal::Signature(..)) | None => {
+                                replaced_modal = replaced_modal.or(lexer.modal_rect.take());
+                                lexer.modal = LSPModal::from_signature(signature);
+                            }
+                        },
                         LSPResponse::Renames(workspace_edit) => {
-                            gs.event.push(workspace_edit.into());
+                            gs.event.push(IdiomEvent::RenamePreview(workspace_edit));
+                        }
+                        LSPResponse::Formatting { edits, version } => match version == lexer.version {
+                            true => formatted = Some(edits),
+                            false => gs.error("Formatting dropped, the text was changed"),
+                        },
+                        LSPResponse::CodeAction(actions) => {
+                            let quick_fix = lexer.quick_fix == Some(*request.id());
+                            if quick_fix {
+                                lexer.quick_fix = None;
+                            }
+                            match preferred_action(&actions).filter(|_| quick_fix) {
+                                Some(action) => gs.event.push(IdiomEvent::CodeAction(Box::new(action.clone()))),
+                                None if actions.is_empty() => gs.message("No code actions available (LSP)"),
+                                None => gs.popup(code_actions_selector(actions)),
+                            }
                         }
+                        LSPResponse::ExecuteCommand => (),
                         LSPResponse::Tokens(tokens) => {
                             match tokens {
                                 SemanticTokensResult::Partial(data) => {
@@ -224,23 +372,52 @@ pub fn context(editor: &mut Editor, gs: &mut GlobalState) {
                             };
                             set_tokens_partial(tokens, max_lines, &lexer.legend, content);
                         }
+                        // outdated hints are requested again with the current version
+                        LSPResponse::InlayHints { hints, version, lines } => {
+                            if version == lexer.version && editor.show_inlay_hints {
+                                set_inlay_hints(hints, lines, lexer.char_lsp_pos, content);
+                            }
+                        }
+                        // listed with previews from all open editors
                         LSPResponse::References(locations) => {
-                            if let Some(mut locations) = locations {
-                                if locations.len() == 1 {
-                                    gs.event.push(locations.remove(0).into());
-                                } else {
-                                    gs.popup(refrence_selector(locations));
-                                }
+                            gs.event.push(IdiomEvent::References(locations.unwrap_or_default()));
+                        }
+                        // requested by the symbol search popup
+                        LSPResponse::WorkspaceSymbols(..) => (),
+                        LSPResponse::DocumentSymbols(symbols) => {
+                            let outline = outline_from_response(symbols);
+                            if std::mem::take(&mut lexer.outline_pending) {
+                                gs.popup(OutlinePopup::boxed(&outline, content, true));
                             }
+                            lexer.outline = Some(outline);
                         }
                         LSPResponse::Declaration(declaration) => {
                             gs.try_tree_event(declaration);
                         }
-                        LSPResponse::Definition(definition) => {
-                            gs.try_tree_event(definition);
-                        }
+                        // definitions in other files are peeked by the workspace (the file can be open)
+                        LSPResponse::Definition(definition) => match first_location(definition) {
+                            Some(location) if location.uri == lexer.uri => {
+                                match LSPModal::peek_content(location, content, true) {
+                                    Some(modal) => {
+                                        replaced_modal = replaced_modal.or(lexer.modal_rect.take());
+                                        lexer.modal.replace(modal);
+                                    }
+                                    None => gs.message("No definition found (LSP)"),
+                                }
+                            }
+                            Some(location) => gs.event.push(IdiomEvent::PeekDefinition(location)),
+                            None => gs.message("No definition found (LSP)"),
+                        },
                     },
                     None => {
+                        if let Some(resolve) = lexer.completion_resolve.as_mut().filter(|r| r.is_pending(*request.id()))
+                        {
+                            resolve.request = None;
+                            resolve_failed = true;
+                        }
+                        if matches!(request, LSPResponseType::DocumentSymbol(..)) {
+                            lexer.outline_pending = false;
+                        }
                         if let Some(err) = response.error {
                             gs.error(format!("{request}: {err}"));
                         }
@@ -255,9 +432,43 @@ pub fn context(editor: &mut Editor, gs: &mut GlobalState) {
         }
     }
 
+    // completion resolve
+    if let Some(id) = lexer.completion_resolve.as_ref().and_then(CompletionResolve::timed_out) {
+        lexer.requests.retain(|request| request.id() != &id);
+        resolve_failed = true;
+    }
+    if resolve_failed {
+        resolve_failure(lexer, gs);
+    }
+    if let Some(item) = resolved {
+        let group = lexer.completion_resolve.take().and_then(|resolve| resolve.group);
+        let edits = item.additional_text_edits.unwrap_or_default();
+        if group.is_some_and(|group| !editor.apply_edits_into_group(group, edits)) {
+            gs.error(format!("Unable to apply completion edits ({}), the text was changed", item.label));
+        }
+    }
+    if let Some(modal_rect) = replaced_modal {
+        editor.updated_rect(modal_rect, gs);
+    }
+    match formatted {
+        Some(edits) if edits.is_empty() => gs.message("Already formatted"),
+        Some(edits) => {
+            editor.cursor.select_drop();
+            editor.apply_file_edits(edits);
+            gs.success("Formatted");
+        }
+        None => (),
+    }
+
+    if editor.show_inlay_hints {
+        let view = editor.cursor.at_line..editor.folds.line_at_row(editor.cursor.at_line, editor.cursor.max_rows);
+        editor.lexer.inlay_hints(view, &editor.content, gs);
+    }
+
+    let lexer = &mut editor.lexer;
     if let Some(meta) = lexer.meta.take() {
         let max_lines = (meta.start_line + meta.to) - 1;
-        if max_lines >= content.len() {
+        if max_lines >= editor.content.len() {
             return;
         }
         match (lexer.tokens_partial)(lexer, meta.into(), max_lines) {
@@ -346,6 +557,65 @@ pub fn get_autocomplete(lexer: &mut Lexer, c: CursorPosition, line: String, gs:
     }
 }
 
+pub fn accept_completion(lexer: &mut Lexer, mut item: CompletionItem, gs: &mut GlobalState) {
+    if let Some(data) = item.data.take() {
+        lexer.lang.handle_completion_data(data, gs);
+    };
+    push_completion(item, gs);
+}
+
+/// inserts the completion right away, additional edits follow once the server resolves the item
+/// items carrying the edits already are not resolved
+pub fn accept_completion_resolve(lexer: &mut Lexer, item: CompletionItem, gs: &mut GlobalState) {
+    if item.additional_text_edits.as_ref().is_some_and(|edits| !edits.is_empty()) {
+        return push_completion(item, gs);
+    }
+    gs.event.push(item.clone().into());
+    lexer.completion_resolve = Some(CompletionResolve { item, group: None, request: None });
+    send_resolve(lexer, gs);
+}
+
+/// completion insert followed by its additional edits (if any) - events are handled last pushed first
+fn push_completion(mut item: CompletionItem, gs: &mut GlobalState) {
+    if let Some(edits) = item.additional_text_edits.take().filter(|edits| !edits.is_empty()) {
+        gs.event.push(IdiomEvent::CompletionEdits(edits));
+    }
+    gs.event.push(item.into());
+}
+
+pub fn send_resolve(lexer: &mut Lexer, gs: &mut GlobalState) {
+    let Some(resolve) = lexer.completion_resolve.as_mut().filter(|resolve| resolve.request.is_none()) else {
+        return;
+    };
+    match lexer.client.request_completion_resolve(resolve.item.clone()) {
+        Ok(id) => {
+            resolve.request = Some((id, Instant::now()));
+            lexer.requests.push(LSPResponseType::CompletionResolve(id));
+        }
+        Err(err) => {
+            lexer.completion_resolve = None;
+            gs.send_error(err, lexer.lang.file_type);
+        }
+    }
+}
+
+/// notice with retry action, the inserted completion is kept as it is
+fn resolve_failure(lexer: &mut Lexer, gs: &mut GlobalState) {
+    let Some(resolve) = lexer.completion_resolve.as_mut() else {
+        return;
+    };
+    resolve.request = None;
+    let message = format!("Completion edits not received ({})", resolve.item.label);
+    if lexer.modal.is_none() {
+        let info = DiagnosticInfo {
+            messages: vec![(message.to_owned(), color::yellow())],
+            actions: Some(vec![Action::RetryResolve]),
+        };
+        lexer.modal.replace(LSPModal::actions(info));
+    }
+    gs.error(message);
+}
+
 pub fn completable_dead(_lexer: &Lexer, _idx: usize, _line: &EditorLine) -> bool {
     false
 }
@@ -378,12 +648,38 @@ pub fn tokens_partial_dead(_: &mut Lexer, _: Range, _: usize) -> LSPResult<LSPRe
 pub fn info_position_dead(_: &mut Lexer, _: CursorPosition, _: &mut GlobalState) {}
 
 pub fn references(lexer: &mut Lexer, c: CursorPosition, gs: &mut GlobalState) {
+    // superseded request is cancelled, only the latest results are listed
+    let client = &mut lexer.client;
+    lexer.requests.retain(|request| match request {
+        LSPResponseType::References(id) => {
+            let _ = client.cancel_request(*id);
+            false
+        }
+        _ => true,
+    });
     match lexer.client.request_references(lexer.uri.clone(), c).map(LSPResponseType::References) {
         Ok(request) => lexer.requests.push(request),
         Err(err) => gs.send_error(err, lexer.lang.file_type),
     }
 }
 
+pub fn document_symbols_dead(_: &mut Lexer, _: &mut GlobalState) -> bool {
+    false
+}
+
+pub fn document_symbols(lexer: &mut Lexer, gs: &mut GlobalState) -> bool {
+    match lexer.client.request_document_symbols(lexer.uri.clone()) {
+        Ok(id) => {
+            lexer.requests.push(LSPResponseType::DocumentSymbol(id));
+            true
+        }
+        Err(err) => {
+            gs.send_error(err, lexer.lang.file_type);
+            false
+        }
+    }
+}
+
 pub fn definitions(lexer: &mut Lexer, c: CursorPosition, gs: &mut GlobalState) {
     match lexer.client.request_definitions(lexer.uri.clone(), c).map(LSPResponseType::Definition) {
         Ok(request) => lexer.requests.push(request),
@@ -427,8 +723,162 @@ pub fn renames(lexer: &mut Lexer, c: CursorPosition, new_name: String, gs: &mut
     }
 }
 
+pub fn format_document_dead(_: &mut Lexer, _: FormattingOptions, gs: &mut GlobalState) {
+    gs.message("Formatting is not supported (LSP)");
+}
+
+pub fn format_document(lexer: &mut Lexer, options: FormattingOptions, gs: &mut GlobalState) {
+    let version = lexer.version;
+    match lexer.client.request_formatting(lexer.uri.clone(), options) {
+        Ok(id) => lexer.requests.push(LSPResponseType::Formatting { id, version }),
+        Err(err) => gs.send_error(err, lexer.lang.file_type),
+    }
+}
+
+pub fn format_save_dead(_: &mut Lexer, _: FormattingOptions, _: &mut GlobalState) -> bool {
+    false
+}
+
+pub fn format_save(lexer: &mut Lexer, options: FormattingOptions, gs: &mut GlobalState) -> bool {
+    let version = lexer.version;
+    match lexer.client.request_formatting(lexer.uri.clone(), options) {
+        Ok(id) => {
+            lexer.requests.push(LSPResponseType::Formatting { id, version });
+            lexer.save_after_format = Some((id, Instant::now()));
+            true
+        }
+        Err(err) => {
+            gs.send_error(err, lexer.lang.file_type);
+            false
+        }
+    }
+}
+
+/// format on save waiting for the server - polled for every editor, not only the rendered one
+/// the write is plain so it can not loop
+pub fn save_formatted(editor: &mut Editor, gs: &mut GlobalState) {
+    let lexer = &mut editor.lexer;
+    let Some((id, sent)) = lexer.save_after_format else {
+        return;
+    };
+    let Some(response) = lexer.client.get_responses().map(|mut responses| responses.remove(&id)) else {
+        return;
+    };
+    let edits = match response {
+        Some(response) => {
+            let request =
+                lexer.requests.iter().position(|request| request.id() == &id).map(|idx| lexer.requests.remove(idx));
+            match request.as_ref().and_then(|request| request.parse(response.result)) {
+                Some(LSPResponse::Formatting { edits, version }) if version == lexer.version => Some(edits),
+                Some(..) => {
+                    gs.error("Formatting dropped, the text was changed");
+                    None
+                }
+                None => {
+                    if let Some(err) = response.error {
+                        gs.error(format!("Formatting failed (LSP): {err}"));
+                    }
+                    None
+                }
+            }
+        }
+        None if sent.elapsed() > FORMAT_SAVE_TIMEOUT => {
+            lexer.requests.retain(|request| request.id() != &id);
+            gs.error("Formatting timed out (LSP), saving without it");
+            None
+        }
+        None => return,
+    };
+    lexer.save_after_format = None;
+    // already formatted - nothing to apply
+    if let Some(edits) = edits.filter(|edits| !edits.is_empty()) {
+        editor.cursor.select_drop();
+        editor.apply_file_edits(edits);
+    }
+    editor.save_without_format(gs);
+}
+
+pub fn format_range_dead(_: &mut Lexer, _: Range, _: FormattingOptions, gs: &mut GlobalState) {
+    gs.message("Range formatting is not supported (LSP)");
+}
+
+pub fn format_range(lexer: &mut Lexer, range: Range, options: FormattingOptions, gs: &mut GlobalState) {
+    let version = lexer.version;
+    match lexer.client.request_range_formatting(lexer.uri.clone(), range, options) {
+        Ok(id) => lexer.requests.push(LSPResponseType::Formatting { id, version }),
+        Err(err) => gs.send_error(err, lexer.lang.file_type),
+    }
+}
+
+pub fn inlay_hints_dead(_: &mut Lexer, _: std::ops::Range<usize>, _: &[EditorLine], _: &mut GlobalState) {}
+
+/// single request is kept in flight, a screen above and below the view is covered to limit requests on scroll
+pub fn inlay_hints(lexer: &mut Lexer, view: std::ops::Range<usize>, content: &[EditorLine], gs: &mut GlobalState) {
+    let view = view.start..view.end.min(content.len());
+    let version = lexer.version;
+    if let Some((hints_version, lines)) = lexer.inlay_hints_for.as_ref() {
+        if *hints_version == version && lines.start <= view.start && view.end <= lines.end {
+            return;
+        }
+    }
+    if view.is_empty() || lexer.requests.iter().any(|request| matches!(request, LSPResponseType::InlayHint { .. })) {
+        return;
+    }
+    let lines = view.start.saturating_sub(view.len())..(view.end + view.len()).min(content.len());
+    let last_line = &content[lines.end - 1];
+    let end_char = (lexer.encode_position)(last_line.char_len(), &last_line.content);
+    let range =
+        Range::new(Position::new(lines.start as u32, 0), Position::new((lines.end - 1) as u32, end_char as u32));
+    match lexer.client.request_inlay_hints(lexer.uri.clone(), range) {
+        Ok(id) => {
+            lexer.requests.push(LSPResponseType::InlayHint { id, version, lines: lines.clone() });
+            lexer.inlay_hints_for = Some((version, lines));
+        }
+        Err(err) => gs.send_error(err, lexer.lang.file_type),
+    }
+}
+
+pub fn code_actions_dead(_: &mut Lexer, _: Range, _: Vec<Diagnostic>, _: bool, gs: &mut GlobalState) {
+    gs.message("Code actions are not supported (LSP)");
+}
+
+pub fn code_actions(
+    lexer: &mut Lexer,
+    range: Range,
+    diagnostics: Vec<Diagnostic>,
+    preferred: bool,
+    gs: &mut GlobalState,
+) {
+    match lexer.client.request_code_actions(lexer.uri.clone(), range, diagnostics) {
+        Ok(id) => {
+            lexer.quick_fix = preferred.then_some(id);
+            lexer.requests.push(LSPResponseType::CodeAction(id));
+        }
+        Err(err) => gs.send_error(err, lexer.lang.file_type),
+    }
+}
+
 // UTILS
 
+#[inline]
+fn is_save_format(save_after_format: Option<(i64, Instant)>, id: i64) -> bool {
+    matches!(save_after_format, Some((pending_id, ..)) if pending_id == id)
+}
+
+/// the action marked as preferred by the server - None if there are more of them
+fn preferred_action(actions: &[CodeActionOrCommand]) -> Option<&CodeActionOrCommand> {
+    let mut preferred = actions.iter().filter(
+        |action| matches!(action, CodeActionOrCommand::CodeAction(action) if action.is_preferred == Some(true)),
+    );
+    let action = preferred.next()?;
+    preferred.next().is_none().then_some(action)
+}
+
+#[inline]
+fn is_enabled<T>(provider: &OneOf<bool, T>) -> bool {
+    !matches!(provider, OneOf::Left(false))
+}
+
 #[inline]
 fn range_tokens_are_supported(provider: &SemanticTokensServerCapabilities) -> bool {
     match provider {
@@ -473,3 +923,64 @@ pub fn char_lsp_utf16(ch: char) -> usize {
 pub fn as_url(path: &Path) -> Uri {
     Uri::from_str(format!("file://{}", path.display()).as_str()).expect("Path should always be parsable!")
 }
+
+#[cfg(test)]
+mod test {
+    use super::{preferred_action, remove_lsp, save_formatted};
+    use crate::{
+        configs::FileType,
+        global_state::GlobalState,
+        lsp::{LSPResponseType, Response},
+        render::backend::{Backend, BackendProtocol},
+        syntax::tests::mock_utf32_lexer,
+        workspace::editor::code_tests::mock_editor,
+    };
+    use lsp_types::{CodeAction, CodeActionOrCommand, Command};
+    use std::time::Instant;
+
+    fn action(title: &str, is_preferred: Option<bool>) -> CodeActionOrCommand {
+        CodeActionOrCommand::CodeAction(CodeAction { title: title.to_owned(), is_preferred, ..Default::default() })
+    }
+
+    #[test]
+    fn single_preferred_action() {
+        let command = CodeActionOrCommand::Command(Command::new(String::from("run"), String::from("run"), None));
+        let mut actions = vec![
+            command,
+            action("other", None),
+            action("fix", Some(true)),
+            action("no", Some(false)),
+        ];
+        assert!(matches!(preferred_action(&actions), Some(CodeActionOrCommand::CodeAction(a)) if a.title == "fix"));
+        actions.push(action("second fix", Some(true)));
+        assert!(preferred_action(&actions).is_none());
+        assert!(preferred_action(&[]).is_none());
+    }
+
+    #[test]
+    fn pending_quick_fix_dropped_with_lsp() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let mut lexer = mock_utf32_lexer(&mut gs, FileType::Rust);
+        lexer.quick_fix = Some(3);
+        remove_lsp(&mut lexer);
+        assert!(lexer.quick_fix.is_none());
+    }
+
+    #[test]
+    fn save_already_formatted() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let path = std::env::temp_dir().join(format!("idiom_save_formatted_{}.rs", std::process::id()));
+        let mut editor = mock_editor(vec!["fn main() {}".to_owned()]);
+        editor.path = path.clone();
+        let lexer = &mut editor.lexer;
+        lexer.requests.push(LSPResponseType::Formatting { id: 1, version: lexer.version });
+        lexer.save_after_format = Some((1, Instant::now()));
+        let response = Response { id: 1, result: Some(serde_json::json!([])), error: None };
+        lexer.client.get_responses().unwrap().insert(1, response);
+        save_formatted(&mut editor, &mut gs);
+        assert!(editor.lexer.save_after_format.is_none());
+        assert!(editor.is_saved());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fn main() {}");
+        std::fs::remove_file(path).unwrap();
+    }
+}
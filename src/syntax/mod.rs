@@ -1,4 +1,5 @@
 pub mod diagnostics;
+pub mod inlay_hints;
 pub mod langs;
 pub mod legend;
 mod lsp_calls;
@@ -7,8 +8,12 @@ pub mod modal;
 pub mod tokens;
 use crate::{
     configs::{EditorAction, FileType, Theme},
-    global_state::{GlobalState, IdiomEvent},
+    global_state::{GlobalState, IdiomEvent, LspStatus},
     lsp::{LSPClient, LSPError, LSPResponseType, LSPResult},
+    popups::{
+        popup_outline::{naive_outline, OutlinePopup, OutlineSymbol},
+        popups_editor::invalid_identifier,
+    },
     render::layout::Rect,
     workspace::{
         actions::{EditMetaData, EditType},
@@ -16,17 +21,22 @@ use crate::{
         CursorPosition, Editor,
     },
 };
-pub use diagnostics::{set_diganostics, Action, DiagnosticInfo, DiagnosticLine};
+pub use diagnostics::{set_diganostics, severity_color, Action, DiagnosticInfo, DiagnosticLine, ERR_COLOR};
+pub use inlay_hints::LineHint;
 pub use langs::Lang;
 pub use legend::Legend;
 use lsp_calls::{
-    as_url, char_lsp_pos, completable_dead, context_local, encode_pos_utf32, get_autocomplete_dead, info_position_dead,
-    map_lsp, remove_lsp, renames_dead, start_renames_dead, sync_edits_dead, sync_edits_dead_rev, tokens_dead,
-    tokens_partial_dead,
+    accept_completion, as_url, char_lsp_pos, code_actions_dead, completable_dead, context_local, document_symbols_dead,
+    encode_pos_utf32, format_document_dead, format_range_dead, format_save_dead, get_autocomplete_dead,
+    info_position_dead, inlay_hints_dead, map_lsp, remove_lsp, renames_dead, save_formatted, send_resolve,
+    start_renames_dead, sync_edits_dead, sync_edits_dead_rev, tokens_dead, tokens_partial_dead, CompletionResolve,
 };
-use lsp_types::{PublishDiagnosticsParams, Range, Uri};
+use lsp_types::{Command, CompletionItem, Diagnostic, FormattingOptions, PublishDiagnosticsParams, Range, Uri};
 use modal::{LSPModal, ModalMessage};
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
 pub use tokens::Token;
 
 pub struct Lexer {
@@ -34,6 +44,8 @@ pub struct Lexer {
     pub legend: Legend,
     pub theme: Theme,
     pub diagnostics: Option<PublishDiagnosticsParams>,
+    /// bumped each time diagnostics are set on the content
+    pub diagnostic_updates: usize,
     pub lsp: bool,
     pub uri: Uri,
     pub path: PathBuf,
@@ -46,6 +58,8 @@ pub struct Lexer {
     context: fn(&mut Editor, &mut GlobalState),
     completable: fn(&Self, char_idx: usize, line: &EditorLine) -> bool,
     autocomplete: fn(&mut Self, CursorPosition, String, &mut GlobalState),
+    accept_completion: fn(&mut Self, CompletionItem, &mut GlobalState),
+    completion_resolve: Option<CompletionResolve>,
     tokens: fn(&mut Self) -> LSPResult<LSPResponseType>,
     tokens_partial: fn(&mut Self, Range, usize) -> LSPResult<LSPResponseType>,
     references: fn(&mut Self, CursorPosition, &mut GlobalState),
@@ -55,6 +69,24 @@ pub struct Lexer {
     signatures: fn(&mut Self, CursorPosition, &mut GlobalState),
     start_renames: fn(&mut Self, CursorPosition, &str),
     renames: fn(&mut Self, CursorPosition, String, &mut GlobalState),
+    format_document: fn(&mut Self, FormattingOptions, &mut GlobalState),
+    format_range: fn(&mut Self, Range, FormattingOptions, &mut GlobalState),
+    format_save: fn(&mut Self, FormattingOptions, &mut GlobalState) -> bool,
+    /// formatting request (id and time sent) the save of the editor waits for
+    save_after_format: Option<(i64, Instant)>,
+    /// the flag requests applying the single preferred action without the selector
+    code_actions: fn(&mut Self, Range, Vec<Diagnostic>, bool, &mut GlobalState),
+    /// code action request (id) of the apply preferred fix
+    quick_fix: Option<i64>,
+    inlay_hints: fn(&mut Self, std::ops::Range<usize>, &[EditorLine], &mut GlobalState),
+    /// version and content lines covered by the last inlay hint request
+    inlay_hints_for: Option<(i32, std::ops::Range<usize>)>,
+    /// requests document symbols, false if not supported
+    document_symbols: fn(&mut Self, &mut GlobalState) -> bool,
+    /// document symbols (LSP) as of the last save
+    outline: Option<Vec<OutlineSymbol>>,
+    /// outline is shown when the symbols arrive
+    outline_pending: bool,
     sync: fn(&mut Self, &EditType, &mut [EditorLine]) -> LSPResult<()>,
     sync_rev: fn(&mut Self, &EditType, &mut [EditorLine]) -> LSPResult<()>,
     meta: Option<EditMetaData>,
@@ -70,6 +102,7 @@ impl Lexer {
             theme: gs.unwrap_or_default(Theme::new(), "theme.json: "),
             modal: None,
             modal_rect: None,
+            diagnostic_updates: 0,
             uri: as_url(path),
             path: path.into(),
             version: 0,
@@ -81,6 +114,8 @@ impl Lexer {
             context: context_local,
             completable: completable_dead,
             autocomplete: get_autocomplete_dead,
+            accept_completion,
+            completion_resolve: None,
             tokens: tokens_dead,
             tokens_partial: tokens_partial_dead,
             references: info_position_dead,
@@ -90,6 +125,17 @@ impl Lexer {
             signatures: info_position_dead,
             start_renames: start_renames_dead,
             renames: renames_dead,
+            format_document: format_document_dead,
+            format_range: format_range_dead,
+            format_save: format_save_dead,
+            save_after_format: None,
+            code_actions: code_actions_dead,
+            quick_fix: None,
+            inlay_hints: inlay_hints_dead,
+            inlay_hints_for: None,
+            document_symbols: document_symbols_dead,
+            outline: None,
+            outline_pending: false,
             sync: sync_edits_dead,
             sync_rev: sync_edits_dead_rev,
             encode_position: encode_pos_utf32,
@@ -105,6 +151,7 @@ impl Lexer {
             theme: gs.unwrap_or_default(Theme::new(), "theme.json: "),
             modal: None,
             modal_rect: None,
+            diagnostic_updates: 0,
             uri: as_url(path),
             path: path.into(),
             version: 0,
@@ -116,6 +163,8 @@ impl Lexer {
             context: context_local,
             completable: completable_dead,
             autocomplete: get_autocomplete_dead,
+            accept_completion,
+            completion_resolve: None,
             tokens: tokens_dead,
             tokens_partial: tokens_partial_dead,
             references: info_position_dead,
@@ -125,6 +174,17 @@ impl Lexer {
             signatures: info_position_dead,
             start_renames: start_renames_dead,
             renames: renames_dead,
+            format_document: format_document_dead,
+            format_range: format_range_dead,
+            format_save: format_save_dead,
+            save_after_format: None,
+            code_actions: code_actions_dead,
+            quick_fix: None,
+            inlay_hints: inlay_hints_dead,
+            inlay_hints_for: None,
+            document_symbols: document_symbols_dead,
+            outline: None,
+            outline_pending: false,
             sync: sync_edits_dead,
             sync_rev: sync_edits_dead_rev,
             encode_position: encode_pos_utf32,
@@ -140,6 +200,7 @@ impl Lexer {
             theme: gs.unwrap_or_default(Theme::new(), "theme.json: "),
             modal: None,
             modal_rect: None,
+            diagnostic_updates: 0,
             uri: as_url(path),
             path: path.into(),
             version: 0,
@@ -151,6 +212,8 @@ impl Lexer {
             context: context_local,
             completable: completable_dead,
             autocomplete: get_autocomplete_dead,
+            accept_completion,
+            completion_resolve: None,
             tokens: tokens_dead,
             tokens_partial: tokens_partial_dead,
             references: info_position_dead,
@@ -160,6 +223,17 @@ impl Lexer {
             signatures: info_position_dead,
             start_renames: start_renames_dead,
             renames: renames_dead,
+            format_document: format_document_dead,
+            format_range: format_range_dead,
+            format_save: format_save_dead,
+            save_after_format: None,
+            code_actions: code_actions_dead,
+            quick_fix: None,
+            inlay_hints: inlay_hints_dead,
+            inlay_hints_for: None,
+            document_symbols: document_symbols_dead,
+            outline: None,
+            outline_pending: false,
             sync: sync_edits_dead,
             sync_rev: sync_edits_dead_rev,
             encode_position: encode_pos_utf32,
@@ -173,6 +247,21 @@ impl Lexer {
         (editor.lexer.context)(editor, gs);
     }
 
+    /// saves the editor once the format on save is resolved (LSP)
+    #[inline]
+    pub fn save_formatted(editor: &mut Editor, gs: &mut GlobalState) {
+        save_formatted(editor, gs);
+    }
+
+    /// drops format on save waiting for the server, true if there was one
+    pub fn take_format_save(&mut self) -> bool {
+        let Some((id, ..)) = self.save_after_format.take() else {
+            return false;
+        };
+        self.requests.retain(|request| request.id() != &id);
+        true
+    }
+
     #[inline]
     pub fn refresh_lsp(&mut self, gs: &mut GlobalState) {
         self.requests.clear();
@@ -195,6 +284,23 @@ impl Lexer {
         self.question_lsp = (self.sync_rev)(self, action, content).is_err();
     }
 
+    /// placeholder client has closed channel
+    pub fn lsp_status(&self) -> LspStatus {
+        if self.client.is_local() {
+            LspStatus::Local
+        } else if self.client.is_closed() {
+            LspStatus::None
+        } else {
+            LspStatus::Server
+        }
+    }
+
+    /// returns the area of the replaced modal (to be rendered again)
+    pub fn replace_modal(&mut self, modal: LSPModal) -> Option<Rect> {
+        self.modal.replace(modal);
+        self.modal_rect.take()
+    }
+
     #[inline]
     pub fn modal_is_rendered(&self) -> bool {
         self.modal_rect.is_some()
@@ -214,28 +320,65 @@ impl Lexer {
 
     #[inline]
     pub fn map_modal_if_exists(&mut self, action: EditorAction, gs: &mut GlobalState) -> (bool, Option<Rect>) {
-        if let Some(modal) = &mut self.modal {
-            match modal.map_and_finish(action, &self.lang, gs) {
-                ModalMessage::Taken => return (true, self.modal_rect.take()),
-                ModalMessage::TakenDone => {
-                    self.modal.take();
-                    return (true, self.modal_rect.take());
-                }
-                ModalMessage::Done => {
-                    self.modal.take();
-                    return (false, self.modal_rect.take());
-                }
-                ModalMessage::RenameVar(new_name, c) => {
-                    self.get_rename(c, new_name, gs);
-                    self.modal.take();
-                    return (true, self.modal_rect.take());
-                }
-                ModalMessage::None => {
-                    return (false, self.modal_rect.take());
+        match self.modal.as_mut() {
+            Some(modal) => {
+                let message = modal.map_and_finish(action, gs);
+                self.finish_modal(message, gs)
+            }
+            None => (false, None),
+        }
+    }
+
+    /// click within the rendered modal, (false, None) if it is not hit
+    pub fn map_modal_click(&mut self, row: u16, column: u16, gs: &mut GlobalState) -> (bool, Option<Rect>) {
+        let Some(position) = self.modal_rect.and_then(|rect| rect.relative_position(row, column)) else {
+            return (false, None);
+        };
+        match self.modal.as_mut() {
+            Some(modal) => {
+                let message = modal.map_click(position.line);
+                self.finish_modal(message, gs)
+            }
+            None => (false, None),
+        }
+    }
+
+    /// completions of huge responses are scored between the frames, returns the rect to be rerendered
+    pub fn score_modal_completions(&mut self, gs: &mut GlobalState) -> Option<Rect> {
+        let message = self.modal.as_mut()?.score_completions();
+        match message {
+            ModalMessage::None => None,
+            message => self.finish_modal(message, gs).1,
+        }
+    }
+
+    fn finish_modal(&mut self, message: ModalMessage, gs: &mut GlobalState) -> (bool, Option<Rect>) {
+        match message {
+            ModalMessage::Taken => (true, self.modal_rect.take()),
+            ModalMessage::TakenDone => {
+                self.modal.take();
+                (true, self.modal_rect.take())
+            }
+            ModalMessage::Done => {
+                self.modal.take();
+                (false, self.modal_rect.take())
+            }
+            ModalMessage::Completion(item) => {
+                self.modal.take();
+                (self.accept_completion)(self, *item, gs);
+                (true, self.modal_rect.take())
+            }
+            ModalMessage::RenameVar(new_name, c) => {
+                if !self.lang.is_identifier(&new_name) {
+                    gs.popup(invalid_identifier(&new_name));
+                    return (true, None);
                 }
+                self.get_rename(c, new_name, gs);
+                self.modal.take();
+                (true, self.modal_rect.take())
             }
+            ModalMessage::None => (false, self.modal_rect.take()),
         }
-        (false, None)
     }
 
     pub fn set_lsp_client(&mut self, mut client: LSPClient, content: String, gs: &mut GlobalState) {
@@ -270,6 +413,14 @@ impl Lexer {
         };
     }
 
+    /// client of the local LSP (internal parser) if the file is handled by it
+    pub fn local_client(&self) -> Option<LSPClient> {
+        match self.client.is_local() {
+            true => Some(self.client.clone()),
+            false => None,
+        }
+    }
+
     pub fn update_path(&mut self, path: &Path) -> Result<(), LSPError> {
         self.path = path.into();
         let old_uri = std::mem::replace(&mut self.uri, as_url(path));
@@ -294,6 +445,36 @@ impl Lexer {
         (self.autocomplete)(self, c, line, gs)
     }
 
+    /// binds the pending completion resolve to the undo group of the inserted completion
+    pub fn completion_inserted(&mut self, group: usize) {
+        if let Some(resolve) = self.completion_resolve.as_mut() {
+            resolve.bind(group);
+        }
+    }
+
+    /// drops the pending completion resolve if the inserted completion was undone
+    pub fn completion_undone(&mut self, done_len: usize) {
+        if self.completion_resolve.as_ref().is_some_and(|resolve| resolve.is_undone(done_len)) {
+            self.completion_resolve = None;
+        }
+    }
+
+    #[inline]
+    pub fn retry_completion_resolve(&mut self, gs: &mut GlobalState) {
+        send_resolve(self, gs);
+    }
+
+    /// chars opening call or separating its arguments - parameter hints are requested instead of completion
+    #[inline]
+    pub fn is_signature_trigger(&self, ch: char) -> bool {
+        self.lang.sig_trigger_chars.contains(ch)
+    }
+
+    #[inline]
+    pub fn signature_help(&mut self, c: CursorPosition, gs: &mut GlobalState) {
+        (self.signatures)(self, c, gs);
+    }
+
     #[inline]
     pub fn help(&mut self, c: CursorPosition, content: &[EditorLine], gs: &mut GlobalState) {
         if let Some(actions) = content[c.line].diagnostic_info(&self.lang) {
@@ -313,11 +494,85 @@ impl Lexer {
         (self.renames)(self, c, new_name, gs);
     }
 
+    /// requests formatting of the select (if any) or the whole document, edits are applied on response
+    pub fn format(
+        &mut self,
+        select: Option<(CursorPosition, CursorPosition)>,
+        tab_size: usize,
+        insert_spaces: bool,
+        gs: &mut GlobalState,
+    ) {
+        let options = FormattingOptions { tab_size: tab_size as u32, insert_spaces, ..Default::default() };
+        match select {
+            Some((from, to)) => (self.format_range)(self, Range::new(from.into(), to.into()), options, gs),
+            None => (self.format_document)(self, options, gs),
+        }
+    }
+
+    /// requests formatting of the document, the editor is saved once it is applied (see save_formatted)
+    /// returns false if the save should not wait - no formatting support or failed request
+    pub fn format_before_save(&mut self, tab_size: usize, insert_spaces: bool, gs: &mut GlobalState) -> bool {
+        if self.save_after_format.is_some() {
+            gs.message("Save is waiting for formatting (LSP)");
+            return true;
+        }
+        let options = FormattingOptions { tab_size: tab_size as u32, insert_spaces, ..Default::default() };
+        (self.format_save)(self, options, gs)
+    }
+
+    /// requests actions for the select (or cursor) - diagnostics of the lines are sent as context
+    /// with preferred set the only preferred action is applied right away, the selector is shown otherwise
+    pub fn code_action(
+        &mut self,
+        select: Option<(CursorPosition, CursorPosition)>,
+        c: CursorPosition,
+        content: &[EditorLine],
+        preferred: bool,
+        gs: &mut GlobalState,
+    ) {
+        let (from, to) = select.unwrap_or((c, c));
+        let diagnostics = content
+            .iter()
+            .take(to.line + 1)
+            .skip(from.line)
+            .filter_map(|line| line.diagnostics.as_ref())
+            .flat_map(|diagnostics| diagnostics.data.iter().map(|data| data.lsp.clone()))
+            .collect();
+        (self.code_actions)(self, Range::new(from.into(), to.into()), diagnostics, preferred, gs);
+    }
+
+    /// server side part of code action, the result is applied by the server (if any)
+    pub fn execute_command(&mut self, command: Command, gs: &mut GlobalState) {
+        match self.client.request_execute_command(command) {
+            Ok(id) => self.requests.push(LSPResponseType::ExecuteCommand(id)),
+            Err(err) => gs.send_error(err, self.lang.file_type),
+        }
+    }
+
+    /// requests hints for the view (and a screen around it) if the ones in place are outdated
+    #[inline]
+    pub fn inlay_hints(&mut self, view: std::ops::Range<usize>, content: &[EditorLine], gs: &mut GlobalState) {
+        (self.inlay_hints)(self, view, content, gs);
+    }
+
+    /// hints are removed from the content, new request is sent on the next call for hints
+    pub fn drop_inlay_hints(&mut self, content: &mut [EditorLine]) {
+        self.inlay_hints_for = None;
+        for line in content.iter_mut() {
+            line.set_inlay_hints(Vec::new());
+        }
+    }
+
     #[inline]
     pub fn go_to_declaration(&mut self, c: CursorPosition, gs: &mut GlobalState) {
         (self.declarations)(self, c, gs);
     }
 
+    #[inline]
+    pub fn peek_definition(&mut self, c: CursorPosition, gs: &mut GlobalState) {
+        (self.definitions)(self, c, gs);
+    }
+
     #[inline]
     pub fn go_to_reference(&mut self, c: CursorPosition, gs: &mut GlobalState) {
         (self.references)(self, c, gs);
@@ -347,18 +602,73 @@ impl Lexer {
     pub fn save_and_check_lsp(&mut self, content: String, gs: &mut GlobalState) {
         if self.lsp {
             gs.message("Checking LSP status (on save) ...");
-            if self.client.file_did_save(self.uri.clone(), content).is_err() && self.client.is_closed() {
-                gs.event.push(IdiomEvent::CheckLSP(self.lang.file_type));
-            } else {
+            if self.notify_saved(content) {
                 gs.success("LSP running ...");
+            } else {
+                gs.event.push(IdiomEvent::CheckLSP(self.lang.file_type));
             }
-            match (self.tokens)(self) {
-                Ok(request) => self.requests.push(request),
-                Err(error) => gs.send_error(error, self.lang.file_type),
-            };
+            self.check_on_save(gs);
+        }
+    }
+
+    /// cached symbols (LSP) are shown at once, otherwise they are requested and shown on response
+    /// headers found by patterns are listed if the server does not provide symbols
+    pub fn show_outline(&mut self, content: &[EditorLine], gs: &mut GlobalState) {
+        if let Some(outline) = self.outline.as_ref() {
+            gs.popup(OutlinePopup::boxed(outline, content, true));
+        } else if (self.document_symbols)(self, gs) {
+            self.outline_pending = true;
+            gs.message("Requesting document symbols (LSP) ...");
+        } else {
+            gs.popup(OutlinePopup::boxed(&naive_outline(self.lang.file_type, content), content, false));
         }
     }
 
+    /// sends didSave only, returns false if the server is closed and needs a check
+    pub fn notify_saved(&mut self, content: String) -> bool {
+        if !self.lsp {
+            return true;
+        }
+        self.client.file_did_save(self.uri.clone(), content).is_ok() || !self.client.is_closed()
+    }
+
+    /// semantic tokens of the saved text
+    pub fn refresh_tokens(&mut self, gs: &mut GlobalState) {
+        if !self.lsp {
+            return;
+        }
+        match (self.tokens)(self) {
+            Ok(request) => self.requests.push(request),
+            Err(error) => gs.send_error(error, self.lang.file_type),
+        };
+    }
+
+    /// checks run after save - refreshes the tokens, the outline follows saves only (not every edit)
+    pub fn check_on_save(&mut self, gs: &mut GlobalState) {
+        if !self.lsp {
+            return;
+        }
+        self.refresh_tokens(gs);
+        (self.document_symbols)(self, gs);
+    }
+
+    /// replaces the whole document on LSP side without reopening it
+    pub fn full_sync(&mut self, content: String, gs: &mut GlobalState) {
+        if !self.lsp {
+            return;
+        }
+        self.version += 1;
+        self.meta = None;
+        if let Err(error) = self.client.full_sync(self.uri.clone(), self.version, content) {
+            gs.send_error(error, self.lang.file_type);
+            return;
+        }
+        match (self.tokens)(self) {
+            Ok(request) => self.requests.push(request),
+            Err(error) => gs.send_error(error, self.lang.file_type),
+        };
+    }
+
     pub fn reopen(&mut self, content: String, file_type: FileType) -> Result<(), LSPError> {
         if !self.lsp {
             return Ok(());
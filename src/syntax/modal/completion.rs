@@ -1,55 +1,69 @@
-use super::ModalMessage;
+use super::{signature::SignatureModal, ModalMessage};
 use crate::{
     configs::EditorAction,
     global_state::GlobalState,
-    render::{layout::Rect, state::State},
-    syntax::Lang,
+    render::{
+        backend::{BackendProtocol, Style},
+        layout::{IterLines, Rect},
+        state::{SoftLimit, State},
+    },
     workspace::CursorPosition,
 };
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use lsp_types::CompletionItem;
+use std::cmp::Ordering;
+
+/// completions scored at once - the rest is scored in chunks between the frames
+const SCORE_CHUNK: usize = 1000;
 
 pub struct AutoComplete {
     state: State,
+    limit: SoftLimit,
     filter: String,
     matcher: SkimMatcherV2,
-    filtered: Vec<(String, i64, usize)>,
+    /// (score, completion idx) of the matches so far, sorted by score
+    filtered: Vec<(i64, usize)>,
+    /// completions scored against the current filter
+    scored: usize,
     completions: Vec<CompletionItem>,
+    /// parameter hints of the call being typed - rendered in the first row
+    signature: Option<SignatureModal>,
 }
 
 impl AutoComplete {
     pub fn new(completions: Vec<CompletionItem>, line: String, c: CursorPosition) -> Self {
         let mut filter = String::new();
         for ch in line.chars().take(c.char) {
-            if ch.is_alphabetic() || ch == '_' {
+            if is_token_char(ch) {
                 filter.push(ch);
             } else {
                 filter.clear();
             };
         }
-        let mut modal =
-            Self { state: State::new(), filter, matcher: SkimMatcherV2::default(), filtered: Vec::new(), completions };
+        let mut modal = Self {
+            state: State::new(),
+            limit: SoftLimit::default(),
+            filter,
+            matcher: SkimMatcherV2::default(),
+            filtered: Vec::new(),
+            scored: 0,
+            completions,
+            signature: None,
+        };
         modal.build_matches();
         modal
     }
 
-    pub fn map(&mut self, action: EditorAction, lang: &Lang, gs: &mut GlobalState) -> ModalMessage {
+    pub fn map(&mut self, action: EditorAction) -> ModalMessage {
         match action {
-            EditorAction::NewLine | EditorAction::Indent => {
-                let mut filtered_completion = self.completions.remove(self.filtered.remove(self.state.selected).2);
-                if let Some(data) = filtered_completion.data.take() {
-                    lang.handle_completion_data(data, gs);
-                };
-                gs.event.push(filtered_completion.into());
-                ModalMessage::TakenDone
-            }
+            EditorAction::NewLine | EditorAction::Indent => self.accept(),
             EditorAction::Char(ch) => self.push_filter(ch),
             EditorAction::Down => {
-                self.state.next(self.filtered.len());
+                self.limit.next(&mut self.state, self.filtered.len());
                 ModalMessage::Taken
             }
             EditorAction::Up => {
-                self.state.prev(self.filtered.len());
+                self.limit.prev(&mut self.state, self.filtered.len());
                 ModalMessage::Taken
             }
             EditorAction::Backspace => self.filter_pop(),
@@ -57,53 +71,353 @@ impl AutoComplete {
         }
     }
 
+    /// accepts the option at line of the rendered area - rows above the options are taken without effect
+    pub fn click(&mut self, line: usize) -> ModalMessage {
+        let Some(line) = line.checked_sub(self.header_rows()) else {
+            return ModalMessage::Taken;
+        };
+        let idx = self.state.at_line + line;
+        if idx >= self.limit.shown(self.filtered.len()) {
+            return ModalMessage::Taken;
+        }
+        self.state.selected = idx;
+        self.accept()
+    }
+
+    /// rows rendered above the options - signature label and "showing N of M" indicator
+    fn header_rows(&self) -> usize {
+        let indicator = self.limit.indicator(self.filtered.len(), self.is_scoring()).is_some();
+        usize::from(self.signature.is_some()) + usize::from(indicator)
+    }
+
+    pub fn set_signature(&mut self, signature: Option<SignatureModal>) {
+        self.signature = signature;
+    }
+
+    /// signature help is shown again when the completion is done
+    pub fn take_signature(&mut self) -> Option<SignatureModal> {
+        self.signature.take()
+    }
+
     #[inline]
     pub fn render(&mut self, area: &Rect, gs: &mut GlobalState) {
-        self.state.render_list(self.filtered.iter().map(|(c, ..)| c.as_str()), *area, &mut gs.writer);
+        let mut area = *area;
+        if let Some(signature) = self.signature.as_ref() {
+            if let Some(line) = area.splitoff_rows(1).iter_padded(1).next() {
+                signature.render_label(line, gs);
+            }
+        }
+        if let Some(indicator) = self.limit.indicator(self.filtered.len(), self.is_scoring()) {
+            if let Some(line) = area.splitoff_rows(1).get_line(0) {
+                line.render(&indicator, &mut gs.writer);
+            }
+        }
+        let shown = self.limit.shown(self.filtered.len());
+        self.state.update_at_line(area.height as usize);
+        let mut lines = area.into_iter();
+        let window = self.filtered[..shown].get(self.state.at_line..).unwrap_or_default();
+        for (idx, (_, item_idx)) in (self.state.at_line..).zip(window) {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let item = &self.completions[*item_idx];
+            let matched = self.matched_chars(&item.label);
+            let mut match_style = Style::bold();
+            let is_selected = idx == self.state.selected;
+            if is_selected {
+                gs.writer.set_style(self.state.highlight);
+                match_style.update(self.state.highlight);
+            }
+            let mut builder = line.unsafe_builder(&mut gs.writer);
+            builder.push(" ");
+            let mut segment = String::new();
+            let mut segment_matched = false;
+            for (char_idx, ch) in item.label.chars().enumerate() {
+                let is_matched = matched.contains(&char_idx);
+                if is_matched != segment_matched && !segment.is_empty() {
+                    match segment_matched {
+                        true => builder.push_styled(&segment, match_style),
+                        false => builder.push(&segment),
+                    };
+                    segment.clear();
+                }
+                segment_matched = is_matched;
+                segment.push(ch);
+            }
+            match segment_matched {
+                true => builder.push_styled(&segment, match_style),
+                false => builder.push(&segment),
+            };
+            if let Some(info) = item.detail.as_ref() {
+                builder.push("  ");
+                builder.push(info);
+            }
+            drop(builder);
+            if is_selected {
+                gs.writer.reset_style();
+            }
+        }
+        lines.clear_to_end(&mut gs.writer);
+    }
+
+    /// char indexes of the label matched by the filter - highlighted in the rendered rows
+    fn matched_chars(&self, label: &str) -> Vec<usize> {
+        if self.filter.is_empty() {
+            return Vec::new();
+        }
+        match self.matcher.fuzzy_indices(label, &self.filter) {
+            Some((_, indices)) => indices,
+            None => Vec::new(),
+        }
     }
 
+    /// rows of the modal - the indicator and signature included
     #[inline]
     pub fn len(&self) -> usize {
-        self.filtered.len()
+        if self.filtered.is_empty() {
+            return 0;
+        }
+        let indicator = self.limit.indicator(self.filtered.len(), self.is_scoring()).is_some();
+        self.limit.shown(self.filtered.len()) + indicator as usize + self.signature.is_some() as usize
+    }
+
+    /// nothing matched and nothing is left to score
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.filtered.is_empty() && !self.is_scoring()
+    }
+
+    #[inline]
+    fn is_scoring(&self) -> bool {
+        self.scored < self.completions.len()
+    }
+
+    /// scores the next chunk of completions keeping the selected one, false if there was nothing to score
+    pub fn score_next_chunk(&mut self) -> bool {
+        if !self.is_scoring() {
+            return false;
+        }
+        let selected = self.filtered.get(self.state.selected).map(|(_, item_idx)| *item_idx);
+        self.score_chunk();
+        if let Some(selected) = selected {
+            if let Some(idx) = self.filtered.iter().position(|(_, item_idx)| *item_idx == selected) {
+                self.limit.reach(idx);
+                self.state.selected = idx;
+            }
+        }
+        true
     }
 
+    fn accept(&mut self) -> ModalMessage {
+        match self.filtered.get(self.state.selected) {
+            // modal is dropped after the completion
+            Some((_, item_idx)) => ModalMessage::Completion(Box::new(self.completions.swap_remove(*item_idx))),
+            None => ModalMessage::Done,
+        }
+    }
+
+    /// backspace within the token rescores - the modal is dropped only when the token is left
     fn filter_pop(&mut self) -> ModalMessage {
-        self.filter.pop();
-        self.build_matches();
-        if self.filter.is_empty() {
+        if self.filter.pop().is_none() {
             return ModalMessage::Done;
         }
-        self.filtered.as_slice().into()
+        self.build_matches();
+        self.status()
     }
 
     fn push_filter(&mut self, ch: char) -> ModalMessage {
-        if ch.is_alphabetic() || ch == '_' {
+        if is_token_char(ch) {
             self.filter.push(ch);
             self.build_matches();
-            self.filtered.as_slice().into()
+            self.status()
         } else {
             ModalMessage::Done
         }
     }
 
+    fn status(&self) -> ModalMessage {
+        match self.is_empty() {
+            true => ModalMessage::Done,
+            false => ModalMessage::default(),
+        }
+    }
+
     fn build_matches(&mut self) {
-        self.filtered = self
-            .completions
-            .iter()
-            .enumerate()
-            .filter_map(|(item_idx, item)| {
-                self.matcher.fuzzy_match(item.filter_text.as_ref().unwrap_or(&item.label), &self.filter).map(|score| {
-                    let divisor = item.label.len().abs_diff(self.filter.len()) as i64;
-                    let new_score = if divisor != 0 { score / divisor } else { score };
-                    let line = match item.detail.as_ref() {
-                        Some(info) => format!(" {}  {info}", item.label),
-                        None => format!(" {}", item.label),
-                    };
-                    (line, new_score, item_idx)
-                })
-            })
-            .collect();
-        self.filtered.sort_by(|(_, idx, _), (_, rhidx, _)| rhidx.cmp(idx));
+        self.filtered.clear();
+        self.scored = 0;
+        self.limit.reset();
+        self.score_chunk();
         self.state.select(0, self.filtered.len());
     }
+
+    fn score_chunk(&mut self) {
+        let end = std::cmp::min(self.scored + SCORE_CHUNK, self.completions.len());
+        for (item_idx, item) in self.completions[self.scored..end].iter().enumerate() {
+            let filter_text = item.filter_text.as_ref().unwrap_or(&item.label);
+            if let Some(score) = self.matcher.fuzzy_match(filter_text, &self.filter) {
+                let divisor = item.label.len().abs_diff(self.filter.len()) as i64;
+                let new_score = if divisor != 0 { score / divisor } else { score };
+                self.filtered.push((new_score, self.scored + item_idx));
+            }
+        }
+        self.scored = end;
+        let completions = &self.completions;
+        self.filtered.sort_by(|(score, idx), (rhscore, rhidx)| {
+            rhscore.cmp(score).then_with(|| tie_break(&completions[*idx], &completions[*rhidx])).then(idx.cmp(rhidx))
+        });
+    }
+}
+
+/// equal scores - preselected first, then by sortText (label if missing), then the order of the server
+fn tie_break(item: &CompletionItem, other: &CompletionItem) -> Ordering {
+    let preselect = other.preselect.unwrap_or_default().cmp(&item.preselect.unwrap_or_default());
+    preselect.then_with(|| {
+        let sort_text = item.sort_text.as_ref().unwrap_or(&item.label);
+        sort_text.cmp(other.sort_text.as_ref().unwrap_or(&other.label))
+    })
+}
+
+#[inline]
+fn is_token_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AutoComplete, SCORE_CHUNK};
+    use crate::{
+        configs::EditorAction,
+        global_state::GlobalState,
+        render::{
+            backend::{Backend, BackendProtocol, Style},
+            layout::Rect,
+            state::SoftLimit,
+        },
+        syntax::modal::ModalMessage,
+        workspace::CursorPosition,
+    };
+    use lsp_types::CompletionItem;
+
+    /// rendered text joined by rows - matched chars are printed separately
+    fn rows(rendered: Vec<(Style, String)>) -> Vec<String> {
+        let mut rows = Vec::new();
+        for (_, text) in rendered {
+            if text.starts_with("<<go to") {
+                rows.push(String::new());
+            } else if !text.starts_with("<<") {
+                if let Some(row) = rows.last_mut() {
+                    row.push_str(&text);
+                }
+            }
+        }
+        rows
+    }
+
+    fn item(label: &str, sort_text: Option<&str>, preselect: bool) -> CompletionItem {
+        let mut item = CompletionItem::new_simple(label.to_owned(), String::new());
+        item.sort_text = sort_text.map(str::to_owned);
+        item.preselect = Some(preselect);
+        item
+    }
+
+    #[test]
+    fn huge_completion_response() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let completions =
+            (0..10_000).map(|idx| CompletionItem::new_simple(format!("item_{idx}"), String::new())).collect();
+        let mut modal = AutoComplete::new(completions, String::from("ite"), CursorPosition { line: 0, char: 3 });
+        // first render is bound by the chunk and the soft limit
+        assert_eq!(modal.scored, SCORE_CHUNK);
+        assert_eq!(modal.len(), SoftLimit::STEP + 1);
+        modal.render(&Rect::new(0, 0, 60, 7), &mut gs);
+        let rendered = rows(gs.writer.drain());
+        assert!(rendered.iter().any(|text| text == " showing 50 of 1,000+ "));
+        assert!(rendered.iter().filter(|text| text.starts_with(" item_")).count() <= 6);
+
+        let mut ticks = 0;
+        while modal.score_next_chunk() {
+            ticks += 1;
+        }
+        assert_eq!(ticks, 10_000 / SCORE_CHUNK - 1);
+        modal.render(&Rect::new(0, 0, 60, 7), &mut gs);
+        assert!(gs.writer.drain().iter().any(|(_, text)| text == " showing 50 of 10,000 "));
+
+        for _ in 1..10_000 {
+            modal.map(EditorAction::Down);
+        }
+        modal.render(&Rect::new(0, 0, 60, 7), &mut gs);
+        assert!(rows(gs.writer.drain()).iter().any(|text| text.starts_with(" item_9999")));
+        assert_eq!(modal.len(), 10_000);
+        match modal.click(modal.state.selected - modal.state.at_line) {
+            ModalMessage::Completion(item) => assert_eq!(item.label, "item_9999"),
+            message => panic!("Expected completion got {message:?}"),
+        }
+    }
+
+    #[test]
+    fn click_below_header_rows() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let completions =
+            (0..100).map(|idx| CompletionItem::new_simple(format!("item_{idx}"), String::new())).collect();
+        let mut modal = AutoComplete::new(completions, String::new(), CursorPosition::default());
+        modal.render(&Rect::new(0, 0, 60, 7), &mut gs);
+        let rendered = rows(gs.writer.drain());
+        assert_eq!(rendered[0], " showing 50 of 100 ");
+        assert!(rendered[1].starts_with(" item_0 "));
+        // the indicator row is not an option
+        assert!(matches!(modal.click(0), ModalMessage::Taken));
+        match modal.click(2) {
+            ModalMessage::Completion(item) => assert_eq!(item.label, "item_1"),
+            message => panic!("Expected completion got {message:?}"),
+        }
+    }
+
+    #[test]
+    fn selection_kept_between_chunks() {
+        let mut completions =
+            (0..3000).map(|idx| CompletionItem::new_simple(format!("item_{idx}"), String::new())).collect::<Vec<_>>();
+        // best match comes in the last chunk
+        completions[2500] = CompletionItem::new_simple(String::from("ite"), String::new());
+        let mut modal = AutoComplete::new(completions, String::from("ite"), CursorPosition { line: 0, char: 3 });
+        modal.map(EditorAction::Down);
+        modal.map(EditorAction::Down);
+        while modal.score_next_chunk() {}
+        assert_eq!(modal.filtered[0].1, 2500);
+        match modal.map(EditorAction::NewLine) {
+            ModalMessage::Completion(item) => assert_eq!(item.label, "item_2"),
+            message => panic!("Expected completion got {message:?}"),
+        }
+    }
+
+    #[test]
+    fn ranked_highlighted_and_backspace() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let completions = vec![
+            item("get_other", Some("2"), false),
+            item("go_elsewhere", None, false),
+            item("get_item", Some("1"), false),
+            item("get_item", Some("3"), true),
+            item("item1", None, false),
+        ];
+        let mut modal = AutoComplete::new(completions, String::from("gi"), CursorPosition { line: 0, char: 2 });
+        // word boundary matches first, equal scores - preselect, then sortText
+        let order = modal.filtered.iter().map(|(_, idx)| *idx).collect::<Vec<_>>();
+        assert_eq!(&order[..2], [3, 2]);
+        modal.render(&Rect::new(0, 0, 30, 5), &mut gs);
+        let rendered = gs.writer.drain();
+        let matched = rendered.iter().filter(|(style, _)| *style == Style::bold()).collect::<Vec<_>>();
+        assert_eq!(matched.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>()[..2], ["g", "i"]);
+        assert!(rows(rendered).iter().any(|text| text.trim_end() == " get_item"));
+        // backspace within the token rescores - all shown with empty filter
+        assert!(matches!(modal.map(EditorAction::Backspace), ModalMessage::None));
+        assert!(matches!(modal.map(EditorAction::Backspace), ModalMessage::None));
+        assert_eq!(modal.filtered.len(), 5);
+        assert_eq!(modal.filtered[0].1, 3);
+        // digits are part of the token
+        assert!(matches!(modal.map(EditorAction::Char('1')), ModalMessage::None));
+        assert_eq!(modal.filtered[0].1, 4);
+        modal.map(EditorAction::Backspace);
+        // leaving the token drops the modal
+        assert!(matches!(modal.map(EditorAction::Backspace), ModalMessage::Done));
+    }
 }
@@ -1,4 +1,4 @@
-use super::ModalMessage;
+use super::{markdown::parse_markdown, ModalMessage};
 use crate::{
     configs::{EditorAction, Theme},
     global_state::GlobalState,
@@ -11,7 +11,9 @@ use crate::{
     },
     syntax::{Action, DiagnosticInfo},
 };
-use lsp_types::{Documentation, Hover, HoverContents, MarkedString, SignatureHelp, SignatureInformation};
+use lsp_types::{
+    Documentation, Hover, HoverContents, MarkedString, MarkupContent, MarkupKind, SignatureHelp, SignatureInformation,
+};
 use std::cmp::Ordering;
 
 #[derive(Default)]
@@ -47,7 +49,7 @@ impl Info {
     pub fn from_hover(hover: Hover, theme: &Theme) -> Self {
         let mut lines = Vec::new();
         let mut sty = Highlighter::new(theme);
-        parse_hover(hover, &mut sty, &mut lines);
+        parse_hover(hover, &mut sty, theme, &mut lines);
         Self { text: lines, style_builder: Some(sty), ..Default::default() }
     }
 
@@ -55,7 +57,7 @@ impl Info {
         let mut lines = Vec::new();
         let mut sty = Highlighter::new(theme);
         for info in signature.signatures {
-            parse_sig_info(info, &mut sty, &mut lines);
+            parse_sig_info(info, &mut sty, theme, &mut lines);
         }
         Self { text: lines, style_builder: Some(sty), ..Default::default() }
     }
@@ -92,8 +94,8 @@ impl Info {
                 }
                 ModalMessage::Done
             }
-            EditorAction::Up => self.prev(),
-            EditorAction::Down => self.next(),
+            EditorAction::Up | EditorAction::ScrollUp => self.prev(),
+            EditorAction::Down | EditorAction::ScrollDown => self.next(),
             EditorAction::Left if !matches!(self.mode, Mode::Select) && self.actions.is_some() => {
                 self.mode = Mode::Select;
                 ModalMessage::Taken
@@ -108,7 +110,7 @@ impl Info {
                 self.state.next(self.len());
             }
             Mode::Text => {
-                if self.text_state < self.text.len() {
+                if self.text_state + 1 < self.text.len() {
                     self.text_state += 1;
                 }
             }
@@ -131,10 +133,10 @@ impl Info {
 
     pub fn push_hover(&mut self, hover: Hover, theme: &Theme) {
         match self.style_builder.as_mut() {
-            Some(sty) => parse_hover(hover, sty, &mut self.text),
+            Some(sty) => parse_hover(hover, sty, theme, &mut self.text),
             None => {
                 let mut sty = Highlighter::new(theme);
-                parse_hover(hover, &mut sty, &mut self.text);
+                parse_hover(hover, &mut sty, theme, &mut self.text);
                 self.style_builder.replace(sty);
             }
         }
@@ -145,13 +147,13 @@ impl Info {
         match self.style_builder.as_mut() {
             Some(sty) => {
                 for info in signature.signatures {
-                    parse_sig_info(info, sty, &mut self.text);
+                    parse_sig_info(info, sty, theme, &mut self.text);
                 }
             }
             None => {
                 let mut sty = Highlighter::new(theme);
                 for info in signature.signatures {
-                    parse_sig_info(info, &mut sty, &mut self.text);
+                    parse_sig_info(info, &mut sty, theme, &mut self.text);
                 }
                 self.style_builder.replace(sty);
             }
@@ -201,30 +203,11 @@ impl From<DiagnosticInfo> for Info {
     }
 }
 
-fn parse_sig_info(info: SignatureInformation, sty: &mut Highlighter, lines: &mut Vec<StyledLine>) {
+fn parse_sig_info(info: SignatureInformation, sty: &mut Highlighter, theme: &Theme, lines: &mut Vec<StyledLine>) {
     lines.push(sty.parse_line(&info.label));
     if let Some(text) = info.documentation {
         match text {
-            Documentation::MarkupContent(c) => {
-                if matches!(c.kind, lsp_types::MarkupKind::Markdown) {
-                    let mut is_code = false;
-                    for line in c.value.split("\n") {
-                        if line.starts_with("```") {
-                            is_code = !is_code;
-                            continue;
-                        }
-                        if is_code {
-                            lines.push(sty.parse_line(line));
-                        } else {
-                            lines.push(line.to_owned().into());
-                        }
-                    }
-                } else {
-                    for line in c.value.split("\n") {
-                        lines.push(sty.parse_line(line));
-                    }
-                }
-            }
+            Documentation::MarkupContent(c) => handle_markup(c, sty, theme, lines),
             Documentation::String(s) => {
                 for line in s.split("\n") {
                     lines.push(sty.parse_line(line));
@@ -234,57 +217,40 @@ fn parse_sig_info(info: SignatureInformation, sty: &mut Highlighter, lines: &mut
     }
 }
 
-fn parse_hover(hover: Hover, sty: &mut Highlighter, lines: &mut Vec<StyledLine>) {
+fn parse_hover(hover: Hover, sty: &mut Highlighter, theme: &Theme, lines: &mut Vec<StyledLine>) {
     match hover.contents {
         HoverContents::Array(arr) => {
-            // let mut ctx = LineBuilderContext::default();
             for value in arr {
-                parse_markedstr(value, sty, lines);
+                parse_markedstr(value, sty, theme, lines);
             }
         }
         HoverContents::Markup(markup) => {
-            handle_markup(markup, sty, lines);
+            handle_markup(markup, sty, theme, lines);
         }
         HoverContents::Scalar(value) => {
-            parse_markedstr(value, sty, lines);
+            parse_markedstr(value, sty, theme, lines);
         }
     }
 }
 
-fn handle_markup(markup: lsp_types::MarkupContent, sty: &mut Highlighter, lines: &mut Vec<StyledLine>) {
-    if !matches!(markup.kind, lsp_types::MarkupKind::Markdown) {
-        for line in markup.value.split("\n") {
-            lines.push(sty.parse_line(line));
-        }
-        return;
-    }
-    let mut is_code = false;
-    for line in markup.value.split("\n") {
-        if line.trim().starts_with("```") {
-            is_code = !is_code;
-            continue;
-        }
-        if is_code {
-            lines.push(sty.parse_line(line));
-        } else if line.trim().starts_with('#') {
-            lines.push(line.to_owned().into());
-        } else {
-            lines.push(line.to_owned().into())
+fn handle_markup(markup: MarkupContent, sty: &mut Highlighter, theme: &Theme, lines: &mut Vec<StyledLine>) {
+    match markup.kind {
+        MarkupKind::Markdown => parse_markdown(&markup.value, sty, theme, lines),
+        MarkupKind::PlainText => {
+            for line in markup.value.split("\n") {
+                lines.push(sty.parse_line(line));
+            }
         }
     }
 }
 
-fn parse_markedstr(value: MarkedString, sty: &mut Highlighter, lines: &mut Vec<StyledLine>) {
+/// strings are markdown, language strings are code of the language
+fn parse_markedstr(value: MarkedString, sty: &mut Highlighter, theme: &Theme, lines: &mut Vec<StyledLine>) {
     match value {
         MarkedString::LanguageString(data) => {
-            for text_line in data.value.split("\n") {
-                lines.push(sty.parse_line(text_line));
-            }
-        }
-        MarkedString::String(value) => {
-            for text_line in value.split("\n") {
-                lines.push(StyledLine::from(text_line.to_owned()))
-            }
+            let fenced = format!("```{}\n{}\n```", data.language, data.value);
+            parse_markdown(&fenced, sty, theme, lines);
         }
+        MarkedString::String(value) => parse_markdown(&value, sty, theme, lines),
     }
 }
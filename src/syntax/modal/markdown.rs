@@ -0,0 +1,374 @@
+use super::peek::peek_line;
+use crate::{
+    configs::{FileType, Theme},
+    lsp::{init_local_tokens, Highlighter},
+    render::{
+        backend::{color, Style},
+        widgets::{StyledLine, Text},
+    },
+    workspace::line::EditorLine,
+};
+
+const BULLET: &str = "• ";
+const QUOTE: &str = "│ ";
+const RULE_WIDTH: usize = 40;
+
+/// markdown documentation (LSP) as styled lines
+/// fenced code is highlighted by the tokens of the fence language (generic highlighter if not known),
+/// code spans, emphasis and headers are styled, list bullets are kept and links show the text only
+pub fn parse_markdown(text: &str, sty: &mut Highlighter, theme: &Theme, lines: &mut Vec<StyledLine>) {
+    let mut fence: Option<(Option<FileType>, Vec<&str>)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(info) = trimmed.strip_prefix("```").or_else(|| trimmed.strip_prefix("~~~")) {
+            match fence.take() {
+                Some((file_type, code)) => push_code(file_type, code, sty, theme, lines),
+                None => {
+                    // info string can have attributes after the language (rust,ignore)
+                    let lang = info.split(|ch: char| ch == ',' || ch.is_whitespace()).next().unwrap_or_default();
+                    fence = Some((FileType::from_lang(lang), Vec::new()));
+                }
+            }
+            continue;
+        }
+        match fence.as_mut() {
+            Some((_, code)) => code.push(line),
+            None => lines.push(markdown_line(line, theme)),
+        }
+    }
+    // not closed
+    if let Some((file_type, code)) = fence {
+        push_code(file_type, code, sty, theme, lines);
+    }
+}
+
+fn push_code(
+    file_type: Option<FileType>,
+    code: Vec<&str>,
+    sty: &mut Highlighter,
+    theme: &Theme,
+    lines: &mut Vec<StyledLine>,
+) {
+    let Some(file_type) = file_type else {
+        lines.extend(code.into_iter().map(|line| sty.parse_line(line)));
+        return;
+    };
+    let mut content = code.into_iter().map(|line| EditorLine::from(line.to_owned())).collect::<Vec<_>>();
+    init_local_tokens(file_type, &mut content, theme);
+    for line in content.iter() {
+        let parts = peek_line(line).into_iter().map(|(text, style)| Text::new(text, style)).collect::<Vec<_>>();
+        lines.push(parts.into());
+    }
+}
+
+/// block markers (headers, bullets, quotes, rules) with the inline styled text
+fn markdown_line(line: &str, theme: &Theme) -> StyledLine {
+    let text = line.trim_start();
+    let indent = &line[..line.len() - text.len()];
+    let rule = text.trim_end();
+    if rule.len() >= 3 && ['-', '*', '_'].iter().any(|marker| rule.chars().all(|ch| ch == *marker)) {
+        return (String::from("─").repeat(RULE_WIDTH), Style::fg(color::dark_grey())).into();
+    }
+    let mut builder = InlineBuilder::new(theme);
+    builder.push_text(indent);
+    let hashes = text.chars().take_while(|ch| *ch == '#').count();
+    if (1..=6).contains(&hashes) && text[hashes..].starts_with(' ') {
+        builder.bold = true;
+        builder.parse(text[hashes..].trim());
+        return builder.finish();
+    }
+    let text = match ["- ", "* ", "+ "].iter().find_map(|bullet| text.strip_prefix(bullet)) {
+        Some(item) => {
+            builder.push_text(BULLET);
+            item
+        }
+        None => match text.strip_prefix('>') {
+            Some(quoted) => {
+                builder.push_styled(QUOTE, Style::fg(color::dark_grey()));
+                quoted.strip_prefix(' ').unwrap_or(quoted)
+            }
+            None => text,
+        },
+    };
+    builder.parse(text);
+    builder.finish()
+}
+
+/// parts of the line with the same style are merged
+struct InlineBuilder {
+    parts: Vec<Text>,
+    text: String,
+    style: Option<Style>,
+    code_style: Style,
+    bold: bool,
+    italic: bool,
+}
+
+impl InlineBuilder {
+    fn new(theme: &Theme) -> Self {
+        Self {
+            parts: Vec::new(),
+            text: String::new(),
+            style: None,
+            code_style: Style::fg(theme.class_or_struct),
+            bold: false,
+            italic: false,
+        }
+    }
+
+    fn current_style(&self, code: bool) -> Option<Style> {
+        if !code && !self.bold && !self.italic {
+            return None;
+        }
+        let mut style = if code { self.code_style } else { Style::default() };
+        if self.bold {
+            style.add_bold();
+        }
+        if self.italic {
+            style.add_italic();
+        }
+        Some(style)
+    }
+
+    fn push_styled(&mut self, text: &str, style: Style) {
+        self.push(text, Some(style));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        self.push(text, self.current_style(false));
+    }
+
+    fn push(&mut self, text: &str, style: Option<Style>) {
+        if text.is_empty() {
+            return;
+        }
+        if style != self.style {
+            self.flush();
+            self.style = style;
+        }
+        self.text.push_str(text);
+    }
+
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            self.parts.push(Text::new(std::mem::take(&mut self.text), self.style));
+        }
+    }
+
+    fn finish(mut self) -> StyledLine {
+        self.flush();
+        if self.parts.is_empty() {
+            return String::new().into();
+        }
+        self.parts.into()
+    }
+
+    /// code spans, emphasis (* and _ runs), links and escapes
+    fn parse(&mut self, text: &str) {
+        let chars = text.chars().collect::<Vec<_>>();
+        // "](url)" of links being parsed - skipped once reached
+        let mut link_ends: Vec<(usize, usize)> = Vec::new();
+        let mut idx = 0;
+        while idx < chars.len() {
+            if let Some(pos) = link_ends.iter().position(|(start, _)| *start == idx) {
+                idx = link_ends.remove(pos).1 + 1;
+                continue;
+            }
+            let ch = chars[idx];
+            match ch {
+                '\\' if chars.get(idx + 1).is_some_and(char::is_ascii_punctuation) => {
+                    self.push_text(&chars[idx + 1].to_string());
+                    idx += 2;
+                }
+                '`' => {
+                    let run = count_run(&chars, idx, '`');
+                    let content_start = idx + run;
+                    match find_run(&chars, content_start, '`', run) {
+                        Some(end) => {
+                            let code = chars[content_start..end].iter().collect::<String>();
+                            let code = match code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty() {
+                                true => &code[1..code.len() - 1],
+                                false => code.as_str(),
+                            };
+                            self.push(code, self.current_style(true));
+                            idx = end + run;
+                        }
+                        None => {
+                            self.push_text(&"`".repeat(run));
+                            idx = content_start;
+                        }
+                    }
+                }
+                '*' | '_' => {
+                    let run = count_run(&chars, idx, ch);
+                    let prev = idx.checked_sub(1).map(|prev| chars[prev]);
+                    let next = chars.get(idx + run).copied();
+                    let intraword = ch == '_' && prev.is_some_and(char::is_alphanumeric);
+                    let can_close = prev.is_some_and(|prev| !prev.is_whitespace())
+                        && !(ch == '_' && next.is_some_and(char::is_alphanumeric));
+                    let can_open = next.is_some_and(|next| !next.is_whitespace())
+                        && !intraword
+                        && find_run(&chars, idx + run, ch, run).is_some();
+                    let (toggles_bold, toggles_italic) = match run {
+                        1 => (false, true),
+                        2 => (true, false),
+                        _ => (true, true),
+                    };
+                    let is_open = (!toggles_bold || self.bold) && (!toggles_italic || self.italic);
+                    if (is_open && can_close) || (!is_open && can_open) {
+                        self.bold ^= toggles_bold;
+                        self.italic ^= toggles_italic;
+                    } else {
+                        self.push_text(&ch.to_string().repeat(run));
+                    }
+                    idx += run;
+                }
+                '!' if chars.get(idx + 1) == Some(&'[') && link_end(&chars, idx + 1).is_some() => idx += 1,
+                '[' => {
+                    if let Some(end) = link_end(&chars, idx) {
+                        link_ends.push(end);
+                    } else {
+                        self.push_text("[");
+                    }
+                    idx += 1;
+                }
+                '<' => match chars[idx..].iter().position(|ch| *ch == '>') {
+                    Some(end) if is_autolink(&chars[idx + 1..idx + end]) => {
+                        self.push_text(&chars[idx + 1..idx + end].iter().collect::<String>());
+                        idx += end + 1;
+                    }
+                    _ => {
+                        self.push_text("<");
+                        idx += 1;
+                    }
+                },
+                _ => {
+                    let end = chars[idx..]
+                        .iter()
+                        .position(|ch| matches!(ch, '\\' | '`' | '*' | '_' | '!' | '[' | ']' | '<'))
+                        .map(|offset| idx + offset.max(1))
+                        .unwrap_or(chars.len());
+                    self.push_text(&chars[idx..end].iter().collect::<String>());
+                    idx = end;
+                }
+            }
+        }
+    }
+}
+
+fn count_run(chars: &[char], idx: usize, ch: char) -> usize {
+    chars[idx..].iter().take_while(|c| **c == ch).count()
+}
+
+/// start of the next run of the char with the exact length
+fn find_run(chars: &[char], from: usize, ch: char, len: usize) -> Option<usize> {
+    let mut idx = from;
+    while idx < chars.len() {
+        if chars[idx] == ch {
+            let run = count_run(chars, idx, ch);
+            if run == len {
+                return Some(idx);
+            }
+            idx += run;
+        } else {
+            idx += 1;
+        }
+    }
+    None
+}
+
+/// closing bracket and the end of the destination of the link opened at idx: [text](url) or [text][ref]
+fn link_end(chars: &[char], idx: usize) -> Option<(usize, usize)> {
+    let close = idx + chars[idx..].iter().position(|ch| *ch == ']')?;
+    let closing = match chars.get(close + 1)? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    let end = close + 1 + chars[close + 1..].iter().position(|ch| *ch == closing)?;
+    Some((close, end))
+}
+
+fn is_autolink(text: &[char]) -> bool {
+    let text = text.iter().collect::<String>();
+    !text.contains(char::is_whitespace) && (text.starts_with("http://") || text.starts_with("https://"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{markdown_line, parse_markdown};
+    use crate::{
+        configs::Theme,
+        lsp::Highlighter,
+        render::{
+            backend::Style,
+            widgets::{StyledLine, Text},
+        },
+    };
+
+    fn styled(parts: &[(&str, Option<Style>)]) -> StyledLine {
+        parts.iter().map(|(text, style)| Text::new(text.to_string(), *style)).collect::<Vec<_>>().into()
+    }
+
+    #[test]
+    fn inline_styles() {
+        let theme = Theme::default();
+        let code = Some(Style::fg(theme.class_or_struct));
+        let mut bold = Style::default();
+        bold.add_bold();
+        let mut italic = Style::default();
+        italic.add_italic();
+        let line = markdown_line("Returns `None` if **empty**, see [`Vec`](https://doc.rust-lang.org)", &theme);
+        let expected = styled(&[
+            ("Returns ", None),
+            ("None", code),
+            (" if ", None),
+            ("empty", Some(bold)),
+            (", see ", None),
+            ("Vec", code),
+        ]);
+        assert!(line == expected);
+        // snake case and math are not emphasis
+        let line = markdown_line("call some_fn_name with 2 * 3 * 4 or \\*escaped\\*", &theme);
+        assert!(line == styled(&[("call some_fn_name with 2 * 3 * 4 or *escaped*", None)]));
+        let line = markdown_line("_italic_ and *also*", &theme);
+        assert!(line == styled(&[("italic", Some(italic)), (" and ", None), ("also", Some(italic))]));
+        let line = markdown_line("***both***", &theme);
+        assert_eq!(line.to_string(), "both");
+        // unclosed code span
+        assert_eq!(markdown_line("a ` tick", &theme).to_string(), "a ` tick");
+        let line = markdown_line("<https://x.com> [ref][1] ![img](x.png) [not a link]", &theme);
+        assert_eq!(line.to_string(), "https://x.com ref img [not a link]");
+    }
+
+    #[test]
+    fn blocks() {
+        let theme = Theme::default();
+        let mut bold = Style::default();
+        bold.add_bold();
+        let mut lines = Vec::new();
+        let mut sty = Highlighter::new(&theme);
+        let text = "```rust\nfn main() {}\n```\n\n---\n\n# Header\n  - item\n* item\n> quoted\n```unknown\nplain\n```\n```py\nopen";
+        parse_markdown(text, &mut sty, &theme, &mut lines);
+        let rendered = lines.iter().map(StyledLine::to_string).collect::<Vec<_>>();
+        let rule = "─".repeat(40);
+        assert_eq!(
+            rendered,
+            [
+                "fn main() {}",
+                "",
+                &rule,
+                "",
+                "Header",
+                "  • item",
+                "• item",
+                "│ quoted",
+                "plain",
+                "open"
+            ]
+        );
+        // highlighted by the rust tokens
+        assert!(lines[0] != styled(&[("fn main() {}", None)]));
+        assert!(lines[4] == styled(&[("Header", Some(bold))]));
+    }
+}
@@ -1,23 +1,31 @@
 mod completion;
 mod info;
+mod markdown;
+mod peek;
 mod rename;
+mod signature;
 
 use crate::{
     configs::{EditorAction, Theme},
     global_state::GlobalState,
     render::{backend::BackendProtocol, layout::Rect},
-    syntax::{DiagnosticInfo, Lang},
-    workspace::CursorPosition,
+    syntax::DiagnosticInfo,
+    workspace::{line::EditorLine, CursorPosition},
 };
 use completion::AutoComplete;
 use info::Info;
-use lsp_types::{CompletionItem, Hover, SignatureHelp};
+use lsp_types::{CompletionItem, Hover, Location, SignatureHelp};
+pub use peek::first_location;
+use peek::PeekDefinition;
 use rename::RenameVariable;
+use signature::SignatureModal;
 
 pub enum LSPModal {
     AutoComplete(AutoComplete),
     RenameVar(RenameVariable),
     Info(Info),
+    Signature(SignatureModal),
+    Peek(PeekDefinition),
 }
 
 #[derive(Default, Debug)]
@@ -28,6 +36,7 @@ pub enum ModalMessage {
     Done,
     TakenDone,
     RenameVar(String, CursorPosition),
+    Completion(Box<CompletionItem>),
 }
 
 impl<T> From<&[T]> for ModalMessage {
@@ -41,17 +50,63 @@ impl<T> From<&[T]> for ModalMessage {
 }
 
 impl LSPModal {
-    pub fn map_and_finish(&mut self, action: EditorAction, lang: &Lang, gs: &mut GlobalState) -> ModalMessage {
+    pub fn map_and_finish(&mut self, action: EditorAction, gs: &mut GlobalState) -> ModalMessage {
         match action {
             EditorAction::Cancel | EditorAction::Close => ModalMessage::TakenDone,
             _ => match self {
-                Self::AutoComplete(modal) => modal.map(action, lang, gs),
+                Self::AutoComplete(modal) => match modal.map(action) {
+                    ModalMessage::Done => self.completion_done(action),
+                    message => message,
+                },
                 Self::Info(modal) => modal.map(action, gs),
                 Self::RenameVar(modal) => modal.map(action, gs),
+                Self::Signature(modal) => modal.map(action),
+                Self::Peek(modal) => modal.map(action, gs),
             },
         }
     }
 
+    /// mouse click on line of the rendered modal
+    pub fn map_click(&mut self, line: usize) -> ModalMessage {
+        match self {
+            Self::AutoComplete(modal) => modal.click(line),
+            Self::Info(..) | Self::RenameVar(..) | Self::Signature(..) | Self::Peek(..) => ModalMessage::None,
+        }
+    }
+
+    /// continues scoring of huge completion responses - Taken if the modal needs render
+    pub fn score_completions(&mut self) -> ModalMessage {
+        match self {
+            Self::AutoComplete(modal) => match modal.score_next_chunk() {
+                true if modal.is_empty() => match modal.take_signature() {
+                    Some(signature) => {
+                        *self = Self::Signature(signature);
+                        ModalMessage::None
+                    }
+                    None => ModalMessage::Done,
+                },
+                true => ModalMessage::Taken,
+                false => ModalMessage::None,
+            },
+            _ => ModalMessage::None,
+        }
+    }
+
+    /// signature help attached to the completion takes its place if the action does not close it
+    fn completion_done(&mut self, action: EditorAction) -> ModalMessage {
+        let Self::AutoComplete(modal) = self else {
+            return ModalMessage::Done;
+        };
+        let Some(mut signature) = modal.take_signature() else {
+            return ModalMessage::Done;
+        };
+        let message = signature.map(action);
+        if matches!(message, ModalMessage::None) {
+            *self = Self::Signature(signature);
+        }
+        message
+    }
+
     pub fn render_at(&mut self, col: u16, row: u16, gs: &mut GlobalState) -> Option<Rect> {
         match self {
             Self::AutoComplete(modal) => {
@@ -83,16 +138,46 @@ impl LSPModal {
                     return Some(area);
                 };
             }
+            Self::Signature(modal) => {
+                let area = gs.screen_rect.modal_relative(row, col, 80, modal.len() as u16);
+                if area.height != 0 {
+                    gs.writer.set_style(gs.theme.accent_style);
+                    modal.render(area, gs);
+                    gs.writer.reset_style();
+                    return Some(area);
+                };
+            }
+            Self::Peek(modal) => {
+                let area = gs.screen_rect.modal_relative(row, col, 80, modal.len() as u16);
+                if area.height > 1 {
+                    gs.writer.set_style(gs.theme.accent_style);
+                    modal.render(area, gs);
+                    gs.writer.reset_style();
+                    return Some(area);
+                };
+            }
         }
         None
     }
 
-    pub fn auto_complete(completions: Vec<CompletionItem>, line: String, c: CursorPosition) -> Option<Self> {
-        let modal = AutoComplete::new(completions, line, c);
-        if modal.len() != 0 {
+    /// signature help shown (or attached to the replaced completion) is kept in the first row
+    pub fn auto_complete(
+        completions: Vec<CompletionItem>,
+        line: String,
+        c: CursorPosition,
+        replaced: Option<Self>,
+    ) -> Option<Self> {
+        let signature = match replaced {
+            Some(Self::Signature(signature)) => Some(signature),
+            Some(Self::AutoComplete(mut modal)) => modal.take_signature(),
+            _ => None,
+        };
+        let mut modal = AutoComplete::new(completions, line, c);
+        if !modal.is_empty() {
+            modal.set_signature(signature);
             return Some(LSPModal::AutoComplete(modal));
         }
-        None
+        signature.map(Self::Signature)
     }
 
     pub fn actions(actions: DiagnosticInfo) -> Self {
@@ -106,21 +191,38 @@ impl LSPModal {
     pub fn hover_map(&mut self, hover: Hover, theme: &Theme) {
         match self {
             Self::Info(modal) => modal.push_hover(hover, theme),
+            // help requests signatures with the hover
+            Self::Signature(modal) => {
+                let mut info = Info::from_signature(modal.signature().clone(), theme);
+                info.push_hover(hover, theme);
+                *self = Self::Info(info);
+            }
             _ => *self = Self::Info(Info::from_hover(hover, theme)),
         }
     }
 
-    pub fn from_signature(signature: SignatureHelp, theme: &Theme) -> Self {
-        Self::Info(Info::from_signature(signature, theme))
+    /// None if the server has no signatures
+    pub fn from_signature(signature: SignatureHelp) -> Option<Self> {
+        SignatureModal::new(signature).map(Self::Signature)
     }
 
-    pub fn signature_map(&mut self, signature: SignatureHelp, theme: &Theme) {
-        match self {
-            Self::Info(modal) => modal.push_signature(signature, theme),
-            _ => *self = Self::Info(Info::from_signature(signature, theme)),
+    /// open completion shows the signature in its first row instead of a second modal
+    pub fn completion_signature(&mut self, signature: SignatureHelp) {
+        if let Self::AutoComplete(modal) = self {
+            modal.set_signature(SignatureModal::new(signature));
         }
     }
 
+    /// None if the definition line is not loaded in the content
+    pub fn peek_content(location: Location, content: &[EditorLine], same_file: bool) -> Option<Self> {
+        PeekDefinition::from_content(location, content, same_file).map(Self::Peek)
+    }
+
+    /// None if the file can not be read
+    pub fn peek_file(location: Location, theme: &Theme) -> Option<Self> {
+        PeekDefinition::from_file(location, theme).map(Self::Peek)
+    }
+
     pub fn renames_at(c: CursorPosition, title: &str) -> Self {
         Self::RenameVar(RenameVariable::new(c, title))
     }
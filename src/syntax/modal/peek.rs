@@ -0,0 +1,259 @@
+use super::ModalMessage;
+use crate::{
+    configs::{EditorAction, FileType, Theme},
+    global_state::{GlobalState, IdiomEvent},
+    lsp::init_local_tokens,
+    render::{
+        backend::Style,
+        layout::{IterLines, Rect},
+    },
+    workspace::{
+        line::{EditorLine, LineLoader},
+        CursorPosition,
+    },
+};
+use lsp_types::{GotoDefinitionResponse, Location};
+use std::{ops::Range, path::PathBuf};
+
+/// lines of code shown under the title
+pub const PEEK_ROWS: usize = 15;
+/// lines loaded around the definition - the preview can be scrolled within them
+const PEEK_CONTEXT: usize = 40;
+
+/// text of the line split on token boundaries, plain parts have no style
+type PeekLine = Vec<(String, Option<Style>)>;
+
+/// Preview of the definition target centered on the definition line - Enter opens the location
+/// definitions in open files are peeked from the editor content (with the tokens it already has)
+pub struct PeekDefinition {
+    path: PathBuf,
+    position: CursorPosition,
+    same_file: bool,
+    /// content line of the first loaded line
+    first_line: usize,
+    lines: Vec<PeekLine>,
+    /// first rendered line (index within loaded lines)
+    at_line: usize,
+}
+
+impl PeekDefinition {
+    /// None if the definition line is not loaded in the content
+    pub fn from_content(location: Location, content: &[EditorLine], same_file: bool) -> Option<Self> {
+        let position = CursorPosition::from(location.range.start);
+        let loaded = context_lines(position.line);
+        let lines = content.get(loaded.start..loaded.end.min(content.len()))?.iter().map(peek_line).collect();
+        Self::centered(&location, position, same_file, loaded.start, lines)
+    }
+
+    /// file not open in the workspace - big files are read only up to the loaded lines
+    /// None if the file can not be read or is shorter than the definition line
+    pub fn from_file(location: Location, theme: &Theme) -> Option<Self> {
+        let position = CursorPosition::from(location.range.start);
+        let path = PathBuf::from(location.uri.path().as_str());
+        let loaded = context_lines(position.line);
+        let (content, _) = LineLoader::open(&path, loaded.end).ok()?;
+        let mut lines = content.into_iter().skip(loaded.start).take(loaded.len()).collect::<Vec<_>>();
+        if let Some(file_type) = FileType::derive_type(&path) {
+            init_local_tokens(file_type, &mut lines, theme);
+        }
+        let lines = lines.iter().map(peek_line).collect();
+        Self::centered(&location, position, false, loaded.start, lines)
+    }
+
+    fn centered(
+        location: &Location,
+        position: CursorPosition,
+        same_file: bool,
+        first_line: usize,
+        lines: Vec<PeekLine>,
+    ) -> Option<Self> {
+        let definition_idx = position.line - first_line;
+        if definition_idx >= lines.len() {
+            return None;
+        }
+        let at_line = definition_idx.saturating_sub(PEEK_ROWS / 2);
+        let path = PathBuf::from(location.uri.path().as_str());
+        Some(Self { path, position, same_file, first_line, lines, at_line })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        std::cmp::min(self.lines.len(), PEEK_ROWS) + 1
+    }
+
+    pub fn map(&mut self, action: EditorAction, gs: &mut GlobalState) -> ModalMessage {
+        match action {
+            EditorAction::Up => {
+                self.at_line = self.at_line.saturating_sub(1);
+                ModalMessage::Taken
+            }
+            EditorAction::Down => {
+                if self.at_line + PEEK_ROWS < self.lines.len() {
+                    self.at_line += 1;
+                }
+                ModalMessage::Taken
+            }
+            EditorAction::NewLine => {
+                let CursorPosition { line, char } = self.position;
+                if !self.same_file {
+                    gs.event.push(IdiomEvent::OpenAtLine(self.path.clone(), line));
+                }
+                gs.event.push(IdiomEvent::GoToLine { line, char: Some(char), clear_popup: true });
+                ModalMessage::TakenDone
+            }
+            _ => ModalMessage::Done,
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, gs: &mut GlobalState) {
+        let accent = gs.theme.accent_style;
+        let mut lines = area.into_iter();
+        let Some(title) = lines.next() else {
+            return;
+        };
+        let name = self.path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let mut title_style = accent;
+        title_style.add_bold();
+        title.render_styled(
+            &format!(" {name}:{} (Enter to open) ", self.position.line + 1),
+            title_style,
+            &mut gs.writer,
+        );
+
+        self.at_line = self.at_line.min(self.lines.len().saturating_sub(lines.len()));
+        let number_width = (self.first_line + self.lines.len()).to_string().len();
+        let definition_idx = self.position.line - self.first_line;
+        for (idx, code) in self.lines.iter().enumerate().skip(self.at_line) {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let mut number_style = accent;
+            if idx == definition_idx {
+                number_style.add_reverse();
+            }
+            let mut builder = line.unsafe_builder(&mut gs.writer);
+            builder.push_styled(&format!("{:>number_width$} ", self.first_line + idx + 1), number_style);
+            for (text, style) in code.iter() {
+                let fits = match style {
+                    Some(style) => {
+                        let mut merged = accent;
+                        merged.update(*style);
+                        builder.push_styled(text, merged)
+                    }
+                    None => builder.push(text),
+                };
+                if !fits {
+                    break;
+                }
+            }
+        }
+        lines.clear_to_end(&mut gs.writer);
+    }
+}
+
+/// lines loaded around the definition line
+fn context_lines(line: usize) -> Range<usize> {
+    line.saturating_sub(PEEK_CONTEXT)..line + PEEK_CONTEXT + 1
+}
+
+pub fn first_location(response: GotoDefinitionResponse) -> Option<Location> {
+    match response {
+        GotoDefinitionResponse::Scalar(location) => Some(location),
+        GotoDefinitionResponse::Array(locations) => locations.into_iter().next(),
+        GotoDefinitionResponse::Link(links) => {
+            links.into_iter().next().map(|link| Location { uri: link.target_uri, range: link.target_selection_range })
+        }
+    }
+}
+
+/// tokens are positioned by chars, tabs are expanded
+pub fn peek_line(line: &EditorLine) -> PeekLine {
+    let chars = line.content.chars().map(|ch| if ch == '\t' { ' ' } else { ch }).collect::<Vec<_>>();
+    let mut parts = Vec::new();
+    let mut last_end = 0;
+    let mut token_start = 0;
+    for token in line.tokens.iter() {
+        token_start += token.delta_start;
+        let start = token_start.clamp(last_end, chars.len());
+        let end = (token_start + token.len).min(chars.len());
+        if start >= end {
+            continue;
+        }
+        if last_end < start {
+            parts.push((chars[last_end..start].iter().collect(), None));
+        }
+        parts.push((chars[start..end].iter().collect(), Some(token.style)));
+        last_end = end;
+    }
+    if last_end < chars.len() {
+        parts.push((chars[last_end..].iter().collect(), None));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod test {
+    use super::{peek_line, PeekDefinition, PEEK_CONTEXT, PEEK_ROWS};
+    use crate::{
+        configs::{FileType, Theme},
+        lsp::init_local_tokens,
+        workspace::line::EditorLine,
+    };
+    use lsp_types::{Location, Position, Range, Uri};
+    use std::{path::Path, str::FromStr};
+
+    fn location(path: &Path, line: u32) -> Location {
+        let uri = Uri::from_str(&format!("file://{}", path.display())).unwrap();
+        Location::new(uri, Range::new(Position::new(line, 3), Position::new(line, 7)))
+    }
+
+    #[test]
+    fn peek_from_content() {
+        let content = (0..100).map(|idx| EditorLine::from(format!("fn f{idx}() {{}}"))).collect::<Vec<_>>();
+        let peek = PeekDefinition::from_content(location(Path::new("/project/lib.rs"), 50), &content, false).unwrap();
+        assert_eq!(peek.first_line, 50 - PEEK_CONTEXT);
+        assert_eq!(peek.lines.len(), PEEK_CONTEXT * 2 + 1);
+        assert_eq!(peek.at_line, PEEK_CONTEXT - PEEK_ROWS / 2);
+        assert_eq!(peek.lines[PEEK_CONTEXT][0].0, "fn f50() {}");
+        assert_eq!(peek.path, Path::new("/project/lib.rs"));
+        // close to the start and end of the content
+        let peek =
+            PeekDefinition::from_content(location(Path::new("/project/lib.rs"), 2), &content[..5], true).unwrap();
+        assert_eq!((peek.first_line, peek.lines.len(), peek.at_line), (0, 5, 0));
+        // not loaded
+        assert!(PeekDefinition::from_content(location(Path::new("/project/lib.rs"), 5), &content[..5], true).is_none());
+    }
+
+    #[test]
+    fn peek_from_file() {
+        let path = std::env::temp_dir().join(format!("idiom_peek_{}.rs", std::process::id()));
+        let text = (0..100).map(|idx| format!("fn f{idx}() {{}}")).collect::<Vec<_>>().join("\n");
+        std::fs::write(&path, text).unwrap();
+        let peek = PeekDefinition::from_file(location(&path, 90), &Theme::default()).unwrap();
+        assert_eq!(peek.first_line, 90 - PEEK_CONTEXT);
+        assert_eq!(peek.lines.len(), 100 - peek.first_line);
+        let definition = &peek.lines[90 - peek.first_line];
+        assert_eq!(definition.iter().map(|(text, _)| text.as_str()).collect::<String>(), "fn f90() {}");
+        // tokens from the file type
+        assert!(definition[0].1.is_some());
+        assert!(PeekDefinition::from_file(location(&path, 100), &Theme::default()).is_none());
+        std::fs::remove_file(&path).unwrap();
+        assert!(PeekDefinition::from_file(location(&path, 0), &Theme::default()).is_none());
+    }
+
+    #[test]
+    fn tokens_split_line() {
+        let mut content = vec![
+            EditorLine::from(String::from("fn main() {")),
+            EditorLine::from(String::from("}")),
+        ];
+        init_local_tokens(FileType::Rust, &mut content, &Theme::default());
+        let parts = peek_line(&content[0]);
+        assert_eq!(parts.iter().map(|(text, _)| text.as_str()).collect::<String>(), "fn main() {");
+        assert_eq!(parts[0].0, "fn");
+        assert!(parts[0].1.is_some());
+        assert!(parts.iter().any(|(text, style)| text == "main" && style.is_some()));
+        let plain = peek_line(&EditorLine::from(String::from("\tlet x = 1;")));
+        assert_eq!(plain, vec![(String::from(" let x = 1;"), None)]);
+    }
+}
@@ -0,0 +1,238 @@
+use super::ModalMessage;
+use crate::{
+    configs::EditorAction,
+    global_state::GlobalState,
+    render::layout::{IterLines, Line, Rect},
+};
+use lsp_types::{Documentation, ParameterLabel, SignatureHelp, SignatureInformation};
+use std::ops::Range;
+
+/// Parameter hints shown while typing call arguments - the active parameter is highlighted
+/// stays open while typing (autocomplete can take its place), closed on ')'
+pub struct SignatureModal {
+    label: String,
+    /// byte range of the active parameter within the label
+    active: Option<Range<usize>>,
+    /// first line of the active parameter documentation
+    doc: Option<String>,
+    /// index of shown signature with the number of overloads
+    overloads: Option<(usize, usize)>,
+    signature: SignatureHelp,
+}
+
+impl SignatureModal {
+    /// None if the server has no signatures
+    pub fn new(signature: SignatureHelp) -> Option<Self> {
+        let count = signature.signatures.len();
+        let idx = signature.active_signature.map(|idx| idx as usize).filter(|idx| *idx < count).unwrap_or_default();
+        let info = signature.signatures.get(idx)?;
+        let label = info.label.to_owned();
+        let (active, doc) = match info.active_parameter.or(signature.active_parameter) {
+            Some(param_idx) => active_parameter(info, param_idx as usize),
+            None => (None, None),
+        };
+        let overloads = if count > 1 { Some((idx + 1, count)) } else { None };
+        Some(Self { label, active, doc, overloads, signature })
+    }
+
+    pub fn signature(&self) -> &SignatureHelp {
+        &self.signature
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        if self.doc.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn map(&mut self, action: EditorAction) -> ModalMessage {
+        match action {
+            EditorAction::Char(')') => ModalMessage::Done,
+            EditorAction::Char(..) => ModalMessage::None,
+            _ => ModalMessage::Done,
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, gs: &mut GlobalState) {
+        let mut lines = area.iter_padded(1);
+        if let Some(line) = lines.next() {
+            self.render_label(line, gs);
+        }
+        if let Some(doc) = self.doc.as_ref() {
+            if let Some(line) = lines.next() {
+                line.render(doc, &mut gs.writer);
+            }
+        }
+        lines.clear_to_end(&mut gs.writer);
+    }
+
+    /// signature with the active parameter highlighted - completion renders it above the options
+    pub fn render_label(&self, line: Line, gs: &mut GlobalState) {
+        let mut active_style = gs.theme.accent_style;
+        active_style.add_bold();
+        active_style.underline(None);
+        let mut builder = line.unsafe_builder(&mut gs.writer);
+        if let Some((idx, count)) = self.overloads {
+            builder.push(&format!("{idx}/{count} "));
+        }
+        match self.active.clone() {
+            Some(range) => {
+                builder.push(&self.label[..range.start]);
+                builder.push_styled(&self.label[range.clone()], active_style);
+                builder.push(&self.label[range.end..]);
+            }
+            None => {
+                builder.push(&self.label);
+            }
+        }
+    }
+}
+
+/// byte range of the parameter in the signature label with the first line of its docs
+fn active_parameter(info: &SignatureInformation, param_idx: usize) -> (Option<Range<usize>>, Option<String>) {
+    let Some(param) = info.parameters.as_ref().and_then(|params| params.get(param_idx)) else {
+        return (None, None);
+    };
+    let range = match &param.label {
+        ParameterLabel::Simple(text) if !text.is_empty() => {
+            info.label.find(text.as_str()).map(|start| start..start + text.len())
+        }
+        ParameterLabel::Simple(..) => None,
+        ParameterLabel::LabelOffsets([start, end]) => utf16_to_byte(&info.label, *start as usize)
+            .zip(utf16_to_byte(&info.label, *end as usize))
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| start..end),
+    };
+    let doc = param.documentation.as_ref().and_then(|doc| {
+        let text = match doc {
+            Documentation::String(text) => text,
+            Documentation::MarkupContent(content) => &content.value,
+        };
+        text.lines().map(str::trim).find(|line| !line.is_empty()).map(String::from)
+    });
+    (range, doc)
+}
+
+/// label offsets are in UTF-16 code units
+fn utf16_to_byte(text: &str, offset: usize) -> Option<usize> {
+    let mut utf16_idx = 0;
+    for (byte_idx, ch) in text.char_indices() {
+        if utf16_idx >= offset {
+            return (utf16_idx == offset).then_some(byte_idx);
+        }
+        utf16_idx += ch.len_utf16();
+    }
+    (utf16_idx == offset).then_some(text.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::SignatureModal;
+    use crate::configs::EditorAction;
+    use crate::global_state::GlobalState;
+    use crate::render::backend::{Backend, BackendProtocol};
+    use crate::syntax::modal::{LSPModal, ModalMessage};
+    use crate::workspace::CursorPosition;
+    use lsp_types::{CompletionItem, ParameterInformation, ParameterLabel, SignatureHelp, SignatureInformation};
+
+    fn signature(label: &str, params: Vec<ParameterLabel>, active: u32) -> SignatureInformation {
+        SignatureInformation {
+            label: label.to_owned(),
+            documentation: None,
+            parameters: Some(
+                params.into_iter().map(|label| ParameterInformation { label, documentation: None }).collect(),
+            ),
+            active_parameter: Some(active),
+        }
+    }
+
+    #[test]
+    fn active_parameter() {
+        let help = SignatureHelp {
+            signatures: vec![signature(
+                "fn add(lhs: usize, rhs: usize) -> usize",
+                vec![
+                    ParameterLabel::Simple("lhs: usize".into()),
+                    ParameterLabel::Simple("rhs: usize".into()),
+                ],
+                1,
+            )],
+            active_signature: None,
+            active_parameter: None,
+        };
+        let modal = SignatureModal::new(help).unwrap();
+        assert_eq!(&modal.label[modal.active.clone().unwrap()], "rhs: usize");
+        assert_eq!(modal.overloads, None);
+        assert_eq!(modal.len(), 1);
+        // offsets are UTF-16
+        let help = SignatureHelp {
+            signatures: vec![
+                signature("fn a()", vec![], 0),
+                signature("fn ü(𝔁: i32, y: i32)", vec![ParameterLabel::LabelOffsets([5, 12])], 0),
+            ],
+            active_signature: Some(1),
+            active_parameter: None,
+        };
+        let modal = SignatureModal::new(help).unwrap();
+        assert_eq!(&modal.label[modal.active.clone().unwrap()], "𝔁: i32");
+        assert_eq!(modal.overloads, Some((2, 2)));
+        let empty = SignatureHelp { signatures: vec![], active_signature: None, active_parameter: None };
+        assert!(SignatureModal::new(empty).is_none());
+    }
+
+    #[test]
+    fn closed_on_paren() {
+        let help = SignatureHelp {
+            signatures: vec![signature(
+                "fn f(x: i32)",
+                vec![ParameterLabel::Simple("x: i32".into())],
+                0,
+            )],
+            active_signature: None,
+            active_parameter: None,
+        };
+        let mut modal = SignatureModal::new(help).unwrap();
+        assert!(matches!(modal.map(EditorAction::Char('1')), ModalMessage::None));
+        assert!(matches!(modal.map(EditorAction::Char(')')), ModalMessage::Done));
+        assert!(matches!(modal.map(EditorAction::Down), ModalMessage::Done));
+    }
+
+    #[test]
+    fn shared_with_completion() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let help = || SignatureHelp {
+            signatures: vec![signature(
+                "fn f(x: i32)",
+                vec![ParameterLabel::Simple("x: i32".into())],
+                0,
+            )],
+            active_signature: None,
+            active_parameter: None,
+        };
+        let completions = || vec![CompletionItem::new_simple(String::from("value"), String::new())];
+        let c = CursorPosition { line: 0, char: 3 };
+        let shown = LSPModal::from_signature(help());
+        let mut modal = LSPModal::auto_complete(completions(), String::from("f(v"), c, shown).unwrap();
+        let LSPModal::AutoComplete(completion) = &modal else {
+            panic!("Expected completion");
+        };
+        // signature row above the option
+        assert_eq!(completion.len(), 2);
+        // completion is closed - the signature is shown again
+        assert!(matches!(modal.map_and_finish(EditorAction::Char(','), &mut gs), ModalMessage::None));
+        assert!(matches!(modal, LSPModal::Signature(..)));
+        // closing the call closes both
+        let mut modal = LSPModal::auto_complete(completions(), String::from("f(v"), c, Some(modal)).unwrap();
+        assert!(matches!(modal.map_and_finish(EditorAction::Char(')'), &mut gs), ModalMessage::Done));
+        // signature help arriving with completion open
+        let mut modal = LSPModal::auto_complete(completions(), String::from("f(v"), c, None).unwrap();
+        modal.completion_signature(help());
+        assert!(matches!(&modal, LSPModal::AutoComplete(completion) if completion.len() == 2));
+        // no completions - the signature stays
+        let shown = LSPModal::from_signature(help());
+        assert!(matches!(LSPModal::auto_complete(vec![], String::new(), c, shown), Some(LSPModal::Signature(..))));
+    }
+}
@@ -1,8 +1,14 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Instant};
 
 use lsp_types::SemanticToken;
 
-use crate::{configs::FileType, global_state::GlobalState, render::backend::Style, workspace::line::EditorLine};
+use crate::{
+    configs::{FileType, Theme},
+    global_state::GlobalState,
+    lsp::init_local_tokens,
+    render::backend::Style,
+    workspace::line::EditorLine,
+};
 
 use super::{
     lsp_calls::{char_lsp_utf16, char_lsp_utf8, encode_pos_utf16, encode_pos_utf8},
@@ -515,3 +521,84 @@ fn test_token_dec() {
     tl.decrement_at(1);
     assert_eq!(tl, token_line);
 }
+
+#[test]
+fn test_token_range_edits() {
+    let mut tl = create_tokens();
+    // insert inside the first token
+    tl.increment_at_by(1, 3);
+    let mut token_line = TokenLine::default();
+    token_line.push(Token { len: 6, delta_start: 0, style: Style::default() });
+    token_line.push(Token { len: 4, delta_start: 7, style: Style::default() });
+    assert_eq!(tl, token_line);
+    // remove across both tokens
+    tl.remove_range(4, 9);
+    let mut token_line = TokenLine::default();
+    token_line.push(Token { len: 4, delta_start: 0, style: Style::default() });
+    token_line.push(Token { len: 2, delta_start: 4, style: Style::default() });
+    assert_eq!(tl, token_line);
+    tl.remove_range(0, 10);
+    assert_eq!(tl, TokenLine::default());
+    // removed token is dropped - the next one is shifted from the last kept token
+    let mut tl = TokenLine::default();
+    tl.push(Token { len: 2, delta_start: 0, style: Style::default() });
+    tl.push(Token { len: 2, delta_start: 4, style: Style::default() });
+    tl.push(Token { len: 2, delta_start: 4, style: Style::default() });
+    tl.remove_range(3, 7);
+    let mut token_line = TokenLine::default();
+    token_line.push(Token { len: 2, delta_start: 0, style: Style::default() });
+    token_line.push(Token { len: 2, delta_start: 4, style: Style::default() });
+    assert_eq!(tl, token_line);
+}
+
+#[test]
+fn test_long_line_edits_keep_tokens() {
+    let mut line = EditorLine::new("word ".repeat(400));
+    line.push_token(Token { len: 4, delta_start: 0, style: Style::default() });
+    for _ in 1..400 {
+        line.push_token(Token { len: 4, delta_start: 5, style: Style::default() });
+    }
+    // offsets are updated in place - no reparse of the 2000 chars line
+    line.insert_str(1000, "new ");
+    line.replace_range(1000..1004, "");
+    line.insert(1000, 'x');
+    line.remove(1000);
+    line.replace_range(1002..1003, "R");
+    line.replace_till(4, "WORD");
+    assert_eq!(line.char_len(), 2000);
+    assert_eq!(line.iter_tokens().count(), 400);
+    assert!(line.iter_tokens().all(|token| token.len == 4));
+    assert!(line.iter_tokens().skip(1).all(|token| token.delta_start == 5));
+}
+
+#[test]
+#[ignore = "benchmark - cargo test --release bench_long_line_tokens -- --ignored --nocapture"]
+fn bench_long_line_tokens() {
+    let theme = Theme::default();
+    let text = "let value = call(arg, 42); ".repeat(75);
+    let mut content = vec![EditorLine::new(text.clone()), EditorLine::new(text)];
+    init_local_tokens(FileType::Rust, &mut content, &theme);
+    assert!(content[0].char_len() >= 2000);
+    let edits = 1_000;
+    // offsets are shifted in place
+    let mut line = content.pop().unwrap();
+    let start = Instant::now();
+    for idx in 0..edits {
+        line.insert(1000 + idx % 100, 'x');
+        line.remove(1000 + idx % 100);
+    }
+    let shifted = start.elapsed();
+    // whole line is tokenized again after each edit
+    let start = Instant::now();
+    for idx in 0..edits {
+        content[0].insert(1000 + idx % 100, 'x');
+        init_local_tokens(FileType::Rust, &mut content, &theme);
+        content[0].remove(1000 + idx % 100);
+        init_local_tokens(FileType::Rust, &mut content, &theme);
+    }
+    let reparsed = start.elapsed();
+    println!("{edits} char insert/remove pairs on {} chars line", line.char_len());
+    println!("shifted tokens: {shifted:?}, reparsed line: {reparsed:?}");
+    assert_eq!(line.iter_tokens().count(), content[0].iter_tokens().count());
+    assert!(shifted < reparsed);
+}
@@ -86,17 +86,26 @@ impl TokenLine {
         }
     }
 
-    pub fn increment_at(&mut self, mut idx: usize) {
+    #[inline]
+    pub fn increment_at(&mut self, idx: usize) {
+        self.increment_at_by(idx, 1);
+    }
+
+    /// offsets are shifted for text of count chars inserted at idx - token containing idx grows
+    pub fn increment_at_by(&mut self, mut idx: usize, count: usize) {
+        if count == 0 {
+            return;
+        }
         let mut token_iter = self.inner.iter_mut();
         while let Some(token) = token_iter.next() {
             if idx < token.delta_start {
-                token.delta_start += 1;
+                token.delta_start += count;
                 return;
             };
             if idx < token.delta_start + token.len {
-                token.len += 1;
+                token.len += count;
                 if let Some(next_token) = token_iter.next() {
-                    next_token.delta_start += 1;
+                    next_token.delta_start += count;
                 }
                 return;
             }
@@ -104,6 +113,33 @@ impl TokenLine {
         }
     }
 
+    /// offsets are shifted for removed chars in range from..to - overlapping tokens are shortened,
+    /// tokens left without chars are dropped
+    pub fn remove_range(&mut self, from: usize, to: usize) {
+        if from >= to {
+            return;
+        }
+        let removed = to - from;
+        let shift = |idx: usize| match idx {
+            idx if idx <= from => idx,
+            idx if idx >= to => idx - removed,
+            _ => from,
+        };
+        let mut start = 0;
+        let mut new_start = 0;
+        self.inner.retain_mut(|token| {
+            start += token.delta_start;
+            let shifted_start = shift(start);
+            token.len = shift(start + token.len) - shifted_start;
+            if token.len == 0 {
+                return false;
+            }
+            token.delta_start = shifted_start - new_start;
+            new_start = shifted_start;
+            true
+        });
+    }
+
     pub fn decrement_at(&mut self, mut idx: usize) {
         let mut token_iter = self.inner.iter_mut();
         while let Some(token) = token_iter.next() {
@@ -0,0 +1,136 @@
+use crate::error::{IdiomError, IdiomResult};
+use std::path::{Path, PathBuf};
+
+type Failed = Vec<(PathBuf, IdiomError)>;
+
+/// Paths taken from the tree (marked ones or the selected one) to be pasted into a folder
+pub enum FileClipboard {
+    Cut(Vec<PathBuf>),
+    Copy(Vec<PathBuf>),
+}
+
+impl FileClipboard {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Cut(paths) | Self::Copy(paths) => paths.len(),
+        }
+    }
+
+    /// each path is pasted on its own - failures (like name collisions) are returned with the path,
+    /// the rest is still pasted; returns the moved paths (old, new) for cut
+    pub fn paste(&self, dir: &Path) -> (Vec<(PathBuf, PathBuf)>, Failed) {
+        let mut moved = Vec::new();
+        let mut errors = Vec::new();
+        match self {
+            Self::Cut(paths) => {
+                for path in paths {
+                    match paste_target(path, dir).and_then(|new_path| move_path(path, new_path)) {
+                        Ok(new_path) => moved.push((path.to_owned(), new_path)),
+                        Err(err) => errors.push((path.to_owned(), err)),
+                    }
+                }
+            }
+            Self::Copy(paths) => {
+                for path in paths {
+                    if let Err(err) = paste_target(path, dir).and_then(|new_path| copy_path(path, &new_path)) {
+                        errors.push((path.to_owned(), err));
+                    }
+                }
+            }
+        }
+        (moved, errors)
+    }
+}
+
+fn paste_target(path: &Path, dir: &Path) -> IdiomResult<PathBuf> {
+    let name = path.file_name().ok_or(IdiomError::io_err("Unable to paste path without name!"))?;
+    if dir.starts_with(path) {
+        return Err(IdiomError::io_err("Unable to paste folder into itself!"));
+    }
+    let new_path = dir.join(name);
+    if new_path.exists() {
+        return Err(IdiomError::io_err(format!("{} already exists!", new_path.display())));
+    }
+    Ok(new_path)
+}
+
+fn move_path(path: &Path, new_path: PathBuf) -> IdiomResult<PathBuf> {
+    std::fs::rename(path, &new_path)?;
+    Ok(new_path)
+}
+
+fn copy_path(path: &Path, new_path: &Path) -> IdiomResult<()> {
+    if !path.is_dir() {
+        std::fs::copy(path, new_path)?;
+        return Ok(());
+    }
+    std::fs::create_dir(new_path)?;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        copy_path(&entry.path(), &new_path.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::FileClipboard;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("idiom-file-clipboard-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+        std::fs::create_dir_all(dir.join("dest")).unwrap();
+        std::fs::write(dir.join("src/a.rs"), "a").unwrap();
+        std::fs::write(dir.join("src/b.rs"), "b").unwrap();
+        std::fs::write(dir.join("src/nested/c.rs"), "c").unwrap();
+        dir
+    }
+
+    #[test]
+    fn copy_continues_after_collision() {
+        let dir = temp_dir("copy");
+        let dest = dir.join("dest");
+        std::fs::write(dest.join("a.rs"), "existing").unwrap();
+        let clipboard = FileClipboard::Copy(vec![dir.join("src/a.rs"), dir.join("src/b.rs"), dir.join("src/nested")]);
+        let (moved, errors) = clipboard.paste(&dest);
+        assert!(moved.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, dir.join("src/a.rs"));
+        assert_eq!(std::fs::read_to_string(dest.join("a.rs")).unwrap(), "existing");
+        assert_eq!(std::fs::read_to_string(dest.join("b.rs")).unwrap(), "b");
+        assert_eq!(std::fs::read_to_string(dest.join("nested/c.rs")).unwrap(), "c");
+        // sources are kept
+        assert!(dir.join("src/b.rs").exists());
+        assert!(dir.join("src/nested/c.rs").exists());
+        // folder into itself
+        let (_, errors) = FileClipboard::Copy(vec![dir.join("src")]).paste(&dir.join("src/nested"));
+        assert_eq!(errors.len(), 1);
+        assert!(!dir.join("src/nested/src").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cut_returns_moved_paths() {
+        let dir = temp_dir("cut");
+        let dest = dir.join("dest");
+        std::fs::write(dest.join("b.rs"), "existing").unwrap();
+        let clipboard = FileClipboard::Cut(vec![dir.join("src/a.rs"), dir.join("src/b.rs"), dir.join("src/nested")]);
+        assert_eq!(clipboard.len(), 3);
+        let (moved, errors) = clipboard.paste(&dest);
+        assert_eq!(
+            moved,
+            [
+                (dir.join("src/a.rs"), dest.join("a.rs")),
+                (dir.join("src/nested"), dest.join("nested"))
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, dir.join("src/b.rs"));
+        assert!(!dir.join("src/a.rs").exists());
+        assert!(dir.join("src/b.rs").exists());
+        assert_eq!(std::fs::read_to_string(dest.join("b.rs")).unwrap(), "existing");
+        assert_eq!(std::fs::read_to_string(dest.join("nested/c.rs")).unwrap(), "c");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+use super::{IgnoreRules, TreePath};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// filled with the files under the root once the walk is finished
+pub type IndexSlot = Arc<Mutex<Option<Arc<Vec<PathBuf>>>>>;
+
+/// Cached list of all files in the project (used by the file finder and project search), the walk is done in the background
+/// on first use after the tree changed - slots handed out before keep the old list
+pub struct FileIndex {
+    slot: IndexSlot,
+    stale: bool,
+}
+
+impl Default for FileIndex {
+    fn default() -> Self {
+        Self { slot: IndexSlot::default(), stale: true }
+    }
+}
+
+impl FileIndex {
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn files(&mut self, root: TreePath, ignore: IgnoreRules) -> IndexSlot {
+        if !self.stale {
+            return Arc::clone(&self.slot);
+        }
+        self.stale = false;
+        self.slot = IndexSlot::default();
+        let slot = Arc::clone(&self.slot);
+        tokio::task::spawn_blocking(move || {
+            let files = root.index_files(&ignore);
+            if let Ok(mut slot) = slot.lock() {
+                slot.replace(Arc::new(files));
+            }
+        });
+        Arc::clone(&self.slot)
+    }
+}
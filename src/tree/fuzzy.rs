@@ -0,0 +1,177 @@
+/// matches after path separator, word separator, dot and on camelCase boundary get bonus (the first char as well)
+const BONUS_PATH_SEPARATOR: i64 = 18;
+const BONUS_WORD_SEPARATOR: i64 = 16;
+const BONUS_CAMEL: i64 = 14;
+const BONUS_DOT: i64 = 12;
+const BONUS_CONSECUTIVE: i64 = 20;
+/// gaps are penalized per skipped char - leading and trailing ones less
+const GAP_LEADING: i64 = -1;
+const GAP_TRAILING: i64 = -1;
+const GAP_INNER: i64 = -2;
+/// added to matches within the file name - they rank above matches spread over the directories
+const NAME_BONUS: i64 = 1_000;
+const NONE: i64 = i64::MIN / 2;
+
+/// ranked path with the char indexes matched by the pattern
+pub struct FuzzyMatch {
+    pub idx: usize,
+    pub matched: Vec<usize>,
+}
+
+/// best matches (up to limit) ordered by score - shorter paths first among equal scores, empty pattern keeps the order
+pub fn rank_paths<'a>(paths: impl Iterator<Item = &'a str>, pattern: &str, limit: usize) -> Vec<FuzzyMatch> {
+    if pattern.is_empty() {
+        return paths.enumerate().take(limit).map(|(idx, _)| FuzzyMatch { idx, matched: Vec::new() }).collect();
+    }
+    let mut scored = paths
+        .enumerate()
+        .filter_map(|(idx, path)| {
+            let (score, matched) = score_path(path, pattern)?;
+            Some((score, path.len(), FuzzyMatch { idx, matched }))
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|(score, len, _), (other, other_len, _)| other.cmp(score).then(len.cmp(other_len)));
+    scored.into_iter().take(limit).map(|(.., found)| found).collect()
+}
+
+/// fuzzy score with the matched char indexes - the file name is tried first, the whole path after
+fn score_path(path: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+    let name_start = path.rfind(std::path::MAIN_SEPARATOR).map(|idx| idx + 1).unwrap_or_default();
+    if let Some((score, matched)) = fuzzy_match(&path[name_start..], pattern) {
+        let offset = path[..name_start].chars().count();
+        return Some((score + NAME_BONUS, matched.into_iter().map(|idx| idx + offset).collect()));
+    }
+    fuzzy_match(path, pattern)
+}
+
+/// Subsequence match with the best score (the matched chars are chosen to maximize it) and char indexes of the match
+/// case insensitive unless the pattern has uppercase chars
+pub fn fuzzy_match(text: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let fold = |ch: char| if case_sensitive { ch } else { ch.to_lowercase().next().unwrap_or(ch) };
+    let pattern = pattern.chars().map(fold).collect::<Vec<_>>();
+    let chars = text.chars().collect::<Vec<_>>();
+    if pattern.is_empty() || pattern.len() > chars.len() || !is_subsequence(&pattern, chars.iter().map(|ch| fold(*ch)))
+    {
+        return None;
+    }
+    let bonus = chars.iter().enumerate().map(|(idx, ch)| char_bonus(idx.checked_sub(1).map(|prev| chars[prev]), *ch));
+    let bonus = bonus.collect::<Vec<_>>();
+    let (rows, cols) = (pattern.len(), chars.len());
+    // ending with match at the char / best score up to the char
+    let mut ending = vec![vec![NONE; cols]; rows];
+    let mut best = vec![vec![NONE; cols]; rows];
+    for row in 0..rows {
+        let gap = if row + 1 == rows { GAP_TRAILING } else { GAP_INNER };
+        let mut prev_best = NONE;
+        for col in 0..cols {
+            if fold(chars[col]) == pattern[row] {
+                let score = match (row, col) {
+                    (0, _) => col as i64 * GAP_LEADING + bonus[col],
+                    (_, 0) => NONE,
+                    _ => add(best[row - 1][col - 1], bonus[col]).max(add(ending[row - 1][col - 1], BONUS_CONSECUTIVE)),
+                };
+                ending[row][col] = score;
+                prev_best = score.max(add(prev_best, gap));
+            } else {
+                prev_best = add(prev_best, gap);
+            }
+            best[row][col] = prev_best;
+        }
+    }
+    let mut matched = vec![0; rows];
+    let mut match_required = false;
+    let mut col = cols;
+    for row in (0..rows).rev() {
+        while col > 0 {
+            col -= 1;
+            let score = ending[row][col];
+            if score > NONE && (match_required || score == best[row][col]) {
+                match_required = row > 0 && col > 0 && score == add(ending[row - 1][col - 1], BONUS_CONSECUTIVE);
+                matched[row] = col;
+                break;
+            }
+        }
+    }
+    Some((best[rows - 1][cols - 1], matched))
+}
+
+/// no match stays no match
+fn add(score: i64, value: i64) -> i64 {
+    match score > NONE {
+        true => score + value,
+        false => NONE,
+    }
+}
+
+fn is_subsequence(pattern: &[char], mut chars: impl Iterator<Item = char>) -> bool {
+    pattern.iter().all(|pattern_ch| chars.any(|ch| ch == *pattern_ch))
+}
+
+fn char_bonus(prev: Option<char>, ch: char) -> i64 {
+    match prev {
+        None | Some('/' | '\\') => BONUS_PATH_SEPARATOR,
+        Some('_' | '-' | ' ') => BONUS_WORD_SEPARATOR,
+        Some('.') => BONUS_DOT,
+        Some(prev) if prev.is_lowercase() && ch.is_uppercase() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fuzzy_match, rank_paths, score_path};
+
+    #[test]
+    fn boundaries_preferred() {
+        // separator boundaries are matched over the first occurrence
+        let (_, matched) = fuzzy_match("tree_paths.rs", "tp").unwrap();
+        assert_eq!(matched, [0, 5]);
+        let (_, matched) = fuzzy_match("FileChooser.rs", "fc").unwrap();
+        assert_eq!(matched, [0, 4]);
+        let (camel, _) = fuzzy_match("FileChooser.rs", "fc").unwrap();
+        let (inner, _) = fuzzy_match("fact.rs", "fc").unwrap();
+        assert!(camel > inner);
+        // consecutive beats spread
+        let (_, matched) = fuzzy_match("m_o_d_mod", "mod").unwrap();
+        assert_eq!(matched, [6, 7, 8]);
+        // smart case
+        assert!(fuzzy_match("readme.md", "README").is_none());
+        assert!(fuzzy_match("README.md", "readme").is_some());
+        assert!(fuzzy_match("src", "srcs").is_none());
+        assert!(fuzzy_match("src", "").is_none());
+    }
+
+    #[test]
+    fn file_name_preferred() {
+        let (name_score, matched) = score_path("src/tree/mod.rs", "mod").unwrap();
+        assert_eq!(matched, vec![9, 10, 11]);
+        let (dir_score, matched) = score_path("src/model/lib.rs", "mod").unwrap();
+        assert_eq!(matched, vec![4, 5, 6]);
+        assert!(name_score > dir_score);
+        assert!(score_path("src/lib.rs", "mod").is_none());
+    }
+
+    #[test]
+    fn ranked_with_limit() {
+        let paths = [
+            "src/tree/fact.rs",
+            "src/tree/file_clipboard.rs",
+            "src/popups/FileChooser.rs",
+            "README.md",
+        ];
+        let ranked = rank_paths(paths.into_iter(), "fc", 10);
+        let found = ranked.iter().map(|found| paths[found.idx]).collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                "src/popups/FileChooser.rs",
+                "src/tree/file_clipboard.rs",
+                "src/tree/fact.rs"
+            ]
+        );
+        assert_eq!(ranked[0].matched, [11, 15]);
+        assert_eq!(rank_paths(paths.into_iter(), "fc", 1).len(), 1);
+        assert_eq!(rank_paths(paths.into_iter(), "", 10).len(), 4);
+    }
+}
@@ -0,0 +1,99 @@
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Ignore files (.gitignore and .ignore) of the project, read for the dirs of the checked paths (the project is not walked)
+/// nested files apply to their dir - deeper rules take precedence, .ignore over .gitignore of the same dir
+/// clones share the rules read so far (the file index and search check paths in the background)
+#[derive(Clone, Default)]
+pub struct IgnoreRules {
+    /// empty root (default) ignores nothing
+    root: PathBuf,
+    /// rules by dir, None for dirs without ignore files
+    dirs: Arc<Mutex<HashMap<PathBuf, Option<Gitignore>>>>,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path) -> Self {
+        Self { root: root.to_owned(), dirs: Arc::default() }
+    }
+
+    /// the deepest dir with a match decides
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.root.as_os_str().is_empty() {
+            return false;
+        }
+        let Ok(mut dirs) = self.dirs.lock() else {
+            return false;
+        };
+        for dir in path.ancestors().skip(1).take_while(|dir| dir.starts_with(&self.root)) {
+            if !dirs.contains_key(dir) {
+                dirs.insert(dir.to_owned(), dir_rules(dir));
+            }
+            let Some(rules) = dirs.get(dir).and_then(Option::as_ref) else {
+                continue;
+            };
+            match rules.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(..) => return true,
+                Match::Whitelist(..) => return false,
+                Match::None => (),
+            }
+        }
+        false
+    }
+
+    pub fn is_ignore_file(path: &Path) -> bool {
+        path.file_name().is_some_and(|name| IGNORE_FILES.iter().any(|ignore_file| name == *ignore_file))
+    }
+}
+
+fn dir_rules(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for file in IGNORE_FILES.map(|name| dir.join(name)).iter().filter(|file| file.is_file()) {
+        // invalid lines are skipped
+        builder.add(file);
+        found = true;
+    }
+    if !found {
+        return None;
+    }
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::IgnoreRules;
+
+    #[test]
+    fn nested_rules_take_precedence() {
+        let root = std::env::temp_dir().join(format!("idiom_ignore_rules_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src").join("gen")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::write(root.join("src").join(".ignore"), "!keep.log\ngen/\n").unwrap();
+        let rules = IgnoreRules::new(&root);
+        assert!(rules.is_ignored(&root.join("target"), true));
+        assert!(rules.is_ignored(&root.join("target").join("debug").join("app"), false));
+        assert!(rules.is_ignored(&root.join("run.log"), false));
+        assert!(!rules.is_ignored(&root.join("src").join("keep.log"), false));
+        assert!(rules.is_ignored(&root.join("src").join("other.log"), false));
+        assert!(rules.is_ignored(&root.join("src").join("gen"), true));
+        assert!(!rules.is_ignored(&root.join("src").join("main.rs"), false));
+        // rules are read for the checked dirs only
+        assert_eq!(rules.dirs.lock().unwrap().len(), 4);
+        // paths out of the root and default rules ignore nothing
+        assert!(!rules.is_ignored(&std::env::temp_dir().join("run.log"), false));
+        assert!(!IgnoreRules::default().is_ignored(&root.join("run.log"), false));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,3 +1,7 @@
+mod file_clipboard;
+mod file_index;
+pub mod fuzzy;
+mod ignore_rules;
 mod tree_paths;
 mod watcher;
 use crate::{
@@ -5,20 +9,33 @@ use crate::{
     error::{IdiomError, IdiomResult},
     global_state::{GlobalState, IdiomEvent},
     lsp::{DiagnosticType, TreeDiagnostics},
-    popups::popups_tree::{create_file_popup, rename_file_popup},
-    render::state::State,
+    popups::popups_tree::{create_file_popup, delete_marked_popup, rename_file_popup},
+    render::{
+        backend::{color, Backend, Color, Style},
+        layout::Line,
+        state::State,
+        UTF8Safe,
+    },
     utils::{build_file_or_folder, to_canon_path, to_relative_path},
 };
 use crossterm::event::KeyEvent;
+use file_clipboard::FileClipboard;
+use file_index::FileIndex;
+pub use file_index::IndexSlot;
+use ignore_rules::IgnoreRules;
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     path::{Path, PathBuf},
 };
-pub use tree_paths::TreePath;
-use watcher::TreeWatcher;
+pub use tree_paths::{PathFilter, TreeOrder, TreePath};
+use watcher::{TreeUpdate, TreeWatcher};
 
 type PathParser = fn(&Path) -> IdiomResult<PathBuf>;
 
+const HIDDEN: Color = color::dark_grey();
+const MARKED: Color = color::yellow();
+const MARK: &str = "* ";
+
 pub struct Tree {
     pub key_map: TreeKeyMap,
     pub watcher: TreeWatcher,
@@ -29,6 +46,11 @@ pub struct Tree {
     display_offset: usize,
     path_parser: PathParser,
     rebuild: bool,
+    file_index: FileIndex,
+    filter: PathFilter,
+    /// batch operations (delete, cut, copy) use the marked paths instead of the selected one
+    marked: HashSet<PathBuf>,
+    file_clipboard: Option<FileClipboard>,
 }
 
 impl Tree {
@@ -37,38 +59,35 @@ impl Tree {
             Ok(selected_path) => {
                 let path_str = selected_path.display().to_string();
                 let display_offset = path_str.split(std::path::MAIN_SEPARATOR).count() * 2;
-                let tree = TreePath::from_path(selected_path.clone());
-                Self {
-                    watcher: TreeWatcher::root(&selected_path),
-                    state: State::new(),
-                    key_map,
-                    display_offset,
-                    path_parser: to_canon_path,
-                    selected_path,
-                    tree,
-                    rebuild: true,
-                    diagnostics_state: HashMap::new(),
-                }
+                Self::with_root(key_map, selected_path, display_offset, to_canon_path)
             }
             Err(err) => {
                 gs.error(err.to_string());
-                let selected_path = PathBuf::from("./");
-                let tree = TreePath::from_path(selected_path.clone());
-                Self {
-                    watcher: TreeWatcher::root(&selected_path),
-                    state: State::new(),
-                    key_map,
-                    display_offset: 2,
-                    path_parser: to_relative_path,
-                    selected_path,
-                    tree,
-                    rebuild: true,
-                    diagnostics_state: HashMap::new(),
-                }
+                Self::with_root(key_map, PathBuf::from("./"), 2, to_relative_path)
             }
         }
     }
 
+    fn with_root(key_map: TreeKeyMap, selected_path: PathBuf, display_offset: usize, path_parser: PathParser) -> Self {
+        let filter = PathFilter::new(&selected_path);
+        let tree = TreePath::from_path(selected_path.clone(), &filter);
+        Self {
+            watcher: TreeWatcher::root(&selected_path),
+            state: State::new(),
+            key_map,
+            display_offset,
+            path_parser,
+            selected_path,
+            tree,
+            rebuild: true,
+            file_index: FileIndex::default(),
+            diagnostics_state: HashMap::new(),
+            filter,
+            marked: HashSet::new(),
+            file_clipboard: None,
+        }
+    }
+
     pub fn render(&mut self, gs: &mut GlobalState) {
         let mut iter = self.tree.iter();
         iter.next();
@@ -78,8 +97,20 @@ impl Tree {
                 Some(line) => line,
                 None => return,
             };
+            let line = match self.marked.contains(tree_path.path()) {
+                true => render_mark(line, &mut gs.writer),
+                false => line,
+            };
+            let hidden = (self.filter.show_hidden || self.filter.show_ignored)
+                && self.filter.is_hidden(tree_path.path(), matches!(tree_path, TreePath::Folder { .. }));
             if idx == self.state.selected {
-                tree_path.render_styled(self.display_offset, line, self.state.highlight, &mut gs.writer);
+                let mut style = self.state.highlight;
+                if hidden {
+                    style.set_fg(Some(HIDDEN));
+                }
+                tree_path.render_styled(self.display_offset, line, style, &mut gs.writer);
+            } else if hidden {
+                tree_path.render_styled(self.display_offset, line, Style::fg(HIDDEN), &mut gs.writer);
             } else {
                 tree_path.render(self.display_offset, line, &mut gs.writer);
             }
@@ -108,9 +139,12 @@ impl Tree {
                         gs.event.push(IdiomEvent::OpenAtLine(path, 0));
                     }
                 }
-                TreeAction::Delete => {
-                    let _ = self.delete_file(gs);
-                }
+                TreeAction::Delete => match self.marked.len() {
+                    0 => {
+                        let _ = self.delete_file(gs);
+                    }
+                    count => gs.popup(delete_marked_popup(count)),
+                },
                 TreeAction::NewFile => gs.popup(create_file_popup(self.get_first_selected_folder_display())),
                 TreeAction::Rename => {
                     if let Some(tree_path) = self.tree.get_mut_from_inner(self.state.selected) {
@@ -119,6 +153,18 @@ impl Tree {
                 }
                 TreeAction::IncreaseSize => gs.expand_tree_size(),
                 TreeAction::DecreaseSize => gs.shrink_tree_size(),
+                TreeAction::CopyPath => {
+                    if let Some(tree_path) = self.tree.get_from_inner(self.state.selected) {
+                        gs.clipboard_copy(tree_path.path().display().to_string());
+                        gs.success("Path copied to clipboard");
+                    }
+                }
+                TreeAction::ToggleHidden => self.toggle_hidden(gs),
+                TreeAction::ToggleIgnored => self.toggle_ignored(gs),
+                TreeAction::ToggleMark => self.toggle_mark(),
+                TreeAction::CutFile => self.set_file_clipboard(FileClipboard::Cut(self.batch_paths()), gs),
+                TreeAction::CopyFile => self.set_file_clipboard(FileClipboard::Copy(self.batch_paths()), gs),
+                TreeAction::Paste => gs.event.push(IdiomEvent::PasteFiles),
             }
             return true;
         }
@@ -132,7 +178,7 @@ impl Tree {
             if let Err(err) = self.watcher.watch(path) {
                 gs.error(err.to_string());
             };
-            tree_path.expand();
+            tree_path.expand_with(&self.filter);
             for (d_path, new_diagnostic) in self.diagnostics_state.iter() {
                 tree_path.map_diagnostics_base(d_path, *new_diagnostic);
             }
@@ -162,7 +208,7 @@ impl Tree {
                         selected.take_tree();
                     }
                     TreePath::Folder { tree: None, .. } => {
-                        selected.expand();
+                        selected.expand_with(&self.filter);
                         for (d_path, new_diagnostic) in self.diagnostics_state.iter() {
                             selected.map_diagnostics_base(d_path, *new_diagnostic);
                         }
@@ -248,6 +294,77 @@ impl Tree {
         Ok(())
     }
 
+    /// each path is deleted on its own, failures are reported and the rest is still deleted
+    pub fn delete_marked(&mut self, gs: &mut GlobalState) {
+        let mut failed = 0;
+        for path in self.batch_paths() {
+            let result = match path.is_dir() {
+                true => std::fs::remove_dir_all(&path),
+                false => std::fs::remove_file(&path),
+            };
+            if let Err(err) = result {
+                failed += 1;
+                gs.error(format!("{}: {err}", path.display()));
+            }
+        }
+        self.batch_done(failed);
+        self.reselect();
+        self.rebuild = true;
+    }
+
+    /// pastes into the selected folder (or the folder of the selected file)
+    /// returns moved paths (old, new) so open editors can follow them
+    pub fn paste_files(&mut self, gs: &mut GlobalState) -> Vec<(PathBuf, PathBuf)> {
+        let Some(clipboard) = self.file_clipboard.take() else {
+            gs.message("No files to paste!");
+            return Vec::new();
+        };
+        let (moved, errors) = clipboard.paste(&self.selected_folder());
+        for (path, err) in errors.iter() {
+            gs.error(format!("{}: {err}", path.display()));
+        }
+        if let FileClipboard::Copy(..) = clipboard {
+            self.file_clipboard = Some(clipboard);
+        }
+        self.batch_done(errors.len());
+        self.rebuild = true;
+        moved
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(tree_path) = self.tree.get_from_inner(self.state.selected) else {
+            return;
+        };
+        if !self.marked.remove(tree_path.path()) {
+            self.marked.insert(tree_path.path().to_owned());
+        }
+        self.rebuild = true;
+    }
+
+    fn set_file_clipboard(&mut self, clipboard: FileClipboard, gs: &mut GlobalState) {
+        match clipboard {
+            FileClipboard::Cut(..) => gs.message(format!("Cut {} path(s)", clipboard.len())),
+            FileClipboard::Copy(..) => gs.message(format!("Copied {} path(s)", clipboard.len())),
+        }
+        self.file_clipboard = Some(clipboard);
+    }
+
+    /// marked paths (nested ones are covered by their marked parents) or the selected one
+    fn batch_paths(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return vec![self.selected_path.clone()];
+        }
+        outer_paths(&self.marked)
+    }
+
+    /// marks are cleared on success, otherwise only the paths that still exist stay marked
+    fn batch_done(&mut self, failed: usize) {
+        match failed {
+            0 => self.marked.clear(),
+            _ => self.marked.retain(|path| path.exists()),
+        }
+    }
+
     pub fn rename_path(&mut self, name: String) -> Option<IdiomResult<(PathBuf, PathBuf)>> {
         // not efficient but safe - calls should be rare enough
         let selected = self.tree.get_mut_from_inner(self.state.selected)?;
@@ -274,7 +391,17 @@ impl Tree {
     }
 
     pub fn search_paths(&self, pattern: &str) -> Vec<PathBuf> {
-        self.tree.shallow_copy().search_tree_paths(pattern)
+        self.tree.shallow_copy().search_tree_paths(pattern, &self.filter.search_rules())
+    }
+
+    /// all files of the project for the file finder (gathered in the background)
+    pub fn indexed_files(&mut self) -> IndexSlot {
+        self.file_index.files(self.tree.shallow_copy(), self.filter.search_rules())
+    }
+
+    /// ignore rules for project wide search - follows the tree (ignored paths are searched only while shown)
+    pub fn search_rules(&self) -> IgnoreRules {
+        self.filter.search_rules()
     }
 
     pub fn shallow_copy_root_tree_path(&self) -> TreePath {
@@ -291,7 +418,7 @@ impl Tree {
     pub fn select_by_path(&mut self, path: &PathBuf) {
         let rel_result = (self.path_parser)(path);
         let path = rel_result.as_ref().unwrap_or(path);
-        if self.tree.expand_contained(path, &mut self.watcher) {
+        if self.tree.expand_contained(path, &mut self.watcher, &self.filter) {
             self.selected_path.clone_from(path);
             self.state.selected = self.tree.iter().skip(1).position(|tp| tp.path() == path).unwrap_or_default();
             self.rebuild_diagnostics();
@@ -300,15 +427,19 @@ impl Tree {
     }
 
     pub fn get_first_selected_folder_display(&mut self) -> String {
-        if let Some(tree_path) = self.tree.get_mut_from_inner(self.state.selected) {
+        self.selected_folder().display().to_string()
+    }
+
+    fn selected_folder(&self) -> PathBuf {
+        if let Some(tree_path) = self.tree.get_from_inner(self.state.selected) {
             if tree_path.path().is_dir() {
-                return tree_path.path().as_path().display().to_string();
+                return tree_path.path().to_owned();
             }
             if let Some(parent) = tree_path.path().parent() {
-                return parent.display().to_string();
+                return parent.to_owned();
             }
         }
-        "./".to_owned()
+        PathBuf::from("./")
     }
 
     pub fn get_base_file_names(&self) -> Vec<String> {
@@ -316,10 +447,13 @@ impl Tree {
     }
 
     pub fn sync(&mut self, gs: &mut GlobalState) {
-        self.rebuild = self.watcher.poll(&mut self.tree, self.path_parser, gs);
-        if !self.rebuild {
-            return;
+        match self.watcher.poll(&mut self.tree, self.path_parser, &mut self.filter, gs) {
+            TreeUpdate::None => return,
+            TreeUpdate::Partial => (),
+            TreeUpdate::Rebased => self.marked.clear(),
         }
+        self.rebuild = true;
+        self.file_index.invalidate();
         for (idx, tree_path) in self.tree.iter().skip(1).enumerate() {
             if tree_path.path() == &self.selected_path {
                 self.state.selected = idx;
@@ -328,6 +462,75 @@ impl Tree {
         }
     }
 
+    /// expanded folders are sorted again on change, the selected path is kept
+    pub fn set_order(&mut self, order: TreeOrder) {
+        if self.filter.order == order {
+            return;
+        }
+        self.filter.order = order;
+        self.tree.sort(order);
+        self.reselect();
+        self.rebuild = true;
+    }
+
+    /// hidden paths are listed (dimmed) or removed, expanded dirs that stay listed keep their state
+    /// revealed dirs are not watched until expanded
+    fn toggle_hidden(&mut self, gs: &mut GlobalState) {
+        self.filter.show_hidden = !self.filter.show_hidden;
+        self.refilter(gs);
+        match self.filter.show_hidden {
+            true => gs.message("Showing hidden files"),
+            false => gs.message("Hiding hidden files"),
+        }
+    }
+
+    /// ignore files are loaded again, so edits done outside of the watched dirs are picked up
+    fn toggle_ignored(&mut self, gs: &mut GlobalState) {
+        self.filter.show_ignored = !self.filter.show_ignored;
+        self.filter.reload_ignore();
+        self.file_index.invalidate();
+        self.refilter(gs);
+        match self.filter.show_ignored {
+            true => gs.message("Showing ignored files"),
+            false => gs.message("Hiding ignored files"),
+        }
+    }
+
+    fn refilter(&mut self, gs: &mut GlobalState) {
+        let expanded = self.expanded_dirs();
+        self.tree.sync_base(&self.filter);
+        for removed in expanded.difference(&self.expanded_dirs()) {
+            let _ = self.watcher.stop_watch(removed);
+        }
+        self.rebuild_diagnostics();
+        self.reselect();
+        self.state.update_at_line(gs.tree_area.height as usize);
+        self.rebuild = true;
+    }
+
+    fn expanded_dirs(&self) -> HashSet<PathBuf> {
+        self.tree
+            .iter()
+            .skip(1)
+            .filter(|tree_path| matches!(tree_path, TreePath::Folder { tree: Some(..), .. }))
+            .map(|tree_path| tree_path.path().to_owned())
+            .collect()
+    }
+
+    /// keeps the selected path, falls back to the closest listed parent
+    fn reselect(&mut self) {
+        let selected_path = self.selected_path.clone();
+        for path in selected_path.ancestors() {
+            if let Some(idx) = self.tree.iter().skip(1).position(|tree_path| tree_path.path() == path) {
+                self.state.selected = idx;
+                self.selected_path = path.to_owned();
+                return;
+            }
+        }
+        self.state.selected = 0;
+        self.unsafe_set_path();
+    }
+
     fn unsafe_set_path(&mut self) {
         self.rebuild = true;
         if let Some(selected) = self.tree.get_mut_from_inner(self.state.selected) {
@@ -335,3 +538,104 @@ impl Tree {
         }
     }
 }
+
+/// marker glyph in front of the path, returns the remaining line
+fn render_mark(mut line: Line, backend: &mut Backend) -> Line {
+    let width = MARK.width().min(line.width);
+    Line { width, ..line.clone() }.render_styled(MARK, Style::fg(MARKED), backend);
+    line += width;
+    line
+}
+
+/// sorted paths without the ones nested in other listed paths (moved or deleted with their parent)
+fn outer_paths(paths: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    let mut paths = paths.iter().cloned().collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup_by(|path, parent| path.starts_with(parent));
+    paths
+}
+
+#[cfg(test)]
+mod test {
+    use super::{outer_paths, Tree};
+    use crate::{
+        configs::test::mock_tree_key_map,
+        global_state::GlobalState,
+        render::backend::{Backend, BackendProtocol},
+        utils::to_canon_path,
+    };
+    use std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+    };
+
+    fn select(tree: &mut Tree, path: &Path) {
+        tree.state.selected = tree.tree.iter().skip(1).position(|tree_path| tree_path.path() == path).unwrap();
+        tree.selected_path = path.to_owned();
+    }
+
+    fn listed(tree: &Tree, path: &Path) -> bool {
+        tree.tree.iter().any(|tree_path| tree_path.path() == path)
+    }
+
+    #[test]
+    fn toggle_hidden_keeps_selection() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let dir = std::env::temp_dir().join(format!("idiom-tree-hidden-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+        std::fs::write(dir.join("src/.env"), "").unwrap();
+        std::fs::write(dir.join("src/main.rs"), "").unwrap();
+        std::fs::write(dir.join(".gitignore"), "target").unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let mut tree = Tree::with_root(mock_tree_key_map(), dir.clone(), 2, to_canon_path);
+        // dotfiles are listed by default, ignored paths are not
+        assert!(listed(&tree, &dir.join(".gitignore")));
+        assert!(!listed(&tree, &dir.join("target")));
+        select(&mut tree, &dir.join("src"));
+        assert!(tree.expand_dir_or_get_path(&mut gs).is_none());
+        select(&mut tree, &dir.join("src/.env"));
+        // hidden selection falls back to the parent
+        tree.toggle_hidden(&mut gs);
+        assert!(!listed(&tree, &dir.join("src/.env")));
+        assert_eq!(tree.selected_path, dir.join("src"));
+        assert_eq!(tree.tree.get_from_inner(tree.state.selected).unwrap().path(), &dir.join("src"));
+        // expansion is kept
+        assert!(listed(&tree, &dir.join("src/main.rs")));
+        select(&mut tree, &dir.join("src/main.rs"));
+        tree.toggle_hidden(&mut gs);
+        assert!(listed(&tree, &dir.join("src/.env")));
+        assert_eq!(tree.selected_path, dir.join("src/main.rs"));
+        assert_eq!(tree.tree.get_from_inner(tree.state.selected).unwrap().path(), &dir.join("src/main.rs"));
+        // revealed dirs are not watched until expanded
+        tree.toggle_ignored(&mut gs);
+        assert!(listed(&tree, &dir.join("target")));
+        assert!(!listed(&tree, &dir.join("target/debug")));
+        assert!(tree.watcher.stop_watch(&dir.join("target")).is_err());
+        select(&mut tree, &dir.join("target"));
+        assert!(tree.expand_dir_or_get_path(&mut gs).is_none());
+        assert!(listed(&tree, &dir.join("target/debug")));
+        // hidden expanded dirs are not watched any more
+        tree.toggle_ignored(&mut gs);
+        assert!(!listed(&tree, &dir.join("target")));
+        // no listed parent (root is not a row) - first entry is selected
+        assert_eq!(tree.selected_path, dir.join("src"));
+        assert!(tree.watcher.stop_watch(&dir.join("target")).is_err());
+        assert!(tree.watcher.stop_watch(&dir.join("src")).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn nested_marks_dropped() {
+        let marked = ["src/a", "src/a/b.rs", "src/a/c/d.rs", "src/a-b", "src/e.rs", "src"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<HashSet<_>>();
+        assert_eq!(outer_paths(&marked), [PathBuf::from("src")]);
+        let marked = ["src/a/c/d.rs", "src/a-b", "src/a", "src/a/b.rs", "src/e.rs"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<HashSet<_>>();
+        assert_eq!(outer_paths(&marked), ["src/a", "src/a-b", "src/e.rs"].map(PathBuf::from));
+    }
+}
@@ -1,7 +1,7 @@
-use ignore::{gitignore::Gitignore, Match};
 use tokio::task::JoinSet;
 
 use crate::{
+    configs::TreeSort,
     lsp::DiagnosticType,
     render::{
         backend::{color, Backend, Color, Style},
@@ -16,7 +16,7 @@ use std::{
     sync::Arc,
 };
 
-use super::{watcher::TreeWatcher, PathParser};
+use super::{ignore_rules::IgnoreRules, watcher::TreeWatcher, PathParser};
 
 const ERR: Color = color::red();
 const WAR: Color = color::dark_yellow();
@@ -27,16 +27,113 @@ pub enum TreePath {
     File { path: PathBuf, display: String, diagnostic: DiagnosticType },
 }
 
+/// Decides which paths are listed in the tree and in what order - dotfiles are listed by default and paths ignored
+/// by .gitignore/.ignore files are not, both can be toggled (rendered dimmed when listed), git dir is never listed
+pub struct PathFilter {
+    pub show_hidden: bool,
+    pub show_ignored: bool,
+    pub order: TreeOrder,
+    root: PathBuf,
+    ignore: IgnoreRules,
+}
+
+impl PathFilter {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            show_hidden: true,
+            show_ignored: false,
+            order: TreeOrder::default(),
+            root: root.to_owned(),
+            ignore: IgnoreRules::new(root),
+        }
+    }
+
+    /// lists everything but the git dir
+    pub fn all(root: &Path) -> Self {
+        Self {
+            show_hidden: true,
+            show_ignored: true,
+            order: TreeOrder::default(),
+            root: root.to_owned(),
+            ignore: IgnoreRules::default(),
+        }
+    }
+
+    /// ignore files are parsed again as the paths are checked
+    pub fn reload_ignore(&mut self) {
+        self.ignore = IgnoreRules::new(&self.root);
+    }
+
+    /// rules used by the project wide search and file index - empty while ignored paths are shown
+    pub fn search_rules(&self) -> IgnoreRules {
+        match self.show_ignored {
+            true => IgnoreRules::default(),
+            false => self.ignore.clone(),
+        }
+    }
+
+    /// dotfile or ignored path - nested paths of hidden dirs are hidden as well
+    pub fn is_hidden(&self, path: &Path, is_dir: bool) -> bool {
+        self.is_dotfile(path) || self.ignore.is_ignored(path, is_dir)
+    }
+
+    fn is_dotfile(&self, path: &Path) -> bool {
+        let Ok(rel_path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        rel_path.components().any(|part| part.as_os_str().to_str().is_some_and(|name| name.starts_with('.')))
+    }
+
+    fn lists(&self, path: &Path) -> bool {
+        !is_git_dir(path)
+            && (self.show_hidden || !self.is_dotfile(path))
+            && (self.show_ignored || !self.ignore.is_ignored(path, path.is_dir()))
+    }
+}
+
+/// Order of entries within a folder - folders are always listed before files
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TreeOrder {
+    pub sort: TreeSort,
+    /// digit runs are compared by value
+    pub natural: bool,
+}
+
+impl TreeOrder {
+    fn cmp(&self, left: &TreePath, right: &TreePath) -> Ordering {
+        match (left, right) {
+            (TreePath::Folder { .. }, TreePath::File { .. }) => Ordering::Less,
+            (TreePath::File { .. }, TreePath::Folder { .. }) => Ordering::Greater,
+            (TreePath::Folder { path, .. }, TreePath::Folder { path: other, .. }) => self.cmp_names(path, other),
+            (TreePath::File { path, .. }, TreePath::File { path: other, .. }) => match self.sort {
+                TreeSort::Extension => {
+                    let extension = path.extension().map(|ext| ext.to_string_lossy()).unwrap_or_default();
+                    let other_ext = other.extension().map(|ext| ext.to_string_lossy()).unwrap_or_default();
+                    compare_names(&extension, &other_ext, true, false).then_with(|| self.cmp_names(path, other))
+                }
+                _ => self.cmp_names(path, other),
+            },
+        }
+    }
+
+    /// ties (different case or leading zeros) are broken by plain compare, so the order does not depend on the file system
+    fn cmp_names(&self, left: &Path, right: &Path) -> Ordering {
+        let left = left.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let right = right.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let ignore_case = self.sort != TreeSort::NameCaseSensitive;
+        compare_names(&left, &right, ignore_case, self.natural).then_with(|| left.cmp(&right))
+    }
+}
+
 #[allow(dead_code)]
 impl TreePath {
-    pub fn from_path(path: PathBuf) -> Self {
+    pub fn from_path(path: PathBuf, filter: &PathFilter) -> Self {
         if !path.is_dir() {
             return Self::File { display: get_path_display(&path), path, diagnostic: DiagnosticType::None };
         }
-        let mut tree_buffer = get_nested_paths(&path)
-            .filter_map(|p| if is_git_dir(&p) { None } else { Some(p.into()) })
-            .collect::<Vec<Self>>();
-        tree_buffer.sort_by(order_tree_paths);
+        let mut tree_buffer =
+            get_nested_paths(&path).filter(|p| filter.lists(p)).map(Self::from).collect::<Vec<Self>>();
+        tree_buffer.sort_by(|left, right| filter.order.cmp(left, right));
         Self::Folder {
             display: get_path_display(&path),
             path,
@@ -111,30 +208,50 @@ impl TreePath {
         None
     }
 
+    /// expands with all nested paths (search walks)
     pub fn expand(&mut self) {
+        self.expand_filtered(|_| true, TreeOrder::default());
+    }
+
+    /// expands with the nested paths listed by the filter
+    pub fn expand_with(&mut self, filter: &PathFilter) {
+        self.expand_filtered(|path| filter.lists(path), filter.order);
+    }
+
+    fn expand_filtered(&mut self, keep: impl Fn(&Path) -> bool, order: TreeOrder) {
         if let Self::Folder { tree, path, .. } = self {
             if tree.is_some() {
                 return;
             }
             let mut buffer = Vec::new();
-            for nested_path in get_nested_paths(path) {
+            for nested_path in get_nested_paths(path).filter(|p| keep(p)) {
                 buffer.push(nested_path.into())
             }
-            buffer.sort_by(order_tree_paths);
+            buffer.sort_by(|left, right| order.cmp(left, right));
             tree.replace(buffer);
         }
     }
 
-    pub fn expand_contained(&mut self, rel_path: &Path, watcher: &mut TreeWatcher) -> bool {
+    /// sorts the expanded folders again (order changed)
+    pub fn sort(&mut self, order: TreeOrder) {
+        if let Self::Folder { tree: Some(tree), .. } = self {
+            tree.sort_by(|left, right| order.cmp(left, right));
+            for tree_path in tree.iter_mut() {
+                tree_path.sort(order);
+            }
+        }
+    }
+
+    pub fn expand_contained(&mut self, rel_path: &Path, watcher: &mut TreeWatcher, filter: &PathFilter) -> bool {
         if self.path() == rel_path {
             return true;
         }
         if rel_path.starts_with(self.path()) {
             let should_shrink = self.tree_mut().is_none();
-            self.expand();
+            self.expand_with(filter);
             if let Some(nested_tree) = self.tree_mut() {
                 for tree_path in nested_tree {
-                    if tree_path.expand_contained(rel_path, watcher) {
+                    if tree_path.expand_contained(rel_path, watcher, filter) {
                         let _ = watcher.watch(tree_path.path());
                         return true;
                     }
@@ -174,16 +291,16 @@ impl TreePath {
 
     /// SYNC with real tree
 
-    pub fn sync_base(&mut self) {
+    pub fn sync_base(&mut self, filter: &PathFilter) {
         if let Self::Folder { path, tree: Some(tree), .. } = self {
-            merge_trees(tree, get_nested_paths(path).filter(|p| !is_git_dir(p)).collect());
+            merge_trees(tree, get_nested_paths(path).filter(|p| filter.lists(p)).collect(), filter);
         }
     }
 
-    pub fn sync(&mut self) {
+    pub fn sync(&mut self, filter: &PathFilter) {
         self.reset_diagnostic();
         if let Self::Folder { path, tree: Some(tree), .. } = self {
-            merge_trees(tree, get_nested_paths(path).collect());
+            merge_trees(tree, get_nested_paths(path).filter(|p| filter.lists(p)).collect(), filter);
         }
     }
 
@@ -217,10 +334,10 @@ impl TreePath {
         mut self,
         pattern: Arc<str>,
         buffer: &mut JoinSet<Vec<(PathBuf, String, usize)>>,
-        gitignore: &Gitignore,
+        ignore: &IgnoreRules,
     ) {
         let path = self.path();
-        if matches!(gitignore.matched(path, path.is_dir()), Match::Ignore(..)) {
+        if ignore.is_ignored(path, path.is_dir()) {
             return;
         };
         self.expand();
@@ -244,17 +361,16 @@ impl TreePath {
                     if is_git_dir(tree_path.path()) {
                         continue;
                     }
-                    tree_path.search_in_files(Arc::clone(&pattern), buffer, gitignore);
+                    tree_path.search_in_files(Arc::clone(&pattern), buffer, ignore);
                 }
             }
             _ => (),
         }
     }
 
-    pub fn search_tree_paths(self, pattern: &str) -> Vec<PathBuf> {
+    pub fn search_tree_paths(self, pattern: &str, ignore: &IgnoreRules) -> Vec<PathBuf> {
         let mut buffer = Vec::new();
-        let gitignore = Gitignore::new("./.gitignore").0;
-        self.search_in_paths(pattern, &mut buffer, &gitignore);
+        self.search_in_paths(pattern, &mut buffer, ignore);
         buffer
     }
 
@@ -288,9 +404,9 @@ impl TreePath {
         }
     }
 
-    pub fn search_in_paths(mut self, pattern: &str, buffer: &mut Vec<PathBuf>, gitignore: &Gitignore) {
+    pub fn search_in_paths(mut self, pattern: &str, buffer: &mut Vec<PathBuf>, ignore: &IgnoreRules) {
         let path = self.path();
-        if matches!(gitignore.matched(path, path.is_dir()), Match::Ignore(..)) {
+        if ignore.is_ignored(path, path.is_dir()) {
             return;
         }
         self.expand();
@@ -313,13 +429,36 @@ impl TreePath {
                         if is_git_dir(tree_path.path()) {
                             continue;
                         }
-                        tree_path.search_in_paths(pattern, buffer, gitignore);
+                        tree_path.search_in_paths(pattern, buffer, ignore);
                     }
                 }
             }
         }
     }
 
+    pub fn index_files(self, ignore: &IgnoreRules) -> Vec<PathBuf> {
+        let mut buffer = Vec::new();
+        self.collect_files(&mut buffer, ignore);
+        buffer
+    }
+
+    /// files only, ignored paths and git dirs are skipped
+    fn collect_files(mut self, buffer: &mut Vec<PathBuf>, ignore: &IgnoreRules) {
+        let path = self.path();
+        if is_git_dir(path) || ignore.is_ignored(path, path.is_dir()) {
+            return;
+        }
+        self.expand();
+        match self {
+            Self::File { path, .. } => buffer.push(path),
+            Self::Folder { tree, .. } => {
+                for tree_path in tree.into_iter().flatten() {
+                    tree_path.collect_files(buffer, ignore);
+                }
+            }
+        }
+    }
+
     fn collect_all_paths(mut self, buffer: &mut Vec<PathBuf>) {
         self.expand();
         match self {
@@ -335,10 +474,13 @@ impl TreePath {
         }
     }
 
-    pub fn search_files_join_set(self, pattern: String) -> JoinSet<Vec<(PathBuf, String, usize)>> {
+    pub fn search_files_join_set(
+        self,
+        pattern: String,
+        ignore: &IgnoreRules,
+    ) -> JoinSet<Vec<(PathBuf, String, usize)>> {
         let mut buffer = JoinSet::new();
-        let gitgnore = Gitignore::new("./.gitignore").0;
-        self.search_in_files(pattern.into(), &mut buffer, &gitgnore);
+        self.search_in_files(pattern.into(), &mut buffer, ignore);
         buffer
     }
 
@@ -450,15 +592,46 @@ fn get_path_display(path: &Path) -> String {
     buffer
 }
 
-fn order_tree_paths(left: &TreePath, right: &TreePath) -> Ordering {
-    match (left, right) {
-        (TreePath::Folder { .. }, TreePath::File { .. }) => Ordering::Less,
-        (TreePath::File { .. }, TreePath::Folder { .. }) => Ordering::Greater,
-        _ => left.path().cmp(right.path()),
+/// natural compare takes runs of digits as numbers (leading zeros are ignored)
+fn compare_names(left: &str, right: &str, ignore_case: bool, natural: bool) -> Ordering {
+    let mut left = left.chars().peekable();
+    let mut right = right.chars().peekable();
+    loop {
+        let ordering = match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(..)) => return Ordering::Less,
+            (Some(..), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if natural && l.is_ascii_digit() && r.is_ascii_digit() => {
+                let left_num = take_number(&mut left);
+                let right_num = take_number(&mut right);
+                let left_digits = left_num.trim_start_matches('0');
+                let right_digits = right_num.trim_start_matches('0');
+                left_digits.len().cmp(&right_digits.len()).then_with(|| left_digits.cmp(right_digits))
+            }
+            (Some(l), Some(r)) => {
+                left.next();
+                right.next();
+                match ignore_case {
+                    true => l.to_lowercase().cmp(r.to_lowercase()),
+                    false => l.cmp(&r),
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
     }
 }
 
-fn merge_trees(tree: &mut Vec<TreePath>, new_tree_set: HashSet<PathBuf>) {
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        number.push(digit);
+    }
+    number
+}
+
+fn merge_trees(tree: &mut Vec<TreePath>, new_tree_set: HashSet<PathBuf>, filter: &PathFilter) {
     for path in new_tree_set.iter() {
         if !tree.iter().any(|tree_element| tree_element.path() == path) {
             tree.push(path.clone().into())
@@ -466,14 +639,64 @@ fn merge_trees(tree: &mut Vec<TreePath>, new_tree_set: HashSet<PathBuf>) {
     }
     tree.retain_mut(|tree_path| {
         if new_tree_set.contains(tree_path.path()) {
-            tree_path.sync();
+            tree_path.sync(filter);
             return true;
         }
         false
     });
-    tree.sort_by(order_tree_paths)
+    tree.sort_by(|left, right| filter.order.cmp(left, right))
 }
 
 fn is_git_dir(path: &Path) -> bool {
     path.file_name().and_then(|os_str| os_str.to_str()) == Some(".git")
 }
+
+#[cfg(test)]
+mod test {
+    use super::{compare_names, TreeOrder, TreePath};
+    use crate::{configs::TreeSort, lsp::DiagnosticType};
+    use std::{cmp::Ordering, path::PathBuf};
+
+    fn sorted(names: &[&str], order: TreeOrder) -> Vec<String> {
+        let mut paths =
+            names.iter().map(|name| TreePath::from(PathBuf::from("idiom_missing_dir").join(name))).collect::<Vec<_>>();
+        paths.sort_by(|left, right| order.cmp(left, right));
+        paths.iter().map(|path| path.path().file_name().unwrap().to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn natural_compare() {
+        assert_eq!(compare_names("file2", "file10", true, true), Ordering::Less);
+        assert_eq!(compare_names("file2", "file10", true, false), Ordering::Greater);
+        assert_eq!(compare_names("file002", "file2", true, true), Ordering::Equal);
+        assert_eq!(compare_names("v1.10", "v1.9", false, true), Ordering::Greater);
+        assert_eq!(compare_names("Abc", "abc", true, false), Ordering::Equal);
+        assert_eq!(compare_names("Abc", "abc", false, false), Ordering::Less);
+        assert_eq!(compare_names("abc", "abcd", true, true), Ordering::Less);
+    }
+
+    #[test]
+    fn tree_order() {
+        let names = ["file10.rs", "file2.rs", "File1.rs", ".env", "file02.rs"];
+        let natural = TreeOrder { natural: true, ..Default::default() };
+        assert_eq!(sorted(&names, natural), [".env", "File1.rs", "file02.rs", "file2.rs", "file10.rs"]);
+        assert_eq!(sorted(&names, TreeOrder::default()), [".env", "file02.rs", "File1.rs", "file10.rs", "file2.rs"]);
+        let names = ["b.rs", "Z.rs", "a.rs"];
+        assert_eq!(sorted(&names, TreeOrder::default()), ["a.rs", "b.rs", "Z.rs"]);
+        let case_sensitive = TreeOrder { sort: TreeSort::NameCaseSensitive, natural: false };
+        assert_eq!(sorted(&names, case_sensitive), ["Z.rs", "a.rs", "b.rs"]);
+        let by_extension = TreeOrder { sort: TreeSort::Extension, natural: true };
+        let names = ["b.toml", "c.rs", "Makefile", "a10.rs", "a9.rs"];
+        assert_eq!(sorted(&names, by_extension), ["Makefile", "a9.rs", "a10.rs", "c.rs", "b.toml"]);
+        // folders are listed first
+        let folder = TreePath::Folder {
+            path: PathBuf::from("idiom_missing_dir/zdir"),
+            tree: None,
+            display: String::new(),
+            diagnostic: DiagnosticType::None,
+        };
+        let file = TreePath::from(PathBuf::from("idiom_missing_dir/a.rs"));
+        assert_eq!(natural.cmp(&folder, &file), Ordering::Less);
+        assert_eq!(by_extension.cmp(&file, &folder), Ordering::Greater);
+    }
+}
@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use super::PathParser;
+use super::{IgnoreRules, PathFilter, PathParser};
 use crate::error::IdiomError;
 use crate::{
     error::IdiomResult,
@@ -16,6 +16,13 @@ use notify::{
 
 const TICK: Duration = Duration::from_secs(1);
 
+pub enum TreeUpdate {
+    None,
+    Partial,
+    /// base was synced after watcher errors (events could be lost) - path based state can be stale
+    Rebased,
+}
+
 pub enum TreeWatcher {
     System { inner: RecommendedWatcher, receiver: std::sync::mpsc::Receiver<Result<Event, Error>> },
     Manual { clock: Instant },
@@ -44,22 +51,39 @@ impl TreeWatcher {
         Ok(())
     }
 
-    pub fn poll(&mut self, tree: &mut TreePath, path_parser: PathParser, gs: &mut GlobalState) -> bool {
+    pub fn poll(
+        &mut self,
+        tree: &mut TreePath,
+        path_parser: PathParser,
+        filter: &mut PathFilter,
+        gs: &mut GlobalState,
+    ) -> TreeUpdate {
         match self {
             Self::System { receiver, .. } => {
                 let mut handler = EventHandles::default();
                 while let Ok(event) = receiver.try_recv() {
-                    handler.handle(event, tree, gs, path_parser);
+                    handler.handle(event, tree, gs, path_parser, filter);
+                }
+                // listed paths depend on the ignore files
+                if !handler.contains(EventHandles::IGNORE) {
+                    filter.reload_ignore();
+                    tree.sync_base(filter);
+                }
+                if !handler.contains(EventHandles::ERRORS) {
+                    TreeUpdate::Rebased
+                } else if !handler.is_all() {
+                    TreeUpdate::Partial
+                } else {
+                    TreeUpdate::None
                 }
-                !handler.is_all()
             }
             Self::Manual { clock, .. } => {
                 if clock.elapsed() > TICK {
-                    tree.sync_base();
+                    tree.sync_base(filter);
                     *clock = Instant::now();
-                    true
+                    TreeUpdate::Partial
                 } else {
-                    false
+                    TreeUpdate::None
                 }
             }
         }
@@ -70,6 +94,8 @@ bitflags! {
     /// Workspace and Footer are always drawn
     #[derive(PartialEq, Eq)]
     pub struct EventHandles: u8 {
+        const ERRORS = 0b0001_0000;
+        const IGNORE = 0b0000_1000;
         const CONTENT = 0b0000_0100;
         const TREE_PARTIAL = 0b0000_0010;
         const TREE  = 0b0000_0001;
@@ -78,7 +104,7 @@ bitflags! {
 
 impl Default for EventHandles {
     fn default() -> Self {
-        Self::CONTENT | Self::TREE | Self::TREE_PARTIAL
+        Self::ERRORS | Self::IGNORE | Self::CONTENT | Self::TREE | Self::TREE_PARTIAL
     }
 }
 
@@ -89,34 +115,91 @@ impl EventHandles {
         tree: &mut TreePath,
         gs: &mut GlobalState,
         path_parser: fn(&Path) -> IdiomResult<PathBuf>,
+        filter: &PathFilter,
     ) {
-        if let Ok(Event { kind, paths, .. }) = event {
-            use EventKind::*;
-            match kind {
-                Access(AccessKind::Close(AccessMode::Write)) => {
-                    for path in paths {
-                        gs.event.push(IdiomEvent::FileUpdated(path));
-                    }
-                    if self.contains(Self::CONTENT) {
-                        self.remove(Self::CONTENT);
-                    }
+        let Event { kind, paths, .. } = match event {
+            Ok(event) => event,
+            // events could be lost - the whole base is synced
+            Err(..) => {
+                self.remove(Self::ERRORS);
+                if self.contains(Self::TREE) {
+                    tree.sync_base(filter);
+                    self.remove(Self::TREE);
                 }
-                Create(..) | Remove(..) | Modify(ModifyKind::Name(..)) if self.contains(Self::TREE) => {
-                    for path in paths.into_iter() {
-                        match path.parent().and_then(|path| tree.find_by_path_skip_root(path, path_parser)) {
-                            Some(inner_tree) => {
-                                self.remove(Self::TREE_PARTIAL);
-                                inner_tree.sync();
-                            }
-                            None => {
-                                tree.sync_base();
-                                self.remove(Self::TREE)
-                            }
+                return;
+            }
+        };
+        use EventKind::*;
+        if matches!(
+            kind,
+            Access(AccessKind::Close(AccessMode::Write)) | Create(..) | Remove(..) | Modify(ModifyKind::Name(..))
+        ) && paths.iter().any(|path| IgnoreRules::is_ignore_file(path))
+        {
+            self.remove(Self::IGNORE);
+        }
+        match kind {
+            Access(AccessKind::Close(AccessMode::Write)) => {
+                for path in paths {
+                    gs.event.push(IdiomEvent::FileUpdated(path));
+                }
+                if self.contains(Self::CONTENT) {
+                    self.remove(Self::CONTENT);
+                }
+            }
+            Create(..) | Remove(..) | Modify(ModifyKind::Name(..)) if self.contains(Self::TREE) => {
+                for path in paths.into_iter() {
+                    match path.parent().and_then(|path| tree.find_by_path_skip_root(path, path_parser)) {
+                        Some(inner_tree) => {
+                            self.remove(Self::TREE_PARTIAL);
+                            inner_tree.sync(filter);
+                        }
+                        None => {
+                            tree.sync_base(filter);
+                            self.remove(Self::TREE)
                         }
                     }
                 }
-                _ => {}
             }
+            _ => {}
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{TreeUpdate, TreeWatcher};
+    use crate::{
+        global_state::GlobalState,
+        render::backend::{Backend, BackendProtocol},
+        tree::{PathFilter, TreePath},
+        utils::to_canon_path,
+    };
+    use notify::{event::CreateKind, Config, Error, Event, EventKind, RecommendedWatcher, Watcher};
+
+    #[test]
+    fn rebased_only_on_errors() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let dir = std::env::temp_dir().join(format!("idiom-tree-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("unknown")).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let mut filter = PathFilter::new(&dir);
+        let mut tree = TreePath::from_path(dir.clone(), &filter);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let inner = RecommendedWatcher::new(|_| {}, Config::default()).unwrap();
+        let mut watcher = TreeWatcher::System { inner, receiver };
+        // unknown parent - base is synced but the event is known
+        std::fs::write(dir.join("unknown/file.rs"), "").unwrap();
+        let event = Event::new(EventKind::Create(CreateKind::File)).add_path(dir.join("unknown/file.rs"));
+        sender.send(Ok(event)).unwrap();
+        assert!(matches!(watcher.poll(&mut tree, to_canon_path, &mut filter, &mut gs), TreeUpdate::Partial));
+        assert!(matches!(watcher.poll(&mut tree, to_canon_path, &mut filter, &mut gs), TreeUpdate::None));
+        // ignore files reload the base
+        std::fs::write(dir.join(".gitignore"), "unknown").unwrap();
+        let event = Event::new(EventKind::Create(CreateKind::File)).add_path(dir.join(".gitignore"));
+        sender.send(Ok(event)).unwrap();
+        assert!(matches!(watcher.poll(&mut tree, to_canon_path, &mut filter, &mut gs), TreeUpdate::Partial));
+        sender.send(Err(Error::generic("lost events"))).unwrap();
+        assert!(matches!(watcher.poll(&mut tree, to_canon_path, &mut filter, &mut gs), TreeUpdate::Rebased));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
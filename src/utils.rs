@@ -161,11 +161,6 @@ impl<T> TrackedList<T> {
         self.inner.get(index)
     }
 
-    #[inline(always)]
-    pub fn inner(&self) -> &Vec<T> {
-        &self.inner
-    }
-
     #[allow(dead_code)]
     #[inline(always)]
     pub fn inner_mut(&mut self) -> &mut Vec<T> {
@@ -219,21 +214,6 @@ impl<T> TrackedList<T> {
         self.inner.get_mut(index)
     }
 
-    #[inline(always)]
-    pub fn push(&mut self, element: T) {
-        self.updated = true;
-        self.inner.push(element);
-    }
-
-    #[inline(always)]
-    pub fn pop(&mut self) -> Option<T> {
-        let result = self.inner.pop();
-        if result.is_some() {
-            self.updated = true;
-        }
-        result
-    }
-
     #[inline(always)]
     pub fn remove(&mut self, index: usize) -> T {
         self.updated = true;
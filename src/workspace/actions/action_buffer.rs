@@ -152,8 +152,11 @@ impl TextBuffer {
         Self { line, last: char + 1, char: char as u32, text }
     }
 
+    /// typed chars are merged until the cursor moves, the word typed after whitespace starts new step
     fn push(&mut self, line: usize, char: usize, ch: char) -> Option<Edit> {
-        if line == self.line && char == self.last && (ch.is_alphabetic() || ch == '_') {
+        let word_start =
+            !ch.is_whitespace() && self.text.ends_with(char::is_whitespace) && !self.text.trim_start().is_empty();
+        if line == self.line && char == self.last && !word_start {
             self.last += 1;
             self.text.push(ch);
             return None;
@@ -223,21 +226,35 @@ mod tests {
         buf.push(0, 0, 'a');
         buf.push(0, 1, 'b');
         buf.push(0, 2, 'c');
-        if let Some(edit) = buf.push(0, 3, ' ') {
+        assert!(buf.push(0, 3, ' ').is_none());
+        assert!(buf.push(0, 4, ' ').is_none());
+        // word after whitespace
+        if let Some(edit) = buf.push(0, 5, 'a') {
             assert!(edit.reverse.is_empty());
-            assert_eq!(edit.text, "abc");
+            assert_eq!(edit.text, "abc  ");
             assert_eq!(edit.cursor, CursorPosition { line: 0, char: 0 });
         } else {
             panic!("Expected edit!")
         }
-        buf.push(0, 4, 'a');
-        buf.push(0, 5, '_');
-        if let Some(edit) = buf.push(0, 6, '1') {
+        buf.push(0, 6, '_');
+        buf.push(0, 7, '1');
+        // cursor moved
+        if let Some(edit) = buf.push(0, 2, 'b') {
             assert!(edit.reverse.is_empty());
-            assert_eq!(edit.text, " a_");
-            assert_eq!(edit.cursor, CursorPosition { line: 0, char: 3 });
+            assert_eq!(edit.text, "a_1");
+            assert_eq!(edit.cursor, CursorPosition { line: 0, char: 5 });
         } else {
             panic!("Expected edit!")
         }
     }
+
+    #[test]
+    fn test_text_leading_whitespace() {
+        let mut buf = ActionBuffer::None;
+        for (idx, ch) in " one ".chars().enumerate() {
+            assert!(buf.push(0, idx, ch).is_none());
+        }
+        let edit = buf.collect().unwrap();
+        assert_eq!(edit.text, " one ");
+    }
 }
@@ -1,16 +1,18 @@
 use super::super::{
-    cursor::Cursor,
+    cursor::{Cursor, Select},
     line::EditorLine,
+    snippet::TabStop,
     utils::{clip_content, insert_clip, is_scope, remove_content, token_range_at},
     CursorPosition,
 };
 use super::meta::EditMetaData;
 use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
 use crate::{configs::IndentConfigs, render::UTF8Safe, utils::Offset};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Edit {
     pub meta: EditMetaData,
     pub cursor: CursorPosition,
@@ -34,13 +36,15 @@ impl Edit {
 
     pub fn swap_down(up_line: usize, cfg: &IndentConfigs, content: &mut [EditorLine]) -> (Offset, Offset, Self) {
         let to = up_line + 1;
-        let reverse = format!("{}\n{}\n", content[up_line], content[to]);
+        // edit ends at the start of the next line, unless the swap is at the end of the file
+        let (tail, lines) = if to + 1 < content.len() { ("\n", 3) } else { ("", 2) };
+        let reverse = format!("{}\n{}{tail}", content[up_line], content[to]);
         content.swap(up_line, to);
         let up_offset = cfg.indent_line(up_line, content);
         let down_offset = cfg.indent_line(to, content);
-        let text = format!("{}\n{}\n", content[up_line], content[to]);
+        let text = format!("{}\n{}{tail}", content[up_line], content[to]);
         let cursor = CursorPosition { line: up_line, char: 0 };
-        (up_offset, down_offset, Self::without_select(cursor, 3, 3, text, reverse))
+        (up_offset, down_offset, Self::without_select(cursor, lines, lines, text, reverse))
     }
 
     pub fn merge_next_line(line: usize, content: &mut Vec<EditorLine>) -> Self {
@@ -183,26 +187,32 @@ impl Edit {
         (cursor, Self::without_select(from_cursor, 1, 2, text, reverse))
     }
 
+    /// replaces the token at the cursor, tab stops are returned as selects in the content
     #[inline]
     pub fn insert_snippet(
         c: &Cursor,
         snippet: String,
-        cursor_offset: Option<(usize, usize)>,
+        stops: &[TabStop],
         cfg: &IndentConfigs,
         content: &mut Vec<EditorLine>,
-    ) -> (CursorPosition, Self) {
+    ) -> (Vec<Select>, Self) {
         let code_line = &mut content[c.line];
         let range = token_range_at(code_line, c.char);
         let from = CursorPosition { line: c.line, char: range.start };
         let to = CursorPosition { line: c.line, char: range.end };
         let indent = cfg.derive_indent_from(code_line);
         let snippet = snippet.replace('\n', &format!("\n{}", &indent));
-        let new_cursor = cursor_offset.map(|(line, char)| CursorPosition {
-            line: line + c.line,
-            char: if line == 0 { from.char + char } else { indent.len() + char },
-        });
-        let edit = Edit::replace_select(from, to, snippet, content);
-        (new_cursor.unwrap_or(edit.end_position()), edit)
+        let selects = stops
+            .iter()
+            .map(|stop| {
+                let start = CursorPosition {
+                    line: stop.line + c.line,
+                    char: if stop.line == 0 { from.char + stop.char } else { indent.len() + stop.char },
+                };
+                (start, CursorPosition { line: start.line, char: start.char + stop.len })
+            })
+            .collect();
+        (selects, Edit::replace_select(from, to, snippet, content))
     }
 
     /// UTILS
@@ -252,6 +262,23 @@ impl Edit {
         CursorPosition { line, char }
     }
 
+    /// maps position taken before the edit to the position after it, None if the position was within replaced text
+    pub fn shift_position(&self, position: CursorPosition) -> Option<CursorPosition> {
+        let start = self.start_position();
+        if (position.line, position.char) <= (start.line, start.char) {
+            return Some(position);
+        }
+        let removed_end = self.end_position_rev();
+        if (position.line, position.char) < (removed_end.line, removed_end.char) {
+            return None;
+        }
+        let end = self.end_position();
+        if position.line == removed_end.line {
+            return Some(CursorPosition { line: end.line, char: end.char + (position.char - removed_end.char) });
+        }
+        Some(CursorPosition { line: position.line - removed_end.line + end.line, char: position.char })
+    }
+
     /// apply reverse edit (goes into undone)
     pub fn apply_rev(
         &self,
@@ -0,0 +1,112 @@
+use super::{Actions, EditType};
+use crate::{configs::get_config_dir, workspace::utils::bytes_hash};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const HISTORY_DIR: &str = "history";
+/// approximate bytes of stored steps - the oldest are left out above it
+const STORE_CAP: usize = 1024 * 1024;
+
+/// undo/redo steps of a file, valid only for the content they lead to
+#[derive(Serialize)]
+struct HistoryRef<'a> {
+    content_hash: u64,
+    done: &'a [EditType],
+    undone: &'a [EditType],
+}
+
+#[derive(Deserialize)]
+struct StoredHistory {
+    content_hash: u64,
+    done: Vec<EditType>,
+    undone: Vec<EditType>,
+}
+
+impl Actions {
+    /// stores the steps of file, content_hash is of the content on disk the steps lead to
+    pub fn store_history(&mut self, file: &Path, content_hash: u64) {
+        if let Some(history) = history_path(file) {
+            let _ = self.write_history(&history, content_hash);
+        }
+    }
+
+    /// restores the steps of file if they lead to the content, stale history is removed
+    pub fn restore_history(&mut self, file: &Path, content_hash: u64) {
+        if let Some(history) = history_path(file) {
+            self.read_history(&history, content_hash);
+        }
+    }
+
+    /// removes stored steps of file - used when the last steps are not on disk
+    pub fn discard_history(file: &Path) {
+        if let Some(history) = history_path(file) {
+            let _ = std::fs::remove_file(history);
+        }
+    }
+
+    /// writes the newest steps fitting the cap into history, nothing to store removes the file
+    pub fn write_history(&mut self, history: &Path, content_hash: u64) -> std::io::Result<()> {
+        // typed text not yet collected is part of the content
+        if let Some(edit) = self.buffer.collect() {
+            let action = EditType::from(edit);
+            self.undone.clear();
+            self.done_bytes += action.approx_bytes();
+            self.done.push(action);
+        }
+        let mut budget = std::cmp::min(self.limit.bytes, STORE_CAP);
+        let done = &self.done[self.done.len() - fitting(self.done.iter().rev(), &mut budget)..];
+        let undone = &self.undone[self.undone.len() - fitting(self.undone.iter().rev(), &mut budget)..];
+        if done.is_empty() && undone.is_empty() {
+            return match std::fs::remove_file(history) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+                _ => Ok(()),
+            };
+        }
+        let text = serde_json::to_string(&HistoryRef { content_hash, done, undone })?;
+        if let Some(dir) = history.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(history, text)
+    }
+
+    /// loads steps from history if recorded against the content hash, otherwise the file is removed
+    pub fn read_history(&mut self, history: &Path, content_hash: u64) {
+        let Ok(text) = std::fs::read_to_string(history) else {
+            return;
+        };
+        match serde_json::from_str::<StoredHistory>(&text) {
+            Ok(stored) if stored.content_hash == content_hash => {
+                self.done_bytes = stored.done.iter().map(EditType::approx_bytes).sum();
+                self.done = stored.done;
+                self.undone = stored.undone;
+                self.enforce_limit();
+            }
+            // changed outside idiom or written by other version
+            _ => {
+                let _ = std::fs::remove_file(history);
+            }
+        }
+    }
+}
+
+/// count of steps (newest first) fitting in the budget
+fn fitting<'a>(steps: impl Iterator<Item = &'a EditType>, budget: &mut usize) -> usize {
+    let mut count = 0;
+    for step in steps {
+        match budget.checked_sub(step.approx_bytes()) {
+            Some(left) => *budget = left,
+            None => break,
+        }
+        count += 1;
+    }
+    count
+}
+
+/// file named by hash of the path in the config folder
+fn history_path(file: &Path) -> Option<PathBuf> {
+    let hash = bytes_hash(file.as_os_str().as_encoded_bytes());
+    let mut path = get_config_dir()?;
+    path.push(HISTORY_DIR);
+    path.push(format!("{hash:016x}.json"));
+    Some(path)
+}
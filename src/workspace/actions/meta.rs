@@ -1,10 +1,11 @@
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     fmt::Debug,
     ops::{Add, AddAssign},
 };
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct EditMetaData {
     pub start_line: usize,
     pub from: usize, // ignored after Add - is set to 0;
@@ -111,6 +112,18 @@ impl EditMetaData {
     pub const fn rev(&self) -> Self {
         EditMetaData { start_line: self.start_line, from: self.to, to: self.from }
     }
+
+    /// maps line from before the edit to the line after it
+    /// lines touched by the edit keep their place, clamped to the lines left after it
+    pub fn shift_line(&self, line: usize) -> usize {
+        if line < self.start_line {
+            line
+        } else if line >= self.start_line + self.from {
+            line + self.to - self.from
+        } else {
+            self.start_line + std::cmp::min(line - self.start_line, self.to.saturating_sub(1))
+        }
+    }
 }
 
 impl Debug for EditMetaData {
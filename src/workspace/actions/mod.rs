@@ -1,24 +1,122 @@
 mod action_buffer;
 mod edits;
+mod history;
 mod meta;
 
 use super::{
     cursor::{Cursor, CursorPosition, Select},
     line::EditorLine,
-    utils::{get_closing_char, is_closing_repeat},
+    snippet::Snippet,
+    utils::{copy_content, indent_len, is_closing_repeat, reindent_clip, token_range_at},
 };
 use crate::{configs::IndentConfigs, syntax::Lexer, utils::Offset};
 use action_buffer::ActionBuffer;
 pub use edits::Edit;
 use lsp_types::{TextDocumentContentChangeEvent, TextEdit};
 pub use meta::EditMetaData;
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaseMode {
+    Upper,
+    Lower,
+    Title,
+    Toggle,
+}
+
+impl CaseMode {
+    /// char based - the transformed text can have different len (ß -> SS)
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::Upper => text.to_uppercase(),
+            Self::Lower => text.to_lowercase(),
+            Self::Title => {
+                let mut word_start = true;
+                let mut result = String::with_capacity(text.len());
+                for ch in text.chars() {
+                    match word_start {
+                        true => result.extend(ch.to_uppercase()),
+                        false => result.extend(ch.to_lowercase()),
+                    }
+                    word_start = !ch.is_alphanumeric();
+                }
+                result
+            }
+            Self::Toggle => {
+                let mut result = String::with_capacity(text.len());
+                for ch in text.chars() {
+                    match ch.is_uppercase() {
+                        true => result.extend(ch.to_lowercase()),
+                        false => result.extend(ch.to_uppercase()),
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+/// last edit in a form that can be applied again at the cursor
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repeat {
+    /// text typed in a single burst
+    Text(String),
+    Paste(String),
+    DeleteLine,
+    DeleteToEndOfLine,
+    DeleteToStartOfLine,
+    CommentOut,
+    /// indent (unindent) of the selected lines or at the cursor if nothing is selected
+    Indent,
+    IndentStart,
+    Unindent,
+    NewLine,
+    /// select wrapped with the char (and its closing pair)
+    Surround(char),
+}
+
+/// cap of the undo history, the oldest steps are dropped first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistoryLimit {
+    pub entries: usize,
+    /// approximate - estimated from the text of the edits
+    pub bytes: usize,
+}
+
+/// no limit - editors take it from the configs
+impl Default for HistoryLimit {
+    fn default() -> Self {
+        Self { entries: usize::MAX, bytes: usize::MAX }
+    }
+}
 
 #[derive(Default)]
 pub struct Actions {
     pub cfg: IndentConfigs,
+    pub limit: HistoryLimit,
+    /// pause ending the buffered step, without it only boundaries (word start, cursor moves) end it
+    pub group_timeout: Option<Duration>,
     done: Vec<EditType>,
+    /// approximate size of done
+    done_bytes: usize,
+    /// steps dropped from the start of done - done_len stays the index of the next step
+    evicted: usize,
+    /// dropping of steps was reported
+    warned: bool,
     undone: Vec<EditType>,
     buffer: ActionBuffer,
+    /// time of the last change kept in the buffer
+    buffered_at: Option<Instant>,
+    /// time of the last edit not yet written to disk
+    last_edit: Option<Instant>,
+    /// bumped by every edit - views built from the content are rebuilt when it changes
+    revision: usize,
+    /// set after the edit is done, any other edit drops it
+    repeat: Option<Repeat>,
 }
 
 impl Actions {
@@ -26,6 +124,73 @@ impl Actions {
         Self { cfg, ..Default::default() }
     }
 
+    pub fn with_limit(mut self, limit: HistoryLimit) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_group_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.group_timeout = timeout;
+        self
+    }
+
+    /// (steps, approximate bytes) of the undo and redo history
+    pub fn history_stats(&self) -> (usize, usize) {
+        let undone_bytes = self.undone.iter().map(EditType::approx_bytes).sum::<usize>();
+        (self.done.len() + self.undone.len(), self.done_bytes + undone_bytes)
+    }
+
+    /// true the first time after steps were dropped from the history
+    pub fn take_truncation_warning(&mut self) -> bool {
+        if self.evicted == 0 || self.warned {
+            return false;
+        }
+        self.warned = true;
+        true
+    }
+
+    #[inline]
+    pub fn last_edit(&self) -> Option<Instant> {
+        self.last_edit
+    }
+
+    /// called after the content is written (or the edits are no longer pending)
+    #[inline]
+    pub fn clear_last_edit(&mut self) {
+        self.last_edit = None;
+    }
+
+    pub fn last_repeat(&self) -> Option<&Repeat> {
+        self.repeat.as_ref()
+    }
+
+    #[inline]
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    #[inline]
+    fn mark_edit(&mut self) {
+        self.last_edit = Some(Instant::now());
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    #[inline]
+    fn mark_buffered(&mut self) {
+        self.buffered_at = Some(Instant::now());
+        self.mark_edit();
+    }
+
+    /// buffer left idle longer than the timeout is pushed as its own step
+    fn push_idle_buffer(&mut self, content: &mut [EditorLine], lexer: &mut Lexer) {
+        let Some(timeout) = self.group_timeout else {
+            return;
+        };
+        if self.buffered_at.is_some_and(|buffered_at| buffered_at.elapsed() >= timeout) {
+            self.push_buffer(content, lexer);
+        }
+    }
+
     pub fn swap_up(&mut self, cursor: &mut Cursor, content: &mut [EditorLine], lexer: &mut Lexer) {
         if cursor.line == 0 {
             return;
@@ -50,6 +215,38 @@ impl Actions {
         self.push_done(action, lexer, content);
     }
 
+    /// moves lines one position up/down for preview, the edit is kept by the caller (not in the undo history)
+    /// returns None if the lines are at the edge
+    pub fn move_lines(
+        &mut self,
+        lines: Range<usize>,
+        down: bool,
+        content: &mut [EditorLine],
+        lexer: &mut Lexer,
+    ) -> Option<EditType> {
+        self.push_buffer(content, lexer);
+        let edits = match down {
+            true if lines.end < content.len() => {
+                lines.rev().map(|line| Edit::swap_down(line, &self.cfg, content).2).collect::<Vec<_>>()
+            }
+            false if lines.start > 0 => lines.map(|line| Edit::swap_down(line - 1, &self.cfg, content).2).collect(),
+            _ => return None,
+        };
+        let action = EditType::from(edits);
+        lexer.sync(&action, content);
+        self.mark_edit();
+        Some(action)
+    }
+
+    /// reverts the preview edits of move_lines (in order they were made)
+    pub fn revert_preview(&mut self, edits: Vec<EditType>, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
+        for action in edits.into_iter().rev() {
+            action.apply_rev(content);
+            lexer.sync_rev(&action, content);
+            self.mark_edit();
+        }
+    }
+
     /// Insert new text at the top of the file preserving cursor/select relative position
     pub fn insert_top_cursor_relative_offset(
         &mut self,
@@ -90,18 +287,23 @@ impl Actions {
         self.push_done(action, lexer, content);
     }
 
+    /// cursor is set on the first stop, the stops are returned as selects
     pub fn insert_snippet(
         &mut self,
         c: &mut Cursor,
-        snippet: String,
-        cursor_offset: Option<(usize, usize)>,
+        snippet: Snippet,
         content: &mut Vec<EditorLine>,
         lexer: &mut Lexer,
-    ) {
+    ) -> Vec<Select> {
         self.push_buffer(content, lexer);
-        let (position, action) = Edit::insert_snippet(c, snippet, cursor_offset, &self.cfg, content);
-        c.set_position(position);
+        let (selects, action) = Edit::insert_snippet(c, snippet.text, &snippet.stops, &self.cfg, content);
+        match selects.first() {
+            Some((from, to)) if from != to => c.select_set(*from, *to),
+            Some((position, _)) => c.set_position(*position),
+            None => c.set_position(action.end_position()),
+        }
         self.push_done(action, lexer, content);
+        selects
     }
 
     pub fn mass_replace(
@@ -124,13 +326,87 @@ impl Actions {
         self.push_done(actions, lexer, content);
     }
 
-    pub fn apply_edits(&mut self, edits: Vec<TextEdit>, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
+    /// returns line changes of the edits in order of application, no step is made without edits
+    pub fn apply_edits(
+        &mut self,
+        edits: Vec<TextEdit>,
+        content: &mut Vec<EditorLine>,
+        lexer: &mut Lexer,
+    ) -> Vec<EditMetaData> {
+        if edits.is_empty() {
+            return Vec::new();
+        }
         self.push_buffer(content, lexer);
         let actions = edits
             .into_iter()
             .map(|e| Edit::replace_select(e.range.start.into(), e.range.end.into(), e.new_text, content))
             .collect::<Vec<Edit>>();
+        let line_changes = actions.iter().map(|edit| edit.meta).collect();
         self.push_done(actions, lexer, content);
+        line_changes
+    }
+
+    /// applies edits positioned before the done group (shifted through the edits done since)
+    /// merged into the group if it is the last step (single undo reverts both), otherwise pushed as own step
+    /// so the changes done since are kept out of it
+    /// returns false (nothing is applied) if the group was undone or an edit overlaps later changes
+    pub fn apply_edits_into(
+        &mut self,
+        group: usize,
+        mut edits: Vec<TextEdit>,
+        cursor: &mut Cursor,
+        content: &mut Vec<EditorLine>,
+        lexer: &mut Lexer,
+    ) -> bool {
+        self.push_buffer(content, lexer);
+        let Some(group) = group.checked_sub(self.evicted).filter(|group| *group < self.done.len()) else {
+            return false;
+        };
+        if edits.is_empty() {
+            return true;
+        }
+        let done_since = self.done[group..].iter().flat_map(EditType::edits).collect::<Vec<_>>();
+        let shift =
+            |position: CursorPosition| done_since.iter().try_fold(position, |pos, edit| edit.shift_position(pos));
+        for edit in edits.iter_mut() {
+            let (Some(start), Some(end)) = (shift(edit.range.start.into()), shift(edit.range.end.into())) else {
+                return false;
+            };
+            edit.range.start = start.into();
+            edit.range.end = end.into();
+        }
+        edits.sort_by(|a, b| {
+            (b.range.start.line, b.range.start.character).cmp(&(a.range.start.line, a.range.start.character))
+        });
+        let new_edits = edits
+            .into_iter()
+            .map(|e| Edit::replace_select(e.range.start.into(), e.range.end.into(), e.new_text, content))
+            .collect::<Vec<Edit>>();
+        let position = CursorPosition::from(&*cursor);
+        let new_position = new_edits.iter().fold(position, |pos, edit| edit.shift_position(pos).unwrap_or(pos));
+        cursor.at_line = (cursor.at_line + new_position.line).saturating_sub(position.line);
+        cursor.set_position(new_position);
+        let new_edits = EditType::Multi(new_edits);
+        lexer.sync(&new_edits, content);
+        let step = match (group + 1 == self.done.len()).then(|| self.pop_done()).flatten() {
+            Some(group_step) => {
+                let mut merged = group_step.into_edits();
+                merged.extend(new_edits.into_edits());
+                EditType::Multi(merged)
+            }
+            None => new_edits,
+        };
+        self.done_bytes += step.approx_bytes();
+        self.done.push(step);
+        self.enforce_limit();
+        self.undone.clear();
+        self.mark_edit();
+        true
+    }
+
+    /// steps done since the start, including the dropped ones
+    pub fn done_len(&self) -> usize {
+        self.evicted + self.done.len()
     }
 
     pub fn indent(&mut self, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
@@ -149,6 +425,7 @@ impl Actions {
                 cursor.add_to_char(self.cfg.indent.len());
             }
         }
+        self.repeat = Some(Repeat::Indent);
     }
 
     pub fn indent_start(&mut self, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
@@ -165,6 +442,7 @@ impl Actions {
                 self.push_done(edit, lexer, content);
             }
         }
+        self.repeat = Some(Repeat::IndentStart);
     }
 
     fn indent_range(
@@ -232,6 +510,7 @@ impl Actions {
                     });
             }
         }
+        self.repeat = Some(Repeat::Unindent);
     }
 
     pub fn new_line(&mut self, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
@@ -254,10 +533,12 @@ impl Actions {
                 self.push_done(edit, lexer, content);
             }
         }
+        self.repeat = Some(Repeat::NewLine);
     }
 
     pub fn comment_out(&mut self, pat: &str, cursor: &mut Cursor, content: &mut [EditorLine], lexer: &mut Lexer) {
         // TODO refactor
+        self.push_buffer(content, lexer);
         match cursor.select_take() {
             Some((mut from, mut to)) => {
                 let from_char = from.char;
@@ -298,16 +579,22 @@ impl Actions {
                 } else if let Some((offset, edit)) = into_comment(pat, line, cursor.into()) {
                     self.push_done(edit, lexer, content);
                     cursor.char = offset.offset(cursor.char);
+                } else {
+                    return;
                 }
             }
         }
+        self.repeat = Some(Repeat::CommentOut);
     }
 
     pub fn push_char(&mut self, ch: char, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
         match cursor.select_take() {
+            Some((from, to)) if self.cfg.emphasis.contains(&ch) => {
+                self.toggle_emphasis(ch, from, to, cursor, content, lexer);
+            }
             Some((mut from, mut to)) => {
                 self.push_buffer(content, lexer);
-                match get_closing_char(ch) {
+                match self.cfg.closing_char(ch) {
                     Some(closing) => {
                         content[to.line].insert(to.char, closing);
                         content[from.line].insert(from.char, ch);
@@ -320,6 +607,7 @@ impl Actions {
                         self.push_done(vec![first_edit, second_edit.new_select(from, to)], lexer, content);
                         cursor.set_position(to);
                         cursor.select_set(from, to);
+                        self.repeat = Some(Repeat::Surround(ch));
                     }
                     None => {
                         cursor.set_position(from);
@@ -332,19 +620,65 @@ impl Actions {
         }
     }
 
+    /// wraps the select with the marker, * and _ are doubled (bold) on the second press
+    /// pressing the marker on the widest form removes it - the inner select is kept
+    fn toggle_emphasis(
+        &mut self,
+        ch: char,
+        mut from: CursorPosition,
+        mut to: CursorPosition,
+        cursor: &mut Cursor,
+        content: &mut [EditorLine],
+        lexer: &mut Lexer,
+    ) {
+        let widest = if ch == '`' { 1 } else { 2 };
+        let before = content[from.line][..from.char].chars().rev().take_while(|c| *c == ch).count();
+        let after = content[to.line][to.char..].chars().take_while(|c| *c == ch).count();
+        self.push_buffer(content, lexer);
+        if before.min(after) >= widest {
+            let first_edit = Edit::remove_from_line(to.line, to.char, to.char + widest, &mut content[to.line]);
+            let start = from.char - widest;
+            let second_edit = Edit::remove_from_line(from.line, start, from.char, &mut content[from.line]);
+            let select = (from, to);
+            from.char -= widest;
+            if from.line == to.line {
+                to.char -= widest;
+            }
+            let edits = vec![first_edit.select(select.0, select.1), second_edit.new_select(from, to)];
+            self.push_done(edits, lexer, content);
+        } else {
+            content[to.line].insert(to.char, ch);
+            content[from.line].insert(from.char, ch);
+            let first_edit = Edit::record_in_line_insertion(to, ch.into()).select(from, to);
+            let second_edit = Edit::record_in_line_insertion(from, ch.into());
+            from.char += 1;
+            if from.line == to.line {
+                to.char += 1;
+            }
+            self.push_done(vec![first_edit, second_edit.new_select(from, to)], lexer, content);
+        }
+        cursor.set_position(to);
+        cursor.select_set(from, to);
+        self.repeat = Some(Repeat::Surround(ch));
+    }
+
     fn push_char_simple(&mut self, ch: char, cursor: &mut Cursor, content: &mut [EditorLine], lexer: &mut Lexer) {
         if let Some(line) = content.get_mut(cursor.line) {
-            if is_closing_repeat(line, ch, cursor.char) {
-            } else if let Some(closing) = get_closing_char(ch) {
+            let opening = self.cfg.opening_char(ch);
+            if opening.map(|opening| is_closing_repeat(line, ch, opening, cursor.char)).unwrap_or_default() {
+            } else if let Some(closing) = self.cfg.closing_char(ch) {
                 let new_text = format!("{ch}{closing}");
                 line.insert_str(cursor.char, &new_text);
                 self.push_buffer(content, lexer);
                 self.push_done(Edit::record_in_line_insertion(cursor.into(), new_text), lexer, content);
             } else {
+                self.push_idle_buffer(content, lexer);
+                let line = &mut content[cursor.line];
                 let buf_result = self.buffer.push(cursor.line, cursor.char, ch);
                 line.insert(cursor.char, ch);
+                self.mark_buffered();
                 if let Some(edit) = buf_result {
-                    self.push_done(edit, lexer, content);
+                    self.push_collected(edit, lexer, content);
                 }
             }
             cursor.add_to_char(1);
@@ -368,14 +702,149 @@ impl Actions {
                 }
             }
             None => {
+                self.push_idle_buffer(content, lexer);
                 let _ = self
                     .buffer
                     .del(cursor.line, cursor.char, &mut content[cursor.line])
                     .map(|edit| self.push_done(edit, lexer, content));
+                self.mark_buffered();
             }
         }
     }
 
+    /// transforms the case of the select (or the token under the cursor) as single edit
+    pub fn transform_case(
+        &mut self,
+        mode: CaseMode,
+        cursor: &mut Cursor,
+        content: &mut Vec<EditorLine>,
+        lexer: &mut Lexer,
+    ) {
+        if content.is_empty() {
+            return;
+        }
+        let select = cursor.select_get();
+        let (from, to) = match select {
+            Some(select) => select,
+            None => {
+                let range = token_range_at(&content[cursor.line], cursor.char);
+                if range.is_empty() {
+                    return;
+                }
+                (
+                    CursorPosition { line: cursor.line, char: range.start },
+                    CursorPosition { line: cursor.line, char: range.end },
+                )
+            }
+        };
+        let text = copy_content(from, to, content);
+        let new_text = mode.apply(&text);
+        if new_text == text {
+            return;
+        }
+        self.push_buffer(content, lexer);
+        let position = CursorPosition::from(&*cursor);
+        let edit = Edit::replace_select(from, to, new_text, content);
+        match select {
+            Some(..) => cursor.select_set(from, edit.end_position()),
+            None => cursor.set_position(CursorPosition {
+                line: position.line,
+                char: position.char.min(content[position.line].char_len()),
+            }),
+        }
+        self.push_done(edit, lexer, content);
+    }
+
+    /// removes all lines touched by the select (or the cursor line) without going through the clipboard
+    pub fn delete_line(&mut self, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
+        if content.is_empty() {
+            return;
+        }
+        let (start, end) = match cursor.select_take() {
+            // select ending at the start of a line (select line) does not include it
+            Some((from, to)) if to.char == 0 && to.line > from.line => (from.line, to.line - 1),
+            Some((from, to)) => (from.line, to.line),
+            None => (cursor.line, cursor.line),
+        };
+        let line_end = CursorPosition { line: end, char: content[end].char_len() };
+        let (from, to) = if end + 1 < content.len() {
+            (CursorPosition { line: start, char: 0 }, CursorPosition { line: end + 1, char: 0 })
+        } else if start != 0 {
+            // last line - the new line of the previous one is removed instead
+            (CursorPosition { line: start - 1, char: content[start - 1].char_len() }, line_end)
+        } else {
+            (CursorPosition::default(), line_end)
+        };
+        if from == to {
+            return;
+        }
+        self.push_buffer(content, lexer);
+        let edit = Edit::remove_select(from, to, content);
+        cursor.set_position(CursorPosition { line: start.min(content.len() - 1), char: 0 });
+        self.push_done(edit, lexer, content);
+        self.repeat = Some(Repeat::DeleteLine);
+    }
+
+    /// removes the text after the cursor on the current line (the select if there is one), returns the removed text
+    pub fn delete_to_end_of_line(
+        &mut self,
+        cursor: &mut Cursor,
+        content: &mut Vec<EditorLine>,
+        lexer: &mut Lexer,
+    ) -> Option<String> {
+        if content.is_empty() {
+            return None;
+        }
+        let (from, to) = match cursor.select_take() {
+            Some(select) => select,
+            None => (
+                CursorPosition::from(&*cursor),
+                CursorPosition { line: cursor.line, char: content[cursor.line].char_len() },
+            ),
+        };
+        let removed = self.remove_range(from, to, cursor, content, lexer)?;
+        self.repeat = Some(Repeat::DeleteToEndOfLine);
+        Some(removed)
+    }
+
+    /// removes the text before the cursor on the current line (the select if there is one), returns the removed text
+    pub fn delete_to_start_of_line(
+        &mut self,
+        cursor: &mut Cursor,
+        content: &mut Vec<EditorLine>,
+        lexer: &mut Lexer,
+    ) -> Option<String> {
+        if content.is_empty() {
+            return None;
+        }
+        let (from, to) = match cursor.select_take() {
+            Some(select) => select,
+            None => (CursorPosition { line: cursor.line, char: 0 }, CursorPosition::from(&*cursor)),
+        };
+        let removed = self.remove_range(from, to, cursor, content, lexer)?;
+        self.repeat = Some(Repeat::DeleteToStartOfLine);
+        Some(removed)
+    }
+
+    /// single undo step, the cursor is placed at the start of the range
+    fn remove_range(
+        &mut self,
+        from: CursorPosition,
+        to: CursorPosition,
+        cursor: &mut Cursor,
+        content: &mut Vec<EditorLine>,
+        lexer: &mut Lexer,
+    ) -> Option<String> {
+        if from == to {
+            return None;
+        }
+        self.push_buffer(content, lexer);
+        let removed = copy_content(from, to, content);
+        cursor.set_position(from);
+        self.push_done(Edit::remove_select(from, to, content), lexer, content);
+        Some(removed)
+    }
+
     pub fn backspace(&mut self, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
         if content.is_empty() || cursor.line == 0 && cursor.char == 0 && cursor.select_is_none() {
             return;
@@ -394,10 +863,12 @@ impl Actions {
                 self.push_done(edit, lexer, content);
             }
             None => {
+                self.push_idle_buffer(content, lexer);
                 let _ = self
                     .buffer
                     .backspace(cursor.line, cursor.char, &mut content[cursor.line], &self.cfg.indent)
                     .map(|edit| self.push_done(edit, lexer, content));
+                self.mark_buffered();
                 cursor.set_char(self.buffer.last_char());
             }
         }
@@ -405,12 +876,13 @@ impl Actions {
 
     pub fn undo(&mut self, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
         self.push_buffer(content, lexer);
-        if let Some(action) = self.done.pop() {
+        if let Some(action) = self.pop_done() {
             let (position, select) = action.apply_rev(content);
             lexer.sync_rev(&action, content);
             cursor.set_position(position);
             cursor.select_replace(select);
             self.undone.push(action);
+            self.mark_edit();
         }
     }
 
@@ -421,18 +893,71 @@ impl Actions {
             lexer.sync(&action, content);
             cursor.set_position(position);
             cursor.select_replace(select);
+            self.done_bytes += action.approx_bytes();
             self.done.push(action);
+            self.mark_edit();
         }
     }
 
     pub fn paste(&mut self, clip: String, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
         self.push_buffer(content, lexer);
+        let repeat = Repeat::Paste(clip.clone());
         let edit = match cursor.select_take() {
             Some((from, to)) => Edit::replace_select(from, to, clip, content),
             None => Edit::insert_clip(cursor.into(), clip, content),
         };
         cursor.set_position(edit.end_position());
         self.push_done(edit, lexer, content);
+        self.repeat = Some(repeat);
+    }
+
+    /// paste with the clip lines moved to the indent of the line - full lines pasted within the indent
+    /// are inserted from the line start, so the line keeps its indent
+    pub fn paste_reindent(
+        &mut self,
+        clip: String,
+        cursor: &mut Cursor,
+        content: &mut Vec<EditorLine>,
+        lexer: &mut Lexer,
+    ) {
+        let select = cursor.select_get();
+        let (mut from, mut to) = select.unwrap_or_else(|| (cursor.into(), cursor.into()));
+        let Some(indent) = content.get(from.line).map(|line| line.content[..indent_len(&line.content)].to_owned())
+        else {
+            return self.paste(clip, cursor, content, lexer);
+        };
+        self.push_buffer(content, lexer);
+        let before_cursor = match from.char <= indent.len() {
+            true if clip.ends_with('\n') => {
+                from.char = 0;
+                if select.is_none() {
+                    to.char = 0;
+                }
+                Some(0)
+            }
+            true => Some(from.char),
+            false => None,
+        };
+        let clip = reindent_clip(&clip, &indent, &self.cfg, before_cursor);
+        cursor.select_drop();
+        let edit = match from == to {
+            true => Edit::insert_clip(from, clip, content),
+            false => Edit::replace_select(from, to, clip, content),
+        };
+        cursor.set_position(edit.end_position());
+        self.push_done(edit, lexer, content);
+    }
+
+    /// replaces the last done edit (the paste) with a new clip - undo still restores the state before paste
+    pub fn cycle_paste(&mut self, clip: String, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) {
+        self.push_buffer(content, lexer);
+        if let Some(action) = self.pop_done() {
+            let (position, select) = action.apply_rev(content);
+            lexer.sync_rev(&action, content);
+            cursor.set_position(position);
+            cursor.select_replace(select);
+        }
+        self.paste(clip, cursor, content, lexer);
     }
 
     pub fn cut(&mut self, cursor: &mut Cursor, content: &mut Vec<EditorLine>, lexer: &mut Lexer) -> String {
@@ -459,30 +984,94 @@ impl Actions {
     fn push_done(&mut self, edit: impl Into<EditType>, lexer: &mut Lexer, content: &mut [EditorLine]) {
         let action: EditType = edit.into();
         lexer.sync(&action, content);
+        self.done_bytes += action.approx_bytes();
         self.done.push(action);
+        self.enforce_limit();
+        self.repeat = None;
+        self.mark_edit();
+    }
+
+    fn pop_done(&mut self) -> Option<EditType> {
+        let action = self.done.pop()?;
+        self.done_bytes -= action.approx_bytes();
+        Some(action)
+    }
+
+    /// drops the oldest steps over the limit, the last step is always kept
+    fn enforce_limit(&mut self) {
+        let mut dropped = 0;
+        while self.done.len() - dropped > 1
+            && (self.done.len() - dropped > self.limit.entries || self.done_bytes > self.limit.bytes)
+        {
+            self.done_bytes -= self.done[dropped].approx_bytes();
+            dropped += 1;
+        }
+        if dropped != 0 {
+            self.done.drain(..dropped);
+            self.evicted += dropped;
+        }
+    }
+
+    /// edit collected from the buffer - typed text (insert only) is kept for repeat
+    fn push_collected(&mut self, edit: Edit, lexer: &mut Lexer, content: &mut [EditorLine]) {
+        let typed = match edit.get_removed_text().is_empty() {
+            true => Some(Repeat::Text(edit.get_new_text().to_owned())),
+            false => None,
+        };
+        self.push_done(edit, lexer, content);
+        self.repeat = typed;
     }
 
     pub fn push_buffer(&mut self, content: &mut [EditorLine], lexer: &mut Lexer) {
+        self.buffered_at = None;
         if let Some(action) = self.buffer.collect() {
             self.undone.clear();
-            self.push_done(action, lexer, content);
+            self.push_collected(action, lexer, content);
         }
     }
 
     pub fn clear(&mut self) {
         self.done.clear();
+        self.done_bytes = 0;
+        self.evicted = 0;
+        self.warned = false;
         self.undone.clear();
         let _ = self.buffer.collect();
+        self.buffered_at = None;
+        self.last_edit = None;
+        self.revision = self.revision.wrapping_add(1);
+        self.repeat = None;
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum EditType {
     Single(Edit),
     Multi(Vec<Edit>),
 }
 
 impl EditType {
+    /// edits in order of application
+    pub fn edits(&self) -> &[Edit] {
+        match self {
+            Self::Single(edit) => std::slice::from_ref(edit),
+            Self::Multi(edits) => edits,
+        }
+    }
+
+    /// estimated memory of the edits and their text
+    pub fn approx_bytes(&self) -> usize {
+        let text = self.edits().iter().map(|edit| edit.text.len() + edit.reverse.len()).sum::<usize>();
+        std::mem::size_of::<Self>() + std::mem::size_of_val(self.edits()) + text
+    }
+
+    pub fn into_edits(self) -> Vec<Edit> {
+        match self {
+            Self::Single(edit) => vec![edit],
+            Self::Multi(edits) => edits,
+        }
+    }
+
     pub fn apply_rev(&self, content: &mut Vec<EditorLine>) -> (CursorPosition, Option<Select>) {
         match self {
             Self::Single(action) => action.apply_rev(content),
@@ -3,6 +3,7 @@ use crate::configs::IndentConfigs;
 use crate::workspace::actions::Edit;
 use crate::workspace::cursor::Cursor;
 use crate::workspace::line::EditorLine;
+use crate::workspace::snippet::TabStop;
 use crate::workspace::CursorPosition;
 
 pub fn create_content() -> Vec<EditorLine> {
@@ -245,14 +246,17 @@ fn test_insert_snippet() {
     let cfg = IndentConfigs::default();
     let mut cursor = Cursor::default();
     cursor.set_position((7, 5).into());
-    let (pos, edit) = Edit::insert_snippet(&cursor, "text() {\n    \n}".to_owned(), Some((1, 0)), &cfg, &mut content);
+    let stops = [TabStop { line: 1, char: 0, len: 0 }];
+    let (selects, edit) = Edit::insert_snippet(&cursor, "text() {\n    \n}".to_owned(), &stops, &cfg, &mut content);
     let mut edits = vec![edit];
     match_line(&content[7], &"    text() {");
     match_line(&content[8], &"        ");
     match_line(&content[9], &"    } is the first scope");
-    assert_eq!(pos, CursorPosition { line: 8, char: 4 });
+    assert_eq!(selects, [(CursorPosition { line: 8, char: 4 }, CursorPosition { line: 8, char: 4 })]);
     cursor.set_position((0, 6).into());
-    let (pos, edit) = Edit::insert_snippet(&cursor, "text() {\n    \n}".to_owned(), None, &cfg, &mut content);
+    let (selects, edit) = Edit::insert_snippet(&cursor, "text() {\n    \n}".to_owned(), &[], &cfg, &mut content);
+    assert!(selects.is_empty());
+    let pos = edit.end_position();
     edits.push(edit);
     match_line(&content[0], &"here text() {");
     match_line(&content[1], &"    ");
@@ -0,0 +1,296 @@
+use super::{line::EditorLine, utils::content_hash, Editor};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+
+const ANNOTATIONS_FILE: &str = "./.idiom/annotations";
+/// lines searched below and above the last known position for the annotated content
+const SEARCH_RADIUS: usize = 20;
+
+/// Review note attached to a line - never written into the file itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub line: usize,
+    /// hash of the line content, used to find the line again after edits
+    pub hash: u64,
+    pub text: String,
+    /// the line was not found near the last known position
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+impl Annotation {
+    pub fn new(line: usize, content: &EditorLine, text: String) -> Self {
+        Self { line, hash: line_hash(content), text, orphaned: false }
+    }
+}
+
+/// Annotations of the file open in editor
+#[derive(Default)]
+pub struct FileAnnotations {
+    notes: Vec<Annotation>,
+    /// sorted lines with marker in the gutter
+    lines: Vec<usize>,
+    /// content length and edit revision the notes were last matched against
+    synced: (usize, usize),
+}
+
+impl FileAnnotations {
+    pub fn new(notes: Vec<Annotation>, content: &mut [EditorLine]) -> Self {
+        let mut annotations = Self { notes, ..Default::default() };
+        annotations.refresh(content);
+        annotations
+    }
+
+    /// matches the notes to lines again after edits (revision changed)
+    /// lines edited in place keep their notes if the line count did not change
+    #[inline]
+    pub fn sync(&mut self, content: &mut [EditorLine], revision: usize) {
+        let synced = (content.len(), revision);
+        if self.synced == synced {
+            return;
+        }
+        let in_place = self.synced.0 == content.len();
+        self.synced = synced;
+        if self.notes.is_empty() {
+            return;
+        }
+        reassociate(&mut self.notes, content, in_place);
+        self.update_lines(content);
+    }
+
+    /// matches the notes to lines by content
+    pub fn refresh(&mut self, content: &mut [EditorLine]) {
+        self.synced.0 = content.len();
+        reassociate(&mut self.notes, content, false);
+        self.update_lines(content);
+    }
+
+    /// attaches the note to the line replacing the previous one, empty text removes the note
+    pub fn set(&mut self, line: usize, text: String, content: &mut [EditorLine]) {
+        self.notes.retain(|note| note.orphaned || note.line != line);
+        if let Some(code) = content.get(line).filter(|_| !text.is_empty()) {
+            self.notes.push(Annotation::new(line, code, text));
+        }
+        self.update_lines(content);
+    }
+
+    pub fn get(&self, line: usize) -> Option<&Annotation> {
+        self.notes.iter().find(|note| !note.orphaned && note.line == line)
+    }
+
+    #[inline]
+    pub fn lines(&self) -> &[usize] {
+        &self.lines
+    }
+
+    #[inline]
+    pub fn notes(&self) -> &[Annotation] {
+        &self.notes
+    }
+
+    fn update_lines(&mut self, content: &mut [EditorLine]) {
+        let mut lines = self.notes.iter().filter(|note| !note.orphaned).map(|note| note.line).collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines.dedup();
+        for idx in self.lines.iter().chain(lines.iter()) {
+            if let Some(line) = content.get_mut(*idx) {
+                line.clear_cache();
+            }
+        }
+        self.lines = lines;
+    }
+}
+
+/// Project wide annotations keyed by file path, stored in .idiom/annotations
+#[derive(Default)]
+pub struct Annotations {
+    files: HashMap<PathBuf, Vec<Annotation>>,
+    path: Option<PathBuf>,
+}
+
+impl Annotations {
+    pub fn load() -> Self {
+        let path = PathBuf::from(ANNOTATIONS_FILE);
+        let files =
+            std::fs::read_to_string(&path).ok().and_then(|text| serde_json::from_str(&text).ok()).unwrap_or_default();
+        Self { files, path: Some(path) }
+    }
+
+    pub fn restore(&self, editor: &mut Editor) {
+        if let Some(notes) = self.files.get(&editor.path) {
+            editor.annotations = FileAnnotations::new(notes.clone(), &mut editor.content);
+        }
+    }
+
+    pub fn store(&mut self, editor: &Editor) {
+        if editor.ephemeral || editor.is_loading() {
+            return;
+        }
+        let mut notes = editor.annotations.notes().to_vec();
+        if notes.is_empty() {
+            self.files.remove(&editor.path);
+            return;
+        }
+        reassociate(&mut notes, &editor.content, false);
+        self.files.insert(editor.path.clone(), notes);
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        // projects without notes are left without .idiom folder
+        if self.files.is_empty() && !path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(&self.files)?)
+    }
+
+    /// adds notes from other workspace (import), returns the count of notes not present yet
+    pub fn merge(&mut self, path: PathBuf, notes: Vec<Annotation>) -> usize {
+        let current = self.files.entry(path).or_default();
+        let before = current.len();
+        for note in notes {
+            if !current.iter().any(|known| known.hash == note.hash && known.text == note.text) {
+                current.push(note);
+            }
+        }
+        current.len() - before
+    }
+
+    /// all annotations sorted by path and line
+    pub fn list(&self) -> Vec<(PathBuf, Annotation)> {
+        let mut list = self
+            .files
+            .iter()
+            .flat_map(|(path, notes)| notes.iter().map(|note| (path.clone(), note.clone())))
+            .collect::<Vec<_>>();
+        list.sort_by(|(path, note), (other_path, other)| path.cmp(other_path).then(note.line.cmp(&other.line)));
+        list
+    }
+
+    /// markdown summary grouped by file (paths relative to the project)
+    pub fn markdown(&self) -> String {
+        let base = PathBuf::from("./").canonicalize().ok();
+        let mut summary = String::from("# Review notes\n");
+        let mut current = None;
+        for (path, note) in self.list() {
+            if current.as_ref() != Some(&path) {
+                let display = base.as_ref().and_then(|base| path.strip_prefix(base).ok()).unwrap_or(&path);
+                summary.push_str(&format!("\n## {}\n\n", display.display()));
+                current = Some(path);
+            }
+            match note.orphaned {
+                true => summary.push_str(&format!("- line {} (orphaned): {}\n", note.line + 1, note.text)),
+                false => summary.push_str(&format!("- line {}: {}\n", note.line + 1, note.text)),
+            }
+        }
+        summary
+    }
+}
+
+#[inline]
+pub(super) fn line_hash(line: &EditorLine) -> u64 {
+    content_hash(std::slice::from_ref(line))
+}
+
+/// moves the annotations to the nearest lines with the same content, not found are marked as orphaned
+/// with lines edited in place (line count unchanged) attached notes not found stay on the line with its new content
+fn reassociate(notes: &mut [Annotation], content: &[EditorLine], in_place: bool) {
+    for note in notes.iter_mut() {
+        match find_line(content, note.line, note.hash) {
+            Some(line) => {
+                note.line = line;
+                note.orphaned = false;
+            }
+            None => match content.get(note.line).filter(|_| in_place && !note.orphaned) {
+                Some(code) => note.hash = line_hash(code),
+                None => note.orphaned = true,
+            },
+        }
+    }
+}
+
+/// nearest line with matching hash within the search radius (below is preferred)
+pub(super) fn find_line(content: &[EditorLine], line: usize, hash: u64) -> Option<usize> {
+    let matching = |idx: &usize| content.get(*idx).is_some_and(|code| line_hash(code) == hash);
+    (0..=SEARCH_RADIUS)
+        .find_map(|offset| Some(line + offset).filter(matching).or_else(|| line.checked_sub(offset).filter(matching)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileAnnotations, SEARCH_RADIUS};
+    use crate::workspace::line::EditorLine;
+
+    fn lines(count: usize) -> Vec<EditorLine> {
+        (0..count).map(|idx| EditorLine::from(format!("line {idx}"))).collect()
+    }
+
+    #[test]
+    fn reassociate_after_insert_above() {
+        let mut content = lines(10);
+        let mut annotations = FileAnnotations::default();
+        annotations.set(5, "check this".to_owned(), &mut content);
+        annotations.set(8, "and this".to_owned(), &mut content);
+        assert_eq!(annotations.lines(), &[5, 8]);
+        for _ in 0..3 {
+            content.insert(2, EditorLine::from("new".to_owned()));
+        }
+        annotations.sync(&mut content, 1);
+        assert_eq!(annotations.lines(), &[8, 11]);
+        assert_eq!(annotations.get(8).map(|note| note.text.as_str()), Some("check this"));
+        assert!(annotations.get(5).is_none());
+        // moved too far from the last known position
+        for _ in 0..SEARCH_RADIUS + 1 {
+            content.insert(0, EditorLine::from("new".to_owned()));
+        }
+        annotations.sync(&mut content, 2);
+        assert!(annotations.lines().is_empty());
+        assert!(annotations.notes().iter().all(|note| note.orphaned));
+    }
+
+    #[test]
+    fn orphaned_after_delete() {
+        let mut content = lines(10);
+        let mut annotations = FileAnnotations::default();
+        annotations.set(4, "removed".to_owned(), &mut content);
+        annotations.set(6, "kept".to_owned(), &mut content);
+        content.remove(4);
+        annotations.sync(&mut content, 1);
+        assert_eq!(annotations.lines(), &[5]);
+        let orphaned = annotations.notes().iter().find(|note| note.orphaned).unwrap();
+        assert_eq!(orphaned.text, "removed");
+        // line content comes back (undo) - the note is attached again
+        content.insert(4, EditorLine::from("line 4".to_owned()));
+        annotations.sync(&mut content, 2);
+        assert_eq!(annotations.lines(), &[4, 6]);
+        // empty text removes the note
+        annotations.set(4, String::new(), &mut content);
+        assert_eq!(annotations.lines(), &[6]);
+        assert_eq!(annotations.notes().len(), 1);
+    }
+
+    #[test]
+    fn follow_edits_keeping_line_count() {
+        let mut content = lines(10);
+        let mut annotations = FileAnnotations::default();
+        annotations.set(3, "moved".to_owned(), &mut content);
+        annotations.set(6, "edited".to_owned(), &mut content);
+        // line moved down (swap) - the note follows the content
+        content.swap(3, 4);
+        annotations.sync(&mut content, 1);
+        assert_eq!(annotations.lines(), &[4, 6]);
+        // line edited in place keeps the note, later moves follow the new content
+        content[6].insert_str(0, "// ");
+        annotations.sync(&mut content, 2);
+        assert_eq!(annotations.get(6).map(|note| note.text.as_str()), Some("edited"));
+        content.swap(6, 7);
+        annotations.sync(&mut content, 3);
+        assert_eq!(annotations.lines(), &[4, 7]);
+        assert!(annotations.notes().iter().all(|note| !note.orphaned));
+    }
+}
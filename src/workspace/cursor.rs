@@ -1,7 +1,24 @@
-use crate::{global_state::GlobalState, workspace::line::EditorLine};
+use crate::{
+    global_state::GlobalState,
+    workspace::{
+        line::EditorLine,
+        utils::{copy_content, CharClass},
+    },
+};
 use lsp_types::Position;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
 pub type Select = (CursorPosition, CursorPosition);
 
+/// Counts shown in the footer while there is a select
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionStats {
+    pub lines: usize,
+    pub chars: usize,
+    /// whitespace separated
+    pub words: usize,
+}
+
 #[derive(Debug, Default)]
 pub struct Cursor {
     pub line: usize,
@@ -11,6 +28,7 @@ pub struct Cursor {
     pub max_rows: usize,
     pub text_width: usize,
     select: Option<Select>,
+    block: Option<Select>, // (anchor, head) of rectangular select
 }
 
 impl Cursor {
@@ -20,12 +38,6 @@ impl Cursor {
         Self { text_width, max_rows, ..Default::default() }
     }
 
-    pub fn set_cursor_checked_with_select(&mut self, position: CursorPosition, content: &[EditorLine]) {
-        self.set_cursor_checked(position, content);
-        self.init_select();
-        self.push_to_select();
-    }
-
     pub fn set_cursor_checked(&mut self, mut position: CursorPosition, content: &[EditorLine]) {
         if self.line < position.line {
             let mut current_line_len = content[self.line].char_len();
@@ -39,6 +51,11 @@ impl Cursor {
                 position.char += offset * self.text_width;
             };
         };
+        self.set_cursor_clamped(position, content);
+    }
+
+    /// sets position as is, only limiting it to the content
+    pub fn set_cursor_clamped(&mut self, position: CursorPosition, content: &[EditorLine]) {
         match content.get(position.line) {
             Some(line) => {
                 if line.char_len() > position.char {
@@ -95,15 +112,21 @@ impl Cursor {
         self.line = 0;
     }
 
+    /// first non whitespace char, or column 0 if already there (end of whitespace only lines)
     pub fn start_of_line(&mut self, content: &[EditorLine]) {
-        self.char = 0;
-        for ch in content[self.line].chars() {
-            if !ch.is_whitespace() {
-                self.phantm_char = self.char;
-                return;
-            }
-            self.char += 1;
-        }
+        self._start_of_line(content);
+    }
+
+    pub fn select_start_of_line(&mut self, content: &[EditorLine]) {
+        self.init_select();
+        self._start_of_line(content);
+        self.push_to_select();
+    }
+
+    fn _start_of_line(&mut self, content: &[EditorLine]) {
+        let indent = content[self.line].chars().take_while(|ch| ch.is_whitespace()).count();
+        self.char = if self.char == indent { 0 } else { indent };
+        self.phantm_char = self.char;
     }
 
     pub fn up(&mut self, content: &[EditorLine]) {
@@ -203,21 +226,23 @@ impl Cursor {
         self.push_to_select();
     }
 
+    /// skips whitespace and moves to the start of the previous run of word or punctuation chars
     fn _jump_left(&mut self, content: &[EditorLine]) {
         let mut line = &content[self.line][..self.char];
-        let mut last_was_char = false;
         if line.is_empty() && self.line > 0 {
             self.move_left(content);
             line = &content[self.line][..self.char];
         }
-        for ch in line.chars().rev() {
-            if last_was_char && !ch.is_alphabetic() || self.char == 0 {
-                self.phantm_char = self.char;
-                return;
-            }
+        let mut chars = line.chars().rev().peekable();
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {
             self.char -= 1;
-            last_was_char = ch.is_alphabetic();
         }
+        if let Some(class) = chars.peek().copied().map(CharClass::of) {
+            while chars.next_if(|ch| CharClass::of(*ch) == class).is_some() {
+                self.char -= 1;
+            }
+        }
+        self.phantm_char = self.char;
     }
 
     pub fn select_left(&mut self, content: &[EditorLine]) {
@@ -254,21 +279,23 @@ impl Cursor {
         self.push_to_select();
     }
 
+    /// skips whitespace and moves to the end of the next run of word or punctuation chars
     pub fn _jump_right(&mut self, content: &[EditorLine]) {
         let mut line = &content[self.line][self.char..];
-        let mut last_was_char = false;
         if line.is_empty() && content.len() - 1 > self.line {
             self.move_right(content);
             line = &content[self.line][self.char..];
         }
-        for ch in line.chars() {
-            if last_was_char && !ch.is_alphabetic() {
-                self.phantm_char = self.char;
-                return;
-            }
+        let mut chars = line.chars().peekable();
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {
             self.char += 1;
-            last_was_char = ch.is_alphabetic();
         }
+        if let Some(class) = chars.peek().copied().map(CharClass::of) {
+            while chars.next_if(|ch| CharClass::of(*ch) == class).is_some() {
+                self.char += 1;
+            }
+        }
+        self.phantm_char = self.char;
     }
 
     pub fn select_right(&mut self, content: &[EditorLine]) {
@@ -374,12 +401,83 @@ impl Cursor {
             .unwrap_or_default()
     }
 
+    pub fn select_stats(&self, content: &[EditorLine]) -> Option<SelectionStats> {
+        let (from, to) = self.select_get().filter(|(from, to)| from != to)?;
+        let words = copy_content(from, to, content).split_whitespace().count();
+        Some(SelectionStats { lines: to.line - from.line + 1, chars: self.select_len(content), words })
+    }
+
     pub fn reset(&mut self) {
         self.line = 0;
         self.char = 0;
         self.phantm_char = 0;
         self.at_line = 0;
         self.select = None;
+        self.block = None;
+    }
+
+    /// returns lines (end exclusive) and chars covered by the column select
+    /// columns are char indexes, not display width - on mixed width lines the block is not a visual rectangle
+    pub fn block_get(&self) -> Option<(Range<usize>, Range<usize>)> {
+        let (anchor, head) = self.block?;
+        let lines = anchor.line.min(head.line)..anchor.line.max(head.line) + 1;
+        let chars = anchor.char.min(head.char)..anchor.char.max(head.char);
+        Some((lines, chars))
+    }
+
+    pub fn block_is_none(&self) -> bool {
+        self.block.is_none()
+    }
+
+    pub fn block_drop(&mut self) {
+        self.block = None;
+    }
+
+    /// starts column select if needed and moves its head one line up/down
+    pub fn block_vertical(&mut self, down: bool, content: &[EditorLine]) {
+        let (_, head) = self.block_init();
+        let line = match down {
+            true if head.line + 1 < content.len() => head.line + 1,
+            false if head.line > 0 => head.line - 1,
+            _ => return,
+        };
+        self.block_head(CursorPosition { line, char: head.char }, content);
+    }
+
+    /// starts column select if needed and moves its head one char left/right
+    pub fn block_horizontal(&mut self, right: bool, content: &[EditorLine]) {
+        let (_, head) = self.block_init();
+        let char = match right {
+            true if head.char < content[head.line].char_len() => head.char + 1,
+            false if head.char > 0 => head.char - 1,
+            _ => return,
+        };
+        self.block_head(CursorPosition { line: head.line, char }, content);
+    }
+
+    /// moves both block sides to the same column (after edit)
+    pub fn block_set_char(&mut self, char: usize, content: &[EditorLine]) {
+        if let Some((anchor, head)) = self.block.as_mut() {
+            anchor.char = char;
+            head.char = char;
+            let head = *head;
+            self.block_head(head, content);
+        }
+    }
+
+    fn block_init(&mut self) -> Select {
+        self.select = None;
+        let position = CursorPosition { line: self.line, char: self.char };
+        *self.block.get_or_insert((position, position))
+    }
+
+    // lines shorter than the column keep the cursor at the end of line
+    fn block_head(&mut self, head: CursorPosition, content: &[EditorLine]) {
+        if let Some((_, block_head)) = self.block.as_mut() {
+            *block_head = head;
+        }
+        self.line = head.line;
+        self.set_char(head.char.min(content[head.line].char_len()));
     }
 }
 
@@ -407,7 +505,7 @@ impl From<&mut Cursor> for Position {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct CursorPosition {
     pub line: usize,
     pub char: usize, // this is char position not byte index
@@ -0,0 +1,202 @@
+use crate::workspace::line::EditorLine;
+use std::ops::Range;
+use unicode_width::UnicodeWidthStr;
+
+/// Text replacing the lines range with trailing comments aligned
+#[derive(Debug, PartialEq)]
+pub struct Aligned {
+    pub lines: Range<usize>,
+    pub text: String,
+    pub aligned: usize,
+    /// lines with code past the target column - left as they are
+    pub skipped: usize,
+}
+
+/// aligns trailing comments in the selected lines or in the block of lines with trailing comments around the cursor
+/// target column is the widest code + 1 or the configured column (0 based) when set
+/// char_literals - ' is used only for char literals (lifetimes in rust), otherwise it is string quote
+pub fn align_comments(
+    content: &[EditorLine],
+    select: Option<Range<usize>>,
+    cursor_line: usize,
+    comment: &str,
+    char_literals: bool,
+    column: Option<usize>,
+) -> Option<Aligned> {
+    let lines = match select {
+        Some(lines) => lines,
+        None => block_at(content, cursor_line, comment, char_literals)?,
+    };
+    if lines.is_empty() || lines.end > content.len() {
+        return None;
+    }
+    let splits = content[lines.clone()]
+        .iter()
+        .map(|line| split_trailing(&line.content, comment, char_literals))
+        .collect::<Vec<_>>();
+    if splits.iter().all(Option::is_none) {
+        return None;
+    }
+    let target = match column {
+        Some(column) => column,
+        None => splits.iter().flatten().map(|(code, _)| code.width() + 1).max()?,
+    };
+    // lines already at the target are not counted
+    let mut aligned = 0;
+    let mut skipped = 0;
+    let mut text = Vec::with_capacity(splits.len());
+    for (line, split) in content[lines.clone()].iter().zip(splits) {
+        match split {
+            Some((code, comment)) if code.width() < target => {
+                let aligned_line = format!("{code}{}{comment}", " ".repeat(target - code.width()));
+                if aligned_line != line.content {
+                    aligned += 1;
+                }
+                text.push(aligned_line);
+            }
+            Some(..) => {
+                skipped += 1;
+                text.push(line.content.to_owned());
+            }
+            None => text.push(line.content.to_owned()),
+        }
+    }
+    Some(Aligned { lines, text: text.join("\n"), aligned, skipped })
+}
+
+/// contiguous lines with trailing comments around the line
+fn block_at(content: &[EditorLine], line: usize, comment: &str, char_literals: bool) -> Option<Range<usize>> {
+    let has_trailing = |idx: usize| split_trailing(&content[idx].content, comment, char_literals).is_some();
+    if line >= content.len() || !has_trailing(line) {
+        return None;
+    }
+    let mut start = line;
+    while start > 0 && has_trailing(start - 1) {
+        start -= 1;
+    }
+    let mut end = line + 1;
+    while end < content.len() && has_trailing(end) {
+        end += 1;
+    }
+    Some(start..end)
+}
+
+/// splits line into code (without trailing whitespace) and comment, if comment follows code
+fn split_trailing<'a>(line: &'a str, comment: &str, char_literals: bool) -> Option<(&'a str, &'a str)> {
+    let idx = comment_idx(line, comment, char_literals)?;
+    let code = line[..idx].trim_end();
+    if code.is_empty() {
+        return None;
+    }
+    Some((code, &line[idx..]))
+}
+
+/// byte index of the comment token outside of strings
+fn comment_idx(line: &str, comment: &str, char_literals: bool) -> Option<usize> {
+    let mut chars = line.char_indices().peekable();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' | '`' => skip_string(&mut chars, ch)?,
+            '\'' if !char_literals => skip_string(&mut chars, ch)?,
+            '\'' => {
+                // 'c' and '\n' are literals, anything else is lifetime or label
+                let rest = &line[idx + 1..];
+                let mut rest_chars = rest.chars();
+                match (rest_chars.next(), rest_chars.next()) {
+                    (Some('\\'), ..) => skip_string(&mut chars, ch)?,
+                    (Some(_), Some('\'')) => {
+                        chars.next();
+                        chars.next();
+                    }
+                    _ => (),
+                }
+            }
+            _ if line[idx..].starts_with(comment) => return Some(idx),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// consumes chars up to the closing quote, None if the string is not closed on the line
+fn skip_string(chars: &mut impl Iterator<Item = (usize, char)>, quote: char) -> Option<()> {
+    while let Some((_, ch)) = chars.next() {
+        if ch == '\\' {
+            chars.next();
+        } else if ch == quote {
+            return Some(());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{align_comments, comment_idx, Aligned};
+    use crate::workspace::line::EditorLine;
+
+    fn lines(text: &[&str]) -> Vec<EditorLine> {
+        text.iter().map(|line| EditorLine::from(line.to_string())).collect()
+    }
+
+    #[test]
+    fn align_block_at_cursor() {
+        let content = lines(&[
+            "// header",
+            "const A: u8 = 1; // first",
+            "const LONGER: u8 = 2;     // second",
+            "const B: u8 = 3;// third",
+            "",
+            "const C: u8 = 4; // not in block",
+        ]);
+        let result = align_comments(&content, None, 2, "//", true, None).unwrap();
+        assert_eq!(
+            result,
+            Aligned {
+                lines: 1..4,
+                text: [
+                    "const A: u8 = 1;      // first",
+                    "const LONGER: u8 = 2; // second",
+                    "const B: u8 = 3;      // third",
+                ]
+                .join("\n"),
+                aligned: 3,
+                skipped: 0,
+            }
+        );
+        assert!(align_comments(&content, None, 0, "//", true, None).is_none());
+        assert!(align_comments(&content, None, 4, "//", true, None).is_none());
+    }
+
+    #[test]
+    fn strings_with_comment_token() {
+        assert_eq!(comment_idx("let url = \"http://x\"; // link", "//", true), Some(22));
+        assert_eq!(comment_idx("let c = '\"'; // quote", "//", true), Some(13));
+        assert_eq!(comment_idx("fn f<'a>(x: &'a str) // it's", "//", true), Some(21));
+        assert_eq!(comment_idx("key = 'value # not' # comment", "#", false), Some(20));
+        assert_eq!(comment_idx("key = \"unclosed # comment", "#", false), None);
+        let content = lines(&["x = \"#\" # one", "longer_name = 2 # two"]);
+        let result = align_comments(&content, Some(0..2), 0, "#", false, None).unwrap();
+        assert_eq!(result.text, "x = \"#\"         # one\nlonger_name = 2 # two");
+        let content = lines(&["; routine", "mov ax, ';' ; load", "inc ax ; next"]);
+        let result = align_comments(&content, None, 2, ";", false, None).unwrap();
+        assert_eq!(result.lines, 1..3);
+        assert_eq!(result.text, "mov ax, ';' ; load\ninc ax      ; next");
+    }
+
+    #[test]
+    fn unicode_width_and_column() {
+        let content = lines(&[
+            "name = \"日本\" # wide",
+            "x = 1 # narrow",
+            "very_long_value = 100 # past",
+        ]);
+        let result = align_comments(&content, Some(0..3), 0, "#", false, Some(16)).unwrap();
+        assert_eq!(result.text, "name = \"日本\"   # wide\nx = 1           # narrow\nvery_long_value = 100 # past");
+        assert_eq!(result.aligned, 2);
+        assert_eq!(result.skipped, 1);
+        let result = align_comments(&content, Some(0..2), 0, "#", false, None).unwrap();
+        assert_eq!(result.text, "name = \"日本\" # wide\nx = 1         # narrow");
+        assert_eq!(result.aligned, 1);
+    }
+}
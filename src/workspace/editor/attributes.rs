@@ -0,0 +1,207 @@
+use std::{
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// errno of writes blocked by attributes - mode bits fail with EACCES instead
+const EPERM: i32 = 1;
+const FS_IMMUTABLE_FL: u32 = 0x10;
+const FS_APPEND_FL: u32 = 0x20;
+
+/// reads the attribute flags of path (lsattr)
+pub type AttributeProbe = fn(&Path) -> io::Result<u32>;
+
+/// chattr attributes failing saves, even if the mode bits allow writing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileAttribute {
+    Immutable,
+    AppendOnly,
+}
+
+impl FileAttribute {
+    fn from_flags(flags: u32) -> Option<Self> {
+        if flags & FS_IMMUTABLE_FL != 0 {
+            Some(Self::Immutable)
+        } else if flags & FS_APPEND_FL != 0 {
+            Some(Self::AppendOnly)
+        } else {
+            None
+        }
+    }
+
+    /// flag used by chattr
+    pub fn flag(self) -> char {
+        match self {
+            Self::Immutable => 'i',
+            Self::AppendOnly => 'a',
+        }
+    }
+}
+
+impl Display for FileAttribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Immutable => f.write_str("immutable"),
+            Self::AppendOnly => f.write_str("append-only"),
+        }
+    }
+}
+
+/// attribute that blocked the write and the path holding it - the file or its directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeBlock {
+    pub attribute: FileAttribute,
+    pub path: PathBuf,
+}
+
+impl Display for AttributeBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is {} (chattr -{})", self.path.display(), self.attribute, self.attribute.flag())
+    }
+}
+
+/// finds the attribute behind failed write of path, the directory is checked if the file is not blocked
+/// (creating files in immutable directory fails the same way)
+pub fn blocking_attribute(error: &io::Error, path: &Path, probe: AttributeProbe) -> Option<AttributeBlock> {
+    if error.raw_os_error() != Some(EPERM) {
+        return None;
+    }
+    attribute_block(path, probe)
+}
+
+/// attribute currently blocking writes to path
+pub fn attribute_block(path: &Path, probe: AttributeProbe) -> Option<AttributeBlock> {
+    let directory = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    [path, directory].into_iter().find_map(|path| {
+        let attribute = probe(path).ok().and_then(FileAttribute::from_flags)?;
+        Some(AttributeBlock { attribute, path: path.to_owned() })
+    })
+}
+
+/// save error with the attribute named if one blocked the write
+pub fn save_error_message(error: &io::Error, path: &Path) -> String {
+    match blocking_attribute(error, path, probe_attributes) {
+        Some(block) => block.to_string(),
+        None => error.to_string(),
+    }
+}
+
+/// runs chattr with op ('-' or '+') of the attribute behind the elevation command prefix
+pub fn chattr(elevation: &str, op: char, block: &AttributeBlock) -> Result<(), String> {
+    let mut parts = elevation.split_whitespace();
+    let mut command = match parts.next() {
+        Some(program) => {
+            let mut command = Command::new(program);
+            command.args(parts).arg("chattr");
+            command
+        }
+        None => Command::new("chattr"),
+    };
+    command.arg(format!("{op}{}", block.attribute.flag())).arg(&block.path).stdin(Stdio::null());
+    let output = command.output().map_err(|error| error.to_string())?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(String::from_utf8_lossy(&output.stderr).trim().to_owned()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn probe_attributes(path: &Path) -> io::Result<u32> {
+    use std::os::fd::AsRawFd;
+    // read only open works for immutable files and directories
+    let file = std::fs::File::open(path)?;
+    let mut flags: libc::c_int = 0;
+    // SAFETY: FS_IOC_GETFLAGS writes int flags into the pointer, fd is valid while file lives
+    match unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(flags as u32),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn probe_attributes(_path: &Path) -> io::Result<u32> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        attribute_block, blocking_attribute, AttributeBlock, FileAttribute, EPERM, FS_APPEND_FL, FS_IMMUTABLE_FL,
+    };
+    use std::{
+        io,
+        path::{Path, PathBuf},
+    };
+
+    fn immutable_file(path: &Path) -> io::Result<u32> {
+        match path.ends_with("file.rs") {
+            true => Ok(FS_IMMUTABLE_FL | FS_APPEND_FL),
+            false => Ok(0),
+        }
+    }
+
+    fn append_only_dir(path: &Path) -> io::Result<u32> {
+        match path.ends_with("dir") {
+            true => Ok(FS_APPEND_FL),
+            false => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn no_attributes(_: &Path) -> io::Result<u32> {
+        Ok(0)
+    }
+
+    #[test]
+    fn blocked_file() {
+        let eperm = io::Error::from_raw_os_error(EPERM);
+        let path = PathBuf::from("dir/file.rs");
+        assert_eq!(
+            blocking_attribute(&eperm, &path, immutable_file),
+            Some(AttributeBlock { attribute: FileAttribute::Immutable, path: path.clone() })
+        );
+        assert_eq!(
+            blocking_attribute(&eperm, &path, immutable_file).unwrap().to_string(),
+            "dir/file.rs is immutable (chattr -i)"
+        );
+        assert_eq!(blocking_attribute(&eperm, &path, no_attributes), None);
+        // mode bits - attributes are not checked
+        let eacces = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert_eq!(blocking_attribute(&eacces, &path, immutable_file), None);
+    }
+
+    #[test]
+    fn blocked_directory() {
+        let eperm = io::Error::from_raw_os_error(EPERM);
+        let block = blocking_attribute(&eperm, Path::new("dir/new_file.rs"), append_only_dir).unwrap();
+        assert_eq!(block.path, PathBuf::from("dir"));
+        assert_eq!(block.to_string(), "dir is append-only (chattr -a)");
+        assert_eq!(attribute_block(Path::new("file.rs"), immutable_file).unwrap().path, PathBuf::from("file.rs"));
+        assert_eq!(attribute_block(Path::new("other.rs"), append_only_dir), None);
+    }
+
+    /// real ioctl path - needs root and file system supporting chattr, skipped otherwise
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn chattr_immutable() {
+        use super::probe_attributes;
+        use std::process::Command;
+        // SAFETY: no preconditions
+        if unsafe { libc::geteuid() } != 0 {
+            return;
+        }
+        let path = std::env::temp_dir().join(format!("idiom_chattr_{}", std::process::id()));
+        std::fs::write(&path, "text").unwrap();
+        let chattr = |flag| Command::new("chattr").arg(flag).arg(&path).output().is_ok_and(|out| out.status.success());
+        if !chattr("+i") {
+            std::fs::remove_file(&path).unwrap();
+            return;
+        }
+        let error = std::fs::write(&path, "new text").unwrap_err();
+        let block = blocking_attribute(&error, &path, probe_attributes);
+        assert!(chattr("-i"));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(block, Some(AttributeBlock { attribute: FileAttribute::Immutable, path }));
+    }
+}
@@ -1,13 +1,27 @@
 use super::super::{
-    cursor::{Cursor, CursorPosition},
+    annotations::FileAnnotations,
+    cursor::{Cursor, CursorPosition, SelectionStats},
     editor::{utils::build_display, FileUpdate},
+    folds::Folds,
     Editor,
 };
-use crate::global_state::GlobalState;
-use crate::render::backend::{Backend, BackendProtocol};
-use crate::syntax::Lexer;
-use crate::workspace::{actions::Actions, line::EditorLine};
-use crate::{configs::FileType, workspace::renderer::Renderer};
+use crate::global_state::{EditorStatus, GlobalState};
+use crate::render::{
+    backend::{color, Backend, BackendProtocol, Style},
+    layout::Rect,
+};
+use crate::syntax::{DiagnosticLine, Lexer};
+use crate::workspace::{
+    actions::{Actions, CaseMode, HistoryLimit},
+    line::EditorLine,
+    snippet::Snippet,
+    utils::content_hash,
+};
+use crate::{
+    configs::{EditorAction, EditorConfigs, FileType, IndentLabel, RenderWhitespace, ViewFamily, ViewOverrides},
+    workspace::renderer::{Renderer, Scrollbar},
+};
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, TextEdit};
 use std::path::PathBuf;
 
 pub fn mock_editor(content: Vec<String>) -> Editor {
@@ -16,6 +30,7 @@ pub fn mock_editor(content: Vec<String>) -> Editor {
     let mut gs = GlobalState::new(Backend::init()).unwrap();
     let content: Vec<EditorLine> = content.into_iter().map(EditorLine::from).collect();
     Editor {
+        saved_hash: content_hash(&content),
         line_number_offset: if content.is_empty() { 0 } else { (content.len().ilog10() + 1) as usize },
         lexer: Lexer::with_context(ft, &path, &mut gs),
         file_type: ft,
@@ -27,6 +42,45 @@ pub fn mock_editor(content: Vec<String>) -> Editor {
         content,
         renderer: Renderer::code(),
         last_render_at_line: None,
+        bracket_match: None,
+        ephemeral: false,
+        folds: Folds::default(),
+        pinned: false,
+        paste_cycle: None,
+        line_move: None,
+        register: None,
+        snippet: None,
+        loader: None,
+        parsing: None,
+        pending_jump: None,
+        wrap_column: 80,
+        comment_column: None,
+        sticky_scope: false,
+        scrollbar: None,
+        annotations: FileAnnotations::default(),
+        highlight_trailing_whitespace: true,
+        show_inlay_hints: false,
+        format_on_save: false,
+        delete_to_clipboard: false,
+        indent_override: None,
+        formatter: None,
+        external_format: None,
+        trim_trailing_whitespace: false,
+        insert_final_newline: false,
+        line_numbers: true,
+        relative_line_numbers: false,
+        highlight_current_line: false,
+        render_whitespace: RenderWhitespace::None,
+        rulers: Vec::new(),
+        view_family: ViewFamily::Code,
+        view: EditorConfigs::default().view(ViewFamily::Code, ft),
+        view_overrides: ViewOverrides::default(),
+        attribute_elevation: None,
+        persist_undo: false,
+        numbered_line: 0,
+        render_check: None,
+        status: EditorStatus::default(),
+        status_key: None,
     }
 }
 
@@ -55,3 +109,1619 @@ fn test_display() {
     assert_eq!(build_display(buf.as_path()), "editor/mod.rs");
     assert_eq!(build_display(PathBuf::from("bumba").as_path()), "bumba");
 }
+
+#[test]
+fn test_revert() {
+    let path = std::env::temp_dir().join("idiom_test_revert.rs");
+    std::fs::write(&path, "first\nsecond").unwrap();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["first".to_owned(), "second".to_owned(), "third line".to_owned()]);
+    editor.path = path.clone();
+    editor.cursor.set_position(CursorPosition { line: 2, char: 8 });
+    editor.revert(&mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "first");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "second");
+    assert_eq!(editor.content.len(), 2);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 6 });
+    assert!(editor.is_saved());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_lazy_load_on_edit() {
+    let path = std::env::temp_dir().join(format!("idiom_test_lazy_load_{}.txt", std::process::id()));
+    let text = (0..200_000).map(|idx| format!("line number {idx} of the log\n")).collect::<String>();
+    std::fs::write(&path, text).unwrap();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    let mut editor = Editor::from_path_text(path.clone(), &EditorConfigs::default(), &mut gs).unwrap();
+    assert!(editor.loader.is_some());
+    editor.map(EditorAction::Down, &mut gs);
+    assert!(editor.loader.is_some());
+    // view with the lines ahead of it
+    assert_eq!(editor.content.len(), 1020);
+    assert!(!editor.is_dirty());
+    // edits load the whole file first
+    editor.map(EditorAction::Char('x'), &mut gs);
+    assert!(editor.loader.is_none());
+    assert_eq!(editor.content.len(), 200_001);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "xline number 1 of the log");
+    assert_eq!(pull_line(&editor, 199_999).unwrap(), "line number 199999 of the log");
+    assert!(!editor.is_saved());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_lazy_load_save_copy() {
+    let path = std::env::temp_dir().join(format!("idiom_test_lazy_copy_{}.txt", std::process::id()));
+    let text = (0..200_000).map(|idx| format!("line number {idx} of the log\n")).collect::<String>();
+    std::fs::write(&path, &text).unwrap();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    let mut editor = Editor::from_path_text(path.clone(), &EditorConfigs::default(), &mut gs).unwrap();
+    assert!(editor.loader.is_some());
+    // copy is written with the lines not reached yet
+    let copy = path.with_extension("copy");
+    editor.save_copy(copy.clone(), &mut gs);
+    assert!(editor.loader.is_none());
+    assert_eq!(std::fs::read_to_string(&copy).unwrap(), text);
+    std::fs::remove_file(copy).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_lazy_load_reload() {
+    let path = std::env::temp_dir().join(format!("idiom_test_lazy_reload_{}.txt", std::process::id()));
+    let text = (0..200_000).map(|idx| format!("line number {idx} of the log\n")).collect::<String>();
+    std::fs::write(&path, &text).unwrap();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    let mut editor = Editor::from_path_text(path.clone(), &EditorConfigs::default(), &mut gs).unwrap();
+    editor.go_to(900);
+    editor.render(&mut gs);
+    editor.go_to(1800);
+    editor.render(&mut gs);
+    std::fs::write(&path, text.replace("line number 1800 ", "changed line ")).unwrap();
+    editor.reload_preserving_cursor(&mut gs);
+    // streamed again up to the cursor, the rest is left on disk
+    assert!(editor.loader.is_some());
+    assert!(editor.content.len() < 200_000);
+    assert_eq!(editor.cursor.line, 1800);
+    assert_eq!(pull_line(&editor, 1800).unwrap(), "changed line of the log");
+    assert!(!editor.is_dirty());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_background_parse() {
+    let path = std::env::temp_dir().join(format!("idiom_test_background_parse_{}.rs", std::process::id()));
+    std::fs::write(&path, "fn main() {\n\tlet x = 1;\n}").unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    let cfg = EditorConfigs::default();
+    let mut editor = Editor::from_path_background(path.clone(), FileType::Rust, &cfg, &mut gs).unwrap();
+    assert!(editor.is_loading());
+    assert_eq!(pull_line(&editor, 0).unwrap(), "loading…");
+    // input is taken without effect, closing is left to the workspace
+    assert!(editor.map(EditorAction::Char('x'), &mut gs));
+    assert!(!editor.map(EditorAction::Close, &mut gs));
+    assert!(editor.write_file().is_err());
+    let parsed = loop {
+        if let Some(parsed) = editor.take_parsed() {
+            break parsed.unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    };
+    editor.set_parsed(parsed, &cfg);
+    assert!(!editor.is_loading());
+    assert_eq!(editor.content.len(), 3);
+    assert!(editor.is_saved());
+    // indent is detected on the parsed lines
+    assert_eq!(editor.actions.cfg.indent, "\t");
+    editor.map(EditorAction::Char('x'), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "xfn main() {");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_background_parse_at_select() {
+    let path = std::env::temp_dir().join(format!("idiom_test_background_select_{}.rs", std::process::id()));
+    std::fs::write(&path, "fn main() {\n    let x = 1;\n}").unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    let cfg = EditorConfigs::default();
+    let mut editor = Editor::from_path_background(path.clone(), FileType::Rust, &cfg, &mut gs).unwrap();
+    // the placeholder has a single line - the jump waits for the parsed lines
+    editor.go_to_select(CursorPosition { line: 1, char: 8 }, CursorPosition { line: 1, char: 9 });
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition::default());
+    let parsed = loop {
+        if let Some(parsed) = editor.take_parsed() {
+            break parsed.unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    };
+    editor.set_parsed(parsed, &cfg);
+    assert!(editor.apply_pending_jump());
+    assert_eq!(
+        editor.cursor.select_get(),
+        Some((CursorPosition { line: 1, char: 8 }, CursorPosition { line: 1, char: 9 }))
+    );
+    assert_eq!(editor.copy(), Some(String::from("x")));
+    // select out of the content is clamped
+    editor.go_to_select(CursorPosition { line: 2, char: 0 }, CursorPosition { line: 9, char: 9 });
+    assert_eq!(
+        editor.cursor.select_get(),
+        Some((CursorPosition { line: 2, char: 0 }, CursorPosition { line: 2, char: 1 }))
+    );
+    editor.refresh_status();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_trim_trailing_whitespace_on_save() {
+    let path = std::env::temp_dir().join("idiom_test_trim_trailing.rs");
+    std::fs::write(&path, "keep   \nchange").unwrap();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec![
+        "keep   ".to_owned(),
+        "changed  ".to_owned(),
+        "   ".to_owned(),
+        "last".to_owned(),
+    ]);
+    editor.path = path.clone();
+    editor.trim_trailing_whitespace = true;
+    editor.cursor.set_position(CursorPosition { line: 1, char: 9 });
+    editor.save(&mut gs);
+    // unchanged line keeps its whitespace
+    assert_eq!(pull_line(&editor, 0).unwrap(), "keep   ");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "changed");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "");
+    assert_eq!(pull_line(&editor, 3).unwrap(), "last");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 7 });
+    assert!(editor.is_saved());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep   \nchanged\n\nlast");
+    // trimming is a single undo step
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "changed  ");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "   ");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_insert_final_newline_on_save() {
+    let path = std::env::temp_dir().join("idiom_test_final_newline.rs");
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["first".to_owned(), "last".to_owned()]);
+    editor.path = path.clone();
+    editor.insert_final_newline = true;
+    editor.cursor.set_position(CursorPosition { line: 1, char: 4 });
+    editor.save(&mut gs);
+    assert_eq!(editor.content.len(), 3);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 4 });
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nlast\n");
+    assert!(editor.is_saved());
+    // repeated saves do not add more lines
+    editor.save(&mut gs);
+    assert_eq!(editor.content.len(), 3);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nlast\n");
+    assert!(editor.is_saved());
+    // the new line is undoable
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.len(), 2);
+    assert!(!editor.is_saved());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_format_on_save_without_lsp() {
+    let path = std::env::temp_dir().join("idiom_test_format_on_save.rs");
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["fn main() {".to_owned(), "}".to_owned()]);
+    editor.path = path.clone();
+    editor.format_on_save = true;
+    // no formatting support - the file is written right away
+    editor.save(&mut gs);
+    assert!(editor.is_saved());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "fn main() {\n}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_external_formatter() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["fn main() {".to_owned(), "    call();".to_owned(), "}".to_owned()]);
+    let wait = |editor: &mut Editor, gs: &mut GlobalState| {
+        while editor.external_format.is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            editor.poll_external_format(gs);
+        }
+    };
+    editor.formatter = Some(String::from("tr a-z A-Z"));
+    editor.map(EditorAction::Format, &mut gs);
+    wait(&mut editor, &mut gs);
+    assert_eq!(editor.content.len(), 3);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    CALL();");
+    // single edit
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "fn main() {");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    call();");
+    // failed command leaves the text
+    editor.formatter = Some(String::from("false"));
+    editor.map(EditorAction::Format, &mut gs);
+    wait(&mut editor, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "fn main() {");
+    assert!(gs.has_popup());
+}
+
+#[test]
+fn test_pending_format_save_flushed() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let path = std::env::temp_dir().join(format!("idiom_flush_save_{}.rs", std::process::id()));
+    std::fs::write(&path, "line").unwrap();
+    let mut editor = mock_editor(vec!["line".to_owned()]);
+    editor.path = path.clone();
+    editor.format_on_save = true;
+    // formatter outliving the editor
+    editor.formatter = Some(String::from("sleep 10"));
+    editor.map(EditorAction::Char('x'), &mut gs);
+    editor.map(EditorAction::Save, &mut gs);
+    editor.poll_external_format(&mut gs);
+    editor.poll_format_save(&mut gs);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "line");
+    // closed editor (or exit) saves without the format
+    editor.flush_pending_save(&mut gs);
+    assert!(editor.external_format.is_none());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "xline");
+    // nothing is pending
+    editor.map(EditorAction::Char('y'), &mut gs);
+    editor.flush_pending_save(&mut gs);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "xline");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_relative_numbers_invalidate_render() {
+    let mut editor = mock_editor(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+    editor.line_number_offset = 1;
+    editor.last_render_at_line = Some(0);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 0 });
+    // absolute numbers do not depend on the cursor
+    editor.sync_gutter();
+    assert_eq!(editor.last_render_at_line, Some(0));
+    editor.relative_line_numbers = true;
+    editor.sync_gutter();
+    assert_eq!(editor.last_render_at_line, None);
+    // same line - cached lines are still valid
+    editor.last_render_at_line = Some(0);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 1 });
+    editor.sync_gutter();
+    assert_eq!(editor.last_render_at_line, Some(0));
+}
+
+#[test]
+fn test_cycle_paste() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["start ".to_owned()]);
+    gs.clipboard.push("old".to_owned());
+    gs.clipboard.push("new".to_owned());
+    editor.cursor.set_position(CursorPosition { line: 0, char: 6 });
+    editor.map(EditorAction::Paste, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "start new");
+    editor.map(EditorAction::CyclePaste, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "start old");
+    // wraps back to latest
+    editor.map(EditorAction::CyclePaste, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "start new");
+    editor.map(EditorAction::CyclePaste, &mut gs);
+    // cycles collapse into the paste
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "start ");
+}
+
+#[test]
+fn test_cycle_paste_cutoff() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["start ".to_owned()]);
+    gs.clipboard.push("old".to_owned());
+    gs.clipboard.push("new".to_owned());
+    editor.cursor.set_position(CursorPosition { line: 0, char: 6 });
+    editor.map(EditorAction::CyclePaste, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "start ");
+    editor.map(EditorAction::Paste, &mut gs);
+    editor.map(EditorAction::Left, &mut gs);
+    editor.map(EditorAction::Right, &mut gs);
+    editor.map(EditorAction::CyclePaste, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "start new");
+    editor.map(EditorAction::Paste, &mut gs);
+    editor.map(EditorAction::Char('x'), &mut gs);
+    let line = pull_line(&editor, 0).unwrap();
+    editor.map(EditorAction::CyclePaste, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), line);
+}
+
+#[test]
+fn test_code_wrap_mouse() {
+    let mut editor = mock_editor(vec!["0123456789".to_owned(), "abc".to_owned(), "x".to_owned()]);
+    editor.renderer = Renderer::code_wrap();
+    editor.resize(6, 10);
+    assert_eq!(editor.cursor.text_width, 4);
+    // line number takes 2 columns - second row of the first line
+    editor.mouse_cursor(CursorPosition { line: 1, char: 3 });
+    assert_eq!((editor.cursor.line, editor.cursor.char), (0, 5));
+    editor.mouse_cursor(CursorPosition { line: 3, char: 4 });
+    assert_eq!((editor.cursor.line, editor.cursor.char), (1, 2));
+    editor.mouse_select(CursorPosition { line: 4, char: 9 });
+    assert_eq!((editor.cursor.line, editor.cursor.char), (2, 1));
+    // cursor movement steps over wrapped rows
+    editor.cursor.set_position(CursorPosition { line: 0, char: 9 });
+    editor.map(EditorAction::Up, &mut GlobalState::new(Backend::init()).unwrap());
+    assert_eq!((editor.cursor.line, editor.cursor.char), (0, 5));
+}
+
+#[test]
+fn test_column_select_edit() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["let a = 1;".to_owned(), "x".to_owned(), "let c = 3;".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 4 });
+    editor.map(EditorAction::ColumnSelectDown, &mut gs);
+    // short line keeps the cursor at the end
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 1 });
+    editor.map(EditorAction::ColumnSelectDown, &mut gs);
+    editor.map(EditorAction::Char('_'), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let _a = 1;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "x_");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "let _c = 3;");
+    editor.map(EditorAction::SelectRight, &mut gs);
+    editor.map(EditorAction::Delete, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let _ = 1;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "x_");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "let _ = 3;");
+    editor.map(EditorAction::Backspace, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let  = 1;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "x_");
+    editor.map(EditorAction::Undo, &mut gs);
+    assert!(editor.cursor.block_is_none());
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let _ = 1;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "x_");
+    editor.map(EditorAction::ColumnSelectUp, &mut gs);
+    editor.map(EditorAction::Cancel, &mut gs);
+    assert!(editor.cursor.block_is_none());
+}
+
+#[test]
+fn test_move_line_mode() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["first", "second", "third", "fourth"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.cursor.set_position(CursorPosition { line: 0, char: 2 });
+    editor.map(EditorAction::MoveLineMode, &mut gs);
+    editor.map(EditorAction::Down, &mut gs);
+    editor.map(EditorAction::Down, &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "first");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 2, char: 2 });
+    editor.map(EditorAction::NewLine, &mut gs);
+    assert_eq!(
+        editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(),
+        ["second", "third", "first", "fourth"]
+    );
+    // not in the mode anymore
+    editor.map(EditorAction::Down, &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "first");
+    // single undo restores the original
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+}
+
+#[test]
+fn test_move_line_mode_abort() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["first", "second line", "third line", "x", "last"];
+    let mut editor = mock_editor(["irst", "second line", "third line", "x", "last"].map(String::from).to_vec());
+    // edit before the mode stays in the history
+    editor.map(EditorAction::Char('f'), &mut gs);
+    editor.cursor.select_set(CursorPosition { line: 1, char: 0 }, CursorPosition { line: 2, char: 3 });
+    editor.map(EditorAction::MoveLineMode, &mut gs);
+    editor.map(EditorAction::Down, &mut gs);
+    editor.map(EditorAction::Down, &mut gs);
+    // moved across the shorter line
+    assert_eq!(
+        editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(),
+        ["first", "x", "last", "second line", "third line"]
+    );
+    editor.map(EditorAction::Down, &mut gs);
+    editor.map(EditorAction::Cancel, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+    assert_eq!(editor.cursor.line, 2);
+    // only the edit before the mode is undone
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "irst");
+    assert_eq!(editor.content.iter().skip(1).map(|l| l.to_string()).collect::<Vec<_>>(), lines[1..]);
+}
+
+#[test]
+fn test_swap_last_lines_undo() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["a".to_owned(), "b".to_owned()]);
+    editor.map(EditorAction::SwapDown, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "b");
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "a");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "b");
+}
+
+#[test]
+fn test_dedupe_selection() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["a", "b", "b", "c", "b", "b", "d", "d"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    // ends on the start of the last line - it is not part of the select
+    editor.cursor.select_set(CursorPosition { line: 1, char: 0 }, CursorPosition { line: 7, char: 0 });
+    editor.dedupe_selection();
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), ["a", "b", "c", "b", "d", "d"]);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 4, char: 1 });
+    assert!(editor.cursor.select_is_none());
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+}
+
+#[test]
+fn test_delete_line() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["first", "second", "third", "last"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    gs.clipboard.push("clip".to_owned());
+    editor.cursor.set_position(CursorPosition { line: 1, char: 3 });
+    editor.map(EditorAction::DeleteLine, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), ["first", "third", "last"]);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 0 });
+    assert_eq!(gs.clipboard.pull().as_deref(), Some("clip"));
+    // last line
+    editor.cursor.set_position(CursorPosition { line: 2, char: 2 });
+    editor.map(EditorAction::DeleteLine, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), ["first", "third"]);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 0 });
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+    // select touching multiple lines
+    editor.cursor.select_set(CursorPosition { line: 1, char: 4 }, CursorPosition { line: 2, char: 1 });
+    editor.map(EditorAction::DeleteLine, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), ["first", "last"]);
+    assert!(editor.cursor.select_is_none());
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+}
+
+#[test]
+fn test_delete_line_single() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["only".to_owned()]);
+    editor.map(EditorAction::DeleteLine, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), [""]);
+    // nothing left to delete
+    editor.map(EditorAction::DeleteLine, &mut gs);
+    editor.map(EditorAction::DeleteToEndOfLine, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), ["only"]);
+    let mut empty = mock_editor(vec![]);
+    empty.map(EditorAction::DeleteLine, &mut gs);
+    empty.map(EditorAction::DeleteToEndOfLine, &mut gs);
+    assert!(empty.content.is_empty());
+}
+
+#[test]
+fn test_delete_to_end_of_line() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["let a = 🦀 + b;".to_owned(), "last".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 5 });
+    editor.map(EditorAction::DeleteToEndOfLine, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let a");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "last");
+    // at the end of line nothing happens (lines are not merged)
+    editor.map(EditorAction::DeleteToEndOfLine, &mut gs);
+    assert_eq!(editor.content.len(), 2);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 2 });
+    editor.map(EditorAction::DeleteToEndOfLine, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "la");
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let a = 🦀 + b;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "last");
+}
+
+#[test]
+fn test_completion_import_shifts_cursor() {
+    let mut editor = mock_editor(vec![
+        "fn main() {".to_owned(),
+        "    let map = Hash".to_owned(),
+        "}".to_owned(),
+    ]);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 18 });
+    editor.replace_token("HashMap".to_owned());
+    let import = TextEdit::new(
+        Range::new(Position::new(0, 0), Position::new(0, 0)),
+        "use std::collections::HashMap;\n".to_owned(),
+    );
+    assert!(editor.apply_completion_edits(vec![import]));
+    assert_eq!(pull_line(&editor, 0).unwrap(), "use std::collections::HashMap;");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "    let map = HashMap");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 2, char: 21 });
+    // single undo reverts both the completion and the import
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.len(), 3);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    let map = Hash");
+}
+
+#[test]
+fn test_snippet_tab_stops() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["    ca".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 6 });
+    editor.insert_snippet(Snippet::parse("call(${1:first}, ${2:second})$0"));
+    assert_eq!(pull_line(&editor, 0).unwrap(), "    call(first, second)");
+    assert_eq!(
+        editor.cursor.select_get(),
+        Some((CursorPosition { line: 0, char: 9 }, CursorPosition { line: 0, char: 14 }))
+    );
+    // replacement of different length moves the later stops
+    editor.map(EditorAction::Char('x'), &mut gs);
+    editor.map(EditorAction::Indent, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "    call(x, second)");
+    assert_eq!(
+        editor.cursor.select_get(),
+        Some((CursorPosition { line: 0, char: 12 }, CursorPosition { line: 0, char: 18 }))
+    );
+    editor.map(EditorAction::Unintent, &mut gs);
+    assert_eq!(
+        editor.cursor.select_get(),
+        Some((CursorPosition { line: 0, char: 9 }, CursorPosition { line: 0, char: 10 }))
+    );
+    editor.map(EditorAction::Indent, &mut gs);
+    editor.map(EditorAction::Indent, &mut gs);
+    assert_eq!(editor.cursor.select_get(), None);
+    assert_eq!(editor.cursor.char, 19);
+    // snippet mode ended on $0 - tab indents again
+    assert!(editor.snippet.is_none());
+}
+
+#[test]
+fn test_delete_to_start_of_line() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["let a = 🦀 + b;".to_owned(), "last".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 8 });
+    editor.map(EditorAction::DeleteToStartOfLine, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "🦀 + b;");
+    assert_eq!(editor.cursor.char, 0);
+    // at the start of line nothing happens (lines are not merged)
+    editor.map(EditorAction::DeleteToStartOfLine, &mut gs);
+    assert_eq!(editor.content.len(), 2);
+    // removed text is copied only if enabled
+    assert!(gs.clipboard.history(0).is_none());
+    editor.delete_to_clipboard = true;
+    editor.cursor.set_position(CursorPosition { line: 0, char: 1 });
+    editor.map(EditorAction::DeleteToEndOfLine, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "🦀");
+    assert_eq!(gs.clipboard.history(0), Some(" + b;"));
+    // select is removed instead
+    editor.cursor.select_set(CursorPosition { line: 0, char: 1 }, CursorPosition { line: 1, char: 2 });
+    editor.map(EditorAction::DeleteToStartOfLine, &mut gs);
+    assert_eq!(editor.content.len(), 1);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "🦀st");
+    assert_eq!(gs.clipboard.history(0), Some("\nla"));
+    // each delete is single undo step
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "last");
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let a = 🦀 + b;");
+}
+
+#[test]
+fn test_reflow_paragraph() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["    // one two three", "    // four five six seven", "    let x = 1;"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.wrap_column = 20;
+    editor.cursor.set_position(CursorPosition { line: 1, char: 13 });
+    editor.map(EditorAction::ReflowParagraph, &mut gs);
+    assert_eq!(
+        editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(),
+        [
+            "    // one two three",
+            "    // four five six",
+            "    // seven",
+            "    let x = 1;"
+        ]
+    );
+    // cursor stays on "five"
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 13 });
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+}
+
+#[test]
+fn test_align_comments() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = [
+        "let a = 1; // one",
+        "let longer = \"//\"; // two",
+        "let b = 'b'; // three",
+    ];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.cursor.set_position(CursorPosition { line: 1, char: 4 });
+    // the longest line is already in place
+    assert_eq!(editor.align_comments(), Some((2, 0)));
+    assert_eq!(
+        editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(),
+        [
+            "let a = 1;         // one",
+            "let longer = \"//\"; // two",
+            "let b = 'b';       // three"
+        ]
+    );
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 4 });
+    // aligned block is left without undo step
+    assert_eq!(editor.align_comments(), Some((0, 0)));
+    // single undo restores all lines
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+    editor.comment_column = Some(14);
+    assert_eq!(editor.align_comments(), Some((2, 1)));
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let a = 1;    // one");
+}
+
+#[test]
+fn test_transform_case() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["let straße = ünïcode_value;".to_owned(), "next line".to_owned()]);
+    // no select - token under the cursor
+    editor.cursor.set_position(CursorPosition { line: 0, char: 6 });
+    editor.transform_case(CaseMode::Upper);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let STRASSE = ünïcode_value;");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 0, char: 6 });
+    editor.cursor.select_set(CursorPosition { line: 0, char: 14 }, CursorPosition { line: 1, char: 4 });
+    editor.transform_case(CaseMode::Title);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let STRASSE = Ünïcode_Value;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "Next line");
+    // select is kept over the transformed text
+    assert!(select_eq((CursorPosition { line: 0, char: 14 }, CursorPosition { line: 1, char: 4 }), &editor));
+    editor.transform_case(CaseMode::Toggle);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let STRASSE = üNÏCODE_vALUE;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "nEXT line");
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let straße = ünïcode_value;");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "next line");
+}
+
+#[test]
+fn test_goto_matching_bracket() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["fn main() {", "    let a = [(1), 2];", "}"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.cursor.set_position(CursorPosition { line: 0, char: 10 });
+    editor.map(EditorAction::GoToMatchingBracket, &mut gs);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 2, char: 0 });
+    editor.map(EditorAction::GoToMatchingBracket, &mut gs);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 0, char: 10 });
+    // nested
+    editor.cursor.set_position(CursorPosition { line: 1, char: 19 });
+    editor.map(EditorAction::GoToMatchingBracket, &mut gs);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 12 });
+    // not on bracket
+    editor.cursor.set_position(CursorPosition { line: 1, char: 6 });
+    editor.map(EditorAction::GoToMatchingBracket, &mut gs);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 6 });
+}
+
+#[test]
+fn test_auto_close_by_file_type() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut cfg = EditorConfigs::default();
+    let mut editor = mock_editor(vec![String::new()]);
+    editor.actions = Actions::new(cfg.get_indent_cfg(&FileType::Rust));
+    for ch in "fn f<'a>(".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    assert_eq!(pull_line(&editor, 0).unwrap(), "fn f<'a>()");
+
+    let mut editor = mock_editor(vec![String::new()]);
+    editor.actions = Actions::new(cfg.get_indent_cfg(&FileType::Python));
+    editor.map(EditorAction::Char('\''), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "''");
+    // typing the closing char steps over it
+    editor.map(EditorAction::Char('\''), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "''");
+    assert_eq!(editor.cursor.char, 2);
+
+    cfg.auto_close_overrides.insert("rust".to_owned(), "()''".to_owned());
+    let mut editor = mock_editor(vec![String::new()]);
+    editor.actions = Actions::new(cfg.get_indent_cfg(&FileType::Rust));
+    for ch in "['".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    assert_eq!(pull_line(&editor, 0).unwrap(), "[''");
+}
+
+#[test]
+fn test_markdown_emphasis() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["some text here".to_owned()]);
+    editor.actions = Actions::new(EditorConfigs::default().get_md_indent_cfg());
+    let (from, to) = (CursorPosition { line: 0, char: 5 }, CursorPosition { line: 0, char: 9 });
+    editor.cursor.select_set(from, to);
+    editor.map(EditorAction::Char('*'), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some *text* here");
+    assert!(select_eq((CursorPosition { line: 0, char: 6 }, CursorPosition { line: 0, char: 10 }), &editor));
+    // upgrade to bold instead of nesting
+    editor.map(EditorAction::Char('*'), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some **text** here");
+    assert!(select_eq((CursorPosition { line: 0, char: 7 }, CursorPosition { line: 0, char: 11 }), &editor));
+    // unwrap
+    editor.map(EditorAction::Char('*'), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some text here");
+    assert!(select_eq((from, to), &editor));
+    // each step is a single undo
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some **text** here");
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some *text* here");
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some text here");
+    // code span toggles off on the second press
+    editor.cursor.select_set(from, to);
+    editor.map(EditorAction::Char('`'), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some `text` here");
+    editor.map(EditorAction::Char('`'), &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "some text here");
+    assert!(select_eq((from, to), &editor));
+
+    // code and text files replace the select
+    for cfg in [
+        EditorConfigs::default().get_indent_cfg(&FileType::Rust),
+        EditorConfigs::default().default_indent_cfg(),
+    ] {
+        let mut editor = mock_editor(vec!["some text here".to_owned()]);
+        editor.actions = Actions::new(cfg);
+        editor.cursor.select_set(from, to);
+        editor.map(EditorAction::Char('*'), &mut gs);
+        assert_eq!(pull_line(&editor, 0).unwrap(), "some * here");
+    }
+}
+
+#[test]
+fn test_sticky_scope() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 40, 5);
+    let mut content = vec!["fn main() {".to_owned()];
+    content.extend((0..10).map(|idx| format!("    let x{idx} = {idx};")));
+    content.push("}".to_owned());
+    let mut editor = mock_editor(content);
+    editor.sticky_scope = true;
+    editor.resize(40, 5);
+    assert_eq!(editor.cursor.max_rows, 4);
+    editor.cursor.set_position(CursorPosition { line: 5, char: 0 });
+    editor.cursor.at_line = 3;
+    editor.render(&mut gs);
+    let accent = gs.theme.accent_style;
+    let rendered = gs.writer.drain();
+    assert!(rendered.iter().any(|(style, text)| *style == accent && text == "fn main() {"));
+    // first code row is below the header
+    assert!(rendered.iter().any(|(_, text)| text == "<<go to row: 1 col: 0>>"));
+    // clicking the code row maps under the header, clicking the header jumps to the scope
+    editor.mouse_cursor(CursorPosition { line: 1, char: 5 });
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 3, char: 2 });
+    editor.mouse_cursor(CursorPosition { line: 0, char: 5 });
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 0, char: 0 });
+}
+
+#[test]
+fn test_folding() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 40, 4);
+    let mut content = vec!["fn main() {".to_owned()];
+    content.extend((0..10).map(|idx| format!("    let x{idx} = {idx};")));
+    content.extend(["}".to_owned(), "".to_owned(), "fn other() {}".to_owned()]);
+    let mut editor = mock_editor(content);
+    editor.resize(40, 4);
+    editor.cursor.set_position(CursorPosition { line: 3, char: 4 });
+    editor.map(EditorAction::Fold, &mut gs);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 0, char: 4 });
+    editor.render(&mut gs);
+    let rendered = gs.writer.drain();
+    assert!(rendered.iter().any(|(_, text)| text == " … }"));
+    assert!(!rendered.iter().any(|(_, text)| text.contains("x0")));
+    // folded block is a single row
+    editor.map(EditorAction::Down, &mut gs);
+    assert_eq!(editor.cursor.line, 12);
+    editor.map(EditorAction::Down, &mut gs);
+    editor.map(EditorAction::Up, &mut gs);
+    editor.map(EditorAction::Up, &mut gs);
+    assert_eq!(editor.cursor.line, 0);
+    editor.mouse_cursor(CursorPosition { line: 2, char: 5 });
+    assert_eq!(editor.cursor.line, 13);
+    // new line above moves the fold with its header
+    editor.cursor.set_position(CursorPosition { line: 0, char: 0 });
+    editor.map(EditorAction::NewLine, &mut gs);
+    assert_eq!(editor.folds.folded(1).map(|(end, _)| end), Some(12));
+    // jump into the block opens it
+    editor.go_to(5);
+    editor.render(&mut gs);
+    assert!(editor.folds.is_empty());
+    editor.map(EditorAction::FoldAll, &mut gs);
+    assert_eq!(editor.cursor.line, 1);
+    editor.map(EditorAction::FoldAll, &mut gs);
+    assert!(editor.folds.is_empty());
+}
+
+#[test]
+fn test_scrollbar() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 20, 10);
+    let mut editor = mock_editor((0..40).map(|idx| format!("line {idx}")).collect());
+    editor.scrollbar = Some(Scrollbar::default());
+    editor.resize(20, 10);
+    assert_eq!(editor.cursor.text_width, 16);
+    let error = Diagnostic { severity: Some(DiagnosticSeverity::ERROR), ..Default::default() };
+    editor.content[20].set_diagnostics(DiagnosticLine::from(error));
+    editor.render(&mut gs);
+    let rendered = gs.writer.drain();
+    assert!(rendered.iter().any(|(style, text)| *style == Style::fg(color::red()) && text == "│"));
+    // click on the bar scrolls without moving the cursor into the text column
+    editor.mouse_cursor(CursorPosition { line: 5, char: 19 });
+    assert_eq!(editor.cursor.at_line, 20);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 20, char: 0 });
+    // dragging keeps scrolling, even if the mouse leaves the column
+    editor.mouse_select(CursorPosition { line: 8, char: 3 });
+    assert_eq!(editor.cursor.at_line, 30);
+    assert_eq!(editor.cursor.line, 30);
+    assert!(editor.cursor.select_get().is_none());
+    editor.mouse_cursor(CursorPosition { line: 2, char: 7 });
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 32, char: 4 });
+}
+
+#[test]
+fn test_selection_stats() {
+    let mut editor = mock_editor(vec!["fn main() {".to_owned(), "    let x = 1;".to_owned(), "}".to_owned()]);
+    assert!(editor.selection_stats().is_none());
+    editor.cursor.select_set(CursorPosition { line: 0, char: 3 }, CursorPosition { line: 0, char: 3 });
+    assert!(editor.selection_stats().is_none());
+    editor.cursor.select_set(CursorPosition { line: 1, char: 8 }, CursorPosition { line: 0, char: 3 });
+    let stats = editor.selection_stats().unwrap();
+    assert_eq!(stats, SelectionStats { lines: 2, chars: 17, words: 3 });
+}
+
+#[test]
+fn test_status_refresh() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["some text here".to_owned()]);
+    editor.refresh_status();
+    assert_eq!(editor.status.select, None);
+    editor.cursor.select_set(CursorPosition { line: 0, char: 0 }, CursorPosition { line: 0, char: 4 });
+    editor.refresh_status();
+    assert_eq!(editor.status.select, Some(SelectionStats { lines: 1, chars: 4, words: 1 }));
+    // same select over edited content is counted again
+    editor.map(EditorAction::Char('x'), &mut gs);
+    editor.cursor.select_set(CursorPosition { line: 0, char: 0 }, CursorPosition { line: 0, char: 4 });
+    editor.refresh_status();
+    assert_eq!(pull_line(&editor, 0).unwrap(), "x text here");
+    assert_eq!(editor.status.select, Some(SelectionStats { lines: 1, chars: 4, words: 2 }));
+    editor.cursor.select_drop();
+    editor.refresh_status();
+    assert_eq!(editor.status.select, None);
+    assert_eq!(editor.status.cursor, CursorPosition { line: 0, char: 4 });
+    // indent set by the user is kept over config reload
+    editor.set_indent(String::from("\t"));
+    editor.refresh_status();
+    assert_eq!(editor.status.indent, IndentLabel::Tabs);
+    editor.refresh_cfg(&EditorConfigs::default());
+    editor.refresh_status();
+    assert_eq!(editor.status.indent, IndentLabel::Tabs);
+    assert_eq!(editor.actions.cfg.indent, "\t");
+}
+
+#[test]
+fn test_go_to_position() {
+    let mut editor = mock_editor((0..100).map(|idx| format!("line {idx}")).collect());
+    editor.cursor.max_rows = 20;
+    editor.cursor.select_set(CursorPosition { line: 0, char: 0 }, CursorPosition { line: 0, char: 2 });
+    editor.go_to_position(CursorPosition { line: 50, char: 3 });
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 50, char: 3 });
+    assert_eq!(editor.cursor.at_line, 40);
+    assert!(editor.cursor.select_get().is_none());
+    editor.go_to_position(CursorPosition { line: 5, char: 30 });
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 5, char: 6 });
+    assert_eq!(editor.cursor.at_line, 0);
+    editor.go_to_position(CursorPosition { line: 150, char: 0 });
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 99, char: 0 });
+}
+
+#[test]
+fn test_completion_edits_after_typing() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec![
+        "// header".to_owned(),
+        String::new(),
+        "fn main() {".to_owned(),
+        "    let map = Hash".to_owned(),
+        "}".to_owned(),
+    ]);
+    editor.cursor.set_position(CursorPosition { line: 3, char: 18 });
+    editor.replace_token("HashMap".to_owned());
+    let group = editor.actions.done_len() - 1;
+    // typing while the resolve is pending
+    for ch in "::new();".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    editor.cursor.set_position(CursorPosition { line: 0, char: 9 });
+    editor.map(EditorAction::NewLine, &mut gs);
+    editor.cursor.set_position(CursorPosition { line: 4, char: 30 });
+    // import positioned before the completion was inserted
+    let import = TextEdit::new(
+        Range::new(Position::new(1, 0), Position::new(1, 0)),
+        "use std::collections::HashMap;\n".to_owned(),
+    );
+    assert!(editor.apply_edits_into_group(group, vec![import]));
+    assert_eq!(
+        editor.content.iter().map(|line| line.to_string()).collect::<Vec<_>>(),
+        vec![
+            "// header",
+            "",
+            "use std::collections::HashMap;",
+            "",
+            "fn main() {",
+            "    let map = HashMap::new();",
+            "}"
+        ]
+    );
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 5, char: 30 });
+    // typing done since is kept out of the import step
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(
+        editor.content.iter().map(|line| line.to_string()).collect::<Vec<_>>(),
+        vec!["// header", "", "", "fn main() {", "    let map = HashMap::new();", "}"]
+    );
+    while editor.actions.done_len() > group {
+        editor.map(EditorAction::Undo, &mut gs);
+    }
+    assert_eq!(
+        editor.content.iter().map(|line| line.to_string()).collect::<Vec<_>>(),
+        vec!["// header", "", "fn main() {", "    let map = Hash", "}"]
+    );
+    assert!(!editor.apply_edits_into_group(group, vec![]));
+}
+
+#[test]
+fn test_completion_edits_merged() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec![String::new(), "let map = Hash".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 14 });
+    editor.replace_token("HashMap".to_owned());
+    let import = TextEdit::new(
+        Range::new(Position::new(0, 0), Position::new(0, 0)),
+        "use std::collections::HashMap;".to_owned(),
+    );
+    assert!(editor.apply_completion_edits(vec![import]));
+    assert_eq!(pull_line(&editor, 0).unwrap(), "use std::collections::HashMap;");
+    // nothing done since the completion - single undo reverts both
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|line| line.to_string()).collect::<Vec<_>>(), vec!["", "let map = Hash"]);
+}
+
+#[test]
+fn test_completion_edits_overlap() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["use std::{};".to_owned(), "let map = Hash".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 14 });
+    editor.replace_token("HashMap".to_owned());
+    let group = editor.actions.done_len() - 1;
+    // braces removed while waiting - the import lands within removed text
+    editor.cursor.select_set(CursorPosition { line: 0, char: 9 }, CursorPosition { line: 0, char: 11 });
+    editor.map(EditorAction::Backspace, &mut gs);
+    let import =
+        TextEdit::new(Range::new(Position::new(0, 10), Position::new(0, 10)), "collections::HashMap".to_owned());
+    assert!(!editor.apply_edits_into_group(group, vec![import]));
+    assert_eq!(editor.content[0].to_string(), "use std::;");
+}
+
+#[test]
+fn test_repeat_typed_word() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["let a = 1;".to_owned(), "let b = 2;".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 10 });
+    for ch in " one".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    // movements are not recorded
+    editor.map(EditorAction::Down, &mut gs);
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let a = 1; one");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "let b = 2; one");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 1, char: 14 });
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "let b = 2;");
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let a = 1; one");
+    // other edits drop the recorded one
+    editor.map(EditorAction::Backspace, &mut gs);
+    editor.map(EditorAction::Up, &mut gs);
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "let a = 1; one");
+}
+
+#[test]
+fn test_repeat_comment_toggle() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["let a = 1;".to_owned(), "    let b = 2;".to_owned()]);
+    editor.map(EditorAction::CommentOut, &mut gs);
+    let commented = pull_line(&editor, 0).unwrap();
+    assert!(commented.starts_with("//"));
+    editor.cursor.set_position(CursorPosition { line: 1, char: 6 });
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), format!("    {commented}").replace(" a = 1", " b = 2"));
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    let b = 2;");
+    assert_eq!(pull_line(&editor, 0).unwrap(), commented);
+}
+
+#[test]
+fn test_repeat_surround() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["fn main() {", "    call(value);", "        other(value);", "}"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.cursor.select_set(CursorPosition { line: 1, char: 9 }, CursorPosition { line: 1, char: 14 });
+    editor.map(EditorAction::Char('['), &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    call([value]);");
+    editor.map(EditorAction::Cancel, &mut gs);
+    // no select - token under the cursor is wrapped
+    editor.cursor.set_position(CursorPosition { line: 2, char: 16 });
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "        other([value]);");
+    assert!(select_eq((CursorPosition { line: 2, char: 15 }, CursorPosition { line: 2, char: 20 }), &editor));
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "        other(value);");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    call([value]);");
+}
+
+#[test]
+fn test_repeat_indent_on_current_select() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["a = 1", "b = 2", "c = 3", "d = 4"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.cursor.select_set(CursorPosition { line: 0, char: 0 }, CursorPosition { line: 1, char: 2 });
+    editor.map(EditorAction::IndentStart, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "    a = 1");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    b = 2");
+    // repeat takes the select at the time of repeat
+    editor.cursor.select_set(CursorPosition { line: 1, char: 0 }, CursorPosition { line: 2, char: 2 });
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "    a = 1");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "        b = 2");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "    c = 3");
+    // no select - the cursor line
+    editor.cursor.select_drop();
+    editor.cursor.set_position(CursorPosition { line: 3, char: 1 });
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 3).unwrap(), "    d = 4");
+    editor.map(EditorAction::Unintent, &mut gs);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 0 });
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    b = 2");
+    assert_eq!(pull_line(&editor, 3).unwrap(), "d = 4");
+}
+
+#[test]
+fn test_repeat_new_line() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["first".to_owned(), "second".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 5 });
+    editor.map(EditorAction::NewLine, &mut gs);
+    editor.cursor.set_position(CursorPosition { line: 2, char: 3 });
+    editor.map(EditorAction::RepeatLast, &mut gs);
+    assert_eq!(editor.content.len(), 4);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "sec");
+    assert_eq!(pull_line(&editor, 3).unwrap(), "ond");
+}
+
+#[test]
+fn test_named_registers() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["first".to_owned(), "second".to_owned()]);
+    gs.clipboard.push("clip".to_owned());
+    editor.map(EditorAction::Register, &mut gs);
+    editor.map(EditorAction::Char('a'), &mut gs);
+    editor.map(EditorAction::Copy, &mut gs);
+    assert_eq!(gs.clipboard.register('a'), Some("first\n"));
+    // not typed - the prefix took the name
+    assert_eq!(pull_line(&editor, 0).unwrap(), "first");
+    editor.cursor.set_position(CursorPosition { line: 1, char: 0 });
+    editor.map(EditorAction::Register, &mut gs);
+    editor.map(EditorAction::Char('a'), &mut gs);
+    editor.map(EditorAction::Paste, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "first");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "second");
+    // prefix is dropped by other actions
+    editor.map(EditorAction::Register, &mut gs);
+    editor.map(EditorAction::Char('b'), &mut gs);
+    editor.map(EditorAction::Cancel, &mut gs);
+    editor.map(EditorAction::Paste, &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "clipsecond");
+    // not a register name - typed as usual
+    editor.map(EditorAction::Register, &mut gs);
+    editor.map(EditorAction::Char('-'), &mut gs);
+    gs.clipboard.push("clip".to_owned());
+    editor.map(EditorAction::Paste, &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "clip-clipsecond");
+    // paste with register id
+    editor.paste(Some('a'), &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "clip-clipfirst");
+    editor.paste(Some('z'), &mut gs);
+    assert_eq!(pull_line(&editor, 3).unwrap(), "second");
+}
+
+#[test]
+fn test_paste_reindent() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = ["fn main() {", "    let a = 1;", "}"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    // full lines copied from deeper block - inserted before the line at its indent
+    gs.clipboard.push("            if a {\n                call();\n            }\n".to_owned());
+    editor.cursor.set_position(CursorPosition { line: 1, char: 2 });
+    editor.map(EditorAction::PasteReindent, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    if a {");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "        call();");
+    assert_eq!(pull_line(&editor, 3).unwrap(), "    }");
+    assert_eq!(pull_line(&editor, 4).unwrap(), "    let a = 1;");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 4, char: 0 });
+    // single step undo
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.len(), 3);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    let a = 1;");
+    // copy started after the indent, pasted over select past the line indent
+    gs.clipboard.push("match x {\n\t\t\tNone => (),\n\t\t}".to_owned());
+    editor.cursor.select_set(CursorPosition { line: 1, char: 12 }, CursorPosition { line: 1, char: 13 });
+    editor.map(EditorAction::PasteReindent, &mut gs);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    let a = match x {");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "        None => (),");
+    assert_eq!(pull_line(&editor, 3).unwrap(), "    };");
+    // spaces of the clip written as tabs in tab indented editor
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.set_indent(String::from("\t"));
+    gs.clipboard.push("  if a {\n      call();\n  }\n".to_owned());
+    editor.cursor.select_drop();
+    editor.cursor.set_position(CursorPosition { line: 2, char: 0 });
+    editor.map(EditorAction::PasteReindent, &mut gs);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "if a {");
+    assert_eq!(pull_line(&editor, 3).unwrap(), "\tcall();");
+    assert_eq!(pull_line(&editor, 4).unwrap(), "}");
+    assert_eq!(pull_line(&editor, 5).unwrap(), "}");
+}
+
+#[test]
+fn test_history_limit_entries() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["x".to_owned()]);
+    editor.actions = Actions::default().with_limit(HistoryLimit { entries: 3, bytes: usize::MAX });
+    editor.cursor.set_position(CursorPosition { line: 0, char: 1 });
+    for _ in 0..6 {
+        editor.map(EditorAction::NewLine, &mut gs);
+    }
+    assert_eq!(editor.content.len(), 7);
+    assert_eq!(editor.actions.history_stats().0, 3);
+    assert_eq!(editor.actions.done_len(), 6);
+    // reported once by map
+    assert!(!editor.actions.take_truncation_warning());
+    // oldest steps are gone, the rest undo cleanly
+    for _ in 0..6 {
+        editor.map(EditorAction::Undo, &mut gs);
+    }
+    assert_eq!(editor.content.len(), 4);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 3, char: 0 });
+    for _ in 0..3 {
+        editor.map(EditorAction::Redo, &mut gs);
+    }
+    assert_eq!(editor.content.len(), 7);
+    // new edit clears redo
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Char('a'), &mut gs);
+    editor.map(EditorAction::Redo, &mut gs);
+    assert_eq!(editor.content.len(), 6);
+    assert_eq!(pull_line(&editor, 5).unwrap(), "a");
+}
+
+#[test]
+fn test_history_limit_after_clear() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["x".to_owned()]);
+    editor.actions = Actions::default().with_limit(HistoryLimit { entries: 2, bytes: usize::MAX });
+    editor.cursor.set_position(CursorPosition { line: 0, char: 1 });
+    let fill = |editor: &mut Editor| {
+        for _ in 0..3 {
+            editor.actions.new_line(&mut editor.cursor, &mut editor.content, &mut editor.lexer);
+            editor.actions.push_buffer(&mut editor.content, &mut editor.lexer);
+        }
+    };
+    fill(&mut editor);
+    assert_eq!(editor.actions.done_len(), 3);
+    assert!(editor.actions.take_truncation_warning());
+    // reloaded content starts a fresh history - dropped steps are reported again
+    editor.actions.clear();
+    assert_eq!(editor.actions.done_len(), 0);
+    fill(&mut editor);
+    assert_eq!(editor.actions.done_len(), 3);
+    assert!(editor.actions.take_truncation_warning());
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.actions.done_len(), 2);
+}
+
+#[test]
+fn test_history_limit_bytes() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec![String::new()]);
+    editor.actions = Actions::default().with_limit(HistoryLimit { entries: usize::MAX, bytes: 3000 });
+    for ch in ['a', 'b', 'c'] {
+        let clip = ch.to_string().repeat(1000);
+        editor.actions.paste(clip, &mut editor.cursor, &mut editor.content, &mut editor.lexer);
+        editor.actions.push_buffer(&mut editor.content, &mut editor.lexer);
+    }
+    let (entries, bytes) = editor.actions.history_stats();
+    assert_eq!(entries, 2);
+    assert!(bytes > 2000 && bytes <= 3000);
+    assert!(editor.actions.take_truncation_warning());
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "a".repeat(1000));
+    // undone steps are counted until dropped by a new edit
+    assert_eq!(editor.actions.history_stats(), (entries, bytes));
+}
+
+#[test]
+fn test_history_bytes_after_merge() {
+    let history = temp_undo_history("merge");
+    let mut editor = mock_editor(vec![
+        "fn main() {".to_owned(),
+        "    let map = Hash".to_owned(),
+        "}".to_owned(),
+    ]);
+    editor.cursor.set_position(CursorPosition { line: 1, char: 18 });
+    editor.replace_token("HashMap".to_owned());
+    let group = editor.actions.done_len() - 1;
+    let import = TextEdit::new(Range::new(Position::new(0, 0), Position::new(0, 0)), "use std::x;\n".to_owned());
+    assert!(editor.apply_edits_into_group(group, vec![import]));
+    assert_eq!(editor.actions.history_stats().0, 1);
+    // bytes counted for the merged step are the same as counted on load
+    let hash = content_hash(&editor.content);
+    editor.actions.write_history(&history, hash).unwrap();
+    let mut restored = Actions::default();
+    restored.read_history(&history, hash);
+    assert_eq!(restored.history_stats(), editor.actions.history_stats());
+    std::fs::remove_file(history).unwrap();
+}
+
+fn temp_undo_history(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("idiom_history_{name}_{}.json", std::process::id()))
+}
+
+#[test]
+fn test_persist_undo_restore() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let history = temp_undo_history("restore");
+    let mut editor = mock_editor(vec!["let x = 1;".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 10 });
+    editor.map(EditorAction::NewLine, &mut gs);
+    for ch in "x += 1;".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    editor.map(EditorAction::NewLine, &mut gs);
+    let last_state = editor.content.iter().map(|line| line.to_string()).collect::<Vec<_>>();
+    editor.map(EditorAction::Undo, &mut gs);
+    let hash = content_hash(&editor.content);
+    editor.actions.write_history(&history, hash).unwrap();
+
+    let mut reopened = mock_editor(editor.content.iter().map(|line| line.to_string()).collect());
+    reopened.actions.read_history(&history, hash);
+    for _ in 0..20 {
+        reopened.map(EditorAction::Undo, &mut gs);
+    }
+    assert_eq!(reopened.content.len(), 1);
+    assert_eq!(pull_line(&reopened, 0).unwrap(), "let x = 1;");
+    for _ in 0..20 {
+        reopened.map(EditorAction::Redo, &mut gs);
+    }
+    assert_eq!(reopened.content.iter().map(|line| line.to_string()).collect::<Vec<_>>(), last_state);
+    std::fs::remove_file(history).unwrap();
+}
+
+#[test]
+fn test_persist_undo_stale() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let history = temp_undo_history("stale");
+    let mut editor = mock_editor(vec!["text".to_owned()]);
+    editor.map(EditorAction::NewLine, &mut gs);
+    let hash = content_hash(&editor.content);
+    editor.actions.write_history(&history, hash).unwrap();
+    // file changed outside idiom
+    let mut reopened = mock_editor(vec!["other".to_owned(), "text".to_owned()]);
+    reopened.actions.read_history(&history, hash.wrapping_add(1));
+    assert_eq!(reopened.actions.history_stats(), (0, 0));
+    assert!(!history.exists());
+}
+
+#[test]
+fn test_persist_undo_capped() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let history = temp_undo_history("cap");
+    let mut editor = mock_editor(vec![String::new()]);
+    for ch in ['a', 'b', 'c'] {
+        let clip = ch.to_string().repeat(1000);
+        editor.actions.paste(clip, &mut editor.cursor, &mut editor.content, &mut editor.lexer);
+    }
+    editor.actions.limit = HistoryLimit { entries: usize::MAX, bytes: 3000 };
+    let hash = content_hash(&editor.content);
+    editor.actions.write_history(&history, hash).unwrap();
+    let mut restored = Actions::default();
+    restored.read_history(&history, hash);
+    // newest steps are kept
+    assert_eq!(restored.history_stats().0, 2);
+    editor.actions = restored;
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "a".repeat(1000));
+    std::fs::remove_file(history).unwrap();
+}
+
+fn visible_lines(editor: &Editor) -> Vec<String> {
+    let end = std::cmp::min(editor.cursor.at_line + editor.cursor.max_rows, editor.content.len());
+    editor.content[editor.cursor.at_line..end].iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn test_view_kept_on_edits_above() {
+    let mut editor = mock_editor((0..100).map(|idx| format!("line {idx}")).collect());
+    editor.cursor.max_rows = 10;
+    editor.cursor.at_line = 40;
+    editor.cursor.set_position(CursorPosition { line: 45, char: 3 });
+    let visible = visible_lines(&editor);
+    let insert = (0..10).map(|idx| format!("new {idx}\n")).collect::<String>();
+    let edit = TextEdit::new(Range::new(Position::new(0, 0), Position::new(0, 0)), insert);
+    let removal = TextEdit::new(Range::new(Position::new(60, 0), Position::new(62, 0)), String::new());
+    editor.apply_file_edits(vec![edit, removal]);
+    assert_eq!(visible_lines(&editor), visible);
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 55, char: 3 });
+    assert_eq!(pull_line(&editor, 55).unwrap(), "line 45");
+
+    // viewed lines removed - nearest surviving line
+    let removal = TextEdit::new(Range::new(Position::new(45, 0), Position::new(60, 0)), String::new());
+    editor.apply_file_edits(vec![removal]);
+    assert_eq!(editor.cursor.at_line, 45);
+    assert_eq!(editor.cursor.line, 45);
+    assert_eq!(pull_line(&editor, 45).unwrap(), "line 50");
+}
+
+#[test]
+fn test_apply_no_file_edits() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["formatted".to_owned()]);
+    editor.apply_file_edits(vec![]);
+    assert_eq!(editor.actions.done_len(), 0);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "formatted");
+}
+
+#[test]
+fn test_view_kept_on_rebase() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let path = std::env::temp_dir().join(format!("idiom_rebase_view_{}", std::process::id()));
+    let lines = (0..50).map(|idx| format!("line {idx}")).collect::<Vec<_>>();
+    let mut editor = mock_editor(lines.clone());
+    editor.path = path.clone();
+    editor.cursor.max_rows = 10;
+    editor.cursor.at_line = 20;
+    editor.cursor.set_position(CursorPosition { line: 22, char: 0 });
+    let visible = visible_lines(&editor);
+    let mut on_disk = (0..10).map(|idx| format!("new {idx}")).collect::<Vec<_>>();
+    on_disk.extend(lines);
+    std::fs::write(&path, on_disk.join("\n")).unwrap();
+    editor.rebase(&mut gs);
+    assert_eq!(visible_lines(&editor), visible);
+    assert_eq!(editor.cursor.line, 32);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_undo_groups_typed_words() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec![String::new()]);
+    for ch in "hello world".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "hello ");
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "");
+}
+
+#[test]
+fn test_undo_group_split_by_move() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec![String::new()]);
+    for ch in "wor".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    editor.map(EditorAction::Left, &mut gs);
+    editor.map(EditorAction::Right, &mut gs);
+    editor.map(EditorAction::Char('d'), &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "wor");
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "");
+}
+
+#[test]
+fn test_undo_groups_deletions() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec!["text".to_owned()]);
+    editor.cursor.set_position(CursorPosition { line: 0, char: 4 });
+    for ch in "ing".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    for _ in 0..5 {
+        editor.map(EditorAction::Backspace, &mut gs);
+    }
+    assert_eq!(pull_line(&editor, 0).unwrap(), "te");
+    // deletions are separate from the typed text
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "texting");
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "text");
+}
+
+#[test]
+fn test_undo_group_timeout() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut editor = mock_editor(vec![String::new()]);
+    editor.actions = Actions::default().with_group_timeout(Some(std::time::Duration::ZERO));
+    for ch in "abc".chars() {
+        editor.map(EditorAction::Char(ch), &mut gs);
+    }
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "ab");
+}
+
+#[test]
+fn test_retab() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let lines = [
+        "fn main() {",
+        "\tlet a = 1;",
+        "  \tif a {",
+        "\t\t  call(\"\t\");",
+        "\t}",
+        "}",
+    ];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.cursor.set_position(CursorPosition { line: 3, char: 5 });
+    assert_eq!(editor.retab(true, 4), 4);
+    assert_eq!(pull_line(&editor, 1).unwrap(), "    let a = 1;");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "    if a {");
+    // tabs after the indent are kept
+    assert_eq!(pull_line(&editor, 3).unwrap(), "          call(\"\t\");");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 3, char: 11 });
+    assert_eq!(editor.retab(true, 4), 0);
+    assert_eq!(editor.retab(false, 4), 4);
+    assert_eq!(pull_line(&editor, 2).unwrap(), "\tif a {");
+    assert_eq!(pull_line(&editor, 3).unwrap(), "\t\t  call(\"\t\");");
+    assert_eq!(CursorPosition::from(&editor.cursor), CursorPosition { line: 3, char: 5 });
+    // single undo step each
+    editor.map(EditorAction::Undo, &mut gs);
+    editor.map(EditorAction::Undo, &mut gs);
+    assert_eq!(editor.content.iter().map(|l| l.to_string()).collect::<Vec<_>>(), lines);
+}
+
+#[test]
+fn test_retab_select() {
+    let lines = ["\tfirst", "\tsecond", "\tthird"];
+    let mut editor = mock_editor(lines.iter().map(|l| l.to_string()).collect());
+    editor.cursor.select_set(CursorPosition { line: 1, char: 3 }, CursorPosition { line: 2, char: 0 });
+    assert_eq!(editor.retab(true, 2), 1);
+    assert_eq!(pull_line(&editor, 0).unwrap(), "\tfirst");
+    assert_eq!(pull_line(&editor, 1).unwrap(), "  second");
+    assert_eq!(pull_line(&editor, 2).unwrap(), "\tthird");
+    assert!(select_eq((CursorPosition { line: 1, char: 4 }, CursorPosition { line: 2, char: 0 }), &editor));
+}
+
+#[test]
+fn test_view_defaults_by_family() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    let mut cfg = EditorConfigs::default();
+    cfg.relative_line_numbers = true;
+    cfg.rulers = vec![100];
+    cfg.view_defaults.insert(String::from("rust"), ViewOverrides { wrap: Some(true), ..Default::default() });
+    cfg.view_defaults.insert(String::from("code"), ViewOverrides { rulers: Some(vec![80]), ..Default::default() });
+    let dir = std::env::temp_dir().join(format!("idiom_view_defaults_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files = ["main.rs", "main.py", "notes.txt", "README.md"].map(|name| dir.join(name));
+    for path in files.iter() {
+        std::fs::write(path, "text\n").unwrap();
+    }
+    let [rust, python, text, md] = files;
+    let rust = Editor::from_path(rust, FileType::Rust, &cfg, &mut gs).unwrap();
+    let python = Editor::from_path(python, FileType::Python, &cfg, &mut gs).unwrap();
+    let text = Editor::from_path_text(text, &cfg, &mut gs).unwrap();
+    let md = Editor::from_path_md(md, &cfg, &mut gs).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(rust.view_settings().wrap);
+    assert_eq!(rust.rulers, vec![80]);
+    assert!(rust.relative_line_numbers);
+    assert_eq!(rust.line_number_offset, 1);
+    assert!(!python.view_settings().wrap);
+    assert_eq!(python.rulers, vec![80]);
+    // prose defaults
+    for editor in [&text, &md] {
+        assert!(!editor.line_numbers);
+        assert_eq!(editor.line_number_offset, 0);
+        assert!(editor.rulers.is_empty());
+    }
+    assert_eq!(
+        rust.view_report(),
+        "wrap on (rust), line numbers on (global), relative numbers on (global), current line off (global), whitespace None (global), \
+         rulers [80] (code)"
+    );
+    assert!(md.view_report().contains("line numbers off (markdown)"));
+}
+
+#[test]
+fn test_view_reset() {
+    let mut editor = mock_editor(vec!["text".to_owned()]);
+    let mut cfg = EditorConfigs::default();
+    editor.toggle_relative_line_numbers();
+    editor.toggle_wrap();
+    editor.cycle_render_whitespace();
+    assert!(editor.relative_line_numbers);
+    assert_eq!(editor.render_whitespace, RenderWhitespace::Trailing);
+    assert!(editor
+        .view_report()
+        .starts_with("wrap on (buffer), line numbers on (global), relative numbers on (buffer)"));
+    // toggles win over the configs
+    cfg.relative_line_numbers = false;
+    cfg.highlight_current_line = true;
+    editor.refresh_cfg(&cfg);
+    assert!(editor.relative_line_numbers);
+    assert!(editor.highlight_current_line);
+    editor.reset_view();
+    assert_eq!(editor.view_settings(), cfg.view(ViewFamily::Code, FileType::Rust).global);
+    assert!(!editor.relative_line_numbers);
+    assert_eq!(editor.render_whitespace, RenderWhitespace::None);
+    assert!(editor
+        .view_report()
+        .starts_with("wrap off (global), line numbers on (global), relative numbers off (global)"));
+}
+
+#[test]
+fn test_toggle_line_numbers() {
+    let mut editor = mock_editor(vec!["text".to_owned(); 12]);
+    editor.resize(40, 10);
+    assert_eq!(editor.line_number_offset, 2);
+    assert_eq!(editor.cursor.text_width, 37);
+    editor.toggle_line_numbers();
+    assert!(!editor.line_numbers);
+    assert_eq!(editor.line_number_offset, 0);
+    assert_eq!(editor.cursor.text_width, 39);
+    editor.toggle_line_numbers();
+    assert_eq!(editor.line_number_offset, 2);
+    assert_eq!(editor.cursor.text_width, 37);
+}
@@ -0,0 +1,151 @@
+/// cells of the LCS table - above it the changed lines are shown removed and added as whole
+const LCS_LIMIT: usize = 4_000_000;
+
+#[derive(Debug, PartialEq)]
+pub enum LineDiff {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// line level diff turning old into new, lines differing only by CR (CRLF line ending) are the same
+/// common start and end are matched directly, LCS runs only on the lines between them
+pub fn line_diff(old: &[&str], new: &[&str]) -> Vec<LineDiff> {
+    let prefix = old.iter().zip(new).take_while(|(old, new)| same_line(old, new)).count();
+    let suffix =
+        old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(old, new)| same_line(old, new)).count();
+    let old_changed = &old[prefix..old.len() - suffix];
+    let new_changed = &new[prefix..new.len() - suffix];
+    let mut diff = Vec::with_capacity(prefix + suffix + old_changed.len() + new_changed.len());
+    diff.extend(old[..prefix].iter().map(|line| LineDiff::Same(line.to_string())));
+    if (old_changed.len() + 1).saturating_mul(new_changed.len() + 1) > LCS_LIMIT {
+        diff.extend(old_changed.iter().map(|line| LineDiff::Removed(line.to_string())));
+        diff.extend(new_changed.iter().map(|line| LineDiff::Added(line.to_string())));
+    } else {
+        lcs_diff(old_changed, new_changed, &mut diff);
+    }
+    diff.extend(old[old.len() - suffix..].iter().map(|line| LineDiff::Same(line.to_string())));
+    diff
+}
+
+/// maps line of old to the same line in new, removed lines go to the line taking their place
+pub fn shift_line(diff: &[LineDiff], line: usize) -> usize {
+    let (mut old, mut new) = (0, 0);
+    for change in diff {
+        match change {
+            LineDiff::Same(..) | LineDiff::Removed(..) if old == line => return new,
+            LineDiff::Same(..) => {
+                old += 1;
+                new += 1;
+            }
+            LineDiff::Removed(..) => old += 1,
+            LineDiff::Added(..) => new += 1,
+        }
+    }
+    new.saturating_sub(1)
+}
+
+fn lcs_diff(old: &[&str], new: &[&str], diff: &mut Vec<LineDiff>) {
+    let width = new.len() + 1;
+    // len of the longest common subsequence of old[i..] and new[j..] at i * width + j
+    let mut table = vec![0_u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * width + j] = match same_line(old[i], new[j]) {
+                true => table[(i + 1) * width + j + 1] + 1,
+                false => table[(i + 1) * width + j].max(table[i * width + j + 1]),
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if same_line(old[i], new[j]) {
+            diff.push(LineDiff::Same(old[i].to_owned()));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            diff.push(LineDiff::Removed(old[i].to_owned()));
+            i += 1;
+        } else {
+            diff.push(LineDiff::Added(new[j].to_owned()));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|line| LineDiff::Removed(line.to_string())));
+    diff.extend(new[j..].iter().map(|line| LineDiff::Added(line.to_string())));
+}
+
+#[inline]
+fn same_line(old: &str, new: &str) -> bool {
+    old.strip_suffix('\r').unwrap_or(old) == new.strip_suffix('\r').unwrap_or(new)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{line_diff, shift_line, LineDiff};
+
+    #[test]
+    fn changed_lines() {
+        let old = ["a", "b", "c", "d", "e"];
+        let new = ["a", "c", "x", "d", "e", "f"];
+        assert_eq!(
+            line_diff(&old, &new),
+            vec![
+                LineDiff::Same("a".to_owned()),
+                LineDiff::Removed("b".to_owned()),
+                LineDiff::Same("c".to_owned()),
+                LineDiff::Added("x".to_owned()),
+                LineDiff::Same("d".to_owned()),
+                LineDiff::Same("e".to_owned()),
+                LineDiff::Added("f".to_owned()),
+            ]
+        );
+        assert!(line_diff(&old, &old).iter().all(|line| matches!(line, LineDiff::Same(..))));
+    }
+
+    #[test]
+    fn crlf_lines() {
+        let old = ["a\r", "b\r", "c\r", ""];
+        let new = ["a", "x", "c", ""];
+        assert_eq!(
+            line_diff(&old, &new),
+            vec![
+                LineDiff::Same("a\r".to_owned()),
+                LineDiff::Removed("b\r".to_owned()),
+                LineDiff::Added("x".to_owned()),
+                LineDiff::Same("c\r".to_owned()),
+                LineDiff::Same("".to_owned()),
+            ]
+        );
+        let changed = ["a\r", "y\r", "b\r", "c\r", ""];
+        let diff = line_diff(&old, &changed);
+        assert_eq!(diff.iter().filter(|line| !matches!(line, LineDiff::Same(..))).count(), 1);
+    }
+
+    #[test]
+    fn over_limit() {
+        let old = (0..3000).map(|idx| idx.to_string()).collect::<Vec<_>>();
+        let new = (0..3000).map(|idx| (idx * 2).to_string()).collect::<Vec<_>>();
+        let old = old.iter().map(String::as_str).collect::<Vec<_>>();
+        let new = new.iter().map(String::as_str).collect::<Vec<_>>();
+        let diff = line_diff(&old, &new);
+        // first line is common, the rest is replaced as whole
+        assert_eq!(diff[0], LineDiff::Same("0".to_owned()));
+        assert!(diff[1..3000].iter().all(|line| matches!(line, LineDiff::Removed(..))));
+        assert!(diff[3000..].iter().all(|line| matches!(line, LineDiff::Added(..))));
+        assert_eq!(diff.len(), 1 + 2 * 2999);
+    }
+
+    #[test]
+    fn shifted_lines() {
+        let old = ["a", "b", "c", "d"];
+        let new = ["x", "y", "a", "c", "z", "d"];
+        let diff = line_diff(&old, &new);
+        assert_eq!(shift_line(&diff, 0), 2);
+        // removed line goes to the line taking its place
+        assert_eq!(shift_line(&diff, 1), 3);
+        assert_eq!(shift_line(&diff, 2), 3);
+        assert_eq!(shift_line(&diff, 3), 5);
+        assert_eq!(shift_line(&diff, 10), 5);
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{io::AsyncWriteExt, process::Command, task::JoinHandle};
+
+/// time the formatter can run before its result is dropped (the command is killed)
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(5);
+
+type FormatSlot = Arc<Mutex<Option<Result<String, String>>>>;
+
+/// External formatter running on the text of the buffer - stdin to stdout
+/// the command is killed if the format is dropped before it finished (editor closed)
+pub struct ExternalFormat {
+    task: JoinHandle<()>,
+    slot: FormatSlot,
+    started: Instant,
+    /// hash of the formatted content - the result is dropped if the text changed
+    pub content_hash: u64,
+    /// editor is saved once formatted (format on save)
+    pub save: bool,
+}
+
+impl ExternalFormat {
+    pub fn start(command: &str, text: String, content_hash: u64, save: bool) -> Self {
+        let slot = FormatSlot::default();
+        let result = Arc::clone(&slot);
+        let command = command.to_owned();
+        let task = tokio::spawn(async move {
+            let formatted = run(&command, text).await;
+            if let Ok(mut slot) = result.lock() {
+                slot.replace(formatted);
+            }
+        });
+        Self { task, slot, started: Instant::now(), content_hash, save }
+    }
+
+    /// formatted text or the error (stderr of failed command), None while running
+    pub fn poll(&self) -> Option<Result<String, String>> {
+        if let Some(result) = self.slot.lock().ok().and_then(|mut slot| slot.take()) {
+            return Some(result);
+        }
+        if self.started.elapsed() > FORMAT_TIMEOUT {
+            return Some(Err(format!("Formatter timed out after {}s", FORMAT_TIMEOUT.as_secs())));
+        }
+        None
+    }
+}
+
+impl Drop for ExternalFormat {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// command is split on whitespace (no shell), the text is written to stdin while stdout is read
+async fn run(command: &str, text: String) -> Result<String, String> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| String::from("Formatter command is empty"))?;
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| format!("Failed to run {program}: {error}"))?;
+    let mut stdin = child.stdin.take().ok_or_else(|| String::from("Formatter stdin is not available"))?;
+    let has_text = !text.is_empty();
+    // stdin is closed once written (dropped) - the formatter reads until EOF
+    let write = async move { stdin.write_all(text.as_bytes()).await };
+    let (written, output) = tokio::join!(write, child.wait_with_output());
+    let output = output.map_err(|error| error.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim_end().to_owned();
+        return Err(match stderr.is_empty() {
+            true => format!("{program} failed ({})", output.status),
+            false => stderr,
+        });
+    }
+    // formatters writing the file in place (no stdout) would clear the buffer
+    if has_text && output.stdout.is_empty() {
+        return Err(format!("{program} returned no text (the formatted text is expected on stdout)"));
+    }
+    written.map_err(|error| format!("Failed to write to {program}: {error}"))?;
+    String::from_utf8(output.stdout).map_err(|_| format!("{program} output is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::run;
+
+    #[test]
+    fn formatter_output() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let text = String::from("fn main() {}\n");
+        assert_eq!(runtime.block_on(run("cat", text.clone())).unwrap(), text);
+        assert_eq!(runtime.block_on(run("tr a-z A-Z", text.clone())).unwrap(), "FN MAIN() {}\n");
+        let error = runtime.block_on(run("ls /idiom_missing_dir", text.clone())).unwrap_err();
+        assert!(error.contains("idiom_missing_dir"));
+        assert!(runtime.block_on(run("false", text.clone())).unwrap_err().contains("false failed"));
+        assert!(runtime.block_on(run("true", text.clone())).unwrap_err().contains("no text"));
+        assert!(runtime.block_on(run("idiom_missing_formatter", text)).is_err());
+    }
+}
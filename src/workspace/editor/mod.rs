@@ -1,23 +1,56 @@
+mod align;
+mod attributes;
+mod diff;
+mod formatter;
+mod reflow;
 mod utils;
 
 use super::{
-    actions::Actions,
-    cursor::{Cursor, CursorPosition},
-    line::EditorLine,
-    renderer::Renderer,
-    utils::{copy_content, find_line_start, token_range_at},
+    actions::{Actions, CaseMode, Repeat},
+    annotations::FileAnnotations,
+    cursor::{Cursor, CursorPosition, SelectionStats},
+    folds::Folds,
+    line::{parse_in_background, EditorLine, LineLoader, ParseSlot},
+    renderer::{decorated, scope_header, RenderCheck, Renderer, Scrollbar},
+    snippet::{Snippet, SnippetStops},
+    utils::{content_hash, copy_content, find_line_start, find_matching_bracket, token_range_at},
 };
 use crate::{
-    configs::{EditorAction, EditorConfigs, FileType},
+    cli::FileAnchor,
+    configs::{
+        EditorAction, EditorConfigs, FileType, IndentLabel, RenderWhitespace, SearchOptions, ViewDefaults, ViewFamily,
+        ViewOverrides, ViewSettings,
+    },
     error::{IdiomError, IdiomResult},
-    global_state::GlobalState,
+    global_state::{EditorStatus, GlobalState},
     lsp::LSPError,
+    popups::popups_editor::{formatter_failed, protected_file, revert_changes},
     render::layout::Rect,
-    syntax::{tokens::calc_wraps, Lexer},
+    syntax::{modal::LSPModal, tokens::calc_wraps, Lexer},
 };
+use attributes::{attribute_block, blocking_attribute, chattr, probe_attributes};
+pub use attributes::{save_error_message, AttributeBlock};
+pub use diff::{line_diff, LineDiff};
+use formatter::ExternalFormat;
 use lsp_types::TextEdit;
-use std::{cmp::Ordering, path::PathBuf};
-use utils::{big_file_protection, build_display, FileUpdate};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+pub use utils::{big_file_protection, build_display, is_big_file, is_medium_file, SaveResult, VIEW_SECTIONS};
+use utils::{
+    calc_line_number_offset, final_newline_edit, is_view_action, retab_edits, trailing_whitespace_edits, FileUpdate,
+    LineMove, PendingJump, Register, StatusKey,
+};
+
+/// lines of big text files loaded after the view
+const LOAD_AHEAD: usize = 1000;
+const LOADING: &str = "loading…";
+const MOVE_LINE_HINT: &str = "Move line mode: Up/Down to move, Enter to commit, Esc to abort";
+const REGISTER_HINT: &str = "Register: press a-z or 0-9, then copy, cut or paste";
 
 #[allow(dead_code)]
 pub struct Editor {
@@ -32,6 +65,80 @@ pub struct Editor {
     pub update_status: FileUpdate,
     pub line_number_offset: usize,
     pub last_render_at_line: Option<usize>,
+    pub bracket_match: Option<CursorPosition>,
+    /// scratch/preview buffers - never counted as unsaved work and excluded from session
+    pub ephemeral: bool,
+    /// pinned tabs keep their place at the front of the tab bar
+    pub pinned: bool,
+    // end of the last paste and index of the pasted clip in clipboard history
+    paste_cycle: Option<(CursorPosition, usize)>,
+    line_move: Option<LineMove>,
+    /// register prefix of the next copy, cut or paste
+    register: Option<Register>,
+    /// tab stops of the last inserted snippet, Tab/Shift-Tab move between them
+    snippet: Option<SnippetStops>,
+    /// big text files are read as the view reaches the lines, editing loads the rest (None once loaded)
+    loader: Option<LineLoader>,
+    /// lines of the file being parsed in the background, the content is a placeholder until they are set
+    parsing: Option<ParseSlot>,
+    /// jump requested while parsing, the cursor is placed once the parsed lines are set
+    pending_jump: Option<PendingJump>,
+    /// column used by reflow paragraph
+    pub wrap_column: usize,
+    /// column used by align trailing comments
+    pub comment_column: Option<usize>,
+    /// first row shows the scope header (code files only)
+    pub sticky_scope: bool,
+    /// last column shows the scrollbar
+    pub scrollbar: Option<Scrollbar>,
+    /// review notes on lines, stored outside of the file
+    pub annotations: FileAnnotations,
+    /// folded blocks (code without wrap only)
+    pub folds: Folds,
+    /// trailing whitespace is rendered with background (code renderer)
+    pub highlight_trailing_whitespace: bool,
+    /// LSP inlay hints are requested for the view and rendered within the lines (code renderer)
+    pub show_inlay_hints: bool,
+    /// document is formatted by LSP before save (if supported)
+    pub format_on_save: bool,
+    /// text removed by delete to the end (start) of line is copied to the clipboard
+    pub delete_to_clipboard: bool,
+    /// indent set by the user, kept over config reloads
+    indent_override: Option<String>,
+    /// external command formatting the document instead of LSP
+    formatter: Option<String>,
+    /// formatter command running on the text, the result is applied by poll_external_format
+    external_format: Option<ExternalFormat>,
+    /// trailing whitespace of lines changed since the last save is removed on save
+    pub trim_trailing_whitespace: bool,
+    /// empty last line is added on save if missing (code files only)
+    pub insert_final_newline: bool,
+    /// gutter is left with the separating column only if false
+    pub line_numbers: bool,
+    /// line numbers are rendered relative to the cursor line
+    pub relative_line_numbers: bool,
+    pub highlight_current_line: bool,
+    pub render_whitespace: RenderWhitespace,
+    /// text columns of the rulers in code
+    pub rulers: Vec<usize>,
+    view_family: ViewFamily,
+    /// view settings of the family and file type from the configs
+    view: ViewDefaults,
+    /// view toggled in this buffer, wins over the configs
+    view_overrides: ViewOverrides,
+    /// command prefix removing attributes blocking the save, None if not configured
+    pub attribute_elevation: Option<String>,
+    /// undo history is stored on drop and restored on open
+    persist_undo: bool,
+    /// cursor line the relative numbers were last rendered for
+    numbered_line: usize,
+    /// debug comparison of cached renders with full ones (off unless configured)
+    pub render_check: Option<Box<RenderCheck>>,
+    /// footer status, select stats are counted only when the select or the content changes
+    pub status: EditorStatus,
+    status_key: Option<StatusKey>,
+    // hash of the content as last loaded from or written to disk
+    saved_hash: u64,
 }
 
 impl Editor {
@@ -43,21 +150,32 @@ impl Editor {
     ) -> IdiomResult<Self> {
         big_file_protection(&path)?;
         let content = EditorLine::parse_lines(&path).map_err(IdiomError::GeneralError)?;
-        let display = build_display(&path);
-        let line_number_offset = if content.is_empty() { 1 } else { (content.len().ilog10() + 1) as usize };
-        Ok(Self {
-            cursor: Cursor::sized(gs, line_number_offset),
-            line_number_offset,
-            lexer: Lexer::with_context(file_type, &path, gs),
-            content,
-            renderer: Renderer::code(),
-            actions: Actions::new(cfg.get_indent_cfg(&file_type)),
-            file_type,
-            display,
-            update_status: FileUpdate::None,
-            path,
-            last_render_at_line: None,
-        })
+        Ok(Self::code(path, file_type, content, cfg, gs).with_stored_history())
+    }
+
+    /// code editor with a placeholder line until the lines parsed in the background are set (set_parsed)
+    pub fn from_path_background(
+        path: PathBuf,
+        file_type: FileType,
+        cfg: &EditorConfigs,
+        gs: &mut GlobalState,
+    ) -> IdiomResult<Self> {
+        big_file_protection(&path)?;
+        let parsing = parse_in_background(path.clone());
+        let mut editor = Self::code(path, file_type, vec![EditorLine::new(LOADING.to_owned())], cfg, gs);
+        editor.parsing = Some(parsing);
+        Ok(editor)
+    }
+
+    #[inline]
+    fn code(
+        path: PathBuf,
+        file_type: FileType,
+        content: Vec<EditorLine>,
+        cfg: &EditorConfigs,
+        gs: &mut GlobalState,
+    ) -> Self {
+        Self::build(path, file_type, ViewFamily::Code, content, cfg, gs)
     }
 
     pub fn from_path_text(path: PathBuf, cfg: &EditorConfigs, gs: &mut GlobalState) -> IdiomResult<Self> {
@@ -65,79 +183,311 @@ impl Editor {
         gs.message(
             "The file is opened in text mode, beware idiom is not designed with plain text performance in mind!",
         );
-        let mut content = EditorLine::parse_lines(&path).map_err(IdiomError::GeneralError)?;
-        let display = build_display(&path);
-        let line_number_offset = if content.is_empty() { 1 } else { (content.len().ilog10() + 1) as usize };
-        let cursor = Cursor::sized(gs, line_number_offset);
-        calc_wraps(&mut content, cursor.text_width);
-        Ok(Self {
-            cursor,
-            line_number_offset,
-            lexer: Lexer::text_lexer(&path, gs),
-            content,
-            renderer: Renderer::text(),
-            actions: Actions::new(cfg.default_indent_cfg()),
-            file_type: FileType::Ignored,
-            display,
-            update_status: FileUpdate::None,
-            path,
-            last_render_at_line: None,
-        })
+        let (content, loader) = LineLoader::open(&path, LOAD_AHEAD).map_err(IdiomError::GeneralError)?;
+        let mut editor = Self::build(path, FileType::Ignored, ViewFamily::Text, content, cfg, gs);
+        editor.loader = loader;
+        Ok(editor.with_stored_history())
     }
 
     pub fn from_path_md(path: PathBuf, cfg: &EditorConfigs, gs: &mut GlobalState) -> IdiomResult<Self> {
         big_file_protection(&path)?;
         gs.message("The file is opened in MD mode, beware idiom is not designed with MD performance in mind!");
-        let mut content = EditorLine::parse_lines(&path).map_err(IdiomError::GeneralError)?;
+        let content = EditorLine::parse_lines(&path).map_err(IdiomError::GeneralError)?;
+        Ok(Self::build(path, FileType::Ignored, ViewFamily::Markdown, content, cfg, gs).with_stored_history())
+    }
+
+    /// shared by all constructors - code specific features (sticky scope, formatter, LSP) are set only for code
+    fn build(
+        path: PathBuf,
+        file_type: FileType,
+        view_family: ViewFamily,
+        mut content: Vec<EditorLine>,
+        cfg: &EditorConfigs,
+        gs: &mut GlobalState,
+    ) -> Self {
+        let is_code = view_family == ViewFamily::Code;
+        let indent_cfg = match view_family {
+            ViewFamily::Code => cfg.with_detected_indent(cfg.get_indent_cfg(&file_type), &content),
+            ViewFamily::Text => cfg.with_detected_indent(cfg.default_indent_cfg(), &content),
+            ViewFamily::Markdown => cfg.get_md_indent_cfg(),
+        };
+        let view = cfg.view(view_family, file_type);
+        let settings = view.resolve(&ViewOverrides::default());
         let display = build_display(&path);
-        let line_number_offset = if content.is_empty() { 1 } else { (content.len().ilog10() + 1) as usize };
-        let cursor = Cursor::sized(gs, line_number_offset);
-        calc_wraps(&mut content, cursor.text_width);
-        Ok(Self {
+        let wrap_column = cfg.wrap_column(&path);
+        let saved_hash = content_hash(&content);
+        let line_number_offset = calc_line_number_offset(content.len(), settings.line_numbers);
+        let scrollbar = cfg.scrollbar.then(Scrollbar::default);
+        let sticky_scope = is_code && cfg.sticky_scope;
+        let cursor = sized_cursor(gs, line_number_offset, sticky_scope, scrollbar.is_some());
+        let (lexer, renderer) = match view_family {
+            ViewFamily::Code => (
+                Lexer::with_context(file_type, &path, gs),
+                match settings.wrap {
+                    true => Renderer::code_wrap(),
+                    false => Renderer::code(),
+                },
+            ),
+            ViewFamily::Text => (Lexer::text_lexer(&path, gs), Renderer::text()),
+            ViewFamily::Markdown => (Lexer::text_lexer(&path, gs), Renderer::markdown()),
+        };
+        if !is_code {
+            calc_wraps(&mut content, cursor.text_width);
+        }
+        Self {
             cursor,
             line_number_offset,
-            lexer: Lexer::text_lexer(&path, gs),
+            lexer,
             content,
-            renderer: Renderer::markdown(),
-            actions: Actions::new(cfg.default_indent_cfg()),
-            file_type: FileType::Ignored,
+            renderer,
+            actions: Actions::new(indent_cfg)
+                .with_limit(cfg.history_limit())
+                .with_group_timeout(cfg.undo_group_timeout()),
+            file_type,
             display,
             update_status: FileUpdate::None,
             path,
             last_render_at_line: None,
-        })
+            bracket_match: None,
+            ephemeral: false,
+            folds: Folds::default(),
+            pinned: false,
+            paste_cycle: None,
+            line_move: None,
+            register: None,
+            snippet: None,
+            loader: None,
+            parsing: None,
+            pending_jump: None,
+            wrap_column,
+            comment_column: cfg.comment_column,
+            sticky_scope,
+            scrollbar,
+            annotations: FileAnnotations::default(),
+            highlight_trailing_whitespace: cfg.highlight_trailing_whitespace,
+            show_inlay_hints: cfg.show_inlay_hints,
+            format_on_save: cfg.format_on_save,
+            delete_to_clipboard: cfg.delete_to_clipboard,
+            indent_override: None,
+            formatter: match is_code {
+                true => cfg.formatter(&file_type),
+                false => None,
+            },
+            external_format: None,
+            trim_trailing_whitespace: cfg.trim_trailing_whitespace_on_save,
+            insert_final_newline: is_code && cfg.insert_final_newline,
+            line_numbers: settings.line_numbers,
+            relative_line_numbers: settings.relative_line_numbers,
+            highlight_current_line: settings.highlight_current_line,
+            render_whitespace: settings.render_whitespace,
+            rulers: settings.rulers,
+            view_family,
+            view,
+            view_overrides: ViewOverrides::default(),
+            attribute_elevation: cfg.attribute_elevation.clone(),
+            persist_undo: cfg.persist_undo,
+            numbered_line: 0,
+            render_check: RenderCheck::new(cfg.render_check_frames),
+            status: EditorStatus::default(),
+            status_key: None,
+            saved_hash,
+        }
+    }
+
+    fn with_stored_history(mut self) -> Self {
+        if self.persist_undo {
+            self.actions.restore_history(&self.path, self.saved_hash);
+        }
+        self
     }
 
     #[inline]
     pub fn render(&mut self, gs: &mut GlobalState) {
-        let new_offset = if self.content.is_empty() { 1 } else { (self.content.len().ilog10() + 1) as usize };
-        if new_offset != self.line_number_offset {
-            self.line_number_offset = new_offset;
-            self.last_render_at_line.take();
-        };
-        (self.renderer.render)(self, gs);
+        self.load_to_view(gs);
+        self.sync_gutter();
+        self.annotations.sync(&mut self.content, self.actions.revision());
+        self.sync_folds();
+        match self.render_check.take() {
+            Some(mut check) => {
+                check.render(self.renderer, self, gs);
+                self.render_check = Some(check);
+            }
+            None => decorated(self, gs, self.renderer.render, true),
+        }
     }
 
     /// renders only updated lines
     #[inline]
     pub fn fast_render(&mut self, gs: &mut GlobalState) {
-        let new_offset = if self.content.is_empty() { 1 } else { (self.content.len().ilog10() + 1) as usize };
+        self.load_to_view(gs);
+        self.sync_gutter();
+        self.annotations.sync(&mut self.content, self.actions.revision());
+        self.sync_folds();
+        match self.render_check.take() {
+            Some(mut check) => {
+                check.fast_render(self.renderer, self, gs);
+                self.render_check = Some(check);
+            }
+            None => decorated(self, gs, self.renderer.fast_render, false),
+        }
+    }
+
+    /// drops the rendered lines - the next render draws all of them
+    pub fn invalidate_render(&mut self, reason: &'static str) {
+        self.last_render_at_line = None;
+        if let Some(check) = self.render_check.as_mut() {
+            check.invalidated(reason);
+        }
+    }
+
+    /// loads lines of partially loaded file up to the view and the lines ahead of it
+    fn load_to_view(&mut self, gs: &mut GlobalState) {
+        let Some(loader) = self.loader.as_mut().filter(|loader| loader.error().is_none()) else {
+            return;
+        };
+        let wanted = self.folds.line_at_row(self.cursor.at_line, self.cursor.max_rows) + LOAD_AHEAD;
+        let Some(count) = wanted.checked_sub(self.content.len()).filter(|count| *count != 0) else {
+            return;
+        };
+        let from = self.content.len();
+        let result = loader.load(&mut self.content, count);
+        calc_wraps(&mut self.content[from..], self.cursor.text_width);
+        match result {
+            Ok(true) => self.loaded(),
+            // nothing is edited before the file is loaded - the loaded lines match the file
+            Ok(false) => self.saved_hash = content_hash(&self.content),
+            Err(error) => gs.error(format!("Failed to load {}: {error}", self.display)),
+        }
+    }
+
+    /// loads the rest of the file, false if it failed - editing is not possible
+    fn finish_loading(&mut self, gs: &mut GlobalState) -> bool {
+        let Some(loader) = self.loader.as_mut() else {
+            return true;
+        };
+        let from = self.content.len();
+        let result = loader.load_all(&mut self.content);
+        calc_wraps(&mut self.content[from..], self.cursor.text_width);
+        match result {
+            Ok(()) => {
+                self.loaded();
+                true
+            }
+            Err(error) => {
+                gs.error(format!("File is not fully loaded ({error}), editing is disabled"));
+                false
+            }
+        }
+    }
+
+    fn loaded(&mut self) {
+        self.loader = None;
+        self.saved_hash = content_hash(&self.content);
+    }
+
+    /// content is a placeholder - the file is parsed in the background
+    #[inline]
+    pub fn is_loading(&self) -> bool {
+        self.parsing.is_some()
+    }
+
+    /// lines parsed in the background, None while parsing (or if the editor is not loading)
+    pub fn take_parsed(&mut self) -> Option<Result<Vec<EditorLine>, String>> {
+        self.parsing.as_ref()?.lock().ok()?.take()
+    }
+
+    /// replaces the placeholder with the parsed lines, stored undo history is restored against them
+    pub fn set_parsed(&mut self, content: Vec<EditorLine>, cfg: &EditorConfigs) {
+        self.parsing = None;
+        self.actions.cfg = cfg.with_detected_indent(cfg.get_indent_cfg(&self.file_type), &content);
+        self.content = content;
+        self.saved_hash = content_hash(&self.content);
+        self.cursor.select_drop();
+        self.cursor.set_position(CursorPosition::default());
+        self.cursor.at_line = 0;
+        if self.persist_undo {
+            self.actions.restore_history(&self.path, self.saved_hash);
+        }
+        self.invalidate_render("parsed");
+    }
+
+    /// places the cursor requested while the file was parsed, false if the anchor does not resolve
+    pub fn apply_pending_jump(&mut self) -> bool {
+        match self.pending_jump.take() {
+            None => true,
+            Some(PendingJump::Line(line)) => {
+                self.go_to(line);
+                true
+            }
+            Some(PendingJump::Position(position)) => {
+                self.go_to_position(position);
+                true
+            }
+            Some(PendingJump::Select(from, to)) => {
+                self.go_to_select(from, to);
+                true
+            }
+            Some(PendingJump::Anchor(anchor)) => self.go_to_anchor(anchor),
+        }
+    }
+
+    /// drops the rendered lines if the gutter changed - width or (relative numbers) the cursor line
+    #[inline]
+    fn sync_gutter(&mut self) {
+        let new_offset = calc_line_number_offset(self.content.len(), self.line_numbers);
         if new_offset != self.line_number_offset {
+            self.cursor.text_width = (self.cursor.text_width + self.line_number_offset).saturating_sub(new_offset);
             self.line_number_offset = new_offset;
-            self.last_render_at_line.take();
+            self.invalidate_render("line number width");
         };
-        (self.renderer.fast_render)(self, gs)
+        if self.relative_line_numbers && self.numbered_line != self.cursor.line {
+            self.numbered_line = self.cursor.line;
+            self.invalidate_render("relative numbers");
+        }
+    }
+
+    /// folds follow added or removed lines, the cursor placed within folded block (jump, search) opens it
+    #[inline]
+    fn sync_folds(&mut self) {
+        if self.folds.is_empty() {
+            return;
+        }
+        if self.folds.sync(&mut self.content, self.actions.revision())
+            | self.folds.reveal(self.cursor.line, &mut self.content)
+        {
+            self.invalidate_render("folds");
+        }
     }
 
     #[inline(always)]
     pub fn clear_screen_cache(&mut self, gs: &mut GlobalState) {
         self.lexer.refresh_lsp(gs);
-        self.last_render_at_line = None;
+        self.invalidate_render("screen cache cleared");
     }
 
     #[inline]
+    /// modal created outside of the lexer (peek into other file)
+    pub fn set_modal(&mut self, modal: LSPModal, gs: &GlobalState) {
+        if let Some(rect) = self.lexer.replace_modal(modal) {
+            self.updated_rect(rect, gs);
+        }
+    }
+
     pub fn updated_rect(&mut self, rect: Rect, gs: &GlobalState) {
-        let skip_offset = rect.row.saturating_sub(gs.editor_area.row) as usize;
+        if let Some(scrollbar) = self.scrollbar.as_mut() {
+            scrollbar.clear_cache();
+        }
+        let mut skip_offset = rect.row.saturating_sub(gs.editor_area.row) as usize;
+        if self.sticky_scope {
+            match skip_offset.checked_sub(1) {
+                Some(offset) => skip_offset = offset,
+                // header is drawn again with the full render
+                None => self.invalidate_render("scope header"),
+            }
+        }
+        if let Some(check) = self.render_check.as_mut() {
+            check.invalidated("updated rect");
+        }
         for line in self.content.iter_mut().skip(self.cursor.at_line + skip_offset).take(rect.width) {
             line.clear_cache();
         }
@@ -150,8 +500,179 @@ impl Editor {
         self.lexer.update_path(&self.path)
     }
 
+    /// line, char and word (whitespace separated) counts of the select, None without select
     #[inline]
+    pub fn selection_stats(&self) -> Option<SelectionStats> {
+        self.cursor.select_stats(&self.content)
+    }
+
+    /// indent used by the editor (detected or set), shown in the status bar
+    pub fn indent_label(&self) -> IndentLabel {
+        self.actions.cfg.indent_label()
+    }
+
+    /// footer status is rebuilt if changed since the last call
+    pub fn refresh_status(&mut self) {
+        let file_type = match (self.file_type, self.view_family) {
+            (FileType::Ignored, ViewFamily::Markdown) => "markdown",
+            (FileType::Ignored, _) => "text",
+            (file_type, _) => file_type.into(),
+        };
+        let len = self.content.len();
+        let cursor = CursorPosition::from(&self.cursor);
+        let select = self.cursor.select_get();
+        let lsp = self.lexer.lsp_status();
+        let revision = self.actions.revision();
+        let status = &self.status;
+        let current = self.status_key.as_ref().is_some_and(|key| {
+            key.revision == revision
+                && key.select == select
+                && key.indent == self.actions.cfg.indent_label()
+                && status.len == len
+                && status.cursor == cursor
+                && status.file_type == file_type
+                && status.lsp == lsp
+        });
+        if current {
+            return;
+        }
+        self.status_key = Some(StatusKey { revision, select, indent: self.indent_label() });
+        self.status = EditorStatus {
+            len,
+            cursor,
+            select: self.cursor.select_stats(&self.content),
+            indent: self.indent_label(),
+            file_type,
+            lsp,
+        };
+    }
+
+    /// overrides the indent of the editor ("\t" or spaces), detection and config reloads do not change it
+    pub fn set_indent(&mut self, indent: String) {
+        self.actions.cfg.indent.clone_from(&indent);
+        self.indent_override = Some(indent);
+    }
+
+    /// view settings in effect - toggles of the buffer over the configs
+    pub fn view_settings(&self) -> ViewSettings {
+        self.view.resolve(&self.view_overrides)
+    }
+
+    /// view settings with the scope (buffer, file type, family or global) each comes from
+    pub fn view_report(&self) -> String {
+        self.view.report(&self.view_overrides)
+    }
+
+    /// view toggles of the buffer
+    pub fn view_overrides(&self) -> &ViewOverrides {
+        &self.view_overrides
+    }
+
+    pub fn set_view_overrides(&mut self, overrides: ViewOverrides) {
+        self.view_overrides = overrides;
+        self.apply_view();
+    }
+
+    /// drops the toggles of the buffer
+    pub fn reset_view(&mut self) {
+        self.view_overrides = ViewOverrides::default();
+        self.apply_view();
+    }
+
+    /// soft wrap of code, text and markdown are always wrapped
+    pub fn toggle_wrap(&mut self) {
+        if self.view_family == ViewFamily::Code {
+            self.view_overrides.wrap = Some(!self.view_settings().wrap);
+            self.apply_view();
+        }
+    }
+
+    pub fn toggle_line_numbers(&mut self) {
+        self.view_overrides.line_numbers = Some(!self.view_settings().line_numbers);
+        self.apply_view();
+    }
+
+    pub fn toggle_relative_line_numbers(&mut self) {
+        self.view_overrides.relative_line_numbers = Some(!self.view_settings().relative_line_numbers);
+        self.apply_view();
+    }
+
+    pub fn toggle_current_line_highlight(&mut self) {
+        self.view_overrides.highlight_current_line = Some(!self.view_settings().highlight_current_line);
+        self.apply_view();
+    }
+
+    /// none -> trailing -> all
+    pub fn cycle_render_whitespace(&mut self) {
+        self.view_overrides.render_whitespace = Some(match self.view_settings().render_whitespace {
+            RenderWhitespace::None => RenderWhitespace::Trailing,
+            RenderWhitespace::Trailing => RenderWhitespace::All,
+            RenderWhitespace::All => RenderWhitespace::None,
+        });
+        self.apply_view();
+    }
+
+    fn apply_view(&mut self) {
+        let settings = self.view_settings();
+        if self.view_family == ViewFamily::Code {
+            if settings.wrap {
+                self.folds.unfold_all(&mut self.content);
+            }
+            self.renderer = match settings.wrap {
+                true => Renderer::code_wrap(),
+                false => Renderer::code(),
+            };
+        }
+        self.line_numbers = settings.line_numbers;
+        self.relative_line_numbers = settings.relative_line_numbers;
+        self.highlight_current_line = settings.highlight_current_line;
+        self.render_whitespace = settings.render_whitespace;
+        self.rulers = settings.rulers;
+        self.sync_gutter();
+        self.invalidate_render("view settings");
+    }
+
+    /// shows the file as opened from another path leading to the same file on disk
+    pub fn set_display_path(&mut self, path: &Path) {
+        self.display = build_display(path);
+    }
+
     pub fn map(&mut self, action: EditorAction, gs: &mut GlobalState) -> bool {
+        if self.is_loading() {
+            return !matches!(action, EditorAction::Close | EditorAction::FocusOtherSplit);
+        }
+        if self.loader.is_some() {
+            match is_view_action(action) {
+                true => self.load_to_view(gs),
+                false if !self.finish_loading(gs) => return true,
+                false => (),
+            }
+        }
+        let line = self.cursor.line;
+        let snippet_watch = self.snippet.as_ref().map(|snippet| snippet.watch(&self.content));
+        let taken = self.map_action(action, gs);
+        if let (Some(watch), Some(snippet)) = (snippet_watch, self.snippet.as_mut()) {
+            if !snippet.track(watch, (&self.cursor).into(), &self.content) {
+                self.snippet = None;
+            }
+        }
+        if !self.folds.is_empty() {
+            self.step_over_folds(line, action);
+        }
+        if self.actions.take_truncation_warning() {
+            let (entries, bytes) = self.actions.history_stats();
+            gs.message(format!(
+                "Undo history limit reached - oldest edits dropped (keeping {entries} edits, ~{} KB)",
+                bytes / 1024
+            ));
+        }
+        taken
+    }
+
+    #[inline]
+    fn map_action(&mut self, action: EditorAction, gs: &mut GlobalState) -> bool {
+        // any action other than paste/cycle paste ends the cycle
+        let paste_cycle = self.paste_cycle.take();
         let (taken, render_update) = self.lexer.map_modal_if_exists(action, gs);
         if let Some(modal_rect) = render_update {
             self.updated_rect(modal_rect, gs);
@@ -159,11 +680,32 @@ impl Editor {
         if taken {
             return true;
         };
+        if let Some(line_move) = self.line_move.take() {
+            self.map_line_move(line_move, action, gs);
+            return true;
+        }
+        if let Some(register) = self.register.take() {
+            if self.map_register(register, action, gs) {
+                return true;
+            }
+        }
+        if let Some(snippet) = self.snippet.take() {
+            if self.map_snippet(snippet, action) {
+                return true;
+            }
+        }
+        if !self.cursor.block_is_none() && self.map_column_select(action, gs) {
+            self.actions.push_buffer(&mut self.content, &mut self.lexer);
+            return true;
+        }
         match action {
             EditorAction::Char(ch) => {
                 self.actions.push_char(ch, &mut self.cursor, &mut self.content, &mut self.lexer);
                 let line = &self.content[self.cursor.line];
-                if self.lexer.should_autocomplete(self.cursor.char, line) {
+                if self.lexer.is_signature_trigger(ch) {
+                    self.actions.push_buffer(&mut self.content, &mut self.lexer);
+                    self.lexer.signature_help((&self.cursor).into(), gs);
+                } else if self.lexer.should_autocomplete(self.cursor.char, line) {
                     let line = line.to_string();
                     self.actions.push_buffer(&mut self.content, &mut self.lexer);
                     self.lexer.get_autocomplete((&self.cursor).into(), line, gs);
@@ -172,14 +714,49 @@ impl Editor {
             }
             EditorAction::NewLine => self.actions.new_line(&mut self.cursor, &mut self.content, &mut self.lexer),
             EditorAction::Indent => self.actions.indent(&mut self.cursor, &mut self.content, &mut self.lexer),
-            EditorAction::Backspace => self.actions.backspace(&mut self.cursor, &mut self.content, &mut self.lexer),
-            EditorAction::Delete => self.actions.del(&mut self.cursor, &mut self.content, &mut self.lexer),
+            // deletions stay in the buffer like typed chars - grouped until other action
+            EditorAction::Backspace => {
+                self.actions.backspace(&mut self.cursor, &mut self.content, &mut self.lexer);
+                return true;
+            }
+            EditorAction::Delete => {
+                self.actions.del(&mut self.cursor, &mut self.content, &mut self.lexer);
+                return true;
+            }
             EditorAction::RemoveLine => {
                 self.select_line();
                 if !self.cursor.select_is_none() {
                     self.actions.del(&mut self.cursor, &mut self.content, &mut self.lexer);
                 };
             }
+            EditorAction::DeleteLine => self.actions.delete_line(&mut self.cursor, &mut self.content, &mut self.lexer),
+            EditorAction::DeleteToEndOfLine => {
+                if let Some(removed) =
+                    self.actions.delete_to_end_of_line(&mut self.cursor, &mut self.content, &mut self.lexer)
+                {
+                    if self.delete_to_clipboard {
+                        gs.clipboard_copy(removed);
+                    }
+                }
+            }
+            EditorAction::DeleteToStartOfLine => {
+                if let Some(removed) =
+                    self.actions.delete_to_start_of_line(&mut self.cursor, &mut self.content, &mut self.lexer)
+                {
+                    if self.delete_to_clipboard {
+                        gs.clipboard_copy(removed);
+                    }
+                }
+            }
+            EditorAction::ReflowParagraph => self.reflow_paragraph(),
+            EditorAction::RepeatLast => self.repeat_last(),
+            EditorAction::GoToMatchingBracket => self.goto_matching_bracket(),
+            EditorAction::Fold => self.fold_at_cursor(gs),
+            EditorAction::Unfold => self.unfold_at_cursor(gs),
+            EditorAction::FoldAll => match self.folds.is_empty() {
+                true => self.fold_all(gs),
+                false => self.unfold_all(),
+            },
             EditorAction::IndentStart => {
                 self.actions.indent_start(&mut self.cursor, &mut self.content, &mut self.lexer)
             }
@@ -207,6 +784,18 @@ impl Editor {
             EditorAction::ScrollDown => self.cursor.scroll_down(&self.content),
             EditorAction::SwapUp => self.actions.swap_up(&mut self.cursor, &mut self.content, &mut self.lexer),
             EditorAction::SwapDown => self.actions.swap_down(&mut self.cursor, &mut self.content, &mut self.lexer),
+            EditorAction::MoveLineMode => {
+                let lines = match self.cursor.select_take() {
+                    // select ending at line start does not include the line
+                    Some((from, to)) if to.char == 0 && to.line > from.line => from.line..to.line,
+                    Some((from, to)) => from.line..to.line + 1,
+                    None => self.cursor.line..self.cursor.line + 1,
+                };
+                self.line_move = Some(LineMove::new(lines));
+                gs.message(MOVE_LINE_HINT);
+            }
+            EditorAction::ColumnSelectUp => self.cursor.block_vertical(false, &self.content),
+            EditorAction::ColumnSelectDown => self.cursor.block_vertical(true, &self.content),
             EditorAction::JumpLeft => self.cursor.jump_left(&self.content),
             EditorAction::JumpLeftSelect => self.cursor.jump_left_select(&self.content),
             EditorAction::JumpRight => self.cursor.jump_right(&self.content),
@@ -214,15 +803,29 @@ impl Editor {
             EditorAction::EndOfLine => self.cursor.end_of_line(&self.content),
             EditorAction::EndOfFile => self.cursor.end_of_file(&self.content),
             EditorAction::StartOfLine => self.cursor.start_of_line(&self.content),
+            EditorAction::SelectStartOfLine => self.cursor.select_start_of_line(&self.content),
             EditorAction::StartOfFile => self.cursor.start_of_file(),
             EditorAction::FindReferences => self.lexer.go_to_reference((&self.cursor).into(), gs),
             EditorAction::GoToDeclaration => self.lexer.go_to_declaration((&self.cursor).into(), gs),
+            EditorAction::PeekDefinition => self.lexer.peek_definition((&self.cursor).into(), gs),
+            EditorAction::Outline => self.lexer.show_outline(&self.content, gs),
             EditorAction::Help => self.lexer.help((&self.cursor).into(), &self.content, gs),
             EditorAction::LSPRename => {
                 let line = &self.content[self.cursor.line];
                 let token_range = token_range_at(line, self.cursor.char);
                 self.lexer.start_rename((&self.cursor).into(), &line[token_range]);
             }
+            EditorAction::Format if self.formatter.is_some() => self.format_external(false, gs),
+            EditorAction::Format => {
+                let insert_spaces = self.actions.cfg.indent != "\t";
+                self.lexer.format(self.cursor.select_get(), self.indent_width(), insert_spaces, gs);
+            }
+            EditorAction::CodeAction => {
+                self.lexer.code_action(self.cursor.select_get(), (&self.cursor).into(), &self.content, false, gs)
+            }
+            EditorAction::QuickFix => {
+                self.lexer.code_action(self.cursor.select_get(), (&self.cursor).into(), &self.content, true, gs)
+            }
             EditorAction::RefreshUI => self.lexer.refresh_lsp(gs),
             EditorAction::CommentOut => self.actions.comment_out(
                 self.file_type.comment_start(),
@@ -230,37 +833,296 @@ impl Editor {
                 &mut self.content,
                 &mut self.lexer,
             ),
-            EditorAction::Undo => self.actions.undo(&mut self.cursor, &mut self.content, &mut self.lexer),
+            EditorAction::Undo => {
+                self.actions.undo(&mut self.cursor, &mut self.content, &mut self.lexer);
+                self.lexer.completion_undone(self.actions.done_len());
+            }
             EditorAction::Redo => self.actions.redo(&mut self.cursor, &mut self.content, &mut self.lexer),
             EditorAction::Save => self.save(gs),
+            EditorAction::RevertBuffer => match self.is_saved() {
+                true => gs.message("No changes to revert!"),
+                false => gs.popup(revert_changes(self.path.clone())),
+            },
             EditorAction::Cancel => {
                 if self.cursor.select_take().is_none() {
                     self.actions.push_buffer(&mut self.content, &mut self.lexer);
                     return false;
                 }
             }
-            EditorAction::Paste => {
-                if let Some(clip) = gs.clipboard.pull() {
-                    self.actions.paste(clip, &mut self.cursor, &mut self.content, &mut self.lexer);
+            EditorAction::Paste => self.paste(None, gs),
+            EditorAction::PasteReindent => {
+                if let Some(clip) = gs.clipboard_paste() {
+                    self.actions.paste_reindent(clip, &mut self.cursor, &mut self.content, &mut self.lexer);
                 }
             }
+            EditorAction::CyclePaste => self.cycle_paste(paste_cycle, gs),
+            EditorAction::Register => {
+                self.register = Some(Register::Pending);
+                gs.message(REGISTER_HINT);
+            }
             EditorAction::Cut => {
                 if let Some(clip) = self.cut() {
-                    gs.clipboard.push(clip);
+                    gs.clipboard_copy(clip);
                 }
             }
             EditorAction::Copy => {
                 if let Some(clip) = self.copy() {
-                    gs.clipboard.push(clip);
+                    gs.clipboard_copy(clip);
+                }
+            }
+            EditorAction::Close | EditorAction::FocusOtherSplit => return false,
+        }
+        self.actions.push_buffer(&mut self.content, &mut self.lexer);
+        true
+    }
+
+    /// vertical moves step over folded blocks (as over the single rows they take), other moves into them open them
+    fn step_over_folds(&mut self, from: usize, action: EditorAction) {
+        if self.folds.sync(&mut self.content, self.actions.revision()) {
+            self.invalidate_render("folds");
+        }
+        let vertical = matches!(
+            action,
+            EditorAction::Up
+                | EditorAction::Down
+                | EditorAction::SelectUp
+                | EditorAction::SelectDown
+                | EditorAction::ScrollUp
+                | EditorAction::ScrollDown
+        );
+        if let Some((header, end)) = self.folds.hiding_block(self.cursor.at_line).filter(|_| vertical) {
+            let scrolled_down = matches!(action, EditorAction::ScrollDown) && end + 1 < self.content.len();
+            self.cursor.at_line = if scrolled_down { end + 1 } else { header };
+        }
+        let Some((header, end)) = self.folds.hiding_block(self.cursor.line) else {
+            return;
+        };
+        if !vertical {
+            self.folds.reveal(self.cursor.line, &mut self.content);
+            return self.invalidate_render("folds");
+        }
+        let line = match self.cursor.line > from && end + 1 < self.content.len() {
+            true => end + 1,
+            false => header,
+        };
+        self.cursor.line = line;
+        self.cursor.adjust_char(&self.content[line]);
+        self.cursor.push_to_select();
+    }
+
+    fn map_line_move(&mut self, mut line_move: LineMove, action: EditorAction, gs: &mut GlobalState) {
+        let down = match action {
+            EditorAction::Up | EditorAction::SwapUp => false,
+            EditorAction::Down | EditorAction::SwapDown => true,
+            EditorAction::NewLine => return self.commit_line_move(line_move),
+            EditorAction::Cancel => {
+                self.actions.revert_preview(line_move.steps, &mut self.content, &mut self.lexer);
+                self.cursor.line = self.cursor.line + line_move.origin.start - line_move.lines.start;
+                self.cursor.adjust_char(&self.content[self.cursor.line]);
+                return;
+            }
+            _ => {
+                gs.message(MOVE_LINE_HINT);
+                self.line_move = Some(line_move);
+                return;
+            }
+        };
+        if let Some(step) = self.actions.move_lines(line_move.lines.clone(), down, &mut self.content, &mut self.lexer) {
+            line_move.moved(down, step);
+            match down {
+                true => self.cursor.line += 1,
+                false => self.cursor.line -= 1,
+            }
+            self.cursor.adjust_char(&self.content[self.cursor.line]);
+        }
+        self.line_move = Some(line_move);
+    }
+
+    /// named copy, cut and paste - other actions (and chars that are not a name) drop the prefix
+    /// and are mapped as usual
+    fn map_register(&mut self, register: Register, action: EditorAction, gs: &mut GlobalState) -> bool {
+        match (register, action) {
+            (Register::Pending, EditorAction::Char(name)) if name.is_ascii_alphanumeric() => {
+                self.register = Some(Register::Named(name));
+                gs.message(format!("Register '{name}': copy, cut or paste"));
+            }
+            (Register::Named(name), EditorAction::Copy) => {
+                if let Some(clip) = self.copy() {
+                    gs.clipboard.set_register(name, clip);
+                    gs.success(format!("Copied to register '{name}'"));
                 }
             }
-            EditorAction::Close => return false,
+            (Register::Named(name), EditorAction::Cut) => {
+                if let Some(clip) = self.cut() {
+                    gs.clipboard.set_register(name, clip);
+                    gs.success(format!("Cut to register '{name}'"));
+                }
+            }
+            (Register::Named(name), EditorAction::Paste) => self.paste(Some(name), gs),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Tab/Shift-Tab move between the snippet stops, snippet mode ends on the last one
+    fn map_snippet(&mut self, mut snippet: SnippetStops, action: EditorAction) -> bool {
+        let stop = match action {
+            EditorAction::Indent => snippet.next(),
+            EditorAction::Unintent => snippet.prev(),
+            _ => {
+                self.snippet = Some(snippet);
+                return false;
+            }
+        };
+        if let Some((from, to)) = stop {
+            self.cursor.select_drop();
+            match from == to {
+                true => self.cursor.set_position(from),
+                false => self.cursor.select_set(from, to),
+            }
+        }
+        if !snippet.is_last() {
+            self.snippet = Some(snippet);
+        }
+        true
+    }
+
+    /// replaces the preview steps with single edit over the changed region
+    fn commit_line_move(&mut self, line_move: LineMove) {
+        if line_move.steps.is_empty() {
+            return;
+        }
+        let region = line_move.region();
+        let text = self.content[region.clone()].iter().map(|line| line.to_string()).collect::<Vec<_>>().join("\n");
+        let position = CursorPosition::from(&self.cursor);
+        self.actions.revert_preview(line_move.steps, &mut self.content, &mut self.lexer);
+        let from = CursorPosition { line: region.start, char: 0 };
+        let to = CursorPosition { line: region.end - 1, char: self.content[region.end - 1].char_len() };
+        self.actions.replace_select(from, to, text, &mut self.cursor, &mut self.content, &mut self.lexer);
+        self.cursor.set_position(position);
+    }
+
+    /// editing actions are applied on each line of the column select, any other action drops it
+    fn map_column_select(&mut self, action: EditorAction, gs: &mut GlobalState) -> bool {
+        let Some((lines, chars)) = self.cursor.block_get() else {
+            return false;
+        };
+        match action {
+            EditorAction::ColumnSelectUp | EditorAction::SelectUp => self.cursor.block_vertical(false, &self.content),
+            EditorAction::ColumnSelectDown | EditorAction::SelectDown => {
+                self.cursor.block_vertical(true, &self.content)
+            }
+            EditorAction::SelectLeft => self.cursor.block_horizontal(false, &self.content),
+            EditorAction::SelectRight => self.cursor.block_horizontal(true, &self.content),
+            EditorAction::Char(ch) => self.column_replace(lines, chars, ch.to_string()),
+            EditorAction::Backspace if chars.is_empty() => {
+                if chars.start != 0 {
+                    self.column_replace(lines, chars.start - 1..chars.start, String::new());
+                }
+            }
+            EditorAction::Delete if chars.is_empty() => {
+                self.column_replace(lines, chars.start..chars.start + 1, String::new())
+            }
+            EditorAction::Backspace | EditorAction::Delete => self.column_replace(lines, chars, String::new()),
+            EditorAction::Paste => match gs.clipboard_paste() {
+                Some(clip) if clip.contains('\n') => gs.message("Multi line clip can not be pasted in column select!"),
+                Some(clip) => self.column_replace(lines, chars, clip),
+                None => (),
+            },
+            EditorAction::Cancel => self.cursor.block_drop(),
+            _ => {
+                self.cursor.block_drop();
+                return false;
+            }
+        }
+        true
+    }
+
+    fn column_replace(&mut self, lines: Range<usize>, chars: Range<usize>, clip: String) {
+        let ranges = lines
+            .map(|line| {
+                let line_len = self.content[line].char_len();
+                let from = CursorPosition { line, char: chars.start.min(line_len) };
+                let to = CursorPosition { line, char: chars.end.min(line_len) };
+                (from, to)
+            })
+            .collect();
+        let char = chars.start + clip.chars().count();
+        self.actions.mass_replace(&mut self.cursor, ranges, clip, &mut self.content, &mut self.lexer);
+        self.cursor.block_set_char(char, &self.content);
+    }
+
+    fn cycle_paste(&mut self, paste_cycle: Option<(CursorPosition, usize)>, gs: &mut GlobalState) {
+        let Some((end, idx)) = paste_cycle else {
+            gs.message("Nothing to cycle - paste first!");
+            return;
+        };
+        if end != CursorPosition::from(&self.cursor) || !self.cursor.select_is_none() {
+            gs.message("Nothing to cycle - paste first!");
+            return;
+        }
+        let history_len = gs.clipboard.history_len();
+        let idx = (idx + 1) % history_len.max(1);
+        if let Some(clip) = gs.clipboard.history(idx).map(str::to_owned) {
+            self.actions.cycle_paste(clip, &mut self.cursor, &mut self.content, &mut self.lexer);
+        }
+        self.paste_cycle = Some(((&self.cursor).into(), idx));
+        gs.message(format!("Pasted clip {}/{history_len} from history", idx + 1));
+    }
+
+    /// folding is done by the code renderer without wrap only - wrapped line takes multiple rows
+    #[inline(always)]
+    fn foldable(&self, gs: &mut GlobalState) -> bool {
+        let foldable = self.view_family == ViewFamily::Code && !self.view_settings().wrap;
+        if !foldable {
+            gs.message("Folding is available only for code without wrap!");
+        }
+        foldable
+    }
+
+    /// folds the block opened on the cursor line, or the one enclosing it
+    pub fn fold_at_cursor(&mut self, gs: &mut GlobalState) {
+        if !self.foldable(gs) {
+            return;
+        }
+        let Some(header) = self.folds.fold(self.cursor.line, &mut self.content) else {
+            gs.message("No block to fold!");
+            return;
+        };
+        self.cursor.select_drop();
+        if header != self.cursor.line {
+            let char = self.cursor.char.min(self.content[header].char_len());
+            self.cursor.set_position(CursorPosition { line: header, char });
+        }
+        self.invalidate_render("folds");
+    }
+
+    pub fn unfold_at_cursor(&mut self, gs: &mut GlobalState) {
+        match self.folds.unfold(self.cursor.line, &mut self.content) {
+            true => self.invalidate_render("folds"),
+            false => gs.message("No folded block on the line!"),
+        }
+    }
+
+    pub fn fold_all(&mut self, gs: &mut GlobalState) {
+        if !self.foldable(gs) {
+            return;
         }
-        self.actions.push_buffer(&mut self.content, &mut self.lexer);
-        true
+        let folded = self.folds.fold_all(&mut self.content);
+        self.cursor.select_drop();
+        if let Some((header, _)) = self.folds.hiding_block(self.cursor.line) {
+            let char = self.cursor.char.min(self.content[header].char_len());
+            self.cursor.set_position(CursorPosition { line: header, char });
+        }
+        gs.message(format!("Folded {folded} blocks"));
+        self.invalidate_render("folds");
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.folds.unfold_all(&mut self.content);
+        self.invalidate_render("folds");
     }
 
-    #[inline(always)]
     pub fn select_token(&mut self) {
         let range = token_range_at(&self.content[self.cursor.line], self.cursor.char);
         if !range.is_empty() {
@@ -286,6 +1148,211 @@ impl Editor {
         };
     }
 
+    /// jumps to the bracket matching the one under (or right before) the cursor
+    /// the scan is not limited to the screen, unlike the highlight
+    pub fn goto_matching_bracket(&mut self) {
+        let position = CursorPosition::from(&self.cursor);
+        if let Some(bracket) = find_matching_bracket(&self.content, position, self.content.len()) {
+            self.cursor.select_drop();
+            self.cursor.set_position(bracket);
+        }
+    }
+
+    /// applies the last repeatable edit again at the cursor
+    /// surround without select wraps the token under the cursor
+    pub fn repeat_last(&mut self) {
+        self.actions.push_buffer(&mut self.content, &mut self.lexer);
+        let Some(repeat) = self.actions.last_repeat().cloned() else {
+            return;
+        };
+        match repeat {
+            Repeat::Text(text) => {
+                for ch in text.chars() {
+                    self.actions.push_char(ch, &mut self.cursor, &mut self.content, &mut self.lexer);
+                }
+            }
+            Repeat::Paste(clip) => self.actions.paste(clip, &mut self.cursor, &mut self.content, &mut self.lexer),
+            Repeat::DeleteLine => self.actions.delete_line(&mut self.cursor, &mut self.content, &mut self.lexer),
+            Repeat::DeleteToEndOfLine => {
+                self.actions.delete_to_end_of_line(&mut self.cursor, &mut self.content, &mut self.lexer);
+            }
+            Repeat::DeleteToStartOfLine => {
+                self.actions.delete_to_start_of_line(&mut self.cursor, &mut self.content, &mut self.lexer);
+            }
+            Repeat::CommentOut => self.actions.comment_out(
+                self.file_type.comment_start(),
+                &mut self.cursor,
+                &mut self.content,
+                &mut self.lexer,
+            ),
+            Repeat::Indent => self.actions.indent(&mut self.cursor, &mut self.content, &mut self.lexer),
+            Repeat::IndentStart => self.actions.indent_start(&mut self.cursor, &mut self.content, &mut self.lexer),
+            Repeat::Unindent => self.actions.unindent(&mut self.cursor, &mut self.content, &mut self.lexer),
+            Repeat::NewLine => self.actions.new_line(&mut self.cursor, &mut self.content, &mut self.lexer),
+            Repeat::Surround(ch) => {
+                if self.cursor.select_is_none() {
+                    let range = token_range_at(&self.content[self.cursor.line], self.cursor.char);
+                    if range.is_empty() {
+                        return;
+                    }
+                    self.cursor.select_set(
+                        CursorPosition { line: self.cursor.line, char: range.start },
+                        CursorPosition { line: self.cursor.line, char: range.end },
+                    );
+                }
+                self.actions.push_char(ch, &mut self.cursor, &mut self.content, &mut self.lexer);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn transform_case(&mut self, mode: CaseMode) {
+        self.actions.transform_case(mode, &mut self.cursor, &mut self.content, &mut self.lexer);
+    }
+
+    /// rewraps the paragraph under the cursor (or the selected lines) to the wrap column
+    pub fn reflow_paragraph(&mut self) {
+        let select = self.cursor.select_get().map(|(from, to)| match to.char == 0 && to.line > from.line {
+            true => from.line..to.line,
+            false => from.line..to.line + 1,
+        });
+        let comment = match self.file_type {
+            FileType::Ignored => None,
+            _ => Some(self.file_type.comment_start()),
+        };
+        let position = CursorPosition::from(&self.cursor);
+        let Some(result) = reflow::reflow(&self.content, select, position, self.wrap_column, comment) else {
+            return;
+        };
+        let from = CursorPosition { line: result.lines.start, char: 0 };
+        let last = result.lines.end - 1;
+        let to = CursorPosition { line: last, char: self.content[last].char_len() };
+        self.actions.replace_select(from, to, result.text, &mut self.cursor, &mut self.content, &mut self.lexer);
+        self.cursor.set_position(result.cursor);
+    }
+
+    /// aligns trailing comments in the selected lines or in the block around the cursor as single edit
+    /// returns the number of aligned and skipped (code past the column) lines, no edit is made if none is aligned
+    pub fn align_comments(&mut self) -> Option<(usize, usize)> {
+        if self.file_type == FileType::Ignored {
+            return None;
+        }
+        let select = self.cursor.select_get().map(|(from, to)| match to.char == 0 && to.line > from.line {
+            true => from.line..to.line,
+            false => from.line..to.line + 1,
+        });
+        let comment = self.file_type.comment_start();
+        let char_literals = self.file_type == FileType::Rust;
+        let result = align::align_comments(
+            &self.content,
+            select,
+            self.cursor.line,
+            comment,
+            char_literals,
+            self.comment_column,
+        )?;
+        if result.aligned == 0 {
+            return Some((0, result.skipped));
+        }
+        let position = CursorPosition::from(&self.cursor);
+        let from = CursorPosition { line: result.lines.start, char: 0 };
+        let last = result.lines.end - 1;
+        let to = CursorPosition { line: last, char: self.content[last].char_len() };
+        self.actions.replace_select(from, to, result.text, &mut self.cursor, &mut self.content, &mut self.lexer);
+        let char = position.char.min(self.content[position.line].char_len());
+        self.cursor.set_position(CursorPosition { line: position.line, char });
+        Some((result.aligned, result.skipped))
+    }
+
+    /// removes consecutive duplicate lines touched by the select keeping the first occurrence
+    pub fn dedupe_selection(&mut self) {
+        let Some((from, to)) = self.cursor.select_get() else {
+            return;
+        };
+        // select ending at the start of a line (select line) does not include it
+        let end = if to.char == 0 && to.line > from.line { to.line } else { to.line + 1 };
+        let lines = &self.content[from.line..end];
+        let mut unique: Vec<&EditorLine> = Vec::with_capacity(lines.len());
+        for line in lines {
+            if unique.last().map(|last| last.content != line.content).unwrap_or(true) {
+                unique.push(line);
+            }
+        }
+        if unique.len() == lines.len() {
+            return;
+        }
+        let text = unique.iter().map(|line| line.content.as_str()).collect::<Vec<_>>().join("\n");
+        let from = CursorPosition { line: from.line, char: 0 };
+        let to = CursorPosition { line: end - 1, char: self.content[end - 1].char_len() };
+        self.actions.replace_select(from, to, text, &mut self.cursor, &mut self.content, &mut self.lexer);
+    }
+
+    /// rewrites the indent of the selected lines (whole file without select) as spaces or tabs in single edit
+    /// returns the number of changed lines
+    pub fn retab(&mut self, to_spaces: bool, width: usize) -> usize {
+        let lines = match self.cursor.select_get() {
+            Some((from, to)) if to.char == 0 && to.line > from.line => from.line..to.line,
+            Some((from, to)) => from.line..to.line + 1,
+            None => 0..self.content.len(),
+        };
+        let edits = retab_edits(&self.content, lines, to_spaces, width);
+        let changed = edits.len();
+        if changed == 0 {
+            return 0;
+        }
+        // positions past the indent keep their distance from its end
+        let indents = edits
+            .iter()
+            .map(|edit| (edit.range.start.line as usize, (edit.range.end.character as usize, edit.new_text.len())))
+            .collect::<HashMap<_, _>>();
+        let remap = |position: CursorPosition| match indents.get(&position.line) {
+            Some((old, new)) if position.char >= *old => CursorPosition { char: position.char - old + new, ..position },
+            Some((_, new)) => CursorPosition { char: position.char.min(*new), ..position },
+            None => position,
+        };
+        let position = CursorPosition::from(&self.cursor);
+        let select = self.cursor.select_get();
+        self.apply_file_edits(edits);
+        match select {
+            Some((from, to)) if from == position => self.cursor.select_set(remap(to), remap(from)),
+            Some((from, to)) => self.cursor.select_set(remap(from), remap(to)),
+            None => self.cursor.set_position(remap(position)),
+        }
+        changed
+    }
+
+    /// width of the indent, tabs are counted as the configured indent spaces
+    pub fn indent_width(&self) -> usize {
+        self.actions.cfg.indent_width()
+    }
+
+    /// cheap check against the content last loaded from or written to disk
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.saved_hash != content_hash(&self.content)
+    }
+
+    /// editor should be auto saved - always or if the last edit is older than the delay
+    /// editors with changes on disk are left to the user, the edit mark is taken (retried only after new edits)
+    pub fn take_auto_save(&mut self, delay: Option<Duration>) -> bool {
+        if !self.auto_save_due(delay) {
+            return false;
+        }
+        self.actions.clear_last_edit();
+        true
+    }
+
+    #[inline]
+    pub fn auto_save_due(&self, delay: Option<Duration>) -> bool {
+        if self.ephemeral || self.update_status.changed_on_disk() {
+            return false;
+        }
+        match delay {
+            Some(delay) => self.actions.last_edit().is_some_and(|edited_at| edited_at.elapsed() >= delay),
+            None => true,
+        }
+    }
+
     pub fn is_saved(&self) -> bool {
         if let Ok(file_content) = std::fs::read_to_string(&self.path) {
             return self
@@ -310,11 +1377,14 @@ impl Editor {
     #[inline(always)]
     pub fn replace_token(&mut self, new: String) {
         self.actions.replace_token(new, &mut self.cursor, &mut self.content, &mut self.lexer);
+        self.lexer.completion_inserted(self.actions.done_len() - 1);
     }
 
     #[inline(always)]
-    pub fn insert_snippet(&mut self, snippet: String, cursor_offset: Option<(usize, usize)>) {
-        self.actions.insert_snippet(&mut self.cursor, snippet, cursor_offset, &mut self.content, &mut self.lexer);
+    pub fn insert_snippet(&mut self, snippet: Snippet) {
+        let stops = self.actions.insert_snippet(&mut self.cursor, snippet, &mut self.content, &mut self.lexer);
+        self.lexer.completion_inserted(self.actions.done_len() - 1);
+        self.snippet = SnippetStops::new(stops);
     }
 
     pub fn mass_replace(&mut self, mut ranges: Vec<(CursorPosition, CursorPosition)>, clip: String) {
@@ -336,25 +1406,111 @@ impl Editor {
             }
             line_ord
         });
-        self.actions.apply_edits(edits, &mut self.content, &mut self.lexer);
+        let line_changes = self.actions.apply_edits(edits, &mut self.content, &mut self.lexer);
+        self.shift_view(|line| line_changes.iter().fold(line, |line, change| change.shift_line(line)));
+    }
+
+    /// keeps the same content on screen and under the cursor after lines above them changed
+    /// lines that no longer exist are replaced by the nearest surviving one
+    fn shift_view(&mut self, shift: impl Fn(usize) -> usize) {
+        let Some(last) = self.content.len().checked_sub(1) else {
+            return;
+        };
+        let line = std::cmp::min(shift(self.cursor.line), last);
+        let at_line = std::cmp::min(shift(self.cursor.at_line), line);
+        if line != self.cursor.line {
+            self.cursor.select_drop();
+        }
+        let char = std::cmp::min(self.cursor.char, self.content[line].char_len());
+        self.cursor.set_position(CursorPosition { line, char });
+        self.cursor.at_line = at_line;
+    }
+
+    /// additional edits of the completion inserted last (imports) - single undo reverts both if nothing was done since
+    pub fn apply_completion_edits(&mut self, edits: Vec<TextEdit>) -> bool {
+        let Some(group) = self.actions.done_len().checked_sub(1) else {
+            return false;
+        };
+        let position = CursorPosition::from(&self.cursor);
+        if !self.apply_edits_into_group(group, edits) {
+            return false;
+        }
+        if let Some(snippet) = self.snippet.as_mut() {
+            if position.char != self.cursor.char || !snippet.shift_lines(position.line, self.cursor.line) {
+                self.snippet = None;
+            }
+        }
+        true
+    }
+
+    /// applies edits into the undo group (own step if anything was done since), false if it is not possible
+    pub fn apply_edits_into_group(&mut self, group: usize, edits: Vec<TextEdit>) -> bool {
+        self.actions.apply_edits_into(group, edits, &mut self.cursor, &mut self.content, &mut self.lexer)
     }
 
     #[inline(always)]
     pub fn go_to(&mut self, line: usize) {
+        if self.is_loading() {
+            self.pending_jump = Some(PendingJump::Line(line));
+            return;
+        }
         self.cursor.select_drop();
-        if self.content.len() >= line {
+        if self.content.len() > line {
             self.cursor.line = line;
             self.cursor.char = find_line_start(&self.content[line]);
             self.cursor.at_line = line.saturating_sub(self.cursor.max_rows / 2);
         }
     }
 
-    #[inline(always)]
+    /// moves the cursor to the position (clamped within the content) and centers the view on it
+    pub fn go_to_position(&mut self, position: CursorPosition) {
+        if self.is_loading() {
+            self.pending_jump = Some(PendingJump::Position(position));
+            return;
+        }
+        self.cursor.select_drop();
+        let Some(position) = self.clamp_position(position) else {
+            return;
+        };
+        self.cursor.at_line = position.line.saturating_sub(self.cursor.max_rows / 2);
+        self.cursor.set_position(position);
+    }
+
+    /// selects the range (clamped within the content) and centers the view on its end
     pub fn go_to_select(&mut self, from: CursorPosition, to: CursorPosition) {
+        if self.is_loading() {
+            self.pending_jump = Some(PendingJump::Select(from, to));
+            return;
+        }
+        let (Some(from), Some(to)) = (self.clamp_position(from), self.clamp_position(to)) else {
+            return;
+        };
         self.cursor.at_line = to.line.saturating_sub(self.cursor.max_rows / 2);
         self.cursor.select_set(from, to);
     }
 
+    /// resolves the anchor against the content, false if it does not match
+    /// editors parsing in the background keep the anchor until the lines are set
+    pub fn go_to_anchor(&mut self, anchor: FileAnchor) -> bool {
+        if self.is_loading() {
+            self.pending_jump = Some(PendingJump::Anchor(anchor));
+            return true;
+        }
+        match anchor.resolve(&self.content) {
+            Some((from, to)) if from == to => self.go_to_position(from),
+            Some((from, to)) => self.go_to_select(from, to),
+            None => return false,
+        }
+        true
+    }
+
+    #[inline]
+    fn clamp_position(&self, position: CursorPosition) -> Option<CursorPosition> {
+        let last = self.content.len().checked_sub(1)?;
+        let line = position.line.min(last);
+        Some(CursorPosition { line, char: position.char.min(self.content[line].char_len()) })
+    }
+
     pub fn find(&self, pat: &str, buffer: &mut Vec<(CursorPosition, CursorPosition)>) {
         if pat.is_empty() {
             return;
@@ -366,6 +1522,23 @@ impl Editor {
         }
     }
 
+    /// find with regex/case/whole word options - invalid regex matches nothing
+    pub fn find_with(&self, pat: &str, options: SearchOptions, buffer: &mut Vec<(CursorPosition, CursorPosition)>) {
+        if options.is_plain() {
+            return self.find(pat, buffer);
+        }
+        let Some(matcher) = Some(pat).filter(|pat| !pat.is_empty()).and_then(|pat| options.build(pat).ok()) else {
+            return;
+        };
+        for (line_idx, line_content) in self.content.iter().enumerate() {
+            for found in matcher.find_iter(&line_content[..]).filter(|found| !found.is_empty()) {
+                let char_idx = line_content.unsafe_utf8_to_idx(found.start());
+                let end = char_idx + found.as_str().chars().count();
+                buffer.push(((line_idx, char_idx).into(), (line_idx, end).into()));
+            }
+        }
+    }
+
     pub fn find_with_line(&mut self, pat: &str) -> Vec<((CursorPosition, CursorPosition), String)> {
         let mut buffer = Vec::new();
         if pat.is_empty() {
@@ -382,6 +1555,26 @@ impl Editor {
         buffer
     }
 
+    /// pastes the clipboard or the named register - cycle paste continues only from clipboard paste
+    pub fn paste(&mut self, register: Option<char>, gs: &mut GlobalState) {
+        let Some(name) = register else {
+            if let Some(clip) = gs.clipboard_paste() {
+                self.paste_from_history(clip, 0);
+            }
+            return;
+        };
+        match gs.clipboard.register(name).map(str::to_owned) {
+            Some(clip) => self.actions.paste(clip, &mut self.cursor, &mut self.content, &mut self.lexer),
+            None => gs.message(format!("Register '{name}' is empty!")),
+        }
+    }
+
+    /// pastes clip at index of clipboard history - cycle paste continues from it
+    pub fn paste_from_history(&mut self, clip: String, history_idx: usize) {
+        self.actions.paste(clip, &mut self.cursor, &mut self.content, &mut self.lexer);
+        self.paste_cycle = Some(((&self.cursor).into(), history_idx));
+    }
+
     #[inline(always)]
     pub fn cut(&mut self) -> Option<String> {
         if self.content.is_empty() {
@@ -412,37 +1605,97 @@ impl Editor {
         );
     }
 
-    pub fn mouse_cursor(&mut self, mut position: CursorPosition) {
+    /// click on the LSP modal (accepting completion), false if the modal was not hit
+    pub fn mouse_modal(&mut self, row: u16, column: u16, gs: &mut GlobalState) -> bool {
+        let (taken, render_update) = self.lexer.map_modal_click(row, column, gs);
+        if let Some(modal_rect) = render_update {
+            self.updated_rect(modal_rect, gs);
+        }
+        taken
+    }
+
+    pub fn mouse_cursor(&mut self, position: CursorPosition) {
         self.cursor.select_drop();
-        position.line += self.cursor.at_line;
-        position.char = position.char.saturating_sub(self.line_number_offset + 1);
-        self.cursor.set_cursor_checked(position, &self.content);
+        self.cursor.block_drop();
+        if self.scrollbar.as_mut().is_some_and(|bar| bar.grab(position.char)) {
+            let row = self.below_scope_header(position).map(|position| position.line).unwrap_or_default();
+            return self.scroll_to_bar(row);
+        }
+        match self.below_scope_header(position) {
+            Some(position) => (self.renderer.set_cursor)(self, position),
+            None => {
+                if let Some(line) = scope_header(&self.content, self.cursor.at_line) {
+                    let char = self.content[line].chars().take_while(|ch| ch.is_whitespace()).count();
+                    self.cursor.set_position(CursorPosition { line, char });
+                }
+            }
+        }
+    }
+
+    pub fn mouse_select(&mut self, position: CursorPosition) {
+        if self.scrollbar.as_ref().is_some_and(Scrollbar::is_dragging) {
+            let row = self.below_scope_header(position).map(|position| position.line).unwrap_or_default();
+            return self.scroll_to_bar(row);
+        }
+        let Some(position) = self.below_scope_header(position) else {
+            return;
+        };
+        self.cursor.block_drop();
+        (self.renderer.set_cursor)(self, position);
+        self.cursor.init_select();
+        self.cursor.push_to_select();
     }
 
-    pub fn mouse_select(&mut self, mut position: CursorPosition) {
-        position.line += self.cursor.at_line;
-        position.char = position.char.saturating_sub(self.line_number_offset + 1);
-        self.cursor.set_cursor_checked_with_select(position, &self.content);
+    /// sets the view proportionally to the scrollbar row, the cursor is moved only to stay on screen
+    fn scroll_to_bar(&mut self, row: usize) {
+        let Some(scrollbar) = self.scrollbar.as_ref() else {
+            return;
+        };
+        let cursor = &mut self.cursor;
+        let at_line = scrollbar.line_at(row, self.content.len(), cursor.max_rows);
+        cursor.select_drop();
+        cursor.at_line = at_line;
+        let last_line = (at_line + cursor.max_rows).min(self.content.len()).saturating_sub(1);
+        if cursor.line < at_line || cursor.line > last_line {
+            let line = cursor.line.clamp(at_line, last_line.max(at_line));
+            cursor.set_cursor_clamped(CursorPosition { line, char: cursor.char }, &self.content);
+        }
     }
 
-    pub fn mouse_copy_paste(&mut self, mut position: CursorPosition, clip: Option<String>) -> Option<String> {
+    pub fn mouse_copy_paste(&mut self, position: CursorPosition, clip: Option<String>) -> Option<String> {
         if let Some((from, to)) = self.cursor.select_get() {
             return Some(copy_content(from, to, &self.content));
         };
-        position.line += self.cursor.at_line;
-        position.char = position.char.saturating_sub(self.line_number_offset + 1);
-        self.cursor.set_cursor_checked(position, &self.content);
+        let position = self.below_scope_header(position)?;
+        (self.renderer.set_cursor)(self, position);
         self.actions.paste(clip?, &mut self.cursor, &mut self.content, &mut self.lexer);
         None
     }
 
+    /// line diff from the content of the editor to the file on disk
+    pub fn disk_diff(&self) -> IdiomResult<Vec<LineDiff>> {
+        big_file_protection(&self.path)?;
+        let disk = std::fs::read_to_string(&self.path).map_err(|err| IdiomError::io_err(err.to_string()))?;
+        let disk = disk.split('\n').collect::<Vec<_>>();
+        let content = self.content.iter().map(|line| line.content.as_str()).collect::<Vec<_>>();
+        Ok(diff::line_diff(&content, &disk))
+    }
+
+    /// reloads the file from disk keeping the cursor and the view (clamped to the new content)
+    /// used for files changed on disk without local edits - nothing is lost, the undo history is dropped
+    pub fn reload_preserving_cursor(&mut self, gs: &mut GlobalState) {
+        if let Err(error) = self.reload_content("reload", gs) {
+            gs.error(format!("File reload failed! ERR: {error}"));
+        }
+    }
+
     pub fn rebase(&mut self, gs: &mut GlobalState) {
         if let Err(error) = big_file_protection(&self.path) {
             gs.error(format!("Failed to load file {}", error));
             return;
         };
         self.actions.clear();
-        self.cursor.reset();
+        self.cursor.select_drop();
         self.lexer.close();
         let content = match std::fs::read_to_string(&self.path) {
             Ok(content) => content,
@@ -451,32 +1704,320 @@ impl Editor {
                 return;
             }
         };
-        self.content = content.split('\n').map(|line| EditorLine::new(line.to_owned())).collect();
+        let new_content = content.split('\n').map(|line| EditorLine::new(line.to_owned())).collect::<Vec<_>>();
+        let diff = {
+            let old = self.content.iter().map(|line| line.content.as_str()).collect::<Vec<_>>();
+            let new = new_content.iter().map(|line| line.content.as_str()).collect::<Vec<_>>();
+            diff::line_diff(&old, &new)
+        };
+        self.content = new_content;
+        self.loader = None;
+        self.shift_view(|line| diff::shift_line(&diff, line));
+        self.invalidate_render("rebase");
+        self.saved_hash = content_hash(&self.content);
         match self.lexer.reopen(content, self.file_type) {
             Ok(()) => gs.success("File rebased!"),
             Err(err) => gs.error(format!("Filed to reactivate LSP after rebase! ERR: {}", err)),
         }
     }
 
+    /// discards all changes and loads the file from disk, keeping the cursor where possible
+    pub fn revert(&mut self, gs: &mut GlobalState) {
+        match self.reload_content("revert", gs) {
+            Ok(()) => gs.success("Changes reverted!"),
+            Err(error) => gs.error(format!("File revert failed! ERR: {error}")),
+        }
+    }
+
+    /// replaces the content with the file on disk (undo history is dropped), the cursor is kept within the lines
+    /// text files are streamed again - read up to the cursor and the view, the rest as the view reaches it
+    fn reload_content(&mut self, reason: &'static str, gs: &mut GlobalState) -> Result<(), String> {
+        big_file_protection(&self.path).map_err(|error| error.to_string())?;
+        let (mut content, loader) = match self.view_family {
+            ViewFamily::Text => {
+                let view_end = self.folds.line_at_row(self.cursor.at_line, self.cursor.max_rows);
+                LineLoader::open(&self.path, view_end.max(self.cursor.line + 1) + LOAD_AHEAD)?
+            }
+            _ => (EditorLine::parse_lines(&self.path)?, None),
+        };
+        if self.view_family != ViewFamily::Code {
+            calc_wraps(&mut content, self.cursor.text_width);
+        }
+        let (line, char, at_line) = (self.cursor.line, self.cursor.char, self.cursor.at_line);
+        self.actions.clear();
+        self.cursor.select_drop();
+        self.content = content;
+        self.loader = loader;
+        self.saved_hash = content_hash(&self.content);
+        self.cursor.set_position(CursorPosition { line, char });
+        self.cursor.adjust_max_line(&self.content);
+        self.cursor.adjust_char(&self.content[self.cursor.line]);
+        self.cursor.at_line = std::cmp::min(at_line, self.cursor.line);
+        self.update_status = FileUpdate::None;
+        self.invalidate_render(reason);
+        self.lexer.full_sync(self.stringify(), gs);
+        Ok(())
+    }
+
+    /// with format on save the document is formatted first (LSP or formatter) and written once the edits arrive
     pub fn save(&mut self, gs: &mut GlobalState) {
-        if let Some(content) = self.try_write_file(gs) {
-            self.update_status.deny();
-            self.lexer.save_and_check_lsp(content, gs);
-            gs.success(format!("SAVED {}", self.path.display()));
+        if self.format_before_save(gs) {
+            return;
+        }
+        self.save_without_format(gs);
+    }
+
+    /// save used by save all - same steps as save, messages are left to the caller
+    pub fn save_quiet(&mut self, gs: &mut GlobalState) -> SaveResult {
+        if self.format_before_save(gs) {
+            return SaveResult::Formatting;
+        }
+        self.apply_save_edits(gs);
+        match self.write_file() {
+            Ok(content) => SaveResult::Saved(content),
+            Err(error) => match blocking_attribute(&error, &self.path, probe_attributes) {
+                Some(block) => SaveResult::Blocked(block),
+                None => SaveResult::Failed(error.to_string()),
+            },
+        }
+    }
+
+    /// starts format on save, true if the save waits for the formatter (the editor is saved once formatted)
+    fn format_before_save(&mut self, gs: &mut GlobalState) -> bool {
+        if !self.format_on_save {
+            return false;
+        }
+        if self.formatter.is_some() {
+            self.format_external(true, gs);
+            return true;
+        }
+        let insert_spaces = self.actions.cfg.indent != "\t";
+        self.lexer.format_before_save(self.indent_width(), insert_spaces, gs)
+    }
+
+    /// runs the formatter command on the text in the background (see poll_external_format)
+    fn format_external(&mut self, save: bool, gs: &mut GlobalState) {
+        if let Some(format) = self.external_format.as_mut() {
+            format.save |= save;
+            gs.message("Formatter is running");
+            return;
+        }
+        let Some(command) = self.formatter.as_ref() else {
+            return;
+        };
+        let format = ExternalFormat::start(command, self.file_text(), content_hash(&self.content), save);
+        self.external_format = Some(format);
+    }
+
+    /// applies the text of finished formatter as a single edit, errors (stderr) are shown in popup
+    pub fn poll_external_format(&mut self, gs: &mut GlobalState) {
+        let Some(result) = self.external_format.as_ref().and_then(ExternalFormat::poll) else {
+            return;
+        };
+        let Some(format) = self.external_format.take() else {
+            return;
+        };
+        match result {
+            Ok(_) if format.content_hash != content_hash(&self.content) => {
+                gs.error("Formatting dropped, the text was changed");
+            }
+            Ok(text) if text == self.file_text() => (),
+            Ok(text) => {
+                self.replace_text(text);
+                if !format.save {
+                    gs.success("Formatted");
+                }
+            }
+            Err(error) => {
+                gs.error(format!("Formatting failed: {}", self.display));
+                gs.popup(formatter_failed(&error));
+            }
+        }
+        if format.save {
+            self.save_without_format(gs);
+        }
+    }
+
+    /// format on save waiting for the server (LSP), the editor is saved once it is resolved
+    #[inline]
+    pub fn poll_format_save(&mut self, gs: &mut GlobalState) {
+        Lexer::save_formatted(self, gs);
+    }
+
+    /// save waiting for the formatter is done without it - the editor is closed or the app exits
+    pub fn flush_pending_save(&mut self, gs: &mut GlobalState) {
+        let external = self.external_format.take().is_some_and(|format| format.save);
+        if self.lexer.take_format_save() || external {
+            self.save_without_format(gs);
+        }
+    }
+
+    /// whole text replaced by a single edit - undone at once and synced as any other edit
+    fn replace_text(&mut self, text: String) {
+        let last = self.content.len() - 1;
+        let end = CursorPosition { line: last, char: self.content[last].char_len() };
+        let range = lsp_types::Range::new(CursorPosition::default().into(), end.into());
+        self.cursor.select_drop();
+        self.apply_file_edits(vec![TextEdit::new(range, text)]);
+    }
+
+    pub fn save_without_format(&mut self, gs: &mut GlobalState) {
+        self.apply_save_edits(gs);
+        match self.write_file() {
+            Ok(content) => {
+                self.lexer.save_and_check_lsp(content, gs);
+                gs.success(format!("SAVED {}", self.path.display()));
+            }
+            Err(error) => match blocking_attribute(&error, &self.path, probe_attributes) {
+                Some(block) => {
+                    gs.error(format!("Save failed: {block}"));
+                    gs.popup(protected_file(block, self.attribute_elevation.is_some()));
+                }
+                None => gs.error(error.to_string()),
+            },
+        }
+    }
+
+    /// removes the attribute blocking the save with the configured elevation and restores it after the write
+    pub fn save_elevated(&mut self, gs: &mut GlobalState) {
+        let Some(elevation) = self.attribute_elevation.clone() else {
+            gs.error("Attribute elevation is not configured (attribute_elevation)!");
+            return;
+        };
+        let Some(block) = attribute_block(&self.path, probe_attributes) else {
+            return self.save(gs);
+        };
+        if let Err(error) = chattr(&elevation, '-', &block) {
+            gs.error(format!("Failed to remove attribute of {}: {error}", block.path.display()));
+            return;
+        }
+        let result = self.write_file();
+        if let Err(error) = chattr(&elevation, '+', &block) {
+            gs.error(format!("Failed to restore attribute: {block}! ERR: {error}"));
+        }
+        match result {
+            Ok(content) => {
+                self.lexer.save_and_check_lsp(content, gs);
+                gs.success(format!("SAVED {} ({} restored)", self.path.display(), block.attribute));
+            }
+            Err(error) => gs.error(error.to_string()),
         }
     }
 
-    pub fn try_write_file(&self, gs: &mut GlobalState) -> Option<String> {
+    /// writes the content to another file, the editor stays on its path (and dirty)
+    pub fn save_copy(&mut self, path: PathBuf, gs: &mut GlobalState) {
+        if !self.finish_loading(gs) {
+            return;
+        }
         let content = self.content.iter().map(|l| l.to_string()).collect::<Vec<_>>().join("\n");
-        if let Err(error) = std::fs::write(&self.path, &content) {
-            gs.error(error.to_string());
-            return None;
+        match std::fs::write(&path, content) {
+            Ok(()) => gs.success(format!("Copy saved to {}", path.display())),
+            Err(error) => gs.error(format!("Failed to save copy: {}", save_error_message(&error, &path))),
+        }
+    }
+
+    /// trims trailing whitespace from lines changed since the last save and adds final new line as single edit
+    /// cursor within the removed whitespace is clamped to the line end
+    fn apply_save_edits(&mut self, gs: &mut GlobalState) {
+        if !self.finish_loading(gs) {
+            return;
+        }
+        let mut edits = match self.trim_trailing_whitespace {
+            true => trailing_whitespace_edits(&self.content, &std::fs::read_to_string(&self.path).unwrap_or_default()),
+            false => Vec::new(),
+        };
+        if self.insert_final_newline {
+            edits.extend(final_newline_edit(&self.content));
+        }
+        if edits.is_empty() {
+            return;
+        }
+        self.apply_file_edits(edits);
+        let position = CursorPosition::from(&self.cursor);
+        self.cursor.set_cursor_clamped(position, &self.content);
+    }
+
+    pub fn try_write_file(&mut self, gs: &mut GlobalState) -> Option<String> {
+        self.write_file().map_err(|error| gs.error(save_error_message(&error, &self.path))).ok()
+    }
+
+    /// writes the content to disk, on success the editor is no longer dirty
+    pub fn write_file(&mut self) -> std::io::Result<String> {
+        if self.is_loading() {
+            return Err(std::io::Error::other("the file is still loading"));
         }
-        Some(content)
+        if let Some(loader) = self.loader.as_mut() {
+            loader.load_all(&mut self.content).map_err(std::io::Error::other)?;
+            self.loader = None;
+        }
+        let content = self.file_text();
+        std::fs::write(&self.path, &content)?;
+        self.saved_hash = content_hash(&self.content);
+        self.actions.clear_last_edit();
+        self.update_status.deny();
+        Ok(content)
     }
 
     pub fn refresh_cfg(&mut self, new_cfg: &EditorConfigs) {
-        self.actions.cfg = new_cfg.get_indent_cfg(&self.file_type);
+        let is_md = self.path.extension().map(|ext| ext.eq_ignore_ascii_case("md")).unwrap_or_default();
+        self.actions.cfg = match is_md {
+            true => new_cfg.get_md_indent_cfg(),
+            false => new_cfg.with_detected_indent(new_cfg.get_indent_cfg(&self.file_type), &self.content),
+        };
+        if let Some(indent) = self.indent_override.as_ref() {
+            self.actions.cfg.indent.clone_from(indent);
+        }
+        self.actions.limit = new_cfg.history_limit();
+        self.actions.group_timeout = new_cfg.undo_group_timeout();
+        self.wrap_column = new_cfg.wrap_column(&self.path);
+        self.comment_column = new_cfg.comment_column;
+        self.format_on_save = new_cfg.format_on_save;
+        self.delete_to_clipboard = new_cfg.delete_to_clipboard;
+        self.formatter = match self.view_family {
+            ViewFamily::Code => new_cfg.formatter(&self.file_type),
+            _ => None,
+        };
+        self.trim_trailing_whitespace = new_cfg.trim_trailing_whitespace_on_save;
+        self.insert_final_newline = new_cfg.insert_final_newline && self.file_type != FileType::Ignored;
+        self.view = new_cfg.view(self.view_family, self.file_type);
+        self.apply_view();
+        self.attribute_elevation.clone_from(&new_cfg.attribute_elevation);
+        self.persist_undo = new_cfg.persist_undo;
+        if new_cfg.render_check_frames != self.render_check.as_ref().map_or(0, |check| check.every()) {
+            self.render_check = RenderCheck::new(new_cfg.render_check_frames);
+        }
+        if new_cfg.highlight_trailing_whitespace != self.highlight_trailing_whitespace {
+            self.highlight_trailing_whitespace = new_cfg.highlight_trailing_whitespace;
+            self.invalidate_render("config");
+        }
+        if new_cfg.show_inlay_hints != self.show_inlay_hints {
+            self.show_inlay_hints = new_cfg.show_inlay_hints;
+            if !self.show_inlay_hints {
+                self.lexer.drop_inlay_hints(&mut self.content);
+            }
+        }
+        let sticky_scope = new_cfg.sticky_scope && self.file_type != FileType::Ignored;
+        if sticky_scope != self.sticky_scope {
+            self.sticky_scope = sticky_scope;
+            self.cursor.max_rows = match sticky_scope {
+                true => self.cursor.max_rows.saturating_sub(1),
+                false => self.cursor.max_rows + 1,
+            };
+            self.invalidate_render("config");
+        }
+        if new_cfg.scrollbar != self.scrollbar.is_some() {
+            self.scrollbar = new_cfg.scrollbar.then(Scrollbar::default);
+            self.cursor.text_width = match new_cfg.scrollbar {
+                true => self.cursor.text_width.saturating_sub(1),
+                false => self.cursor.text_width + 1,
+            };
+            self.invalidate_render("config");
+        }
+    }
+
+    /// text as written to the file (stringify adds new line for LSP)
+    fn file_text(&self) -> String {
+        self.content.iter().map(|l| l.to_string()).collect::<Vec<_>>().join("\n")
     }
 
     #[inline]
@@ -486,15 +2027,46 @@ impl Editor {
         text
     }
 
+    /// position relative to the code rows, None if it is on the scope header
+    fn below_scope_header(&self, mut position: CursorPosition) -> Option<CursorPosition> {
+        if self.sticky_scope {
+            position.line = position.line.checked_sub(1)?;
+        }
+        Some(position)
+    }
+
     pub fn resize(&mut self, width: usize, height: usize) {
-        self.cursor.max_rows = height;
-        self.line_number_offset = if self.content.is_empty() { 1 } else { (self.content.len().ilog10() + 1) as usize };
-        self.cursor.text_width = width.saturating_sub(self.line_number_offset + 1);
+        self.cursor.max_rows = match self.sticky_scope {
+            true => height.saturating_sub(1),
+            false => height,
+        };
+        self.line_number_offset = calc_line_number_offset(self.content.len(), self.line_numbers);
+        let scrollbar_width = self.scrollbar.is_some() as usize;
+        self.cursor.text_width = width.saturating_sub(self.line_number_offset + 1 + scrollbar_width);
+    }
+}
+
+/// cursor sized to the part of the editor area not taken by the scope header and the scrollbar
+fn sized_cursor(gs: &GlobalState, line_number_offset: usize, sticky_scope: bool, scrollbar: bool) -> Cursor {
+    let mut cursor = Cursor::sized(gs, line_number_offset);
+    if sticky_scope {
+        cursor.max_rows = cursor.max_rows.saturating_sub(1);
+    }
+    if scrollbar {
+        cursor.text_width = cursor.text_width.saturating_sub(1);
     }
+    cursor
 }
 
 impl Drop for Editor {
     fn drop(&mut self) {
+        if self.persist_undo && !self.ephemeral && !self.is_loading() {
+            // steps are valid only if they lead to the content on disk
+            match self.is_dirty() {
+                true => Actions::discard_history(&self.path),
+                false => self.actions.store_history(&self.path, self.saved_hash),
+            }
+        }
         self.lexer.close();
     }
 }
@@ -0,0 +1,353 @@
+use crate::workspace::{cursor::CursorPosition, line::EditorLine};
+use std::ops::Range;
+use unicode_width::UnicodeWidthStr;
+
+/// Text replacing the lines range and the cursor position after the replace
+#[derive(Debug, PartialEq)]
+pub struct Reflow {
+    pub lines: Range<usize>,
+    pub text: String,
+    pub cursor: CursorPosition,
+}
+
+enum Block<'a> {
+    /// lines that are never rewrapped (blank, fences, fenced code, tables, headings, code)
+    Verbatim(&'a str),
+    Paragraph {
+        lines: Range<usize>,
+        first: &'a str,
+        hanging: String,
+        words: Vec<&'a str>,
+    },
+}
+
+enum CursorAt {
+    Word { block: usize, word: usize, offset: usize },
+    Line { block: usize, char: usize },
+}
+
+/// rewraps the paragraph under the cursor or all paragraphs in the selected lines to the column
+/// comment is the line comment of code files - only comment lines are rewrapped in that case
+pub fn reflow(
+    content: &[EditorLine],
+    select: Option<Range<usize>>,
+    cursor: CursorPosition,
+    column: usize,
+    comment: Option<&str>,
+) -> Option<Reflow> {
+    let lines = match select {
+        Some(lines) => lines,
+        None => paragraph_at(content, cursor.line, comment)?,
+    };
+    if lines.is_empty() || lines.end > content.len() {
+        return None;
+    }
+    let fenced = fenced_at(content, lines.start, comment);
+    let cursor_at = match lines.contains(&cursor.line) {
+        true => Some((cursor.line - lines.start, cursor.char)),
+        false => None,
+    };
+    let (blocks, cursor_at) = parse(&content[lines.clone()], comment, fenced, cursor_at);
+    let mut out = Vec::new();
+    let mut new_cursor = None;
+    for (idx, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Verbatim(line) => {
+                if let Some(CursorAt::Line { block, char }) = cursor_at {
+                    if block == idx {
+                        new_cursor = Some(CursorPosition { line: lines.start + out.len(), char });
+                    }
+                }
+                out.push(line.to_string());
+            }
+            Block::Paragraph { first, hanging, words, .. } => {
+                let positions = fill(first, hanging, words, column, &mut out);
+                if let Some(CursorAt::Word { block, word, offset }) = cursor_at {
+                    if block == idx {
+                        let (line, char) = positions[word];
+                        new_cursor = Some(CursorPosition { line: lines.start + line, char: char + offset });
+                    }
+                }
+            }
+        }
+    }
+    if out.iter().map(String::as_str).eq(content[lines.clone()].iter().map(|line| line.content.as_str())) {
+        return None;
+    }
+    let cursor = new_cursor.unwrap_or(CursorPosition {
+        line: lines.start + out.len() - 1,
+        char: out.last().map(|line| line.chars().count()).unwrap_or_default(),
+    });
+    Some(Reflow { lines, text: out.join("\n"), cursor })
+}
+
+/// lines of the paragraph containing the line, None if the line is not part of a paragraph
+fn paragraph_at(content: &[EditorLine], line: usize, comment: Option<&str>) -> Option<Range<usize>> {
+    let (blocks, _) = parse(content, comment, false, None);
+    blocks.into_iter().find_map(|block| match block {
+        Block::Paragraph { lines, .. } if lines.contains(&line) => Some(lines),
+        _ => None,
+    })
+}
+
+fn fenced_at(content: &[EditorLine], line: usize, comment: Option<&str>) -> bool {
+    content[..line]
+        .iter()
+        .filter_map(|line| split_prefix(&line.content, comment))
+        .filter(|(_, body)| is_fence(body))
+        .count()
+        % 2
+        == 1
+}
+
+fn parse<'a>(
+    lines: &'a [EditorLine],
+    comment: Option<&str>,
+    mut fenced: bool,
+    cursor: Option<(usize, usize)>,
+) -> (Vec<Block<'a>>, Option<CursorAt>) {
+    let mut blocks = Vec::new();
+    let mut open = false;
+    let mut cursor_at = None;
+    for (idx, line) in lines.iter().enumerate() {
+        let text = line.content.as_str();
+        let Some((prefix, body)) = split_prefix(text, comment) else {
+            blocks.push(Block::Verbatim(text));
+            open = false;
+            continue;
+        };
+        if is_fence(body) {
+            fenced = !fenced;
+        }
+        if fenced || is_fence(body) || is_verbatim(body) {
+            if let Some((cursor_line, char)) = cursor {
+                if cursor_line == idx {
+                    cursor_at = Some(CursorAt::Line { block: blocks.len(), char });
+                }
+            }
+            blocks.push(Block::Verbatim(text));
+            open = false;
+            continue;
+        }
+        let continuation = match blocks.last() {
+            Some(Block::Paragraph { hanging, .. }) if open => text
+                .strip_prefix(hanging.as_str())
+                .filter(|rest| !rest.starts_with(char::is_whitespace) && list_marker(rest).is_none()),
+            _ => None,
+        };
+        let words_body = match continuation {
+            Some(rest) => rest,
+            None => {
+                let (first, hanging) = match list_marker(body) {
+                    Some(marker) => {
+                        let first = &text[..prefix.len() + marker];
+                        (first, format!("{prefix}{:width$}", "", width = UnicodeWidthStr::width(&body[..marker])))
+                    }
+                    None if body.starts_with(char::is_whitespace) => {
+                        blocks.push(Block::Verbatim(text));
+                        open = false;
+                        continue;
+                    }
+                    None => (prefix, prefix.to_owned()),
+                };
+                blocks.push(Block::Paragraph { lines: idx..idx, first, hanging, words: Vec::new() });
+                open = true;
+                &text[first.len()..]
+            }
+        };
+        let block = blocks.len() - 1;
+        let Some(Block::Paragraph { lines: paragraph_lines, words, .. }) = blocks.last_mut() else {
+            unreachable!("paragraph pushed or continued");
+        };
+        paragraph_lines.end = idx + 1;
+        let body_start = text[..text.len() - words_body.len()].chars().count();
+        for (word_start, word) in split_words(words_body) {
+            if let Some((cursor_line, char)) = cursor {
+                let word_start = body_start + word_start;
+                if cursor_line == idx && (char >= word_start || cursor_at.is_none()) {
+                    let offset = char.saturating_sub(word_start).min(word.chars().count());
+                    cursor_at = Some(CursorAt::Word { block, word: words.len(), offset });
+                }
+            }
+            words.push(word);
+        }
+    }
+    (blocks, cursor_at)
+}
+
+/// words with their char idx within the text
+fn split_words(text: &str) -> Vec<(usize, &str)> {
+    let mut word_start = None;
+    let mut words = Vec::new();
+    for (char_idx, (byte_idx, ch)) in text.char_indices().enumerate() {
+        match (ch.is_whitespace(), word_start) {
+            (true, Some((start_byte, start_char))) => {
+                words.push((start_char, &text[start_byte..byte_idx]));
+                word_start = None;
+            }
+            (false, None) => word_start = Some((byte_idx, char_idx)),
+            _ => (),
+        }
+    }
+    if let Some((start_byte, start_char)) = word_start {
+        words.push((start_char, &text[start_byte..]));
+    }
+    words
+}
+
+/// greedy fill returning the (line, char) of each word relative to the first line
+fn fill(first: &str, hanging: &str, words: &[&str], column: usize, out: &mut Vec<String>) -> Vec<(usize, usize)> {
+    let start = out.len();
+    let mut positions = Vec::with_capacity(words.len());
+    let mut line = first.to_owned();
+    let mut width = UnicodeWidthStr::width(first);
+    let mut has_word = false;
+    for word in words {
+        let word_width = UnicodeWidthStr::width(*word);
+        if has_word && width + 1 + word_width > column {
+            out.push(std::mem::replace(&mut line, hanging.to_owned()));
+            width = UnicodeWidthStr::width(hanging);
+            has_word = false;
+        }
+        if has_word {
+            line.push(' ');
+            width += 1;
+        }
+        positions.push((out.len() - start, line.chars().count()));
+        line.push_str(word);
+        width += word_width;
+        has_word = true;
+    }
+    out.push(line);
+    positions
+}
+
+/// splits the line into prefix (indent with quote or comment markers) and body
+/// returns None for non comment lines in code
+fn split_prefix<'a>(line: &'a str, comment: Option<&str>) -> Option<(&'a str, &'a str)> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let mut marker = match comment {
+        Some(comment) => {
+            let tail = rest.strip_prefix(comment)?;
+            // doc comments (///, //!) and repeated markers
+            comment.len() + tail.len() - tail.trim_start_matches(|ch| comment.contains(ch) || ch == '!').len()
+        }
+        None => 0,
+    };
+    while rest[marker..].starts_with('>') {
+        marker += 1;
+        if rest[marker..].starts_with(' ') {
+            marker += 1;
+        }
+    }
+    if comment.is_some() && rest[marker..].starts_with(' ') {
+        marker += 1;
+    }
+    Some(line.split_at(indent + marker))
+}
+
+fn is_fence(body: &str) -> bool {
+    let body = body.trim_start();
+    body.starts_with("```") || body.starts_with("~~~")
+}
+
+fn is_verbatim(body: &str) -> bool {
+    let body = body.trim();
+    body.is_empty() || body.starts_with('|') || body.starts_with('#')
+}
+
+/// byte len of list marker with the following space ("- ", "* ", "+ ", "1. ", "2) ")
+fn list_marker(body: &str) -> Option<usize> {
+    let digits = body.len() - body.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+    let marker = match digits {
+        0 if body.starts_with(['-', '*', '+']) => 1,
+        0 => return None,
+        _ if body[digits..].starts_with(['.', ')']) => digits + 1,
+        _ => return None,
+    };
+    body[marker..].starts_with(' ').then_some(marker + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{reflow, Reflow};
+    use crate::workspace::{cursor::CursorPosition, line::EditorLine};
+
+    fn lines(text: &[&str]) -> Vec<EditorLine> {
+        text.iter().map(|line| EditorLine::from(line.to_string())).collect()
+    }
+
+    #[test]
+    fn paragraph() {
+        let content = lines(&["first", "aaa bbb ccc ddd", "eee", "", "other"]);
+        let result = reflow(&content, None, CursorPosition { line: 2, char: 1 }, 8, None).unwrap();
+        assert_eq!(
+            result,
+            Reflow {
+                lines: 0..3,
+                text: "first\naaa bbb\nccc ddd\neee".to_owned(),
+                cursor: CursorPosition { line: 3, char: 1 }
+            }
+        );
+        // blank line is not part of a paragraph
+        assert_eq!(reflow(&content, None, CursorPosition { line: 3, char: 0 }, 8, None), None);
+        // already wrapped
+        assert_eq!(reflow(&content, None, CursorPosition { line: 4, char: 0 }, 8, None), None);
+    }
+
+    #[test]
+    fn quoted() {
+        let content = lines(&["> a quoted text that", "> is too long", "", "> > nested 🦀🦀 quote"]);
+        let result = reflow(&content, Some(0..4), CursorPosition { line: 1, char: 5 }, 12, None).unwrap();
+        assert_eq!(result.text, "> a quoted\n> text that\n> is too\n> long\n\n> > nested\n> > 🦀🦀\n> > quote");
+        // cursor stays on "too"
+        assert_eq!(result.cursor, CursorPosition { line: 2, char: 5 });
+    }
+
+    #[test]
+    fn rust_comment() {
+        let content = lines(&[
+            "fn main() {",
+            "    // short comment",
+            "    // that continues here",
+            "    let a = 1;",
+            "    /// doc comment",
+            "    /// ```",
+            "    /// let keep = 1;",
+            "    /// ```",
+            "}",
+        ]);
+        let result = reflow(&content, None, CursorPosition { line: 2, char: 13 }, 30, Some("//")).unwrap();
+        assert_eq!(result.lines, 1..3);
+        assert_eq!(result.text, "    // short comment that\n    // continues here");
+        // cursor stays on the second char of "continues"
+        assert_eq!(result.cursor, CursorPosition { line: 2, char: 8 });
+        // code lines are not part of comments
+        assert_eq!(reflow(&content, None, CursorPosition { line: 3, char: 0 }, 10, Some("//")), None);
+        // fenced code in doc comments is kept
+        let result = reflow(&content, Some(4..8), CursorPosition { line: 4, char: 8 }, 12, Some("//")).unwrap();
+        assert_eq!(result.text, "    /// doc\n    /// comment\n    /// ```\n    /// let keep = 1;\n    /// ```");
+    }
+
+    #[test]
+    fn list_hanging_indent() {
+        let content = lines(&[
+            "- first item with some",
+            "  text",
+            "10. numbered item that is long",
+            "```",
+            "- in code block stays as it is",
+            "```",
+            "| table | row |",
+        ]);
+        let result = reflow(&content, Some(0..7), CursorPosition { line: 1, char: 3 }, 14, None).unwrap();
+        assert_eq!(
+            result.text,
+            "- first item\n  with some\n  text\n10. numbered\n    item that\n    is long\n```\n- in code block stays as it is\n```\n| table | row |"
+        );
+        assert_eq!(result.cursor, CursorPosition { line: 2, char: 3 });
+        // cursor inside fenced block
+        assert_eq!(reflow(&content, None, CursorPosition { line: 4, char: 0 }, 14, None), None);
+    }
+}
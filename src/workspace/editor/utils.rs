@@ -1,12 +1,86 @@
-use crate::error::{IdiomError, IdiomResult};
+use super::{AttributeBlock, Editor};
+use crate::{
+    cli::FileAnchor,
+    configs::{EditorAction, IndentLabel},
+    error::{IdiomError, IdiomResult},
+    workspace::{actions::EditType, line::EditorLine, session::ViewSection, CursorPosition},
+};
+use lsp_types::{Position, TextEdit};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{
+    collections::HashMap,
+    ops::Range,
     os::unix::fs::MetadataExt,
     path::{Path, PathBuf, MAIN_SEPARATOR, MAIN_SEPARATOR_STR},
 };
 
+/// Move line mode state - lines are moved on the content for preview (outside of the undo history),
+/// steps are collapsed into single edit on commit or reverted on abort
+pub struct LineMove {
+    pub origin: Range<usize>,
+    pub lines: Range<usize>,
+    pub steps: Vec<EditType>,
+}
+
+impl LineMove {
+    pub fn new(lines: Range<usize>) -> Self {
+        Self { origin: lines.clone(), lines, steps: Vec::new() }
+    }
+
+    pub fn moved(&mut self, down: bool, step: EditType) {
+        self.steps.push(step);
+        match down {
+            true => self.lines = self.lines.start + 1..self.lines.end + 1,
+            false => self.lines = self.lines.start - 1..self.lines.end - 1,
+        }
+    }
+
+    /// all lines changed by the move
+    pub fn region(&self) -> Range<usize> {
+        self.origin.start.min(self.lines.start)..self.origin.end.max(self.lines.end)
+    }
+}
+
+/// Register prefix - the name is pressed after the register key, the clip action follows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Register {
+    Pending,
+    Named(char),
+}
+
+/// jump requested while the file is parsed in the background - applied once the lines are set
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingJump {
+    Line(usize),
+    Position(CursorPosition),
+    Select(CursorPosition, CursorPosition),
+    Anchor(FileAnchor),
+}
+
+/// inputs of the footer status not kept in it - the status is rebuilt only once they (or the status fields) change
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusKey {
+    pub revision: usize,
+    pub select: Option<(CursorPosition, CursorPosition)>,
+    pub indent: IndentLabel,
+}
+
+/// outcome of save without messages (save all reports a single summary)
+pub enum SaveResult {
+    /// written text
+    Saved(String),
+    /// format on save is running, the editor is saved once formatted
+    Formatting,
+    Blocked(AttributeBlock),
+    Failed(String),
+}
+
 pub enum FileUpdate {
     None,
     Updated,
+    /// the update was shown, but the editor was neither saved nor rebased since
+    Collected,
     Deny,
 }
 
@@ -18,16 +92,21 @@ impl FileUpdate {
     pub fn collect(&mut self) -> bool {
         match self {
             Self::Updated => {
-                *self = Self::None;
+                *self = Self::Collected;
                 true
             }
             _ => false,
         }
     }
 
+    /// the file on disk has changes not in the editor
+    pub fn changed_on_disk(&self) -> bool {
+        matches!(self, Self::Updated | Self::Collected)
+    }
+
     pub fn mark_updated(&mut self) {
         match self {
-            Self::None => *self = Self::Updated,
+            Self::None | Self::Collected => *self = Self::Updated,
             Self::Deny => *self = Self::None,
             _ => (),
         }
@@ -48,10 +127,185 @@ pub fn build_display(path: &Path) -> String {
     buffer.join(MAIN_SEPARATOR_STR)
 }
 
+/// files over the limit are not loaded into editors
+const BIG_FILE_LIMIT: u64 = 50 * 1024 * 1024;
+
 pub fn big_file_protection(path: &Path) -> IdiomResult<()> {
     let meta = std::fs::metadata(path)?;
-    if meta.size() > 50 * 1024 * 1024 {
+    if meta.size() > BIG_FILE_LIMIT {
         return Err(IdiomError::IOError("File over 50MB".to_owned()));
     }
     Ok(())
 }
+
+/// actions possible on partially loaded files - anything else loads the rest first
+pub fn is_view_action(action: EditorAction) -> bool {
+    matches!(
+        action,
+        EditorAction::Up
+            | EditorAction::Down
+            | EditorAction::Left
+            | EditorAction::Right
+            | EditorAction::SelectUp
+            | EditorAction::SelectDown
+            | EditorAction::SelectLeft
+            | EditorAction::SelectRight
+            | EditorAction::SelectToken
+            | EditorAction::SelectLine
+            | EditorAction::ScrollUp
+            | EditorAction::ScrollDown
+            | EditorAction::JumpLeft
+            | EditorAction::JumpLeftSelect
+            | EditorAction::JumpRight
+            | EditorAction::JumpRightSelect
+            | EditorAction::EndOfLine
+            | EditorAction::StartOfLine
+            | EditorAction::SelectStartOfLine
+            | EditorAction::StartOfFile
+            | EditorAction::Copy
+            | EditorAction::Cancel
+            | EditorAction::RefreshUI
+            | EditorAction::Close
+            | EditorAction::FocusOtherSplit
+    )
+}
+
+/// code files over the size are parsed in the background
+const MEDIUM_FILE_LIMIT: u64 = 256 * 1024;
+
+/// file slow enough to parse to stall the UI
+pub fn is_medium_file(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|meta| meta.size() > MEDIUM_FILE_LIMIT)
+}
+
+/// file over the limit of the editors - can be viewed read-only
+pub fn is_big_file(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|meta| meta.size() > BIG_FILE_LIMIT)
+}
+
+/// width of the line number gutter (without the separating space), 0 if the numbers are hidden
+/// relative distances never exceed the line count, so the width fits both numbering modes
+#[inline]
+pub fn calc_line_number_offset(content_len: usize, line_numbers: bool) -> usize {
+    if !line_numbers {
+        0
+    } else if content_len == 0 {
+        1
+    } else {
+        (content_len.ilog10() + 1) as usize
+    }
+}
+
+/// edits removing trailing whitespace from lines not found in the saved text (changed since save)
+pub fn trailing_whitespace_edits(content: &[EditorLine], saved: &str) -> Vec<TextEdit> {
+    let mut unchanged = HashMap::<&str, usize>::new();
+    for line in saved.lines() {
+        *unchanged.entry(line).or_default() += 1;
+    }
+    let mut edits = Vec::new();
+    for (idx, line) in content.iter().enumerate() {
+        if let Some(count) = unchanged.get_mut(line.content.as_str()).filter(|count| **count != 0) {
+            *count -= 1;
+            continue;
+        }
+        let Some(start) = line.trailing_whitespace() else {
+            continue;
+        };
+        let start = Position::new(idx as u32, line.content[..start].chars().count() as u32);
+        let end = Position::new(idx as u32, line.char_len() as u32);
+        edits.push(TextEdit::new(lsp_types::Range::new(start, end), String::new()));
+    }
+    edits
+}
+
+/// edits rewriting the leading whitespace of the lines as spaces or tabs (tab stops every width columns)
+/// indent not filling a whole tab stays as spaces
+pub fn retab_edits(content: &[EditorLine], lines: Range<usize>, to_spaces: bool, width: usize) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    if width == 0 {
+        return edits;
+    }
+    for (idx, line) in content.iter().enumerate().take(lines.end).skip(lines.start) {
+        let indent = &line.content[..line.content.len() - line.content.trim_start_matches([' ', '\t']).len()];
+        let new_indent = retab_indent(indent, to_spaces, width);
+        if new_indent == indent {
+            continue;
+        }
+        // indent is ascii - byte len is char len
+        let range = lsp_types::Range::new(Position::new(idx as u32, 0), Position::new(idx as u32, indent.len() as u32));
+        edits.push(TextEdit::new(range, new_indent));
+    }
+    edits
+}
+
+fn retab_indent(indent: &str, to_spaces: bool, width: usize) -> String {
+    let column = indent.chars().fold(0, |column, ch| match ch {
+        '\t' => (column / width + 1) * width,
+        _ => column + 1,
+    });
+    match to_spaces {
+        true => " ".repeat(column),
+        false => format!("{}{}", "\t".repeat(column / width), " ".repeat(column % width)),
+    }
+}
+
+/// edit adding empty last line, so the written file ends with new line
+pub fn final_newline_edit(content: &[EditorLine]) -> Option<TextEdit> {
+    let (idx, line) = content.iter().enumerate().next_back().filter(|(_, line)| line.char_len() != 0)?;
+    let end = Position::new(idx as u32, line.char_len() as u32);
+    Some(TextEdit::new(lsp_types::Range::new(end, end), String::from("\n")))
+}
+
+/// view state sections persisted in session between runs
+pub const VIEW_SECTIONS: &[ViewSection] = &[
+    ViewSection { key: "cursor", store: store_cursor_view, restore: restore_cursor_view },
+    ViewSection { key: "folds", store: store_folds_view, restore: restore_folds_view },
+];
+
+#[derive(Serialize, Deserialize)]
+struct CursorView {
+    line: usize,
+    char: usize,
+    at_line: usize,
+    #[serde(default)]
+    select: Option<((usize, usize), (usize, usize))>,
+}
+
+fn store_cursor_view(editor: &Editor) -> Option<Value> {
+    let select = editor.cursor.select_get().map(|(from, to)| ((from.line, from.char), (to.line, to.char)));
+    let view =
+        CursorView { line: editor.cursor.line, char: editor.cursor.char, at_line: editor.cursor.at_line, select };
+    serde_json::to_value(view).ok()
+}
+
+fn restore_cursor_view(editor: &mut Editor, value: Value) -> Option<()> {
+    let view = serde_json::from_value::<CursorView>(value).ok()?;
+    if editor.content.is_empty() || view.line >= editor.content.len() {
+        return None;
+    }
+    match view.select {
+        Some((from, to)) if from.0 < editor.content.len() && to.0 < editor.content.len() => {
+            let from = CursorPosition::from(from);
+            let to = CursorPosition::from(to);
+            let (from, to) = if (from.line, from.char) == (view.line, view.char) { (to, from) } else { (from, to) };
+            editor.cursor.select_set(from, to);
+        }
+        _ => editor.cursor.set_cursor_checked((view.line, view.char).into(), &editor.content),
+    }
+    editor.cursor.at_line = view.at_line.min(view.line);
+    Some(())
+}
+
+fn store_folds_view(editor: &Editor) -> Option<Value> {
+    if editor.folds.is_empty() {
+        return None;
+    }
+    serde_json::to_value(editor.folds.headers()).ok()
+}
+
+/// the cursor is restored first - folds hiding it are opened on the next sync
+fn restore_folds_view(editor: &mut Editor, value: Value) -> Option<()> {
+    let headers = serde_json::from_value::<Vec<usize>>(value).ok()?;
+    editor.folds.restore(&headers, &mut editor.content);
+    Some(())
+}
@@ -0,0 +1,401 @@
+use super::{
+    annotations::{find_line, line_hash},
+    cursor::Cursor,
+    line::EditorLine,
+    renderer::{indent_width, scope_header},
+};
+
+const OPENING: [char; 3] = ['{', '(', '['];
+const CLOSING: [char; 3] = ['}', ')', ']'];
+const FOLDED: &str = " …";
+
+/// Folded block - the header line stays visible, lines after it up to the end are hidden
+#[derive(Debug, Clone, PartialEq)]
+struct Fold {
+    header: usize,
+    end: usize,
+    /// hash of the header content, used to find the line again after edits
+    hash: u64,
+    /// rendered after the header content
+    summary: String,
+}
+
+impl Fold {
+    fn new(header: usize, end: usize, content: &[EditorLine]) -> Self {
+        Self { header, end, hash: line_hash(&content[header]), summary: summary(content, header, end) }
+    }
+
+    #[inline]
+    fn hides(&self, line: usize) -> bool {
+        self.header < line && line <= self.end
+    }
+}
+
+/// Folded blocks of the editor, rendered as single rows - cursor movement and scrolling step over them
+/// nested folds are kept while the outer block is folded, opening it shows them still folded
+#[derive(Default)]
+pub struct Folds {
+    /// sorted by header
+    folds: Vec<Fold>,
+    /// content length and edit revision the folds were last matched against
+    synced: (usize, usize),
+}
+
+impl Folds {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    /// folds the block opened by the line, or the enclosing one if the line opens none (or is folded already)
+    /// returns the header of the folded block
+    pub fn fold(&mut self, line: usize, content: &mut [EditorLine]) -> Option<usize> {
+        let mut header = line;
+        loop {
+            if let Err(idx) = self.position(header) {
+                if let Some(end) = block_end(content, header) {
+                    self.folds.insert(idx, Fold::new(header, end, content));
+                    content[header].clear_cache();
+                    return Some(header);
+                }
+            }
+            header = scope_header(content, header)?;
+        }
+    }
+
+    /// opens the block folded on the line, false if there is none
+    pub fn unfold(&mut self, line: usize, content: &mut [EditorLine]) -> bool {
+        let Ok(idx) = self.position(line) else {
+            return false;
+        };
+        self.folds.remove(idx);
+        content[line].clear_cache();
+        true
+    }
+
+    /// folds every block (nested ones as well), returns the count of newly folded blocks
+    pub fn fold_all(&mut self, content: &mut [EditorLine]) -> usize {
+        let folded = self.folds.len();
+        self.folds = (0..content.len())
+            .filter_map(|header| Some(Fold::new(header, block_end(content, header)?, content)))
+            .collect();
+        self.clear_headers(content);
+        self.folds.len().saturating_sub(folded)
+    }
+
+    /// returns the count of opened blocks
+    pub fn unfold_all(&mut self, content: &mut [EditorLine]) -> usize {
+        self.clear_headers(content);
+        std::mem::take(&mut self.folds).len()
+    }
+
+    /// headers of the folded blocks (nested ones as well) - stored in the session
+    pub fn headers(&self) -> Vec<usize> {
+        self.folds.iter().map(|fold| fold.header).collect()
+    }
+
+    /// folds the blocks opened by the headers (restored session), headers opening no block are skipped
+    pub fn restore(&mut self, headers: &[usize], content: &mut [EditorLine]) {
+        for &header in headers {
+            if let (Err(idx), Some(end)) = (self.position(header), block_end(content, header)) {
+                self.folds.insert(idx, Fold::new(header, end, content));
+                content[header].clear_cache();
+            }
+        }
+    }
+
+    /// opens the folds hiding the line (cursor jumped into folded block), true if any was opened
+    pub fn reveal(&mut self, line: usize, content: &mut [EditorLine]) -> bool {
+        let folded = self.folds.len();
+        self.folds.retain(|fold| match fold.hides(line) {
+            true => {
+                if let Some(header) = content.get_mut(fold.header) {
+                    header.clear_cache();
+                }
+                false
+            }
+            false => true,
+        });
+        folded != self.folds.len()
+    }
+
+    /// matches the folds with their header lines after edits (revision changed), true if folds were updated
+    /// header edited in place keeps the fold, folds with header not found or no longer opening a block are dropped
+    pub fn sync(&mut self, content: &mut [EditorLine], revision: usize) -> bool {
+        let synced = (content.len(), revision);
+        if self.synced == synced {
+            return false;
+        }
+        let same_len = self.synced.0 == content.len();
+        self.synced = synced;
+        if self.folds.is_empty() {
+            return false;
+        }
+        self.clear_headers(content);
+        let mut folds = std::mem::take(&mut self.folds)
+            .into_iter()
+            .filter_map(|fold| {
+                let header = find_line(content, fold.header, fold.hash)
+                    .or_else(|| Some(fold.header).filter(|header| same_len && *header < content.len()))?;
+                Some(Fold::new(header, block_end(content, header)?, content))
+            })
+            .collect::<Vec<_>>();
+        folds.sort_by_key(|fold| fold.header);
+        folds.dedup_by_key(|fold| fold.header);
+        self.folds = folds;
+        self.clear_headers(content);
+        true
+    }
+
+    #[inline]
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.hiding(line).is_some()
+    }
+
+    /// last hidden line with the summary of the block folded on the line
+    #[inline]
+    pub fn folded(&self, line: usize) -> Option<(usize, &str)> {
+        let fold = &self.folds[self.position(line).ok()?];
+        Some((fold.end, fold.summary.as_str()))
+    }
+
+    /// visible lines from the line (visible itself) up to the other one (excluded) - each takes single row
+    pub fn visible_rows(&self, from: usize, to: usize) -> usize {
+        let mut line = from;
+        let mut rows = 0;
+        while line < to {
+            rows += 1;
+            line = self.next_visible(line);
+        }
+        rows
+    }
+
+    /// row of the line counted from the visible line, None if the line is hidden
+    pub fn row_of(&self, from: usize, line: usize) -> Option<usize> {
+        (!self.is_hidden(line)).then(|| self.visible_rows(from, line))
+    }
+
+    /// visible line on the row counted from the visible line, can be past the content end
+    pub fn line_at_row(&self, from: usize, row: usize) -> usize {
+        (0..row).fold(from, |line, _| self.next_visible(line))
+    }
+
+    /// moves the first line in view to keep the cursor on screen, folded blocks take single row
+    /// the first line in view is never hidden
+    pub fn repositioning(&self, cursor: &mut Cursor) {
+        if let Some(fold) = self.hiding(cursor.at_line) {
+            cursor.at_line = fold.header;
+        }
+        if cursor.line < cursor.at_line {
+            cursor.at_line = cursor.line;
+            return;
+        }
+        let mut top = cursor.line;
+        let mut rows = 1;
+        while top > cursor.at_line && rows < cursor.max_rows {
+            top = self.prev_visible(top);
+            rows += 1;
+        }
+        if top > cursor.at_line {
+            cursor.at_line = top;
+        }
+    }
+
+    /// header and end of the outermost fold hiding the line
+    pub fn hiding_block(&self, line: usize) -> Option<(usize, usize)> {
+        self.hiding(line).map(|fold| (fold.header, fold.end))
+    }
+
+    fn hiding(&self, line: usize) -> Option<&Fold> {
+        self.folds.iter().take_while(|fold| fold.header < line).find(|fold| fold.hides(line))
+    }
+
+    #[inline]
+    fn next_visible(&self, line: usize) -> usize {
+        self.folded(line).map(|(end, _)| end).unwrap_or(line) + 1
+    }
+
+    #[inline]
+    fn prev_visible(&self, line: usize) -> usize {
+        let prev = line - 1;
+        self.hiding(prev).map(|fold| fold.header).unwrap_or(prev)
+    }
+
+    #[inline]
+    fn position(&self, header: usize) -> Result<usize, usize> {
+        self.folds.binary_search_by_key(&header, |fold| fold.header)
+    }
+
+    fn clear_headers(&self, content: &mut [EditorLine]) {
+        for fold in self.folds.iter() {
+            if let Some(line) = content.get_mut(fold.header) {
+                line.clear_cache();
+            }
+        }
+    }
+}
+
+/// last line of the block opened by the line - following lines with deeper indent (blank lines within)
+/// line at the header indent closing the bracket opened by the header is part of the block
+pub fn block_end(content: &[EditorLine], header: usize) -> Option<usize> {
+    let indent = indent_width(content.get(header)?)?;
+    let opens_bracket = content[header].content.trim_end().ends_with(OPENING);
+    let mut end = None;
+    for (idx, line) in content.iter().enumerate().skip(header + 1) {
+        match indent_width(line) {
+            None => continue,
+            Some(width) if width > indent => end = Some(idx),
+            Some(width) => {
+                if width == indent && end.is_some() && opens_bracket && line.content.trim_start().starts_with(CLOSING) {
+                    end = Some(idx);
+                }
+                break;
+            }
+        }
+    }
+    end
+}
+
+/// marker of the hidden lines followed by the closing line if it is folded (brace blocks)
+fn summary(content: &[EditorLine], header: usize, end: usize) -> String {
+    match indent_width(&content[end]) == indent_width(&content[header]) {
+        true => format!("{FOLDED} {}", content[end].content.trim()),
+        false => String::from(FOLDED),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{block_end, Folds};
+    use crate::workspace::{cursor::Cursor, line::EditorLine};
+
+    fn content(text: &str) -> Vec<EditorLine> {
+        text.lines().map(|line| EditorLine::from(line.to_owned())).collect()
+    }
+
+    const CODE: &str = "fn main() {
+    let data = vec![
+        1,
+        2,
+    ];
+
+    if data.is_empty() {
+        return;
+    }
+}
+def run():
+    pass
+
+x = 1";
+
+    #[test]
+    fn blocks_by_indent() {
+        let content = content(CODE);
+        assert_eq!(block_end(&content, 0), Some(9));
+        assert_eq!(block_end(&content, 1), Some(4));
+        assert_eq!(block_end(&content, 2), None);
+        assert_eq!(block_end(&content, 6), Some(8));
+        // no closing bracket - the block ends with the last indented line
+        assert_eq!(block_end(&content, 10), Some(11));
+        assert_eq!(block_end(&content, 12), None);
+        assert_eq!(block_end(&content, 13), None);
+    }
+
+    #[test]
+    fn folded_rows() {
+        let mut content = content(CODE);
+        let mut folds = Folds::default();
+        // inside of the block - the enclosing one is folded
+        assert_eq!(folds.fold(2, &mut content), Some(1));
+        assert_eq!(folds.folded(1), Some((4, " … ];")));
+        assert!(folds.is_hidden(4));
+        assert!(!folds.is_hidden(5));
+        assert_eq!(folds.visible_rows(0, 6), 3);
+        assert_eq!(folds.line_at_row(0, 2), 5);
+        // folded again - the parent is folded, nested one is kept
+        assert_eq!(folds.fold(1, &mut content), Some(0));
+        assert_eq!(folds.folded(0), Some((9, " … }")));
+        assert_eq!(folds.line_at_row(0, 1), 10);
+        assert_eq!(folds.visible_rows(0, 13), 4);
+        assert!(folds.unfold(0, &mut content));
+        assert!(folds.is_hidden(3));
+        assert!(!folds.unfold(0, &mut content));
+        // cursor jumps into the fold
+        assert!(folds.reveal(3, &mut content));
+        assert!(folds.is_empty());
+        assert_eq!(folds.fold_all(&mut content), 4);
+        assert_eq!(folds.folded(10), Some((11, " …")));
+        assert_eq!(folds.unfold_all(&mut content), 4);
+    }
+
+    #[test]
+    fn restored_headers() {
+        let mut content = content(CODE);
+        let mut folds = Folds::default();
+        folds.fold(1, &mut content);
+        folds.fold(10, &mut content);
+        assert_eq!(folds.headers(), vec![1, 10]);
+        let mut restored = Folds::default();
+        // only lines opening a block are folded
+        restored.restore(&[10, 2, 1, 40], &mut content);
+        assert_eq!(restored.headers(), vec![1, 10]);
+        assert_eq!(restored.folded(1), Some((4, " … ];")));
+    }
+
+    #[test]
+    fn folds_follow_edits() {
+        let mut content = content(CODE);
+        let mut folds = Folds::default();
+        folds.fold(6, &mut content);
+        folds.fold(10, &mut content);
+        content.insert(0, EditorLine::from(String::from("// header")));
+        content.insert(0, EditorLine::from(String::from("")));
+        assert!(folds.sync(&mut content, 1));
+        assert_eq!(folds.folded(8), Some((10, " … }")));
+        assert_eq!(folds.folded(12), Some((13, " …")));
+        // block of the fold removed
+        content.remove(13);
+        assert!(folds.sync(&mut content, 2));
+        assert_eq!(folds.folded(8), Some((10, " … }")));
+        assert!(folds.folded(12).is_none());
+        assert!(!folds.sync(&mut content, 2));
+    }
+
+    #[test]
+    fn folds_follow_edits_keeping_line_count() {
+        let mut content = content("a {\n    1\n}\nb {\n    2\n    3\n}");
+        let mut folds = Folds::default();
+        folds.fold(0, &mut content);
+        // block moved below the other one - the fold follows its header
+        content.rotate_left(3);
+        assert!(folds.sync(&mut content, 1));
+        assert!(folds.folded(0).is_none());
+        assert_eq!(folds.folded(4), Some((6, " … }")));
+        // header edited in place
+        content[4].insert_str(0, "x");
+        assert!(folds.sync(&mut content, 2));
+        assert_eq!(folds.folded(4), Some((6, " … }")));
+        // closing line changed to block content
+        content[6] = EditorLine::from(String::from("    }"));
+        assert!(folds.sync(&mut content, 3));
+        assert_eq!(folds.folded(4), Some((6, " …")));
+    }
+
+    #[test]
+    fn repositioning_counts_rows() {
+        let mut content = content(CODE);
+        let mut folds = Folds::default();
+        folds.fold(1, &mut content);
+        folds.fold(6, &mut content);
+        let mut cursor = Cursor::default();
+        cursor.max_rows = 4;
+        cursor.line = 10;
+        // rows: 0, 1 (folded), 5, 6 (folded), 9, 10
+        folds.repositioning(&mut cursor);
+        assert_eq!(cursor.at_line, 5);
+        cursor.at_line = 3;
+        cursor.line = 5;
+        folds.repositioning(&mut cursor);
+        assert_eq!(cursor.at_line, 1);
+    }
+}
@@ -1,9 +1,10 @@
 use super::status::RenderStatus;
 use super::EditorLine;
 use crate::{
+    configs::RenderWhitespace,
     global_state::GlobalState,
     render::{
-        backend::{color, BackendProtocol, Style},
+        backend::{color, BackendProtocol, Color, Style},
         layout::Line,
     },
     syntax::Lexer,
@@ -18,13 +19,102 @@ pub struct LineContext<'a> {
     line: usize,
     char: usize,
     select: Option<(CursorPosition, CursorPosition)>,
+    block: Option<(Range<usize>, Range<usize>)>,
+    /// sorted lines with annotations (marked in the gutter)
+    annotated: &'a [usize],
+    /// background of trailing whitespace, None if not highlighted
+    trailing_whitespace: Option<Color>,
+    /// cursor line the numbers are relative to, None for absolute numbers
+    relative_to: Option<usize>,
+    /// background of the cursor line (and its number), None if not highlighted
+    current_line: Option<Color>,
+    whitespace: RenderWhitespace,
+    /// text columns with a ruler
+    rulers: &'a [usize],
 }
 
+const ANNOTATION_MARK: &str = "•";
+pub const RULER: char = '│';
+
 impl<'a> LineContext<'a> {
     pub fn collect_context(lexer: &'a mut Lexer, cursor: &Cursor, line_number_offset: usize) -> Self {
         let line_number = cursor.at_line;
         let select = cursor.select_get();
-        Self { line: cursor.line - line_number, char: cursor.char, select, lexer, line_number, line_number_offset }
+        let block = cursor.block_get();
+        Self {
+            line: cursor.line - line_number,
+            char: cursor.char,
+            select,
+            block,
+            lexer,
+            line_number,
+            line_number_offset,
+            annotated: &[],
+            trailing_whitespace: None,
+            relative_to: None,
+            current_line: None,
+            whitespace: RenderWhitespace::None,
+            rulers: &[],
+        }
+    }
+
+    pub fn with_annotated(mut self, annotated: &'a [usize]) -> Self {
+        self.annotated = annotated;
+        self
+    }
+
+    pub fn with_trailing_whitespace(mut self, highlight: bool) -> Self {
+        self.trailing_whitespace = highlight.then_some(self.lexer.theme.trailing_whitespace);
+        self
+    }
+
+    /// numbers show the distance from the cursor line, the cursor line keeps its own number
+    pub fn with_relative_numbers(mut self, relative: bool) -> Self {
+        self.relative_to = relative.then_some(self.line_number + self.line);
+        self
+    }
+
+    pub fn with_current_line(mut self, highlight: bool) -> Self {
+        self.current_line = highlight.then_some(self.lexer.theme.current_line);
+        self
+    }
+
+    pub fn with_whitespace(mut self, whitespace: RenderWhitespace) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    pub fn with_rulers(mut self, rulers: &'a [usize]) -> Self {
+        self.rulers = rulers;
+        self
+    }
+
+    /// char index from which whitespace of the line is rendered as markers
+    #[inline]
+    pub fn whitespace_from(&self, line: &EditorLine) -> Option<usize> {
+        match self.whitespace {
+            RenderWhitespace::None => None,
+            RenderWhitespace::All => Some(0),
+            RenderWhitespace::Trailing => match line.is_simple() {
+                true => line.trailing_whitespace(),
+                false => line.trailing_whitespace().map(|start| line.content[..start].chars().count()),
+            },
+        }
+    }
+
+    #[inline(always)]
+    pub fn current_line(&self) -> Option<Color> {
+        self.current_line
+    }
+
+    #[inline(always)]
+    pub fn rulers(&self) -> &[usize] {
+        self.rulers
+    }
+
+    #[inline(always)]
+    pub fn trailing_whitespace(&self) -> Option<Color> {
+        self.trailing_whitespace
     }
 
     /// Ensures during deletion of lines, if scrolling has happened that last line will be rendered
@@ -54,23 +144,86 @@ impl<'a> LineContext<'a> {
     #[inline]
     pub fn setup_cursor(&mut self, line: Line, backend: &mut impl BackendProtocol) -> usize {
         self.line_number += 1;
-        let text = format!("{: >1$} ", self.line_number, self.line_number_offset);
+        let number_style = self.current_line.map(Style::bg).unwrap_or_default();
+        if self.is_annotated() {
+            let text = self.gutter_number();
+            let remaining_width = line.width - (text.len() + 1);
+            backend.print_styled_at(line.row, line.col, text, number_style);
+            let mut mark_style = Style::fg(color::yellow());
+            mark_style.set_bg(self.current_line);
+            backend.print_styled(ANNOTATION_MARK, mark_style);
+            backend.clear_to_eol();
+            self.render_rulers(line, remaining_width, self.current_line, backend);
+            return remaining_width;
+        }
+        let mut text = self.gutter_number();
+        text.push(' ');
         let remaining_width = line.width - text.len();
-        backend.print_at(line.row, line.col, text);
+        backend.print_styled_at(line.row, line.col, text, number_style);
         backend.clear_to_eol();
+        self.render_rulers(line, remaining_width, self.current_line, backend);
         remaining_width
     }
 
     #[inline]
     pub fn setup_line(&mut self, line: Line, backend: &mut impl BackendProtocol) -> usize {
         self.line_number += 1;
-        let text = format!("{: >1$} ", self.line_number, self.line_number_offset);
+        if self.is_annotated() {
+            let text = self.gutter_number();
+            let remaining_width = line.width - (text.len() + 1);
+            backend.print_styled_at(line.row, line.col, text, Style::fg(color::dark_grey()));
+            backend.print_styled(ANNOTATION_MARK, Style::fg(color::yellow()));
+            backend.clear_to_eol();
+            self.render_rulers(line, remaining_width, None, backend);
+            return remaining_width;
+        }
+        let mut text = self.gutter_number();
+        text.push(' ');
         let remaining_width = line.width - text.len();
         backend.print_styled_at(line.row, line.col, text, Style::fg(color::dark_grey()));
         backend.clear_to_eol();
+        self.render_rulers(line, remaining_width, None, backend);
         remaining_width
     }
 
+    /// rulers are drawn on the cleared line ahead of the text, so text reaching them covers them
+    fn render_rulers(&self, line: Line, text_width: usize, bg: Option<Color>, backend: &mut impl BackendProtocol) {
+        if self.rulers.is_empty() {
+            return;
+        }
+        let text_col = line.col + (line.width - text_width) as u16;
+        let mut style = Style::fg(color::dark_grey());
+        style.set_bg(bg);
+        for ruler in self.rulers.iter().filter(|ruler| **ruler < text_width) {
+            backend.print_styled_at(line.row, text_col + *ruler as u16, RULER, style);
+        }
+        backend.go_to(line.row, text_col);
+    }
+
+    /// number of the line set up last padded to the gutter width, empty if the numbers are hidden
+    #[inline]
+    fn gutter_number(&self) -> String {
+        match self.line_number_offset {
+            0 => String::new(),
+            offset => format!("{: >1$}", self.displayed_number(), offset),
+        }
+    }
+
+    /// number of the line set up last in the gutter
+    #[inline]
+    fn displayed_number(&self) -> usize {
+        match self.relative_to {
+            Some(cursor_line) if cursor_line + 1 != self.line_number => cursor_line.abs_diff(self.line_number - 1),
+            _ => self.line_number,
+        }
+    }
+
+    /// the line set up last has annotation
+    #[inline]
+    fn is_annotated(&self) -> bool {
+        !self.annotated.is_empty() && self.annotated.binary_search(&(self.line_number - 1)).is_ok()
+    }
+
     #[inline]
     pub fn wrap_line(&mut self, line: Line, backend: &mut impl BackendProtocol) {
         let text = format!("{: >1$} ", "", self.line_number_offset);
@@ -80,14 +233,28 @@ impl<'a> LineContext<'a> {
 
     #[inline]
     pub fn get_select(&self, width: usize) -> Option<Range<usize>> {
+        if let Some((lines, chars)) = self.block.as_ref() {
+            return build_block_buffer(lines, chars, self.line_number);
+        }
         build_select_buffer(self.select, self.line_number, width - (self.line_number_offset + 1))
     }
 
     #[inline]
     pub fn get_select_full_line(&self, char_len: usize) -> Option<Range<usize>> {
+        if let Some((lines, chars)) = self.block.as_ref() {
+            return build_block_buffer(lines, chars, self.line_number);
+        }
         build_select_buffer(self.select, self.line_number, char_len)
     }
 
+    /// wrapped lines shift the cursor on screen - modals are positioned relative to it
+    /// should be called after the cursor line is rendered
+    #[inline]
+    pub fn set_cursor_screen_position(&mut self, row: usize, col: usize) {
+        self.line = row;
+        self.char = col;
+    }
+
     #[inline]
     pub fn skip_line(&mut self) {
         self.line_number += 1;
@@ -121,3 +288,14 @@ pub fn build_select_buffer(
         (.., Ordering::Equal) => Some(0..to.char),
     })
 }
+
+/// empty column select is shown as one char wide, so that all cursors are visible
+fn build_block_buffer(lines: &Range<usize>, chars: &Range<usize>, at_line: usize) -> Option<Range<usize>> {
+    if !lines.contains(&at_line) {
+        return None;
+    }
+    match chars.is_empty() {
+        true => Some(chars.start..chars.start + 1),
+        false => Some(chars.clone()),
+    }
+}
@@ -0,0 +1,123 @@
+use super::EditorLine;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// files over the size are loaded as the view reaches the lines
+const LAZY_LOAD_BYTES: u64 = 4 * 1024 * 1024;
+
+/// filled with the lines of the file once parsed
+pub type ParseSlot = Arc<Mutex<Option<Result<Vec<EditorLine>, String>>>>;
+
+/// EditorLine::parse_lines on a blocking task - the slot is filled when done
+pub fn parse_in_background(path: PathBuf) -> ParseSlot {
+    let slot = ParseSlot::default();
+    let parsed = Arc::clone(&slot);
+    tokio::task::spawn_blocking(move || {
+        let lines = EditorLine::parse_lines(&path);
+        if let Ok(mut slot) = parsed.lock() {
+            slot.replace(lines);
+        }
+    });
+    slot
+}
+
+/// Reads lines of the file on demand - lines are split as in EditorLine::parse_lines
+pub struct LineLoader {
+    reader: BufReader<File>,
+    /// the file ends with new line (or is empty) - last line is empty
+    ends_with_newline: bool,
+    buffer: Vec<u8>,
+    /// reading failed - nothing more is loaded
+    error: Option<String>,
+}
+
+impl LineLoader {
+    /// small files are loaded at once (None loader), big ones up to lines
+    pub fn open(path: &Path, lines: usize) -> Result<(Vec<EditorLine>, Option<Self>), String> {
+        let size = std::fs::metadata(path).map_err(|err| err.to_string())?.len();
+        if size <= LAZY_LOAD_BYTES {
+            return Ok((EditorLine::parse_lines(path)?, None));
+        }
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let mut loader =
+            Self { reader: BufReader::new(file), ends_with_newline: true, buffer: Vec::new(), error: None };
+        let mut content = Vec::new();
+        match loader.load(&mut content, lines)? {
+            true => Ok((content, None)),
+            false => Ok((content, Some(loader))),
+        }
+    }
+
+    /// appends up to count lines, true if the end of the file was reached
+    pub fn load(&mut self, content: &mut Vec<EditorLine>, count: usize) -> Result<bool, String> {
+        if let Some(error) = self.error.as_ref() {
+            return Err(error.to_owned());
+        }
+        self.read_lines(content, count).map_err(|error| self.error.insert(error).to_owned())
+    }
+
+    #[inline]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn read_lines(&mut self, content: &mut Vec<EditorLine>, count: usize) -> Result<bool, String> {
+        for _ in 0..count {
+            self.buffer.clear();
+            let read = self.reader.read_until(b'\n', &mut self.buffer).map_err(|err| err.to_string())?;
+            if read == 0 {
+                if self.ends_with_newline {
+                    content.push(EditorLine::default());
+                }
+                return Ok(true);
+            }
+            self.ends_with_newline = self.buffer.last() == Some(&b'\n');
+            if self.ends_with_newline {
+                self.buffer.pop();
+            }
+            let text = std::str::from_utf8(&self.buffer).map_err(|err| err.to_string())?;
+            content.push(EditorLine::new(text.to_owned()));
+        }
+        Ok(false)
+    }
+
+    /// appends the rest of the file
+    pub fn load_all(&mut self, content: &mut Vec<EditorLine>) -> Result<(), String> {
+        while !self.load(content, usize::MAX)? {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LineLoader, LAZY_LOAD_BYTES};
+    use crate::workspace::line::EditorLine;
+
+    #[test]
+    fn lines_match_full_parse() {
+        let path = std::env::temp_dir().join(format!("idiom_line_loader_{}.txt", std::process::id()));
+        let line = "log entry with some text\r";
+        let count = LAZY_LOAD_BYTES as usize / line.len() + 10;
+        let text = (0..count).map(|idx| format!("{idx} {line}\n")).collect::<String>();
+        std::fs::write(&path, &text).unwrap();
+        let (mut content, loader) = LineLoader::open(&path, 100).unwrap();
+        let mut loader = loader.unwrap();
+        assert_eq!(content.len(), 100);
+        assert!(!loader.load(&mut content, 50).unwrap());
+        assert_eq!(content[149].to_string(), format!("149 {line}"));
+        loader.load_all(&mut content).unwrap();
+        let parsed = EditorLine::parse_lines(&path).unwrap();
+        assert_eq!(content.len(), count + 1);
+        assert!(content.iter().map(ToString::to_string).eq(parsed.iter().map(ToString::to_string)));
+        // small files are loaded at once
+        std::fs::write(&path, "one\ntwo").unwrap();
+        let (content, loader) = LineLoader::open(&path, 1).unwrap();
+        assert!(loader.is_none());
+        assert_eq!(content.len(), 2);
+        let _ = std::fs::remove_file(path);
+    }
+}
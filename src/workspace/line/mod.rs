@@ -1,12 +1,16 @@
 mod context;
+mod loader;
 mod status;
 use status::RenderStatus;
+#[cfg(test)]
+pub use status::SKIP_RESET;
 
 use crate::{
     render::{utils::UTF8SafeStringExt, UTF8Safe},
-    syntax::{tokens::TokenLine, DiagnosticLine, Lang, Token},
+    syntax::{tokens::TokenLine, DiagnosticLine, Lang, LineHint, Token},
 };
-pub use context::LineContext;
+pub use context::{LineContext, RULER};
+pub use loader::{parse_in_background, LineLoader, ParseSlot};
 use std::{
     fmt::Display,
     ops::{Index, Range, RangeFrom, RangeFull, RangeTo},
@@ -22,6 +26,8 @@ pub struct EditorLine {
     // syntax
     pub tokens: TokenLine,
     pub diagnostics: Option<DiagnosticLine>,
+    /// LSP inlay hints, dropped on any change of the content
+    pub inlay_hints: Vec<LineHint>,
     // used for caching - 0 is reseved for file tabs and can be used to reset line
     pub cached: RenderStatus,
 }
@@ -72,7 +78,10 @@ impl EditorLine {
 
     #[inline]
     pub fn replace_till(&mut self, to: usize, string: &str) {
-        self.cached.reset();
+        self.content_changed();
+        let inserted = string.char_len();
+        self.tokens.increment_at_by(0, inserted);
+        self.tokens.remove_range(inserted, to + inserted);
         if self.content.len() == self.char_len {
             self.char_len += string.char_len();
             self.char_len -= to;
@@ -85,7 +94,8 @@ impl EditorLine {
 
     #[inline]
     pub fn replace_from(&mut self, from: usize, string: &str) {
-        self.cached.reset();
+        self.content_changed();
+        self.tokens.remove_range(from, self.char_len);
         if self.content.len() == self.char_len {
             self.char_len = from + string.char_len();
             self.content.truncate(from);
@@ -97,7 +107,10 @@ impl EditorLine {
 
     #[inline]
     pub fn replace_range(&mut self, range: Range<usize>, string: &str) {
-        self.cached.reset();
+        self.content_changed();
+        let inserted = string.char_len();
+        self.tokens.increment_at_by(range.start, inserted);
+        self.tokens.remove_range(range.start + inserted, range.end + inserted);
         if self.char_len == self.content.len() {
             self.char_len += string.char_len();
             self.char_len -= range.len();
@@ -125,7 +138,7 @@ impl EditorLine {
 
     #[inline]
     pub fn insert(&mut self, idx: usize, ch: char) {
-        self.cached.reset();
+        self.content_changed();
         self.tokens.increment_at(idx);
         if self.char_len == self.content.len() {
             // base update on delta start
@@ -139,7 +152,7 @@ impl EditorLine {
 
     #[inline]
     pub fn push(&mut self, ch: char) {
-        self.cached.reset();
+        self.content_changed();
         self.tokens.increment_at(self.char_len);
         self.char_len += 1;
         self.content.push(ch);
@@ -147,7 +160,8 @@ impl EditorLine {
 
     #[inline]
     pub fn insert_str(&mut self, idx: usize, string: &str) {
-        self.cached.reset();
+        self.content_changed();
+        self.tokens.increment_at_by(idx, string.char_len());
         if self.char_len == self.content.len() {
             self.char_len += string.char_len();
             self.content.insert_str(idx, string);
@@ -159,14 +173,14 @@ impl EditorLine {
 
     #[inline]
     pub fn push_str(&mut self, string: &str) {
-        self.cached.reset();
+        self.content_changed();
         self.char_len += string.char_len();
         self.content.push_str(string);
     }
 
     #[inline]
     pub fn push_line(&mut self, line: Self) {
-        self.cached.reset();
+        self.content_changed();
         self.char_len += line.char_len;
         self.content.push_str(&line.content)
     }
@@ -183,7 +197,7 @@ impl EditorLine {
 
     #[inline]
     pub fn remove(&mut self, idx: usize) -> char {
-        self.cached.reset();
+        self.content_changed();
         self.tokens.decrement_at(idx);
         if self.content.len() == self.char_len {
             self.char_len -= 1;
@@ -203,6 +217,16 @@ impl EditorLine {
         self.content.trim_end()
     }
 
+    /// byte index where trailing whitespace starts, lines of whitespace only start at 0
+    #[inline]
+    pub fn trailing_whitespace(&self) -> Option<usize> {
+        let code_len = self.content.trim_end().len();
+        if code_len < self.content.len() {
+            return Some(code_len);
+        }
+        None
+    }
+
     #[inline]
     pub fn chars(&self) -> std::str::Chars<'_> {
         self.content.chars()
@@ -223,12 +247,12 @@ impl EditorLine {
         self.tokens.clear();
         self.content.clear();
         self.char_len = 0;
-        self.cached.reset();
+        self.content_changed();
     }
 
     #[inline]
     pub fn split_off(&mut self, at: usize) -> Self {
-        self.cached.reset();
+        self.content_changed();
         if self.content.len() == self.char_len {
             let content = self.content.split_off(at);
             if !content.is_empty() {
@@ -374,10 +398,45 @@ impl EditorLine {
         };
     }
 
+    #[inline]
+    pub fn set_inlay_hints(&mut self, hints: Vec<LineHint>) {
+        if self.inlay_hints != hints {
+            self.inlay_hints = hints;
+            self.cached.reset();
+        }
+    }
+
+    /// total width taken by the hints when rendered
+    #[inline]
+    pub fn inlay_hints_width(&self) -> usize {
+        self.inlay_hints.iter().map(|hint| hint.width).sum()
+    }
+
+    /// char under the screen column of the line rendered with its hints, columns on hint map to the char after it
+    pub fn char_at_hinted_column(&self, column: usize) -> usize {
+        let mut shift = 0;
+        for hint in self.inlay_hints.iter() {
+            if hint.char_idx + shift >= column {
+                break;
+            }
+            shift += hint.width;
+            if column < hint.char_idx + shift {
+                return hint.char_idx;
+            }
+        }
+        column - shift
+    }
+
     #[inline]
     pub fn clear_cache(&mut self) {
         self.cached.reset();
     }
+
+    #[inline]
+    fn content_changed(&mut self) {
+        self.cached.reset();
+        self.inlay_hints.clear();
+    }
 }
 
 impl Display for EditorLine {
@@ -1,7 +1,13 @@
 use std::{ops::Range, str::Chars};
 use unicode_width::UnicodeWidthChar;
 
-#[derive(Default)]
+#[cfg(test)]
+thread_local! {
+    /// breaks invalidation of line caches - lets tests make sure the render check catches stale lines
+    pub static SKIP_RESET: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+#[derive(Debug, Default)]
 pub enum RenderStatus {
     Cursor {
         line: u16,
@@ -20,9 +26,21 @@ pub enum RenderStatus {
 impl RenderStatus {
     #[inline(always)]
     pub fn reset(&mut self) {
+        #[cfg(test)]
+        if SKIP_RESET.get() {
+            return;
+        }
         *self = Self::None;
     }
 
+    /// screen row the line was rendered on
+    pub fn row(&self) -> Option<u16> {
+        match self {
+            Self::Cursor { line, .. } | Self::Line { line, .. } => Some(*line),
+            Self::None => None,
+        }
+    }
+
     #[inline(always)]
     pub fn line(&mut self, line: u16, select: Option<Range<usize>>) {
         *self = Self::Line { line, select }
@@ -1,40 +1,89 @@
 pub mod actions;
+pub mod annotations;
 pub mod cursor;
 pub mod editor;
+pub mod folds;
 pub mod line;
+pub mod project_search;
 pub mod renderer;
+pub mod session;
+mod snapshot;
+pub mod snippet;
+mod split;
+mod tab_bar;
 pub mod utils;
 use crate::{
-    configs::{EditorAction, EditorConfigs, EditorKeyMap, FileType},
+    configs::{AutoSave, EditorAction, EditorConfigs, EditorKeyMap, FileType, SearchOptions},
     error::{IdiomError, IdiomResult},
-    global_state::{GlobalState, IdiomEvent},
-    lsp::LSP,
-    popups::popups_editor::file_updated,
-    render::backend::{color, BackendProtocol, Style},
+    global_state::{GlobalState, IdiomEvent, KeyboardHint},
+    instance::{Peer, Request, Response},
+    lsp::{LSPClient, LSP},
+    popups::{
+        popup_large_file::LargeFilePopup,
+        popup_recent::RecentFilesPopup,
+        popup_references::ReferencesPopup,
+        popup_rename::RenamePreview,
+        popups_editor::{
+            annotation_popup, annotation_view, annotations_list, close_pinned, file_updated, import_skipped,
+            instance_file, protected_file, save_failures, shared_file,
+        },
+    },
+    render::{
+        backend::{color, BackendProtocol, Style},
+        UTF8Safe,
+    },
+    syntax::modal::LSPModal,
+    tree::TreeOrder,
     utils::TrackedList,
 };
+use annotations::Annotations;
 use crossterm::event::KeyEvent;
 pub use cursor::CursorPosition;
 pub use editor::Editor;
-use lsp_types::{DocumentChangeOperation, DocumentChanges, OneOf, ResourceOp, TextDocumentEdit, WorkspaceEdit};
+use editor::{is_big_file, is_medium_file, AttributeBlock, SaveResult};
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, Location, OneOf, ResourceOp, TextDocumentEdit, WorkspaceEdit,
+};
+use project_search::{replace_in_file, search_file, search_lines, FileMatches, LineMatcher};
+use session::Session;
+use snapshot::{ImportReport, Snapshot, CONTEXT_FILE};
+use split::SplitView;
 use std::{
+    borrow::Cow,
     collections::{hash_map::Entry, HashMap},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::Duration,
 };
+use tab_bar::{TabBar, TabHit, LEFT_CLIPPED, RIGHT_CLIPPED, SEPARATOR};
+use utils::{requested_path, FileId};
+
+const PIN: &str = "▪ ";
 
 /// implement Drop to attempt keep state upon close/crash
 pub struct Workspace {
+    /// ordered as the tab bar - pinned editors first
     editors: TrackedList<Editor>,
+    /// index of the active editor (focused pane of split)
+    active: usize,
     base_config: EditorConfigs,
     key_map: EditorKeyMap,
     tab_style: Style,
+    tab_bar: TabBar,
     lsp_servers: HashMap<FileType, LSP>,
+    session: Session,
+    annotations: Annotations,
+    split: Option<SplitView>,
+    /// instance holding the workspace lock - set if running standalone next to it
+    peer: Option<Peer>,
     map_callback: fn(&mut Self, &KeyEvent, &mut GlobalState) -> bool,
 }
 
 impl Workspace {
     pub async fn new(key_map: EditorKeyMap, base_tree_paths: Vec<String>, gs: &mut GlobalState) -> Self {
         let mut base_config = gs.unwrap_or_default(EditorConfigs::new(), ".config: ");
+        gs.configure_terminal(base_config.color_support.as_deref(), base_config.mouse);
+        gs.clipboard.set_history_size(base_config.clipboard_history);
+        gs.clipboard.set_provider(base_config.clipboard_provider);
         let mut lsp_servers = HashMap::new();
         for (ft, lsp_cmd) in base_config.derive_lsp_preloads(base_tree_paths, gs) {
             gs.success(format!("Preloading {lsp_cmd}"));
@@ -46,23 +95,51 @@ impl Workspace {
             }
         }
         let tab_style = Style::fg(color::dark_yellow());
-        Self { editors: TrackedList::new(), base_config, key_map, lsp_servers, map_callback: map_editor, tab_style }
+        Self {
+            editors: TrackedList::new(),
+            active: 0,
+            base_config,
+            key_map,
+            lsp_servers,
+            session: Session::load(),
+            annotations: Annotations::load(),
+            split: None,
+            peer: None,
+            map_callback: map_editor,
+            tab_style,
+            tab_bar: TabBar::default(),
+        }
     }
 
     pub fn render(&mut self, gs: &mut GlobalState) {
-        if let Some(editor) = self.editors.get_mut(0) {
+        if let Some(active_editor) = self.editors.get(self.active) {
             let line = match gs.tab_area.into_iter().next() {
                 Some(line) => line,
                 None => return,
             };
+            let labels = self.editors.iter().map(tab_label).collect::<Vec<_>>();
+            let widths = labels.iter().map(|label| label.width()).collect::<Vec<_>>();
+            let visible = self.tab_bar.visible(&widths, self.active, &active_editor.path, line.width);
             gs.writer.set_style(Style::underlined(None));
             {
                 let mut builder = line.unsafe_builder(&mut gs.writer);
-                builder.push_styled(&editor.display, self.tab_style);
-                for editor in self.editors.iter().skip(1) {
-                    if !builder.push(" | ") || !builder.push(&editor.display) {
+                if visible.start > 0 {
+                    builder.push(LEFT_CLIPPED);
+                }
+                for position in visible.clone() {
+                    if position != visible.start && !builder.push(SEPARATOR) {
                         break;
+                    }
+                    let pushed = match position == self.active {
+                        true => builder.push_styled(&labels[position], self.tab_style),
+                        false => builder.push(&labels[position]),
                     };
+                    if !pushed {
+                        break;
+                    }
+                }
+                if visible.end < labels.len() {
+                    builder.push(RIGHT_CLIPPED);
                 }
             }
             gs.writer.reset_style();
@@ -77,6 +154,101 @@ impl Workspace {
         }
     }
 
+    pub fn render_editors(&mut self, gs: &mut GlobalState) {
+        match self.split.as_mut() {
+            Some(split) => split.render(self.editors.inner_mut_no_update(), self.active, gs, true),
+            None => {
+                if let Some(editor) = self.editors.get_mut_no_update(self.active) {
+                    editor.render(gs);
+                }
+            }
+        }
+    }
+
+    /// returns false if there is nothing to render
+    pub fn fast_render_editors(&mut self, gs: &mut GlobalState) -> bool {
+        match self.split.as_mut() {
+            Some(split) => split.render(self.editors.inner_mut_no_update(), self.active, gs, false),
+            None => match self.editors.get_mut_no_update(self.active) {
+                Some(editor) => editor.fast_render(gs),
+                None => return false,
+            },
+        }
+        true
+    }
+
+    pub fn toggle_split(&mut self, gs: &mut GlobalState) {
+        if self.split.is_some() {
+            self.collapse_split(gs);
+            return;
+        }
+        if self.editors.len() < 2 {
+            gs.error("Split view requires at least two open editors!");
+            return;
+        }
+        self.split = Some(SplitView::new((self.active + 1) % self.editors.len()));
+        gs.force_screen_rebuild();
+    }
+
+    fn collapse_split(&mut self, gs: &mut GlobalState) {
+        let Some(split) = self.split.take() else {
+            return;
+        };
+        self.resize_all(gs.editor_area.width, gs.editor_area.height as usize);
+        for idx in [self.active, split.other] {
+            if let Some(editor) = self.editors.get_mut(idx) {
+                editor.invalidate_render("split change");
+            }
+        }
+        gs.force_screen_rebuild();
+    }
+
+    pub fn focus_other_split(&mut self, gs: &mut GlobalState) {
+        let Some(split) = self.split.as_mut() else {
+            return;
+        };
+        if self.editors.len() < 2 {
+            self.collapse_split(gs);
+            return;
+        }
+        split.swap_focus();
+        let previous = std::mem::replace(&mut self.active, split.other);
+        split.other = previous;
+        for idx in [self.active, previous] {
+            if let Some(editor) = self.editors.get_mut(idx) {
+                editor.invalidate_render("split change");
+            }
+        }
+        if let Some(editor) = self.editors.get_mut_no_update(self.active) {
+            gs.event.push(IdiomEvent::SelectPath(editor.path.clone()));
+            if editor.update_status.collect() {
+                gs.popup(file_updated(editor.path.clone()));
+            }
+        }
+    }
+
+    /// maps screen position to the active editor, clicking on the other pane of a split moves the focus to it
+    pub fn mouse_position(&mut self, row: u16, column: u16, gs: &mut GlobalState) -> Option<CursorPosition> {
+        let Some(split) = self.split.as_ref() else {
+            return gs.editor_area.relative_position(row, column);
+        };
+        let (focused, position) = split.position(gs.editor_area, row, column)?;
+        if !focused {
+            self.focus_other_split(gs);
+        }
+        Some(position)
+    }
+
+    /// same as mouse_position, but does not change focus (used while dragging)
+    pub fn mouse_position_focused(&self, row: u16, column: u16, gs: &GlobalState) -> Option<CursorPosition> {
+        match self.split.as_ref() {
+            Some(split) => {
+                split.position(gs.editor_area, row, column).and_then(|(focused, pos)| focused.then_some(pos))
+            }
+            None => gs.editor_area.relative_position(row, column),
+        }
+    }
+
     pub fn map(&mut self, key: &KeyEvent, gs: &mut GlobalState) -> bool {
         (self.map_callback)(self, key, gs)
     }
@@ -98,6 +270,9 @@ impl Workspace {
         for editor in self.editors.iter_mut() {
             editor.resize(width, height);
         }
+        if let Some(split) = self.split.as_mut() {
+            split.reset();
+        }
     }
 
     #[inline]
@@ -109,9 +284,152 @@ impl Workspace {
         self.editors.iter().map(|editor| editor.display.to_owned()).collect()
     }
 
+    /// pinned editor is moved after the other pinned ones, unpinned before the rest - nothing else moves
+    pub fn toggle_pin(&mut self, gs: &mut GlobalState) {
+        let pinned = self.pinned_count();
+        let Some(editor) = self.editors.get_mut(self.active) else {
+            return;
+        };
+        editor.pinned = !editor.pinned;
+        match editor.pinned {
+            true => gs.success(format!("Pinned {}", editor.display)),
+            false => gs.success(format!("Unpinned {}", editor.display)),
+        }
+        let to = match editor.pinned {
+            true => pinned,
+            false => pinned - 1,
+        };
+        self.move_editor(self.active, to);
+    }
+
+    fn pinned_count(&self) -> usize {
+        self.editors.iter().take_while(|editor| editor.pinned).count()
+    }
+
+    /// indexes of the active editor and the split pane follow the moved editor
+    fn move_editor(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        let editor = self.editors.remove(from);
+        self.editors.insert(to, editor);
+        self.active = moved_index(self.active, from, to);
+        if let Some(split) = self.split.as_mut() {
+            split.other = moved_index(split.other, from, to);
+        }
+    }
+
+    /// new editors are opened in front of the unpinned tabs and activated
+    fn insert_editor(&mut self, editor: Editor) {
+        let idx = self.pinned_count();
+        self.editors.insert(idx, editor);
+        if let Some(split) = self.split.as_mut() {
+            if split.other >= idx {
+                split.other += 1;
+            }
+        }
+        self.active = idx;
+    }
+
+    fn next_tab(&mut self, gs: &mut GlobalState) {
+        if self.editors.len() < 2 {
+            return;
+        }
+        self.activate_editor((self.active + 1) % self.editors.len(), gs);
+    }
+
+    fn prev_tab(&mut self, gs: &mut GlobalState) {
+        if self.editors.len() < 2 {
+            return;
+        }
+        self.activate_editor((self.active + self.editors.len() - 1) % self.editors.len(), gs);
+    }
+
     #[inline(always)]
     pub fn get_active(&mut self) -> Option<&mut Editor> {
-        self.editors.get_mut_no_update(0)
+        self.editors.get_mut_no_update(self.active)
+    }
+
+    /// matching lines per file - open editors are searched in their current (unsaved) content
+    /// binary, big and non UTF-8 files are skipped
+    pub fn find_in_files(
+        &self,
+        files: &[PathBuf],
+        pattern: &str,
+        options: SearchOptions,
+    ) -> Result<Vec<FileMatches>, regex::Error> {
+        let matcher = LineMatcher::new(pattern, options)?;
+        Ok(self.find_matching(files, &matcher))
+    }
+
+    fn find_matching(&self, files: &[PathBuf], matcher: &LineMatcher) -> Vec<FileMatches> {
+        files
+            .iter()
+            .filter_map(|path| match self.editors.iter().find(|editor| &editor.path == path) {
+                Some(editor) => search_lines(path, editor.content.iter().map(|line| &line[..]), matcher),
+                None => search_file(path, matcher),
+            })
+            .collect()
+    }
+
+    /// replaces in every matching file (not only in the listed results)
+    /// open editors are edited (the change can be undone and is saved by the user), other files are written
+    /// failures are reported per file and the rest is still replaced
+    pub fn replace_in_files(
+        &mut self,
+        files: &[PathBuf],
+        pattern: &str,
+        options: SearchOptions,
+        new_text: &str,
+        gs: &mut GlobalState,
+    ) {
+        let matcher = match LineMatcher::new(pattern, options) {
+            Ok(matcher) => matcher,
+            Err(err) => return gs.error(err.to_string()),
+        };
+        let mut replaced = 0;
+        let mut changed_files = 0;
+        for FileMatches { path, .. } in self.find_matching(files, &matcher) {
+            let count = match self.editors.find(|editor| editor.path == path) {
+                Some(editor) => {
+                    let mut ranges = Vec::new();
+                    editor.find_with(pattern, options, &mut ranges);
+                    let count = ranges.len();
+                    if count != 0 {
+                        editor.mass_replace(ranges, new_text.to_owned());
+                    }
+                    count
+                }
+                None => match replace_in_file(&path, &matcher, new_text) {
+                    Ok(count) => count,
+                    Err(err) => {
+                        gs.error(format!("{}: {err}", path.display()));
+                        continue;
+                    }
+                },
+            };
+            if count != 0 {
+                replaced += count;
+                changed_files += 1;
+            }
+        }
+        gs.success(format!("Replaced {replaced} matches in {changed_files} files"));
+    }
+
+    /// clients of all running LSP servers and of local LSPs handling open editors
+    pub fn lsp_clients(&self) -> Vec<LSPClient> {
+        let local_clients = self.editors.iter().filter_map(|editor| editor.lexer.local_client());
+        self.lsp_servers.values().map(LSP::aquire_client).chain(local_clients).collect()
+    }
+
+    /// client of the active file LSP if it supports workspace symbols
+    pub fn workspace_symbols_client(&mut self) -> Option<LSPClient> {
+        let file_type = self.get_active()?.file_type;
+        let client = self.lsp_servers.get(&file_type)?.aquire_client();
+        match client.capabilities.workspace_symbol_provider {
+            None | Some(OneOf::Left(false)) => None,
+            Some(..) => Some(client),
+        }
     }
 
     #[inline]
@@ -135,20 +453,26 @@ impl Workspace {
         }
     }
 
+    /// tabs keep their order, the editor in the other split pane takes the focus
     pub fn activate_editor(&mut self, idx: usize, gs: &mut GlobalState) {
-        if idx < self.editors.len() {
-            let mut editor = self.editors.remove(idx);
-            editor.clear_screen_cache(gs);
-            gs.event.push(IdiomEvent::SelectPath(editor.path.clone()));
-            if editor.update_status.collect() {
-                gs.popup(file_updated(editor.path.clone()))
-            }
-            self.editors.insert(0, editor);
+        if idx >= self.editors.len() {
+            return;
+        }
+        if self.split.as_ref().is_some_and(|split| split.other == idx) {
+            return self.focus_other_split(gs);
+        }
+        self.active = idx;
+        let editor = &mut self.editors.inner_mut()[idx];
+        editor.clear_screen_cache(gs);
+        gs.event.push(IdiomEvent::SelectPath(editor.path.clone()));
+        if editor.update_status.collect() {
+            gs.popup(file_updated(editor.path.clone()))
         }
     }
 
+    /// changes are applied only if there are no document changes (both are the same edit by LSP spec)
     pub fn apply_edits(&mut self, edits: WorkspaceEdit, gs: &mut GlobalState) {
-        if let Some(edits) = edits.changes {
+        if let (Some(edits), None) = (edits.changes, edits.document_changes.as_ref()) {
             for (file_url, file_edits) in edits {
                 if let Some(editor) = self.get_editor(file_url.path().as_str()) {
                     editor.apply_file_edits(file_edits);
@@ -268,11 +592,21 @@ impl Workspace {
                 }
             }
         };
+        // LSP is attached once the lines are set (set_parsed_files)
+        if is_medium_file(&file_path) {
+            return Editor::from_path_background(file_path, file_type, &self.base_config, gs);
+        }
         let mut new = Editor::from_path(file_path, file_type, &self.base_config, gs)?;
+        self.attach_lsp(&mut new, gs).await;
+        Ok(new)
+    }
+
+    async fn attach_lsp(&mut self, new: &mut Editor, gs: &mut GlobalState) {
+        let file_type = new.file_type;
         let lsp_cmd = match self.base_config.derive_lsp(&new.file_type) {
             None => {
                 new.lexer.local_lsp(file_type, new.stringify(), gs);
-                return Ok(new);
+                return;
             }
             Some(cmd) => cmd,
         };
@@ -284,7 +618,7 @@ impl Workspace {
                 Ok(lsp) => {
                     let client = lsp.aquire_client();
                     new.lexer.set_lsp_client(client, new.stringify(), gs);
-                    for editor in self.editors.iter_mut().filter(|e| e.file_type == new.file_type) {
+                    for editor in self.editors.iter_mut().filter(|e| e.file_type == new.file_type && !e.is_loading()) {
                         editor.lexer.set_lsp_client(lsp.aquire_client(), editor.stringify(), gs);
                     }
                     entry.insert(lsp);
@@ -298,45 +632,372 @@ impl Workspace {
                 new.lexer.set_lsp_client(entry.get().aquire_client(), new.stringify(), gs);
             }
         }
-        Ok(new)
     }
 
-    pub async fn new_from(&mut self, file_path: PathBuf, gs: &mut GlobalState) -> IdiomResult<bool> {
-        let file_path = file_path.canonicalize()?;
-        if let Some(idx) = self.editors.iter().position(|e| e.path == file_path) {
-            let mut editor = self.editors.remove(idx);
+    /// swaps in the lines of files parsed in the background, failed files are closed
+    pub fn set_parsed_files(&mut self, gs: &mut GlobalState) {
+        let mut idx = 0;
+        while let Some(editor) = self.editors.get_mut_no_update(idx) {
+            match editor.take_parsed() {
+                None => idx += 1,
+                Some(Ok(content)) => {
+                    editor.set_parsed(content, &self.base_config);
+                    self.session.restore(editor);
+                    self.annotations.restore(editor);
+                    if !editor.apply_pending_jump() {
+                        gs.error("Unable to resolve file anchor!");
+                    }
+                    gs.event.push(IdiomEvent::FileParsed(editor.path.clone()));
+                    idx += 1;
+                }
+                Some(Err(error)) => {
+                    gs.error(format!("Failed to load {}: {error}", editor.display));
+                    self.close_editor(idx, gs);
+                }
+            }
+        }
+    }
+
+    /// results of formatters (external and format on save) - editors are formatted and saved in the background
+    pub fn poll_formats(&mut self, gs: &mut GlobalState) {
+        for editor in self.editors.inner_mut_no_update().iter_mut() {
+            editor.poll_external_format(gs);
+            editor.poll_format_save(gs);
+        }
+    }
+
+    /// LSP of the file parsed in the background
+    pub async fn parsed_file(&mut self, path: PathBuf, gs: &mut GlobalState) {
+        let Some(idx) = self.editors.iter().position(|editor| editor.path == path && !editor.is_loading()) else {
+            return;
+        };
+        let mut editor = self.editors.remove(idx);
+        self.attach_lsp(&mut editor, gs).await;
+        self.editors.insert(idx, editor);
+        if let Some(editor) = self.get_active() {
             editor.clear_screen_cache(gs);
-            if editor.update_status.collect() {
-                gs.popup(file_updated(editor.path.clone()));
+        }
+    }
+
+    pub async fn new_from(&mut self, file_path: PathBuf, gs: &mut GlobalState) -> IdiomResult<Opened> {
+        let requested = requested_path(&file_path)?;
+        let file_path = file_path.canonicalize()?;
+        if self.activate_open(&requested, &file_path, gs) {
+            return Ok(Opened::Existing);
+        }
+        if is_big_file(&file_path) {
+            gs.popup(LargeFilePopup::boxed(file_path)?);
+            gs.message("File over 50MB - opened read-only");
+            return Ok(Opened::ReadOnly);
+        }
+        let mut editor = self.build_editor(file_path, gs).await?;
+        self.session.opened(&editor.path);
+        if !editor.is_loading() {
+            self.session.restore(&mut editor);
+            self.annotations.restore(&mut editor);
+        }
+        self.insert_editor(editor);
+        self.toggle_editor();
+        if self.editors.len() > 1 {
+            gs.keyboard_hint(KeyboardHint::Tabs);
+        }
+        Ok(Opened::New)
+    }
+
+    /// activates the editor of the file if it is open, the tab shows the path it was requested by
+    fn activate_open(&mut self, requested: &Path, path: &Path, gs: &mut GlobalState) -> bool {
+        let Some(idx) = self.find_open(path) else {
+            return false;
+        };
+        self.editors.inner_mut()[idx].set_display_path(requested);
+        self.activate_editor(idx, gs);
+        true
+    }
+
+    /// index of the editor with the file - matched by canonical path or by the identity of the file on disk
+    fn find_open(&self, path: &Path) -> Option<usize> {
+        if let Some(idx) = self.editors.iter().position(|e| e.path == path) {
+            return Some(idx);
+        }
+        let id = FileId::of(path)?;
+        self.editors.iter().position(|e| FileId::of(&e.path).as_ref() == Some(&id))
+    }
+
+    /// path of another editor writing to the same file on disk as the editor at idx
+    fn shared_with(&self, idx: usize) -> Option<PathBuf> {
+        let id = FileId::of(&self.editors.get(idx)?.path)?;
+        self.editors
+            .iter()
+            .enumerate()
+            .find(|(other_idx, e)| *other_idx != idx && FileId::of(&e.path).as_ref() == Some(&id))
+            .map(|(_, e)| e.path.clone())
+    }
+
+    pub fn set_peer(&mut self, peer: Peer) {
+        self.peer = Some(peer);
+    }
+
+    /// sends the file from the command line to the instance holding the lock
+    pub fn forward_to_peer(&mut self) -> IdiomResult<()> {
+        match self.peer.as_mut() {
+            Some(peer) => Ok(peer.forward_pending()?),
+            None => Err(IdiomError::any("No other instance running!")),
+        }
+    }
+
+    pub fn take_peer_pending(&mut self) -> Option<(PathBuf, crate::cli::FileAnchor)> {
+        self.peer.as_mut().and_then(Peer::take_pending)
+    }
+
+    /// answers another instance started in the workspace
+    pub fn instance_request(&self, request: Request, gs: &mut GlobalState) -> Response {
+        match request {
+            Request::Ping => Response::Pong {
+                pid: std::process::id(),
+                open: self.editors.iter().map(|editor| editor.path.clone()).collect(),
+            },
+            Request::Open { path, line } => {
+                gs.message(format!("Opened {} from another instance", path.display()));
+                gs.event.push(IdiomEvent::OpenAtLine(path, line));
+                Response::Opened
             }
-            self.editors.insert(0, editor);
-            return Ok(false);
         }
-        let editor = self.build_editor(file_path, gs).await?;
-        self.editors.insert(0, editor);
+    }
+
+    /// opens note editor for the cursor line of the active editor
+    pub fn annotate_line(&mut self, gs: &mut GlobalState) {
+        let Some(editor) = self.get_active() else {
+            return;
+        };
+        let line = editor.cursor.line;
+        let text = editor.annotations.get(line).map(|note| note.text.to_owned()).unwrap_or_default();
+        gs.popup(annotation_popup(line, text));
+    }
+
+    /// sets the note on the cursor line of the active editor, empty text removes it
+    pub fn set_annotation(&mut self, text: String, gs: &mut GlobalState) {
+        let Some(editor) = self.editors.get_mut(self.active) else {
+            return;
+        };
+        let removed = text.is_empty();
+        editor.annotations.set(editor.cursor.line, text, &mut editor.content);
+        self.annotations.store(editor);
+        match self.annotations.save() {
+            Err(error) => gs.error(format!("Failed to save annotations: {error}")),
+            Ok(()) if removed => gs.message("Note removed"),
+            Ok(()) => gs.success("Note saved"),
+        }
+    }
+
+    pub fn show_annotation(&mut self, gs: &mut GlobalState) {
+        let Some(editor) = self.get_active() else {
+            return;
+        };
+        match editor.annotations.get(editor.cursor.line) {
+            Some(note) => gs.popup(annotation_view(note.line, note.text.to_owned())),
+            None => gs.message("No note on the line"),
+        }
+    }
+
+    /// lists notes of the project, open editors are matched against the current content
+    pub fn list_annotations(&mut self, gs: &mut GlobalState) {
+        for editor in self.editors.iter() {
+            self.annotations.store(editor);
+        }
+        let list = self.annotations.list();
+        if list.is_empty() {
+            return gs.message("No notes in the project");
+        }
+        gs.popup(annotations_list(list));
+    }
+
+    /// definition is peeked from the content of the open editor (unsaved changes with its tokens),
+    /// files that are not open are read - the preview is shown in the active editor
+    pub fn peek_definition(&mut self, location: Location, gs: &mut GlobalState) {
+        let path = PathBuf::from(location.uri.path().as_str());
+        let open = self
+            .editors
+            .iter()
+            .find(|editor| editor.path == path)
+            .and_then(|editor| LSPModal::peek_content(location.clone(), &editor.content, false));
+        let Some(editor) = self.get_active() else {
+            return;
+        };
+        match open.or_else(|| LSPModal::peek_file(location, &editor.lexer.theme)) {
+            Some(modal) => editor.set_modal(modal, gs),
+            None => gs.message("No definition found (LSP)"),
+        }
+    }
+
+    /// single reference is opened, more are listed grouped by file with the count in the footer
+    pub fn show_references(&mut self, mut locations: Vec<Location>, gs: &mut GlobalState) {
+        match locations.len() {
+            0 => gs.message("No references found"),
+            1 => gs.event.push(locations.remove(0).into()),
+            _ => {
+                let editors = &self.editors;
+                let popup = ReferencesPopup::new(locations, |path| {
+                    editors.iter().find(|editor| editor.path == path).map(|editor| editor.content.as_slice())
+                });
+                gs.message(popup.summary());
+                gs.popup(Box::new(popup));
+            }
+        }
+    }
+
+    /// edits of the rename listed by file, applied on confirm
+    pub fn preview_rename(&mut self, edit: WorkspaceEdit, gs: &mut GlobalState) {
+        let editors = &self.editors;
+        let preview = RenamePreview::new(edit, |path| {
+            editors.iter().find(|editor| editor.path == path).map(|editor| editor.content.as_slice())
+        });
+        match preview {
+            Some(preview) => gs.popup(Box::new(preview)),
+            None => gs.message("Rename produced no changes (LSP)"),
+        }
+    }
+
+    /// fuzzy searchable list of the recently closed files
+    pub fn recent_files(&mut self, gs: &mut GlobalState) {
+        if self.session.recent().is_empty() {
+            return gs.message("No recently closed files");
+        }
+        gs.popup(RecentFilesPopup::boxed(self.session.recent()));
+    }
+
+    /// copies markdown summary of the project notes into clipboard
+    pub fn export_annotations(&mut self, gs: &mut GlobalState) {
+        for editor in self.editors.iter() {
+            self.annotations.store(editor);
+        }
+        if self.annotations.list().is_empty() {
+            return gs.message("No notes in the project");
+        }
+        gs.clipboard_copy(self.annotations.markdown());
+        gs.success("Review notes copied to clipboard");
+    }
+
+    /// writes tabs, unsaved changes, scratch buffers, notes and config into .idiom/context.json
+    pub fn export_context(&mut self, gs: &mut GlobalState) {
+        for editor in self.editors.iter() {
+            self.annotations.store(editor);
+        }
+        let base = PathBuf::from("./").canonicalize().ok();
+        let notes = self.annotations.list();
+        let (context, left_out) =
+            Snapshot::capture(self.editors.iter(), notes, snapshot::local_config(), base.as_deref());
+        match context.write(Path::new(CONTEXT_FILE)) {
+            Err(error) => gs.error(format!("Context export failed: {error}")),
+            Ok(()) if left_out != 0 => gs.message(format!(
+                "Context exported to {CONTEXT_FILE}, size cap reached - {left_out} buffer(s) left out"
+            )),
+            Ok(()) => gs.success(format!("Context exported to {CONTEXT_FILE}")),
+        }
+    }
+
+    /// recreates context from .idiom/context.json, everything not restored is listed in popup
+    pub async fn import_context(&mut self, gs: &mut GlobalState) {
+        let context = match Snapshot::read(Path::new(CONTEXT_FILE)) {
+            Ok(context) => context,
+            Err(error) => return gs.error(format!("Context import failed: {error}")),
+        };
+        let base = PathBuf::from("./").canonicalize().ok();
+        let mut report = ImportReport::default();
+        if let Some(config) = context.config() {
+            match snapshot::replace_config(config) {
+                Ok(true) => self.reload_config(gs),
+                Ok(false) => (),
+                Err(error) => report.skip("editor config", error),
+            }
+        }
+        for editor in self.editors.iter() {
+            self.annotations.store(editor);
+        }
+        for (path, notes) in context.annotations(base.as_deref()) {
+            self.annotations.merge(path, notes);
+        }
+        for editor in self.editors.iter_mut() {
+            self.annotations.restore(editor);
+        }
+        for text in context.scratch() {
+            if let Err(error) = self.new_scratch(gs) {
+                report.skip("scratch buffer", error);
+                continue;
+            }
+            if let Some(editor) = self.get_active() {
+                snapshot::replace_content(editor, text.to_owned());
+            }
+        }
+        // opened last to first - the first tab ends active
+        for tab in context.tabs().iter().rev() {
+            let path = tab.path(base.as_deref());
+            match self.new_from(path.clone(), gs).await {
+                Ok(Opened::ReadOnly) => {
+                    report.skip(path.display(), "file over 50MB - opened read-only");
+                    continue;
+                }
+                Err(error) => {
+                    report.skip(path.display(), error);
+                    continue;
+                }
+                Ok(..) => (),
+            }
+            if let Some(editor) = self.get_active() {
+                tab.restore(editor, &mut report);
+            }
+        }
+        let scratch = context.scratch().len();
+        if report.skipped.is_empty() {
+            gs.success(format!("Context imported: {} tabs, {scratch} scratch buffers", report.restored));
+            return;
+        }
+        gs.error(format!("Context imported: {} tabs, {} items skipped", report.restored, report.skipped.len()));
+        gs.popup(import_skipped(report.skipped));
+    }
+
+    /// opens empty text buffer backed by temp file
+    pub fn new_scratch(&mut self, gs: &mut GlobalState) -> IdiomResult<()> {
+        let (idx, path) = (1..)
+            .map(|idx| (idx, std::env::temp_dir().join(format!("idiom_scratch_{}_{idx}.txt", std::process::id()))))
+            .find(|(_, path)| self.editors.iter().all(|editor| &editor.path != path))
+            .expect("infinite iter");
+        std::fs::write(&path, "")?;
+        let mut editor = Editor::from_path_text(path, &self.base_config, gs)?;
+        editor.ephemeral = true;
+        editor.display = format!("scratch {idx}");
+        self.insert_editor(editor);
         self.toggle_editor();
-        Ok(true)
+        if self.editors.len() > 1 {
+            gs.keyboard_hint(KeyboardHint::Tabs);
+        }
+        Ok(())
     }
 
-    pub async fn new_at_line(&mut self, file_path: PathBuf, line: usize, gs: &mut GlobalState) -> IdiomResult<()> {
-        if self.new_from(file_path, gs).await? {
+    pub async fn new_at_line(&mut self, file_path: PathBuf, line: usize, gs: &mut GlobalState) -> IdiomResult<Opened> {
+        let opened = self.new_from(file_path, gs).await?;
+        if opened == Opened::New {
             if let Some(editor) = self.get_active() {
                 editor.go_to(line);
             }
         };
-        Ok(())
+        Ok(opened)
     }
 
-    pub fn select_tab_mouse(&mut self, col_idx: usize) -> Option<usize> {
+    /// returns the index of the clicked editor, clicks on the clipped indicators scroll the tabs
+    pub fn select_tab_mouse(&mut self, col_idx: usize, max_width: usize) -> Option<usize> {
         self.toggle_tabs();
-        let mut cols_len = 0;
-        for (editor_idx, editor) in self.editors.iter().enumerate() {
-            cols_len += editor.display.len() + 3;
-            if col_idx < cols_len {
-                return Some(editor_idx);
-            };
+        let widths = self.editors.iter().map(|editor| tab_label(editor).width()).collect::<Vec<_>>();
+        match self.tab_bar.hit(&widths, max_width, col_idx)? {
+            TabHit::Tab(idx) => Some(idx),
+            TabHit::ScrollLeft => {
+                self.tab_bar.scroll_left();
+                None
+            }
+            TabHit::ScrollRight => {
+                self.tab_bar.scroll_right(widths.len());
+                None
+            }
         }
-        None
     }
 
     #[inline]
@@ -367,11 +1028,17 @@ impl Workspace {
     pub fn notify_update(&mut self, path: PathBuf, gs: &mut GlobalState) {
         for (idx, editor) in self.editors.iter_mut().enumerate() {
             if editor.path == path {
-                if editor.is_saved() {
+                if editor.is_saved() || editor.is_loading() {
+                    return;
+                }
+                // no edits since the last load or save - the buffer follows the file
+                if !editor.is_dirty() {
+                    editor.reload_preserving_cursor(gs);
+                    gs.message(format!("Reloaded {} (changed on disk)", editor.display));
                     return;
                 }
                 editor.update_status.mark_updated();
-                if idx == 0 && editor.update_status.collect() {
+                if idx == self.active && editor.update_status.collect() {
                     gs.popup(file_updated(path));
                 }
                 return;
@@ -379,11 +1046,45 @@ impl Workspace {
         }
     }
 
+    /// pinned editors are closed after confirmation
     pub fn close_active(&mut self, gs: &mut GlobalState) {
-        if self.editors.is_empty() {
+        match self.editors.get_mut_no_update(self.active) {
+            None => (),
+            Some(editor) if editor.pinned => gs.popup(close_pinned(editor.path.clone())),
+            Some(..) => self.close_active_unchecked(gs),
+        }
+    }
+
+    #[inline]
+    pub fn close_active_unchecked(&mut self, gs: &mut GlobalState) {
+        self.close_editor(self.active, gs);
+    }
+
+    /// the tab on the place of the closed one (or the last) becomes active if the active one is closed
+    fn close_editor(&mut self, idx: usize, gs: &mut GlobalState) {
+        if idx >= self.editors.len() {
             return;
         }
-        let editor = self.editors.remove(0);
+        // the split is kept while both of its panes stay open
+        match self.split.as_mut() {
+            Some(split) if idx == self.active || idx == split.other => self.collapse_split(gs),
+            Some(split) if idx < split.other => split.other -= 1,
+            _ => (),
+        }
+        let mut editor = self.editors.remove(idx);
+        if self.active > idx || self.active == self.editors.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        editor.flush_pending_save(gs);
+        self.session.store(&editor);
+        self.session.closed(&editor);
+        self.annotations.store(&editor);
+        if let Err(error) = self.annotations.save() {
+            gs.error(format!("Failed to save annotations: {error}"));
+        }
+        if editor.ephemeral {
+            let _ = std::fs::remove_file(&editor.path);
+        }
         drop(editor);
         match self.get_active() {
             None => {
@@ -402,7 +1103,7 @@ impl Workspace {
 
     pub fn are_updates_saved(&self) -> bool {
         for editor in self.editors.iter() {
-            if !editor.is_saved() {
+            if !editor.ephemeral && !editor.is_saved() {
                 return false;
             }
         }
@@ -413,32 +1114,91 @@ impl Workspace {
         if self.editors.is_empty() {
             return;
         }
-        if idx == 0 || self.editors.len() == 1 {
-            self.toggle_editor();
-            gs.insert_mode();
-            return;
+        let idx = idx.min(self.editors.len() - 1);
+        if idx != self.active {
+            self.activate_editor(idx, gs);
         }
-        let mut editor =
-            if idx >= self.editors.len() { self.editors.pop().expect("garded") } else { self.editors.remove(idx) };
-        gs.event.push(IdiomEvent::SelectPath(editor.path.clone()));
-        editor.clear_screen_cache(gs);
-        if editor.update_status.collect() {
-            gs.popup(file_updated(editor.path.clone()));
-        }
-        self.editors.insert(0, editor);
         self.toggle_editor();
         gs.insert_mode();
     }
 
-    pub fn save_all(&mut self, gs: &mut GlobalState) {
-        for editor in self.editors.iter_mut() {
-            editor.save(gs);
+    /// saves only dirty editors and reports a single summary, failures are listed in popup
+    /// returns true if nothing failed
+    pub fn save_all(&mut self, gs: &mut GlobalState) -> bool {
+        let blocked = self.save_blocks();
+        let active = self.editors.get(self.active).map(|editor| editor.path.clone());
+        save_editors(self.editors.iter_mut(), &blocked, false, gs).report(active, gs)
+    }
+
+    /// dirty editors save all can not write without asking - the single save asks instead
+    fn save_blocks(&self) -> Vec<(PathBuf, String)> {
+        let mut blocked = Vec::new();
+        let dirty = self.editors.iter().enumerate().filter(|(_, editor)| !editor.ephemeral && editor.is_dirty());
+        for (idx, editor) in dirty {
+            if let Some(other) = self.shared_with(idx) {
+                blocked.push((editor.path.clone(), format!("same file as {}", other.display())));
+            }
         }
+        if let Some(peer) = self.peer.as_ref() {
+            let open = peer.open_files();
+            for editor in self.editors.iter().filter(|editor| open.contains(&editor.path)) {
+                blocked.push((editor.path.clone(), format!("open in another instance (pid {})", peer.pid)));
+            }
+        }
+        blocked
+    }
+
+    /// auto saves editors without edits for the configured delay, called on every loop
+    pub fn auto_save_idle(&mut self, gs: &mut GlobalState) {
+        if let AutoSave::AfterDelay(ms) = self.base_config.auto_save {
+            self.auto_save(Some(Duration::from_millis(ms)), gs);
+        }
+    }
+
+    pub fn focus_lost(&mut self, gs: &mut GlobalState) {
+        if self.base_config.auto_save == AutoSave::OnFocusLoss {
+            self.auto_save(None, gs);
+        }
+    }
+
+    /// saves without per file messages - files changed on disk keep waiting for the user
+    fn auto_save(&mut self, delay: Option<Duration>, gs: &mut GlobalState) {
+        if !self.editors.iter().any(|editor| editor.auto_save_due(delay)) {
+            return;
+        }
+        let blocked = self.save_blocks();
+        let editors = self.editors.inner_mut_no_update().iter_mut();
+        let editors = editors.filter_map(|editor| editor.take_auto_save(delay).then_some(editor));
+        save_editors(editors, &blocked, false, gs).report_auto(gs);
+    }
+
+    /// save all followed by the check on save hooks of the saved editors
+    pub fn save_all_and_check(&mut self, gs: &mut GlobalState) -> bool {
+        let blocked = self.save_blocks();
+        let active = self.editors.get(self.active).map(|editor| editor.path.clone());
+        save_editors(self.editors.iter_mut(), &blocked, true, gs).report(active, gs)
+    }
+
+    #[inline]
+    pub fn tree_follows_file(&self) -> bool {
+        self.base_config.tree_follow_file
+    }
+
+    #[inline]
+    pub fn tree_order(&self) -> TreeOrder {
+        TreeOrder { sort: self.base_config.tree_sort, natural: self.base_config.tree_natural_sort }
     }
 
     pub fn refresh_cfg(&mut self, new_key_map: EditorKeyMap, gs: &mut GlobalState) {
         self.key_map = new_key_map;
+        self.reload_config(gs);
+    }
+
+    fn reload_config(&mut self, gs: &mut GlobalState) {
         gs.unwrap_or_default(self.base_config.refresh(), ".config: ");
+        gs.configure_terminal(self.base_config.color_support.as_deref(), self.base_config.mouse);
+        gs.clipboard.set_history_size(self.base_config.clipboard_history);
+        gs.clipboard.set_provider(self.base_config.clipboard_provider);
         for editor in self.editors.iter_mut() {
             editor.refresh_cfg(&self.base_config);
             editor.lexer.reload_theme(gs);
@@ -450,26 +1210,149 @@ impl Workspace {
         }
     }
 
-    pub async fn graceful_exit(&mut self) {
+    pub async fn graceful_exit(&mut self, gs: &mut GlobalState) {
+        for editor in self.editors.inner_mut_no_update().iter_mut() {
+            editor.flush_pending_save(gs);
+        }
+        for editor in self.editors.iter() {
+            self.session.store(editor);
+            self.annotations.store(editor);
+            if editor.ephemeral {
+                let _ = std::fs::remove_file(&editor.path);
+            }
+        }
+        self.session.save();
+        let _ = self.annotations.save();
         for (_, lsp) in self.lsp_servers.iter_mut() {
             let _ = lsp.graceful_exit().await;
         }
     }
 }
 
+/// how the file is shown by new_from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Opened {
+    New,
+    /// editor of the file is activated
+    Existing,
+    /// file over the editor limit is shown in read-only popup, there is no editor
+    ReadOnly,
+}
+
+#[derive(Debug, Default)]
+struct SaveSummary {
+    saved: Vec<PathBuf>,
+    /// saved once formatted (format on save)
+    formatting: Vec<PathBuf>,
+    failed: Vec<(PathBuf, String)>,
+    /// editor blocked by file attribute - offered in popup if it is the only failure
+    protected: Option<(PathBuf, AttributeBlock, bool)>,
+}
+
+impl SaveSummary {
+    /// single failure of the active editor gets the same popup as single save
+    fn report(self, active: Option<PathBuf>, gs: &mut GlobalState) -> bool {
+        if self.failed.is_empty() {
+            match (self.saved.len(), self.formatting.len()) {
+                (0, 0) => gs.message("Nothing to save"),
+                (1, 0) => gs.success(format!("SAVED {}", self.saved[0].display())),
+                (count, 0) => gs.success(format!("SAVED {count} files")),
+                (0, formatting) => gs.message(format!("Saving {formatting} file(s) after formatting")),
+                (count, formatting) => gs.success(format!("SAVED {count} files, {formatting} after formatting")),
+            }
+            return true;
+        }
+        gs.error(format!("SAVED {} files, FAILED {}", self.saved.len(), self.failed.len()));
+        match self.protected {
+            Some((path, block, elevation)) if self.failed.len() == 1 && active.as_ref() == Some(&path) => {
+                gs.popup(protected_file(block, elevation));
+            }
+            _ => gs.popup(save_failures(self.failed)),
+        }
+        false
+    }
+
+    /// single message for all files, failures do not interrupt with popup
+    fn report_auto(self, gs: &mut GlobalState) {
+        match (self.saved.len(), self.failed.len()) {
+            (0, 0) => (),
+            (saved, 0) => gs.message(format!("Auto saved {saved} file(s)")),
+            (saved, failed) => gs.error(format!("Auto saved {saved} file(s), FAILED {failed}")),
+        }
+    }
+}
+
+/// saves dirty editors ordered by file type and path, so notifications reach each server as a batch
+/// the server status is checked once per file type instead of once per file
+/// blocked editors (shared file, open in another instance) are reported as failed with the reason
+fn save_editors<'a>(
+    editors: impl Iterator<Item = &'a mut Editor>,
+    blocked: &[(PathBuf, String)],
+    checks: bool,
+    gs: &mut GlobalState,
+) -> SaveSummary {
+    let mut dirty = editors.filter(|editor| !editor.ephemeral && editor.is_dirty()).collect::<Vec<_>>();
+    dirty.sort_by(|a, b| <&str>::from(a.file_type).cmp(<&str>::from(b.file_type)).then_with(|| a.path.cmp(&b.path)));
+    let mut summary = SaveSummary::default();
+    let mut closed_servers = Vec::new();
+    for editor in dirty {
+        if let Some((path, reason)) = blocked.iter().find(|(path, _)| path == &editor.path) {
+            summary.failed.push((path.clone(), reason.clone()));
+            continue;
+        }
+        match editor.save_quiet(gs) {
+            SaveResult::Saved(content) => {
+                if !editor.lexer.notify_saved(content) && !closed_servers.contains(&editor.file_type) {
+                    closed_servers.push(editor.file_type);
+                }
+                match checks {
+                    true => editor.lexer.check_on_save(gs),
+                    false => editor.lexer.refresh_tokens(gs),
+                }
+                summary.saved.push(editor.path.clone());
+            }
+            SaveResult::Formatting => summary.formatting.push(editor.path.clone()),
+            SaveResult::Blocked(block) => {
+                summary.failed.push((editor.path.clone(), block.to_string()));
+                let elevation = editor.attribute_elevation.is_some();
+                summary.protected.get_or_insert((editor.path.clone(), block, elevation));
+            }
+            SaveResult::Failed(error) => summary.failed.push((editor.path.clone(), error)),
+        }
+    }
+    for file_type in closed_servers {
+        gs.event.push(IdiomEvent::CheckLSP(file_type));
+    }
+    summary
+}
+
 /// handels keybindings for editor
 fn map_editor(ws: &mut Workspace, key: &KeyEvent, gs: &mut GlobalState) -> bool {
-    let editor = match ws.editors.get_mut_no_update(0) {
-        None => return false,
-        Some(editor) => editor,
-    };
     let action = match ws.key_map.map(key) {
         None => return false,
         Some(action) => action,
     };
+    if matches!(action, EditorAction::Save) {
+        // duplicate slipped through on open (e.g. hard link or symlink created later)
+        if let (Some(editor), Some(other)) = (ws.editors.get(ws.active), ws.shared_with(ws.active)) {
+            gs.popup(shared_file(editor.path.clone(), other));
+            return true;
+        }
+        if let (Some(editor), Some(peer)) = (ws.editors.get(ws.active), ws.peer.as_ref()) {
+            if peer.has_open(&editor.path) {
+                gs.popup(instance_file(editor.path.clone(), peer.pid));
+                return true;
+            }
+        }
+    }
+    let editor = match ws.editors.get_mut_no_update(ws.active) {
+        None => return false,
+        Some(editor) => editor,
+    };
     if !editor.map(action, gs) {
         match action {
             EditorAction::Close => ws.close_active(gs),
+            EditorAction::FocusOtherSplit => ws.focus_other_split(gs),
             EditorAction::Cancel if ws.editors.len() > 1 => ws.toggle_tabs(),
             _ => return false,
         }
@@ -493,26 +1376,9 @@ fn map_tabs(ws: &mut Workspace, key: &KeyEvent, gs: &mut GlobalState) -> bool {
                 gs.select_mode();
                 return false;
             }
-            EditorAction::Right | EditorAction::Indent => {
-                let editor = ws.editors.remove(0);
-                ws.editors.push(editor);
-                let editor = &mut ws.editors.inner_mut_no_update()[0];
-                editor.clear_screen_cache(gs);
-                if editor.update_status.collect() {
-                    gs.popup(file_updated(editor.path.clone()));
-                }
-                gs.event.push(IdiomEvent::SelectPath(ws.editors.inner()[0].path.clone()));
-            }
-            EditorAction::Left | EditorAction::Unintent => {
-                if let Some(mut editor) = ws.editors.pop() {
-                    gs.event.push(IdiomEvent::SelectPath(editor.path.clone()));
-                    editor.clear_screen_cache(gs);
-                    if editor.update_status.collect() {
-                        gs.popup(file_updated(editor.path.clone()));
-                    }
-                    ws.editors.insert(0, editor);
-                }
-            }
+            EditorAction::Right | EditorAction::Indent => ws.next_tab(gs),
+            EditorAction::Left | EditorAction::Unintent => ws.prev_tab(gs),
+            EditorAction::Char('p') => ws.toggle_pin(gs),
             EditorAction::Cancel => {
                 ws.toggle_editor();
                 return false;
@@ -527,5 +1393,25 @@ fn map_tabs(ws: &mut Workspace, key: &KeyEvent, gs: &mut GlobalState) -> bool {
     false
 }
 
+fn tab_label(editor: &Editor) -> Cow<'_, str> {
+    match editor.pinned {
+        true => Cow::Owned(format!("{PIN}{}", editor.display)),
+        false => Cow::Borrowed(&editor.display),
+    }
+}
+
+/// index of an editor after the editor at `from` is moved to `to`
+fn moved_index(idx: usize, from: usize, to: usize) -> usize {
+    if idx == from {
+        to
+    } else if from < idx && idx <= to {
+        idx - 1
+    } else if to <= idx && idx < from {
+        idx + 1
+    } else {
+        idx
+    }
+}
+
 #[cfg(test)]
 mod tests;
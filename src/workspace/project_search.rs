@@ -0,0 +1,156 @@
+use super::editor::big_file_protection;
+use crate::{
+    configs::SearchOptions,
+    error::{IdiomError, IdiomResult},
+};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+const MAX_TEXT_CHARS: usize = 300;
+/// files with NUL byte within the head are considered binary (same heuristic as git)
+const BINARY_PROBE: usize = 8_000;
+
+/// matching lines of a single file (0 based line index with trimmed text)
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub lines: Vec<(usize, String)>,
+}
+
+#[derive(Clone)]
+pub enum LineMatcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl LineMatcher {
+    pub fn new(pattern: &str, options: SearchOptions) -> Result<Self, regex::Error> {
+        match options.is_plain() {
+            true => Ok(Self::Literal(pattern.to_owned())),
+            false => options.build(pattern).map(Self::Regex),
+        }
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        match self {
+            Self::Literal(pattern) => line.contains(pattern.as_str()),
+            Self::Regex(regex) => regex.is_match(line),
+        }
+    }
+
+    /// replaced line with the number of replacements - the new text is inserted as is (no capture groups)
+    /// empty matches are skipped, same as the editor search
+    pub fn replace_line(&self, line: &str, new_text: &str) -> (String, usize) {
+        match self {
+            Self::Literal(pattern) => {
+                (line.replace(pattern.as_str(), new_text), line.matches(pattern.as_str()).count())
+            }
+            Self::Regex(regex) => {
+                let mut replaced = String::with_capacity(line.len());
+                let mut count = 0;
+                let mut last_end = 0;
+                for found in regex.find_iter(line).filter(|found| !found.is_empty()) {
+                    replaced.push_str(&line[last_end..found.start()]);
+                    replaced.push_str(new_text);
+                    last_end = found.end();
+                    count += 1;
+                }
+                replaced.push_str(&line[last_end..]);
+                (replaced, count)
+            }
+        }
+    }
+}
+
+pub fn search_file(path: &Path, matcher: &LineMatcher) -> Option<FileMatches> {
+    let content = read_text(path).ok()?;
+    search_lines(path, content.lines(), matcher)
+}
+
+/// same as search_file but over text already in memory (open editors)
+pub fn search_lines<'a>(
+    path: &Path,
+    lines: impl Iterator<Item = &'a str>,
+    matcher: &LineMatcher,
+) -> Option<FileMatches> {
+    let lines = lines
+        .enumerate()
+        .filter(|(_, line)| matcher.is_match(line))
+        .map(|(idx, line)| (idx, line.trim_start().chars().take(MAX_TEXT_CHARS).collect()))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(FileMatches { path: path.to_owned(), lines })
+}
+
+/// replaces matches within the lines (line endings are kept), the file is written only if changed
+pub fn replace_in_file(path: &Path, matcher: &LineMatcher, new_text: &str) -> IdiomResult<usize> {
+    let content = read_text(path)?;
+    let mut replaced = String::with_capacity(content.len());
+    let mut count = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let (new_line, found) = matcher.replace_line(text, new_text);
+        count += found;
+        replaced.push_str(&new_line);
+        replaced.push_str(&line[text.len()..]);
+    }
+    if count != 0 {
+        std::fs::write(path, replaced)?;
+    }
+    Ok(count)
+}
+
+/// big, binary and non UTF-8 files are skipped
+fn read_text(path: &Path) -> IdiomResult<String> {
+    big_file_protection(path)?;
+    let bytes = std::fs::read(path)?;
+    if bytes[..bytes.len().min(BINARY_PROBE)].contains(&0) {
+        return Err(IdiomError::io_err("Binary file"));
+    }
+    String::from_utf8(bytes).map_err(|_| IdiomError::io_err("File is not UTF-8"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{replace_in_file, search_file, LineMatcher};
+    use crate::configs::SearchOptions;
+
+    #[test]
+    fn search_skips_binary() {
+        let dir = std::env::temp_dir().join(format!("idiom-project-search-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let text = dir.join("text.rs");
+        std::fs::write(&text, "fn main() {\n    let Value = 1;\n    let value = 2;\n}\n").unwrap();
+        let binary = dir.join("data.bin");
+        std::fs::write(&binary, b"value\0\x01\x02").unwrap();
+        let literal = LineMatcher::new("value", SearchOptions::default()).unwrap();
+        let found = search_file(&text, &literal).unwrap();
+        assert_eq!(found.lines, vec![(2, String::from("let value = 2;"))]);
+        assert!(search_file(&binary, &literal).is_none());
+        let options = SearchOptions { ignore_case: true, whole_word: true, ..Default::default() };
+        let found = search_file(&text, &LineMatcher::new("value", options).unwrap()).unwrap();
+        assert_eq!(found.lines.iter().map(|(idx, _)| *idx).collect::<Vec<_>>(), [1, 2]);
+        assert!(LineMatcher::new("(", SearchOptions { regex: true, ..Default::default() }).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn replace_keeps_line_endings() {
+        let dir = std::env::temp_dir().join(format!("idiom-project-replace-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("text.rs");
+        std::fs::write(&path, "let value = value;\r\nlet values = 1;\nvalue").unwrap();
+        let options = SearchOptions { whole_word: true, ..Default::default() };
+        let matcher = LineMatcher::new("value", options).unwrap();
+        assert_eq!(replace_in_file(&path, &matcher, "x").unwrap(), 3);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "let x = x;\r\nlet values = 1;\nx");
+        // nothing to replace
+        assert_eq!(replace_in_file(&path, &matcher, "x").unwrap(), 0);
+        let literal = LineMatcher::new("x", SearchOptions::default()).unwrap();
+        assert_eq!(literal.replace_line("x + x", "$1"), (String::from("$1 + $1"), 2));
+        std::fs::write(&path, b"value\0").unwrap();
+        assert!(replace_in_file(&path, &matcher, "x").is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+use super::{decorated, Renderer};
+use crate::{
+    configs::get_config_dir,
+    global_state::GlobalState,
+    render::backend::{BackendProtocol, Screen},
+    workspace::Editor,
+};
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const LOG_FILE: &str = "render_check.log";
+
+/// row drawn from the caches differing from the render without them
+#[derive(Debug)]
+pub struct Mismatch {
+    pub row: u16,
+    /// line drawn at or above the row
+    pub line: Option<usize>,
+    /// render status of the line before the check
+    pub cache: String,
+    pub invalidated: &'static str,
+    pub expected: String,
+    pub found: String,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = self.line.map(|idx| (idx + 1).to_string()).unwrap_or_else(|| String::from("-"));
+        write!(f, "row {} line {line} cache {} (last invalidation: {})", self.row, self.cache, self.invalidated)?;
+        match self.expected == self.found {
+            true => write!(f, " style differs on {:?}", self.expected),
+            false => write!(f, " expected {:?} found {:?}", self.expected, self.found),
+        }
+    }
+}
+
+/// debug check of the render caches (opt in) - the output of the editor is kept as a screen model
+/// and every N frames compared with render ignoring the caches, differences are logged
+pub struct RenderCheck {
+    every: usize,
+    frame: usize,
+    screen: Screen,
+    synced: bool,
+    invalidated: &'static str,
+    log: Option<PathBuf>,
+    mismatches: Vec<Mismatch>,
+}
+
+impl RenderCheck {
+    /// 0 frames disables the check
+    pub fn new(every: usize) -> Option<Box<Self>> {
+        if every == 0 {
+            return None;
+        }
+        Some(Box::new(Self {
+            every,
+            frame: 0,
+            screen: Screen::default(),
+            synced: false,
+            invalidated: "opened",
+            log: get_config_dir().map(|dir| dir.join(LOG_FILE)),
+            mismatches: Vec::new(),
+        }))
+    }
+
+    pub fn every(&self) -> usize {
+        self.every
+    }
+
+    /// reason the editor dropped rendered lines - reported with the mismatches found after it
+    pub fn invalidated(&mut self, reason: &'static str) {
+        self.invalidated = reason;
+    }
+
+    /// rows found stale on the last check
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.mismatches
+    }
+
+    pub fn render(&mut self, renderer: Renderer, editor: &mut Editor, gs: &mut GlobalState) {
+        self.screen = Screen::default();
+        gs.writer.start_recording();
+        decorated(editor, gs, renderer.render, true);
+        gs.writer.stop_recording(&mut self.screen);
+        self.synced = true;
+    }
+
+    pub fn fast_render(&mut self, renderer: Renderer, editor: &mut Editor, gs: &mut GlobalState) {
+        // nothing to compare against before the first full render
+        if !self.synced {
+            return self.render(renderer, editor, gs);
+        }
+        gs.writer.start_recording();
+        decorated(editor, gs, renderer.fast_render, false);
+        gs.writer.stop_recording(&mut self.screen);
+        self.frame += 1;
+        if self.frame % self.every == 0 {
+            self.verify(renderer, editor, gs);
+        }
+    }
+
+    /// renders without caches off-screen and compares it with the screen
+    /// stale rows are drawn by the next frame (full render) - the check does not write to the terminal
+    fn verify(&mut self, renderer: Renderer, editor: &mut Editor, gs: &mut GlobalState) {
+        let area = gs.editor_area;
+        let cached = editor
+            .content
+            .iter()
+            .enumerate()
+            .skip(editor.cursor.at_line)
+            .take(area.height as usize)
+            .map(|(idx, line)| (idx, format!("{:?}", line.cached)))
+            .collect::<Vec<_>>();
+        let mut fresh = Screen::default();
+        gs.writer.start_recording();
+        decorated(editor, gs, renderer.render, true);
+        gs.writer.discard_recording(&mut fresh);
+        let rows = editor
+            .content
+            .iter()
+            .enumerate()
+            .skip(editor.cursor.at_line)
+            .take(area.height as usize)
+            .filter_map(|(idx, line)| line.cached.row().map(|row| (row, idx)))
+            .collect::<Vec<_>>();
+        let cols = area.col as usize..area.col as usize + area.width;
+        self.mismatches.clear();
+        for row in area.row..area.row + area.height {
+            let (expected, found) = (fresh.row(row), self.screen.row(row));
+            if cols.clone().all(|col| expected.cell(col) == found.cell(col)) {
+                continue;
+            }
+            let line = rows.iter().rev().find(|(line_row, _)| *line_row <= row).map(|(_, idx)| *idx);
+            let cache = cached
+                .iter()
+                .find(|(idx, _)| Some(*idx) == line)
+                .map(|(_, cache)| cache.to_owned())
+                .unwrap_or_else(|| String::from("-"));
+            self.mismatches.push(Mismatch {
+                row,
+                line,
+                cache,
+                invalidated: self.invalidated,
+                expected: fresh.text(row, cols.clone()),
+                found: self.screen.text(row, cols.clone()),
+            });
+        }
+        if !self.mismatches.is_empty() {
+            editor.last_render_at_line = None;
+            self.write_log(&editor.path);
+        }
+    }
+
+    fn write_log(&self, path: &Path) {
+        let Some(log) = self.log.as_ref() else {
+            return;
+        };
+        let mut text = String::new();
+        for mismatch in self.mismatches.iter() {
+            text.push_str(&format!("{} frame {}: {mismatch}\n", path.display(), self.frame));
+        }
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(log);
+        let _ = file.and_then(|mut file| file.write_all(text.as_bytes()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RenderCheck;
+    use crate::global_state::GlobalState;
+    use crate::render::{
+        backend::{Backend, BackendProtocol},
+        layout::Rect,
+    };
+    use crate::workspace::{editor::code_tests::mock_editor, line::SKIP_RESET, Editor};
+
+    fn checked_editor(gs: &mut GlobalState) -> Editor {
+        gs.editor_area = Rect::new(0, 0, 40, 6);
+        let mut editor = mock_editor((0..10).map(|idx| format!("let x{idx} = {idx};")).collect());
+        editor.resize(40, 6);
+        let mut check = RenderCheck::new(1).unwrap();
+        check.log = None;
+        editor.render_check = Some(check);
+        editor.render(gs);
+        editor
+    }
+
+    #[test]
+    fn caches_in_sync() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let mut editor = checked_editor(&mut gs);
+        let edits: [fn(&mut Editor); 4] = [
+            |editor| editor.content[3].insert_str(0, "// "),
+            |editor| editor.cursor.select_set((0, 2).into(), (2, 1).into()),
+            |editor| editor.cursor.select_drop(),
+            |editor| editor.cursor.set_position((8, 3).into()),
+        ];
+        for edit in edits {
+            edit(&mut editor);
+            editor.fast_render(&mut gs);
+            assert!(editor.render_check.as_ref().unwrap().mismatches().is_empty());
+        }
+    }
+
+    #[test]
+    fn stale_line_caught() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let mut editor = checked_editor(&mut gs);
+        editor.invalidate_render("test");
+        editor.fast_render(&mut gs);
+        SKIP_RESET.set(true);
+        editor.content[3].insert_str(0, "// ");
+        SKIP_RESET.set(false);
+        editor.fast_render(&mut gs);
+        let check = editor.render_check.as_ref().unwrap();
+        assert_eq!(check.mismatches().len(), 1);
+        let mismatch = &check.mismatches()[0];
+        assert_eq!(mismatch.row, 3);
+        assert_eq!(mismatch.line, Some(3));
+        assert_eq!(mismatch.invalidated, "test");
+        assert!(mismatch.cache.starts_with("Line { line: 3"));
+        assert!(mismatch.expected.contains("// let x3 = 3;"));
+        assert!(mismatch.found.contains("let x3 = 3;") && !mismatch.found.contains("//"));
+        // next frame redraws the row
+        assert_eq!(editor.last_render_at_line, None);
+        editor.fast_render(&mut gs);
+        assert!(editor.render_check.as_ref().unwrap().mismatches().is_empty());
+        assert!(gs.writer.data.iter().any(|(_, text)| text.contains("// let x3")));
+    }
+
+    #[test]
+    fn check_renders_off_screen() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let mut editor = checked_editor(&mut gs);
+        editor.render_check.as_mut().unwrap().every = 2;
+        editor.cursor.set_position((4, 2).into());
+        let written = gs.writer.data.len();
+        editor.fast_render(&mut gs);
+        let frame = gs.writer.data.len() - written;
+        editor.cursor.set_position((5, 2).into());
+        editor.fast_render(&mut gs);
+        // the check frame writes only the output of the fast render
+        assert_eq!(gs.writer.data.len() - written, frame * 2);
+        assert!(gs.writer.recording.is_none());
+        assert!(editor.render_check.as_ref().unwrap().mismatches().is_empty());
+        assert_eq!(editor.last_render_at_line, Some(0));
+    }
+
+    #[test]
+    fn check_every_n_frames() {
+        let mut gs = GlobalState::new(Backend::init()).unwrap();
+        let mut editor = checked_editor(&mut gs);
+        editor.render_check.as_mut().unwrap().every = 3;
+        editor.fast_render(&mut gs);
+        SKIP_RESET.set(true);
+        editor.content[1].insert_str(0, "// ");
+        SKIP_RESET.set(false);
+        // stale row is found only on the check frame
+        editor.fast_render(&mut gs);
+        assert!(editor.render_check.as_ref().unwrap().mismatches().is_empty());
+        editor.fast_render(&mut gs);
+        let check = editor.render_check.as_ref().unwrap();
+        assert_eq!(check.mismatches().len(), 1);
+        assert_eq!(check.mismatches()[0].row, 1);
+        assert_eq!(check.mismatches()[0].invalidated, "opened");
+    }
+}
@@ -1,7 +1,10 @@
-use super::{WRAP_CLOSE, WRAP_OPEN};
+use super::{current_line_fill, current_line_select, WRAP_CLOSE, WRAP_OPEN};
 use crate::{
-    render::backend::{Backend, BackendProtocol, Style},
-    workspace::line::{EditorLine, LineContext},
+    render::backend::{Backend, BackendProtocol, Color, Style},
+    workspace::{
+        line::{EditorLine, LineContext},
+        renderer::whitespace::print_char,
+    },
 };
 use std::ops::Range;
 
@@ -12,17 +15,19 @@ pub fn render(
     select: Option<Range<usize>>,
     backend: &mut Backend,
 ) {
+    let (select, color) = current_line_select(select, ctx);
     if line_width > line.char_len {
         match select {
-            Some(select) => self::select(line, ctx, select, backend),
+            Some(select) => self::select(line, ctx, select, color, backend),
             None => self::basic(line, ctx, backend),
         }
-        if let Some(diagnostics) = line.diagnostics.as_ref() {
-            diagnostics.inline_render(line_width - line.char_len, backend);
+        match line.diagnostics.as_ref() {
+            Some(diagnostics) => diagnostics.inline_render(line_width - line.char_len, backend),
+            None => current_line_fill(line_width, line_width - line.char_len, line.char_len, ctx, backend),
         }
     } else {
         match select {
-            Some(select) => self::partial_select(line, ctx, line_width, select, backend),
+            Some(select) => self::partial_select(line, ctx, line_width, select, color, backend),
             None => self::partial(line, ctx, line_width, backend),
         }
     }
@@ -35,6 +40,7 @@ pub fn basic(line: &EditorLine, ctx: &LineContext, backend: &mut Backend) {
     let mut lined_up = None;
     let mut idx = 0;
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     if let Some(token) = iter_tokens.next() {
         if token.delta_start == 0 {
             counter = token.len;
@@ -76,7 +82,7 @@ pub fn basic(line: &EditorLine, ctx: &LineContext, backend: &mut Backend) {
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -87,8 +93,7 @@ pub fn basic(line: &EditorLine, ctx: &LineContext, backend: &mut Backend) {
 }
 
 #[inline]
-pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, backend: &mut Backend) {
-    let select_color = ctx.lexer.theme.selected;
+pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, select_color: Color, backend: &mut Backend) {
     let mut reset_style = Style::default();
     let mut iter_tokens = line.iter_tokens();
     let mut counter = 0;
@@ -96,6 +101,7 @@ pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, backen
     let mut lined_up = None;
     let mut idx = 0;
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     if let Some(token) = iter_tokens.next() {
         if token.delta_start == 0 {
             backend.set_style(token.style);
@@ -145,7 +151,7 @@ pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, backen
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -158,6 +164,7 @@ pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, backen
 #[inline(always)]
 pub fn partial(line: &mut EditorLine, ctx: &LineContext, line_width: usize, backend: &mut Backend) {
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     let (mut idx, reduction) = line.cached.generate_skipped_chars_simple(cursor_idx, line_width);
     if idx != 0 {
         backend.print_styled(WRAP_OPEN, Style::reversed());
@@ -214,7 +221,7 @@ pub fn partial(line: &mut EditorLine, ctx: &LineContext, line_width: usize, back
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -231,9 +238,11 @@ pub fn partial_select(
     ctx: &LineContext,
     line_width: usize,
     select: Range<usize>,
+    select_color: Color,
     backend: &mut Backend,
 ) {
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     let (mut idx, reduction) = line.cached.generate_skipped_chars_simple(cursor_idx, line_width);
     if idx != 0 {
         backend.print_styled(WRAP_OPEN, Style::reversed());
@@ -243,7 +252,6 @@ pub fn partial_select(
     let mut lined_up = None;
     let mut tokens = line.iter_tokens();
     let mut cursor = idx;
-    let select_color = ctx.lexer.theme.selected;
     let mut reset_style = Style::default();
     if select.start <= idx && idx < select.end {
         reset_style.set_bg(Some(select_color));
@@ -306,7 +314,7 @@ pub fn partial_select(
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed());
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
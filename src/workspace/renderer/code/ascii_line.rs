@@ -3,6 +3,7 @@ use std::ops::Range;
 use crate::{
     render::backend::Style,
     syntax::{tokens::TokenLine, Lexer},
+    workspace::renderer::whitespace::print_char,
     BackendProtocol,
 };
 
@@ -50,6 +51,7 @@ pub fn ascii_line_with_select(
     tokens: &TokenLine,
     select: Range<usize>,
     lexer: &Lexer,
+    whitespace: Option<usize>,
     backend: &mut impl BackendProtocol,
 ) {
     let select_color = lexer.theme.selected;
@@ -104,7 +106,7 @@ pub fn ascii_line_with_select(
         }
         counter -= 1;
 
-        backend.print(text);
+        print_char(text, idx, whitespace, backend);
     }
     backend.reset_style();
 }
@@ -1,12 +1,15 @@
 use unicode_width::UnicodeWidthChar;
 
 use crate::{
-    render::backend::{Backend, BackendProtocol, Style},
-    workspace::line::{EditorLine, LineContext},
+    render::backend::{Backend, BackendProtocol, Color, Style},
+    workspace::{
+        line::{EditorLine, LineContext},
+        renderer::whitespace::print_char,
+    },
 };
 use std::ops::Range;
 
-use super::{width_remainder, WRAP_CLOSE, WRAP_OPEN};
+use super::{current_line_fill, current_line_select, truncation_marker, width_remainder, WRAP_OPEN};
 
 pub fn render(
     line: &mut EditorLine,
@@ -15,17 +18,19 @@ pub fn render(
     select: Option<Range<usize>>,
     backend: &mut Backend,
 ) {
+    let (select, color) = current_line_select(select, ctx);
     if let Some(remainder) = width_remainder(line, line_width) {
         match select {
-            Some(select) => self::select(line, ctx, select, backend),
+            Some(select) => self::select(line, ctx, select, color, backend),
             None => self::basic(line, ctx, backend),
         }
-        if let Some(diagnostic) = line.diagnostics.as_ref() {
-            diagnostic.inline_render(remainder, backend);
+        match line.diagnostics.as_ref() {
+            Some(diagnostic) => diagnostic.inline_render(remainder, backend),
+            None => current_line_fill(line_width, remainder, line.char_len, ctx, backend),
         }
     } else {
         match select {
-            Some(select) => partial_select(line, ctx, select, line_width, backend),
+            Some(select) => partial_select(line, ctx, select, color, line_width, backend),
             None => partial(line, ctx, line_width, backend),
         }
     }
@@ -39,6 +44,7 @@ pub fn basic(line: &EditorLine, ctx: &LineContext, backend: &mut Backend) {
     let mut idx = 0;
     let char_position = ctx.lexer.char_lsp_pos;
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     if let Some(token) = tokens.next() {
         if token.delta_start == 0 {
             counter = token.len;
@@ -81,7 +87,7 @@ pub fn basic(line: &EditorLine, ctx: &LineContext, backend: &mut Backend) {
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -91,9 +97,8 @@ pub fn basic(line: &EditorLine, ctx: &LineContext, backend: &mut Backend) {
     backend.reset_style();
 }
 
-pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, backend: &mut Backend) {
+pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, select_color: Color, backend: &mut Backend) {
     let char_position = ctx.lexer.char_lsp_pos;
-    let select_color = ctx.lexer.theme.selected;
     let mut reset_style = Style::default();
     let mut tokens = line.iter_tokens();
     let mut counter = 0;
@@ -101,6 +106,7 @@ pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, backen
     let mut lined_up = None;
     let mut idx = 0;
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     if let Some(token) = tokens.next() {
         if token.delta_start == 0 {
             backend.set_style(token.style);
@@ -151,7 +157,7 @@ pub fn select(line: &EditorLine, ctx: &LineContext, select: Range<usize>, backen
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
 
         idx += 1;
@@ -166,6 +172,7 @@ pub fn partial(line: &mut EditorLine, ctx: &mut LineContext, mut line_width: usi
     line_width -= 2;
 
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     let char_position = ctx.lexer.char_lsp_pos;
     let mut idx = line.cached.generate_skipped_chars_complex(cursor_idx, line_width, line.content.chars());
     let mut content = line.chars();
@@ -243,7 +250,7 @@ pub fn partial(line: &mut EditorLine, ctx: &mut LineContext, mut line_width: usi
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed());
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
 
         idx += 1;
@@ -252,7 +259,7 @@ pub fn partial(line: &mut EditorLine, ctx: &mut LineContext, mut line_width: usi
         backend.print_styled(" ", Style::reversed());
     } else if line.char_len() > idx {
         backend.reset_style();
-        backend.print_styled(WRAP_CLOSE, Style::reversed());
+        truncation_marker(line_width, backend);
     }
 }
 
@@ -260,12 +267,14 @@ pub fn partial_select(
     line: &mut EditorLine,
     ctx: &mut LineContext,
     select: Range<usize>,
+    select_color: Color,
     mut line_width: usize,
     backend: &mut Backend,
 ) {
     line_width -= 2;
 
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(line);
     let char_position = ctx.lexer.char_lsp_pos;
     let mut idx = line.cached.generate_skipped_chars_complex(cursor_idx, line_width, line.content.chars());
     let mut content = line.chars();
@@ -277,7 +286,6 @@ pub fn partial_select(
         counter_to_idx -= 1;
     }
 
-    let select_color = ctx.lexer.theme.selected;
     let mut reset_style = Style::default();
     if select.start <= idx && idx < select.end {
         reset_style.set_bg(Some(select_color));
@@ -358,7 +366,7 @@ pub fn partial_select(
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed());
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -366,6 +374,6 @@ pub fn partial_select(
         backend.print_styled(" ", Style::reversed());
     } else if line.char_len() > idx {
         backend.reset_style();
-        backend.print_styled(WRAP_CLOSE, Style::reversed());
+        truncation_marker(line_width, backend);
     }
 }
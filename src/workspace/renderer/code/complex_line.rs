@@ -1,16 +1,22 @@
 use std::ops::Range;
 
 use crate::{
-    render::backend::{BackendProtocol, Style},
-    syntax::{tokens::TokenLine, Lexer},
+    render::backend::{color, BackendProtocol, Style},
+    syntax::{tokens::TokenLine, Lexer, LineHint},
+    workspace::renderer::whitespace::print_char,
 };
 
+/// hints are printed before the char at their index, the ones past the content are printed after it
 pub fn complex_line(
     content: impl Iterator<Item = char>,
     tokens: &TokenLine,
     lexer: &Lexer,
+    whitespace: Option<usize>,
+    hints: &[LineHint],
     backend: &mut impl BackendProtocol,
 ) {
+    let hint_style = Style::fg(color::dark_grey());
+    let mut hints = hints.iter().peekable();
     let mut iter_tokens = tokens.iter();
     let mut counter = 0;
     let mut last_len = 0;
@@ -26,7 +32,7 @@ pub fn complex_line(
         }
         last_len = token.len;
     };
-    for text in content {
+    for (idx, text) in content.enumerate() {
         if counter == 0 {
             match lined_up.take() {
                 Some(style) => {
@@ -54,9 +60,15 @@ pub fn complex_line(
         }
         counter = counter.saturating_sub(char_position(text));
 
-        backend.print(text);
+        while let Some(hint) = hints.next_if(|hint| hint.char_idx == idx) {
+            backend.print_styled(&hint.text, hint_style);
+        }
+        print_char(text, idx, whitespace, backend);
     }
     backend.reset_style();
+    for hint in hints {
+        backend.print_styled(&hint.text, hint_style);
+    }
 }
 
 pub fn complex_line_with_select(
@@ -64,6 +76,7 @@ pub fn complex_line_with_select(
     tokens: &TokenLine,
     select: Range<usize>,
     lexer: &Lexer,
+    whitespace: Option<usize>,
     backend: &mut impl BackendProtocol,
 ) {
     let select_color = lexer.theme.selected;
@@ -118,7 +131,7 @@ pub fn complex_line_with_select(
         }
         counter -= 1;
 
-        backend.print(text);
+        print_char(text, idx, whitespace, backend);
     }
     backend.reset_style();
 }
@@ -2,16 +2,22 @@ pub mod ascii_cursor;
 pub mod ascii_line;
 pub mod complex_cursor;
 pub mod complex_line;
+pub mod wrap;
 
 use crate::render::backend::Style;
 use crate::render::{
-    backend::{Backend, BackendProtocol},
-    layout::Line,
+    backend::{color, Backend, BackendProtocol, Color},
+    layout::{Line, Rect},
+    utils::width_cut,
     UTF8Safe,
 };
+use crate::syntax::LineHint;
 use crate::workspace::{
-    cursor::Cursor,
-    line::{EditorLine, LineContext},
+    cursor::{Cursor, CursorPosition},
+    folds::Folds,
+    line::{EditorLine, LineContext, RULER},
+    renderer::whitespace::print_text,
+    utils::find_matching_bracket,
 };
 use std::ops::Range;
 use unicode_width::UnicodeWidthChar;
@@ -76,32 +82,43 @@ fn render_with_select(
         backend.print_styled(" ", Style::bg(ctx.lexer.theme.selected));
         return;
     }
-    if code.is_simple() {
-        if line_width > code.char_len() {
-            let content = code.content.chars();
-            ascii_line::ascii_line_with_select(content, &code.tokens, select, ctx.lexer, backend);
-            if let Some(diagnostic) = code.diagnostics.as_ref() {
-                diagnostic.inline_render(line_width - code.char_len, backend)
+    let whitespace = ctx.whitespace_from(code);
+    match width_cut(&code.content, line_width, WRAP_CLOSE.len()) {
+        Some(cut) => {
+            let content = code.content[..cut.bytes].chars();
+            match code.is_simple() {
+                true => {
+                    ascii_line::ascii_line_with_select(content, &code.tokens, select, ctx.lexer, whitespace, backend)
+                }
+                false => complex_line::complex_line_with_select(
+                    content,
+                    &code.tokens,
+                    select,
+                    ctx.lexer,
+                    whitespace,
+                    backend,
+                ),
             }
-        } else {
-            let content = code.content.chars().take(line_width.saturating_sub(2));
-            ascii_line::ascii_line_with_select(content, &code.tokens, select, ctx.lexer, backend);
-            backend.print_styled(">>", Style::reversed());
+            truncation_marker(cut.pad, backend);
         }
-    // handles non ascii shrunk lines
-    } else if let Ok(truncated) = code.content.truncate_if_wider(line_width) {
-        let mut content = truncated.chars();
-        if let Some(ch) = content.next_back() {
-            if UnicodeWidthChar::width(ch).unwrap_or_default() <= 1 {
-                content.next_back();
+        None => {
+            let content = code.content.chars();
+            match code.is_simple() {
+                true => {
+                    ascii_line::ascii_line_with_select(content, &code.tokens, select, ctx.lexer, whitespace, backend)
+                }
+                false => complex_line::complex_line_with_select(
+                    content,
+                    &code.tokens,
+                    select,
+                    ctx.lexer,
+                    whitespace,
+                    backend,
+                ),
+            }
+            if let Some(diagnostic) = code.diagnostics.as_ref() {
+                diagnostic.inline_render(line_width - code.content.width(), backend)
             }
-        };
-        complex_line::complex_line_with_select(content, &code.tokens, select, ctx.lexer, backend);
-        backend.print_styled(">>", Style::reversed());
-    } else {
-        complex_line::complex_line_with_select(code.content.chars(), &code.tokens, select, ctx.lexer, backend);
-        if let Some(diagnostic) = code.diagnostics.as_ref() {
-            diagnostic.inline_render(line_width - code.content.width(), backend)
         }
     }
 }
@@ -113,34 +130,108 @@ fn render_no_select(
     ctx: &mut LineContext,
     backend: &mut impl BackendProtocol,
 ) {
-    if code.is_simple() {
-        if line_width > code.content.len() {
-            ascii_line::ascii_line(&code.content, &code.tokens, backend);
+    match width_cut(&code.content, line_width, WRAP_CLOSE.len()) {
+        Some(cut) => {
+            render_content(code, &code.content[..cut.bytes], &[], ctx, backend);
+            truncation_marker(cut.pad, backend);
+        }
+        None => {
+            // hints are shown only if the whole line fits with them
+            let hinted_width = code.content.width() + code.inlay_hints_width();
+            let (hints, width) = match hinted_width <= line_width {
+                true => (code.inlay_hints.as_slice(), hinted_width),
+                false => (&[] as &[LineHint], code.content.width()),
+            };
+            render_content(code, &code.content, hints, ctx, backend);
             if let Some(diagnostic) = code.diagnostics.as_ref() {
-                diagnostic.inline_render(line_width - code.char_len, backend)
+                diagnostic.inline_render(line_width - width, backend)
             }
-        } else {
-            ascii_line::ascii_line(&code.content[..line_width.saturating_sub(2)], &code.tokens, backend);
-            backend.print_styled(">>", Style::reversed());
         }
-    // handles non ascii shrunk lines
-    } else if let Ok(truncated) = code.content.truncate_if_wider(line_width) {
-        let mut content = truncated.chars();
-        if let Some(ch) = content.next_back() {
-            if UnicodeWidthChar::width(ch).unwrap_or_default() <= 1 {
-                content.next_back();
+    }
+}
+
+/// renders the visible part of the line, trailing whitespace within it is painted with background
+/// lines with whitespace markers or inlay hints are rendered char by char (ascii chars take single lsp position)
+#[inline(always)]
+fn render_content(
+    code: &EditorLine,
+    content: &str,
+    hints: &[LineHint],
+    ctx: &LineContext,
+    backend: &mut impl BackendProtocol,
+) {
+    let trailing = ctx.trailing_whitespace().and_then(|color| Some((code.trailing_whitespace()?, color)));
+    let (text, trailing) = match trailing {
+        Some((start, color)) if start < content.len() => (&content[..start], Some((start, color))),
+        _ => (content, None),
+    };
+    let whitespace = ctx.whitespace_from(code);
+    match code.is_simple() && whitespace.is_none() && hints.is_empty() {
+        true => ascii_line::ascii_line(text, &code.tokens, backend),
+        false => complex_line::complex_line(text.chars(), &code.tokens, ctx.lexer, whitespace, hints, backend),
+    }
+    if let Some((start, color)) = trailing {
+        match whitespace {
+            None => backend.print_styled(&content[start..], Style::bg(color)),
+            Some(..) => {
+                backend.set_bg(Some(color));
+                print_text(&content[start..], text.chars().count(), whitespace, backend);
+                backend.reset_style();
             }
-        };
-        complex_line::complex_line(content, &code.tokens, ctx.lexer, backend);
-        backend.print_styled(">>", Style::reversed());
-    } else {
-        complex_line::complex_line(code.content.chars(), &code.tokens, ctx.lexer, backend);
-        if let Some(diagnostic) = code.diagnostics.as_ref() {
-            diagnostic.inline_render(line_width - code.content.width(), backend)
         }
     }
 }
 
+/// pads the cells left by wide char that could not fit, before the marker
+#[inline(always)]
+fn truncation_marker(pad: usize, backend: &mut impl BackendProtocol) {
+    if pad != 0 {
+        backend.print(format!("{:pad$}", ""));
+    }
+    backend.print_styled(WRAP_CLOSE, Style::reversed());
+}
+
+/// cursor line without select is rendered as selected whole (with the current line color)
+#[inline(always)]
+fn current_line_select(select: Option<Range<usize>>, ctx: &LineContext) -> (Option<Range<usize>>, Color) {
+    match (select, ctx.current_line()) {
+        (None, Some(color)) => (Some(0..usize::MAX), color),
+        (select, ..) => (select, ctx.lexer.theme.selected),
+    }
+}
+
+/// extends the current line background to the end of the line, keeping the rulers
+/// remainder is the width left after the content, the cursor cell is taken out if at the end
+#[inline(always)]
+fn current_line_fill(
+    line_width: usize,
+    remainder: usize,
+    char_len: usize,
+    ctx: &LineContext,
+    backend: &mut impl BackendProtocol,
+) {
+    let Some(color) = ctx.current_line() else {
+        return;
+    };
+    let mut start = line_width - remainder;
+    let mut pad = remainder;
+    if ctx.cursor_char() >= char_len && pad != 0 {
+        start += 1;
+        pad -= 1;
+    }
+    if pad == 0 {
+        return;
+    }
+    let mut style = Style::bg(color);
+    if ctx.rulers().is_empty() {
+        return backend.print_styled(format!("{:pad$}", ""), style);
+    }
+    style.set_fg(Some(color::dark_grey()));
+    let fill =
+        (start..start + pad).map(|col| if ctx.rulers().contains(&col) { RULER } else { ' ' }).collect::<String>();
+    backend.print_styled(fill, style);
+}
+
 #[inline(always)]
 pub fn cursor_fast(code: &mut EditorLine, ctx: &mut LineContext, line: Line, backend: &mut Backend) {
     let select = ctx.get_select(line.width);
@@ -157,6 +248,88 @@ pub fn cursor_fast(code: &mut EditorLine, ctx: &mut LineContext, line: Line, bac
     backend.reset_style();
 }
 
+/// finds bracket matching the one under the cursor (if in view), the cursor cell is never highlighted
+/// lines gaining or losing the highlight have their cache cleared so they get rendered again
+/// folded blocks take single row - brackets are searched in all lines of the view, hidden ones are not matched
+pub fn update_bracket_match(
+    content: &mut [EditorLine],
+    cursor: &Cursor,
+    folds: &Folds,
+    last_match: &mut Option<CursorPosition>,
+) {
+    let view = cursor.at_line..folds.line_at_row(cursor.at_line, cursor.max_rows);
+    let position = CursorPosition::from(cursor);
+    let new_match = find_matching_bracket(content, position, view.len())
+        .filter(|bracket| *bracket != position && view.contains(&bracket.line) && !folds.is_hidden(bracket.line));
+    if *last_match == new_match {
+        return;
+    }
+    for position in [last_match.take(), new_match].into_iter().flatten() {
+        if let Some(line) = content.get_mut(position.line) {
+            line.clear_cache();
+        }
+    }
+    *last_match = new_match;
+}
+
+/// draws matching bracket over already rendered line (row is counted from the top of the area)
+/// lines that do not fit the screen are skipped, as their rendered content is shifted/truncated
+/// bg - background of the line under the bracket (highlighted cursor line)
+pub fn bracket_match(
+    content: &[EditorLine],
+    bracket: CursorPosition,
+    row: usize,
+    line_number_offset: usize,
+    area: Rect,
+    bg: Option<Color>,
+    backend: &mut Backend,
+) {
+    let Some(line) = content.get(bracket.line) else {
+        return;
+    };
+    let Some(ch) = line.chars().nth(bracket.char) else {
+        return;
+    };
+    let line_width = area.width.saturating_sub(line_number_offset + 1);
+    if line.content.width() >= line_width {
+        return;
+    }
+    let offset =
+        line.chars().take(bracket.char).map(|ch| UnicodeWidthChar::width(ch).unwrap_or_default()).sum::<usize>();
+    let row = area.row + row as u16;
+    let col = area.col + (line_number_offset + 1 + offset) as u16;
+    let mut style = Style::bold();
+    style.underline(None);
+    style.set_bg(bg);
+    backend.print_styled_at(row, col, ch, style);
+}
+
+/// header of folded block is narrowed to its content (and the cursor cell), the rest of the line is left for the summary
+#[inline(always)]
+pub fn fold_header(code: &EditorLine, line: Line, line_number_offset: usize, summary: &str) -> (Line, Line) {
+    let content_width = line_number_offset + 1 + code.content.width() + 1;
+    let min_width = line.width.min(line_number_offset + 2);
+    let width = line.width.saturating_sub(summary.width()).min(content_width).max(min_width);
+    let summary_line = Line { row: line.row, col: line.col + width as u16, width: line.width - width };
+    (Line { width, ..line }, summary_line)
+}
+
+/// opening line of the scope enclosing the first line in view - nearest line above with lower indent
+pub fn scope_header(content: &[EditorLine], at_line: usize) -> Option<usize> {
+    let indent = content.get(at_line..)?.iter().find_map(indent_width)?;
+    if indent == 0 {
+        return None;
+    }
+    content[..at_line].iter().rposition(|line| matches!(indent_width(line), Some(width) if width < indent))
+}
+
+/// None for blank lines
+#[inline(always)]
+pub fn indent_width(line: &EditorLine) -> Option<usize> {
+    let width = line.chars().take_while(|ch| ch.is_whitespace()).count();
+    (width < line.char_len()).then_some(width)
+}
+
 pub fn repositioning(cursor: &mut Cursor) {
     if cursor.line < cursor.at_line {
         cursor.at_line = cursor.line;
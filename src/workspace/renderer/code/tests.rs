@@ -1,14 +1,18 @@
-use super::{cursor as rend_cursor, inner_render};
-use crate::configs::FileType;
+use super::wrap::{render as wrap_render, WrapMarks};
+use super::{bracket_match, cursor as rend_cursor, inner_render, scope_header, update_bracket_match};
+use crate::configs::{FileType, RenderWhitespace};
 use crate::global_state::GlobalState;
-use crate::render::backend::{Backend, BackendProtocol, Style};
+use crate::render::backend::{color, Backend, BackendProtocol, Style};
 use crate::render::layout::{Line, Rect};
+use crate::render::UTF8Safe;
 use crate::syntax::tests::{
     create_token_pairs_utf16, create_token_pairs_utf32, create_token_pairs_utf8, longline_token_pair_utf16,
     longline_token_pair_utf32, longline_token_pair_utf8, mock_utf16_lexer, mock_utf32_lexer, mock_utf8_lexer,
     zip_text_tokens,
 };
+use crate::syntax::LineHint;
 use crate::workspace::cursor::Cursor;
+use crate::workspace::folds::Folds;
 use crate::workspace::line::{EditorLine, LineContext};
 use crate::workspace::CursorPosition;
 
@@ -720,7 +724,8 @@ fn test_content_shrunk(mut render_data: Vec<(Style, String)>) {
             "=",
             " ",
             "\"te",
-            ">>"
+            // 🚀 does not fit before the marker - padded
+            " >>"
         ]
     );
     let (line_num, line) = parse_simple_line(&mut render_data);
@@ -750,3 +755,384 @@ fn test_line_wrap(mut render_data: Vec<(Style, String)>) {
     );
     assert!(render_data.is_empty());
 }
+
+#[test]
+fn test_bracket_match() {
+    let mut content: Vec<EditorLine> =
+        ["fn a(b: [u8; 2]) {", "    if (b[0]) {}", "}"].into_iter().map(|l| EditorLine::from(l.to_owned())).collect();
+    let mut cursor = Cursor::default();
+    cursor.max_rows = 10;
+    let folds = Folds::default();
+    let mut bracket = None;
+    // on opening
+    cursor.set_position(CursorPosition { line: 0, char: 17 });
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, Some(CursorPosition { line: 2, char: 0 }));
+    // right after closing
+    cursor.set_position(CursorPosition { line: 0, char: 15 });
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, Some(CursorPosition { line: 0, char: 4 }));
+    // nested
+    cursor.set_position(CursorPosition { line: 1, char: 7 });
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, Some(CursorPosition { line: 1, char: 12 }));
+    cursor.set_position(CursorPosition { line: 1, char: 2 });
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, None);
+    // out of view
+    cursor.max_rows = 2;
+    cursor.set_position(CursorPosition { line: 0, char: 17 });
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, None);
+}
+
+#[test]
+fn test_bracket_match_keeps_line_bg() {
+    let content = vec![EditorLine::from("if (a) {}".to_owned())];
+    let mut backend = Backend::init();
+    let bg = color::dark_grey();
+    bracket_match(&content, CursorPosition { line: 0, char: 3 }, 0, 1, Rect::new(0, 0, 40, 5), Some(bg), &mut backend);
+    let mut style = Style::bold();
+    style.underline(None);
+    style.set_bg(Some(bg));
+    assert_eq!(backend.data.last(), Some(&(style, String::from("("))));
+    assert_eq!(backend.data[backend.data.len() - 2].1, "<<go to row: 0 col: 5>>");
+}
+
+#[test]
+fn test_bracket_match_folded() {
+    let mut content: Vec<EditorLine> = ["fn a() {", "    if b {", "        c();", "    }", "}"]
+        .into_iter()
+        .map(|l| EditorLine::from(l.to_owned()))
+        .collect();
+    let mut cursor = Cursor::default();
+    cursor.max_rows = 3;
+    let mut folds = Folds::default();
+    let mut bracket = None;
+    // closing line is in view once the inner block takes single row
+    cursor.set_position(CursorPosition { line: 0, char: 7 });
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, None);
+    folds.fold(1, &mut content);
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, Some(CursorPosition { line: 4, char: 0 }));
+    // match hidden in the fold
+    cursor.set_position(CursorPosition { line: 1, char: 9 });
+    update_bracket_match(&mut content, &cursor, &folds, &mut bracket);
+    assert_eq!(bracket, None);
+}
+
+#[test]
+fn test_soft_wrap() {
+    let rect = Rect::new(0, 0, 12, 5);
+    let mut lines = rect.into_iter();
+
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+
+    let mut cursor = Cursor::default();
+    cursor.set_position(CursorPosition { line: 0, char: 12 });
+
+    let (tokens, text) = longline_token_pair_utf8();
+    let content = zip_text_tokens(text, tokens);
+
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1);
+    let marks = WrapMarks { select: None, cursor: Some(12), bracket: None, skip: 0 };
+    assert_eq!(wrap_render(&content[0], &mut ctx, &mut lines, marks, &mut gs.writer), Some((1, 2)));
+    assert_eq!(lines.next().map(|line| line.row), Some(3));
+
+    let mut rows = vec![];
+    for (style, text) in gs.writer.drain() {
+        if text.starts_with("<<go to row") {
+            rows.push(vec![]);
+        } else if !text.starts_with("<<") {
+            rows.last_mut().unwrap().push((style, text));
+        }
+    }
+    let row_text = |row: &Vec<(Style, String)>| row.iter().skip(1).map(|(_, t)| t.as_str()).collect::<String>();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0][0].1, "1 ");
+    assert_eq!(row_text(&rows[0]), "fn get_lon");
+    // wrapped rows have no line number
+    assert_eq!(rows[1][0].1, "  ");
+    assert_eq!(row_text(&rows[1]), "g_line() -");
+    assert_eq!(row_text(&rows[2]), "> String {");
+    // token style is kept across the wrap
+    let (before_wrap, _) = rows[0].last().unwrap();
+    let (after_wrap, _) = &rows[1][1];
+    assert_eq!(before_wrap, after_wrap);
+    assert_ne!(after_wrap, &Style::default());
+    // cursor
+    assert_eq!(rows[1][3], (Style::reversed(), "l".to_owned()));
+}
+
+#[test]
+fn test_trailing_whitespace_highlight() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let highlight = Style::bg(lexer.theme.trailing_whitespace);
+    let cursor = Cursor::default();
+
+    let mut code = EditorLine::from("let a = 1;   ".to_owned());
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_trailing_whitespace(true);
+    inner_render(&mut code, &mut ctx, Line { row: 0, col: 0, width: 30 }, None, &mut gs.writer);
+    let output = gs.writer.drain();
+    assert!(output.contains(&(highlight, "   ".to_owned())));
+    assert!(!output.iter().any(|(style, text)| style == &highlight && text != "   "));
+
+    // whitespace only line is highlighted as a whole
+    let mut code = EditorLine::from("  ".to_owned());
+    inner_render(&mut code, &mut ctx, Line { row: 0, col: 0, width: 30 }, None, &mut gs.writer);
+    assert!(gs.writer.drain().contains(&(highlight, "  ".to_owned())));
+
+    // disabled
+    let mut code = EditorLine::from("let a = 1;   ".to_owned());
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_trailing_whitespace(false);
+    inner_render(&mut code, &mut ctx, Line { row: 0, col: 0, width: 30 }, None, &mut gs.writer);
+    assert!(!gs.writer.drain().iter().any(|(style, _)| style == &highlight));
+}
+
+#[test]
+fn test_trailing_whitespace_highlight_wrapped_utf8() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let highlight = lexer.theme.trailing_whitespace;
+    let cursor = Cursor::default();
+    let mut lines = Rect::new(0, 0, 12, 5).into_iter();
+
+    // trailing whitespace starts at byte 4, but char 2
+    let code = EditorLine::from("éé   ".to_owned());
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_trailing_whitespace(true);
+    let marks = WrapMarks { select: None, cursor: None, bracket: None, skip: 0 };
+    wrap_render(&code, &mut ctx, &mut lines, marks, &mut gs.writer);
+    let highlighted = gs
+        .writer
+        .drain()
+        .into_iter()
+        .filter(|(style, text)| style.with_bg(highlight) == *style && !text.starts_with("<<"))
+        .map(|(_, text)| text)
+        .collect::<String>();
+    assert_eq!(highlighted, "   ");
+}
+
+#[test]
+fn test_current_line_highlight() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let color = lexer.theme.current_line;
+    let has_bg = |style: &Style| style.with_bg(color) == *style;
+    let cursor = Cursor::default();
+
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_current_line(true);
+    let mut code = EditorLine::from("let a".to_owned());
+    rend_cursor(&mut code, &mut ctx, Line { row: 0, col: 0, width: 30 }, &mut gs.writer);
+    let output = gs.writer.drain();
+    // gutter number, text after the cursor and the rest of the line
+    assert!(output.contains(&(Style::bg(color), "1 ".to_owned())));
+    assert!(output.iter().filter(|(_, text)| text == "t").all(|(style, _)| has_bg(style)));
+    assert!(output.contains(&(Style::bg(color), " ".repeat(23))));
+    // cursor keeps its own style
+    assert!(output.contains(&(Style::reversed(), "l".to_owned())));
+
+    // lines other than the cursor line are not highlighted
+    let mut code = EditorLine::from("let a".to_owned());
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 30 }, None, &mut gs.writer);
+    assert!(!gs.writer.drain().iter().any(|(style, _)| has_bg(style)));
+
+    // disabled
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_current_line(false);
+    let mut code = EditorLine::from("let a".to_owned());
+    rend_cursor(&mut code, &mut ctx, Line { row: 0, col: 0, width: 30 }, &mut gs.writer);
+    assert!(!gs.writer.drain().iter().any(|(style, _)| has_bg(style)));
+}
+
+#[test]
+fn test_rulers() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let cursor = Cursor::default();
+    let rulers = [4, 20, 100];
+    let ruler_style = Style::fg(color::dark_grey());
+
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_rulers(&rulers);
+    let mut code = EditorLine::from("let a = 1;".to_owned());
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 30 }, None, &mut gs.writer);
+    let output = gs.writer.drain();
+    // drawn after the gutter (2 cols) before the text, outside of text width is skipped
+    let rulers_start = output.iter().position(|(_, text)| text == "<<clear EOL>>").unwrap() + 1;
+    assert_eq!(
+        &output[rulers_start..rulers_start + 5],
+        &[
+            (Style::default(), String::from("<<go to row: 1 col: 6>>")),
+            (ruler_style, String::from("│")),
+            (Style::default(), String::from("<<go to row: 1 col: 22>>")),
+            (ruler_style, String::from("│")),
+            (Style::default(), String::from("<<go to row: 1 col: 2>>")),
+        ]
+    );
+    assert_eq!(output.iter().filter(|(_, text)| text == "│").count(), 2);
+
+    // current line fill keeps the rulers
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_rulers(&rulers).with_current_line(true);
+    let mut code = EditorLine::from("ab".to_owned());
+    rend_cursor(&mut code, &mut ctx, Line { row: 0, col: 0, width: 30 }, &mut gs.writer);
+    let mut fill_style = Style::bg(lexer.theme.current_line);
+    fill_style.set_fg(Some(color::dark_grey()));
+    let output = gs.writer.drain();
+    let (_, fill) = output.iter().find(|(style, text)| style == &fill_style && text.chars().count() > 1).unwrap();
+    assert_eq!(fill.chars().count(), 26);
+    assert_eq!(fill.chars().position(|ch| ch == '│'), Some(2));
+    assert_eq!(fill.chars().rev().position(|ch| ch == '│'), Some(7));
+}
+
+#[test]
+fn test_render_whitespace() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let cursor = Cursor::default();
+    let markers = |output: Vec<(Style, String)>| {
+        output.into_iter().filter(|(_, text)| text == "·" || text == "→").map(|(_, text)| text).collect::<String>()
+    };
+
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_whitespace(RenderWhitespace::All);
+    let mut code = EditorLine::from("a b\tc ".to_owned());
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 30 }, None, &mut gs.writer);
+    let output = gs.writer.drain();
+    assert!(output.iter().any(|(style, text)| text == "·" && style == &Style::fg(color::dark_grey())));
+    assert!(!output.iter().any(|(_, text)| text == " " || text == "\t"));
+    assert_eq!(markers(output), "·→·");
+    // cursor line
+    let mut code = EditorLine::from("a b\tc ".to_owned());
+    rend_cursor(&mut code, &mut ctx, Line { row: 0, col: 0, width: 30 }, &mut gs.writer);
+    assert_eq!(markers(gs.writer.drain()), "·→·");
+
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_whitespace(RenderWhitespace::Trailing);
+    let mut code = EditorLine::from("let a = 1; \t".to_owned());
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 30 }, None, &mut gs.writer);
+    assert_eq!(markers(gs.writer.drain()), "·→");
+    // complex line
+    let mut code = EditorLine::from("🦀 a  ".to_owned());
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 30 }, None, &mut gs.writer);
+    assert_eq!(markers(gs.writer.drain()), "··");
+
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1).with_whitespace(RenderWhitespace::None);
+    let mut code = EditorLine::from("a b\tc ".to_owned());
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 30 }, None, &mut gs.writer);
+    assert!(markers(gs.writer.drain()).is_empty());
+}
+
+#[test]
+fn test_inlay_hints() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let cursor = Cursor::default();
+    let hint_style = Style::fg(color::dark_grey());
+    let text = |output: &[(Style, String)]| {
+        output
+            .iter()
+            .filter(|(_, text)| !text.starts_with("<<"))
+            .skip(1)
+            .map(|(_, text)| text.as_str())
+            .collect::<String>()
+    };
+
+    let mut code = EditorLine::from("let a = f(1);".to_owned());
+    code.set_inlay_hints(vec![
+        LineHint { char_idx: 5, text: ": i32".into(), width: 5 },
+        LineHint { char_idx: 10, text: "x: ".into(), width: 3 },
+        LineHint { char_idx: 13, text: " // end".into(), width: 7 },
+    ]);
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1);
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 40 }, None, &mut gs.writer);
+    let output = gs.writer.drain();
+    assert_eq!(text(&output), "let a: i32 = f(x: 1); // end");
+    assert!(output.contains(&(hint_style, "x: ".to_owned())));
+    // clicks on hints map to the next char
+    assert_eq!(code.char_at_hinted_column(4), 4);
+    assert_eq!(code.char_at_hinted_column(7), 5);
+    assert_eq!(code.char_at_hinted_column(11), 6);
+    assert_eq!(code.char_at_hinted_column(17), 10);
+    assert_eq!(code.char_at_hinted_column(19), 11);
+
+    // not enough space for all of the hints
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 20 }, None, &mut gs.writer);
+    let output = gs.writer.drain();
+    assert_eq!(text(&output), "let a = f(1);");
+
+    // changed line has no hints
+    code.push(' ');
+    inner_render(&mut code, &mut ctx, Line { row: 1, col: 0, width: 40 }, None, &mut gs.writer);
+    assert_eq!(text(&gs.writer.drain()), "let a = f(1); ");
+}
+
+#[test]
+fn test_relative_line_numbers() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let mut cursor = Cursor::default();
+    cursor.set_position(CursorPosition { line: 11, char: 0 });
+    cursor.at_line = 9;
+
+    let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 2).with_relative_numbers(true);
+    let mut numbers = vec![];
+    for row in 0..5 {
+        let mut code = EditorLine::from("x".to_owned());
+        let line = Line { row, col: 0, width: 20 };
+        match row == 2 {
+            true => {
+                ctx.setup_cursor(line, &mut gs.writer);
+            }
+            false => inner_render(&mut code, &mut ctx, line, None, &mut gs.writer),
+        }
+        let output = gs.writer.drain();
+        numbers.push(output.into_iter().find(|(_, text)| !text.starts_with("<<")).unwrap().1);
+    }
+    // cursor line keeps the absolute number
+    assert_eq!(numbers, [" 2 ", " 1 ", "12 ", " 1 ", " 2 "]);
+}
+
+#[test]
+fn test_truncation_wide_char_boundary() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let mut lexer = mock_utf8_lexer(&mut gs, FileType::Rust);
+    let cursor = Cursor::default();
+    // line number takes 2 columns
+    for width in 5..14 {
+        for select in [None, Some(0..3), Some(2..20)] {
+            let mut code = EditorLine::from("ab🚀cd🚀🚀ef".to_owned());
+            let mut ctx = LineContext::collect_context(&mut lexer, &cursor, 1);
+            let line = Line { row: 0, col: 0, width };
+            inner_render(&mut code, &mut ctx, line, select.clone(), &mut gs.writer);
+            let rendered = gs.writer.drain();
+            let cells: usize = rendered.iter().filter(|(_, t)| !t.starts_with("<<")).map(|(_, t)| t.width()).sum();
+            let truncated = rendered.iter().any(|(_, t)| t.ends_with(">>"));
+            match truncated {
+                true => assert_eq!(cells, width, "width {width} select {select:?}"),
+                false => assert!(cells <= width, "width {width} select {select:?}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_scope_header() {
+    let content: Vec<EditorLine> = [
+        "impl Editor {",
+        "    fn render(&self) {",
+        "        let a = 1;",
+        "",
+        "        let b = 2;",
+        "    }",
+        "}",
+    ]
+    .into_iter()
+    .map(|line| EditorLine::from(line.to_owned()))
+    .collect();
+    assert_eq!(scope_header(&content, 0), None);
+    assert_eq!(scope_header(&content, 1), Some(0));
+    assert_eq!(scope_header(&content, 2), Some(1));
+    // blank line uses the indent of the next line
+    assert_eq!(scope_header(&content, 3), Some(1));
+    assert_eq!(scope_header(&content, 5), Some(0));
+    assert_eq!(scope_header(&content, 6), None);
+}
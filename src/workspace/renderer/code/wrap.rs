@@ -0,0 +1,316 @@
+use crate::{
+    render::{
+        backend::{BackendProtocol, Style},
+        layout::RectIter,
+    },
+    workspace::{
+        cursor::{Cursor, CursorPosition},
+        line::{EditorLine, LineContext},
+        renderer::whitespace::print_char,
+    },
+};
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+/// Marks rendered over the wrapped line
+pub struct WrapMarks {
+    pub select: Option<Range<usize>>,
+    pub cursor: Option<usize>,
+    pub bracket: Option<usize>,
+    /// rows of the line scrolled above the screen (cursor line taller than the screen)
+    pub skip: usize,
+}
+
+/// screen position (row, col) of the char within the wrapped line
+/// position after the last char is the cell used by the cursor
+pub fn char_position(line: &EditorLine, char_idx: usize, width: usize) -> (usize, usize) {
+    let width = width.max(1);
+    if line.is_simple() {
+        let char_idx = char_idx.min(line.char_len());
+        return (char_idx / width, char_idx % width);
+    }
+    let (mut row, mut col) = (0, 0);
+    let mut chars = line.chars();
+    for ch in chars.by_ref().take(char_idx) {
+        let char_width = UnicodeWidthChar::width(ch).unwrap_or_default();
+        if col + char_width > width && col != 0 {
+            row += 1;
+            col = 0;
+        }
+        col += char_width;
+    }
+    let next_width = chars.next().and_then(UnicodeWidthChar::width).unwrap_or(1);
+    if col + next_width > width && col != 0 {
+        return (row + 1, 0);
+    }
+    (row, col)
+}
+
+/// number of screen rows taken by the line
+#[inline]
+pub fn wrapped_rows(line: &EditorLine, width: usize) -> usize {
+    char_position(line, line.char_len(), width).0 + 1
+}
+
+/// char index rendered at screen position (row, col) relative to the line start
+pub fn char_at(line: &EditorLine, row: usize, col: usize, width: usize) -> usize {
+    let width = width.max(1);
+    if line.is_simple() {
+        return (row * width + col.min(width - 1)).min(line.char_len());
+    }
+    let (mut current_row, mut current_col) = (0, 0);
+    for (idx, ch) in line.chars().enumerate() {
+        let char_width = UnicodeWidthChar::width(ch).unwrap_or_default();
+        if current_col + char_width > width && current_col != 0 {
+            if current_row == row {
+                return idx;
+            }
+            current_row += 1;
+            current_col = 0;
+        }
+        current_col += char_width;
+        if current_row == row && current_col > col {
+            return idx;
+        }
+    }
+    line.char_len()
+}
+
+/// keeps the cursor row on screen, returns number of rows to skip on the cursor line
+pub fn repositioning(cursor: &mut Cursor, content: &[EditorLine]) -> usize {
+    let width = cursor.text_width;
+    let cursor_row = char_position(&content[cursor.line], cursor.char, width).0;
+    if cursor_row >= cursor.max_rows {
+        cursor.at_line = cursor.line;
+        return cursor_row + 1 - cursor.max_rows;
+    }
+    if cursor.at_line > cursor.line {
+        cursor.at_line = cursor.line;
+        return 0;
+    }
+    let mut rows = cursor_row + 1;
+    rows += content[cursor.at_line..cursor.line].iter().map(|line| wrapped_rows(line, width)).sum::<usize>();
+    while rows > cursor.max_rows && cursor.at_line < cursor.line {
+        rows -= wrapped_rows(&content[cursor.at_line], width);
+        cursor.at_line += 1;
+    }
+    0
+}
+
+/// maps screen position relative to the editor area into content position
+pub fn screen_to_content(
+    content: &[EditorLine],
+    at_line: usize,
+    width: usize,
+    mut row: usize,
+    col: usize,
+) -> CursorPosition {
+    for (line_idx, line) in content.iter().enumerate().skip(at_line) {
+        let rows = wrapped_rows(line, width);
+        if row < rows {
+            return CursorPosition { line: line_idx, char: char_at(line, row, col, width) };
+        }
+        row -= rows;
+    }
+    let line = content.len().saturating_sub(1);
+    CursorPosition { line, char: content.get(line).map(EditorLine::char_len).unwrap_or_default() }
+}
+
+/// renders line over as many rows as needed carrying token styles across the wraps
+/// returns the screen position (row, col) of the cursor relative to the first rendered row
+pub fn render(
+    code: &EditorLine,
+    ctx: &mut LineContext,
+    lines: &mut RectIter,
+    marks: WrapMarks,
+    backend: &mut impl BackendProtocol,
+) -> Option<(usize, usize)> {
+    let WrapMarks { select, cursor, bracket, skip } = marks;
+    let line_width = match (lines.next(), cursor.is_some()) {
+        (Some(line), true) => ctx.setup_cursor(line, backend),
+        (Some(line), false) => ctx.setup_line(line, backend),
+        (None, ..) => return None,
+    };
+    let (select, select_color) = match cursor {
+        Some(..) => super::current_line_select(select, ctx),
+        None => (select, ctx.lexer.theme.selected),
+    };
+    if code.char_len == 0 {
+        match cursor {
+            Some(..) => backend.print_styled(" ", Style::reversed()),
+            None if select.is_some() => backend.print_styled(" ", Style::bg(select_color)),
+            None => (),
+        }
+        return cursor.map(|_| (0, 0));
+    }
+    let char_position = ctx.lexer.char_lsp_pos;
+    let is_simple = code.is_simple();
+    let whitespace = ctx.whitespace_from(code);
+    // trailing whitespace is not highlighted under cursor or select
+    let trailing = match (cursor, select.as_ref(), ctx.trailing_whitespace()) {
+        (None, None, Some(color)) => {
+            code.trailing_whitespace().map(|start| (code.content[..start].chars().count(), color))
+        }
+        _ => None,
+    };
+    let mut reset_style = Style::default();
+    let mut iter_tokens = code.iter_tokens();
+    let mut counter = 0;
+    let mut last_len = 0;
+    let mut lined_up = None;
+    if let Some(token) = iter_tokens.next() {
+        if token.delta_start == 0 {
+            counter = token.len;
+            backend.set_style(token.style);
+        } else {
+            lined_up.replace(token.style);
+            counter = token.delta_start;
+        }
+        last_len = token.len;
+    };
+    let mut row = 0;
+    let mut col = 0;
+    let mut cursor_screen = None;
+    for (idx, text) in code.chars().enumerate() {
+        if let Some((_, color)) = trailing.filter(|(start, _)| *start == idx) {
+            backend.set_bg(Some(color));
+            reset_style.set_bg(Some(color));
+        }
+        if let Some(select) = select.as_ref() {
+            if select.start == idx {
+                backend.set_bg(Some(select_color));
+                reset_style.set_bg(Some(select_color));
+            }
+            if select.end == idx {
+                backend.set_bg(None);
+                reset_style.set_bg(None);
+            }
+        }
+        if counter == 0 {
+            match lined_up.take() {
+                Some(style) => {
+                    backend.update_style(style);
+                    counter = last_len;
+                }
+                None => match iter_tokens.next() {
+                    None => {
+                        counter = usize::MAX;
+                        backend.set_style(reset_style);
+                    }
+                    Some(token) => {
+                        if token.delta_start > last_len {
+                            counter = token.delta_start - last_len;
+                            lined_up.replace(token.style);
+                            backend.set_style(reset_style);
+                        } else {
+                            counter = token.len;
+                            backend.update_style(token.style);
+                        }
+                        last_len = token.len;
+                    }
+                },
+            }
+        }
+        counter = counter.saturating_sub(if is_simple { 1 } else { char_position(text) });
+
+        let char_width = UnicodeWidthChar::width(text).unwrap_or_default();
+        if col + char_width > line_width && col != 0 {
+            row += 1;
+            col = 0;
+            if row > skip && !wrap_row(ctx, lines, backend) {
+                return cursor_screen;
+            }
+        }
+        col += char_width;
+        if row < skip {
+            continue;
+        }
+        if cursor == Some(idx) {
+            cursor_screen = Some((row - skip, col - char_width));
+            backend.print_styled(text, Style::reversed());
+        } else if bracket == Some(idx) {
+            let mut style = backend.get_style();
+            style.add_bold();
+            style.underline(None);
+            backend.print_styled(text, style);
+        } else {
+            print_char(text, idx, whitespace, backend);
+        }
+    }
+    backend.reset_style();
+    if cursor == Some(code.char_len) {
+        if col + 1 > line_width {
+            row += 1;
+            col = 0;
+            if row > skip && !wrap_row(ctx, lines, backend) {
+                return cursor_screen;
+            }
+        }
+        cursor_screen = Some((row.saturating_sub(skip), col));
+        backend.print_styled(" ", Style::reversed());
+        col += 1;
+    }
+    if let Some(diagnostic) = code.diagnostics.as_ref() {
+        if row >= skip {
+            diagnostic.inline_render(line_width.saturating_sub(col), backend);
+        }
+    }
+    cursor_screen
+}
+
+/// moves to the next row keeping the current style
+fn wrap_row(ctx: &mut LineContext, lines: &mut RectIter, backend: &mut impl BackendProtocol) -> bool {
+    let Some(line) = lines.next() else {
+        backend.reset_style();
+        return false;
+    };
+    let style = backend.get_style();
+    backend.reset_style();
+    ctx.wrap_line(line, backend);
+    backend.set_style(style);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{char_at, char_position, screen_to_content, wrapped_rows};
+    use crate::workspace::{line::EditorLine, CursorPosition};
+
+    #[test]
+    fn simple_positions() {
+        let line = EditorLine::from("0123456789".to_owned());
+        assert_eq!(char_position(&line, 3, 4), (0, 3));
+        assert_eq!(char_position(&line, 4, 4), (1, 0));
+        assert_eq!(char_position(&line, 10, 4), (2, 2));
+        assert_eq!(wrapped_rows(&line, 4), 3);
+        assert_eq!(wrapped_rows(&line, 5), 3);
+        assert_eq!(wrapped_rows(&line, 11), 1);
+        assert_eq!(char_at(&line, 1, 2, 4), 6);
+        assert_eq!(char_at(&line, 2, 3, 4), 10);
+    }
+
+    #[test]
+    fn complex_positions() {
+        // wide char does not fit at the end of the first row
+        let line = EditorLine::from("abc🦀def".to_owned());
+        assert_eq!(char_position(&line, 3, 4), (1, 0));
+        assert_eq!(char_position(&line, 4, 4), (1, 2));
+        assert_eq!(char_position(&line, 7, 4), (2, 1));
+        assert_eq!(wrapped_rows(&line, 4), 3);
+        assert_eq!(char_at(&line, 0, 3, 4), 3);
+        assert_eq!(char_at(&line, 1, 1, 4), 3);
+        assert_eq!(char_at(&line, 1, 2, 4), 4);
+        assert_eq!(char_at(&line, 2, 0, 4), 6);
+    }
+
+    #[test]
+    fn screen_mapping() {
+        let content: Vec<EditorLine> =
+            ["0123456789", "abc", "xy🦀z"].into_iter().map(|l| EditorLine::from(l.to_owned())).collect();
+        assert_eq!(screen_to_content(&content, 0, 4, 1, 1), CursorPosition { line: 0, char: 5 });
+        assert_eq!(screen_to_content(&content, 0, 4, 3, 2), CursorPosition { line: 1, char: 2 });
+        assert_eq!(screen_to_content(&content, 0, 4, 4, 3), CursorPosition { line: 2, char: 2 });
+        assert_eq!(screen_to_content(&content, 1, 4, 1, 0), CursorPosition { line: 2, char: 0 });
+        assert_eq!(screen_to_content(&content, 1, 4, 9, 0), CursorPosition { line: 2, char: 4 });
+    }
+}
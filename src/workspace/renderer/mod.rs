@@ -1,71 +1,206 @@
+mod check;
 mod code;
+mod scrollbar;
 mod text;
+mod whitespace;
 
-use super::{line::LineContext, Editor};
-use crate::{global_state::GlobalState, render::layout::IterLines, syntax::Lexer};
+use super::{cursor::Cursor, line::LineContext, CursorPosition, Editor};
+use crate::{
+    global_state::GlobalState,
+    render::{
+        backend::{color, BackendProtocol, Color, Style},
+        layout::{IterLines, Line},
+        UTF8Safe,
+    },
+    syntax::Lexer,
+};
+pub use check::RenderCheck;
+use code::wrap::WrapMarks;
+pub use code::{indent_width, scope_header};
+pub use scrollbar::Scrollbar;
 
 /// Component containing logic regarding rendering
 /// In order to escape complicated state machines and any form on polymorphism,
 /// it derives the correct function pointers on file opening.
+#[derive(Clone, Copy)]
 pub struct Renderer {
     pub render: fn(&mut Editor, &mut GlobalState),
     pub fast_render: fn(&mut Editor, &mut GlobalState),
+    /// sets cursor from position relative to the editor area (mouse)
+    pub set_cursor: fn(&mut Editor, CursorPosition),
 }
 
 impl Renderer {
     pub fn code() -> Self {
-        Self { render: code_render, fast_render: fast_code_render }
+        Self { render: code_render, fast_render: fast_code_render, set_cursor: screen_cursor }
+    }
+
+    pub fn code_wrap() -> Self {
+        Self { render: code_wrap_render, fast_render: fast_code_wrap_render, set_cursor: wrapped_screen_cursor }
     }
 
     pub fn text() -> Self {
-        Self { render: text_render, fast_render: fast_text_render }
+        Self { render: text_render, fast_render: fast_text_render, set_cursor: screen_cursor }
     }
 
     pub fn markdown() -> Self {
-        Self { render: md_render, fast_render: fast_md_render }
+        Self { render: md_render, fast_render: fast_md_render, set_cursor: screen_cursor }
+    }
+}
+
+/// renders the editor in the area left by the enabled decorations
+/// the scope header takes the first row (opening line of the scope above the view), the scrollbar the last column
+/// decorations are redrawn only if they changed or the editor was fully rendered
+pub fn decorated(editor: &mut Editor, gs: &mut GlobalState, render: fn(&mut Editor, &mut GlobalState), full: bool) {
+    let area = gs.editor_area;
+    let header = editor.sticky_scope && area.height > 1;
+    let scrollbar = editor.scrollbar.is_some() && area.width > 1;
+    if !header && !scrollbar {
+        return render(editor, gs);
+    }
+    let last_at_line = editor.last_render_at_line;
+    let mut bar_area = area;
+    if header {
+        bar_area.row += 1;
+        bar_area.height -= 1;
+    }
+    let mut code_area = bar_area;
+    if scrollbar {
+        code_area.width -= 1;
+    }
+    gs.editor_area = code_area;
+    render(editor, gs);
+    gs.editor_area = area;
+    if header && (full || last_at_line != editor.last_render_at_line) {
+        render_scope_header(editor, Line { row: area.row, col: area.col, width: area.width }, gs);
+    }
+    if let Some(bar) = editor.scrollbar.as_mut().filter(|_| scrollbar) {
+        let (content, at_line) = (&editor.content, editor.cursor.at_line);
+        bar.render(content, at_line, editor.lexer.diagnostic_updates, bar_area, full, &mut gs.writer);
+    }
+}
+
+/// highlighted cursor line keeps its background under the matching bracket
+fn bracket_bg(highlight_current_line: bool, cursor: &Cursor, bracket: CursorPosition, color: Color) -> Option<Color> {
+    (highlight_current_line && bracket.line == cursor.line).then_some(color)
+}
+
+fn render_scope_header(editor: &Editor, line: Line, gs: &mut GlobalState) {
+    match scope_header(&editor.content, editor.cursor.at_line) {
+        Some(idx) => {
+            let number = match (editor.line_number_offset, editor.relative_line_numbers) {
+                (0, _) => String::from(" "),
+                (offset, true) => format!("{: >1$} ", editor.cursor.line.abs_diff(idx), offset),
+                (offset, false) => format!("{: >1$} ", idx + 1, offset),
+            };
+            gs.writer.print_styled_at(line.row, line.col, &number, Style::fg(color::dark_grey()));
+            let text = editor.content[idx].content.trim_end();
+            let width = line.width.saturating_sub(number.len());
+            let text_line = Line { row: line.row, col: line.col + number.len() as u16, width };
+            text_line.render_styled(text, gs.theme.accent_style, &mut gs.writer);
+        }
+        None => line.render_empty(&mut gs.writer),
     }
 }
 
+fn screen_cursor(editor: &mut Editor, mut position: CursorPosition) {
+    position.line = editor.folds.line_at_row(editor.cursor.at_line, position.line);
+    position.char = position.char.saturating_sub(editor.line_number_offset + 1);
+    // inlay hints are rendered on lines other than the cursor line, if the line fits with them
+    if let Some(line) = editor.content.get(position.line).filter(|_| position.line != editor.cursor.line) {
+        if line.content.width() + line.inlay_hints_width() <= editor.cursor.text_width {
+            position.char = line.char_at_hinted_column(position.char);
+        }
+    }
+    editor.cursor.set_cursor_checked(position, &editor.content);
+}
+
+fn wrapped_screen_cursor(editor: &mut Editor, position: CursorPosition) {
+    let Some(col) = position.char.checked_sub(editor.line_number_offset + 1) else {
+        return;
+    };
+    let cursor = &mut editor.cursor;
+    let position =
+        code::wrap::screen_to_content(&editor.content, cursor.at_line, cursor.text_width, position.line, col);
+    cursor.set_cursor_clamped(position, &editor.content);
+}
+
 // CODE
 
 fn code_render(editor: &mut Editor, gs: &mut GlobalState) {
     Lexer::context(editor, gs);
-    code::repositioning(&mut editor.cursor);
+    code_repositioning(editor);
+    code::update_bracket_match(&mut editor.content, &editor.cursor, &editor.folds, &mut editor.bracket_match);
     code_render_full(editor, gs);
 }
 
 fn fast_code_render(editor: &mut Editor, gs: &mut GlobalState) {
     Lexer::context(editor, gs);
-    code::repositioning(&mut editor.cursor);
+    code_repositioning(editor);
+    code::update_bracket_match(&mut editor.content, &editor.cursor, &editor.folds, &mut editor.bracket_match);
     if !matches!(editor.last_render_at_line, Some(idx) if idx == editor.cursor.at_line) {
         return code_render_full(editor, gs);
     }
     let mut lines = gs.editor_area.into_iter();
-    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset);
+    editor.refresh_status();
+    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset)
+        .with_annotated(editor.annotations.lines())
+        .with_relative_numbers(editor.relative_line_numbers)
+        .with_current_line(editor.highlight_current_line)
+        .with_whitespace(editor.render_whitespace)
+        .with_rulers(&editor.rulers)
+        .with_trailing_whitespace(editor.highlight_trailing_whitespace);
     ctx.correct_last_line_match(&mut editor.content, lines.len());
     let backend = &mut gs.writer;
-    for (line_idx, text) in editor.content.iter_mut().enumerate().skip(editor.cursor.at_line) {
-        if let Some(line) = lines.next() {
-            if editor.cursor.line == line_idx {
-                code::cursor_fast(text, &mut ctx, line, backend);
-            } else {
-                let select = ctx.get_select(line.width);
-                if text.cached.should_render_line(line.row, &select) {
-                    code::inner_render(text, &mut ctx, line, select, backend);
-                } else {
-                    ctx.skip_line();
-                }
-            }
-        } else {
+    let folds = &editor.folds;
+    let mut content = editor.content.iter_mut().enumerate().skip(editor.cursor.at_line);
+    while let Some((line_idx, text)) = content.next() {
+        let Some(mut line) = lines.next() else {
             break;
         };
+        let folded = folds.folded(line_idx).map(|(end, summary)| {
+            let (code_line, summary_line) = code::fold_header(text, line.clone(), editor.line_number_offset, summary);
+            line = code_line;
+            (end, summary_line, summary)
+        });
+        if editor.cursor.line == line_idx {
+            code::cursor_fast(text, &mut ctx, line, backend);
+        } else {
+            let select = ctx.get_select(line.width);
+            if text.cached.should_render_line(line.row, &select) {
+                code::inner_render(text, &mut ctx, line, select, backend);
+            } else {
+                ctx.skip_line();
+            }
+        }
+        if let Some((end, summary_line, summary)) = folded {
+            summary_line.render_styled(summary, Style::fg(color::dark_grey()), backend);
+            for _ in content.by_ref().take(end - line_idx) {
+                ctx.skip_line();
+            }
+        }
     }
     if !ctx.lexer.modal_is_rendered() {
         for line in lines {
             line.render_empty(&mut gs.writer);
         }
+        // modal is not redrawn on fast render - highlight could overlap it
+        if let Some(bracket) = editor.bracket_match {
+            if let Some(row) = editor.folds.row_of(editor.cursor.at_line, bracket.line) {
+                let offset = editor.line_number_offset;
+                let bg =
+                    bracket_bg(editor.highlight_current_line, &editor.cursor, bracket, ctx.lexer.theme.current_line);
+                code::bracket_match(&editor.content, bracket, row, offset, gs.editor_area, bg, &mut gs.writer);
+            }
+        }
+    }
+    gs.render_stats(&editor.status);
+    if !editor.folds.is_empty() {
+        ctx.set_cursor_screen_position(
+            editor.folds.visible_rows(editor.cursor.at_line, editor.cursor.line),
+            ctx.cursor_char(),
+        );
     }
-    gs.render_stats(editor.content.len(), editor.cursor.select_len(&editor.content), (&editor.cursor).into());
     ctx.render_modal(gs);
 }
 
@@ -73,27 +208,196 @@ fn fast_code_render(editor: &mut Editor, gs: &mut GlobalState) {
 fn code_render_full(editor: &mut Editor, gs: &mut GlobalState) {
     editor.last_render_at_line.replace(editor.cursor.at_line);
     let mut lines = gs.editor_area.into_iter();
-    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset);
+    editor.refresh_status();
+    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset)
+        .with_annotated(editor.annotations.lines())
+        .with_relative_numbers(editor.relative_line_numbers)
+        .with_current_line(editor.highlight_current_line)
+        .with_whitespace(editor.render_whitespace)
+        .with_rulers(&editor.rulers)
+        .with_trailing_whitespace(editor.highlight_trailing_whitespace);
+    let backend = &mut gs.writer;
+    let folds = &editor.folds;
+    let mut content = editor.content.iter_mut().enumerate().skip(editor.cursor.at_line);
+    while let Some((line_idx, text)) = content.next() {
+        let Some(mut line) = lines.next() else {
+            break;
+        };
+        let folded = folds.folded(line_idx).map(|(end, summary)| {
+            let (code_line, summary_line) = code::fold_header(text, line.clone(), editor.line_number_offset, summary);
+            line = code_line;
+            (end, summary_line, summary)
+        });
+        if editor.cursor.line == line_idx {
+            code::cursor(text, &mut ctx, line, backend);
+        } else {
+            let select = ctx.get_select(line.width);
+            code::inner_render(text, &mut ctx, line, select, backend);
+        }
+        if let Some((end, summary_line, summary)) = folded {
+            summary_line.render_styled(summary, Style::fg(color::dark_grey()), backend);
+            for _ in content.by_ref().take(end - line_idx) {
+                ctx.skip_line();
+            }
+        }
+    }
+    for line in lines {
+        line.render_empty(&mut gs.writer);
+    }
+    if let Some(bracket) = editor.bracket_match {
+        if let Some(row) = editor.folds.row_of(editor.cursor.at_line, bracket.line) {
+            let offset = editor.line_number_offset;
+            let bg = bracket_bg(editor.highlight_current_line, &editor.cursor, bracket, ctx.lexer.theme.current_line);
+            code::bracket_match(&editor.content, bracket, row, offset, gs.editor_area, bg, &mut gs.writer);
+        }
+    }
+    gs.render_stats(&editor.status);
+    if !editor.folds.is_empty() {
+        ctx.set_cursor_screen_position(
+            editor.folds.visible_rows(editor.cursor.at_line, editor.cursor.line),
+            ctx.cursor_char(),
+        );
+    }
+    ctx.forced_modal_render(gs);
+}
+
+/// folded blocks take single row
+#[inline(always)]
+fn code_repositioning(editor: &mut Editor) {
+    match editor.folds.is_empty() {
+        true => code::repositioning(&mut editor.cursor),
+        false => editor.folds.repositioning(&mut editor.cursor),
+    }
+}
+
+// CODE WRAPPED
+
+fn code_wrap_render(editor: &mut Editor, gs: &mut GlobalState) {
+    Lexer::context(editor, gs);
+    let skip = code::wrap::repositioning(&mut editor.cursor, &editor.content);
+    code::update_bracket_match(&mut editor.content, &editor.cursor, &editor.folds, &mut editor.bracket_match);
+    code_wrap_render_full(editor, gs, skip);
+}
+
+fn fast_code_wrap_render(editor: &mut Editor, gs: &mut GlobalState) {
+    Lexer::context(editor, gs);
+    let skip = code::wrap::repositioning(&mut editor.cursor, &editor.content);
+    code::update_bracket_match(&mut editor.content, &editor.cursor, &editor.folds, &mut editor.bracket_match);
+    if !matches!(editor.last_render_at_line, Some(idx) if idx == editor.cursor.at_line) {
+        return code_wrap_render_full(editor, gs, skip);
+    }
+    let mut lines = gs.editor_area.into_iter();
+    editor.refresh_status();
+    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset)
+        .with_annotated(editor.annotations.lines())
+        .with_relative_numbers(editor.relative_line_numbers)
+        .with_current_line(editor.highlight_current_line)
+        .with_whitespace(editor.render_whitespace)
+        .with_rulers(&editor.rulers)
+        .with_trailing_whitespace(editor.highlight_trailing_whitespace);
     let backend = &mut gs.writer;
+    let width = editor.cursor.text_width;
+    let mut cursor_screen = None;
     for (line_idx, text) in editor.content.iter_mut().enumerate().skip(editor.cursor.at_line) {
-        if let Some(line) = lines.next() {
-            if editor.cursor.line == line_idx {
-                code::cursor(text, &mut ctx, line, backend);
+        if lines.is_finished() {
+            break;
+        }
+        let row = lines.next_line_idx();
+        let rows_before = ctx_rows(&lines, gs.editor_area.height);
+        let select = ctx.get_select_full_line(text.char_len());
+        let bracket = editor.bracket_match.filter(|pos| pos.line == line_idx).map(|pos| pos.char);
+        if editor.cursor.line == line_idx {
+            let cursor = ctx.cursor_char();
+            if text.cached.should_render_cursor(row, cursor, &select) || text.cached.skipped_chars() != skip {
+                text.cached.cursor(row, cursor, skip, select.clone());
+                let marks = WrapMarks { select, cursor: Some(cursor), bracket, skip };
+                cursor_screen = code::wrap::render(text, &mut ctx, &mut lines, marks, backend)
+                    .map(|(rel_row, col)| (rows_before + rel_row, col));
             } else {
-                let select = ctx.get_select(line.width);
-                code::inner_render(text, &mut ctx, line, select, backend);
+                let (rel_row, col) = code::wrap::char_position(text, cursor, width);
+                cursor_screen = Some(((rows_before + rel_row).saturating_sub(skip), col));
+                ctx.skip_line();
+                lines.forward(code::wrap::wrapped_rows(text, width) - skip);
             }
+        } else if text.cached.should_render_line(row, &select) {
+            text.cached.line(row, select.clone());
+            code::wrap::render(
+                text,
+                &mut ctx,
+                &mut lines,
+                WrapMarks { select, cursor: None, bracket, skip: 0 },
+                backend,
+            );
         } else {
+            ctx.skip_line();
+            lines.forward(code::wrap::wrapped_rows(text, width));
+        }
+    }
+    for line in lines {
+        line.render_empty(&mut gs.writer);
+    }
+    gs.render_stats(&editor.status);
+    if let Some((row, col)) = cursor_screen {
+        ctx.set_cursor_screen_position(row, col);
+    }
+    ctx.render_modal(gs);
+}
+
+#[inline(always)]
+fn code_wrap_render_full(editor: &mut Editor, gs: &mut GlobalState, skip: usize) {
+    editor.last_render_at_line.replace(editor.cursor.at_line);
+    let mut lines = gs.editor_area.into_iter();
+    editor.refresh_status();
+    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset)
+        .with_annotated(editor.annotations.lines())
+        .with_relative_numbers(editor.relative_line_numbers)
+        .with_current_line(editor.highlight_current_line)
+        .with_whitespace(editor.render_whitespace)
+        .with_rulers(&editor.rulers)
+        .with_trailing_whitespace(editor.highlight_trailing_whitespace);
+    let backend = &mut gs.writer;
+    let mut cursor_screen = None;
+    for (line_idx, text) in editor.content.iter_mut().enumerate().skip(editor.cursor.at_line) {
+        if lines.is_finished() {
             break;
-        };
+        }
+        let row = lines.next_line_idx();
+        let select = ctx.get_select_full_line(text.char_len());
+        let bracket = editor.bracket_match.filter(|pos| pos.line == line_idx).map(|pos| pos.char);
+        if editor.cursor.line == line_idx {
+            let cursor = ctx.cursor_char();
+            let rows_before = ctx_rows(&lines, gs.editor_area.height);
+            text.cached.cursor(row, cursor, skip, select.clone());
+            let marks = WrapMarks { select, cursor: Some(cursor), bracket, skip };
+            cursor_screen = code::wrap::render(text, &mut ctx, &mut lines, marks, backend)
+                .map(|(rel_row, col)| (rows_before + rel_row, col));
+        } else {
+            text.cached.line(row, select.clone());
+            code::wrap::render(
+                text,
+                &mut ctx,
+                &mut lines,
+                WrapMarks { select, cursor: None, bracket, skip: 0 },
+                backend,
+            );
+        }
     }
     for line in lines {
         line.render_empty(&mut gs.writer);
     }
-    gs.render_stats(editor.content.len(), editor.cursor.select_len(&editor.content), (&editor.cursor).into());
+    gs.render_stats(&editor.status);
+    if let Some((row, col)) = cursor_screen {
+        ctx.set_cursor_screen_position(row, col);
+    }
     ctx.forced_modal_render(gs);
 }
 
+/// rows already used in the editor area
+#[inline(always)]
+fn ctx_rows(lines: &impl IterLines, height: u16) -> usize {
+    height as usize - lines.len()
+}
+
 // TEXT
 
 fn text_render(editor: &mut Editor, gs: &mut GlobalState) {
@@ -108,7 +412,12 @@ fn fast_text_render(editor: &mut Editor, gs: &mut GlobalState) {
     }
     editor.last_render_at_line.replace(editor.cursor.at_line);
     let mut lines = gs.editor_area.into_iter();
-    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset);
+    editor.refresh_status();
+    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset)
+        .with_annotated(editor.annotations.lines())
+        .with_relative_numbers(editor.relative_line_numbers)
+        .with_current_line(editor.highlight_current_line)
+        .with_whitespace(editor.render_whitespace);
     let backend = &mut gs.writer;
     for (line_idx, text) in editor.content.iter_mut().enumerate().skip(editor.cursor.at_line) {
         if lines.is_finished() {
@@ -134,14 +443,19 @@ fn fast_text_render(editor: &mut Editor, gs: &mut GlobalState) {
     for line in lines {
         line.render_empty(&mut gs.writer);
     }
-    gs.render_stats(editor.content.len(), editor.cursor.select_len(&editor.content), (&editor.cursor).into());
+    gs.render_stats(&editor.status);
 }
 
 #[inline(always)]
 fn text_full_render(editor: &mut Editor, gs: &mut GlobalState, skip: usize) {
     editor.last_render_at_line.replace(editor.cursor.at_line);
     let mut lines = gs.editor_area.into_iter();
-    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset);
+    editor.refresh_status();
+    let mut ctx = LineContext::collect_context(&mut editor.lexer, &editor.cursor, editor.line_number_offset)
+        .with_annotated(editor.annotations.lines())
+        .with_relative_numbers(editor.relative_line_numbers)
+        .with_current_line(editor.highlight_current_line)
+        .with_whitespace(editor.render_whitespace);
     let backend = &mut gs.writer;
     for (line_idx, text) in editor.content.iter_mut().enumerate().skip(editor.cursor.at_line) {
         if lines.is_finished() {
@@ -157,7 +471,7 @@ fn text_full_render(editor: &mut Editor, gs: &mut GlobalState, skip: usize) {
     for line in lines {
         line.render_empty(&mut gs.writer);
     }
-    gs.render_stats(editor.content.len(), editor.cursor.select_len(&editor.content), (&editor.cursor).into());
+    gs.render_stats(&editor.status);
 }
 
 // MARKDOWN
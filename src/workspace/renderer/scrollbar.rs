@@ -0,0 +1,155 @@
+use crate::{
+    render::{
+        backend::{color, BackendProtocol, Color, Style},
+        layout::Rect,
+    },
+    syntax::{DiagnosticLine, ERR_COLOR},
+    workspace::line::EditorLine,
+};
+use std::ops::Range;
+
+const TRACK: &str = "│";
+const THUMB: &str = "┃";
+
+/// One column scrollbar on the right edge of the editor with markers for lines with diagnostics
+#[derive(Default)]
+pub struct Scrollbar {
+    /// marker color per row of the bar
+    markers: Vec<Option<Color>>,
+    /// (content len, diagnostic updates, height) the markers were computed for
+    markers_key: Option<(usize, usize, usize)>,
+    /// last rendered thumb, None forces redraw
+    thumb: Option<Range<usize>>,
+    /// column of the bar relative to the editor area
+    col: Option<usize>,
+    height: usize,
+    dragging: bool,
+}
+
+impl Scrollbar {
+    /// renders the bar in the last column of the area, skipped if nothing changed since the last render
+    pub fn render(
+        &mut self,
+        content: &[EditorLine],
+        at_line: usize,
+        diagnostic_updates: usize,
+        area: Rect,
+        full: bool,
+        backend: &mut impl BackendProtocol,
+    ) {
+        let Some(rel_col) = area.width.checked_sub(1) else {
+            return;
+        };
+        let col = area.col + rel_col as u16;
+        let height = area.height as usize;
+        let markers_changed = self.update_markers(content, diagnostic_updates, height);
+        let thumb = thumb(content.len(), at_line, height);
+        if !full && !markers_changed && self.col == Some(rel_col) && self.thumb.as_ref() == Some(&thumb) {
+            return;
+        }
+        for (idx, marker) in self.markers.iter().enumerate() {
+            let on_thumb = thumb.contains(&idx);
+            let symbol = if on_thumb { THUMB } else { TRACK };
+            let style = match marker {
+                Some(color) => Style::fg(*color),
+                None if on_thumb => Style::fg(color::grey()),
+                None => Style::fg(color::dark_grey()),
+            };
+            backend.print_styled_at(area.row + idx as u16, col, symbol, style);
+        }
+        self.thumb = Some(thumb);
+        self.col = Some(rel_col);
+        self.height = height;
+    }
+
+    /// forces redraw on the next render
+    pub fn clear_cache(&mut self) {
+        self.thumb = None;
+    }
+
+    /// true if the column (relative to the editor area) is the bar - starts dragging
+    pub fn grab(&mut self, col: usize) -> bool {
+        self.dragging = self.col == Some(col);
+        self.dragging
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// first line of the view for the bar row
+    pub fn line_at(&self, row: usize, content_len: usize, max_rows: usize) -> usize {
+        if self.height == 0 {
+            return 0;
+        }
+        let line = row.min(self.height - 1) * content_len / self.height;
+        line.min(content_len.saturating_sub(max_rows))
+    }
+
+    /// recalculates marker rows if content length, diagnostics or height changed
+    fn update_markers(&mut self, content: &[EditorLine], diagnostic_updates: usize, height: usize) -> bool {
+        let key = (content.len(), diagnostic_updates, height);
+        if self.markers_key == Some(key) {
+            return false;
+        }
+        self.markers_key = Some(key);
+        self.markers.clear();
+        self.markers.resize(height, None);
+        if height == 0 {
+            return true;
+        }
+        for (idx, line) in content.iter().enumerate() {
+            let Some(color) = line.diagnostics.as_ref().and_then(DiagnosticLine::marker_color) else {
+                continue;
+            };
+            let marker = &mut self.markers[marker_row(idx, content.len(), height)];
+            if marker.is_none() || color == ERR_COLOR {
+                *marker = Some(color);
+            }
+        }
+        true
+    }
+}
+
+/// rows of the bar covered by the thumb
+fn thumb(content_len: usize, at_line: usize, height: usize) -> Range<usize> {
+    if content_len <= height {
+        return 0..height;
+    }
+    let size = (height * height / content_len).max(1);
+    let start = (at_line * height / content_len).min(height - size);
+    start..start + size
+}
+
+#[inline]
+fn marker_row(line: usize, content_len: usize, height: usize) -> usize {
+    line * height / content_len
+}
+
+#[cfg(test)]
+mod test {
+    use super::{marker_row, thumb, Scrollbar};
+
+    #[test]
+    fn thumb_size_and_position() {
+        assert_eq!(thumb(10, 0, 20), 0..20);
+        assert_eq!(thumb(100, 0, 10), 0..1);
+        assert_eq!(thumb(40, 0, 20), 0..10);
+        assert_eq!(thumb(40, 20, 20), 10..20);
+        assert_eq!(thumb(40, 39, 20), 10..20);
+        assert_eq!(thumb(1000, 999, 10), 9..10);
+    }
+
+    #[test]
+    fn rows_and_lines() {
+        assert_eq!(marker_row(0, 100, 10), 0);
+        assert_eq!(marker_row(55, 100, 10), 5);
+        assert_eq!(marker_row(99, 100, 10), 9);
+        let bar = Scrollbar { height: 10, ..Default::default() };
+        assert_eq!(bar.line_at(0, 100, 10), 0);
+        assert_eq!(bar.line_at(5, 100, 10), 50);
+        assert_eq!(bar.line_at(9, 100, 10), 90);
+        assert_eq!(bar.line_at(15, 100, 20), 80);
+        assert_eq!(bar.line_at(3, 5, 10), 0);
+    }
+}
@@ -4,7 +4,10 @@ use crate::{
         layout::RectIter,
         utils::ByteChunks,
     },
-    workspace::line::{EditorLine, LineContext},
+    workspace::{
+        line::{EditorLine, LineContext},
+        renderer::whitespace::{print_char, print_text},
+    },
 };
 use std::ops::Range;
 
@@ -14,8 +17,13 @@ pub fn line(text: &mut EditorLine, lines: &mut RectIter, ctx: &mut LineContext,
         None => return,
     };
     let mut chunks = ByteChunks::new(&text.content, line_width);
+    let whitespace = ctx.whitespace_from(text);
+    let mut idx = 0;
     match chunks.next() {
-        Some(chunk) => backend.print(chunk.text),
+        Some(chunk) => {
+            print_text(chunk.text, idx, whitespace, backend);
+            idx += chunk.text.len();
+        }
         None => return,
     }
     for chunk in chunks {
@@ -25,7 +33,8 @@ pub fn line(text: &mut EditorLine, lines: &mut RectIter, ctx: &mut LineContext,
                 ctx.wrap_line(line, backend);
             }
         }
-        backend.print(chunk.text);
+        print_text(chunk.text, idx, whitespace, backend);
+        idx += chunk.text.len();
     }
 }
 
@@ -46,6 +55,7 @@ pub fn line_with_select(
     }
     let mut line_end = line_width;
     let select_color = ctx.lexer.theme.selected;
+    let whitespace = ctx.whitespace_from(text);
     for (idx, text) in text.content.chars().enumerate() {
         if idx == line_end {
             line_end += line_width;
@@ -65,7 +75,7 @@ pub fn line_with_select(
         if select.end == idx {
             backend.reset_style();
         }
-        backend.print(text);
+        print_char(text, idx, whitespace, backend);
     }
     backend.reset_style();
 }
@@ -86,6 +96,7 @@ pub fn cursor(
 
 pub fn basic(text: &mut EditorLine, skip: usize, lines: &mut RectIter, ctx: &mut LineContext, backend: &mut Backend) {
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(text);
     let line_width = match lines.next() {
         Some(line) => ctx.setup_line(line, backend),
         None => return,
@@ -105,7 +116,7 @@ pub fn basic(text: &mut EditorLine, skip: usize, lines: &mut RectIter, ctx: &mut
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -125,6 +136,7 @@ pub fn select(
     backend: &mut Backend,
 ) {
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(text);
     let line_width = match lines.next() {
         Some(line) => ctx.setup_line(line, backend),
         None => return,
@@ -158,7 +170,7 @@ pub fn select(
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -6,7 +6,10 @@ use crate::{
         layout::RectIter,
         utils::WriteChunks,
     },
-    workspace::line::{EditorLine, LineContext},
+    workspace::{
+        line::{EditorLine, LineContext},
+        renderer::whitespace::{print_char, print_text},
+    },
 };
 use std::ops::Range;
 
@@ -16,8 +19,13 @@ pub fn line(text: &mut EditorLine, lines: &mut RectIter, ctx: &mut LineContext,
         None => return,
     };
     let mut chunks = WriteChunks::new(&text.content, line_width);
+    let whitespace = ctx.whitespace_from(text);
+    let mut idx = 0;
     match chunks.next() {
-        Some(chunk) => backend.print(chunk.text),
+        Some(chunk) => {
+            print_text(chunk.text, idx, whitespace, backend);
+            idx += chunk.text.chars().count();
+        }
         None => return,
     }
     for chunk in chunks {
@@ -27,7 +35,8 @@ pub fn line(text: &mut EditorLine, lines: &mut RectIter, ctx: &mut LineContext,
                 ctx.wrap_line(line, backend);
             }
         }
-        backend.print(chunk.text);
+        print_text(chunk.text, idx, whitespace, backend);
+        idx += chunk.text.chars().count();
     }
 }
 
@@ -44,6 +53,7 @@ pub fn line_with_select(
     };
     let mut remaining_width = line_width;
     let select_color = ctx.lexer.theme.selected;
+    let whitespace = ctx.whitespace_from(text);
     for (idx, text) in text.content.chars().enumerate() {
         let current_width = UnicodeWidthChar::width(text).unwrap_or_default();
         if remaining_width < current_width {
@@ -65,7 +75,7 @@ pub fn line_with_select(
         if select.end == idx {
             backend.reset_style();
         }
-        backend.print(text);
+        print_char(text, idx, whitespace, backend);
     }
     backend.reset_style();
 }
@@ -92,6 +102,7 @@ pub fn basic(
     backend: &mut Backend,
 ) {
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(text);
     let line_width = match lines.next() {
         Some(line) => ctx.setup_line(line, backend),
         None => return,
@@ -108,7 +119,7 @@ pub fn basic(
                 remaining_width = line_width - char_w;
                 skip -= 1;
                 if skip == 0 {
-                    backend.print(ch);
+                    print_char(ch, idx - 1, whitespace, backend);
                     break;
                 }
             } else {
@@ -130,7 +141,7 @@ pub fn basic(
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -150,6 +161,7 @@ pub fn select(
     backend: &mut Backend,
 ) {
     let cursor_idx = ctx.cursor_char();
+    let whitespace = ctx.whitespace_from(text);
     let line_width = match lines.next() {
         Some(line) => ctx.setup_line(line, backend),
         None => return,
@@ -170,7 +182,7 @@ pub fn select(
                     if idx > select.start && select.end > idx {
                         backend.set_bg(Some(select_color));
                     }
-                    backend.print(ch);
+                    print_char(ch, idx - 1, whitespace, backend);
                     break;
                 }
             } else {
@@ -204,7 +216,7 @@ pub fn select(
         if cursor_idx == idx {
             backend.print_styled(text, Style::reversed())
         } else {
-            backend.print(text);
+            print_char(text, idx, whitespace, backend);
         }
         idx += 1;
     }
@@ -0,0 +1,37 @@
+use crate::render::backend::{color, BackendProtocol};
+
+/// markers take single column - the same as the char in simple (ascii) lines
+#[inline(always)]
+fn marker(ch: char) -> Option<char> {
+    match ch {
+        ' ' => Some('·'),
+        '\t' => Some('→'),
+        _ => None,
+    }
+}
+
+/// prints the char at idx of the line, whitespace from the index on is shown as dim marker
+#[inline(always)]
+pub fn print_char(ch: char, idx: usize, whitespace: Option<usize>, backend: &mut impl BackendProtocol) {
+    match marker(ch) {
+        Some(marker) if whitespace.is_some_and(|from| idx >= from) => {
+            let mut style = backend.get_style();
+            style.set_fg(Some(color::dark_grey()));
+            backend.print_styled(marker, style);
+        }
+        _ => backend.print(ch),
+    }
+}
+
+/// prints text starting at char idx of the line, see print_char
+#[inline]
+pub fn print_text(text: &str, idx: usize, whitespace: Option<usize>, backend: &mut impl BackendProtocol) {
+    match whitespace {
+        None => backend.print(text),
+        Some(..) => {
+            for (offset, ch) in text.chars().enumerate() {
+                print_char(ch, idx + offset, whitespace, backend);
+            }
+        }
+    }
+}
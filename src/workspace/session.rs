@@ -0,0 +1,346 @@
+use super::{editor::VIEW_SECTIONS, utils::content_hash, CursorPosition, Editor};
+use crate::configs::get_config_dir;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SESSION_FILE: &str = "session.json";
+const SESSION_VERSION: u32 = 1;
+/// max entries in the recently closed list
+const RECENT_CAP: usize = 100;
+/// max files with stored view state, least recently stored are dropped
+const VIEW_STATE_CAP: usize = 100;
+
+/// Section of the per-file view state owned by a single feature.
+/// Features join by adding an entry into editor::VIEW_SECTIONS,
+/// the session code only moves the opaque values around.
+pub struct ViewSection {
+    pub key: &'static str,
+    pub store: fn(&Editor) -> Option<Value>,
+    pub restore: fn(&mut Editor, Value) -> Option<()>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct SessionFile {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    files: HashMap<PathBuf, FileViewState>,
+    #[serde(default)]
+    recent: Vec<ClosedFile>,
+}
+
+impl SessionFile {
+    fn migrate(self) -> (HashMap<PathBuf, FileViewState>, Vec<ClosedFile>) {
+        match self.version {
+            // unversioned or future formats are not trusted - state is cheap to lose
+            SESSION_VERSION => (self.files, self.recent),
+            _ => (HashMap::new(), Vec::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileViewState {
+    hash: u64,
+    #[serde(default)]
+    sections: HashMap<String, Value>,
+    /// unix timestamp in seconds
+    #[serde(default)]
+    stored_at: u64,
+}
+
+/// Recently closed file with the cursor position at closing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClosedFile {
+    pub path: PathBuf,
+    pub line: usize,
+    pub char: usize,
+    /// unix timestamp in seconds
+    pub closed_at: u64,
+}
+
+impl ClosedFile {
+    pub fn position(&self) -> CursorPosition {
+        CursorPosition { line: self.line, char: self.char }
+    }
+}
+
+/// Keeps view state of closed editors keyed by canonical path.
+/// State is restored only if the content hash still matches, stale entries are dropped.
+/// Also keeps the recently closed files (most recent first), files that no longer exist are pruned on save.
+/// Both are capped - view state of the least recently closed files is dropped first.
+#[derive(Default)]
+pub struct Session {
+    files: HashMap<PathBuf, FileViewState>,
+    recent: Vec<ClosedFile>,
+    path: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn load() -> Self {
+        Self::load_from(get_config_dir().map(|mut dir| {
+            dir.push(SESSION_FILE);
+            dir
+        }))
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let (files, recent) = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str::<SessionFile>(&text).ok())
+            .map(SessionFile::migrate)
+            .unwrap_or_default();
+        Self { files, recent, path }
+    }
+
+    /// records the file as recently closed, replacing the previous record of the path
+    pub fn closed(&mut self, editor: &Editor) {
+        if editor.ephemeral {
+            return;
+        }
+        let position = CursorPosition::from(&editor.cursor);
+        self.push_closed(ClosedFile {
+            path: editor.path.clone(),
+            line: position.line,
+            char: position.char,
+            closed_at: unix_now(),
+        });
+    }
+
+    fn push_closed(&mut self, file: ClosedFile) {
+        self.opened(&file.path);
+        self.recent.insert(0, file);
+        self.recent.truncate(RECENT_CAP);
+    }
+
+    /// open files are not listed as recently closed
+    pub fn opened(&mut self, path: &Path) {
+        self.recent.retain(|file| file.path != path);
+    }
+
+    pub fn recent(&self) -> &[ClosedFile] {
+        &self.recent
+    }
+
+    pub fn store(&mut self, editor: &Editor) {
+        self.store_with(editor, VIEW_SECTIONS);
+    }
+
+    pub fn restore(&mut self, editor: &mut Editor) {
+        self.restore_with(editor, VIEW_SECTIONS);
+    }
+
+    fn store_with(&mut self, editor: &Editor, view_sections: &[ViewSection]) {
+        if editor.ephemeral || editor.is_loading() {
+            return;
+        }
+        let mut sections = match self.files.remove(&editor.path) {
+            // keeps sections from unknown features (newer versions) as long as the content is the same
+            Some(state) if state.hash == content_hash(&editor.content) => state.sections,
+            _ => HashMap::new(),
+        };
+        for section in view_sections {
+            match (section.store)(editor) {
+                Some(value) => sections.insert(section.key.to_owned(), value),
+                None => sections.remove(section.key),
+            };
+        }
+        if sections.is_empty() {
+            return;
+        }
+        let state = FileViewState { hash: content_hash(&editor.content), sections, stored_at: unix_now() };
+        self.files.insert(editor.path.clone(), state);
+        self.prune_files();
+    }
+
+    fn prune_files(&mut self) {
+        if self.files.len() <= VIEW_STATE_CAP {
+            return;
+        }
+        let excess = self.files.len() - VIEW_STATE_CAP;
+        let mut stored = self.files.iter().map(|(path, state)| (state.stored_at, path.clone())).collect::<Vec<_>>();
+        stored.sort_unstable();
+        for (_, path) in stored.into_iter().take(excess) {
+            self.files.remove(&path);
+        }
+    }
+
+    fn restore_with(&mut self, editor: &mut Editor, view_sections: &[ViewSection]) {
+        let Some(state) = self.files.get(&editor.path) else {
+            return;
+        };
+        if state.hash != content_hash(&editor.content) {
+            self.files.remove(&editor.path);
+            return;
+        }
+        for section in view_sections {
+            // missing or malformed sections are skipped - the feature keeps its defaults
+            if let Some(value) = state.sections.get(section.key) {
+                let _ = (section.restore)(editor, value.clone());
+            }
+        }
+    }
+
+    pub fn save(&mut self) {
+        self.recent.retain(|file| file.path.exists());
+        self.files.retain(|path, _| path.exists());
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let session = SessionFile { version: SESSION_VERSION, files: self.files.clone(), recent: self.recent.clone() };
+        if let Ok(text) = serde_json::to_string(&session) {
+            let _ = std::fs::write(path, text);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClosedFile, FileViewState, Session, ViewSection, RECENT_CAP, VIEW_STATE_CAP};
+    use crate::workspace::{editor::code_tests::mock_editor, line::EditorLine, utils::content_hash, Editor};
+    use serde_json::Value;
+    use std::{collections::HashMap, path::PathBuf};
+
+    fn store_line(editor: &Editor) -> Option<Value> {
+        Some(Value::from(editor.cursor.line))
+    }
+
+    fn restore_line(editor: &mut Editor, value: Value) -> Option<()> {
+        editor.cursor.line = value.as_u64()? as usize;
+        Some(())
+    }
+
+    const SECTIONS: &[ViewSection] = &[ViewSection { key: "line", store: store_line, restore: restore_line }];
+
+    fn editor_at(path: &str, content: &[&str]) -> Editor {
+        let mut editor = mock_editor(content.iter().map(|line| line.to_string()).collect());
+        editor.path = PathBuf::from(path);
+        editor
+    }
+
+    #[test]
+    fn restore_matching_hash() {
+        let mut session = Session::default();
+        let mut editor = editor_at("/a.rs", &["one", "two", "three"]);
+        editor.cursor.line = 2;
+        session.store_with(&editor, SECTIONS);
+        let mut reopened = editor_at("/a.rs", &["one", "two", "three"]);
+        session.restore_with(&mut reopened, SECTIONS);
+        assert_eq!(reopened.cursor.line, 2);
+    }
+
+    #[test]
+    fn stale_hash_discards_state() {
+        let mut session = Session::default();
+        let mut editor = editor_at("/a.rs", &["one", "two", "three"]);
+        editor.cursor.line = 2;
+        session.store_with(&editor, SECTIONS);
+        let mut reopened = editor_at("/a.rs", &["one", "two changed", "three"]);
+        session.restore_with(&mut reopened, SECTIONS);
+        assert_eq!(reopened.cursor.line, 0);
+        assert!(session.files.is_empty());
+    }
+
+    #[test]
+    fn unknown_and_missing_sections() {
+        let mut session = Session::default();
+        let content = ["one", "two"];
+        let hash = content_hash(&content.iter().map(|l| EditorLine::from(l.to_string())).collect::<Vec<_>>());
+        let mut sections = HashMap::new();
+        sections.insert("folds".to_owned(), Value::from(vec![1, 2]));
+        session.files.insert(PathBuf::from("/a.rs"), FileViewState { hash, sections, stored_at: 0 });
+        let mut editor = editor_at("/a.rs", &content);
+        session.restore_with(&mut editor, SECTIONS);
+        assert_eq!(editor.cursor.line, 0);
+        editor.cursor.line = 1;
+        session.store_with(&editor, SECTIONS);
+        let state = session.files.get(&PathBuf::from("/a.rs")).unwrap();
+        assert_eq!(state.sections.get("folds"), Some(&Value::from(vec![1, 2])));
+        assert_eq!(state.sections.get("line"), Some(&Value::from(1)));
+    }
+
+    #[test]
+    fn malformed_section_is_skipped() {
+        let mut session = Session::default();
+        let editor = editor_at("/a.rs", &["one", "two"]);
+        session.store_with(&editor, SECTIONS);
+        session.files.get_mut(&PathBuf::from("/a.rs")).unwrap().sections.insert("line".to_owned(), Value::from("x"));
+        let mut reopened = editor_at("/a.rs", &["one", "two"]);
+        session.restore_with(&mut reopened, SECTIONS);
+        assert_eq!(reopened.cursor.line, 0);
+    }
+
+    #[test]
+    fn view_state_capped() {
+        let mut session = Session::default();
+        for idx in 0..VIEW_STATE_CAP {
+            let state = FileViewState { hash: 0, sections: HashMap::new(), stored_at: idx as u64 + 1 };
+            session.files.insert(PathBuf::from(format!("/{idx}.rs")), state);
+        }
+        session.store_with(&editor_at("/a.rs", &["one"]), SECTIONS);
+        assert_eq!(session.files.len(), VIEW_STATE_CAP);
+        assert!(session.files.contains_key(&PathBuf::from("/a.rs")));
+        assert!(!session.files.contains_key(&PathBuf::from("/0.rs")));
+        assert!(session.files.contains_key(&PathBuf::from("/1.rs")));
+        // missing files are dropped on save
+        session.save();
+        assert!(session.files.is_empty());
+    }
+
+    fn closed_file(path: PathBuf, closed_at: u64) -> ClosedFile {
+        ClosedFile { path, line: 1, char: 2, closed_at }
+    }
+
+    #[test]
+    fn recent_round_trip() {
+        let dir = std::env::temp_dir().join("idiom_test_recent_round_trip");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.rs"), dir.join("b.rs"));
+        std::fs::write(&a, "").unwrap();
+        std::fs::write(&b, "").unwrap();
+        let session_path = dir.join("session.json");
+        let mut session = Session { path: Some(session_path.clone()), ..Default::default() };
+        session.push_closed(closed_file(a.clone(), 1));
+        session.push_closed(closed_file(b.clone(), 2));
+        session.save();
+        let loaded = Session::load_from(Some(session_path));
+        assert_eq!(loaded.recent(), &[closed_file(b, 2), closed_file(a, 1)]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recent_dedup_and_prune() {
+        let mut session = Session::default();
+        session.push_closed(closed_file(PathBuf::from("/a.rs"), 1));
+        session.push_closed(closed_file(PathBuf::from("/b.rs"), 2));
+        session.push_closed(closed_file(PathBuf::from("/a.rs"), 3));
+        assert_eq!(
+            session.recent(),
+            &[
+                closed_file(PathBuf::from("/a.rs"), 3),
+                closed_file(PathBuf::from("/b.rs"), 2)
+            ]
+        );
+        // opening removes the file from the list
+        session.opened(&PathBuf::from("/a.rs"));
+        assert_eq!(session.recent(), &[closed_file(PathBuf::from("/b.rs"), 2)]);
+        for idx in 0..RECENT_CAP + 5 {
+            session.push_closed(closed_file(PathBuf::from(format!("/{idx}.rs")), idx as u64));
+        }
+        assert_eq!(session.recent().len(), RECENT_CAP);
+        assert_eq!(session.recent()[0].path, PathBuf::from(format!("/{}.rs", RECENT_CAP + 4)));
+        // missing files are dropped on save
+        session.save();
+        assert!(session.recent().is_empty());
+    }
+}
@@ -0,0 +1,415 @@
+use super::{
+    annotations::Annotation,
+    editor::{line_diff, LineDiff, VIEW_SECTIONS},
+    utils::{content_hash, text_hash},
+    CursorPosition, Editor,
+};
+use crate::configs::{get_config_dir, EditorConfigs, ViewOverrides, EDITOR_CFG_FILE};
+use lsp_types::{Range, TextEdit};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+pub const CONTEXT_FILE: &str = "./.idiom/context.json";
+const CONTEXT_VERSION: u32 = 1;
+/// bytes of buffer text (unsaved changes and scratch buffers) in the export, the rest is left out
+const SIZE_CAP: usize = 1024 * 1024;
+
+/// Editing context to be recreated on other machine - tabs with view state and unsaved changes,
+/// scratch buffers, annotations and the editor config.
+/// Unsaved changes are kept as hunks against the file on disk, unchanged files only by path.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    version: u32,
+    /// in tab order - the first is active
+    #[serde(default)]
+    tabs: Vec<TabState>,
+    #[serde(default)]
+    scratch: Vec<String>,
+    #[serde(default)]
+    annotations: Vec<(PathBuf, Vec<Annotation>)>,
+    /// editor.toml as written by the user
+    #[serde(default)]
+    config: Option<String>,
+}
+
+impl Snapshot {
+    /// snapshot of the editors with count of buffers left out by the size cap
+    /// paths within base are stored relative to it
+    pub fn capture<'a>(
+        editors: impl Iterator<Item = &'a Editor>,
+        notes: Vec<(PathBuf, Annotation)>,
+        config: Option<String>,
+        base: Option<&Path>,
+    ) -> (Self, usize) {
+        let mut snapshot = Self { version: CONTEXT_VERSION, config, ..Default::default() };
+        let mut budget = SIZE_CAP;
+        let mut left_out = 0;
+        let mut fits = |size: usize| match budget.checked_sub(size) {
+            Some(left) => {
+                budget = left;
+                true
+            }
+            None => {
+                left_out += 1;
+                false
+            }
+        };
+        for editor in editors {
+            if editor.ephemeral {
+                let text = editor.content.iter().map(|line| line.content.as_str()).collect::<Vec<_>>().join("\n");
+                if fits(text.len()) {
+                    snapshot.scratch.push(text);
+                }
+                continue;
+            }
+            let changes = match editor.is_loading() {
+                true => None,
+                false => Changes::of(editor).filter(|changes| fits(changes.size())),
+            };
+            snapshot.tabs.push(TabState {
+                path: relative(&editor.path, base),
+                hash: content_hash(&editor.content),
+                sections: VIEW_SECTIONS
+                    .iter()
+                    .filter_map(|section| Some((section.key.to_owned(), (section.store)(editor)?)))
+                    .collect(),
+                view: editor.view_overrides().clone(),
+                changes,
+            });
+        }
+        for (path, note) in notes {
+            let path = relative(&path, base);
+            match snapshot.annotations.last_mut() {
+                Some((last, notes)) if *last == path => notes.push(note),
+                _ => snapshot.annotations.push((path, vec![note])),
+            }
+        }
+        (snapshot, left_out)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))?;
+        let snapshot = serde_json::from_str::<Self>(&text).map_err(|error| error.to_string())?;
+        match snapshot.version {
+            CONTEXT_VERSION => Ok(snapshot),
+            version => Err(format!("Unsupported context version {version}")),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn tabs(&self) -> &[TabState] {
+        &self.tabs
+    }
+
+    pub fn scratch(&self) -> &[String] {
+        &self.scratch
+    }
+
+    pub fn config(&self) -> Option<&str> {
+        self.config.as_deref()
+    }
+
+    pub fn annotations(&self, base: Option<&Path>) -> impl Iterator<Item = (PathBuf, Vec<Annotation>)> + '_ {
+        let base = base.map(Path::to_path_buf);
+        self.annotations.iter().map(move |(path, notes)| (absolute(path, base.as_deref()), notes.clone()))
+    }
+}
+
+/// Open file with the view state, restored only on the same content
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TabState {
+    path: PathBuf,
+    /// hash of the buffer content the view state belongs to
+    hash: u64,
+    #[serde(default)]
+    sections: HashMap<String, Value>,
+    #[serde(default)]
+    view: ViewOverrides,
+    #[serde(default)]
+    changes: Option<Changes>,
+}
+
+impl TabState {
+    pub fn path(&self, base: Option<&Path>) -> PathBuf {
+        absolute(&self.path, base)
+    }
+
+    /// applies unsaved changes (all or none) and the view state to the editor opened from the file
+    pub fn restore(&self, editor: &mut Editor, report: &mut ImportReport) {
+        let name = self.path.display();
+        if let Some(changes) = self.changes.as_ref() {
+            let patched = match editor.is_dirty() {
+                true => Err("the open buffer has other unsaved changes"),
+                false => match std::fs::read_to_string(&editor.path) {
+                    Ok(text) => changes.apply(&text),
+                    Err(..) => Err("file not readable"),
+                },
+            };
+            match patched {
+                Ok(text) => replace_content(editor, text),
+                Err(reason) => report.skip(format_args!("{name} (unsaved changes)"), reason),
+            }
+        }
+        if content_hash(&editor.content) != self.hash {
+            report.skip(format_args!("{name} (view state)"), "content differs");
+            return;
+        }
+        editor.set_view_overrides(self.view.clone());
+        for section in VIEW_SECTIONS {
+            // malformed sections are skipped - the feature keeps its defaults
+            if let Some(value) = self.sections.get(section.key) {
+                let _ = (section.restore)(editor, value.clone());
+            }
+        }
+        report.restored += 1;
+    }
+}
+
+/// Unsaved changes of the buffer as hunks against the file on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct Changes {
+    /// hash of the file content the hunks apply to
+    base: u64,
+    hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Hunk {
+    /// first line replaced (in the file on disk)
+    line: usize,
+    removed: Vec<String>,
+    added: Vec<String>,
+}
+
+impl Changes {
+    /// None if the buffer is the same as the file on disk (or it can not be read)
+    fn of(editor: &Editor) -> Option<Self> {
+        let disk = std::fs::read_to_string(&editor.path).ok()?;
+        let base = text_hash(&disk);
+        if base == content_hash(&editor.content) {
+            return None;
+        }
+        let old = disk.split('\n').collect::<Vec<_>>();
+        let new = editor.content.iter().map(|line| line.content.as_str()).collect::<Vec<_>>();
+        let mut hunks: Vec<Hunk> = Vec::new();
+        let mut line = 0;
+        let mut open = false;
+        for change in line_diff(&old, &new) {
+            if matches!(change, LineDiff::Same(..)) {
+                line += 1;
+                open = false;
+                continue;
+            }
+            if !open {
+                hunks.push(Hunk { line, removed: Vec::new(), added: Vec::new() });
+                open = true;
+            }
+            let hunk = hunks.last_mut().expect("pushed above");
+            match change {
+                LineDiff::Removed(text) => {
+                    hunk.removed.push(text);
+                    line += 1;
+                }
+                LineDiff::Added(text) => hunk.added.push(text),
+                LineDiff::Same(..) => unreachable!("skipped above"),
+            }
+        }
+        Some(Self { base, hunks })
+    }
+
+    /// approximate bytes of the changes
+    fn size(&self) -> usize {
+        self.hunks.iter().flat_map(|hunk| hunk.removed.iter().chain(hunk.added.iter())).map(String::len).sum()
+    }
+
+    /// text with the hunks applied, fails if it is not the text the changes were made against
+    fn apply(&self, text: &str) -> Result<String, &'static str> {
+        if text_hash(text) != self.base {
+            return Err("file changed on disk");
+        }
+        let lines = text.split('\n').collect::<Vec<_>>();
+        let mut patched = Vec::with_capacity(lines.len());
+        let mut next = 0;
+        for hunk in self.hunks.iter() {
+            let end = hunk.line + hunk.removed.len();
+            if hunk.line < next
+                || end > lines.len()
+                || !lines[hunk.line..end].iter().copied().eq(hunk.removed.iter().map(String::as_str))
+            {
+                return Err("changes do not apply");
+            }
+            patched.extend_from_slice(&lines[next..hunk.line]);
+            patched.extend(hunk.added.iter().map(String::as_str));
+            next = end;
+        }
+        patched.extend_from_slice(&lines[next..]);
+        Ok(patched.join("\n"))
+    }
+}
+
+/// Outcome of the import - everything not recreated is listed
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub restored: usize,
+    pub skipped: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn skip(&mut self, item: impl Display, reason: impl Display) {
+        self.skipped.push(format!("{item}: {reason}"));
+    }
+}
+
+/// editor.toml of the config folder
+pub fn local_config() -> Option<String> {
+    std::fs::read_to_string(get_config_dir()?.join(EDITOR_CFG_FILE)).ok()
+}
+
+/// writes imported editor config keeping the local one as editor.toml.bak, false if they are the same
+pub fn replace_config(config: &str) -> Result<bool, String> {
+    let Some(path) = get_config_dir().map(|dir| dir.join(EDITOR_CFG_FILE)) else {
+        return Err(String::from("no config folder"));
+    };
+    if std::fs::read_to_string(&path).is_ok_and(|local| local == config) {
+        return Ok(false);
+    }
+    toml::from_str::<EditorConfigs>(config).map_err(|error| error.to_string())?;
+    if path.exists() {
+        std::fs::copy(&path, path.with_extension("toml.bak")).map_err(|error| error.to_string())?;
+    }
+    std::fs::write(&path, config).map_err(|error| error.to_string())?;
+    Ok(true)
+}
+
+/// single edit replacing the content - undone as one step
+pub fn replace_content(editor: &mut Editor, text: String) {
+    let last = editor.content.len().saturating_sub(1);
+    let end = CursorPosition { line: last, char: editor.content.get(last).map_or(0, |line| line.char_len()) };
+    editor.apply_file_edits(vec![TextEdit::new(
+        Range::new(CursorPosition::default().into(), end.into()),
+        text,
+    )]);
+    let position = CursorPosition::from(&editor.cursor);
+    editor.cursor.set_cursor_clamped(position, &editor.content);
+}
+
+fn relative(path: &Path, base: Option<&Path>) -> PathBuf {
+    base.and_then(|base| path.strip_prefix(base).ok()).unwrap_or(path).to_path_buf()
+}
+
+fn absolute(path: &Path, base: Option<&Path>) -> PathBuf {
+    match base {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ImportReport, Snapshot, SIZE_CAP};
+    use crate::workspace::{
+        annotations::Annotation, editor::code_tests::mock_editor, line::EditorLine, CursorPosition, Editor,
+    };
+    use std::path::{Path, PathBuf};
+
+    fn file_editor(path: PathBuf) -> Editor {
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut editor = mock_editor(text.split('\n').map(str::to_owned).collect());
+        editor.resize(80, 20);
+        editor.path = path;
+        editor
+    }
+
+    fn edit_line(editor: &mut Editor, line: usize, text: &str) {
+        let end = CursorPosition { line, char: editor.content[line].char_len() };
+        editor.replace_select(CursorPosition { line, char: 0 }, end, text);
+    }
+
+    fn lines(editor: &Editor) -> Vec<&str> {
+        editor.content.iter().map(|line| line.content.as_str()).collect()
+    }
+
+    #[test]
+    fn partial_import() {
+        let dir = std::env::temp_dir().join("idiom_test_context_export");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (same, edited, moved) = (dir.join("same.rs"), dir.join("edited.rs"), dir.join("moved.rs"));
+        std::fs::write(&same, "private text\nunchanged").unwrap();
+        std::fs::write(&edited, "one\ntwo\nthree\nfour").unwrap();
+        std::fs::write(&moved, "alpha\nbeta").unwrap();
+
+        let mut editors = vec![
+            file_editor(same.clone()),
+            file_editor(edited.clone()),
+            file_editor(moved.clone()),
+        ];
+        editors[0].cursor.set_position(CursorPosition { line: 1, char: 3 });
+        edit_line(&mut editors[1], 1, "TWO");
+        editors[1].replace_select(CursorPosition { line: 3, char: 4 }, CursorPosition { line: 3, char: 4 }, "\nfive");
+        edit_line(&mut editors[2], 0, "ALPHA");
+        let mut scratch = mock_editor(vec!["scratch notes".to_owned()]);
+        scratch.ephemeral = true;
+        editors.push(scratch);
+        let note = Annotation::new(0, &EditorLine::from("one".to_owned()), "check".to_owned());
+        let (snapshot, left_out) =
+            Snapshot::capture(editors.iter(), vec![(edited.clone(), note)], None, Some(dir.as_path()));
+        assert_eq!(left_out, 0);
+        snapshot.write(&dir.join("context.json")).unwrap();
+        let exported = std::fs::read_to_string(dir.join("context.json")).unwrap();
+        // unchanged files are referenced only by path
+        assert!(!exported.contains("private text"));
+        assert!(exported.contains("\"same.rs\""));
+
+        // target machine - one file changed on disk before import
+        std::fs::write(&moved, "alpha\nbeta\ngamma").unwrap();
+        let snapshot = Snapshot::read(&dir.join("context.json")).unwrap();
+        assert_eq!(snapshot.scratch(), ["scratch notes"]);
+        let notes = snapshot.annotations(Some(dir.as_path())).collect::<Vec<_>>();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].0, edited);
+        let mut report = ImportReport::default();
+        let mut imported = Vec::new();
+        for tab in snapshot.tabs() {
+            let mut editor = file_editor(tab.path(Some(dir.as_path())));
+            tab.restore(&mut editor, &mut report);
+            imported.push(editor);
+        }
+        assert_eq!(report.restored, 2);
+        assert_eq!(
+            report.skipped,
+            [
+                format!("{} (unsaved changes): file changed on disk", Path::new("moved.rs").display()),
+                format!("{} (view state): content differs", Path::new("moved.rs").display()),
+            ]
+        );
+        assert_eq!(CursorPosition::from(&imported[0].cursor), CursorPosition { line: 1, char: 3 });
+        assert_eq!(lines(&imported[1]), ["one", "TWO", "three", "four", "five"]);
+        assert!(!imported[1].is_saved());
+        // left untouched
+        assert_eq!(lines(&imported[2]), ["alpha", "beta", "gamma"]);
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "one\ntwo\nthree\nfour");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn size_cap() {
+        let mut scratch = mock_editor(vec!["x".repeat(SIZE_CAP + 1)]);
+        scratch.ephemeral = true;
+        let mut small = mock_editor(vec!["small".to_owned()]);
+        small.ephemeral = true;
+        let (snapshot, left_out) = Snapshot::capture([scratch, small].iter(), Vec::new(), None, None);
+        assert_eq!(left_out, 1);
+        assert_eq!(snapshot.scratch(), ["small"]);
+    }
+}
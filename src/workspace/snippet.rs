@@ -0,0 +1,337 @@
+use super::{cursor::Select, line::EditorLine, CursorPosition};
+
+/// Tab stop position relative to the start of the snippet, the chars of the first line are counted from the insert
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabStop {
+    pub line: usize,
+    pub char: usize,
+    /// chars of the default text selected on the stop (single line only)
+    pub len: usize,
+}
+
+/// Expanded LSP snippet - stops are in the order of visiting, the last one is $0 (or the end of the text)
+/// nested placeholders are flattened to their default text, repeated stops are visited at first occurrence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub text: String,
+    pub stops: Vec<TabStop>,
+}
+
+impl Snippet {
+    pub fn parse(snippet: &str) -> Self {
+        let mut parser = Parser { chars: snippet.chars().collect(), idx: 0, text: String::new(), line: 0, char: 0 };
+        let mut stops = Vec::new();
+        parser.parse_any(false, &mut stops);
+        let has_final = stops.iter().any(|(number, _)| *number == 0);
+        stops.sort_by_key(|(number, _)| match number {
+            0 => usize::MAX,
+            number => *number,
+        });
+        let mut stops = stops.into_iter().map(|(_, stop)| stop).collect::<Vec<_>>();
+        if !has_final {
+            stops.push(TabStop { line: parser.line, char: parser.char, len: 0 });
+        }
+        Self { text: parser.text, stops }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    idx: usize,
+    text: String,
+    line: usize,
+    char: usize,
+}
+
+impl Parser {
+    fn push(&mut self, ch: char) {
+        if ch == '\n' {
+            self.line += 1;
+            self.char = 0;
+        } else {
+            self.char += 1;
+        }
+        self.text.push(ch);
+    }
+
+    fn push_str(&mut self, text: &str) {
+        text.chars().for_each(|ch| self.push(ch));
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn next_if(&mut self, predicate: impl Fn(char) -> bool) -> Option<char> {
+        let ch = self.peek().filter(|ch| predicate(*ch))?;
+        self.idx += 1;
+        Some(ch)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(ch) = self.next_if(&predicate) {
+            taken.push(ch);
+        }
+        taken
+    }
+
+    fn take_variable(&mut self) -> String {
+        match self.peek() {
+            Some(ch) if ch.is_ascii_alphabetic() || ch == '_' => {
+                self.take_while(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// text until the end of the snippet or the closing brace of the placeholder (consumed)
+    fn parse_any(&mut self, in_placeholder: bool, stops: &mut Vec<(usize, TabStop)>) {
+        while let Some(ch) = self.peek() {
+            self.idx += 1;
+            match ch {
+                '\\' => match self.next_if(|ch| matches!(ch, '$' | '}' | '\\')) {
+                    Some(escaped) => self.push(escaped),
+                    None => self.push(ch),
+                },
+                '}' if in_placeholder => return,
+                '$' => self.parse_dollar(stops),
+                ch => self.push(ch),
+            }
+        }
+    }
+
+    fn parse_dollar(&mut self, stops: &mut Vec<(usize, TabStop)>) {
+        let number = self.take_while(|ch| ch.is_ascii_digit());
+        if let Ok(number) = number.parse() {
+            self.add_stop(number, self.line, self.char, stops);
+            return;
+        }
+        let name = self.take_variable();
+        if !name.is_empty() {
+            // unknown variables are inserted by name
+            return self.push_str(&name);
+        }
+        if self.next_if(|ch| ch == '{').is_none() {
+            return self.push('$');
+        }
+        let (line, char) = (self.line, self.char);
+        let number = self.take_while(|ch| ch.is_ascii_digit());
+        let name = if number.is_empty() { self.take_variable() } else { String::new() };
+        let mut nested = Vec::new();
+        match self.peek() {
+            Some('}') => {
+                self.idx += 1;
+            }
+            Some(':') => {
+                self.idx += 1;
+                // nested stops are flattened to the default text
+                self.parse_any(true, &mut nested);
+            }
+            Some('|') if !number.is_empty() => {
+                self.idx += 1;
+                self.parse_choice();
+            }
+            // transforms are not supported - skipped with the variable
+            Some('/') => {
+                while let Some(ch) = self.peek() {
+                    self.idx += 1;
+                    match ch {
+                        '\\' => self.idx += 1,
+                        '}' => break,
+                        _ => (),
+                    }
+                }
+            }
+            _ => return self.push_str(&format!("${{{number}{name}")),
+        }
+        if let Ok(number) = number.parse() {
+            self.add_stop(number, line, char, stops);
+        }
+    }
+
+    /// first option of ${1|one,two|} is inserted
+    fn parse_choice(&mut self) {
+        let mut first = true;
+        while let Some(ch) = self.peek() {
+            self.idx += 1;
+            match ch {
+                '\\' => {
+                    if let Some(escaped) = self.next_if(|ch| matches!(ch, '$' | '}' | '\\' | ',' | '|')) {
+                        if first {
+                            self.push(escaped);
+                        }
+                    }
+                }
+                ',' => first = false,
+                '|' if self.next_if(|ch| ch == '}').is_some() => return,
+                ch if first => self.push(ch),
+                _ => (),
+            }
+        }
+    }
+
+    /// stop from the start position to the current one - selection is kept only on a single line
+    fn add_stop(&self, number: usize, line: usize, char: usize, stops: &mut Vec<(usize, TabStop)>) {
+        if stops.iter().any(|(added, _)| *added == number) {
+            return;
+        }
+        let len = if line == self.line { self.char - char } else { 0 };
+        stops.push((number, TabStop { line, char, len }));
+    }
+}
+
+/// Tab stops of the inserted snippet - Tab/Shift-Tab move between them until the last one is reached
+/// typing inside the current stop shifts the stops after it on the same line, edits elsewhere end the snippet
+pub struct SnippetStops {
+    stops: Vec<Select>,
+    current: usize,
+}
+
+/// state of the content before an action, compared after it to track the stops
+pub struct SnippetWatch {
+    current: usize,
+    lines: usize,
+    /// char len of every line with a stop
+    line_lens: Vec<(usize, usize)>,
+}
+
+impl SnippetStops {
+    /// None if there is nothing to move to after the first stop
+    pub fn new(stops: Vec<Select>) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        Some(Self { stops, current: 0 })
+    }
+
+    /// stop moved to, None if the last one is already selected
+    pub fn next(&mut self) -> Option<Select> {
+        if self.is_last() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.stops[self.current])
+    }
+
+    pub fn prev(&mut self) -> Option<Select> {
+        self.current = self.current.checked_sub(1)?;
+        Some(self.stops[self.current])
+    }
+
+    /// $0 (or the end of the snippet) - snippet mode ends on it
+    #[inline]
+    pub fn is_last(&self) -> bool {
+        self.current + 1 == self.stops.len()
+    }
+
+    /// lines inserted or removed above the snippet, false if a stop would move before the start of the content
+    pub fn shift_lines(&mut self, from_line: usize, to_line: usize) -> bool {
+        for (from, to) in self.stops.iter_mut() {
+            let Some(line) = (from.line + to_line).checked_sub(from_line) else {
+                return false;
+            };
+            from.line = line;
+            to.line = line;
+        }
+        true
+    }
+
+    pub fn watch(&self, content: &[EditorLine]) -> SnippetWatch {
+        let mut line_lens = Vec::new();
+        for (from, _) in self.stops.iter() {
+            if line_lens.iter().all(|(line, _)| line != &from.line) {
+                line_lens.push((from.line, content.get(from.line).map(EditorLine::char_len).unwrap_or_default()));
+            }
+        }
+        SnippetWatch { current: self.current, lines: content.len(), line_lens }
+    }
+
+    /// false if the action edited the content outside of the current stop
+    pub fn track(&mut self, watch: SnippetWatch, cursor: CursorPosition, content: &[EditorLine]) -> bool {
+        if watch.current != self.current {
+            return true;
+        }
+        if watch.lines != content.len() {
+            return false;
+        }
+        let (from, to) = self.stops[self.current];
+        let mut delta = 0;
+        for (line, len) in watch.line_lens {
+            let new_len = content[line].char_len();
+            if line == from.line {
+                delta = new_len as isize - len as isize;
+            } else if new_len != len {
+                return false;
+            }
+        }
+        if delta == 0 {
+            return true;
+        }
+        let end = to.char as isize + delta;
+        if cursor.line != from.line || end < from.char as isize || cursor.char < from.char || cursor.char as isize > end
+        {
+            return false;
+        }
+        for (idx, (stop_from, stop_to)) in self.stops.iter_mut().enumerate() {
+            if idx == self.current {
+                stop_to.char = end as usize;
+            } else if stop_from.line == from.line && stop_from.char >= to.char {
+                stop_from.char = (stop_from.char as isize + delta) as usize;
+                stop_to.char = (stop_to.char as isize + delta) as usize;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Snippet, SnippetStops, TabStop};
+    use crate::workspace::{line::EditorLine, CursorPosition};
+
+    fn stop(line: usize, char: usize, len: usize) -> TabStop {
+        TabStop { line, char, len }
+    }
+
+    #[test]
+    fn parse_stops() {
+        let snippet = Snippet::parse("fn ${1:name}(${2:arg}: ${3:u32}) {\n    $0\n}");
+        assert_eq!(snippet.text, "fn name(arg: u32) {\n    \n}");
+        assert_eq!(snippet.stops, [stop(0, 3, 4), stop(0, 8, 3), stop(0, 13, 3), stop(1, 4, 0)]);
+        // no $0 - ends after the text, nested flattened, choice takes the first option
+        let snippet = Snippet::parse("${2:b} ${1:outer ${3:inner}} ${4|one,two|} \\$5 $TM_VAR");
+        assert_eq!(snippet.text, "b outer inner one $5 TM_VAR");
+        assert_eq!(snippet.stops, [stop(0, 2, 11), stop(0, 0, 1), stop(0, 14, 3), stop(0, 27, 0)]);
+        let snippet = Snippet::parse("plain text");
+        assert_eq!(snippet.stops, [stop(0, 10, 0)]);
+        let snippet = Snippet::parse("${1:multi\nline}$1");
+        assert_eq!(snippet.text, "multi\nline");
+        assert_eq!(snippet.stops, [stop(0, 0, 0), stop(1, 4, 0)]);
+    }
+
+    #[test]
+    fn stops_follow_typing() {
+        let pos = |line, char| CursorPosition { line, char };
+        let mut content: Vec<EditorLine> = vec!["call(arg, other);".into()];
+        let mut stops = SnippetStops::new(vec![
+            (pos(0, 5), pos(0, 8)),
+            (pos(0, 10), pos(0, 15)),
+            (pos(0, 17), pos(0, 17)),
+        ])
+        .unwrap();
+        // select replaced with longer text
+        let watch = stops.watch(&content);
+        content[0] = "call(value, other);".into();
+        assert!(stops.track(watch, pos(0, 10), &content));
+        assert_eq!(stops.next(), Some((pos(0, 12), pos(0, 17))));
+        assert_eq!(stops.prev(), Some((pos(0, 5), pos(0, 10))));
+        assert_eq!(stops.prev(), None);
+        // typing outside of the stop ends the snippet
+        let watch = stops.watch(&content);
+        content[0] = "call(value, other);x".into();
+        assert!(!stops.track(watch, pos(0, 20), &content));
+        assert!(SnippetStops::new(vec![(pos(0, 1), pos(0, 1))]).is_none());
+    }
+}
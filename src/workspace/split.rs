@@ -0,0 +1,169 @@
+use super::{CursorPosition, Editor};
+use crate::{
+    global_state::GlobalState,
+    render::{
+        backend::{color, BackendProtocol, Style},
+        layout::Rect,
+    },
+};
+use std::path::PathBuf;
+
+const SEPARATOR: &str = "│";
+
+/// Vertical split of the editor area between two editors.
+/// The focused pane holds the active editor and the other pane the editor at `other`,
+/// focus change swaps them so each editor keeps rendering within the same pane.
+pub struct SplitView {
+    /// index of the editor in the other (not focused) pane
+    pub other: usize,
+    focus_right: bool,
+    // editor last rendered in each pane (left, right) - new occupant requires resize and full render
+    rendered: [Option<PathBuf>; 2],
+}
+
+impl SplitView {
+    pub fn new(other: usize) -> Self {
+        Self { other, focus_right: false, rendered: [None, None] }
+    }
+
+    /// returns (focused, other) panes
+    pub fn panes(&self, area: Rect) -> (Rect, Rect) {
+        let (left, right) = split_area(area);
+        match self.focus_right {
+            true => (right, left),
+            false => (left, right),
+        }
+    }
+
+    #[inline]
+    pub fn swap_focus(&mut self) {
+        self.focus_right = !self.focus_right;
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        self.rendered = [None, None];
+    }
+
+    /// maps screen position to the pane containing it (true if it is the focused one)
+    pub fn position(&self, area: Rect, row: u16, column: u16) -> Option<(bool, CursorPosition)> {
+        let (focused, other) = self.panes(area);
+        if let Some(position) = relative_position(focused, row, column) {
+            return Some((true, position));
+        }
+        relative_position(other, row, column).map(|position| (false, position))
+    }
+
+    pub fn render(&mut self, editors: &mut [Editor], active: usize, gs: &mut GlobalState, full: bool) {
+        let full_area = gs.editor_area;
+        let (focused_pane, other_pane) = self.panes(full_area);
+        if let Some((focused, other)) = pair_mut(editors, active, self.other) {
+            // inactive is rendered first, so that the footer stats belong to the focused editor
+            self.render_pane(other, other_pane, false, gs, full);
+            self.render_pane(focused, focused_pane, true, gs, full);
+            if full {
+                let (_, right) = split_area(full_area);
+                for row in right.row..right.row + right.height {
+                    gs.writer.print_styled_at(row, right.col - 1, SEPARATOR, Style::fg(color::dark_grey()));
+                }
+            }
+        }
+        gs.editor_area = full_area;
+    }
+
+    fn render_pane(&mut self, editor: &mut Editor, pane: Rect, focused: bool, gs: &mut GlobalState, full: bool) {
+        gs.editor_area = pane;
+        let slot = &mut self.rendered[(focused == self.focus_right) as usize];
+        if slot.as_ref() != Some(&editor.path) {
+            slot.replace(editor.path.clone());
+            editor.resize(pane.width, pane.height as usize);
+            editor.invalidate_render("split pane");
+        }
+        let rerendered = full || editor.last_render_at_line != Some(editor.cursor.at_line);
+        // inactive pane is drawn faint - focus change rerenders both panes
+        gs.writer.set_dim(!focused);
+        match full {
+            true => editor.render(gs),
+            false => editor.fast_render(gs),
+        }
+        if !focused && rerendered {
+            dim_cursor_line_number(editor, pane, gs);
+        }
+        gs.writer.set_dim(false);
+    }
+}
+
+/// the cursor line number is the only highlighted one - inactive pane uses the same style for all
+fn dim_cursor_line_number(editor: &Editor, pane: Rect, gs: &mut GlobalState) {
+    if editor.line_number_offset == 0 {
+        return;
+    }
+    let Some(rel_line) = editor.cursor.line.checked_sub(editor.cursor.at_line) else {
+        return;
+    };
+    if rel_line >= pane.height as usize {
+        return;
+    }
+    let text = format!("{: >1$} ", editor.cursor.line + 1, editor.line_number_offset);
+    gs.writer.print_styled_at(pane.row + rel_line as u16, pane.col, text, Style::fg(color::dark_grey()));
+}
+
+/// mutable references to two different editors
+fn pair_mut(editors: &mut [Editor], first: usize, second: usize) -> Option<(&mut Editor, &mut Editor)> {
+    if first < second {
+        let (head, tail) = editors.split_at_mut(second);
+        Some((head.get_mut(first)?, tail.first_mut()?))
+    } else if second < first {
+        let (head, tail) = editors.split_at_mut(first);
+        Some((tail.first_mut()?, head.get_mut(second)?))
+    } else {
+        None
+    }
+}
+
+/// splits area in two with separator column in between
+fn split_area(area: Rect) -> (Rect, Rect) {
+    let mut left = area;
+    let mut right = left.splitoff_cols(area.width - area.width / 2);
+    right.col += 1;
+    right.width = right.width.saturating_sub(1);
+    (left, right)
+}
+
+fn relative_position(pane: Rect, row: u16, column: u16) -> Option<CursorPosition> {
+    if row < pane.row || row >= pane.row + pane.height || column < pane.col {
+        return None;
+    }
+    let char = (column - pane.col) as usize;
+    if char >= pane.width {
+        return None;
+    }
+    Some(CursorPosition { line: (row - pane.row) as usize, char })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{split_area, SplitView};
+    use crate::{render::layout::Rect, workspace::CursorPosition};
+
+    #[test]
+    fn split_rects() {
+        let (left, right) = split_area(Rect::new(1, 10, 81, 20));
+        assert_eq!((left.col, left.width), (10, 40));
+        assert_eq!((right.col, right.width), (51, 40));
+        assert_eq!(left.height, right.height);
+    }
+
+    #[test]
+    fn mouse_routing() {
+        let area = Rect::new(1, 10, 81, 20);
+        let mut split = SplitView::new(1);
+        assert_eq!(split.position(area, 2, 12), Some((true, CursorPosition { line: 1, char: 2 })));
+        assert_eq!(split.position(area, 2, 53), Some((false, CursorPosition { line: 1, char: 2 })));
+        // separator column
+        assert_eq!(split.position(area, 2, 50), None);
+        split.swap_focus();
+        assert_eq!(split.position(area, 2, 53), Some((true, CursorPosition { line: 1, char: 2 })));
+        assert_eq!(split.position(area, 2, 12), Some((false, CursorPosition { line: 1, char: 2 })));
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+pub const LEFT_CLIPPED: &str = "… ";
+pub const RIGHT_CLIPPED: &str = " …";
+pub const SEPARATOR: &str = " | ";
+const INDICATOR_WIDTH: usize = 2;
+const SEPARATOR_WIDTH: usize = 3;
+
+/// Clickable parts of the tab bar
+#[derive(Debug, PartialEq)]
+pub enum TabHit {
+    /// position within the rendered tabs
+    Tab(usize),
+    ScrollLeft,
+    ScrollRight,
+}
+
+/// Horizontal scroll of the tab bar - tabs are positioned by rendered width,
+/// the strip follows the active tab when it changes, indicators scroll it by a tab
+#[derive(Default)]
+pub struct TabBar {
+    /// first visible tab
+    offset: usize,
+    /// active editor the strip was last scrolled to
+    anchor: Option<PathBuf>,
+}
+
+impl TabBar {
+    /// visible tabs for the widths (in render order), the active tab is brought into view if changed since last call
+    pub fn visible(&mut self, widths: &[usize], active: usize, active_path: &Path, max_width: usize) -> Range<usize> {
+        if widths.is_empty() {
+            self.offset = 0;
+            return 0..0;
+        }
+        self.offset = self.offset.min(widths.len() - 1);
+        if self.anchor.as_deref() != Some(active_path) {
+            self.anchor = Some(active_path.to_owned());
+            self.offset = self.offset.min(active);
+            while visible_end(widths, self.offset, max_width) <= active {
+                self.offset += 1;
+            }
+        }
+        self.offset..visible_end(widths, self.offset, max_width)
+    }
+
+    /// the layout is the same as on last render (tabs are not changed in between)
+    pub fn hit(&self, widths: &[usize], max_width: usize, col: usize) -> Option<TabHit> {
+        let offset = self.offset.min(widths.len().checked_sub(1)?);
+        let end = visible_end(widths, offset, max_width);
+        let mut start = 0;
+        if offset > 0 {
+            start += INDICATOR_WIDTH;
+            if col < start {
+                return Some(TabHit::ScrollLeft);
+            }
+        }
+        for (position, width) in widths.iter().enumerate().take(end).skip(offset) {
+            // separator after the tab is part of the hit box
+            start += width + SEPARATOR_WIDTH;
+            if col < start {
+                return Some(TabHit::Tab(position));
+            }
+        }
+        match end < widths.len() {
+            true => Some(TabHit::ScrollRight),
+            false => None,
+        }
+    }
+
+    pub fn scroll_left(&mut self) {
+        self.offset = self.offset.saturating_sub(1);
+    }
+
+    pub fn scroll_right(&mut self, tabs: usize) {
+        if self.offset + 1 < tabs {
+            self.offset += 1;
+        }
+    }
+}
+
+/// end of the tabs fitting from offset (at least one) - space for the clipped indicators is kept
+fn visible_end(widths: &[usize], offset: usize, max_width: usize) -> usize {
+    let mut used = if offset > 0 { INDICATOR_WIDTH } else { 0 };
+    let mut end = offset;
+    while end < widths.len() {
+        let separator = if end == offset { 0 } else { SEPARATOR_WIDTH };
+        let needed = used + separator + widths[end];
+        let indicator = if end + 1 < widths.len() { INDICATOR_WIDTH } else { 0 };
+        if end > offset && needed + indicator > max_width {
+            break;
+        }
+        used = needed;
+        end += 1;
+    }
+    end
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TabBar, TabHit};
+    use crate::render::UTF8Safe;
+    use std::path::Path;
+
+    #[test]
+    fn scrolls_to_active() {
+        let widths = [10, 10, 10, 10];
+        let mut bar = TabBar::default();
+        assert_eq!(bar.visible(&widths, 0, Path::new("a"), 30), 0..2);
+        assert_eq!(bar.visible(&widths, 3, Path::new("d"), 30), 2..4);
+        // indicators scroll without activation
+        bar.scroll_left();
+        assert_eq!(bar.visible(&widths, 3, Path::new("d"), 30), 1..3);
+        bar.scroll_right(widths.len());
+        bar.scroll_right(widths.len());
+        bar.scroll_right(widths.len());
+        assert_eq!(bar.visible(&widths, 3, Path::new("d"), 30), 3..4);
+        assert_eq!(bar.visible(&widths, 0, Path::new("a"), 30), 0..2);
+        assert_eq!(bar.visible(&[], 0, Path::new("a"), 30), 0..0);
+    }
+
+    #[test]
+    fn cjk_hit_boxes() {
+        let names = ["中文文件名.rs", "main.rs", "日本語のファイル.rs", "lib.rs"];
+        assert_eq!(names[0].len(), 18);
+        assert_eq!(names[0].width(), 13);
+        let widths = names.iter().map(|name| name.width()).collect::<Vec<_>>();
+        let mut bar = TabBar::default();
+        // "中文文件名.rs | main.rs | " ...
+        assert_eq!(bar.visible(&widths, 0, Path::new(names[0]), 40), 0..2);
+        assert_eq!(bar.hit(&widths, 40, 0), Some(TabHit::Tab(0)));
+        assert_eq!(bar.hit(&widths, 40, 15), Some(TabHit::Tab(0)));
+        // byte len would still point to the first tab
+        assert_eq!(bar.hit(&widths, 40, 17), Some(TabHit::Tab(1)));
+        assert_eq!(bar.hit(&widths, 40, 25), Some(TabHit::Tab(1)));
+        assert_eq!(bar.hit(&widths, 40, 26), Some(TabHit::ScrollRight));
+        assert_eq!(bar.visible(&widths, 2, Path::new(names[2]), 40), 1..4);
+        // "… main.rs | 日本語のファイル.rs | lib.rs"
+        assert_eq!(bar.hit(&widths, 40, 1), Some(TabHit::ScrollLeft));
+        assert_eq!(bar.hit(&widths, 40, 2), Some(TabHit::Tab(1)));
+        assert_eq!(bar.hit(&widths, 40, 12), Some(TabHit::Tab(2)));
+        assert_eq!(bar.hit(&widths, 40, 33), Some(TabHit::Tab(2)));
+        assert_eq!(bar.hit(&widths, 40, 34), Some(TabHit::Tab(3)));
+        assert_eq!(bar.hit(&widths, 40, 60), None);
+    }
+}
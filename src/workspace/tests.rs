@@ -1,14 +1,21 @@
 use super::{
+    annotations::Annotations,
+    cursor::Cursor,
     editor::Editor,
     line::EditorLine,
-    map_editor,
-    utils::{clip_content, copy_content, insert_clip, remove_content},
+    map_editor, save_editors,
+    session::Session,
+    tab_bar::TabBar,
+    utils::{clip_content, copy_content, insert_clip, remove_content, token_range_at},
     Workspace,
 };
 use crate::{
-    configs::{test::mock_editor_key_map, EditorConfigs},
+    configs::{test::mock_editor_key_map, AutoSave, EditorAction, EditorConfigs, SearchOptions},
     global_state::GlobalState,
-    render::backend::{Backend, BackendProtocol, Style},
+    render::{
+        backend::{Backend, BackendProtocol, Style},
+        layout::Rect,
+    },
     workspace::{
         actions::tests::create_content,
         editor::code_tests::{mock_editor, pull_line, select_eq},
@@ -21,11 +28,17 @@ use std::collections::HashMap;
 pub fn mock_ws(content: Vec<String>) -> Workspace {
     let mut ws = Workspace {
         editors: vec![mock_editor(content)].into(),
+        active: 0,
         base_config: EditorConfigs::default(),
         key_map: mock_editor_key_map(),
         lsp_servers: HashMap::default(),
+        session: Session::default(),
+        annotations: Annotations::default(),
+        split: None,
+        peer: None,
         map_callback: map_editor,
         tab_style: Style::default(),
+        tab_bar: TabBar::default(),
     };
     ws.resize_all(60, 90);
     ws
@@ -315,6 +328,100 @@ fn test_cut_paste() {
     assert_eq!(pull_line(active(&mut ws), 2).unwrap(), "realt one here");
 }
 
+fn jumps(content: &[EditorLine], start: CursorPosition, jump: fn(&mut Cursor, &[EditorLine])) -> Vec<usize> {
+    let mut cursor = Cursor::default();
+    cursor.set_position(start);
+    let mut stops = vec![];
+    loop {
+        jump(&mut cursor, content);
+        let position: CursorPosition = (&cursor).into();
+        if position.line != start.line || stops.last() == Some(&position.char) {
+            return stops;
+        }
+        stops.push(position.char);
+    }
+}
+
+#[test]
+fn test_jump_char_classes() {
+    let content = vec![EditorLine::from("my_var2 = foo.bar(x);".to_owned())];
+    assert_eq!(jumps(&content, CursorPosition::default(), Cursor::jump_right), vec![7, 9, 13, 14, 17, 18, 19, 21]);
+    let end = CursorPosition { line: 0, char: 21 };
+    assert_eq!(jumps(&content, end, Cursor::jump_left), vec![19, 18, 17, 14, 13, 10, 8, 0]);
+    // consecutive punctuation is a single stop
+    let content = vec![EditorLine::from("a -> b::<T>".to_owned())];
+    assert_eq!(jumps(&content, CursorPosition::default(), Cursor::jump_right), vec![1, 4, 6, 9, 10, 11]);
+    // unicode letters are word chars
+    let content = vec![EditorLine::from("пример_1 тест".to_owned())];
+    assert_eq!(jumps(&content, CursorPosition::default(), Cursor::jump_right), vec![8, 13]);
+    assert_eq!(token_range_at(&content[0], 2), 0..8);
+    assert_eq!(token_range_at(&EditorLine::from("x = my_var2;".to_owned()), 6), 4..11);
+}
+
+#[test]
+fn test_jump_line_boundaries() {
+    let content = vec![
+        EditorLine::from("first".to_owned()),
+        EditorLine::from("  second".to_owned()),
+    ];
+    let mut cursor = Cursor::default();
+    cursor.set_position(CursorPosition { line: 0, char: 5 });
+    cursor.jump_right(&content);
+    assert_eq!(CursorPosition::from(&cursor), CursorPosition { line: 1, char: 8 });
+    cursor.set_position(CursorPosition { line: 1, char: 0 });
+    cursor.jump_left(&content);
+    assert_eq!(CursorPosition::from(&cursor), CursorPosition { line: 0, char: 0 });
+    cursor.jump_right_select(&content);
+    assert_eq!(cursor.select_get(), Some((CursorPosition::default(), CursorPosition { line: 0, char: 5 })));
+    cursor.jump_right_select(&content);
+    assert_eq!(cursor.select_get(), Some((CursorPosition::default(), CursorPosition { line: 1, char: 8 })));
+}
+
+#[test]
+fn test_smart_home() {
+    let content = vec![
+        EditorLine::from("    let x = 1;".to_owned()),
+        EditorLine::from("   ".to_owned()),
+        EditorLine::from("x = 2;".to_owned()),
+        EditorLine::from("\t\tx = 3;".to_owned()),
+    ];
+    let mut cursor = Cursor::default();
+    cursor.set_position(CursorPosition { line: 0, char: 9 });
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 4);
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 0);
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 4);
+    // whitespace only line bounces between 0 and the end
+    cursor.set_position(CursorPosition { line: 1, char: 1 });
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 3);
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 0);
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 3);
+    // no indent - first non blank is column 0
+    cursor.set_position(CursorPosition { line: 2, char: 4 });
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 0);
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 0);
+    // tabs are counted as chars
+    cursor.set_position(CursorPosition { line: 3, char: 5 });
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 2);
+    cursor.start_of_line(&content);
+    assert_eq!(cursor.char, 0);
+    // select variant
+    cursor.set_position(CursorPosition { line: 0, char: 4 });
+    cursor.select_start_of_line(&content);
+    let anchor = CursorPosition { line: 0, char: 4 };
+    assert_eq!(cursor.select_get(), Some((CursorPosition::default(), anchor)));
+    cursor.select_start_of_line(&content);
+    assert_eq!(cursor.select_get(), Some((anchor, anchor)));
+}
+
 #[test]
 fn test_jump_select() {
     let mut ws = base_ws();
@@ -330,3 +437,392 @@ fn test_jump_select() {
     shift_press(&mut ws, KeyCode::Down, &mut gs);
     select_eq((CursorPosition::default(), CursorPosition { line: 3, char: 11 }), active(&mut ws));
 }
+
+#[test]
+fn test_split_focus_and_collapse() {
+    let mut ws = base_ws();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    ws.toggle_split(&mut gs);
+    assert!(ws.split.is_none());
+    let mut second = mock_editor(vec!["second".to_owned()]);
+    second.path = std::path::PathBuf::from("second");
+    ws.editors.insert(1, second);
+    gs.editor_area = Rect::new(0, 0, 60, 10);
+    ws.toggle_split(&mut gs);
+    assert!(ws.split.is_some());
+    ws.render_editors(&mut gs);
+    let rendered = gs.writer.drain();
+    let texts = rendered.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>();
+    let dim_start = texts.iter().position(|text| *text == "<<dim: true>>").unwrap();
+    let dim_end = dim_start + texts[dim_start..].iter().position(|text| *text == "<<dim: false>>").unwrap();
+    // the other pane is drawn faint, the focused one after it
+    assert!(texts[dim_start..dim_end].concat().contains("second"));
+    assert!(!texts[dim_start..dim_end].concat().contains("hello"));
+    assert!(texts[dim_end..].concat().contains("hello"));
+    assert!(!gs.writer.dim);
+    ws.focus_other_split(&mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "second");
+    ws.focus_other_split(&mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "hello world!");
+    ws.close_active(&mut gs);
+    assert!(ws.split.is_none());
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "second");
+}
+
+#[test]
+fn test_split_kept_on_other_close() {
+    let mut ws = base_ws();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    for name in ["second", "third", "fourth"] {
+        let mut editor = mock_editor(vec![name.to_owned()]);
+        editor.path = std::path::PathBuf::from(name);
+        ws.editors.insert(ws.editors.len(), editor);
+    }
+    ws.active = 1;
+    ws.toggle_split(&mut gs);
+    assert_eq!(ws.split.as_ref().map(|split| split.other), Some(2));
+    // editors outside the split
+    ws.close_editor(3, &mut gs);
+    assert_eq!(ws.split.as_ref().map(|split| split.other), Some(2));
+    ws.close_editor(0, &mut gs);
+    assert_eq!(ws.split.as_ref().map(|split| split.other), Some(1));
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "second");
+    ws.focus_other_split(&mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "third");
+    ws.focus_other_split(&mut gs);
+    // closing the other pane collapses the split
+    ws.close_editor(1, &mut gs);
+    assert!(ws.split.is_none());
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "second");
+}
+
+#[test]
+fn test_pinned_tabs_keep_position() {
+    let mut ws = base_ws();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    active(&mut ws).path = std::path::PathBuf::from("first");
+    for name in ["second", "third"] {
+        let mut editor = mock_editor(vec![name.to_owned()]);
+        editor.path = std::path::PathBuf::from(name);
+        ws.editors.insert(ws.editors.len(), editor);
+    }
+    let order = |ws: &Workspace| ws.editors.iter().map(|e| e.path.display().to_string()).collect::<Vec<_>>();
+    // activation does not reorder
+    ws.activate_editor(2, &mut gs);
+    assert_eq!(order(&ws), ["first", "second", "third"]);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "third");
+    ws.toggle_pin(&mut gs);
+    assert_eq!(order(&ws), ["third", "first", "second"]);
+    assert_eq!(ws.active, 0);
+    ws.next_tab(&mut gs);
+    ws.next_tab(&mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "second");
+    ws.next_tab(&mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "third");
+    ws.prev_tab(&mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "second");
+    ws.go_to_tab(1, &mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "hello world!");
+    assert_eq!(order(&ws), ["third", "first", "second"]);
+    // pinned after the other pinned, unpinned in front of the rest
+    ws.toggle_pin(&mut gs);
+    assert_eq!(order(&ws), ["third", "first", "second"]);
+    ws.go_to_tab(0, &mut gs);
+    ws.toggle_pin(&mut gs);
+    assert_eq!(order(&ws), ["first", "third", "second"]);
+    assert_eq!(ws.active, 1);
+    // new editors are opened after pinned ones
+    let mut editor = mock_editor(vec!["fourth".to_owned()]);
+    editor.path = std::path::PathBuf::from("fourth");
+    ws.insert_editor(editor);
+    assert_eq!(order(&ws), ["first", "fourth", "third", "second"]);
+    assert_eq!(ws.active, 1);
+    ws.go_to_tab(0, &mut gs);
+    ws.close_active(&mut gs);
+    assert!(gs.has_popup());
+    assert_eq!(ws.editors.len(), 4);
+    ws.close_active_unchecked(&mut gs);
+    assert_eq!(order(&ws), ["fourth", "third", "second"]);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "fourth");
+    // closing the last tab activates the one before
+    ws.go_to_tab(2, &mut gs);
+    ws.close_active(&mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "third");
+}
+
+#[test]
+fn test_scratch_is_not_unsaved_work() {
+    let mut ws = base_ws();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    ws.close_active(&mut gs);
+    ws.new_scratch(&mut gs).unwrap();
+    let scratch = active(&mut ws);
+    assert!(scratch.ephemeral);
+    scratch.insert_text_with_relative_offset("unsaved".to_owned());
+    assert!(!scratch.is_saved());
+    let path = scratch.path.clone();
+    assert!(ws.are_updates_saved());
+    ws.close_active(&mut gs);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_close_records_recent() {
+    let mut ws = base_ws();
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    active(&mut ws).path = std::path::PathBuf::from("closed.rs");
+    active(&mut ws).cursor.set_position(CursorPosition { line: 1, char: 3 });
+    ws.close_active(&mut gs);
+    let recent = ws.session.recent();
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent[0].path, std::path::PathBuf::from("closed.rs"));
+    assert_eq!(recent[0].position(), CursorPosition { line: 1, char: 3 });
+    // scratch buffers are not listed
+    gs.editor_area = Rect::new(0, 0, 80, 20);
+    ws.new_scratch(&mut gs).unwrap();
+    ws.close_active(&mut gs);
+    assert_eq!(ws.session.recent().len(), 1);
+}
+
+#[test]
+fn test_save_all_summary() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let dir = std::env::temp_dir().join(format!("idiom_save_all_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file_editor = |name: &str, dirty: bool| {
+        let path = dir.join(name);
+        std::fs::write(&path, "line").unwrap();
+        let mut editor = mock_editor(vec!["line".to_owned()]);
+        editor.path = path;
+        if dirty {
+            editor.content[0] = EditorLine::from("changed".to_owned());
+        }
+        editor
+    };
+    let mut failing = mock_editor(vec!["line".to_owned()]);
+    failing.path = dir.join("missing_dir").join("c.rs");
+    failing.content[0] = EditorLine::from("changed".to_owned());
+    let mut editors = [
+        file_editor("d.rs", true),
+        failing,
+        file_editor("b.rs", false),
+        file_editor("a.rs", true),
+    ];
+    // changed on disk - would be overwritten if the clean editor was saved
+    std::fs::write(dir.join("b.rs"), "external").unwrap();
+
+    let summary = save_editors(editors.iter_mut(), &[], false, &mut gs);
+    assert_eq!(summary.saved, vec![dir.join("a.rs"), dir.join("d.rs")]);
+    assert_eq!(summary.failed.len(), 1);
+    assert_eq!(summary.failed[0].0, dir.join("missing_dir").join("c.rs"));
+    assert!(!summary.failed[0].1.is_empty());
+    assert_eq!(std::fs::read_to_string(dir.join("a.rs")).unwrap(), "changed");
+    assert_eq!(std::fs::read_to_string(dir.join("d.rs")).unwrap(), "changed");
+    assert_eq!(std::fs::read_to_string(dir.join("b.rs")).unwrap(), "external");
+    assert!(!editors[0].is_dirty() && !editors[3].is_dirty());
+    assert!(editors[1].is_dirty());
+
+    // second run has nothing to write except the failing file
+    let summary = save_editors(editors.iter_mut(), &[], false, &mut gs);
+    assert!(summary.saved.is_empty());
+    assert_eq!(summary.failed.len(), 1);
+
+    // blocked editors are not written
+    editors[3].content[0] = EditorLine::from("blocked".to_owned());
+    let blocked = [(dir.join("a.rs"), String::from("open in another instance (pid 1)"))];
+    let summary = save_editors(editors.iter_mut(), &blocked, false, &mut gs);
+    assert!(summary.saved.is_empty());
+    assert_eq!(summary.failed.len(), 2);
+    assert!(summary.failed.contains(&blocked[0]));
+    assert_eq!(std::fs::read_to_string(dir.join("a.rs")).unwrap(), "changed");
+    assert!(editors[3].is_dirty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_save_all_final_newline() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let path = std::env::temp_dir().join(format!("idiom_save_all_newline_{}.rs", std::process::id()));
+    std::fs::write(&path, "line").unwrap();
+    let mut ws = mock_ws(vec!["line".to_owned()]);
+    active(&mut ws).path = path.clone();
+    active(&mut ws).insert_final_newline = true;
+    active(&mut ws).map(EditorAction::Char('x'), &mut gs);
+    assert!(ws.save_all(&mut gs));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "xline\n");
+    assert!(active(&mut ws).is_saved());
+    // repeated save does not add more lines
+    active(&mut ws).map(EditorAction::Char('y'), &mut gs);
+    assert!(ws.save_all(&mut gs));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "xyline\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_auto_save_after_delay() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let dir = std::env::temp_dir().join(format!("idiom_auto_save_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut ws = mock_ws(vec!["line".to_owned()]);
+    ws.editors.insert(1, mock_editor(vec!["line".to_owned()]));
+    for (editor, name) in ws.editors.inner_mut_no_update().iter_mut().zip(["a.rs", "b.rs"]) {
+        editor.path = dir.join(name);
+        std::fs::write(&editor.path, "line").unwrap();
+        editor.map(EditorAction::Char('x'), &mut gs);
+    }
+    // changed on disk - the user decides
+    ws.editors.inner_mut_no_update()[1].update_status.mark_updated();
+    // save edits are applied as on single save
+    ws.editors.inner_mut_no_update()[0].trim_trailing_whitespace = true;
+    ws.editors.inner_mut_no_update()[0].map(EditorAction::EndOfLine, &mut gs);
+    ws.editors.inner_mut_no_update()[0].map(EditorAction::Char(' '), &mut gs);
+
+    ws.base_config.auto_save = AutoSave::AfterDelay(60_000);
+    ws.auto_save_idle(&mut gs);
+    assert_eq!(std::fs::read_to_string(dir.join("a.rs")).unwrap(), "line");
+
+    ws.base_config.auto_save = AutoSave::AfterDelay(0);
+    ws.auto_save_idle(&mut gs);
+    assert_eq!(std::fs::read_to_string(dir.join("a.rs")).unwrap(), "xline");
+    assert_eq!(std::fs::read_to_string(dir.join("b.rs")).unwrap(), "line");
+    assert!(ws.editors.inner_mut_no_update()[0].is_saved());
+
+    // focus loss saves regardless of the delay, but only with the mode enabled
+    ws.editors.inner_mut_no_update()[0].map(EditorAction::Char('y'), &mut gs);
+    ws.focus_lost(&mut gs);
+    assert_eq!(std::fs::read_to_string(dir.join("a.rs")).unwrap(), "xline");
+    ws.base_config.auto_save = AutoSave::OnFocusLoss;
+    ws.focus_lost(&mut gs);
+    assert_eq!(std::fs::read_to_string(dir.join("a.rs")).unwrap(), "xliney");
+    assert_eq!(std::fs::read_to_string(dir.join("b.rs")).unwrap(), "line");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_same_file_detection() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let dir = std::env::temp_dir().join(format!("idiom_same_file_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let file = dir.join("src").join("a.rs");
+    std::fs::write(&file, "line").unwrap();
+    std::os::unix::fs::symlink(dir.join("src"), dir.join("link")).unwrap();
+    std::fs::hard_link(&file, dir.join("src").join("b.rs")).unwrap();
+    std::fs::write(dir.join("src").join("c.rs"), "line").unwrap();
+
+    let mut ws = mock_ws(vec!["line".to_owned()]);
+    active(&mut ws).path = file.canonicalize().unwrap();
+    // open time - symlinked dir resolves by path, hard link by inode
+    let through_link = dir.join("link").join("a.rs").canonicalize().unwrap();
+    assert_eq!(ws.find_open(&through_link), Some(0));
+    let hard_link = dir.join("src").join("b.rs").canonicalize().unwrap();
+    assert_eq!(ws.find_open(&hard_link), Some(0));
+    assert_eq!(ws.find_open(&dir.join("src").join("c.rs").canonicalize().unwrap()), None);
+    // tab shows the spelling used to open it
+    let requested = super::requested_path(&dir.join("link").join(".").join("a.rs")).unwrap();
+    assert!(ws.activate_open(&requested, &through_link, &mut gs));
+    assert_eq!(active(&mut ws).display, format!("link{}a.rs", std::path::MAIN_SEPARATOR));
+    assert!(ws.activate_open(&file, &through_link, &mut gs));
+    assert_eq!(active(&mut ws).display, format!("src{}a.rs", std::path::MAIN_SEPARATOR));
+
+    // save time - duplicate opened before the link existed
+    let mut duplicate = mock_editor(vec!["line".to_owned()]);
+    duplicate.path = hard_link.clone();
+    ws.editors.insert(1, duplicate);
+    assert_eq!(ws.shared_with(0), Some(hard_link.clone()));
+    assert_eq!(ws.shared_with(1), Some(file.canonicalize().unwrap()));
+    active(&mut ws).content[0] = EditorLine::from("changed".to_owned());
+    ctrl_press(&mut ws, KeyCode::Char('s'), &mut gs);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "line");
+    assert!(active(&mut ws).is_dirty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_annotations() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    gs.editor_area = Rect::new(0, 0, 60, 10);
+    let mut ws = base_ws();
+    active(&mut ws).path = std::path::PathBuf::from("/project/src/lib.rs");
+    active(&mut ws).cursor.set_position(CursorPosition { line: 1, char: 0 });
+    ws.set_annotation("why here?".to_owned(), &mut gs);
+    assert_eq!(active(&mut ws).annotations.lines(), &[1]);
+    active(&mut ws).render(&mut gs);
+    let rendered = gs.writer.drain();
+    assert!(rendered.iter().any(|(_, text)| text == "•"));
+    // lines inserted above move the note with the line content
+    active(&mut ws).cursor.set_position(CursorPosition { line: 0, char: 0 });
+    press(&mut ws, KeyCode::Enter, &mut gs);
+    active(&mut ws).render(&mut gs);
+    assert_eq!(active(&mut ws).annotations.lines(), &[2]);
+    ws.export_annotations(&mut gs);
+    assert_eq!(gs.clipboard.pull().unwrap(), "# Review notes\n\n## /project/src/lib.rs\n\n- line 3: why here?\n");
+    active(&mut ws).cursor.set_position(CursorPosition { line: 2, char: 0 });
+    ws.set_annotation(String::new(), &mut gs);
+    assert!(active(&mut ws).annotations.lines().is_empty());
+}
+
+#[test]
+fn test_reload_on_disk_update() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let path = std::env::temp_dir().join(format!("idiom_reload_on_update_{}", std::process::id()));
+    let lines = (0..40).map(|idx| format!("line {idx}")).collect::<Vec<_>>();
+    let mut ws = mock_ws(lines.clone());
+    active(&mut ws).path = path.clone();
+    active(&mut ws).cursor.at_line = 20;
+    active(&mut ws).cursor.set_position(CursorPosition { line: 25, char: 6 });
+    // no local edits - the buffer follows the file keeping the cursor
+    std::fs::write(&path, lines.iter().map(|line| line.replace("line", "ln")).collect::<Vec<_>>().join("\n")).unwrap();
+    ws.notify_update(path.clone(), &mut gs);
+    assert_eq!(pull_line(active(&mut ws), 25).unwrap(), "ln 25");
+    assert_eq!(CursorPosition::from(&active(&mut ws).cursor), CursorPosition { line: 25, char: 5 });
+    assert_eq!(active(&mut ws).cursor.at_line, 20);
+    assert!(!gs.has_popup());
+    // shorter file - clamped to the last line
+    std::fs::write(&path, "one\ntwo").unwrap();
+    ws.notify_update(path.clone(), &mut gs);
+    assert_eq!(CursorPosition::from(&active(&mut ws).cursor), CursorPosition { line: 1, char: 3 });
+    assert_eq!(active(&mut ws).cursor.at_line, 1);
+    // local edits are not replaced
+    press(&mut ws, KeyCode::Char('x'), &mut gs);
+    std::fs::write(&path, "three").unwrap();
+    ws.notify_update(path.clone(), &mut gs);
+    assert_eq!(pull_line(active(&mut ws), 1).unwrap(), "twox");
+    assert!(gs.has_popup());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_find_and_replace_in_files() {
+    let mut gs = GlobalState::new(Backend::init()).unwrap();
+    let dir = std::env::temp_dir().join(format!("idiom_replace_in_files_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let open = dir.join("open.rs");
+    let closed = dir.join("closed.rs");
+    let other = dir.join("other.rs");
+    let binary = dir.join("data.bin");
+    std::fs::write(&open, "let value = 1;").unwrap();
+    std::fs::write(&closed, "value\nvalue + value\n").unwrap();
+    std::fs::write(&other, "nothing here\n").unwrap();
+    std::fs::write(&binary, b"value\0").unwrap();
+    let mut ws = mock_ws(vec!["let value = 1;".to_owned()]);
+    active(&mut ws).path = open.clone();
+    // open editors are searched in the unsaved content
+    active(&mut ws).map(EditorAction::Char('x'), &mut gs);
+    let files = [open.clone(), closed.clone(), other.clone(), binary.clone()];
+    let found = ws.find_in_files(&files, "value", SearchOptions::default()).unwrap();
+    assert_eq!(found.iter().map(|matches| matches.path.clone()).collect::<Vec<_>>(), [open.clone(), closed.clone()]);
+    assert_eq!(found[0].lines, [(0, String::from("xlet value = 1;"))]);
+    assert_eq!(found[1].lines.len(), 2);
+    assert!(ws.find_in_files(&files, "(", SearchOptions { regex: true, ..Default::default() }).is_err());
+    ws.replace_in_files(&files, "value", SearchOptions::default(), "v", &mut gs);
+    assert_eq!(pull_line(active(&mut ws), 0).unwrap(), "xlet v = 1;");
+    assert!(!active(&mut ws).is_saved());
+    assert_eq!(std::fs::read_to_string(&open).unwrap(), "let value = 1;");
+    assert_eq!(std::fs::read_to_string(&closed).unwrap(), "v\nv + v\n");
+    assert_eq!(std::fs::read_to_string(&other).unwrap(), "nothing here\n");
+    assert_eq!(std::fs::read(&binary).unwrap(), b"value\0");
+    std::fs::remove_dir_all(dir).unwrap();
+}
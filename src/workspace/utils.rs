@@ -1,8 +1,12 @@
 use crate::{
+    configs::IndentConfigs,
     render::UTF8Safe,
     workspace::{cursor::CursorPosition, line::EditorLine},
 };
-use std::ops::Range;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 #[inline(always)]
 pub fn insert_clip(clip: &str, content: &mut Vec<EditorLine>, mut cursor: CursorPosition) -> CursorPosition {
@@ -114,12 +118,115 @@ pub fn is_scope(first_line: &str, second_line: &str) -> bool {
     false
 }
 
-#[inline(always)]
-pub fn is_closing_repeat(line: &EditorLine, ch: char, at: usize) -> bool {
-    if let Some(opening) = get_opening_char(ch) {
-        line[at..].starts_with(ch) && line[..at].contains(opening)
+/// finds bracket matching the one at or right before the position (cursor is between chars)
+/// search is limited to max_lines in the direction of the match
+pub fn find_matching_bracket(
+    content: &[EditorLine],
+    position: CursorPosition,
+    max_lines: usize,
+) -> Option<CursorPosition> {
+    let line = content.get(position.line)?;
+    let (char, bracket) =
+        line.chars().nth(position.char).filter(|ch| is_bracket(*ch)).map(|ch| (position.char, ch)).or_else(|| {
+            let char = position.char.checked_sub(1)?;
+            line.chars().nth(char).filter(|ch| is_bracket(*ch)).map(|ch| (char, ch))
+        })?;
+    let mut depth = 0_usize;
+    if let Some(closing) = get_closing_char(bracket) {
+        for (line_idx, text) in content.iter().enumerate().skip(position.line).take(max_lines) {
+            let skip = if line_idx == position.line { char + 1 } else { 0 };
+            for (char_idx, ch) in text.chars().enumerate().skip(skip) {
+                if ch == closing {
+                    if depth == 0 {
+                        return Some(CursorPosition { line: line_idx, char: char_idx });
+                    }
+                    depth -= 1;
+                } else if ch == bracket {
+                    depth += 1;
+                }
+            }
+        }
     } else {
-        false
+        let opening = get_opening_char(bracket)?;
+        let first_line = position.line.saturating_sub(max_lines.saturating_sub(1));
+        for line_idx in (first_line..=position.line).rev() {
+            let text = &content[line_idx];
+            let end = if line_idx == position.line { char } else { text.char_len() };
+            let chars = text.chars().take(end).collect::<Vec<_>>();
+            for (char_idx, ch) in chars.into_iter().enumerate().rev() {
+                if ch == opening {
+                    if depth == 0 {
+                        return Some(CursorPosition { line: line_idx, char: char_idx });
+                    }
+                    depth -= 1;
+                } else if ch == bracket {
+                    depth += 1;
+                }
+            }
+        }
+    }
+    None
+}
+
+#[inline(always)]
+fn is_bracket(ch: char) -> bool {
+    matches!(ch, '{' | '}' | '(' | ')' | '[' | ']')
+}
+
+#[inline(always)]
+pub fn is_closing_repeat(line: &EditorLine, ch: char, opening: char, at: usize) -> bool {
+    line[at..].starts_with(ch) && line[..at].contains(opening)
+}
+
+/// width of the leading spaces and tabs
+#[inline(always)]
+pub fn indent_len(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// clip lines shifted so the least indented one starts at the indent, blank lines are left empty
+/// the first line is counted only if indented (copied from the line start) - it is placed at the cursor
+/// with the part of the indent not present before it, or without indent if the cursor is past the line indent
+/// indent of the clip lines over the least indented one is written with the indent unit (tabs or spaces)
+pub fn reindent_clip(clip: &str, indent: &str, cfg: &IndentConfigs, before_cursor: Option<usize>) -> String {
+    let tab_width = cfg.indent_width();
+    let mut lines = clip.split('\n');
+    let first = lines.next().unwrap_or_default();
+    let rest = lines.collect::<Vec<_>>();
+    let first_columns = indent_columns(first, tab_width);
+    let min_columns = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent_columns(line, tab_width))
+        .chain(Some(first_columns).filter(|columns| *columns != 0))
+        .min()
+        .unwrap_or_default();
+    let relative = |line: &str, columns: usize| {
+        format!("{indent}{}{}", indent_text(columns - min_columns, &cfg.indent, tab_width), &line[indent_len(line)..])
+    };
+    let mut text = match before_cursor {
+        Some(present) => relative(first, first_columns.max(min_columns))[present.min(indent.len())..].to_owned(),
+        None => first[indent_len(first)..].to_owned(),
+    };
+    for line in rest {
+        text.push('\n');
+        if !line.trim().is_empty() {
+            text.push_str(&relative(line, indent_columns(line, tab_width)));
+        }
+    }
+    text
+}
+
+/// width of the leading whitespace, tab taking the tab width
+fn indent_columns(line: &str, tab_width: usize) -> usize {
+    line[..indent_len(line)].chars().map(|ch| if ch == '\t' { tab_width } else { 1 }).sum()
+}
+
+/// indent of the width in the unit - tabs with spaces for the rest, or spaces only
+fn indent_text(columns: usize, unit: &str, tab_width: usize) -> String {
+    match unit == "\t" && tab_width != 0 {
+        true => format!("{}{}", "\t".repeat(columns / tab_width), " ".repeat(columns % tab_width)),
+        false => " ".repeat(columns),
     }
 }
 
@@ -133,12 +240,33 @@ pub fn find_line_start(line: &EditorLine) -> usize {
     0
 }
 
+/// classes used for word jumps and word ranges - a word is a run of chars of the same class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Word,
+    Whitespace,
+    Punctuation,
+}
+
+impl CharClass {
+    #[inline(always)]
+    pub fn of(ch: char) -> Self {
+        if ch.is_alphanumeric() || ch == '_' {
+            Self::Word
+        } else if ch.is_whitespace() {
+            Self::Whitespace
+        } else {
+            Self::Punctuation
+        }
+    }
+}
+
 #[inline(always)]
 pub fn token_range_at(line: &EditorLine, idx: usize) -> Range<usize> {
     let mut token_start = 0;
     let mut last_not_in_token = false;
     for (char_idx, ch) in line.chars().enumerate() {
-        if ch.is_alphabetic() || ch == '_' {
+        if CharClass::of(ch) == CharClass::Word {
             if last_not_in_token {
                 token_start = char_idx;
             }
@@ -161,6 +289,68 @@ pub fn token_range_at(line: &EditorLine, idx: usize) -> Range<usize> {
     }
 }
 
+/// Identity of the file on disk - same for all paths leading to it (symlinks, hard links, different case)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileId {
+    /// (device, inode)
+    #[cfg(unix)]
+    Inode(u64, u64),
+    /// case folded canonical path - filesystems are expected to be case insensitive
+    #[cfg(not(unix))]
+    Path(String),
+}
+
+impl FileId {
+    #[cfg(unix)]
+    pub fn of(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self::Inode(metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(path: &Path) -> Option<Self> {
+        let path = path.canonicalize().ok()?;
+        Some(Self::Path(path.to_string_lossy().to_lowercase()))
+    }
+}
+
+/// absolute path as spelled by the user (links are not resolved), used to show the file opened through it
+pub fn requested_path(path: &Path) -> std::io::Result<PathBuf> {
+    Ok(std::env::current_dir()?.join(path).components().collect())
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// FNV-1a over the lines - stable between builds unlike std hasher
+pub fn content_hash(content: &[EditorLine]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for (idx, line) in content.iter().enumerate() {
+        if idx != 0 {
+            hash = fnv_extend(hash, b"\n");
+        }
+        hash = fnv_extend(hash, line.content.as_bytes());
+    }
+    hash
+}
+
+/// same as content_hash of the lines the text is split into
+#[inline]
+pub fn text_hash(text: &str) -> u64 {
+    bytes_hash(text.as_bytes())
+}
+
+/// FNV-1a - stable between builds unlike std hasher
+#[inline]
+pub fn bytes_hash(bytes: &[u8]) -> u64 {
+    fnv_extend(FNV_OFFSET, bytes)
+}
+
+#[inline]
+fn fnv_extend(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
 #[inline(always)]
 fn push_on_newline(mut buf: String, string: &str) -> String {
     buf.push('\n');